@@ -6,18 +6,30 @@
 //!
 //! Configuration is loaded from `.claude/agent-guard.toml` (project-level) or
 //! `~/.claude/agent-guard.toml` (user-level), with embedded defaults as fallback.
+//! Rules under `[[custom]]` in either file are added to the built-in patterns
+//! rather than replacing them.
+//!
+//! Each pattern has an `action`: `deny` blocks the command, `ask` hands it to
+//! the user's permission prompt with the pattern's message, and `warn` lets
+//! it through with the message attached for the agent and the user.
+//!
+//! Exceptions: an `[[allow]]` entry lifts a pattern in given working
+//! directories or for given arguments, and `META_GUARD_ALLOW=<id>,...` lifts
+//! patterns for a session. Each use of `META_GUARD_ALLOW` is logged to
+//! `~/.meta/agent_guard.jsonl`.
 
 use anyhow::Result;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::io::Read;
-use std::path::Path;
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 // ── Configuration ───────────────────────────────────────
 
 /// Default agent guard configuration embedded in the binary.
-const DEFAULT_CONFIG: &str = include_str!("../.claude/agent-guard.toml");
+pub const DEFAULT_CONFIG: &str = include_str!("../.claude/agent-guard.toml");
 
 /// Cached compiled patterns loaded once per process.
 /// This avoids repeated file I/O, TOML parsing, and regex compilation.
@@ -32,6 +44,12 @@ pub struct GuardConfig {
     pub metadata: Option<ConfigMetadata>,
     #[serde(default)]
     pub patterns: Vec<PatternDefinition>,
+    /// User-defined rules, evaluated alongside `patterns`.
+    #[serde(default)]
+    pub custom: Vec<PatternDefinition>,
+    /// Exceptions to patterns and custom rules.
+    #[serde(default)]
+    pub allow: Vec<AllowRule>,
 }
 
 /// Metadata about the configuration file.
@@ -53,15 +71,62 @@ pub struct PatternDefinition {
     pub matcher: MatcherConfig,
     #[serde(default)]
     pub validator: Option<ValidatorConfig>,
+    #[serde(default, alias = "severity")]
+    pub action: Action,
     pub message: String,
 }
 
-/// Matcher configuration (currently only regex, extensible for future types).
+/// An exception to a pattern. With neither `cwd` nor `args` the pattern
+/// never applies; with both, both must match.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AllowRule {
+    /// Pattern id, or a glob over ids (`meta.git.*`)
+    pub id: String,
+    /// Working directories relative to the project root, including
+    /// everything below them (`target` covers `target/debug`)
+    #[serde(default)]
+    pub cwd: Vec<String>,
+    /// Globs, any of which an argument of the command must match
+    /// (`wip/*` for `git push --force origin wip/spike`)
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// What happens when a pattern matches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Block the command
+    #[default]
+    Deny,
+    /// Ask the user to approve the command
+    Ask,
+    /// Allow the command with a warning
+    Warn,
+}
+
+impl Action {
+    /// The `permissionDecision` Claude Code expects for this action.
+    fn permission_decision(self) -> &'static str {
+        match self {
+            Action::Deny => "deny",
+            Action::Ask => "ask",
+            Action::Warn => "allow",
+        }
+    }
+}
+
+/// Matcher configuration.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "type")]
 pub enum MatcherConfig {
     #[serde(rename = "regex")]
     Regex { pattern: String },
+
+    /// Words that must appear in the segment in this order, not necessarily
+    /// adjacent: `["terraform", "destroy"]` matches `terraform -chdir=x destroy`
+    #[serde(rename = "tokens")]
+    Tokens { sequence: Vec<String> },
 }
 
 /// Validator configuration for additional pattern checks.
@@ -102,9 +167,74 @@ pub enum ValidatorConfig {
 struct CompiledPattern {
     id: String,
     priority: u32,
-    regex: Regex,
+    matcher: Matcher,
     message: String,
     validator: Option<ValidatorConfig>,
+    action: Action,
+    allow: Vec<Allowance>,
+}
+
+/// A compiled [`AllowRule`] attached to the patterns it names.
+#[derive(Clone)]
+struct Allowance {
+    cwd: Vec<Regex>,
+    args: Vec<Regex>,
+}
+
+impl Allowance {
+    fn permits(&self, segment: &str, cwd: &Path) -> bool {
+        let cwd_matches = self.cwd.is_empty()
+            || cwd.ancestors().any(|dir| {
+                let dir = dir.to_string_lossy();
+                self.cwd.iter().any(|re| re.is_match(&dir))
+            });
+        let args_matches = self.args.is_empty()
+            || segment
+                .split_whitespace()
+                .skip(1)
+                .any(|arg| self.args.iter().any(|re| re.is_match(arg)));
+        cwd_matches && args_matches
+    }
+}
+
+/// Where a command runs and which patterns the session has lifted.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    /// Working directory relative to the project root (empty at the root)
+    pub cwd: PathBuf,
+    /// Pattern id globs from `META_GUARD_ALLOW`
+    pub allowed: Vec<String>,
+}
+
+impl Session {
+    /// A session in `cwd`, with the patterns lifted by `META_GUARD_ALLOW`.
+    pub fn from_env(cwd: PathBuf) -> Self {
+        let allowed = std::env::var("META_GUARD_ALLOW")
+            .map(|v| parse_allowed(&v))
+            .unwrap_or_default();
+        Session { cwd, allowed }
+    }
+
+    fn allows(&self, id: &str) -> bool {
+        self.allowed.iter().any(|glob| glob_matches(glob, id))
+    }
+}
+
+enum Matcher {
+    Regex(Regex),
+    Tokens(Vec<String>),
+}
+
+impl Matcher {
+    fn is_match(&self, segment: &str) -> bool {
+        match self {
+            Matcher::Regex(regex) => regex.is_match(segment),
+            Matcher::Tokens(sequence) => {
+                let mut words = segment.split_whitespace();
+                sequence.iter().all(|token| words.any(|w| w == token))
+            }
+        }
+    }
 }
 
 fn default_schema_version() -> String {
@@ -200,19 +330,23 @@ fn validate_args_match_any(segment: &str, command: &str, values: &[String]) -> b
 
 impl GuardConfig {
     /// Load configuration from the hierarchy: project → user → embedded defaults.
+    ///
+    /// Patterns come from the first file that defines any; custom and allow
+    /// rules are collected from both files.
     pub fn load() -> Self {
-        // Try project-level config first
-        if let Some(config) = Self::load_from_project() {
-            return config;
-        }
+        let files: Vec<Self> = [Self::load_from_project(), Self::load_from_user()]
+            .into_iter()
+            .flatten()
+            .collect();
 
-        // Try user-level config
-        if let Some(config) = Self::load_from_user() {
-            return config;
-        }
-
-        // Fall back to embedded defaults
-        Self::load_from_embedded()
+        let mut config = files
+            .iter()
+            .find(|c| !c.patterns.is_empty())
+            .cloned()
+            .unwrap_or_else(Self::load_from_embedded);
+        config.custom = files.iter().flat_map(|c| c.custom.clone()).collect();
+        config.allow = files.into_iter().flat_map(|c| c.allow).collect();
+        config
     }
 
     /// Load config from project-level `.claude/agent-guard.toml`.
@@ -239,35 +373,57 @@ impl GuardConfig {
         toml::from_str(&contents).ok()
     }
 
-    /// Compile patterns from configuration into regex matchers.
+    /// Compile patterns and custom rules from configuration into matchers.
     /// Returns compiled patterns sorted by priority (highest first).
     fn compile_patterns(self) -> Vec<CompiledPattern> {
         let mut compiled = Vec::new();
 
-        for pattern_def in self.patterns {
+        let allow_rules: Vec<(&AllowRule, Allowance)> = self
+            .allow
+            .iter()
+            .filter_map(|rule| compile_allow_rule(rule).map(|a| (rule, a)))
+            .collect();
+
+        for pattern_def in self.patterns.iter().chain(&self.custom).cloned() {
             if !pattern_def.enabled {
                 continue; // Skip disabled patterns
             }
 
-            let MatcherConfig::Regex { pattern: regex_str } = &pattern_def.matcher;
-
-            let regex = match Regex::new(regex_str) {
-                Ok(r) => r,
-                Err(e) => {
+            let matcher = match &pattern_def.matcher {
+                MatcherConfig::Regex { pattern: regex_str } => match Regex::new(regex_str) {
+                    Ok(r) => Matcher::Regex(r),
+                    Err(e) => {
+                        eprintln!(
+                            "[agent-guard] WARNING: Failed to compile regex for pattern '{}': {}",
+                            pattern_def.id, e
+                        );
+                        continue;
+                    }
+                },
+                MatcherConfig::Tokens { sequence } if sequence.is_empty() => {
                     eprintln!(
-                        "[agent-guard] WARNING: Failed to compile regex for pattern '{}': {}",
-                        pattern_def.id, e
+                        "[agent-guard] WARNING: Pattern '{}' has an empty token sequence",
+                        pattern_def.id
                     );
                     continue;
                 }
+                MatcherConfig::Tokens { sequence } => Matcher::Tokens(sequence.clone()),
             };
 
+            let allow = allow_rules
+                .iter()
+                .filter(|(rule, _)| glob_matches(&rule.id, &pattern_def.id))
+                .map(|(_, allowance)| allowance.clone())
+                .collect();
+
             compiled.push(CompiledPattern {
                 id: pattern_def.id,
                 priority: pattern_def.priority,
-                regex,
+                matcher,
                 message: pattern_def.message,
                 validator: pattern_def.validator,
+                action: pattern_def.action,
+                allow,
             });
         }
 
@@ -278,12 +434,68 @@ impl GuardConfig {
     }
 }
 
+/// Compile the globs of an allow rule, or `None` (with a warning) if one
+/// is invalid.
+fn compile_allow_rule(rule: &AllowRule) -> Option<Allowance> {
+    let compile = |globs: &[String]| -> Result<Vec<Regex>, regex::Error> {
+        globs
+            .iter()
+            .map(|g| Regex::new(&crate::filter::glob_to_regex(g.trim_end_matches('/'))))
+            .collect()
+    };
+    match (compile(&rule.cwd), compile(&rule.args)) {
+        (Ok(cwd), Ok(args)) => Some(Allowance { cwd, args }),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!(
+                "[agent-guard] WARNING: Failed to compile allow rule for '{}': {}",
+                rule.id, e
+            );
+            None
+        }
+    }
+}
+
+/// Whether pattern id `id` matches the glob `glob`.
+fn glob_matches(glob: &str, id: &str) -> bool {
+    Regex::new(&crate::filter::glob_to_regex(glob)).is_ok_and(|re| re.is_match(id))
+}
+
+/// `META_GUARD_ALLOW` → pattern id globs: comma- or space-separated.
+fn parse_allowed(value: &str) -> Vec<String> {
+    value
+        .split([',', ' '])
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Record a command let through by `META_GUARD_ALLOW`, on stderr and in
+/// `~/.meta/agent_guard.jsonl`. Failing to write the log never blocks.
+fn log_session_allow(id: &str, segment: &str, session: &Session) {
+    eprintln!("[agent-guard] META_GUARD_ALLOW lifted '{id}' for: {segment}");
+    let entry = serde_json::json!({
+        "time": chrono::Local::now().to_rfc3339(),
+        "pattern": id,
+        "command": segment,
+        "cwd": session.cwd.display().to_string(),
+    });
+    let _ = meta_core::data_dir::ensure_meta_dir();
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(meta_core::meta_dir().join("agent_guard.jsonl"))
+    {
+        let _ = writeln!(file, "{entry}");
+    }
+}
+
 // ── Public API ──────────────────────────────────────────
 
 /// Entry point for `meta agent guard`.
 ///
-/// Reads PreToolUse hook JSON from stdin, evaluates the command,
-/// prints denial JSON to stdout if destructive, exits silently if safe.
+/// Reads PreToolUse hook JSON from stdin, evaluates the command, and prints
+/// decision JSON to stdout if a pattern matched. Exits silently if safe.
 pub fn handle_guard() -> Result<()> {
     let mut input = String::new();
     std::io::stdin().read_to_string(&mut input)?;
@@ -293,25 +505,57 @@ pub fn handle_guard() -> Result<()> {
         None => return Ok(()), // No command to evaluate — allow
     };
 
-    if let Some(denial) = evaluate_command(&command) {
-        let output = HookOutput {
-            hook_specific_output: HookSpecificOutput {
-                hook_event_name: "PreToolUse".to_string(),
-                permission_decision: "deny".to_string(),
-                permission_decision_reason: denial.reason,
-            },
-        };
+    let session = Session::from_env(relative_cwd(parse_cwd(&input)));
+    let evaluation = evaluate(&command, &session);
+    if let Some(output) = hook_output(evaluation) {
         println!("{}", serde_json::to_string(&output)?);
     }
 
     Ok(())
 }
 
+/// The hook response for an evaluation, or `None` when nothing matched.
+///
+/// A `deny` or `ask` decision carries its message as the reason. Warnings
+/// never approve a command themselves: they go to the agent as
+/// `additionalContext` and to the user as a `systemMessage`, and the
+/// command continues through the normal permission flow.
+fn hook_output(evaluation: Evaluation) -> Option<HookOutput> {
+    let warning = (!evaluation.warnings.is_empty()).then(|| {
+        evaluation
+            .warnings
+            .iter()
+            .map(|w| format!("Warning: {}", w.trim_end()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+    if evaluation.denial.is_none() && warning.is_none() {
+        return None;
+    }
+    let (decision, reason) = match evaluation.denial {
+        Some(d) => (
+            Some(d.action.permission_decision().to_string()),
+            Some(d.reason),
+        ),
+        None => (None, None),
+    };
+    Some(HookOutput {
+        system_message: warning.clone(),
+        hook_specific_output: HookSpecificOutput {
+            hook_event_name: "PreToolUse".to_string(),
+            permission_decision: decision,
+            permission_decision_reason: reason,
+            additional_context: warning,
+        },
+    })
+}
+
 // ── Types ───────────────────────────────────────────────
 
 #[derive(Deserialize)]
 struct HookInput {
     tool_input: Option<ToolInput>,
+    cwd: Option<PathBuf>,
 }
 
 #[derive(Deserialize)]
@@ -321,6 +565,8 @@ struct ToolInput {
 
 #[derive(Serialize)]
 struct HookOutput {
+    #[serde(rename = "systemMessage", skip_serializing_if = "Option::is_none")]
+    system_message: Option<String>,
     #[serde(rename = "hookSpecificOutput")]
     hook_specific_output: HookSpecificOutput,
 }
@@ -329,16 +575,32 @@ struct HookOutput {
 struct HookSpecificOutput {
     #[serde(rename = "hookEventName")]
     hook_event_name: String,
-    #[serde(rename = "permissionDecision")]
-    permission_decision: String,
-    #[serde(rename = "permissionDecisionReason")]
-    permission_decision_reason: String,
+    #[serde(rename = "permissionDecision", skip_serializing_if = "Option::is_none")]
+    permission_decision: Option<String>,
+    #[serde(
+        rename = "permissionDecisionReason",
+        skip_serializing_if = "Option::is_none"
+    )]
+    permission_decision_reason: Option<String>,
+    #[serde(rename = "additionalContext", skip_serializing_if = "Option::is_none")]
+    additional_context: Option<String>,
 }
 
 /// A denial reason returned when a destructive pattern is detected.
 #[derive(Debug, Clone, PartialEq)]
 pub struct DenyReason {
     pub reason: String,
+    /// `Deny`, or `Ask` when the user may still approve the command
+    pub action: Action,
+}
+
+/// Everything the patterns found in a command.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Evaluation {
+    /// The first `deny` match, else the first `ask` match
+    pub denial: Option<DenyReason>,
+    /// Messages of matching `warn` rules, in evaluation order
+    pub warnings: Vec<String>,
 }
 
 // ── Input Parsing ───────────────────────────────────────
@@ -358,34 +620,93 @@ fn parse_command(input: &str) -> Option<String> {
     Some(command)
 }
 
+/// The session's working directory from hook JSON input, if present.
+fn parse_cwd(input: &str) -> Option<PathBuf> {
+    serde_json::from_str::<HookInput>(input.trim()).ok()?.cwd
+}
+
+/// `cwd` relative to the project root the guard runs in; absolute when it
+/// is outside the project, and empty when unknown.
+fn relative_cwd(cwd: Option<PathBuf>) -> PathBuf {
+    let Some(cwd) = cwd else {
+        return PathBuf::new();
+    };
+    std::env::current_dir()
+        .ok()
+        .and_then(|root| cwd.strip_prefix(root).ok().map(Path::to_path_buf))
+        .unwrap_or(cwd)
+}
+
 // ── Command Evaluation ──────────────────────────────────
 
 /// Evaluate a command string for destructive patterns.
-/// Returns a DenyReason if the command should be blocked, None if safe.
+/// Returns a DenyReason if the command should be blocked or confirmed, None if safe.
+/// Exceptions apply as at the project root; `META_GUARD_ALLOW` is ignored.
+pub fn evaluate_command(command: &str) -> Option<DenyReason> {
+    evaluate(command, &Session::default()).denial
+}
+
+/// Evaluate a command string, collecting warnings until a segment is denied.
+/// A later `deny` overrides an earlier `ask`.
 ///
 /// Patterns are loaded and compiled once, then cached for the lifetime of the process.
-pub fn evaluate_command(command: &str) -> Option<DenyReason> {
+pub fn evaluate(command: &str, session: &Session) -> Evaluation {
     let patterns = CACHED_PATTERNS.get_or_init(|| {
         let config = GuardConfig::load();
         config.compile_patterns()
     });
 
-    for segment in split_compound_command(command) {
-        let trimmed = segment.trim();
-        if trimmed.is_empty() {
-            continue;
+    let mut warnings = Vec::new();
+    let denial = evaluate_nested(command, patterns, session, &mut warnings, 0);
+    Evaluation { denial, warnings }
+}
+
+/// Evaluate each segment of `command`, and the payload of each nested
+/// `sh -c` / `bash -c` / `eval` up to [`MAX_NESTING`] levels deep.
+fn evaluate_nested(
+    command: &str,
+    patterns: &[CompiledPattern],
+    session: &Session,
+    warnings: &mut Vec<String>,
+    depth: usize,
+) -> Option<DenyReason> {
+    let mut ask = None;
+    for words in lex(command) {
+        let nested = nested_command(&words);
+        let segment = render(&words, nested.as_ref().map(|(masked, _)| masked.clone()));
+        let mut found = vec![evaluate_segment(&segment, patterns, session, warnings)];
+        if let Some((_, payload)) = nested.filter(|_| depth < MAX_NESTING) {
+            found.push(evaluate_nested(
+                &payload,
+                patterns,
+                session,
+                warnings,
+                depth + 1,
+            ));
         }
-        if let Some(denial) = evaluate_segment(trimmed, patterns) {
-            return Some(denial);
+        for denial in found.into_iter().flatten() {
+            if denial.action == Action::Deny {
+                return Some(denial);
+            }
+            ask.get_or_insert(denial);
         }
     }
-    None
+    ask
 }
 
-/// Evaluate a single command segment using compiled regex patterns.
-fn evaluate_segment(segment: &str, patterns: &[CompiledPattern]) -> Option<DenyReason> {
+/// Evaluate a single command segment using compiled patterns. The first
+/// matching `deny` pattern is returned, else the first matching `ask`
+/// pattern; matching `warn` patterns are added to `warnings`. Patterns
+/// lifted by an allow rule or the session are skipped.
+fn evaluate_segment(
+    segment: &str,
+    patterns: &[CompiledPattern],
+    session: &Session,
+    warnings: &mut Vec<String>,
+) -> Option<DenyReason> {
+    let mut ask = None;
     for pattern in patterns {
-        if pattern.regex.is_match(segment) {
+        if pattern.matcher.is_match(segment) {
             // Additional validation if required
             if let Some(ref validator) = pattern.validator {
                 if !execute_validator(segment, validator) {
@@ -393,6 +714,18 @@ fn evaluate_segment(segment: &str, patterns: &[CompiledPattern]) -> Option<DenyR
                 }
             }
 
+            if pattern
+                .allow
+                .iter()
+                .any(|a| a.permits(segment, &session.cwd))
+            {
+                continue;
+            }
+            if session.allows(&pattern.id) {
+                log_session_allow(&pattern.id, segment, session);
+                continue;
+            }
+
             // Debug logging when META_DEBUG_GUARD is set
             if std::env::var("META_DEBUG_GUARD").is_ok() {
                 eprintln!(
@@ -400,75 +733,209 @@ fn evaluate_segment(segment: &str, patterns: &[CompiledPattern]) -> Option<DenyR
                     pattern.id, segment
                 );
             }
-
-            return Some(DenyReason {
+            let denial = DenyReason {
                 reason: pattern.message.clone(),
-            });
+                action: pattern.action,
+            };
+            match pattern.action {
+                Action::Deny => return Some(denial),
+                Action::Ask => {
+                    ask.get_or_insert(denial);
+                }
+                Action::Warn => warnings.push(denial.reason),
+            }
         }
     }
-    None
+    ask
 }
 
-/// Split a compound command on `&&`, `||`, `;`, and `|` delimiters.
-/// Simple split — does not handle quoting. Sufficient for Claude-generated commands.
-/// Returns trimmed segments.
-fn split_compound_command(command: &str) -> Vec<&str> {
-    let mut segments = Vec::new();
-    let mut rest = command;
+// ── Shell Lexing ────────────────────────────────────
 
-    loop {
-        // Find the earliest delimiter.
-        // Order matters: check `||` before `|`, and multi-char before single-char.
-        let delimiters: &[&str] = &["||", "&&", ";"];
-        let earliest = delimiters
-            .iter()
-            .filter_map(|d| rest.find(d).map(|pos| (pos, d.len())))
-            .min_by_key(|(pos, _)| *pos);
+/// How deep `bash -c "sh -c '...'"` payloads are followed.
+const MAX_NESTING: usize = 4;
 
-        // Also check for standalone pipe `|` (not part of ||)
-        let pipe_pos = find_standalone_pipe(rest);
+/// Shells whose `-c` argument is a command line.
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish"];
 
-        // Take whichever delimiter comes first
-        let next_delimiter = match (earliest, pipe_pos) {
-            (Some((pos1, len1)), Some(pos2)) => {
-                if pos2 < pos1 {
-                    Some((pos2, 1)) // pipe comes first
-                } else {
-                    Some((pos1, len1)) // other delimiter comes first
+/// Commands that run the command after them, with their own flags.
+const WRAPPERS: &[&str] = &["sudo", "env", "command", "exec", "nohup", "time", "nice"];
+
+/// Flags whose argument is code for the program (`psql -c`, `node -e`).
+const CODE_FLAGS: &[&str] = &["-c", "-e", "--command", "--execute", "--eval"];
+
+/// A word of a command after quote removal.
+#[derive(Debug, Default)]
+struct Word {
+    text: String,
+    /// Some part of the word was quoted
+    quoted: bool,
+    /// A `$(...)` or backtick substitution appeared inside double quotes
+    substitution: bool,
+}
+
+/// Split a command line into segments of words on `&&`, `||`, `;`, `|`,
+/// `&` and newlines, honouring single quotes, double quotes and backslash
+/// escapes. Redirections such as `2>&1` stay part of their word.
+fn lex(command: &str) -> Vec<Vec<Word>> {
+    let mut segments = Vec::new();
+    let mut words = Vec::new();
+    let mut word: Option<Word> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let w = word.get_or_insert_with(Word::default);
+                w.quoted = true;
+                w.text.extend(chars.by_ref().take_while(|&ch| ch != '\''));
+            }
+            '"' => {
+                let w = word.get_or_insert_with(Word::default);
+                w.quoted = true;
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        '"' => break,
+                        '\\' if chars.peek().is_some_and(|n| "\"\\$`".contains(*n)) => {
+                            w.text.extend(chars.next());
+                        }
+                        '`' => {
+                            w.substitution = true;
+                            w.text.push(ch);
+                        }
+                        '$' if chars.peek() == Some(&'(') => {
+                            w.substitution = true;
+                            w.text.push(ch);
+                        }
+                        _ => w.text.push(ch),
+                    }
                 }
             }
-            (Some(delim), None) => Some(delim),
-            (None, Some(pos)) => Some((pos, 1)),
-            (None, None) => None,
-        };
-
-        match next_delimiter {
-            Some((pos, len)) => {
-                segments.push(rest[..pos].trim());
-                rest = &rest[pos + len..];
+            '\\' => match chars.next() {
+                Some('\n') | None => {}
+                Some(ch) => word.get_or_insert_with(Word::default).text.push(ch),
+            },
+            ';' | '\n' => {
+                end_word(&mut word, &mut words);
+                end_segment(&mut words, &mut segments);
+            }
+            '|' => {
+                // `||` and `|&` are separators too
+                if matches!(chars.peek(), Some('|' | '&')) {
+                    chars.next();
+                }
+                end_word(&mut word, &mut words);
+                end_segment(&mut words, &mut segments);
             }
-            None => {
-                segments.push(rest.trim());
-                break;
+            '&' if chars.peek() == Some(&'&') => {
+                chars.next();
+                end_word(&mut word, &mut words);
+                end_segment(&mut words, &mut segments);
             }
+            '&' if chars.peek() == Some(&'>')
+                || word.as_ref().is_some_and(|w| w.text.ends_with(['>', '<'])) =>
+            {
+                word.get_or_insert_with(Word::default).text.push(c);
+            }
+            '&' => {
+                end_word(&mut word, &mut words);
+                end_segment(&mut words, &mut segments);
+            }
+            c if c.is_whitespace() => end_word(&mut word, &mut words),
+            c => word.get_or_insert_with(Word::default).text.push(c),
         }
     }
-
+    end_word(&mut word, &mut words);
+    end_segment(&mut words, &mut segments);
     segments
 }
 
-/// Find a standalone pipe `|` that is NOT part of `||`.
-/// Returns the position of the first such pipe, or None if not found.
-fn find_standalone_pipe(s: &str) -> Option<usize> {
-    let bytes = s.as_bytes();
-    for i in 0..bytes.len() {
-        if bytes[i] == b'|' {
-            // Check if it's part of ||
-            let prev_is_pipe = i > 0 && bytes[i - 1] == b'|';
-            let next_is_pipe = i + 1 < bytes.len() && bytes[i + 1] == b'|';
-            if !prev_is_pipe && !next_is_pipe {
-                return Some(i);
+fn end_word(word: &mut Option<Word>, words: &mut Vec<Word>) {
+    words.extend(word.take());
+}
+
+fn end_segment(words: &mut Vec<Word>, segments: &mut Vec<Vec<Word>>) {
+    if !words.is_empty() {
+        segments.push(std::mem::take(words));
+    }
+}
+
+/// The text patterns are matched against for one segment.
+///
+/// Quoted arguments containing spaces are data (`echo "git push --force"`,
+/// `git commit -m "..."`) and are replaced by `"…"`, unless they are code
+/// for the program (`psql -c "DROP DATABASE x"`) or contain a command
+/// substitution. Words in `masked` are a nested command evaluated on its own.
+fn render(words: &[Word], masked: Option<Range<usize>>) -> String {
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let is_code = (i > 0 && is_code_flag(&words[i - 1].text)) || is_code_flag(&w.text);
+            if masked.as_ref().is_some_and(|m| m.contains(&i)) {
+                "\"…\"".to_string()
+            } else if !w.quoted
+                || w.substitution
+                || is_code
+                || !w.text.contains(char::is_whitespace)
+            {
+                if w.text.is_empty() {
+                    "\"\"".to_string()
+                } else {
+                    w.text.clone()
+                }
+            } else {
+                "\"…\"".to_string()
             }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `-c`, or `--command=...`
+fn is_code_flag(word: &str) -> bool {
+    CODE_FLAGS.iter().any(|flag| {
+        word.strip_prefix(flag).is_some_and(|rest| {
+            rest.is_empty() || (flag.starts_with("--") && rest.starts_with('='))
+        })
+    })
+}
+
+/// A command line run by this segment — the argument of `sh -c` (also
+/// after `sudo`, `env` and the like) or the arguments of `eval` — with the
+/// words it occupies.
+fn nested_command(words: &[Word]) -> Option<(Range<usize>, String)> {
+    let mut i = 0;
+    while let Some(w) = words.get(i) {
+        let is_assignment = !w.quoted
+            && w.text.split_once('=').is_some_and(|(name, _)| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+        if is_assignment
+            || WRAPPERS.contains(&w.text.as_str())
+            || (i > 0 && w.text.starts_with('-'))
+        {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+
+    let program = words.get(i)?.text.rsplit('/').next()?;
+    if program == "eval" {
+        let payload: Vec<&str> = words[i + 1..].iter().map(|w| w.text.as_str()).collect();
+        return (!payload.is_empty()).then(|| (i + 1..words.len(), payload.join(" ")));
+    }
+    if !SHELLS.contains(&program) {
+        return None;
+    }
+    for (j, w) in words.iter().enumerate().skip(i + 1) {
+        if !w.text.starts_with('-') {
+            return None; // a script file, not -c
+        }
+        if !w.text.starts_with("--") && w.text.contains('c') {
+            return words
+                .get(j + 1)
+                .map(|payload| (j + 1..j + 2, payload.text.clone()));
         }
     }
     None
@@ -480,6 +947,14 @@ fn find_standalone_pipe(s: &str) -> Option<usize> {
 mod tests {
     use super::*;
 
+    /// The segments of a command as patterns see them.
+    fn split_compound_command(command: &str) -> Vec<String> {
+        lex(command)
+            .iter()
+            .map(|words| render(words, None))
+            .collect()
+    }
+
     // ── parse_command ──────────────────────────────────
 
     #[test]
@@ -700,6 +1175,94 @@ mod tests {
         assert!(evaluate_command("rm file.txt").is_none());
     }
 
+    // ── Non-git destructive commands ──────────────────
+
+    #[test]
+    fn denies_git_history_rewrites() {
+        assert!(evaluate_command("git filter-branch --tree-filter 'rm -f secrets' HEAD").is_some());
+        assert!(evaluate_command("git filter-repo --path secrets --invert-paths").is_some());
+        assert!(evaluate_command("git push --mirror origin").is_some());
+    }
+
+    #[test]
+    fn denies_find_delete_and_truncate() {
+        assert!(evaluate_command("find . -name '*.orig' -delete").is_some());
+        assert!(evaluate_command("truncate -s 0 data.db").is_some());
+        assert!(evaluate_command("find . -name '*.orig'").is_none());
+        assert!(evaluate_command("cargo test truncate_tests").is_none());
+    }
+
+    #[test]
+    fn denies_dd_to_block_devices() {
+        assert!(evaluate_command("dd if=image.iso of=/dev/sdb bs=4M").is_some());
+        assert!(evaluate_command("sudo dd if=/dev/zero of=/dev/nvme0n1").is_some());
+        assert!(evaluate_command("dd if=/dev/zero of=/dev/null count=1").is_none());
+        assert!(evaluate_command("dd if=/dev/zero of=blank.img count=1").is_none());
+    }
+
+    #[test]
+    fn denies_recursive_chmod_777() {
+        assert!(evaluate_command("chmod -R 777 .").is_some());
+        assert!(evaluate_command("chmod 777 -R src").is_some());
+        assert!(evaluate_command("chmod --recursive 0777 build").is_some());
+        assert!(evaluate_command("chmod 777 run.sh").is_none());
+        assert!(evaluate_command("chmod -R 755 scripts").is_none());
+    }
+
+    #[test]
+    fn denies_dropping_databases() {
+        assert!(evaluate_command("psql -c 'DROP DATABASE app_dev'").is_some());
+        assert!(evaluate_command("mysql -e \"drop schema shop\"").is_some());
+        assert!(evaluate_command("dropdb app_dev").is_some());
+        assert!(evaluate_command("mysqladmin -u root drop shop").is_some());
+        assert!(evaluate_command("psql -c 'DROP TABLE tmp_import'").is_none());
+        assert!(evaluate_command("psql -l").is_none());
+    }
+
+    // ── Quoting and nested shells ─────────────────────
+
+    #[test]
+    fn split_respects_quotes_and_redirections() {
+        assert_eq!(
+            split_compound_command(r#"echo "a && b; c" | grep 'x|y'"#),
+            vec![r#"echo "…""#, "grep x|y"]
+        );
+        assert_eq!(
+            split_compound_command("cargo test 2>&1 | tee log; make &> out & wait"),
+            vec!["cargo test 2>&1", "tee log", "make &> out", "wait"]
+        );
+        assert_eq!(
+            split_compound_command("git commit -m 'fix: it'\ngit push"),
+            vec![r#"git commit -m "…""#, "git push"]
+        );
+    }
+
+    #[test]
+    fn allows_destructive_text_in_quoted_arguments() {
+        assert!(evaluate_command(r#"echo "a && git push --force""#).is_none());
+        assert!(evaluate_command("git commit -m 'Revert the git reset --hard'").is_none());
+        assert!(evaluate_command(r#"grep -r "rm -rf /" docs"#).is_none());
+    }
+
+    #[test]
+    fn denies_quoted_code_and_substitutions() {
+        assert!(evaluate_command(r#"psql --command="DROP DATABASE app""#).is_some());
+        assert!(evaluate_command(r#"echo "$(git reset --hard)""#).is_some());
+        assert!(evaluate_command(r#"rm -rf "." "#).is_some());
+    }
+
+    #[test]
+    fn denies_nested_shell_payloads() {
+        assert!(evaluate_command("bash -c 'git push --force'").is_some());
+        assert!(evaluate_command(r#"sh -c "cd api && git reset --hard""#).is_some());
+        assert!(evaluate_command("sudo bash -lc 'git clean -fd'").is_some());
+        assert!(evaluate_command("env FOO=1 /bin/zsh --login -c 'git stash clear'").is_some());
+        assert!(evaluate_command(r#"bash -c "sh -c 'git branch -D old'""#).is_some());
+        assert!(evaluate_command(r#"eval "git push --force""#).is_some());
+        assert!(evaluate_command(r#"bash -c 'echo "git push --force"'"#).is_none());
+        assert!(evaluate_command("bash scripts/release.sh --force").is_none());
+    }
+
     // ── Compound commands ─────────────────────────────
 
     #[test]
@@ -765,10 +1328,12 @@ mod tests {
     #[test]
     fn hook_output_serializes_correctly() {
         let output = HookOutput {
+            system_message: None,
             hook_specific_output: HookSpecificOutput {
                 hook_event_name: "PreToolUse".to_string(),
-                permission_decision: "deny".to_string(),
-                permission_decision_reason: "test reason".to_string(),
+                permission_decision: Some("deny".to_string()),
+                permission_decision_reason: Some("test reason".to_string()),
+                additional_context: None,
             },
         };
         let json = serde_json::to_string(&output).unwrap();
@@ -1129,7 +1694,12 @@ message = "test"
         let patterns = config.compile_patterns();
 
         // This command should normally be denied, but with the pattern disabled it should pass
-        let result = evaluate_segment("git push --force origin main", &patterns);
+        let result = evaluate_segment(
+            "git push --force origin main",
+            &patterns,
+            &Session::default(),
+            &mut vec![],
+        );
         assert!(result.is_none());
     }
 
@@ -1146,7 +1716,13 @@ message = "TEAM POLICY: No force push ever!"
 "#;
         let config: GuardConfig = toml::from_str(toml).unwrap();
         let patterns = config.compile_patterns();
-        let result = evaluate_segment("git push --force", &patterns).unwrap();
+        let result = evaluate_segment(
+            "git push --force",
+            &patterns,
+            &Session::default(),
+            &mut vec![],
+        )
+        .unwrap();
         assert_eq!(result.reason, "TEAM POLICY: No force push ever!");
     }
 
@@ -1164,6 +1740,13 @@ message = "TEAM POLICY: No force push ever!"
         assert!(pattern_ids.contains(&"meta.git.stash_drop"));
         assert!(pattern_ids.contains(&"meta.git.stash_clear"));
         assert!(pattern_ids.contains(&"meta.rm.dangerous_paths"));
+        assert!(pattern_ids.contains(&"meta.git.filter_branch"));
+        assert!(pattern_ids.contains(&"meta.git.push_mirror"));
+        assert!(pattern_ids.contains(&"meta.fs.find_delete"));
+        assert!(pattern_ids.contains(&"meta.fs.truncate"));
+        assert!(pattern_ids.contains(&"meta.fs.dd_device"));
+        assert!(pattern_ids.contains(&"meta.fs.chmod_777_recursive"));
+        assert!(pattern_ids.contains(&"meta.db.drop_database"));
     }
 
     #[test]
@@ -1224,4 +1807,164 @@ message = "medium priority"
         assert_eq!(patterns[1].priority, 100);
         assert_eq!(patterns[2].priority, 50);
     }
+
+    #[test]
+    fn custom_rules_run_alongside_patterns() {
+        let toml = r#"
+schema_version = "1.0"
+
+[[patterns]]
+id = "meta.git.reset_hard"
+matcher = { type = "regex", pattern = 'git\s+reset.*--hard\b' }
+message = "no reset"
+
+[[custom]]
+id = "team.terraform_destroy"
+matcher = { type = "tokens", sequence = ["terraform", "destroy"] }
+message = "no destroy"
+
+[[custom]]
+id = "team.prod_context"
+action = "warn"
+matcher = { type = "regex", pattern = '--context[= ]prod' }
+message = "touching prod"
+"#;
+        let config: GuardConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.custom[1].action, Action::Warn);
+        let patterns = config.compile_patterns();
+        assert_eq!(patterns.len(), 3);
+
+        let mut warnings = vec![];
+        let deny = |segment: &str, warnings: &mut Vec<String>| {
+            evaluate_segment(segment, &patterns, &Session::default(), warnings).map(|d| d.reason)
+        };
+        assert_eq!(
+            deny(
+                "terraform -chdir=infra destroy -auto-approve",
+                &mut warnings
+            )
+            .as_deref(),
+            Some("no destroy")
+        );
+        assert_eq!(deny("terraform plan", &mut warnings), None);
+        assert_eq!(deny("destroy terraform", &mut warnings), None);
+        assert_eq!(
+            deny("git reset --hard", &mut warnings).as_deref(),
+            Some("no reset")
+        );
+        assert!(warnings.is_empty());
+
+        assert_eq!(deny("kubectl --context prod get pods", &mut warnings), None);
+        assert_eq!(warnings, ["touching prod"]);
+    }
+
+    #[test]
+    fn allow_rules_lift_patterns_by_cwd_and_args() {
+        let toml = r#"
+[[patterns]]
+id = "meta.git.clean_force"
+matcher = { type = "regex", pattern = 'git\s+clean' }
+validator = { type = "flags_present", command = "clean", flags = ["f", "d"] }
+message = "no clean"
+
+[[patterns]]
+id = "meta.git.force_push"
+matcher = { type = "regex", pattern = 'git\s+push.*(--force|-f)\b' }
+message = "no force push"
+
+[[allow]]
+id = "meta.git.clean_*"
+cwd = ["target/", "**/build"]
+
+[[allow]]
+id = "meta.git.force_push"
+args = ["wip/*"]
+"#;
+        let patterns = toml::from_str::<GuardConfig>(toml)
+            .unwrap()
+            .compile_patterns();
+        let denied = |segment: &str, cwd: &str| {
+            let session = Session {
+                cwd: PathBuf::from(cwd),
+                allowed: vec![],
+            };
+            evaluate_segment(segment, &patterns, &session, &mut vec![]).is_some()
+        };
+
+        assert!(denied("git clean -fd", ""));
+        assert!(!denied("git clean -fd", "target"));
+        assert!(!denied("git clean -fd", "target/debug"));
+        assert!(!denied("git clean -fd", "crates/core/build"));
+        assert!(denied("git clean -fd", "targets"));
+
+        assert!(!denied("git push --force origin wip/spike", ""));
+        assert!(denied("git push --force origin main", ""));
+        assert!(denied("git push --force origin wip/a/b", ""));
+    }
+
+    #[test]
+    fn session_allow_names_pattern_globs() {
+        let session = Session {
+            cwd: PathBuf::new(),
+            allowed: parse_allowed("meta.git.reset_hard, meta.rm.*"),
+        };
+        assert_eq!(session.allowed, ["meta.git.reset_hard", "meta.rm.*"]);
+        assert!(session.allows("meta.git.reset_hard"));
+        assert!(session.allows("meta.rm.dangerous_paths"));
+        assert!(!session.allows("meta.git.force_push"));
+        assert!(Session::default().allowed.is_empty());
+    }
+
+    #[test]
+    fn ask_and_warn_actions_shape_the_hook_output() {
+        let toml = r#"
+[[patterns]]
+id = "team.migrate"
+action = "ask"
+matcher = { type = "tokens", sequence = ["sqlx", "migrate", "run"] }
+message = "Runs migrations"
+
+[[patterns]]
+id = "team.sudo"
+severity = "warn"
+matcher = { type = "regex", pattern = '^sudo\b' }
+message = "Uses sudo"
+
+[[patterns]]
+id = "meta.git.reset_hard"
+matcher = { type = "regex", pattern = 'git\s+reset.*--hard\b' }
+message = "no reset"
+"#;
+        let patterns = toml::from_str::<GuardConfig>(toml)
+            .unwrap()
+            .compile_patterns();
+        let evaluate = |segment: &str| {
+            let mut warnings = vec![];
+            let denial = evaluate_segment(segment, &patterns, &Session::default(), &mut warnings);
+            Evaluation { denial, warnings }
+        };
+        let json = |evaluation: Evaluation| {
+            serde_json::to_value(hook_output(evaluation).unwrap()).unwrap()
+        };
+
+        let v = json(evaluate("sudo sqlx migrate run"));
+        assert_eq!(v["hookSpecificOutput"]["permissionDecision"], "ask");
+        assert_eq!(
+            v["hookSpecificOutput"]["permissionDecisionReason"],
+            "Runs migrations"
+        );
+        assert_eq!(v["systemMessage"], "Warning: Uses sudo");
+
+        let v = json(evaluate("sudo apt update"));
+        assert!(v["hookSpecificOutput"].get("permissionDecision").is_none());
+        assert_eq!(
+            v["hookSpecificOutput"]["additionalContext"],
+            "Warning: Uses sudo"
+        );
+
+        // A deny anywhere beats an ask
+        let evaluation = evaluate("sqlx migrate run; git reset --hard");
+        assert_eq!(evaluation.denial.unwrap().action, Action::Deny);
+        assert!(hook_output(evaluate("cargo build")).is_none());
+    }
 }
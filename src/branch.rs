@@ -0,0 +1,595 @@
+//! Cross-repo branch operations for `meta branch`.
+//!
+//! `create`, `switch` and `delete` run in every selected repo as one
+//! transaction: all repos are checked before anything changes, and if the
+//! operation then fails in one repo, the repos already changed are put back
+//! in reverse order. The report says which state each repo ended up in.
+//! Each change is also recorded in the operation journal, so a completed
+//! (or stuck) operation can be reverted later with `meta undo`.
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::git::Target;
+use crate::git_utils::{self, git};
+use crate::journal::{Journal, OpStatus, UndoAction};
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchOp {
+    /// Create the branch at HEAD and switch to it
+    Create,
+    Switch,
+    /// Delete the branch; `force` also deletes unmerged branches
+    Delete {
+        force: bool,
+    },
+}
+
+impl BranchOp {
+    pub fn name(self) -> &'static str {
+        match self {
+            BranchOp::Create => "create",
+            BranchOp::Switch => "switch",
+            BranchOp::Delete { .. } => "delete",
+        }
+    }
+
+    fn verb(self) -> &'static str {
+        match self {
+            BranchOp::Create => "create",
+            BranchOp::Switch => "switch to",
+            BranchOp::Delete { .. } => "delete",
+        }
+    }
+}
+
+/// Where a repo ended up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepoState {
+    /// The operation was applied and kept
+    Done,
+    /// The operation failed here; the repo is unchanged
+    Failed,
+    /// Applied, then undone because another repo failed
+    RolledBack,
+    /// Applied, but undoing it failed; the repo needs attention
+    RollbackFailed,
+    /// Never touched because a check failed or an earlier repo failed
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoOutcome {
+    pub name: String,
+    pub path: std::path::PathBuf,
+    pub state: RepoState,
+    /// Branch checked out after the operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchReport {
+    pub operation: &'static str,
+    pub branch: String,
+    /// True when every repo is in the `done` state
+    pub success: bool,
+    pub repos: Vec<RepoOutcome>,
+    /// Journal id for `meta undo`, when something is left to undo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub journal: Option<String>,
+}
+
+/// What to restore in a repo if the transaction is rolled back.
+struct Undo {
+    /// Branch (or commit, when detached) checked out before
+    previous: String,
+    /// Commit the branch pointed at before it was deleted
+    deleted_at: Option<String>,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta branch <create|switch|delete> <name>`.
+pub fn handle_branch(
+    op: BranchOp,
+    name: &str,
+    targets: &[Target],
+    json: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let mut journal = None;
+    let report = if dry_run {
+        check_all(op, name, targets)
+    } else {
+        let journal = journal.insert(Journal::begin(
+            &format!("branch-{}", op.name()),
+            &format!("meta branch {} {name}", op.name()),
+        ));
+        run_with_journal(op, name, targets, journal)?
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", format_report(&report));
+        if let Some(hint) = journal.as_ref().and_then(Journal::hint) {
+            println!("{}", hint.dimmed());
+        }
+    }
+    if !report.success {
+        anyhow::bail!("Could not {} branch '{name}' in every repo", op.verb());
+    }
+    Ok(())
+}
+
+/// Apply `op` to every target, rolling back on the first failure.
+pub fn run(op: BranchOp, name: &str, targets: &[Target]) -> Result<BranchReport> {
+    let mut journal = Journal::disabled(&format!("branch-{}", op.name()), name);
+    run_with_journal(op, name, targets, &mut journal)
+}
+
+/// [`run`], recording each repo's change in `journal`.
+pub fn run_with_journal(
+    op: BranchOp,
+    name: &str,
+    targets: &[Target],
+    journal: &mut Journal,
+) -> Result<BranchReport> {
+    validate_name(name)?;
+    let checked = check_all(op, name, targets);
+    if !checked.success {
+        journal.finish(OpStatus::Failed);
+        return Ok(checked);
+    }
+
+    let mut outcomes: Vec<RepoOutcome> = checked.repos;
+    let mut applied: Vec<(usize, Undo)> = Vec::new();
+    let mut failure = None;
+    for (i, (repo, path)) in targets.iter().enumerate() {
+        match apply(op, name, path) {
+            Ok(undo) => {
+                outcomes[i].state = RepoState::Done;
+                outcomes[i].detail = done_detail(op, name);
+                journal.step(
+                    repo,
+                    path,
+                    &outcomes[i].detail,
+                    undo_actions(op, name, path, &undo),
+                );
+                applied.push((i, undo));
+            }
+            Err(e) => {
+                outcomes[i].state = RepoState::Failed;
+                outcomes[i].detail = format!("{e:#}");
+                failure = Some(i);
+                break;
+            }
+        }
+    }
+
+    if let Some(failed) = failure {
+        for (i, undo) in applied.iter().rev() {
+            let path = &targets[*i].1;
+            match rollback(op, name, path, undo) {
+                Ok(()) => {
+                    journal.mark_undone(&targets[*i].0);
+                    outcomes[*i].state = RepoState::RolledBack;
+                    outcomes[*i].detail = format!("rolled back (back on {})", undo.previous);
+                }
+                Err(e) => {
+                    outcomes[*i].state = RepoState::RollbackFailed;
+                    outcomes[*i].detail = format!("rollback failed: {e:#}");
+                }
+            }
+        }
+        for outcome in outcomes.iter_mut().skip(failed + 1) {
+            outcome.detail = format!("not attempted ({} failed)", targets[failed].0);
+        }
+    }
+
+    for (outcome, (_, path)) in outcomes.iter_mut().zip(targets) {
+        outcome.branch = git_utils::current_branch(path);
+    }
+    journal.finish(match failure {
+        None => OpStatus::Completed,
+        Some(_)
+            if outcomes
+                .iter()
+                .all(|o| o.state != RepoState::RollbackFailed) =>
+        {
+            OpStatus::RolledBack
+        }
+        Some(_) => OpStatus::Failed,
+    });
+    Ok(BranchReport {
+        operation: op.name(),
+        branch: name.to_string(),
+        success: failure.is_none(),
+        repos: outcomes,
+        journal: journal.undo_id().map(str::to_string),
+    })
+}
+
+/// Check every target without changing anything. Failed checks are
+/// reported as `failed`, the rest as `unchanged`.
+pub fn check_all(op: BranchOp, name: &str, targets: &[Target]) -> BranchReport {
+    let repos: Vec<RepoOutcome> = targets
+        .iter()
+        .map(|(repo, path)| {
+            let (state, detail) = match check(op, name, path) {
+                Ok(()) => (
+                    RepoState::Unchanged,
+                    format!("ready to {} {name}", op.verb()),
+                ),
+                Err(e) => (RepoState::Failed, format!("{e:#}")),
+            };
+            RepoOutcome {
+                name: repo.clone(),
+                path: path.clone(),
+                state,
+                branch: is_repo(path)
+                    .then(|| git_utils::current_branch(path))
+                    .flatten(),
+                detail,
+            }
+        })
+        .collect();
+    let success = repos.iter().all(|r| r.state != RepoState::Failed);
+    let mut report = BranchReport {
+        operation: op.name(),
+        branch: name.to_string(),
+        success,
+        repos,
+        journal: None,
+    };
+    if !success {
+        for outcome in &mut report.repos {
+            if outcome.state == RepoState::Unchanged {
+                outcome.detail = "not attempted (checks failed elsewhere)".to_string();
+            }
+        }
+    }
+    report
+}
+
+/// Render the report as one aligned row per repo plus a summary line.
+pub fn format_report(report: &BranchReport) -> String {
+    let name_width = report
+        .repos
+        .iter()
+        .map(|r| r.name.chars().count())
+        .chain(std::iter::once(4))
+        .max()
+        .unwrap_or(0);
+    let state_width = report
+        .repos
+        .iter()
+        .map(|r| state_label(r.state).len())
+        .chain(std::iter::once(5))
+        .max()
+        .unwrap_or(0);
+
+    let mut out = format!(
+        "{}\n",
+        format!("{:<name_width$}  {:<state_width$}  DETAIL", "REPO", "STATE").bold()
+    );
+    for r in &report.repos {
+        let label = format!("{:<state_width$}", state_label(r.state));
+        let label = match r.state {
+            RepoState::Done => label.green(),
+            RepoState::Failed | RepoState::RollbackFailed => label.red(),
+            RepoState::RolledBack => label.yellow(),
+            RepoState::Unchanged => label.normal(),
+        };
+        out.push_str(&format!("{:<name_width$}  {label}  {}\n", r.name, r.detail));
+    }
+
+    let count = |state| report.repos.iter().filter(|r| r.state == state).count();
+    let summary = if report.success {
+        format!("{} repos ok", report.repos.len())
+    } else {
+        let mut parts = vec![format!("{} failed", count(RepoState::Failed))];
+        for (state, label) in [
+            (RepoState::RolledBack, "rolled back"),
+            (RepoState::RollbackFailed, "could not be rolled back"),
+            (RepoState::Unchanged, "unchanged"),
+        ] {
+            let n = count(state);
+            if n > 0 {
+                parts.push(format!("{n} {label}"));
+            }
+        }
+        parts.join(", ")
+    };
+    out.push_str(&format!("\n{summary}\n"));
+    out
+}
+
+// ── Per-repo Steps ──────────────────────────────────────
+
+fn check(op: BranchOp, name: &str, path: &Path) -> Result<()> {
+    anyhow::ensure!(path.exists(), "not cloned");
+    anyhow::ensure!(is_repo(path), "not a git repository");
+    let exists = branch_exists(path, name);
+    let current = git_utils::current_branch(path);
+    match op {
+        BranchOp::Create => {
+            anyhow::ensure!(!exists, "branch '{name}' already exists");
+            git(path, &["rev-parse", "--verify", "-q", "HEAD"])
+                .map_err(|_| anyhow::anyhow!("no commits yet"))?;
+        }
+        BranchOp::Switch => {
+            anyhow::ensure!(exists, "no branch '{name}'");
+            if current.as_deref() != Some(name) {
+                // Untracked files survive a checkout; changes to tracked ones may not
+                let changed = git(path, &["status", "--porcelain", "--untracked-files=no"])?;
+                let dirty = changed.lines().count();
+                anyhow::ensure!(dirty == 0, "working tree has {dirty} uncommitted change(s)");
+            }
+        }
+        BranchOp::Delete { .. } => {
+            anyhow::ensure!(exists, "no branch '{name}'");
+            anyhow::ensure!(
+                current.as_deref() != Some(name),
+                "'{name}' is checked out here; switch away first"
+            );
+        }
+    }
+    Ok(())
+}
+
+fn apply(op: BranchOp, name: &str, path: &Path) -> Result<Undo> {
+    let previous = match git_utils::current_branch(path) {
+        Some(branch) => branch,
+        None => git(path, &["rev-parse", "HEAD"])?,
+    };
+    let mut undo = Undo {
+        previous,
+        deleted_at: None,
+    };
+    match op {
+        BranchOp::Create => git(path, &["checkout", "-q", "-b", name])?,
+        BranchOp::Switch => git(path, &["checkout", "-q", name])?,
+        BranchOp::Delete { force } => {
+            let at = git(path, &["rev-parse", &format!("refs/heads/{name}")])?;
+            git(path, &["branch", if force { "-D" } else { "-d" }, name])?;
+            undo.deleted_at = Some(at);
+            String::new()
+        }
+    };
+    Ok(undo)
+}
+
+fn rollback(op: BranchOp, name: &str, path: &Path, undo: &Undo) -> Result<()> {
+    match op {
+        BranchOp::Create => {
+            git(path, &["checkout", "-q", &undo.previous])?;
+            git(path, &["branch", "-D", name])?;
+        }
+        BranchOp::Switch => {
+            git(path, &["checkout", "-q", &undo.previous])?;
+        }
+        BranchOp::Delete { .. } => {
+            let at = undo
+                .deleted_at
+                .as_deref()
+                .context("commit of deleted branch unknown")?;
+            git(path, &["branch", name, at])?;
+        }
+    }
+    Ok(())
+}
+
+/// The journal's record of how to revert `apply`, for `meta undo`.
+fn undo_actions(op: BranchOp, name: &str, path: &Path, undo: &Undo) -> Vec<UndoAction> {
+    let back = UndoAction::Checkout {
+        to: undo.previous.clone(),
+    };
+    match op {
+        BranchOp::Create => vec![
+            back,
+            UndoAction::DeleteBranch {
+                name: name.to_string(),
+                at: git(path, &["rev-parse", "HEAD"]).unwrap_or_default(),
+            },
+        ],
+        BranchOp::Switch => vec![back],
+        BranchOp::Delete { .. } => undo
+            .deleted_at
+            .iter()
+            .map(|at| UndoAction::RestoreBranch {
+                name: name.to_string(),
+                at: at.clone(),
+            })
+            .collect(),
+    }
+}
+
+fn done_detail(op: BranchOp, name: &str) -> String {
+    match op {
+        BranchOp::Create => format!("created and switched to {name}"),
+        BranchOp::Switch => format!("switched to {name}"),
+        BranchOp::Delete { .. } => format!("deleted {name}"),
+    }
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+/// Only repo roots count, otherwise git reports the enclosing (meta) repo
+fn is_repo(path: &Path) -> bool {
+    path.join(".git").exists()
+}
+
+fn branch_exists(path: &Path, name: &str) -> bool {
+    git(
+        path,
+        &["rev-parse", "--verify", "-q", &format!("refs/heads/{name}")],
+    )
+    .is_ok()
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = Command::new("git")
+        .args(["check-ref-format", "--branch", name])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    anyhow::ensure!(
+        valid && !name.starts_with('-'),
+        "Invalid branch name '{name}'"
+    );
+    Ok(())
+}
+
+fn state_label(state: RepoState) -> &'static str {
+    match state {
+        RepoState::Done => "done",
+        RepoState::Failed => "failed",
+        RepoState::RolledBack => "rolled back",
+        RepoState::RollbackFailed => "rollback failed",
+        RepoState::Unchanged => "unchanged",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::testing::init_repo;
+    use std::path::PathBuf;
+
+    fn workspace(names: &[&str]) -> (tempfile::TempDir, Vec<Target>) {
+        let tmp = tempfile::tempdir().unwrap();
+        let targets = names
+            .iter()
+            .map(|name| {
+                let path: PathBuf = tmp.path().join(name);
+                init_repo(&path);
+                (name.to_string(), path)
+            })
+            .collect();
+        (tmp, targets)
+    }
+
+    fn states(report: &BranchReport) -> Vec<RepoState> {
+        report.repos.iter().map(|r| r.state).collect()
+    }
+
+    #[test]
+    fn create_switch_and_delete_across_repos() {
+        let (_tmp, targets) = workspace(&["api", "web"]);
+
+        let report = run(BranchOp::Create, "feature", &targets).unwrap();
+        assert!(report.success);
+        assert_eq!(states(&report), [RepoState::Done, RepoState::Done]);
+        assert_eq!(report.repos[0].branch.as_deref(), Some("feature"));
+
+        let report = run(BranchOp::Switch, "main", &targets).unwrap();
+        assert!(report.success);
+        assert_eq!(
+            git_utils::current_branch(&targets[1].1).as_deref(),
+            Some("main")
+        );
+
+        let report = run(BranchOp::Delete { force: false }, "feature", &targets).unwrap();
+        assert!(report.success);
+        assert!(!branch_exists(&targets[0].1, "feature"));
+    }
+
+    #[test]
+    fn failed_checks_change_nothing() {
+        let (_tmp, targets) = workspace(&["api", "web"]);
+        git(&targets[0].1, &["branch", "feature"]).unwrap();
+        git(&targets[1].1, &["branch", "feature"]).unwrap();
+        std::fs::write(targets[1].1.join("README.md"), "dirty\n").unwrap();
+        std::fs::write(targets[0].1.join("untracked.txt"), "ok\n").unwrap();
+
+        let report = run(BranchOp::Switch, "feature", &targets).unwrap();
+        assert!(!report.success);
+        assert_eq!(states(&report), [RepoState::Unchanged, RepoState::Failed]);
+        assert!(report.repos[1].detail.contains("1 uncommitted change"));
+        assert_eq!(
+            git_utils::current_branch(&targets[0].1).as_deref(),
+            Some("main")
+        );
+
+        let report = run(BranchOp::Delete { force: false }, "nope", &targets).unwrap();
+        assert_eq!(states(&report), [RepoState::Failed, RepoState::Failed]);
+        assert!(run(BranchOp::Create, "bad..name", &targets).is_err());
+    }
+
+    #[test]
+    fn failure_while_applying_rolls_back_earlier_repos() {
+        let (_tmp, targets) = workspace(&["api", "web", "docs"]);
+        // web passes the checks, but deleting an unmerged branch fails
+        for (_, path) in &targets {
+            git(path, &["branch", "old"]).unwrap();
+        }
+        let web = &targets[1].1;
+        git(web, &["checkout", "-q", "old"]).unwrap();
+        std::fs::write(web.join("new.txt"), "x").unwrap();
+        git(web, &["add", "."]).unwrap();
+        git(web, &["commit", "-q", "-m", "unmerged"]).unwrap();
+        git(web, &["checkout", "-q", "main"]).unwrap();
+
+        let report = run(BranchOp::Delete { force: false }, "old", &targets).unwrap();
+        assert!(!report.success);
+        assert_eq!(
+            states(&report),
+            [
+                RepoState::RolledBack,
+                RepoState::Failed,
+                RepoState::Unchanged
+            ]
+        );
+        assert!(report.repos[2].detail.contains("web failed"));
+        for (_, path) in &targets {
+            assert!(branch_exists(path, "old"));
+        }
+
+        let text = console::strip_ansi_codes(&format_report(&report)).to_string();
+        assert!(
+            text.ends_with("\n1 failed, 1 rolled back, 1 unchanged\n"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn rolled_back_create_removes_the_branch() {
+        let (_tmp, targets) = workspace(&["api", "web"]);
+        let undo = apply(BranchOp::Create, "feature", &targets[0].1).unwrap();
+        assert_eq!(undo.previous, "main");
+        rollback(BranchOp::Create, "feature", &targets[0].1, &undo).unwrap();
+        assert_eq!(
+            git_utils::current_branch(&targets[0].1).as_deref(),
+            Some("main")
+        );
+        assert!(!branch_exists(&targets[0].1, "feature"));
+    }
+
+    #[test]
+    fn journaled_create_can_be_undone() {
+        let (tmp, targets) = workspace(&["api", "web"]);
+        let dir = tmp.path().join("journal");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut journal = Journal::begin_in(&dir, "branch-create", "meta branch create feature");
+        let report = run_with_journal(BranchOp::Create, "feature", &targets, &mut journal).unwrap();
+        let id = report.journal.expect("journal id");
+
+        let mut op = crate::journal::find_in(&dir, &id).unwrap();
+        assert_eq!(op.status, OpStatus::Completed);
+        let results = crate::journal::undo_in(&dir, &mut op).unwrap();
+        assert!(results.iter().all(|r| r.success), "{results:?}");
+        for (_, path) in &targets {
+            assert_eq!(git_utils::current_branch(path).as_deref(), Some("main"));
+            assert!(!branch_exists(path, "feature"));
+        }
+    }
+}
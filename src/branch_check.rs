@@ -0,0 +1,299 @@
+//! Expected working branches for `meta check-branches`.
+//!
+//! A project in `.meta` may name the branch its primary checkout should be
+//! on:
+//!
+//! ```json
+//! "api": { "repo": "git@github.com:org/api.git", "branch": "main" }
+//! ```
+//!
+//! `meta check-branches` lists repos on another branch and `--fix` switches
+//! the clean ones back. `meta status` and `meta exec` print a warning for
+//! each such repo they touch. Worktree checkouts are never checked: being on
+//! a different branch is their point.
+
+use anyhow::{Context, Result};
+use colored::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config;
+use crate::error::MetaError;
+use crate::git_utils;
+
+// ── Types ───────────────────────────────────────────────
+
+/// A project that declares its expected branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchRule {
+    pub name: String,
+    /// Absolute path of the primary checkout
+    pub path: PathBuf,
+    pub branch: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BranchState {
+    /// On the expected branch
+    Ok,
+    /// On another branch, or detached
+    Wrong,
+    /// Switched back by `--fix`
+    Fixed,
+    /// Left alone by `--fix` (see `reason`)
+    Skipped,
+    /// Not cloned, so there is nothing to check
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchCheck {
+    pub name: String,
+    pub path: PathBuf,
+    pub expected: String,
+    /// `None` for a detached HEAD or a missing repo
+    pub actual: Option<String>,
+    pub state: BranchState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// The `branch` of every project in the config that sets one.
+pub fn rules(config_path: &Path) -> Result<Vec<BranchRule>> {
+    let meta_dir = config_path.parent().unwrap_or(Path::new("."));
+    let (projects, _ignore) = config::parse_meta_config(config_path)?;
+    let value = config::read_meta_config_value(config_path)?;
+    Ok(projects
+        .into_iter()
+        .filter_map(|p| {
+            let branch = value["projects"][&p.name].get("branch")?.as_str()?;
+            Some(BranchRule {
+                path: meta_dir.join(&p.path),
+                name: p.name,
+                branch: branch.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Compare each rule with the branch its checkout is on.
+pub fn check(rules: &[BranchRule]) -> Vec<BranchCheck> {
+    rules
+        .par_iter()
+        .map(|rule| {
+            let is_repo = rule.path.join(".git").exists();
+            let actual = is_repo
+                .then(|| git_utils::current_branch(&rule.path))
+                .flatten();
+            let state = if !is_repo {
+                BranchState::Missing
+            } else if actual.as_deref() == Some(rule.branch.as_str()) {
+                BranchState::Ok
+            } else {
+                BranchState::Wrong
+            };
+            BranchCheck {
+                name: rule.name.clone(),
+                path: rule.path.clone(),
+                expected: rule.branch.clone(),
+                actual,
+                state,
+                reason: None,
+            }
+        })
+        .collect()
+}
+
+/// Switch a repo on the wrong branch back, unless it has uncommitted changes.
+pub fn fix(check: &mut BranchCheck) {
+    if check.state != BranchState::Wrong {
+        return;
+    }
+    if git_utils::is_dirty(&check.path) != Some(false) {
+        check.state = BranchState::Skipped;
+        check.reason = Some("has uncommitted changes".to_string());
+        return;
+    }
+    match switch(&check.path, &check.expected) {
+        Ok(()) => check.state = BranchState::Fixed,
+        Err(e) => {
+            check.state = BranchState::Skipped;
+            check.reason = Some(e.to_string());
+        }
+    }
+}
+
+/// One warning line per repo among `dirs` that is on the wrong branch.
+/// Empty when the config sets no branches or cannot be read.
+pub fn warnings(config_path: &Path, dirs: &[String]) -> Vec<String> {
+    let Ok(rules) = rules(config_path) else {
+        return Vec::new();
+    };
+    let rules: Vec<BranchRule> = rules
+        .into_iter()
+        .filter(|r| dirs.iter().any(|d| Path::new(d) == r.path))
+        .collect();
+    check(&rules)
+        .iter()
+        .filter(|c| c.state == BranchState::Wrong)
+        .map(|c| {
+            format!(
+                "{}: {} is on {}, expected '{}' (meta check-branches --fix)",
+                "warning".yellow().bold(),
+                c.name,
+                describe(c.actual.as_deref()),
+                c.expected
+            )
+        })
+        .collect()
+}
+
+/// Entry point for `meta check-branches [--fix]`. Exits non-zero while any
+/// repo is left on the wrong branch.
+pub fn handle_check_branches(config_path: &Path, apply_fix: bool, json: bool) -> Result<()> {
+    let rules = rules(config_path)?;
+    let mut checks = check(&rules);
+    if apply_fix {
+        checks.iter_mut().for_each(fix);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&checks)?);
+    } else {
+        print!("{}", format_checks(&checks));
+    }
+    let wrong = checks
+        .iter()
+        .filter(|c| matches!(c.state, BranchState::Wrong | BranchState::Skipped))
+        .count();
+    if wrong > 0 {
+        return Err(MetaError::ChecksFailed(format!("{wrong} repo(s) on the wrong branch")).into());
+    }
+    Ok(())
+}
+
+/// One line per project with an expected branch.
+pub fn format_checks(checks: &[BranchCheck]) -> String {
+    if checks.is_empty() {
+        return "No project in .meta sets a branch\n".to_string();
+    }
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for c in checks {
+        let actual = describe(c.actual.as_deref());
+        let (mark, detail) = match c.state {
+            BranchState::Ok => ("✓".green(), c.expected.clone()),
+            BranchState::Wrong => ("✗".red(), format!("on {actual}, expected '{}'", c.expected)),
+            BranchState::Fixed => (
+                "→".cyan(),
+                format!("switched from {actual} to '{}'", c.expected),
+            ),
+            BranchState::Skipped => (
+                "!".yellow(),
+                format!(
+                    "on {actual}, expected '{}'; not switched: {}",
+                    c.expected,
+                    c.reason.as_deref().unwrap_or("unknown error")
+                ),
+            ),
+            BranchState::Missing => ("-".dimmed(), "not cloned".to_string()),
+        };
+        out.push_str(&format!("{mark} {:<width$}  {detail}\n", c.name));
+    }
+    out
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn describe(branch: Option<&str>) -> String {
+    branch.map_or_else(|| "a detached HEAD".to_string(), |b| format!("'{b}'"))
+}
+
+fn switch(repo: &Path, branch: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["switch", "--quiet", branch])
+        .current_dir(repo)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run git")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::{git, testing::init_repo};
+
+    fn repo_on(dir: &Path, branch: &str) {
+        init_repo(dir);
+        if branch != "main" {
+            git(dir, &["switch", "-q", "-c", branch]).unwrap();
+        }
+    }
+
+    #[test]
+    fn fix_switches_clean_repos_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = tmp.path().join(".meta");
+        std::fs::write(
+            &config,
+            r#"{"projects": {
+                "api": {"path": "api", "branch": "main"},
+                "web": {"path": "web", "branch": "main"},
+                "docs": {"path": "docs", "branch": "main"},
+                "cli": {"path": "cli", "branch": "develop"},
+                "free": {"path": "free"}
+            }}"#,
+        )
+        .unwrap();
+        repo_on(&tmp.path().join("api"), "feature");
+        repo_on(&tmp.path().join("web"), "feature");
+        std::fs::write(tmp.path().join("web/wip.txt"), "wip").unwrap();
+        repo_on(&tmp.path().join("docs"), "main");
+
+        let rules = rules(&config).unwrap();
+        let names: Vec<&str> = rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["api", "cli", "docs", "web"]);
+
+        let mut checks = check(&rules);
+        let states: Vec<BranchState> = checks.iter().map(|c| c.state).collect();
+        use BranchState::{Fixed, Missing, Skipped, Wrong};
+        assert_eq!(states, [Wrong, Missing, BranchState::Ok, Wrong]);
+
+        checks.iter_mut().for_each(fix);
+        let states: Vec<BranchState> = checks.iter().map(|c| c.state).collect();
+        assert_eq!(states, [Fixed, Missing, BranchState::Ok, Skipped]);
+        assert_eq!(
+            git_utils::current_branch(&tmp.path().join("api")).as_deref(),
+            Some("main")
+        );
+
+        let text = console::strip_ansi_codes(&format_checks(&checks)).to_string();
+        assert!(
+            text.contains("→ api   switched from 'feature' to 'main'"),
+            "{text}"
+        );
+        assert!(
+            text.contains("not switched: has uncommitted changes"),
+            "{text}"
+        );
+
+        // Only the directories a command runs in are warned about
+        let web = tmp.path().join("web").display().to_string();
+        let warnings = warnings(&config, &[web]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("web is on 'feature', expected 'main'"));
+    }
+}
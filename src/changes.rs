@@ -0,0 +1,199 @@
+//! Change detection for `--changed-since`.
+//!
+//! A repo counts as changed when its working tree differs from the base ref
+//! (committed or not) or it has untracked files. Repos where the base cannot
+//! be resolved are treated as changed, so nothing is skipped by mistake.
+//!
+//! The special base [`LAST_SUCCESS`] compares against the commit each repo
+//! was at the last time the same command succeeded there. Those commits are
+//! recorded in `~/.meta/state.json` after every `--changed-since` run.
+
+use anyhow::Result;
+use loop_lib::DirCommand;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::runner::RunReport;
+
+/// Base meaning "the commit of the last successful run of this command".
+pub const LAST_SUCCESS: &str = "last-success";
+
+/// Persisted run state: repo directory → command → commit it last succeeded at.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RunState {
+    #[serde(default)]
+    pub repos: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl RunState {
+    pub fn load() -> Result<Self> {
+        meta_core::store::read(&state_path())
+    }
+
+    /// Commit at which `command` last succeeded in `dir`.
+    pub fn last_success(&self, dir: &str, command: &str) -> Option<&str> {
+        self.repos.get(dir)?.get(command).map(String::as_str)
+    }
+}
+
+fn state_path() -> PathBuf {
+    meta_core::data_dir::data_file("state")
+}
+
+/// Outcome of filtering commands by change.
+#[derive(Debug, Default)]
+pub struct ChangeFilter {
+    pub changed: Vec<DirCommand>,
+    /// Directories skipped because nothing changed.
+    pub unchanged: Vec<String>,
+}
+
+/// Keep only the commands whose directory changed since `base`.
+pub fn filter_changed(commands: Vec<DirCommand>, base: &str) -> Result<ChangeFilter> {
+    let state = if base == LAST_SUCCESS {
+        Some(RunState::load()?)
+    } else {
+        None
+    };
+
+    let flags: Vec<bool> = commands
+        .par_iter()
+        .map(|c| {
+            let base = match &state {
+                Some(state) => state.last_success(&c.dir, &c.cmd),
+                None => Some(base),
+            };
+            base.is_none_or(|base| has_changes(Path::new(&c.dir), base))
+        })
+        .collect();
+
+    let mut filter = ChangeFilter::default();
+    for (command, changed) in commands.into_iter().zip(flags) {
+        if changed {
+            filter.changed.push(command);
+        } else {
+            filter.unchanged.push(command.dir);
+        }
+    }
+    Ok(filter)
+}
+
+/// Whether `repo` differs from `base`. Unknown refs and non-git directories
+/// count as changed.
+pub fn has_changes(repo: &Path, base: &str) -> bool {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .current_dir(repo)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+    };
+
+    let commit = format!("{base}^{{commit}}");
+    match git(&["rev-parse", "--verify", "--quiet", &commit]) {
+        Some(out) if out.status.success() => {}
+        _ => return true,
+    }
+    match git(&["diff", "--quiet", base, "--"]) {
+        Some(out) if out.status.code() == Some(0) => {}
+        _ => return true,
+    }
+    match git(&["ls-files", "--others", "--exclude-standard"]) {
+        Some(out) if out.status.success() => !out.stdout.is_empty(),
+        _ => true,
+    }
+}
+
+/// Record the current commit of every repo whose command succeeded.
+pub fn record_successes(report: &RunReport) -> Result<()> {
+    let heads: Vec<(String, String, String)> = report
+        .results
+        .iter()
+        .filter(|r| r.success())
+        .filter_map(|r| {
+            let head = Command::new("git")
+                .args(["rev-parse", "HEAD"])
+                .current_dir(&r.directory)
+                .stderr(Stdio::null())
+                .output()
+                .ok()
+                .filter(|o| o.status.success())?;
+            let head = String::from_utf8_lossy(&head.stdout).trim().to_string();
+            Some((r.directory.display().to_string(), r.command.clone(), head))
+        })
+        .collect();
+    if heads.is_empty() {
+        return Ok(());
+    }
+
+    meta_core::data_dir::ensure_meta_dir()?;
+    let lock = meta_core::meta_dir().join("state.lock");
+    meta_core::store::update(&state_path(), &lock, |state: &mut RunState| {
+        for (dir, command, head) in heads {
+            state.repos.entry(dir).or_default().insert(command, head);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::{git, testing::init_repo};
+
+    fn repo_with_commit() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        init_repo(tmp.path());
+        git(tmp.path(), &["tag", "base"]).unwrap();
+        tmp
+    }
+
+    #[test]
+    fn detects_commits_edits_and_untracked_files() {
+        let tmp = repo_with_commit();
+        let dir = tmp.path();
+        assert!(!has_changes(dir, "base"));
+
+        std::fs::write(dir.join("new.txt"), "x").unwrap();
+        assert!(has_changes(dir, "base"), "untracked file");
+        git(dir, &["add", "."]).unwrap();
+        git(dir, &["commit", "-q", "-m", "second"]).unwrap();
+        assert!(has_changes(dir, "base"), "new commit");
+        assert!(!has_changes(dir, "HEAD"));
+
+        std::fs::write(dir.join("README.md"), "two").unwrap();
+        assert!(has_changes(dir, "HEAD"), "uncommitted edit");
+    }
+
+    #[test]
+    fn unknown_base_counts_as_changed() {
+        let tmp = repo_with_commit();
+        assert!(has_changes(tmp.path(), "no-such-ref"));
+        let plain = tempfile::tempdir().unwrap();
+        assert!(has_changes(plain.path(), "HEAD"));
+    }
+
+    #[test]
+    fn filter_splits_changed_and_unchanged() {
+        let clean = repo_with_commit();
+        let dirty = repo_with_commit();
+        std::fs::write(dirty.path().join("README.md"), "edited").unwrap();
+        let commands = [clean.path(), dirty.path()]
+            .iter()
+            .map(|d| DirCommand {
+                dir: d.display().to_string(),
+                cmd: "make".to_string(),
+                env: None,
+            })
+            .collect();
+
+        let filter = filter_changed(commands, "base").unwrap();
+        assert_eq!(filter.changed.len(), 1);
+        assert_eq!(filter.changed[0].dir, dirty.path().display().to_string());
+        assert_eq!(filter.unchanged, vec![clean.path().display().to_string()]);
+    }
+}
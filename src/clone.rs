@@ -0,0 +1,579 @@
+//! Native `meta clone`: bootstrap a workspace without the git plugin.
+//!
+//! Clones the meta repo, reads its config, then clones every child project
+//! (in parallel by default). Projects that are already cloned are left alone,
+//! so re-running the same command after a failure picks up where the last run
+//! stopped, and each clone is retried a few times before it counts as failed.
+
+use anyhow::{Context, Result};
+use colored::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::config::{self, ProjectInfo};
+
+/// Retries per repo unless `--retries` says otherwise.
+pub const DEFAULT_RETRIES: usize = 2;
+
+/// Base delay between attempts; attempt N waits N times this long.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+// ── Types ───────────────────────────────────────────────
+
+/// Options for a workspace clone.
+#[derive(Debug, Clone)]
+pub struct CloneOptions {
+    /// URL of the meta repo.
+    pub url: String,
+    /// Where to clone it; defaults to the repo name from the URL.
+    pub directory: Option<PathBuf>,
+    /// Shallow-clone depth passed to `git clone --depth`.
+    pub depth: Option<usize>,
+    /// Only clone these projects (by name). Empty means all.
+    pub include_only: Vec<String>,
+    /// Only clone projects with at least one of these tags. Empty means all.
+    pub tags: Vec<String>,
+    /// Extra attempts per repo after the first failure.
+    pub retries: usize,
+    pub retry_delay: Duration,
+    pub parallel: bool,
+    /// Maximum concurrent clones; `None` uses rayon's default.
+    pub jobs: Option<usize>,
+    /// Also clone the projects of nested meta repos.
+    pub recursive: bool,
+    pub dry_run: bool,
+    /// Suppress per-repo progress lines (e.g. for JSON output).
+    pub quiet: bool,
+}
+
+impl CloneOptions {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            directory: None,
+            depth: None,
+            include_only: Vec::new(),
+            tags: Vec::new(),
+            retries: DEFAULT_RETRIES,
+            retry_delay: RETRY_DELAY,
+            parallel: true,
+            jobs: None,
+            recursive: false,
+            dry_run: false,
+            quiet: false,
+        }
+    }
+
+    /// Build options from `git clone` style arguments (`<url> [dir] [--depth N]`),
+    /// used when `meta git clone` has no plugin to handle it.
+    pub fn from_git_args(args: &[String]) -> Result<Self> {
+        let mut positional = Vec::new();
+        let mut depth = None;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "--depth" {
+                let value = iter.next().context("--depth requires a value")?;
+                depth = Some(value.parse().context("--depth must be a number")?);
+            } else if let Some(value) = arg.strip_prefix("--depth=") {
+                depth = Some(value.parse().context("--depth must be a number")?);
+            } else if !arg.starts_with('-') {
+                positional.push(arg);
+            }
+        }
+
+        let url = positional
+            .first()
+            .context("Usage: meta git clone <meta-repo-url> [directory]")?;
+        let mut options = Self::new(url.as_str());
+        options.directory = positional.get(1).map(PathBuf::from);
+        options.depth = depth;
+        Ok(options)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloneStatus {
+    Cloned,
+    /// Already cloned by an earlier run.
+    Present,
+    /// The project has no `repo` URL.
+    NoRepo,
+    /// Dry run: would have been cloned.
+    Planned,
+    Failed,
+}
+
+/// Outcome for one project.
+#[derive(Debug, Clone, Serialize)]
+pub struct CloneResult {
+    pub name: String,
+    /// Path relative to the meta repo.
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub status: CloneStatus,
+    /// Clone attempts made (0 if nothing was run).
+    pub attempts: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta clone`.
+pub fn handle_clone(options: &CloneOptions, json: bool) -> Result<()> {
+    let mut options = options.clone();
+    options.quiet |= json;
+    let (target, results) = clone_workspace(&options)?;
+    let failed = results
+        .iter()
+        .filter(|r| r.status == CloneStatus::Failed)
+        .count();
+
+    if json {
+        let output = serde_json::json!({
+            "success": failed == 0,
+            "directory": target.display().to_string(),
+            "results": results,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        print_summary(&target, &results);
+    }
+
+    if failed > 0 {
+        anyhow::bail!(
+            "Failed to clone {} project(s); re-run the same command to retry them",
+            failed
+        );
+    }
+    Ok(())
+}
+
+/// Clone the meta repo (unless already present) and all selected projects.
+/// Returns the meta repo directory and one result per project.
+pub fn clone_workspace(options: &CloneOptions) -> Result<(PathBuf, Vec<CloneResult>)> {
+    let target = options
+        .directory
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(repo_dir_name(&options.url)));
+
+    if target.join(".git").exists() {
+        if !options.quiet {
+            println!(
+                "{} {} already cloned, resuming",
+                "•".yellow(),
+                target.display()
+            );
+        }
+    } else if options.dry_run {
+        println!(
+            "{} Would clone {} into {}",
+            "[DRY RUN]".cyan(),
+            options.url,
+            target.display()
+        );
+        return Ok((target, Vec::new()));
+    } else {
+        ensure_clone_target(&target)?;
+        clone_with_retries(
+            &options.url,
+            &target,
+            options,
+            &target.display().to_string(),
+        )
+        .map_err(|(e, _)| anyhow::anyhow!("Failed to clone {}: {}", options.url, e))?;
+        if !options.quiet {
+            println!("{} {}", "✓".green(), target.display());
+        }
+    }
+
+    let results = clone_projects(&target, options, true)?;
+    Ok((target, results))
+}
+
+/// Clone the selected projects of an existing workspace that are not cloned
+/// yet, for `meta sync`. `options.url` is not used.
+pub fn clone_missing(meta_dir: &Path, options: &CloneOptions) -> Result<Vec<CloneResult>> {
+    clone_projects(meta_dir, options, true)
+}
+
+/// Directory name `git clone` would pick for a URL.
+pub fn repo_dir_name(url: &str) -> String {
+    let trimmed = url.trim_end_matches('/');
+    let last = trimmed.rsplit(['/', ':', '\\']).next().unwrap_or(trimmed);
+    last.strip_suffix(".git").unwrap_or(last).to_string()
+}
+
+// ── Cloning ─────────────────────────────────────────────
+
+fn clone_projects(
+    meta_dir: &Path,
+    options: &CloneOptions,
+    apply_filters: bool,
+) -> Result<Vec<CloneResult>> {
+    let (config_path, _format) = config::find_meta_config_in(meta_dir)
+        .ok_or_else(|| anyhow::anyhow!("No .meta config found in {}", meta_dir.display()))?;
+    let (projects, _ignore) = config::parse_meta_config(&config_path)?;
+
+    // Filters name top-level projects; nested meta repos are cloned whole
+    let selected: Vec<&ProjectInfo> = projects
+        .iter()
+        .filter(|p| !apply_filters || is_selected(p, options))
+        .collect();
+
+    let clone_one = |p: &&ProjectInfo| clone_project(meta_dir, p, options);
+    let mut results: Vec<CloneResult> = if options.parallel {
+        match options.jobs {
+            Some(jobs) => rayon::ThreadPoolBuilder::new()
+                .num_threads(jobs)
+                .build()
+                .context("Failed to build clone thread pool")?
+                .install(|| selected.par_iter().map(clone_one).collect()),
+            None => selected.par_iter().map(clone_one).collect(),
+        }
+    } else {
+        selected.iter().map(clone_one).collect()
+    };
+
+    if options.recursive {
+        let mut nested = Vec::new();
+        for result in &results {
+            let dir = meta_dir.join(&result.path);
+            let done = matches!(result.status, CloneStatus::Cloned | CloneStatus::Present);
+            if done && config::find_meta_config_in(&dir).is_some() {
+                for child in clone_projects(&dir, options, false)? {
+                    nested.push(CloneResult {
+                        name: format!("{}/{}", result.name, child.name),
+                        path: format!("{}/{}", result.path, child.path),
+                        ..child
+                    });
+                }
+            }
+        }
+        results.extend(nested);
+    }
+    Ok(results)
+}
+
+fn is_selected(project: &ProjectInfo, options: &CloneOptions) -> bool {
+    let by_name = options.include_only.is_empty() || options.include_only.contains(&project.name);
+    let by_tag = options.tags.is_empty() || project.tags.iter().any(|t| options.tags.contains(t));
+    by_name && by_tag
+}
+
+fn clone_project(meta_dir: &Path, project: &ProjectInfo, options: &CloneOptions) -> CloneResult {
+    let mut result = CloneResult {
+        name: project.name.clone(),
+        path: project.path.clone(),
+        url: project.repo.clone(),
+        status: CloneStatus::Cloned,
+        attempts: 0,
+        error: None,
+    };
+    let dest = meta_dir.join(&project.path);
+
+    let Some(url) = &project.repo else {
+        result.status = CloneStatus::NoRepo;
+        return result;
+    };
+    if dest.join(".git").exists() {
+        result.status = CloneStatus::Present;
+        return result;
+    }
+    if options.dry_run {
+        println!(
+            "{} Would clone {} into {}",
+            "[DRY RUN]".cyan(),
+            url,
+            dest.display()
+        );
+        result.status = CloneStatus::Planned;
+        return result;
+    }
+
+    let outcome = ensure_clone_target(&dest)
+        .map_err(|e| (e.to_string(), 0))
+        .and_then(|()| clone_with_retries(url, &dest, options, &project.name));
+    match outcome {
+        Ok(attempts) => {
+            result.attempts = attempts;
+            if !options.quiet {
+                println!("{} {}", "✓".green(), project.name);
+            }
+        }
+        Err((error, attempts)) => {
+            if !options.quiet {
+                println!("{} {}: {}", "✗".red(), project.name, error);
+            }
+            result.status = CloneStatus::Failed;
+            result.attempts = attempts;
+            result.error = Some(error);
+        }
+    }
+    result
+}
+
+/// The destination must be missing or an empty directory, like `git clone` requires.
+fn ensure_clone_target(dest: &Path) -> Result<()> {
+    if dest.exists() {
+        let empty = dest
+            .read_dir()
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(false);
+        anyhow::ensure!(
+            empty,
+            "{} already exists and is not a git repository",
+            dest.display()
+        );
+    } else if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    Ok(())
+}
+
+/// Run `git clone`, retrying with a growing delay. Returns the number of
+/// attempts on success, or the last error and attempt count on failure.
+fn clone_with_retries(
+    url: &str,
+    dest: &Path,
+    options: &CloneOptions,
+    label: &str,
+) -> std::result::Result<usize, (String, usize)> {
+    let attempts = options.retries + 1;
+    let mut last_error = String::new();
+    for attempt in 1..=attempts {
+        match git_clone(url, dest, options.depth) {
+            Ok(()) => return Ok(attempt),
+            Err(e) => last_error = e,
+        }
+        if attempt < attempts {
+            if !options.quiet {
+                eprintln!(
+                    "{} {}: attempt {}/{} failed, retrying",
+                    "↻".yellow(),
+                    label,
+                    attempt,
+                    attempts
+                );
+            }
+            std::thread::sleep(options.retry_delay * attempt as u32);
+        }
+    }
+    Err((last_error, attempts))
+}
+
+fn git_clone(url: &str, dest: &Path, depth: Option<usize>) -> std::result::Result<(), String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("clone").arg("--quiet");
+    if let Some(depth) = depth {
+        cmd.arg("--depth").arg(depth.to_string());
+    }
+    let output = cmd
+        .arg("--")
+        .arg(url)
+        .arg(dest)
+        // Parallel clones must never block on a credential prompt
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(stderr
+        .lines()
+        .map(str::trim)
+        .rfind(|l| !l.is_empty())
+        .unwrap_or("git clone failed")
+        .to_string())
+}
+
+// ── Output ──────────────────────────────────────────────
+
+fn print_summary(target: &Path, results: &[CloneResult]) {
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    let mut parts = Vec::new();
+    for (status, word) in [
+        (CloneStatus::Cloned, "cloned"),
+        (CloneStatus::Planned, "to clone"),
+        (CloneStatus::Present, "already present"),
+        (CloneStatus::NoRepo, "without a repo URL"),
+        (CloneStatus::Failed, "failed"),
+    ] {
+        let n = count(status);
+        if n > 0 {
+            parts.push(format!("{n} {word}"));
+        }
+    }
+    if parts.is_empty() {
+        parts.push("no projects".to_string());
+    }
+
+    let mark = if count(CloneStatus::Failed) > 0 {
+        "✗".red()
+    } else {
+        "✓".green()
+    };
+    println!();
+    println!("{} {} ({})", mark, parts.join(", "), target.display());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::{git, testing::init_repo};
+    use std::fs;
+
+    /// Create a source repo with an initial commit, plus one adding `files`.
+    fn source_repo(dir: &Path, files: &[(&str, &str)]) {
+        init_repo(dir);
+        if files.is_empty() {
+            return;
+        }
+        for (name, content) in files {
+            fs::write(dir.join(name), content).unwrap();
+        }
+        git(dir, &["add", "-A"]).unwrap();
+        git(dir, &["commit", "-q", "-m", "files"]).unwrap();
+    }
+
+    /// Source meta repo with projects `api` and `web` (tagged frontend).
+    fn sources() -> (tempfile::TempDir, String) {
+        let tmp = tempfile::tempdir().unwrap();
+        let src = tmp.path().join("src");
+        source_repo(&src.join("api"), &[]);
+        source_repo(&src.join("web"), &[]);
+        let meta = serde_json::json!({
+            "projects": {
+                "api": src.join("api").display().to_string(),
+                "web": {
+                    "repo": src.join("web").display().to_string(),
+                    "path": "apps/web",
+                    "tags": ["frontend"],
+                },
+                "notes": { "path": "notes" },
+            }
+        });
+        source_repo(&src.join("meta"), &[(".meta", &meta.to_string())]);
+        let url = src.join("meta").display().to_string();
+        (tmp, url)
+    }
+
+    fn options(url: &str, dest: &Path) -> CloneOptions {
+        let mut options = CloneOptions::new(url);
+        options.directory = Some(dest.to_path_buf());
+        options.retry_delay = Duration::ZERO;
+        options.quiet = true;
+        options
+    }
+
+    fn statuses(results: &[CloneResult]) -> Vec<(&str, CloneStatus)> {
+        results
+            .iter()
+            .map(|r| (r.name.as_str(), r.status))
+            .collect()
+    }
+
+    #[test]
+    fn clones_meta_repo_and_projects() {
+        let (tmp, url) = sources();
+        let dest = tmp.path().join("ws");
+
+        let (target, results) = clone_workspace(&options(&url, &dest)).unwrap();
+        assert_eq!(target, dest);
+        assert_eq!(
+            statuses(&results),
+            vec![
+                ("api", CloneStatus::Cloned),
+                ("notes", CloneStatus::NoRepo),
+                ("web", CloneStatus::Cloned),
+            ]
+        );
+        assert!(dest.join(".meta").exists());
+        assert!(dest.join("api/README.md").exists());
+        assert!(dest.join("apps/web/README.md").exists());
+    }
+
+    #[test]
+    fn rerun_resumes_and_filters_apply() {
+        let (tmp, url) = sources();
+        let dest = tmp.path().join("ws");
+
+        let mut opts = options(&url, &dest);
+        opts.include_only = vec!["api".to_string()];
+        let (_, results) = clone_workspace(&opts).unwrap();
+        assert_eq!(statuses(&results), vec![("api", CloneStatus::Cloned)]);
+        assert!(!dest.join("apps/web").exists());
+
+        let mut opts = options(&url, &dest);
+        opts.tags = vec!["frontend".to_string()];
+        let (_, results) = clone_workspace(&opts).unwrap();
+        assert_eq!(statuses(&results), vec![("web", CloneStatus::Cloned)]);
+
+        let (_, results) = clone_workspace(&options(&url, &dest)).unwrap();
+        assert!(results
+            .iter()
+            .all(|r| matches!(r.status, CloneStatus::Present | CloneStatus::NoRepo)));
+    }
+
+    #[test]
+    fn failures_are_retried_and_reported() {
+        let (tmp, url) = sources();
+        let dest = tmp.path().join("ws");
+        fs::remove_dir_all(tmp.path().join("src/api")).unwrap();
+
+        let mut opts = options(&url, &dest);
+        opts.retries = 1;
+        let (_, results) = clone_workspace(&opts).unwrap();
+        let api = results.iter().find(|r| r.name == "api").unwrap();
+        assert_eq!(api.status, CloneStatus::Failed);
+        assert_eq!(api.attempts, 2);
+        assert!(api.error.is_some());
+        assert!(!dest.join("api").exists());
+
+        let err = handle_clone(&opts, true).unwrap_err();
+        assert!(err.to_string().contains("Failed to clone 1 project(s)"));
+    }
+
+    #[test]
+    fn refuses_non_empty_non_repo_target() {
+        let (tmp, url) = sources();
+        let dest = tmp.path().join("ws");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("stray"), "x").unwrap();
+
+        let err = clone_workspace(&options(&url, &dest)).unwrap_err();
+        assert!(err.to_string().contains("is not a git repository"));
+    }
+
+    #[test]
+    fn repo_dir_name_matches_git() {
+        assert_eq!(repo_dir_name("git@github.com:org/meta.git"), "meta");
+        assert_eq!(repo_dir_name("https://github.com/org/meta/"), "meta");
+        assert_eq!(repo_dir_name("/srv/repos/workspace"), "workspace");
+    }
+
+    #[test]
+    fn options_from_git_args() {
+        let args: Vec<String> = ["git@x:meta.git", "ws", "--depth", "1"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = CloneOptions::from_git_args(&args).unwrap();
+        assert_eq!(options.url, "git@x:meta.git");
+        assert_eq!(options.directory, Some(PathBuf::from("ws")));
+        assert_eq!(options.depth, Some(1));
+
+        assert!(CloneOptions::from_git_args(&[]).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! `meta commit`: one commit in every dirty repo, with a shared message.
+//!
+//! ```text
+//! meta commit -m "Bump shared-types to 2.0"
+//! meta --tag backend commit -m "{repo}: drop the v1 client"
+//! ```
+//!
+//! Each selected repo with uncommitted changes stages everything
+//! (`git add -A`) and commits; clean repos are skipped. In the message,
+//! `{repo}` (or `{name}`) expands to the project name and `{branch}` to the
+//! repo's branch; `{{repo}}` is a literal `{repo}`. The commits are recorded
+//! as one run in `meta history`, with each repo's commit hash, so the set
+//! can be looked up later with `meta history show <id>`, and in the
+//! operation journal, so `meta undo` can take them back (`git reset --soft`,
+//! leaving the changes staged).
+
+use anyhow::{Context, Result};
+use colored::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::git::Target;
+use crate::git_utils::{self, git};
+use crate::history::{RepoRecord, RunRecord};
+use crate::journal::{Journal, OpStatus, UndoAction};
+use crate::placeholders;
+use crate::runner::{shell_quote, RepoStatus};
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitState {
+    Committed,
+    /// Nothing to commit
+    Clean,
+    /// `--dry-run`: would have been committed
+    Planned,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoCommit {
+    pub name: String,
+    pub path: PathBuf,
+    pub state: CommitState,
+    /// The message after expansion
+    pub message: String,
+    /// Full hash of the new commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha: Option<String>,
+    /// HEAD before the commit; `None` in a repo without commits
+    #[serde(skip)]
+    pub parent: Option<String>,
+    /// Changed paths per `git status`, for committed and planned repos
+    #[serde(skip_serializing_if = "is_zero")]
+    pub files: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip)]
+    pub duration: Duration,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// `message` with the placeholders expanded for one repo.
+pub fn expand_message(message: &str, name: &str, path: &Path) -> String {
+    placeholders::expand(message, |var| match var {
+        "repo" | "name" => name.to_string(),
+        "branch" => git_utils::current_branch(path).unwrap_or_else(|| "HEAD".to_string()),
+        other => format!("{{{other}}}"),
+    })
+}
+
+/// Commit in every dirty target, in parallel, returning results in target
+/// order.
+pub fn commit_all(targets: &[Target], message: &str, dry_run: bool) -> Vec<RepoCommit> {
+    targets
+        .par_iter()
+        .map(|(name, path)| commit_repo(name, path, message, dry_run))
+        .collect()
+}
+
+/// The run recorded in `meta history`: one entry per repo that had changes.
+pub fn run_record(commits: &[RepoCommit], args: Vec<String>, duration: Duration) -> RunRecord {
+    let repos: Vec<RepoRecord> = commits
+        .iter()
+        .filter(|c| matches!(c.state, CommitState::Committed | CommitState::Failed))
+        .map(|c| RepoRecord {
+            name: c.name.clone(),
+            directory: c.path.display().to_string(),
+            command: format!("git add -A && git commit -m {}", shell_quote(&c.message)),
+            status: if c.state == CommitState::Committed {
+                RepoStatus::Ok
+            } else {
+                RepoStatus::Failed
+            },
+            exit_code: None,
+            duration_ms: u64::try_from(c.duration.as_millis()).unwrap_or(u64::MAX),
+            commit: c.sha.clone(),
+        })
+        .collect();
+    RunRecord {
+        id: 0,
+        started_at: (chrono::Local::now() - duration).to_rfc3339(),
+        cwd: std::env::current_dir()
+            .map(|d| d.display().to_string())
+            .unwrap_or_default(),
+        args,
+        filters: Vec::new(),
+        success: repos.iter().all(|r| r.status == RepoStatus::Ok),
+        duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+        repos,
+    }
+}
+
+/// Record each commit in `journal`, undone by resetting to its parent.
+pub fn journal_commits(commits: &[RepoCommit], journal: &mut Journal) {
+    for c in commits {
+        if let (Some(sha), Some(parent)) = (&c.sha, &c.parent) {
+            journal.step(
+                &c.name,
+                &c.path,
+                &format!("committed {}", short(sha)),
+                vec![UndoAction::ResetSoft {
+                    from: sha.clone(),
+                    to: parent.clone(),
+                }],
+            );
+        }
+    }
+    let failed = commits.iter().any(|c| c.state == CommitState::Failed);
+    journal.finish(if failed {
+        OpStatus::Failed
+    } else {
+        OpStatus::Completed
+    });
+}
+
+/// Entry point for `meta commit`. `filters` are the selection options in
+/// effect, for the history record. Fails if any repo failed to commit.
+pub fn handle_commit(
+    targets: &[Target],
+    message: &str,
+    filters: Vec<String>,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    anyhow::ensure!(!message.trim().is_empty(), "The commit message is empty");
+    let started = Instant::now();
+    let commits = commit_all(targets, message, dry_run);
+
+    let mut recorded = None;
+    let mut journal = None;
+    if !dry_run && commits.iter().any(|c| c.state != CommitState::Clean) {
+        let journal = journal.insert(Journal::begin(
+            "commit",
+            &format!("meta commit -m {}", shell_quote(message)),
+        ));
+        journal_commits(&commits, journal);
+        let mut record = run_record(
+            &commits,
+            std::env::args().skip(1).collect(),
+            started.elapsed(),
+        );
+        record.filters = filters;
+        match crate::history::record(record) {
+            Ok(id) => recorded = Some(id),
+            Err(e) => eprintln!(
+                "{}: failed to record run history: {e}",
+                "warning".yellow().bold()
+            ),
+        }
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "run": recorded,
+                "journal": journal.as_ref().and_then(Journal::undo_id),
+                "repos": commits,
+            }))?
+        );
+    } else {
+        print!("{}", format_commits(&commits));
+        if let Some(id) = recorded {
+            println!("\nRecorded as run {id} (meta history show {id})");
+        }
+        if let Some(hint) = journal.as_ref().and_then(Journal::hint) {
+            println!("{}", hint.dimmed());
+        }
+    }
+
+    let failed = commits
+        .iter()
+        .filter(|c| c.state == CommitState::Failed)
+        .count();
+    if failed > 0 {
+        anyhow::bail!("Commit failed in {failed} of {} repos", commits.len());
+    }
+    Ok(())
+}
+
+/// One line per repo.
+pub fn format_commits(commits: &[RepoCommit]) -> String {
+    if commits.is_empty() {
+        return "No repos selected\n".to_string();
+    }
+    let width = commits.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for c in commits {
+        let files = format!("{} file{}", c.files, if c.files == 1 { "" } else { "s" });
+        let (mark, detail) = match c.state {
+            CommitState::Committed => (
+                "✓".green(),
+                format!(
+                    "{}  {}  ({files})",
+                    short(c.sha.as_deref().unwrap_or_default()).yellow(),
+                    first_line(&c.message)
+                ),
+            ),
+            CommitState::Clean => ("-".dimmed(), "nothing to commit".dimmed().to_string()),
+            CommitState::Planned => (
+                "→".cyan(),
+                format!("would commit {files}: {}", first_line(&c.message)),
+            ),
+            CommitState::Failed => (
+                "✗".red(),
+                c.error
+                    .clone()
+                    .unwrap_or_else(|| "unknown error".to_string()),
+            ),
+        };
+        out.push_str(&format!("{mark} {:<width$}  {detail}\n", c.name));
+    }
+    out
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn commit_repo(name: &str, path: &Path, message: &str, dry_run: bool) -> RepoCommit {
+    let started = Instant::now();
+    let mut commit = RepoCommit {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        state: CommitState::Clean,
+        message: expand_message(message, name, path),
+        sha: None,
+        parent: None,
+        files: git_utils::dirty_file_count(path).unwrap_or(0),
+        error: None,
+        duration: Duration::ZERO,
+    };
+    if commit.files == 0 {
+        return commit;
+    }
+    if dry_run {
+        commit.state = CommitState::Planned;
+        return commit;
+    }
+    commit.parent = git(path, &["rev-parse", "--verify", "-q", "HEAD"]).ok();
+    let result = git(path, &["add", "-A"])
+        .and_then(|_| git(path, &["commit", "--quiet", "-m", &commit.message]))
+        .and_then(|_| git(path, &["rev-parse", "HEAD"]));
+    match result {
+        Ok(sha) => {
+            commit.state = CommitState::Committed;
+            commit.sha = Some(sha);
+        }
+        Err(e) => {
+            commit.state = CommitState::Failed;
+            commit.error = Some(e.to_string());
+        }
+    }
+    commit.duration = started.elapsed();
+    commit
+}
+
+fn short(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::testing::init_repo;
+
+    #[test]
+    fn commits_dirty_repos_and_records_the_set() {
+        let tmp = tempfile::tempdir().unwrap();
+        let targets: Vec<Target> = ["api", "web", "docs"]
+            .iter()
+            .map(|name| (name.to_string(), tmp.path().join(name)))
+            .collect();
+        for (_, path) in &targets {
+            init_repo(path);
+        }
+        std::fs::write(tmp.path().join("api/a.txt"), "a").unwrap();
+        std::fs::write(tmp.path().join("api/b.txt"), "b").unwrap();
+        std::fs::write(tmp.path().join("web/w.txt"), "w").unwrap();
+
+        let planned = commit_all(&targets, "{repo}: update", true);
+        let states: Vec<CommitState> = planned.iter().map(|c| c.state).collect();
+        use CommitState::{Clean, Committed, Planned};
+        assert_eq!(states, [Planned, Planned, Clean]);
+        assert_eq!(git_utils::is_dirty(&tmp.path().join("api")), Some(true));
+
+        let commits = commit_all(&targets, "{repo}: update on {branch}", false);
+        let states: Vec<CommitState> = commits.iter().map(|c| c.state).collect();
+        assert_eq!(states, [Committed, Committed, Clean]);
+        assert_eq!(commits[0].message, "api: update on main");
+        assert_eq!(commits[0].files, 2);
+        assert_eq!(git_utils::is_dirty(&tmp.path().join("api")), Some(false));
+        assert_eq!(
+            git_utils::last_commit(&tmp.path().join("web"))
+                .unwrap()
+                .subject,
+            "web: update on main"
+        );
+
+        let record = run_record(&commits, vec!["commit".to_string()], Duration::ZERO);
+        assert!(record.success);
+        let names: Vec<&str> = record.repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["api", "web"]);
+        assert_eq!(record.repos[0].commit, commits[0].sha);
+        assert_eq!(
+            record.repos[1].command,
+            "git add -A && git commit -m 'web: update on main'"
+        );
+
+        let text = console::strip_ansi_codes(&format_commits(&commits)).to_string();
+        assert!(text.contains("- docs  nothing to commit"), "{text}");
+        assert!(text.contains("api: update on main  (2 files)"), "{text}");
+    }
+}
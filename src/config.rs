@@ -1,6 +1,1511 @@
-//! Re-export config types from meta_core.
+//! Config discovery and parsing for `.meta` files.
 //!
-//! The config module has moved to `meta_core::config`. This re-export
-//! maintains backwards compatibility for internal consumers.
+//! Types come from `meta_core::config`, which understands JSON (`.meta`,
+//! `.meta.json`) and YAML (`.meta.yaml`, `.meta.yml`). This module layers
+//! TOML (`.meta.toml`) on top with the same semantics, so every consumer in
+//! the CLI discovers and parses all three formats uniformly. It also provides
+//! untyped read/write helpers for keys that meta_core does not model, and
+//! [`ConfigDocument`] for editing a config file in place.
+//!
+//! Settings that are a matter of personal preference (ignore lists,
+//! parallelism, shell, env, plugin settings) can also be given in
+//! `~/.meta/config.yaml`, beneath the workspace config: see [`EffectiveConfig`].
 
+// Items defined below (discovery, parsing, `ConfigFormat`) shadow their
+// meta_core counterparts; everything else is re-exported unchanged
 pub use meta_core::config::*;
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Config file names in discovery priority order.
+const CONFIG_CANDIDATES: &[(&str, ConfigFormat)] = &[
+    (".meta", ConfigFormat::Json),
+    (".meta.json", ConfigFormat::Json),
+    (".meta.yaml", ConfigFormat::Yaml),
+    (".meta.yml", ConfigFormat::Yaml),
+    (".meta.toml", ConfigFormat::Toml),
+];
+
+/// Format of a config file, determined by its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Default file name for a config in this format.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => ".meta",
+            ConfigFormat::Yaml => ".meta.yaml",
+            ConfigFormat::Toml => ".meta.toml",
+        }
+    }
+}
+
+impl std::str::FromStr for ConfigFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(ConfigFormat::Json),
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            "toml" => Ok(ConfigFormat::Toml),
+            other => {
+                anyhow::bail!("Unknown config format '{other}' (expected json, yaml, or toml)")
+            }
+        }
+    }
+}
+
+// ── Discovery ───────────────────────────────────────────
+
+/// Config format implied by a file name (`.yaml`/`.yml`, `.toml`, otherwise JSON).
+pub fn format_for_path(path: &Path) -> ConfigFormat {
+    let name = path.to_string_lossy();
+    if name.ends_with(".yaml") || name.ends_with(".yml") {
+        ConfigFormat::Yaml
+    } else if name.ends_with(".toml") {
+        ConfigFormat::Toml
+    } else {
+        ConfigFormat::Json
+    }
+}
+
+/// Check if a directory has a meta config file, without walking up the tree.
+pub fn find_meta_config_in(dir: &Path) -> Option<(PathBuf, ConfigFormat)> {
+    CONFIG_CANDIDATES.iter().find_map(|(name, format)| {
+        let candidate = dir.join(name);
+        candidate.is_file().then_some((candidate, *format))
+    })
+}
+
+/// Find the meta config file, walking up from `start_dir` to the filesystem root.
+///
+/// If `config_name` is provided, only looks for that specific filename.
+pub fn find_meta_config(
+    start_dir: &Path,
+    config_name: Option<&PathBuf>,
+) -> Option<(PathBuf, ConfigFormat)> {
+    let candidates: Vec<(String, ConfigFormat)> = match config_name {
+        Some(name) => vec![(name.to_string_lossy().to_string(), format_for_path(name))],
+        None => CONFIG_CANDIDATES
+            .iter()
+            .map(|(name, format)| (name.to_string(), *format))
+            .collect(),
+    };
+
+    let mut current_dir = start_dir.to_path_buf();
+    loop {
+        for (name, format) in &candidates {
+            let candidate = current_dir.join(name);
+            if candidate.is_file() {
+                return Some((candidate, *format));
+            }
+        }
+        if !current_dir.pop() {
+            return None;
+        }
+    }
+}
+
+// ── Parsing ─────────────────────────────────────────────
+
+/// Parse a meta config file and return normalized project info and ignore list.
+pub fn parse_meta_config(meta_path: &Path) -> Result<(Vec<ProjectInfo>, Vec<String>)> {
+    let config = read_typed_config(meta_path)?;
+
+    let mut projects: Vec<ProjectInfo> = config
+        .projects
+        .into_iter()
+        .map(|(name, entry)| normalize_project(name, entry))
+        .collect();
+
+    // Sort projects alphabetically by name for deterministic order
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // The user's default ignore list comes first, then the workspace's
+    let mut ignore = user_ignore();
+    for name in config.ignore {
+        if !ignore.contains(&name) {
+            ignore.push(name);
+        }
+    }
+    Ok((projects, ignore))
+}
+
+/// Load defaults from the meta config in `start_dir`, layered over the user
+/// config. Returns `MetaDefaults::default()` if neither sets them or they
+/// fail to parse.
+pub fn load_meta_defaults(start_dir: &Path) -> MetaDefaults {
+    effective_value(start_dir)
+        .get("defaults")
+        .and_then(|defaults| serde_json::from_value(defaults.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Load the `defaults.jobs` concurrency limit from the meta config in `start_dir`.
+///
+/// meta_core's `MetaDefaults` doesn't model this key, so it is read from the
+/// untyped document. Returns `None` if unset, invalid, or there is no config.
+pub fn load_meta_jobs(start_dir: &Path) -> Option<usize> {
+    let value = effective_value(start_dir);
+    let jobs = value.get("defaults")?.get("jobs")?.as_u64()?;
+    usize::try_from(jobs).ok().filter(|&n| n > 0)
+}
+
+/// Load the `defaults.timeout` per-repo command limit from the meta config
+/// in `start_dir`. Returns `None` if unset, invalid, or there is no config.
+pub fn load_meta_timeout(start_dir: &Path) -> Option<std::time::Duration> {
+    let value = effective_value(start_dir);
+    parse_timeout(value.get("defaults")?.get("timeout")?)
+}
+
+/// A timeout is a number of seconds or a string like `"90s"` or `"10m"`.
+pub fn parse_timeout(value: &serde_json::Value) -> Option<std::time::Duration> {
+    match value {
+        serde_json::Value::String(spec) => crate::runner::parse_duration(spec),
+        serde_json::Value::Number(n) => std::time::Duration::try_from_secs_f64(n.as_f64()?)
+            .ok()
+            .filter(|d| !d.is_zero()),
+        _ => None,
+    }
+}
+
+/// Load the workspace `shell` from the meta config at `config_path`,
+/// layered over the user config.
+///
+/// Accepts an array (`["bash", "-lc"]`) or a string (`"bash -lc"`). Returns
+/// `None` if unset, invalid, or there is no config.
+pub fn load_meta_shell(config_path: Option<&Path>) -> Option<Vec<String>> {
+    let value = EffectiveConfig::load(&user_config_path(), config_path)
+        .map(|config| config.value)
+        .unwrap_or_default();
+    match value.get("shell")? {
+        serde_json::Value::String(spec) => crate::runner::parse_shell(spec),
+        serde_json::Value::Array(items) => {
+            let parts: Option<Vec<String>> = items
+                .iter()
+                .map(|v| v.as_str().map(str::to_string))
+                .collect();
+            parts.filter(|p| !p.is_empty())
+        }
+        _ => None,
+    }
+}
+
+/// Load the `env` variables from the meta config in `start_dir`, layered
+/// over the user config. Non-string values are skipped.
+pub fn load_meta_env(start_dir: &Path) -> std::collections::HashMap<String, String> {
+    effective_value(start_dir)
+        .get("env")
+        .and_then(Value::as_object)
+        .map(|vars| {
+            vars.iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Read a meta config file as an untyped JSON value.
+///
+/// meta_core's `MetaConfig` silently drops keys it doesn't know about; this
+/// gives callers access to the full document (e.g. for validation or for
+/// CLI-only settings).
+pub fn read_meta_config_value(meta_path: &Path) -> Result<serde_json::Value> {
+    let text = std::fs::read_to_string(meta_path)
+        .with_context(|| format!("Failed to read meta config file: '{}'", meta_path.display()))?;
+    parse_meta_config_str(&text, format_for_path(meta_path))
+        .with_context(|| format!("Failed to parse config file: {}", meta_path.display()))
+}
+
+/// Parse config text in the given format into an untyped JSON value.
+pub fn parse_meta_config_str(text: &str, format: ConfigFormat) -> Result<serde_json::Value> {
+    Ok(match format {
+        ConfigFormat::Json => serde_json::from_str(text)?,
+        ConfigFormat::Yaml => serde_yaml::from_str(text)?,
+        ConfigFormat::Toml => toml::from_str(text)?,
+    })
+}
+
+/// Serialize a config document in the given format.
+pub fn render_meta_config(value: &serde_json::Value, format: ConfigFormat) -> Result<String> {
+    Ok(match format {
+        ConfigFormat::Json => format!("{}\n", serde_json::to_string_pretty(value)?),
+        ConfigFormat::Yaml => serde_yaml::to_string(value)?,
+        ConfigFormat::Toml => toml::to_string_pretty(value)
+            .context("Config cannot be represented as TOML (null values are not supported)")?,
+    })
+}
+
+/// Write a config document back to disk in the format implied by its file name.
+pub fn write_meta_config_value(meta_path: &Path, value: &serde_json::Value) -> Result<()> {
+    let text = render_meta_config(value, format_for_path(meta_path))?;
+    std::fs::write(meta_path, text).with_context(|| {
+        format!(
+            "Failed to write meta config file: '{}'",
+            meta_path.display()
+        )
+    })
+}
+
+fn read_typed_config(meta_path: &Path) -> Result<MetaConfig> {
+    let mut span = crate::telemetry::Span::start("config.load");
+    span.attr("path", meta_path.display());
+    // A running daemon has it parsed already
+    if let Some(mut value) = crate::daemon::config(meta_path) {
+        url_as_repo(&mut value);
+        if let Ok(config) = serde_json::from_value(value) {
+            span.attr("cached", true);
+            return Ok(config);
+        }
+    }
+    let text = std::fs::read_to_string(meta_path)
+        .with_context(|| format!("Failed to read meta config file: '{}'", meta_path.display()))?;
+    let kind = match format_for_path(meta_path) {
+        ConfigFormat::Json => "JSON",
+        ConfigFormat::Yaml => "YAML",
+        ConfigFormat::Toml => "TOML",
+    };
+    let parsed: Result<MetaConfig> = parse_meta_config_str(&text, format_for_path(meta_path))
+        .and_then(|mut value| {
+            url_as_repo(&mut value);
+            serde_json::from_value(value).map_err(Into::into)
+        });
+    parsed.with_context(|| {
+        format!(
+            "Failed to parse {kind} config file: {}",
+            meta_path.display()
+        )
+    })
+}
+
+/// A project's remote may be given as `url` instead of `repo`; meta_core
+/// only knows `repo`, so copy it there. `repo` wins when both are set.
+fn url_as_repo(config: &mut Value) {
+    let Some(Value::Object(projects)) = config.get_mut("projects") else {
+        return;
+    };
+    for entry in projects.values_mut() {
+        if let Value::Object(fields) = entry {
+            if !fields.contains_key("repo") {
+                if let Some(url) = fields.get("url").cloned() {
+                    fields.insert("repo".to_string(), url);
+                }
+            }
+        }
+    }
+}
+
+/// Convert a raw project entry into `ProjectInfo`, matching meta_core's rules.
+fn normalize_project(name: String, entry: ProjectEntry) -> ProjectInfo {
+    let (repo, path, tags, provides, depends_on, meta) = match entry {
+        // Simple string -> git URL
+        ProjectEntry::Simple(url) => (Some(url), name.clone(), vec![], vec![], vec![], false),
+        ProjectEntry::Extended {
+            repo,
+            path,
+            tags,
+            provides,
+            depends_on,
+            meta,
+        } => (
+            repo,
+            path.unwrap_or_else(|| name.clone()),
+            tags,
+            provides,
+            depends_on,
+            meta,
+        ),
+    };
+    ProjectInfo {
+        name,
+        path: path.replace('\\', "/"),
+        repo,
+        tags,
+        provides,
+        depends_on,
+        meta,
+    }
+}
+
+// ── User Config ─────────────────────────────────────────
+
+/// Keys `~/.meta/config.yaml` may set. Everything else (projects, tasks,
+/// worktree settings) only makes sense per workspace and is ignored there.
+pub const USER_CONFIG_KEYS: &[&str] = &[
+    "ignore",
+    "defaults",
+    "shell",
+    "env",
+    "plugins",
+    "plugin_priority",
+    "plugin_overrides",
+];
+
+/// Where the user-level config lives.
+pub fn user_config_path() -> PathBuf {
+    meta_core::meta_dir().join("config.yaml")
+}
+
+/// The user config as an untyped value, keeping only [`USER_CONFIG_KEYS`].
+/// `None` if the file does not exist.
+pub fn read_user_config(path: &Path) -> Result<Option<Value>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let mut value = read_meta_config_value(path)?;
+    let map = value
+        .as_object_mut()
+        .with_context(|| format!("{} must contain a mapping", path.display()))?;
+    map.retain(|key, _| {
+        let known = USER_CONFIG_KEYS.contains(&key.as_str());
+        if !known {
+            log::warn!(
+                "Ignoring '{key}' in {}: it can only be set in .meta",
+                path.display()
+            );
+        }
+        known
+    });
+    Ok(Some(value))
+}
+
+/// Ignore list from the user config, for merging into every workspace's.
+fn user_ignore() -> Vec<String> {
+    read_user_config(&user_config_path())
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_value(value.get("ignore")?.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// The user config and a workspace config merged into one document.
+///
+/// The workspace wins key by key: tables merge, while lists and plain values
+/// replace the user's. `ignore` is the exception, where the user's entries
+/// are kept and the workspace's are added after them.
+#[derive(Debug, Clone, Default)]
+pub struct EffectiveConfig {
+    pub value: Value,
+    /// Leaf key (as listed by [`EffectiveConfig::entries`]) → file it came from
+    origins: std::collections::BTreeMap<String, PathBuf>,
+}
+
+impl EffectiveConfig {
+    /// Layer `workspace` (if any) over the user config at `user` (if present).
+    pub fn load(user: &Path, workspace: Option<&Path>) -> Result<Self> {
+        let mut layers = Vec::new();
+        if let Some(value) = read_user_config(user)? {
+            layers.push((user.to_path_buf(), value));
+        }
+        if let Some(path) = workspace {
+            layers.push((path.to_path_buf(), read_meta_config_value(path)?));
+        }
+
+        let mut config = Self {
+            value: Value::Object(Default::default()),
+            origins: Default::default(),
+        };
+        for (path, layer) in layers {
+            for (key, _) in leaves(&layer) {
+                config.origins.insert(key, path.clone());
+            }
+            merge(&mut config.value, layer, true);
+        }
+        Ok(config)
+    }
+
+    /// Every leaf value as `(key, value, origin)`, in document order. Keys
+    /// are dotted paths; `ignore` entries are listed one by one as
+    /// `ignore[name]` since they can come from different files.
+    pub fn entries(&self) -> Vec<(String, Value, Option<&Path>)> {
+        leaves(&self.value)
+            .into_iter()
+            .map(|(key, value)| {
+                let origin = self.origins.get(&key).map(PathBuf::as_path);
+                (key, value, origin)
+            })
+            .collect()
+    }
+}
+
+/// The config in `start_dir` layered over the user config, as a document.
+/// Empty when there is neither or either fails to load.
+fn effective_value(start_dir: &Path) -> Value {
+    let workspace = find_meta_config_in(start_dir).map(|(path, _)| path);
+    EffectiveConfig::load(&user_config_path(), workspace.as_deref())
+        .map(|config| config.value)
+        .unwrap_or_default()
+}
+
+/// Merge `over` into `base`: tables recursively, anything else replaced.
+/// At the top level `ignore` lists are concatenated instead.
+fn merge(base: &mut Value, over: Value, top_level: bool) {
+    match (base, over) {
+        (Value::Object(base), Value::Object(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(Value::Array(existing)) if top_level && key == "ignore" => {
+                        for item in value.as_array().into_iter().flatten() {
+                            if !existing.contains(item) {
+                                existing.push(item.clone());
+                            }
+                        }
+                    }
+                    Some(existing) => merge(existing, value, false),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+/// Leaf values of a document, keyed like [`EffectiveConfig::entries`].
+fn leaves(value: &Value) -> Vec<(String, Value)> {
+    fn walk(value: &Value, prefix: &str, out: &mut Vec<(String, Value)>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, child) in map {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{prefix}.{key}")
+                    };
+                    walk(child, &path, out);
+                }
+            }
+            Value::Array(items) if prefix == "ignore" => {
+                for item in items {
+                    let name = item
+                        .as_str()
+                        .map_or_else(|| item.to_string(), str::to_string);
+                    out.push((format!("ignore[{name}]"), item.clone()));
+                }
+            }
+            _ => out.push((prefix.to_string(), value.clone())),
+        }
+    }
+    let mut out = Vec::new();
+    walk(value, "", &mut out);
+    out
+}
+
+/// Entry point for `meta config show [--origin]`.
+pub fn handle_show(config_name: Option<&PathBuf>, origin: bool, json: bool) -> Result<()> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let workspace = find_meta_config(&cwd, config_name).map(|(path, _)| path);
+    let user = user_config_path();
+    let config = EffectiveConfig::load(&user, workspace.as_deref())?;
+
+    if json {
+        let output = if origin {
+            let origins: serde_json::Map<String, Value> = config
+                .entries()
+                .into_iter()
+                .map(|(key, _, from)| {
+                    let from = from.map(|p| Value::String(p.display().to_string()));
+                    (key, from.unwrap_or(Value::Null))
+                })
+                .collect();
+            serde_json::json!({ "config": config.value, "origins": origins })
+        } else {
+            config.value.clone()
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let entries = config.entries();
+    if entries.is_empty() {
+        println!(
+            "No configuration found (looked for .meta and {})",
+            user.display()
+        );
+        return Ok(());
+    }
+    let rendered: Vec<(String, String, Option<&Path>)> = entries
+        .into_iter()
+        .map(|(key, value, from)| (key, value.to_string(), from))
+        .collect();
+    let width = rendered
+        .iter()
+        .map(|(key, value, _)| key.len() + value.len() + 3)
+        .max()
+        .unwrap_or(0);
+    for (key, value, from) in rendered {
+        let line = format!("{key} = {value}");
+        match from {
+            Some(from) if origin => {
+                let from = from
+                    .strip_prefix(&cwd)
+                    .unwrap_or(from)
+                    .display()
+                    .to_string();
+                println!("{line:<width$}  {}", from.dimmed());
+            }
+            _ => println!("{line}"),
+        }
+    }
+    Ok(())
+}
+
+/// Split a dotted key like `projects.api.path` into its parts.
+fn key_path(key: &str) -> Result<Vec<&str>> {
+    let parts: Vec<&str> = key.split('.').collect();
+    anyhow::ensure!(
+        parts.iter().all(|p| !p.is_empty()),
+        "Invalid key '{key}': expected dotted names like defaults.jobs"
+    );
+    Ok(parts)
+}
+
+/// Interpret a value given on the command line: JSON when it parses
+/// (`4`, `true`, `["a","b"]`, `{"x":1}`), otherwise a plain string.
+pub fn parse_cli_value(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// Entry point for `meta config get <key> [--user]`.
+///
+/// Reads the effective value (the workspace config over the user config),
+/// or with `--user` only what `~/.meta/config.yaml` says.
+pub fn handle_get(config_name: Option<&PathBuf>, key: &str, user: bool, json: bool) -> Result<()> {
+    let keys = key_path(key)?;
+    let value = if user {
+        read_user_config(&user_config_path())?.unwrap_or_default()
+    } else {
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        let workspace = find_meta_config(&cwd, config_name).map(|(path, _)| path);
+        EffectiveConfig::load(&user_config_path(), workspace.as_deref())?.value
+    };
+    let found = lookup(&value, &keys).with_context(|| format!("'{key}' is not set"))?;
+
+    match found {
+        Value::String(s) if !json => println!("{s}"),
+        other if json => println!("{}", serde_json::to_string_pretty(other)?),
+        other => println!("{other}"),
+    }
+    Ok(())
+}
+
+/// Apply `keys = new` to the config at `path` (an empty document if the
+/// file does not exist) and validate the result, without writing it.
+pub fn prepare_set(path: &Path, keys: &[&str], new: &Value) -> Result<ConfigDocument> {
+    let original = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => "{}".to_string(),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read meta config file: '{}'", path.display()))
+        }
+    };
+    let mut doc = ConfigDocument::parse(path, &original)?;
+    doc.set(keys, new)?;
+    let text = doc.render()?;
+
+    // Only problems the edit introduced block it; existing ones are not ours to fix
+    let format = format_for_path(path);
+    let before = crate::schema::validate_str(&original, &format);
+    let introduced: Vec<String> = crate::schema::validate_str(&text, &format)
+        .into_iter()
+        .filter(|d| d.severity == crate::schema::Severity::Error)
+        .filter(|d| {
+            !before
+                .iter()
+                .any(|b| b.path == d.path && b.message == d.message)
+        })
+        .map(|d| match d.path.as_str() {
+            "" => d.message,
+            path => format!("{path}: {}", d.message),
+        })
+        .collect();
+    anyhow::ensure!(
+        introduced.is_empty(),
+        "Refusing to write {}: {}",
+        path.display(),
+        introduced.join("; ")
+    );
+    Ok(doc)
+}
+
+/// Entry point for `meta config set <key> <value> [--user]`.
+///
+/// Edits `.meta` (or `~/.meta/config.yaml` with `--user`, creating it) in
+/// place, keeping its format. The edited file is validated before it is
+/// written: an edit that introduces errors is refused and the file is left
+/// untouched.
+pub fn handle_set(
+    config_name: Option<&PathBuf>,
+    key: &str,
+    value: &str,
+    user: bool,
+    json: bool,
+) -> Result<()> {
+    let keys = key_path(key)?;
+    let path = if user {
+        anyhow::ensure!(
+            USER_CONFIG_KEYS.contains(&keys[0]),
+            "'{}' can only be set in .meta (~/.meta/config.yaml takes: {})",
+            keys[0],
+            USER_CONFIG_KEYS.join(", ")
+        );
+        user_config_path()
+    } else {
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        find_meta_config(&cwd, config_name)
+            .map(|(path, _)| path)
+            .context("No .meta config found (use --user for ~/.meta/config.yaml)")?
+    };
+
+    let new = parse_cli_value(value);
+    let doc = prepare_set(&path, &keys, &new)?;
+
+    if doc.drops_comments() {
+        eprintln!(
+            "{}: comments in {} are not preserved when editing YAML",
+            "warning".yellow().bold(),
+            path.display()
+        );
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    doc.save()?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "key": key, "value": new, "file": path.display().to_string() })
+        );
+    } else {
+        println!("Set {key} = {new} in {}", path.display());
+    }
+    Ok(())
+}
+
+// ── Tree Walking ────────────────────────────────────────
+
+/// Walk a meta repository tree, discovering nested meta repos in any format.
+///
+/// `max_depth` of `None` means unlimited recursion; `Some(0)` means only the
+/// top-level projects. Cycles are detected via path canonicalization.
+pub fn walk_meta_tree(start_dir: &Path, max_depth: Option<usize>) -> Result<Vec<MetaTreeNode>> {
+    let (config_path, _format) = find_meta_config(start_dir, None)
+        .ok_or_else(|| anyhow::anyhow!("No .meta config found in {}", start_dir.display()))?;
+
+    let (projects, _ignore) = parse_meta_config(&config_path)?;
+    let meta_dir = config_path.parent().unwrap_or(Path::new("."));
+
+    let mut visited = HashSet::new();
+    visited.insert(meta_dir.canonicalize().unwrap_or(meta_dir.to_path_buf()));
+
+    let depth = max_depth.unwrap_or(usize::MAX);
+    Ok(walk_inner(meta_dir, &projects, depth, 0, &mut visited))
+}
+
+fn walk_inner(
+    base_dir: &Path,
+    projects: &[ProjectInfo],
+    max_depth: usize,
+    current_depth: usize,
+    visited: &mut HashSet<PathBuf>,
+) -> Vec<MetaTreeNode> {
+    let mut nodes: Vec<MetaTreeNode> = projects
+        .iter()
+        .map(|project| {
+            let project_dir = base_dir.join(&project.path);
+            let nested_config = find_meta_config_in(&project_dir);
+            let has_meta = project_dir.is_dir() && nested_config.is_some();
+
+            let children = match nested_config {
+                Some((nested_path, _)) if current_depth < max_depth => {
+                    let canonical = project_dir.canonicalize().unwrap_or(project_dir.clone());
+                    match parse_meta_config(&nested_path) {
+                        // insert() returns false on a cycle
+                        Ok((nested, _)) if visited.insert(canonical) => {
+                            walk_inner(&project_dir, &nested, max_depth, current_depth + 1, visited)
+                        }
+                        _ => vec![],
+                    }
+                }
+                _ => vec![],
+            };
+
+            MetaTreeNode {
+                info: project.clone(),
+                is_meta: has_meta,
+                children,
+            }
+        })
+        .collect();
+
+    nodes.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+    nodes
+}
+
+// ── Orphan Detection ────────────────────────────────────
+
+/// Information about a nested meta repo that its parent does not track.
+#[derive(Debug, Clone)]
+pub struct OrphanWarning {
+    /// Path to the current (orphaned) meta directory
+    pub current: PathBuf,
+    /// Path to the parent meta directory
+    pub parent: PathBuf,
+    /// Suggested key to add to parent's config file
+    pub suggested_key: String,
+    /// Format of the parent's config file (for showing appropriate syntax)
+    pub parent_format: ConfigFormat,
+}
+
+/// Find a parent meta config (if any) above the given meta directory.
+pub fn find_parent_meta_config(meta_dir: &Path) -> Option<(PathBuf, ConfigFormat)> {
+    find_meta_config(meta_dir.parent()?, None)
+}
+
+/// Check if a meta directory is tracked by its parent meta config.
+///
+/// Returns `Some(OrphanWarning)` if there's a parent config that doesn't
+/// include this directory in its project tree, `None` if tracked or if there
+/// is no parent.
+pub fn check_orphan_status(meta_dir: &Path) -> Option<OrphanWarning> {
+    let (parent_config, parent_format) = find_parent_meta_config(meta_dir)?;
+    let parent_meta_dir = parent_config.parent()?;
+
+    let tree = walk_meta_tree(parent_meta_dir, None).ok()?;
+    let flat_paths = flatten_meta_tree(&tree);
+
+    let relative = meta_dir.strip_prefix(parent_meta_dir).ok()?;
+    let relative_str = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if flat_paths.iter().any(|p| p == &*relative_str) {
+        return None;
+    }
+
+    let suggested_key = relative
+        .components()
+        .next()?
+        .as_os_str()
+        .to_string_lossy()
+        .to_string();
+    Some(OrphanWarning {
+        current: meta_dir.to_path_buf(),
+        parent: parent_meta_dir.to_path_buf(),
+        suggested_key,
+        parent_format,
+    })
+}
+
+// ── Editing ─────────────────────────────────────────────
+
+/// A config file loaded for in-place editing.
+///
+/// Edits keep key order and indentation. TOML files are edited through
+/// `toml_edit`, so comments and layout survive as well; YAML is
+/// re-serialized, which drops comments (see [`ConfigDocument::drops_comments`]).
+pub struct ConfigDocument {
+    path: PathBuf,
+    repr: DocumentRepr,
+}
+
+enum DocumentRepr {
+    Json {
+        value: Value,
+        indent: String,
+        trailing_newline: bool,
+    },
+    Yaml {
+        value: Value,
+        had_comments: bool,
+    },
+    Toml {
+        doc: toml_edit::DocumentMut,
+    },
+}
+
+impl ConfigDocument {
+    /// Load a config file, picking the editor from its file name.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read meta config file: '{}'", path.display()))?;
+        Self::parse(path, &text)
+    }
+
+    /// Parse `text` as the contents of the config file at `path`, which
+    /// need not exist yet.
+    pub fn parse(path: &Path, text: &str) -> Result<Self> {
+        let parse_context = || format!("Failed to parse config file: {}", path.display());
+        let repr = match format_for_path(path) {
+            ConfigFormat::Json => DocumentRepr::Json {
+                value: serde_json::from_str(&text).with_context(parse_context)?,
+                indent: detect_indent(&text),
+                trailing_newline: text.ends_with('\n'),
+            },
+            ConfigFormat::Yaml => DocumentRepr::Yaml {
+                value: serde_yaml::from_str(&text).with_context(parse_context)?,
+                had_comments: text.lines().any(|l| l.trim_start().starts_with('#')),
+            },
+            ConfigFormat::Toml => DocumentRepr::Toml {
+                doc: text.parse().with_context(parse_context)?,
+            },
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            repr,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// True if saving will lose comments present in the original file.
+    pub fn drops_comments(&self) -> bool {
+        matches!(
+            self.repr,
+            DocumentRepr::Yaml {
+                had_comments: true,
+                ..
+            }
+        )
+    }
+
+    /// Look up the value at a key path, e.g. `["projects", "api"]`.
+    pub fn get(&self, keys: &[&str]) -> Option<Value> {
+        match &self.repr {
+            DocumentRepr::Json { value, .. } | DocumentRepr::Yaml { value, .. } => {
+                lookup(value, keys).cloned()
+            }
+            DocumentRepr::Toml { doc } => {
+                let value: Value = toml::from_str(&doc.to_string()).ok()?;
+                lookup(&value, keys).cloned()
+            }
+        }
+    }
+
+    /// Set the value at a key path, creating intermediate tables as needed.
+    /// An existing key keeps its position in the file.
+    pub fn set(&mut self, keys: &[&str], new: &Value) -> Result<()> {
+        let (last, parents) = keys.split_last().context("Empty key path")?;
+        let not_a_table =
+            || anyhow::anyhow!("Cannot set '{}': parent is not a table", keys.join("."));
+        match &mut self.repr {
+            DocumentRepr::Json { value, .. } | DocumentRepr::Yaml { value, .. } => {
+                let mut node = value;
+                for key in parents {
+                    node = node
+                        .as_object_mut()
+                        .ok_or_else(not_a_table)?
+                        .entry(key.to_string())
+                        .or_insert_with(|| Value::Object(Default::default()));
+                }
+                node.as_object_mut()
+                    .ok_or_else(not_a_table)?
+                    .insert(last.to_string(), new.clone());
+            }
+            DocumentRepr::Toml { doc } => {
+                let mut table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+                let mut inline = false;
+                for key in parents {
+                    let item = table.entry(key).or_insert_with(|| {
+                        let mut t = toml_edit::Table::new();
+                        t.set_implicit(true);
+                        toml_edit::Item::Table(t)
+                    });
+                    inline |= item.is_inline_table();
+                    table = item.as_table_like_mut().ok_or_else(not_a_table)?;
+                }
+                let mut item = json_to_toml_item(new, !inline)?;
+                if let Some(old) = table.get(last) {
+                    carry_toml_decor(old, &mut item);
+                }
+                table.insert(last, item);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the value at a key path, returning it if it existed.
+    pub fn remove(&mut self, keys: &[&str]) -> Option<Value> {
+        let removed = self.get(keys)?;
+        let (last, parents) = keys.split_last()?;
+        match &mut self.repr {
+            DocumentRepr::Json { value, .. } | DocumentRepr::Yaml { value, .. } => {
+                lookup_mut(value, parents)?
+                    .as_object_mut()?
+                    .shift_remove(*last);
+            }
+            DocumentRepr::Toml { doc } => {
+                toml_table_mut(doc, parents)?.remove(last);
+            }
+        }
+        Some(removed)
+    }
+
+    /// Rename a key under `parent`, keeping its value and position.
+    pub fn rename_key(&mut self, parent: &[&str], from: &str, to: &str) -> Result<()> {
+        let path = |key: &str| [parent, &[key]].concat().join(".");
+        anyhow::ensure!(
+            self.get(&[parent, &[from]].concat()).is_some(),
+            "'{}' not found",
+            path(from)
+        );
+        anyhow::ensure!(
+            self.get(&[parent, &[to]].concat()).is_none(),
+            "'{}' already exists",
+            path(to)
+        );
+
+        match &mut self.repr {
+            DocumentRepr::Json { value, .. } | DocumentRepr::Yaml { value, .. } => {
+                let map = lookup_mut(value, parent)
+                    .and_then(Value::as_object_mut)
+                    .context("Parent is not a table")?;
+                *map = std::mem::take(map)
+                    .into_iter()
+                    .map(|(k, v)| {
+                        if k == from {
+                            (to.to_string(), v)
+                        } else {
+                            (k, v)
+                        }
+                    })
+                    .collect();
+            }
+            DocumentRepr::Toml { doc } => {
+                let table = toml_table_mut(doc, parent).context("Parent is not a table")?;
+                let order: Vec<String> = table
+                    .iter()
+                    .map(|(k, _)| if k == from { to } else { k }.to_string())
+                    .collect();
+                let key = table.key(from).cloned().context("Key not found")?;
+                let item = table.remove(from).context("Key not found")?;
+                let renamed = toml_edit::Key::new(to).with_leaf_decor(key.leaf_decor().clone());
+                if let toml_edit::Entry::Vacant(entry) = table.entry_format(&renamed) {
+                    entry.insert(item);
+                }
+
+                // Re-inserting appends; restore the original order of plain values
+                // (sub-tables keep their place through their own position)
+                let mut parent_table = Some(doc.as_table_mut());
+                for key in parent {
+                    parent_table = parent_table
+                        .and_then(|t| t.get_mut(key))
+                        .and_then(toml_edit::Item::as_table_mut);
+                }
+                if let Some(table) = parent_table {
+                    let position = |k: &toml_edit::Key| order.iter().position(|o| o == k.get());
+                    table.sort_values_by(|a, _, b, _| position(a).cmp(&position(b)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the document back to the file it was loaded from.
+    pub fn save(&self) -> Result<()> {
+        let text = self.render()?;
+        std::fs::write(&self.path, text).with_context(|| {
+            format!(
+                "Failed to write meta config file: '{}'",
+                self.path.display()
+            )
+        })
+    }
+
+    /// The file contents [`ConfigDocument::save`] would write.
+    pub fn render(&self) -> Result<String> {
+        Ok(match &self.repr {
+            DocumentRepr::Json {
+                value,
+                indent,
+                trailing_newline,
+            } => {
+                let mut buf = Vec::new();
+                let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+                let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+                serde::Serialize::serialize(value, &mut ser)?;
+                let mut text = String::from_utf8(buf)?;
+                if *trailing_newline {
+                    text.push('\n');
+                }
+                text
+            }
+            DocumentRepr::Yaml { value, .. } => serde_yaml::to_string(value)?,
+            DocumentRepr::Toml { doc } => doc.to_string(),
+        })
+    }
+}
+
+fn lookup<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().try_fold(value, |node, key| node.get(key))
+}
+
+fn lookup_mut<'a>(value: &'a mut Value, keys: &[&str]) -> Option<&'a mut Value> {
+    keys.iter().try_fold(value, |node, key| node.get_mut(*key))
+}
+
+fn toml_table_mut<'a>(
+    doc: &'a mut toml_edit::DocumentMut,
+    keys: &[&str],
+) -> Option<&'a mut dyn toml_edit::TableLike> {
+    let mut table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    for key in keys {
+        table = table.get_mut(key)?.as_table_like_mut()?;
+    }
+    Some(table)
+}
+
+/// Indentation of the first indented line, defaulting to two spaces.
+fn detect_indent(text: &str) -> String {
+    text.lines()
+        .find_map(|line| {
+            let trimmed = line.trim_start();
+            let width = line.len() - trimmed.len();
+            (width > 0 && !trimmed.is_empty()).then(|| line[..width].to_string())
+        })
+        .unwrap_or_else(|| "  ".to_string())
+}
+
+/// Convert a JSON value into a TOML item. Objects become `[sub.tables]` when
+/// the parent is a regular table and inline tables otherwise.
+fn json_to_toml_item(value: &Value, as_table: bool) -> Result<toml_edit::Item> {
+    Ok(match json_to_toml_value(value)? {
+        toml_edit::Value::InlineTable(inline) if as_table => {
+            toml_edit::Item::Table(inline.into_table())
+        }
+        other => toml_edit::Item::Value(other),
+    })
+}
+
+fn json_to_toml_value(value: &Value) -> Result<toml_edit::Value> {
+    Ok(match value {
+        Value::Null => anyhow::bail!("TOML has no null value"),
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => i.into(),
+            (None, Some(f)) => f.into(),
+            _ => anyhow::bail!("Number {n} cannot be represented in TOML"),
+        },
+        Value::String(s) => s.as_str().into(),
+        Value::Array(items) => items
+            .iter()
+            .map(json_to_toml_value)
+            .collect::<Result<toml_edit::Array>>()?
+            .into(),
+        Value::Object(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (k, v) in map {
+                table.insert(k, json_to_toml_value(v)?);
+            }
+            table.into()
+        }
+    })
+}
+
+/// Keep the comments and position of an item that is being replaced.
+fn carry_toml_decor(old: &toml_edit::Item, new: &mut toml_edit::Item) {
+    match (old, new) {
+        (toml_edit::Item::Value(old), toml_edit::Item::Value(new)) => {
+            *new.decor_mut() = old.decor().clone();
+        }
+        (toml_edit::Item::Table(old), toml_edit::Item::Table(new)) => {
+            *new.decor_mut() = old.decor().clone();
+            if let Some(position) = old.position() {
+                new.set_position(position);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML_CONFIG: &str = r#"
+ignore = ["target"]
+
+[defaults]
+parallel = false
+
+[projects]
+api = "git@github.com:org/api.git"
+
+[projects.web]
+repo = "git@github.com:org/web.git"
+path = "apps/web"
+tags = ["frontend"]
+depends_on = ["api"]
+"#;
+
+    #[test]
+    fn parses_toml_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".meta.toml");
+        std::fs::write(&path, TOML_CONFIG).unwrap();
+
+        let (projects, ignore) = parse_meta_config(&path).unwrap();
+        assert_eq!(ignore, vec!["target"]);
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[0].name, "api");
+        assert_eq!(projects[0].path, "api");
+        assert_eq!(projects[1].path, "apps/web");
+        assert_eq!(projects[1].tags, vec!["frontend"]);
+        assert_eq!(projects[1].depends_on, vec!["api"]);
+
+        assert!(!load_meta_defaults(dir.path()).parallel);
+    }
+
+    #[test]
+    fn finds_toml_config_after_json_and_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".meta.toml"), TOML_CONFIG).unwrap();
+        let (path, format) = find_meta_config_in(dir.path()).unwrap();
+        assert_eq!(format, ConfigFormat::Toml);
+        assert!(path.ends_with(".meta.toml"));
+
+        std::fs::write(dir.path().join(".meta.yaml"), "projects: {}\n").unwrap();
+        let nested = dir.path().join("a/b");
+        std::fs::create_dir_all(&nested).unwrap();
+        let (_, format) = find_meta_config(&nested, None).unwrap();
+        assert_eq!(format, ConfigFormat::Yaml);
+    }
+
+    #[test]
+    fn explicit_config_name_uses_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("custom.toml"), TOML_CONFIG).unwrap();
+        let (_, format) =
+            find_meta_config(dir.path(), Some(&PathBuf::from("custom.toml"))).unwrap();
+        assert_eq!(format, ConfigFormat::Toml);
+    }
+
+    #[test]
+    fn project_url_is_an_alias_for_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".meta");
+        std::fs::write(
+            &path,
+            r#"{"projects": {
+                "api": {"url": "git@x:api.git", "tags": ["backend"]},
+                "web": {"repo": "git@x:web.git", "url": "git@x:other.git"}
+            }}"#,
+        )
+        .unwrap();
+        let (projects, _) = parse_meta_config(&path).unwrap();
+        assert_eq!(projects[0].repo.as_deref(), Some("git@x:api.git"));
+        assert_eq!(projects[0].tags, vec!["backend"]);
+        assert_eq!(projects[1].repo.as_deref(), Some("git@x:web.git"));
+    }
+
+    #[test]
+    fn toml_parse_errors_name_the_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".meta.toml");
+        std::fs::write(&path, "projects = [").unwrap();
+        let err = parse_meta_config(&path).unwrap_err();
+        assert!(err.to_string().contains("Failed to parse TOML config file"));
+    }
+
+    #[test]
+    fn round_trips_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join(".meta.toml");
+        std::fs::write(&source, TOML_CONFIG).unwrap();
+        let value = read_meta_config_value(&source).unwrap();
+
+        for name in [".meta", ".meta.yaml", ".meta.toml"] {
+            let path = dir.path().join(format!("out{name}"));
+            write_meta_config_value(&path, &value).unwrap();
+            assert_eq!(read_meta_config_value(&path).unwrap(), value, "{name}");
+            let (projects, _) = parse_meta_config(&path).unwrap();
+            assert_eq!(projects.len(), 2);
+        }
+    }
+
+    #[test]
+    fn walk_discovers_nested_toml_meta_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(".meta"),
+            r#"{"projects": {"child": "git@x:child.git"}}"#,
+        )
+        .unwrap();
+        let child = dir.path().join("child");
+        std::fs::create_dir(&child).unwrap();
+        std::fs::write(
+            child.join(".meta.toml"),
+            "[projects]\ngrandchild = \"git@x:gc.git\"\n",
+        )
+        .unwrap();
+
+        let tree = walk_meta_tree(dir.path(), None).unwrap();
+        assert!(tree[0].is_meta);
+        assert_eq!(flatten_meta_tree(&tree), vec!["child", "child/grandchild"]);
+    }
+
+    #[test]
+    fn orphan_detection_reports_toml_parent_format() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".meta.toml"), "[projects]\n").unwrap();
+        let nested = dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        std::fs::write(nested.join(".meta"), r#"{"projects": {}}"#).unwrap();
+
+        let warning = check_orphan_status(&nested).unwrap();
+        assert_eq!(warning.parent_format, ConfigFormat::Toml);
+        assert_eq!(warning.suggested_key, "nested");
+    }
+
+    #[test]
+    fn workspace_config_layers_over_user_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("config.yaml");
+        std::fs::write(
+            &user,
+            "ignore: [node_modules, target]\ndefaults:\n  jobs: 4\n  parallel: false\nshell: zsh -c\nenv:\n  EDITOR: vim\nprojects:\n  stray: git@x:stray.git\n",
+        )
+        .unwrap();
+        let workspace = dir.path().join(".meta");
+        std::fs::write(
+            &workspace,
+            r#"{"projects": {"api": "git@x:api.git"}, "ignore": ["dist", "target"], "defaults": {"jobs": 2}, "env": {"RUST_LOG": "debug"}}"#,
+        )
+        .unwrap();
+
+        let config = EffectiveConfig::load(&user, Some(&workspace)).unwrap();
+        assert_eq!(
+            config.value["ignore"],
+            serde_json::json!(["node_modules", "target", "dist"])
+        );
+        assert_eq!(config.value["defaults"]["jobs"], 2);
+        assert_eq!(config.value["defaults"]["parallel"], false);
+        assert_eq!(config.value["env"]["EDITOR"], "vim");
+        assert_eq!(config.value["env"]["RUST_LOG"], "debug");
+        // Only preference keys are taken from the user config
+        assert!(config.value["projects"].get("stray").is_none());
+
+        let origin = |key: &str| {
+            config
+                .entries()
+                .into_iter()
+                .find(|(k, _, _)| k == key)
+                .and_then(|(_, _, from)| from.map(Path::to_path_buf))
+        };
+        assert_eq!(origin("defaults.jobs"), Some(workspace.clone()));
+        assert_eq!(origin("defaults.parallel"), Some(user.clone()));
+        assert_eq!(origin("shell"), Some(user.clone()));
+        assert_eq!(origin("ignore[node_modules]"), Some(user.clone()));
+        assert_eq!(origin("ignore[target]"), Some(workspace.clone()));
+        assert_eq!(origin("projects.api"), Some(workspace.clone()));
+
+        // Without a workspace the user config stands alone
+        let config = EffectiveConfig::load(&user, None).unwrap();
+        assert_eq!(config.value["shell"], "zsh -c");
+        let missing = EffectiveConfig::load(&dir.path().join("none.yaml"), None).unwrap();
+        assert!(missing.entries().is_empty());
+    }
+
+    #[test]
+    fn set_validates_before_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".meta");
+        let original = "{\n    \"projects\": {\n        \"api\": \"git@x:api.git\"\n    }\n}\n";
+        std::fs::write(&path, original).unwrap();
+
+        let doc = prepare_set(&path, &["worktrees_dir"], &parse_cli_value(".wt")).unwrap();
+        assert!(doc
+            .render()
+            .unwrap()
+            .contains("\n    \"worktrees_dir\": \".wt\"\n"));
+
+        let err = prepare_set(&path, &["defaults", "jobs"], &parse_cli_value("0")).unwrap_err();
+        assert!(err.to_string().contains("defaults.jobs"), "{err}");
+        let err =
+            prepare_set(&path, &["projects", "api", "path"], &parse_cli_value("x")).unwrap_err();
+        assert!(err.to_string().contains("parent is not a table"), "{err}");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+
+        // A missing user config starts out empty
+        let user = dir.path().join("home/config.yaml");
+        let doc = prepare_set(&user, &["defaults", "parallel"], &parse_cli_value("false")).unwrap();
+        assert_eq!(
+            doc.get(&["defaults", "parallel"]),
+            Some(serde_json::json!(false))
+        );
+        assert_eq!(parse_cli_value("[\"a\"]"), serde_json::json!(["a"]));
+        assert_eq!(parse_cli_value("zsh -c"), serde_json::json!("zsh -c"));
+    }
+
+    #[test]
+    fn json_edits_keep_key_order_and_indent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".meta");
+        std::fs::write(
+            &path,
+            "{\n    \"projects\": {\n        \"zeta\": \"z\",\n        \"alpha\": \"a\"\n    },\n    \"ignore\": []\n}\n",
+        )
+        .unwrap();
+
+        let mut doc = ConfigDocument::load(&path).unwrap();
+        doc.set(
+            &["projects", "mid"],
+            &serde_json::json!({"path": "libs/mid"}),
+        )
+        .unwrap();
+        doc.rename_key(&["projects"], "zeta", "omega").unwrap();
+        assert_eq!(
+            doc.remove(&["projects", "alpha"]),
+            Some(serde_json::json!("a"))
+        );
+        doc.save().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\n    \"projects\": {\n        \"omega\": \"z\",\n        \"mid\": {\n            \"path\": \"libs/mid\"\n        }\n    },\n    \"ignore\": []\n}\n"
+        );
+    }
+
+    #[test]
+    fn toml_edits_keep_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".meta.toml");
+        std::fs::write(
+            &path,
+            "# workspace\n[projects]\n# the api\napi = \"git@x:api.git\" # primary\nweb = \"git@x:web.git\"\n\n# docs site\n[projects.docs]\nrepo = \"git@x:docs.git\"\n",
+        )
+        .unwrap();
+
+        let mut doc = ConfigDocument::load(&path).unwrap();
+        doc.rename_key(&["projects"], "api", "backend").unwrap();
+        doc.set(&["projects", "web"], &serde_json::json!("git@x:web2.git"))
+            .unwrap();
+        doc.set(
+            &["projects", "cli"],
+            &serde_json::json!({"repo": "git@x:cli.git", "tags": ["tools"]}),
+        )
+        .unwrap();
+        doc.save().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with(
+            "# workspace\n[projects]\n# the api\nbackend = \"git@x:api.git\" # primary\nweb = \"git@x:web2.git\"\n\n# docs site\n[projects.docs]\n"
+        ));
+        assert!(text.contains("[projects.cli]\nrepo = \"git@x:cli.git\"\ntags = [\"tools\"]\n"));
+
+        let mut doc = ConfigDocument::load(&path).unwrap();
+        assert!(doc.remove(&["projects", "docs"]).is_some());
+        doc.save().unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("docs"));
+        let (projects, _) = parse_meta_config(&path).unwrap();
+        let names: Vec<_> = projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["backend", "cli", "web"]);
+    }
+
+    #[test]
+    fn yaml_edits_report_dropped_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".meta.yaml");
+        std::fs::write(&path, "# comment\nprojects:\n  b: x\n  a: y\n").unwrap();
+
+        let mut doc = ConfigDocument::load(&path).unwrap();
+        assert!(doc.drops_comments());
+        doc.rename_key(&["projects"], "b", "c").unwrap();
+        doc.save().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "projects:\n  c: x\n  a: y\n"
+        );
+    }
+
+    #[test]
+    fn rename_key_rejects_missing_and_existing_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".meta");
+        std::fs::write(&path, r#"{"projects": {"a": "x", "b": "y"}}"#).unwrap();
+
+        let mut doc = ConfigDocument::load(&path).unwrap();
+        let err = doc.rename_key(&["projects"], "nope", "c").unwrap_err();
+        assert!(err.to_string().contains("'projects.nope' not found"));
+        let err = doc.rename_key(&["projects"], "a", "b").unwrap_err();
+        assert!(err.to_string().contains("'projects.b' already exists"));
+    }
+
+    #[test]
+    fn load_meta_jobs_reads_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(load_meta_jobs(dir.path()), None);
+
+        std::fs::write(
+            dir.path().join(".meta.toml"),
+            "[defaults]\nparallel = true\njobs = 4\n",
+        )
+        .unwrap();
+        assert_eq!(load_meta_jobs(dir.path()), Some(4));
+        assert!(load_meta_defaults(dir.path()).parallel);
+
+        std::fs::write(dir.path().join(".meta.toml"), "[defaults]\njobs = 0\n").unwrap();
+        assert_eq!(load_meta_jobs(dir.path()), None);
+    }
+
+    #[test]
+    fn load_meta_timeout_accepts_seconds_or_duration() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join(".meta");
+        std::fs::write(&meta, r#"{"projects": {}, "defaults": {"timeout": 90}}"#).unwrap();
+        assert_eq!(
+            load_meta_timeout(dir.path()),
+            Some(std::time::Duration::from_secs(90))
+        );
+
+        std::fs::write(&meta, r#"{"projects": {}, "defaults": {"timeout": "10m"}}"#).unwrap();
+        assert_eq!(
+            load_meta_timeout(dir.path()),
+            Some(std::time::Duration::from_secs(600))
+        );
+
+        std::fs::write(
+            &meta,
+            r#"{"projects": {}, "defaults": {"timeout": "later"}}"#,
+        )
+        .unwrap();
+        assert_eq!(load_meta_timeout(dir.path()), None);
+    }
+
+    #[test]
+    fn load_meta_shell_accepts_array_or_string() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join(".meta");
+        std::fs::write(&meta, r#"{"projects": {}, "shell": ["bash", "-lc"]}"#).unwrap();
+        assert_eq!(
+            load_meta_shell(Some(&meta)),
+            Some(vec!["bash".to_string(), "-lc".to_string()])
+        );
+
+        std::fs::write(&meta, r#"{"projects": {}, "shell": "zsh -c"}"#).unwrap();
+        assert_eq!(
+            load_meta_shell(Some(&meta)),
+            Some(vec!["zsh".to_string(), "-c".to_string()])
+        );
+
+        std::fs::write(&meta, r#"{"projects": {}, "shell": []}"#).unwrap();
+        assert_eq!(load_meta_shell(Some(&meta)), None);
+    }
+}
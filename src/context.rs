@@ -3,17 +3,28 @@
 //! Outputs a structured summary of the workspace: repos, branches, dirty status,
 //! tags, dependencies. Designed for both humans and LLM agents (injected via
 //! Claude Code SessionStart hook).
+//!
+//! `--profile` picks how much to include (`minimal`, `standard`, or `full`
+//! with changed files and diff stats), and `--budget` trims the result to a
+//! size in a fixed order: diffs, file lists, dependencies, key commands,
+//! tags, and finally clean repos from the end of the list.
+//!
+//! Results are cached briefly and invalidated when any repo's HEAD, current
+//! branch ref or index changes. `--watch` prints the context as a JSON line
+//! and again whenever it changes, for harnesses that subscribe instead of
+//! polling.
 
 use anyhow::{Context, Result};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
+use crate::config::{self, ProjectInfo};
+use crate::daemon;
 use crate::dependency_graph::DependencyGraph;
-use crate::git_utils;
-use meta_core::config::{self, ProjectInfo};
 
 // ── Cache ───────────────────────────────────────────────
 
@@ -87,6 +98,15 @@ fn is_cache_valid(cached: &CachedContext, current_root: &PathBuf) -> bool {
             }
         }
 
+        // Staging, commits and `git status` index refreshes rewrite .git/index
+        if let Ok(index_meta) = std::fs::metadata(git_dir.join("index")) {
+            if let Ok(index_mtime) = index_meta.modified() {
+                if index_mtime > cached.timestamp {
+                    return false; // Index changed, invalidate
+                }
+            }
+        }
+
         // Check branch ref file if we know the branch
         if let Some(ref branch) = repo.branch {
             let ref_path = git_dir.join("refs").join("heads").join(branch);
@@ -105,43 +125,94 @@ fn is_cache_valid(cached: &CachedContext, current_root: &PathBuf) -> bool {
 
 // ── Public API ──────────────────────────────────────────
 
+/// Options for `meta context`.
+#[derive(Debug, Clone, Default)]
+pub struct ContextOptions {
+    /// Skip git status queries
+    pub no_status: bool,
+    /// Regenerate instead of using the cache
+    pub no_cache: bool,
+    pub tag_filter: Option<String>,
+    pub profile: ContextProfile,
+    pub budget: Option<Budget>,
+    /// Keep running and print a JSON line whenever the context changes
+    pub watch: bool,
+}
+
 /// Entry point for `meta context`.
-pub fn handle_context(json: bool, no_status: bool, no_cache: bool, verbose: bool) -> Result<()> {
+pub fn handle_context(options: &ContextOptions, json: bool, verbose: bool) -> Result<()> {
     let cwd = std::env::current_dir().context("Failed to get current directory")?;
 
-    let (config_path, _format) = config::find_meta_config(&cwd, None)
-        .ok_or_else(|| anyhow::anyhow!("Not a meta workspace (no .meta config found)"))?;
+    let (config_path, _format) =
+        config::find_meta_config(&cwd, None).ok_or(crate::error::MetaError::ConfigNotFound)?;
 
     let meta_dir = config_path
         .parent()
         .ok_or_else(|| anyhow::anyhow!("Invalid config path"))?
         .to_path_buf();
 
+    if options.watch {
+        return watch_context(&config_path, &meta_dir, options, verbose);
+    }
+
+    // The cache holds the whole workspace at the standard profile, so a
+    // tag-filtered or full view bypasses it
+    let use_cache = !options.no_cache
+        && !options.no_status
+        && options.tag_filter.is_none()
+        && options.profile != ContextProfile::Full;
+
     // Try cache if not bypassed
-    if !no_cache && !no_status {
+    if use_cache {
         if let Some(cached) = load_cache() {
             if is_cache_valid(&cached, &meta_dir) {
                 if verbose {
                     eprintln!("Using cached context (age < {CACHE_TTL_SECONDS}s)");
                 }
-                if json {
-                    println!("{}", serde_json::to_string_pretty(&cached.context)?);
-                } else {
-                    print!("{}", format_markdown(&cached.context));
-                }
-                return Ok(());
+                return print_context(cached.context, json, options.profile, options.budget);
             } else if verbose {
                 eprintln!("Cache expired or invalid, regenerating...");
             }
         }
     }
 
+    let ctx = build_context(&config_path, &meta_dir, options, verbose)?;
+
+    // Save to cache (only if status was collected and cache wasn't bypassed)
+    if use_cache {
+        let cached = CachedContext {
+            context: ctx.clone(),
+            timestamp: SystemTime::now(),
+            workspace_root: meta_dir,
+        };
+        save_cache(&cached, verbose);
+    }
+
+    print_context(ctx, json, options.profile, options.budget)
+}
+
+/// Collect the context for the workspace at `meta_dir`, before any profile
+/// or budget is applied.
+fn build_context(
+    config_path: &Path,
+    meta_dir: &Path,
+    options: &ContextOptions,
+    verbose: bool,
+) -> Result<WorkspaceContext> {
     let workspace_name = meta_dir
         .file_name()
         .map(|n| n.to_string_lossy().to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
-    let (projects, _ignore_list) = config::parse_meta_config(&config_path)?;
+    let (mut projects, _ignore_list) = config::parse_meta_config(config_path)?;
+    if let Some(filter) = &options.tag_filter {
+        // The detected language counts as a tag
+        projects.retain(|p| {
+            let mut tags = p.tags.clone();
+            tags.extend(crate::lang::detect(&meta_dir.join(&p.path)).map(str::to_string));
+            crate::filter::matches_tags(&tags, filter)
+        });
+    }
 
     if verbose {
         eprintln!(
@@ -151,24 +222,38 @@ pub fn handle_context(json: bool, no_status: bool, no_cache: bool, verbose: bool
         );
     }
 
-    let repos: Vec<RepoContext> = if no_status {
-        projects.iter().map(RepoContext::from_project).collect()
+    let repos: Vec<RepoContext> = if options.no_status {
+        projects
+            .iter()
+            .map(|p| {
+                let mut ctx = RepoContext::from_project(p);
+                ctx.lang = crate::lang::detect(&meta_dir.join(&p.path)).map(str::to_string);
+                ctx
+            })
+            .collect()
     } else {
         projects
             .par_iter()
             .map(|p| {
                 let mut ctx = RepoContext::from_project(p);
                 let repo_path = meta_dir.join(&p.path);
+                ctx.lang = crate::lang::detect(&repo_path).map(str::to_string);
                 if repo_path.exists() {
-                    ctx.branch = git_utils::current_branch(&repo_path);
-                    ctx.dirty = git_utils::is_dirty(&repo_path);
-                    ctx.modified_count = git_utils::dirty_file_count(&repo_path);
+                    // From the daemon's cache when one is running
+                    let status = daemon::git_status(&repo_path)
+                        .unwrap_or_else(|| daemon::GitStatus::collect(&repo_path));
+                    ctx.branch = status.branch;
+                    ctx.dirty = status.dirty;
+                    ctx.modified_count = status.modified_count;
 
                     // Get ahead/behind counts
-                    if let Some((ahead, behind)) = git_utils::ahead_behind(&repo_path) {
+                    if let Some((ahead, behind)) = status.ahead_behind {
                         ctx.ahead = Some(ahead);
                         ctx.behind = Some(behind);
                     }
+                    if options.profile == ContextProfile::Full && ctx.dirty == Some(true) {
+                        collect_changes(&mut ctx, &repo_path);
+                    }
                 }
                 ctx
             })
@@ -177,7 +262,7 @@ pub fn handle_context(json: bool, no_status: bool, no_cache: bool, verbose: bool
 
     let dependencies = build_dependency_map(&projects);
 
-    let ctx = WorkspaceContext {
+    Ok(WorkspaceContext {
         name: workspace_name,
         description:
             "Multi-repo workspace managed by `meta`. Use `meta` commands for cross-repo operations."
@@ -186,29 +271,209 @@ pub fn handle_context(json: bool, no_status: bool, no_cache: bool, verbose: bool
         repos,
         commands: key_commands(),
         dependencies,
+        dependency_summary: None,
+        omitted_repos: 0,
+        trimmed: Vec::new(),
+    })
+}
+
+/// `meta context --watch`: print the context as one JSON line, then again
+/// each time a repo's files, HEAD, index or refs change and the context
+/// with it. Runs until interrupted.
+fn watch_context(
+    config_path: &Path,
+    meta_dir: &Path,
+    options: &ContextOptions,
+    verbose: bool,
+) -> Result<()> {
+    let (projects, ignore) = config::parse_meta_config(config_path)?;
+    let dirs: Vec<String> = projects
+        .iter()
+        .map(|p| meta_dir.join(&p.path))
+        .filter(|path| path.is_dir())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+
+    let mut last = String::new();
+    let mut emit = || -> Result<()> {
+        let mut ctx = build_context(config_path, meta_dir, options, verbose)?;
+        apply_profile(&mut ctx, options.profile);
+        if let Some(budget) = options.budget {
+            fit_to_budget(&mut ctx, budget, |c| {
+                serde_json::to_string(c).unwrap_or_default()
+            });
+        }
+        let line = serde_json::to_string(&ctx)?;
+        if line != last {
+            println!("{line}");
+            std::io::stdout().flush()?;
+            last = line;
+        }
+        Ok(())
     };
+    emit()?;
 
-    // Save to cache (only if status was collected and cache wasn't bypassed)
-    if !no_cache && !no_status {
-        let cached = CachedContext {
-            context: ctx.clone(),
-            timestamp: SystemTime::now(),
-            workspace_root: meta_dir,
-        };
-        save_cache(&cached, verbose);
+    let watch_options = crate::watch::WatchOptions {
+        ignore,
+        git_state: true,
+        ..Default::default()
+    };
+    crate::watch::watch(&dirs, &watch_options, |_changed| emit())
+}
+
+/// Apply the profile and budget, then print as JSON or markdown.
+fn print_context(
+    mut ctx: WorkspaceContext,
+    json: bool,
+    profile: ContextProfile,
+    budget: Option<Budget>,
+) -> Result<()> {
+    apply_profile(&mut ctx, profile);
+    let render = |ctx: &WorkspaceContext| {
+        if json {
+            serde_json::to_string_pretty(ctx)
+                .map(|s| s + "\n")
+                .unwrap_or_default()
+        } else {
+            format_markdown(ctx)
+        }
+    };
+    if let Some(budget) = budget {
+        fit_to_budget(&mut ctx, budget, render);
     }
+    print!("{}", render(&ctx));
+    Ok(())
+}
 
-    if json {
-        println!("{}", serde_json::to_string_pretty(&ctx)?);
-    } else {
-        print!("{}", format_markdown(&ctx));
+/// Drop what `profile` leaves out. File lists and diffs are only collected
+/// for [`ContextProfile::Full`], so there is nothing to add here.
+pub fn apply_profile(ctx: &mut WorkspaceContext, profile: ContextProfile) {
+    if profile == ContextProfile::Minimal {
+        ctx.commands.clear();
+        for repo in &mut ctx.repos {
+            repo.tags.clear();
+        }
+        summarize_dependencies(ctx);
+    }
+}
+
+/// Trim `ctx` until `render(ctx)` fits `budget`, dropping content in a
+/// fixed order so the same workspace always trims the same way. Each step
+/// that removed something is recorded in `ctx.trimmed`.
+pub fn fit_to_budget(
+    ctx: &mut WorkspaceContext,
+    budget: Budget,
+    render: impl Fn(&WorkspaceContext) -> String,
+) {
+    let steps: [(&str, fn(&mut WorkspaceContext) -> bool); 6] = [
+        ("diffs truncated", truncate_diffs),
+        ("diffs", |ctx| clear_each(ctx, |r| &mut r.diff_stat)),
+        ("file lists", |ctx| {
+            clear_each(ctx, |r| &mut r.changed_files)
+        }),
+        ("dependencies summarized", summarize_dependencies),
+        ("key commands", |ctx| {
+            !std::mem::take(&mut ctx.commands).is_empty()
+        }),
+        ("tags", |ctx| clear_each(ctx, |r| &mut r.tags)),
+    ];
+    for (name, step) in steps {
+        if budget.fits(&render(ctx)) {
+            return;
+        }
+        if step(ctx) {
+            ctx.trimmed.push(name.to_string());
+        }
     }
 
-    Ok(())
+    // Last resort: drop repos from the end, clean ones before dirty ones
+    while !budget.fits(&render(ctx)) && !ctx.repos.is_empty() {
+        let index = ctx
+            .repos
+            .iter()
+            .rposition(|r| r.dirty != Some(true))
+            .unwrap_or(ctx.repos.len() - 1);
+        ctx.repos.remove(index);
+        if ctx.omitted_repos == 0 {
+            ctx.trimmed.push("repos".to_string());
+        }
+        ctx.omitted_repos += 1;
+    }
+}
+
+/// Rough token count for budgeting: about four bytes per token for
+/// English text and code.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
 }
 
 // ── Types ───────────────────────────────────────────────
 
+/// How much `meta context` includes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContextProfile {
+    /// Repos and their status only; dependencies as a one-line summary
+    Minimal,
+    /// Repos, tags, key commands and dependencies
+    #[default]
+    Standard,
+    /// Standard plus changed files and diff stats for dirty repos
+    Full,
+}
+
+impl std::str::FromStr for ContextProfile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "minimal" => Ok(ContextProfile::Minimal),
+            "standard" => Ok(ContextProfile::Standard),
+            "full" => Ok(ContextProfile::Full),
+            other => anyhow::bail!(
+                "Unknown context profile '{other}' (expected minimal, standard or full)"
+            ),
+        }
+    }
+}
+
+/// A size limit for `meta context` output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// Estimated tokens, see [`estimate_tokens`]
+    Tokens(usize),
+    Bytes(usize),
+}
+
+impl Budget {
+    pub fn fits(&self, text: &str) -> bool {
+        match *self {
+            Budget::Tokens(n) => estimate_tokens(text) <= n,
+            Budget::Bytes(n) => text.len() <= n,
+        }
+    }
+}
+
+impl std::str::FromStr for Budget {
+    type Err = anyhow::Error;
+
+    /// `2000` or `2000tokens` for tokens; `8000b`, `8000bytes` or `8kb`
+    /// for bytes.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_lowercase();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let n: usize = number.parse().ok().filter(|&n| n > 0).with_context(|| {
+            format!("Invalid budget '{s}': expected e.g. 2000, 2000tokens or 8kb")
+        })?;
+        match unit.trim() {
+            "" | "t" | "tokens" => Ok(Budget::Tokens(n)),
+            "b" | "bytes" => Ok(Budget::Bytes(n)),
+            "kb" | "k" => Ok(Budget::Bytes(n * 1024)),
+            other => anyhow::bail!("Unknown budget unit '{other}' (expected tokens, bytes or kb)"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkspaceContext {
     pub name: String,
@@ -218,6 +483,15 @@ pub struct WorkspaceContext {
     pub commands: Vec<CommandRef>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dependencies: Option<HashMap<String, Vec<String>>>,
+    /// Replaces `dependencies` when they are summarized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependency_summary: Option<String>,
+    /// Repos left out to fit the budget
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub omitted_repos: usize,
+    /// What `--budget` dropped, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trimmed: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -244,6 +518,15 @@ pub struct RepoContext {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub behind: Option<usize>,
     pub tags: Vec<String>,
+    /// Detected toolchain, see [`crate::lang`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// `git status --short` lines, with `--profile full`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed_files: Vec<String>,
+    /// `git diff --stat HEAD` lines, with `--profile full`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diff_stat: Vec<String>,
 }
 
 impl RepoContext {
@@ -259,6 +542,9 @@ impl RepoContext {
             ahead: None,
             behind: None,
             tags: p.tags.clone(),
+            lang: None,
+            changed_files: Vec::new(),
+            diff_stat: Vec::new(),
         }
     }
 }
@@ -341,7 +627,7 @@ pub fn format_markdown(ctx: &WorkspaceContext) -> String {
         for r in &ctx.repos {
             out.push_str(&format!(
                 "| {} | {} | {} | {} |\n",
-                r.name,
+                repo_label(r),
                 r.branch.as_deref().unwrap_or("-"),
                 format_status(r),
                 r.tags.join(", "),
@@ -354,7 +640,7 @@ pub fn format_markdown(ctx: &WorkspaceContext) -> String {
         for r in &ctx.repos {
             out.push_str(&format!(
                 "| {} | {} | {} |\n",
-                r.name,
+                repo_label(r),
                 r.branch.as_deref().unwrap_or("-"),
                 format_status(r),
             ));
@@ -365,19 +651,44 @@ pub fn format_markdown(ctx: &WorkspaceContext) -> String {
             out.push_str("| Repo | Tags |\n");
             out.push_str("|------|------|\n");
             for r in &ctx.repos {
-                out.push_str(&format!("| {} | {} |\n", r.name, r.tags.join(", ")));
+                out.push_str(&format!("| {} | {} |\n", repo_label(r), r.tags.join(", ")));
             }
         } else {
             for r in &ctx.repos {
-                out.push_str(&format!("- {}\n", r.name));
+                out.push_str(&format!("- {}\n", repo_label(r)));
+            }
+        }
+    }
+
+    if ctx.omitted_repos > 0 {
+        out.push_str(&format!("… and {} more repos\n", ctx.omitted_repos));
+    }
+
+    // Changed files and diff stats (--profile full)
+    let changed: Vec<&RepoContext> = ctx
+        .repos
+        .iter()
+        .filter(|r| !r.changed_files.is_empty() || !r.diff_stat.is_empty())
+        .collect();
+    if !changed.is_empty() {
+        out.push_str("\n## Changes\n");
+        for r in changed {
+            out.push_str(&format!("### {}\n", r.name));
+            for file in &r.changed_files {
+                out.push_str(&format!("- `{file}`\n"));
+            }
+            if !r.diff_stat.is_empty() {
+                out.push_str(&format!("```\n{}\n```\n", r.diff_stat.join("\n")));
             }
         }
     }
 
     // Key commands
-    out.push_str("\n## Key Commands\n");
-    for cmd in &ctx.commands {
-        out.push_str(&format!("- `{}` — {}\n", cmd.command, cmd.description));
+    if !ctx.commands.is_empty() {
+        out.push_str("\n## Key Commands\n");
+        for cmd in &ctx.commands {
+            out.push_str(&format!("- `{}` — {}\n", cmd.command, cmd.description));
+        }
     }
 
     // Dependencies
@@ -389,11 +700,28 @@ pub fn format_markdown(ctx: &WorkspaceContext) -> String {
             let targets = &deps[key];
             out.push_str(&format!("{} → {}\n", key, targets.join(", ")));
         }
+    } else if let Some(ref summary) = ctx.dependency_summary {
+        out.push_str(&format!("\n## Dependencies\n{summary}\n"));
+    }
+
+    if !ctx.trimmed.is_empty() {
+        out.push_str(&format!(
+            "\n_Trimmed to fit the budget: {}._\n",
+            ctx.trimmed.join(", ")
+        ));
     }
 
     out
 }
 
+/// The repo name, with its language when one was detected: `api (rust)`.
+fn repo_label(r: &RepoContext) -> String {
+    match &r.lang {
+        Some(lang) => format!("{} ({lang})", r.name),
+        None => r.name.clone(),
+    }
+}
+
 fn format_status(r: &RepoContext) -> String {
     let base = match (r.dirty, r.modified_count) {
         (Some(false), _) => "clean".to_string(),
@@ -411,6 +739,78 @@ fn format_status(r: &RepoContext) -> String {
     }
 }
 
+// ── Trimming ────────────────────────────────────────────
+
+/// Diff stat lines kept per repo once diffs are truncated.
+const DIFF_STAT_LINES: usize = 5;
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// Fill in `changed_files` and `diff_stat` for the repo at `path`.
+fn collect_changes(ctx: &mut RepoContext, path: &Path) {
+    ctx.changed_files = git_lines(path, &["status", "--short"]);
+    ctx.diff_stat = git_lines(path, &["diff", "--stat", "HEAD"]);
+}
+
+fn git_lines(path: &Path, args: &[&str]) -> Vec<String> {
+    std::process::Command::new("git")
+        .args(args)
+        .current_dir(path)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(|l| l.trim_end().to_string())
+                .filter(|l| !l.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Keep the first few lines of each diff stat, noting how many were cut.
+fn truncate_diffs(ctx: &mut WorkspaceContext) -> bool {
+    let mut changed = false;
+    for repo in &mut ctx.repos {
+        if repo.diff_stat.len() > DIFF_STAT_LINES + 1 {
+            let cut = repo.diff_stat.len() - DIFF_STAT_LINES;
+            repo.diff_stat.truncate(DIFF_STAT_LINES);
+            repo.diff_stat.push(format!(" … {cut} more lines"));
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Clear one list field of every repo; true if any was non-empty.
+fn clear_each(
+    ctx: &mut WorkspaceContext,
+    field: impl Fn(&mut RepoContext) -> &mut Vec<String>,
+) -> bool {
+    let mut changed = false;
+    for repo in &mut ctx.repos {
+        changed |= !std::mem::take(field(repo)).is_empty();
+    }
+    changed
+}
+
+/// Replace the dependency map with a one-line summary.
+fn summarize_dependencies(ctx: &mut WorkspaceContext) -> bool {
+    let Some(deps) = ctx.dependencies.take() else {
+        return false;
+    };
+    let edges: usize = deps.values().map(Vec::len).sum();
+    ctx.dependency_summary = Some(format!(
+        "{edges} dependencies between {} repos; run `meta graph` for details",
+        deps.len()
+    ));
+    true
+}
+
 // ── Tests ───────────────────────────────────────────────
 
 #[cfg(test)]
@@ -428,6 +828,9 @@ mod tests {
             repos,
             commands: key_commands(),
             dependencies: deps,
+            dependency_summary: None,
+            omitted_repos: 0,
+            trimmed: Vec::new(),
         }
     }
 
@@ -448,6 +851,9 @@ mod tests {
             ahead: None,
             behind: None,
             tags: tags.into_iter().map(|s| s.to_string()).collect(),
+            lang: None,
+            changed_files: Vec::new(),
+            diff_stat: Vec::new(),
         }
     }
 
@@ -574,6 +980,16 @@ mod tests {
         assert!(md.contains("| backend |"));
     }
 
+    #[test]
+    fn markdown_shows_detected_language() {
+        let mut api = make_repo("api", Some("main"), Some(false), Some(0), vec![]);
+        api.lang = Some("rust".to_string());
+        let docs = make_repo("docs", Some("main"), Some(false), Some(0), vec![]);
+        let md = format_markdown(&make_ctx(vec![api, docs], None));
+        assert!(md.contains("| api (rust) | main | clean |"), "{md}");
+        assert!(md.contains("| docs | main | clean |"), "{md}");
+    }
+
     #[test]
     fn markdown_no_status_shows_simple_list() {
         let ctx = make_ctx(
@@ -673,6 +1089,92 @@ mod tests {
         assert_eq!(v["dependencies"]["api"][0], "core");
     }
 
+    // ── Profiles and budgets ────────────────────────────
+
+    #[test]
+    fn budget_parses_tokens_and_bytes() {
+        assert_eq!("2000".parse::<Budget>().unwrap(), Budget::Tokens(2000));
+        assert_eq!("500tokens".parse::<Budget>().unwrap(), Budget::Tokens(500));
+        assert_eq!("8000b".parse::<Budget>().unwrap(), Budget::Bytes(8000));
+        assert_eq!("8kb".parse::<Budget>().unwrap(), Budget::Bytes(8192));
+        assert!("0".parse::<Budget>().is_err());
+        assert!("10 pages".parse::<Budget>().is_err());
+        assert!(Budget::Tokens(2).fits("12345678"));
+        assert!(!Budget::Bytes(7).fits("12345678"));
+    }
+
+    #[test]
+    fn minimal_profile_drops_tags_and_commands() {
+        let mut deps = HashMap::new();
+        deps.insert(
+            "api".to_string(),
+            vec!["core".to_string(), "db".to_string()],
+        );
+        let mut ctx = make_ctx(
+            vec![make_repo(
+                "api",
+                Some("main"),
+                Some(false),
+                Some(0),
+                vec!["backend"],
+            )],
+            Some(deps),
+        );
+        apply_profile(&mut ctx, ContextProfile::Minimal);
+        let md = format_markdown(&ctx);
+        assert!(!md.contains("Key Commands"));
+        assert!(!md.contains("backend"));
+        assert!(md.contains("2 dependencies between 1 repos"));
+    }
+
+    #[test]
+    fn budget_trims_in_a_fixed_order() {
+        let mut dirty = make_repo("api", Some("main"), Some(true), Some(2), vec!["backend"]);
+        dirty.changed_files = vec!["M src/lib.rs".to_string(), "?? notes.md".to_string()];
+        dirty.diff_stat = (0..20).map(|i| format!(" src/f{i}.rs | 1 +")).collect();
+        let mut repos = vec![dirty];
+        repos.extend((0..30).map(|i| {
+            make_repo(
+                &format!("svc-{i}"),
+                Some("main"),
+                Some(false),
+                Some(0),
+                vec![],
+            )
+        }));
+        let full = make_ctx(repos, None);
+
+        // Generous budget: nothing to trim
+        let mut ctx = full.clone();
+        fit_to_budget(&mut ctx, Budget::Tokens(100_000), format_markdown);
+        assert!(ctx.trimmed.is_empty());
+
+        // Tight budget: every step runs, then clean repos go before the dirty one
+        let mut ctx = full.clone();
+        fit_to_budget(&mut ctx, Budget::Bytes(600), format_markdown);
+        assert_eq!(
+            ctx.trimmed,
+            [
+                "diffs truncated",
+                "diffs",
+                "file lists",
+                "key commands",
+                "tags",
+                "repos"
+            ]
+        );
+        assert_eq!(ctx.repos[0].name, "api");
+        assert!(ctx.omitted_repos > 0);
+        let md = format_markdown(&ctx);
+        assert!(md.len() <= 600, "{md}");
+        assert!(md.contains(&format!("… and {} more repos", ctx.omitted_repos)));
+
+        // The same input always trims the same way
+        let mut again = full.clone();
+        fit_to_budget(&mut again, Budget::Bytes(600), format_markdown);
+        assert_eq!(format_markdown(&again), md);
+    }
+
     // ── is_cache_valid ──────────────────────────────────
 
     #[test]
@@ -788,6 +1290,29 @@ mod tests {
         assert!(!is_cache_valid(&cached, &workspace_root));
     }
 
+    #[test]
+    fn cache_invalid_when_index_changed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let workspace_root = temp_dir.path().to_path_buf();
+
+        // Staging a file rewrites .git/index without touching HEAD or refs
+        let git_dir = workspace_root.join("test_repo").join(".git");
+        std::fs::create_dir_all(&git_dir).unwrap();
+        let index = git_dir.join("index");
+        std::fs::write(&index, "DIRC").unwrap();
+        let cache_time = index.metadata().unwrap().modified().unwrap() - Duration::from_secs(5);
+
+        let cached = CachedContext {
+            context: make_ctx(
+                vec![make_repo("test_repo", None, Some(false), Some(0), vec![])],
+                None,
+            ),
+            timestamp: cache_time,
+            workspace_root: workspace_root.clone(),
+        };
+        assert!(!is_cache_valid(&cached, &workspace_root));
+    }
+
     #[test]
     fn cache_valid_when_git_files_unchanged() {
         use std::thread;
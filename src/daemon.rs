@@ -0,0 +1,507 @@
+//! Optional background daemon that keeps expensive lookups warm.
+//!
+//! `meta daemon start` runs a server on a unix socket at
+//! `~/.meta/daemon.sock`. It caches:
+//!
+//! - plugin `--meta-plugin-info` output, keyed by the binary's mtime and size
+//! - parsed `.meta` configs, keyed by the file's mtime and size
+//! - git status per repo, keyed by the mtimes of `.git/HEAD` and
+//!   `.git/index` and kept for at most [`GIT_STATUS_TTL`], since edits to the
+//!   working tree touch neither
+//!
+//! It also prunes worktrees on a schedule: every workspace whose config it
+//! has served and that sets `worktree.prune.auto` gets `meta worktree prune`
+//! once per `worktree.prune.interval` (see [`crate::worktree::PruneSchedule`]).
+//!
+//! Every invocation asks the daemon first and falls back to doing the work
+//! itself when no daemon answers, so the daemon is purely an accelerator.
+//! The protocol is one JSON request and one JSON response per line:
+//!
+//! ```text
+//! → {"op":"plugin_info","path":"/usr/local/bin/meta-git"}
+//! ← {"ok":true,"value":{"name":"git","version":"0.2.0","commands":["git status"]}}
+//! ```
+//!
+//! Set `META_NO_DAEMON=1` to bypass a running daemon.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::git_utils;
+
+/// Environment variable that disables daemon lookups when set to `1`.
+pub const NO_DAEMON_ENV: &str = "META_NO_DAEMON";
+
+/// How long a cached git status is served before it is collected again.
+pub const GIT_STATUS_TTL: Duration = Duration::from_secs(2);
+
+/// How often the daemon checks whether a workspace is due a worktree prune.
+pub const PRUNE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a client waits for the daemon before doing the work itself.
+#[cfg(unix)]
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// ── Protocol ────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Request {
+    Ping,
+    Stop,
+    PluginInfo { path: PathBuf },
+    Config { path: PathBuf },
+    GitStatus { path: PathBuf },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub value: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    fn ok(value: serde_json::Value) -> Self {
+        Self {
+            ok: true,
+            value,
+            error: None,
+        }
+    }
+
+    fn error(e: anyhow::Error) -> Self {
+        Self {
+            ok: false,
+            value: serde_json::Value::Null,
+            error: Some(format!("{e:#}")),
+        }
+    }
+}
+
+/// What `ping` reports about a running daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub uptime_secs: u64,
+    pub cached_plugins: usize,
+    pub cached_configs: usize,
+    pub cached_repos: usize,
+}
+
+/// Git state of one repo, as served from the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitStatus {
+    pub branch: Option<String>,
+    pub dirty: Option<bool>,
+    pub modified_count: Option<usize>,
+    pub ahead_behind: Option<(usize, usize)>,
+}
+
+impl GitStatus {
+    pub fn collect(repo: &Path) -> Self {
+        let modified_count = git_utils::dirty_file_count(repo);
+        Self {
+            branch: git_utils::current_branch(repo),
+            dirty: modified_count.map(|n| n > 0),
+            modified_count,
+            ahead_behind: git_utils::ahead_behind(repo),
+        }
+    }
+}
+
+// ── Client ──────────────────────────────────────────────
+
+/// Where the daemon listens.
+pub fn socket_path() -> PathBuf {
+    meta_core::meta_dir().join("daemon.sock")
+}
+
+/// Raw `--meta-plugin-info` JSON for the plugin at `path`, from the daemon.
+/// `Some(Null)` means the daemon ran it and it is not a plugin.
+pub fn plugin_info(path: &Path) -> Option<serde_json::Value> {
+    query(&Request::PluginInfo {
+        path: path.to_path_buf(),
+    })
+}
+
+/// The untyped config document at `path`, from the daemon.
+pub fn config(path: &Path) -> Option<serde_json::Value> {
+    query(&Request::Config {
+        path: path.to_path_buf(),
+    })
+}
+
+/// Git status of the repo at `path`, from the daemon.
+pub fn git_status(path: &Path) -> Option<GitStatus> {
+    query(&Request::GitStatus {
+        path: path.to_path_buf(),
+    })
+    .and_then(|v| serde_json::from_value(v).ok())
+}
+
+/// Status of the running daemon, or `None` when none answers.
+pub fn ping() -> Option<DaemonStatus> {
+    let value = request(&socket_path(), &Request::Ping).ok()?;
+    serde_json::from_value(value).ok()
+}
+
+fn query(req: &Request) -> Option<serde_json::Value> {
+    if std::env::var(NO_DAEMON_ENV).is_ok_and(|v| v == "1") {
+        return None;
+    }
+    let socket = socket_path();
+    if !socket.exists() {
+        return None;
+    }
+    match request(&socket, req) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            log::debug!("daemon request failed: {e:#}");
+            None
+        }
+    }
+}
+
+#[cfg(unix)]
+fn request(socket: &Path, req: &Request) -> Result<serde_json::Value> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    writeln!(stream, "{}", serde_json::to_string(req)?)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let response: Response = serde_json::from_str(&line)?;
+    match response.error {
+        Some(e) if !response.ok => anyhow::bail!(e),
+        _ => Ok(response.value),
+    }
+}
+
+#[cfg(not(unix))]
+fn request(_socket: &Path, _req: &Request) -> Result<serde_json::Value> {
+    anyhow::bail!("The meta daemon is only supported on Unix")
+}
+
+// ── Commands ────────────────────────────────────────────
+
+/// `meta daemon start`: run in the background (or in the foreground with
+/// `foreground`) unless a daemon already answers.
+pub fn handle_start(foreground: bool) -> Result<()> {
+    if let Some(status) = ping() {
+        println!("meta daemon already running (pid {})", status.pid);
+        return Ok(());
+    }
+    if foreground {
+        return serve(&socket_path());
+    }
+
+    let exe = std::env::current_exe()?;
+    let mut cmd = std::process::Command::new(exe);
+    cmd.args(["daemon", "start", "--foreground"])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Own process group, so Ctrl-C in this terminal doesn't reach it
+        cmd.process_group(0);
+    }
+    cmd.spawn()?;
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while std::time::Instant::now() < deadline {
+        if let Some(status) = ping() {
+            println!("meta daemon started (pid {})", status.pid);
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    anyhow::bail!("meta daemon did not start (try `meta daemon start --foreground`)")
+}
+
+/// `meta daemon stop`
+pub fn handle_stop() -> Result<()> {
+    if ping().is_none() {
+        println!("meta daemon is not running");
+        return Ok(());
+    }
+    request(&socket_path(), &Request::Stop)?;
+    println!("meta daemon stopped");
+    Ok(())
+}
+
+/// `meta daemon status`
+pub fn handle_status(json: bool) -> Result<()> {
+    let status = ping();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
+    }
+    match status {
+        Some(s) => println!(
+            "meta daemon running (pid {}, up {}s): {} plugin(s), {} config(s), {} repo(s) cached",
+            s.pid, s.uptime_secs, s.cached_plugins, s.cached_configs, s.cached_repos
+        ),
+        None => println!("meta daemon is not running"),
+    }
+    Ok(())
+}
+
+// ── Server ──────────────────────────────────────────────
+
+/// Identity of a file's contents as far as the caches are concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint {
+    mtime: Option<SystemTime>,
+    size: u64,
+}
+
+impl Fingerprint {
+    fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self {
+            mtime: meta.modified().ok(),
+            size: meta.len(),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Cache {
+    plugins: std::collections::HashMap<PathBuf, (Fingerprint, serde_json::Value)>,
+    configs: std::collections::HashMap<PathBuf, (Fingerprint, serde_json::Value)>,
+    repos: std::collections::HashMap<PathBuf, (GitKey, SystemTime, GitStatus)>,
+}
+
+type GitKey = (Option<Fingerprint>, Option<Fingerprint>);
+
+fn git_key(repo: &Path) -> GitKey {
+    let git = repo.join(".git");
+    (
+        Fingerprint::of(&git.join("HEAD")),
+        Fingerprint::of(&git.join("index")),
+    )
+}
+
+/// Answer one request, consulting and filling the cache.
+fn answer(cache: &std::sync::Mutex<Cache>, started: SystemTime, req: Request) -> Result<Response> {
+    let value = match req {
+        Request::Ping => {
+            let cache = cache.lock().unwrap();
+            serde_json::to_value(DaemonStatus {
+                pid: std::process::id(),
+                uptime_secs: started.elapsed().map_or(0, |d| d.as_secs()),
+                cached_plugins: cache.plugins.len(),
+                cached_configs: cache.configs.len(),
+                cached_repos: cache.repos.len(),
+            })?
+        }
+        Request::Stop => serde_json::Value::Null,
+        Request::PluginInfo { path } => {
+            let fp = Fingerprint::of(&path)
+                .ok_or_else(|| anyhow::anyhow!("{} not found", path.display()))?;
+            if let Some((cached, value)) = cache.lock().unwrap().plugins.get(&path) {
+                if *cached == fp {
+                    return Ok(Response::ok(value.clone()));
+                }
+            }
+            let value = crate::subprocess_plugins::query_plugin_info(&path).unwrap_or_default();
+            cache
+                .lock()
+                .unwrap()
+                .plugins
+                .insert(path, (fp, value.clone()));
+            value
+        }
+        Request::Config { path } => {
+            let fp = Fingerprint::of(&path)
+                .ok_or_else(|| anyhow::anyhow!("{} not found", path.display()))?;
+            if let Some((cached, value)) = cache.lock().unwrap().configs.get(&path) {
+                if *cached == fp {
+                    return Ok(Response::ok(value.clone()));
+                }
+            }
+            let value = crate::config::read_meta_config_value(&path)?;
+            cache
+                .lock()
+                .unwrap()
+                .configs
+                .insert(path, (fp, value.clone()));
+            value
+        }
+        Request::GitStatus { path } => {
+            let key = git_key(&path);
+            if let Some((cached, at, status)) = cache.lock().unwrap().repos.get(&path) {
+                let fresh = at.elapsed().is_ok_and(|age| age < GIT_STATUS_TTL);
+                if *cached == key && fresh {
+                    return Ok(Response::ok(serde_json::to_value(status)?));
+                }
+            }
+            let status = GitStatus::collect(&path);
+            let value = serde_json::to_value(&status)?;
+            cache
+                .lock()
+                .unwrap()
+                .repos
+                .insert(path, (key, SystemTime::now(), status));
+            value
+        }
+    };
+    Ok(Response::ok(value))
+}
+
+/// Listen on `socket` until a `stop` request arrives.
+#[cfg(unix)]
+pub fn serve(socket: &Path) -> Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixListener;
+    use std::sync::{Arc, Mutex};
+
+    if let Some(dir) = socket.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // Nothing answered the ping, so an existing socket is stale
+    let _ = std::fs::remove_file(socket);
+    let listener = UnixListener::bind(socket)?;
+    let cache = Arc::new(Mutex::new(Cache::default()));
+    let started = SystemTime::now();
+
+    let pruning = Arc::clone(&cache);
+    std::thread::spawn(move || loop {
+        std::thread::sleep(PRUNE_CHECK_INTERVAL);
+        prune_workspaces(&pruning);
+    });
+
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        let cache = Arc::clone(&cache);
+        let socket = socket.to_path_buf();
+        std::thread::spawn(move || {
+            let mut line = String::new();
+            if BufReader::new(&stream).read_line(&mut line).is_err() {
+                return;
+            }
+            let (response, stop) = match serde_json::from_str::<Request>(&line) {
+                Ok(req) => {
+                    let stop = req == Request::Stop;
+                    let response = answer(&cache, started, req).unwrap_or_else(Response::error);
+                    (response, stop)
+                }
+                Err(e) => (Response::error(e.into()), false),
+            };
+            if let Ok(text) = serde_json::to_string(&response) {
+                let _ = writeln!(stream, "{text}");
+            }
+            if stop {
+                let _ = std::fs::remove_file(&socket);
+                std::process::exit(0);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Run any scheduled worktree prunes that are due, for the workspaces whose
+/// configs are cached.
+#[cfg(unix)]
+fn prune_workspaces(cache: &std::sync::Mutex<Cache>) {
+    let configs: Vec<PathBuf> = cache.lock().unwrap().configs.keys().cloned().collect();
+    for config in configs {
+        match crate::worktree::prune_if_due(&config) {
+            Ok(Some(run)) if !run.ok => log::warn!(
+                "scheduled worktree prune failed in {}: {}",
+                config.display(),
+                run.error.unwrap_or_default()
+            ),
+            Ok(_) => {}
+            Err(e) => log::debug!("scheduled prune skipped for {}: {e:#}", config.display()),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn serve(_socket: &Path) -> Result<()> {
+    anyhow::bail!("The meta daemon is only supported on Unix")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn requests_round_trip_as_tagged_json() {
+        let req = Request::GitStatus {
+            path: PathBuf::from("/ws/api"),
+        };
+        let text = serde_json::to_string(&req).unwrap();
+        assert_eq!(text, r#"{"op":"git_status","path":"/ws/api"}"#);
+        assert_eq!(serde_json::from_str::<Request>(&text).unwrap(), req);
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"op":"ping"}"#).unwrap(),
+            Request::Ping
+        );
+    }
+
+    #[test]
+    fn configs_are_cached_until_the_file_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(".meta");
+        std::fs::write(&path, r#"{"projects": {"api": "git@x:api.git"}}"#).unwrap();
+        let cache = Mutex::new(Cache::default());
+        let started = SystemTime::now();
+        let config = |cache: &Mutex<Cache>| {
+            answer(cache, started, Request::Config { path: path.clone() })
+                .unwrap()
+                .value
+        };
+
+        assert_eq!(config(&cache)["projects"]["api"], "git@x:api.git");
+        // A stale entry with a matching fingerprint is served from the cache
+        cache.lock().unwrap().configs.get_mut(&path).unwrap().1 = serde_json::json!("cached");
+        assert_eq!(config(&cache), "cached");
+
+        std::fs::write(&path, r#"{"projects": {"web": "git@x:web-app.git"}}"#).unwrap();
+        assert_eq!(config(&cache)["projects"]["web"], "git@x:web-app.git");
+
+        let ping = answer(&cache, started, Request::Ping).unwrap().value;
+        let status: DaemonStatus = serde_json::from_value(ping).unwrap();
+        assert_eq!(status.cached_configs, 1);
+        assert_eq!(status.pid, std::process::id());
+    }
+
+    #[test]
+    fn serves_requests_over_the_socket() {
+        let tmp = tempfile::tempdir().unwrap();
+        let socket = tmp.path().join("daemon.sock");
+        let listener_socket = socket.clone();
+        std::thread::spawn(move || serve(&listener_socket));
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !socket.exists() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let value = request(&socket, &Request::Ping).unwrap();
+        assert_eq!(value["pid"], std::process::id());
+        let err = request(
+            &socket,
+            &Request::Config {
+                path: tmp.path().join("missing"),
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not found"), "{err}");
+    }
+}
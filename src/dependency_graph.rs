@@ -178,6 +178,42 @@ impl DependencyGraph {
         result
     }
 
+    /// Get a project together with all of its transitive dependencies and
+    /// dependents. Empty if the project is not in the graph.
+    pub fn neighborhood(&self, project: &str) -> HashSet<&str> {
+        let Some((name, _)) = self.projects.get_key_value(project) else {
+            return HashSet::new();
+        };
+        let mut result: HashSet<&str> = self.get_all_dependencies(project).into_iter().collect();
+        result.insert(name.as_str());
+
+        let mut queue = VecDeque::from([name.as_str()]);
+        let mut seen = HashSet::new();
+        while let Some(current) = queue.pop_front() {
+            if !seen.insert(current) {
+                continue;
+            }
+            result.insert(current);
+            if let Some(deps) = self.dependents.get(current) {
+                queue.extend(deps.iter().map(|d| d.as_str()));
+            }
+        }
+
+        result
+    }
+
+    /// All resolved edges as `(project, dependency)` pairs, sorted
+    pub fn edges(&self) -> Vec<(&str, &str)> {
+        let mut edges: Vec<(&str, &str)> = self
+            .dependencies
+            .iter()
+            .flat_map(|(from, deps)| deps.iter().map(move |to| (from.as_str(), to.as_str())))
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    }
+
     /// Get all transitive dependents (impact analysis)
     /// Returns all projects that would be affected if the given project changes
     pub fn analyze_impact(&self, project: &str) -> ImpactAnalysis {
@@ -294,6 +330,55 @@ impl DependencyGraph {
         Ok(filtered)
     }
 
+    /// Order a subset of projects for `--order topo` execution.
+    ///
+    /// Names not in the graph (such as the meta repo itself) have no
+    /// dependencies. Dependencies on projects outside `names` are followed
+    /// transitively, so `a -> b -> c` still orders `c` before `a` when `b`
+    /// is filtered out. Otherwise the input order is kept.
+    pub fn schedule(&self, names: &[&str]) -> Result<Schedule> {
+        let waits: Vec<Vec<usize>> = names
+            .iter()
+            .map(|name| {
+                let deps = self.get_all_dependencies(name);
+                names
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, other)| deps.contains(other))
+                    .map(|(j, _)| j)
+                    .collect()
+            })
+            .collect();
+
+        // Kahn's algorithm, always taking the earliest ready name
+        let mut position: Vec<Option<usize>> = vec![None; names.len()];
+        let mut order = Vec::with_capacity(names.len());
+        while order.len() < names.len() {
+            let next = (0..names.len()).find(|&i| {
+                position[i].is_none() && waits[i].iter().all(|&j| position[j].is_some())
+            });
+            let Some(i) = next else {
+                let stuck: Vec<&str> = (0..names.len())
+                    .filter(|&i| position[i].is_none())
+                    .map(|i| names[i])
+                    .collect();
+                anyhow::bail!("Dependency cycle detected between: {}", stuck.join(", "));
+            };
+            position[i] = Some(order.len());
+            order.push(i);
+        }
+
+        let waits_for = order
+            .iter()
+            .map(|&i| {
+                let mut deps: Vec<usize> = waits[i].iter().filter_map(|&j| position[j]).collect();
+                deps.sort_unstable();
+                deps
+            })
+            .collect();
+        Ok(Schedule { order, waits_for })
+    }
+
     /// Get project info
     pub fn get_project(&self, name: &str) -> Option<&ProjectDependencies> {
         self.projects.get(name)
@@ -350,6 +435,15 @@ impl DependencyGraph {
     }
 }
 
+/// Execution plan from [`DependencyGraph::schedule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// Indices into the scheduled names, dependencies first.
+    pub order: Vec<usize>,
+    /// For each position in `order`, the earlier positions it must wait for.
+    pub waits_for: Vec<Vec<usize>>,
+}
+
 /// Result of impact analysis
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImpactAnalysis {
@@ -536,6 +630,75 @@ mod tests {
         assert!(api_pos < web_pos);
     }
 
+    #[test]
+    fn test_schedule() {
+        let graph = DependencyGraph::build(create_test_projects()).unwrap();
+
+        let names = [
+            ".",
+            "web-app",
+            "api-service",
+            "shared-utils",
+            "auth-service",
+        ];
+        let schedule = graph.schedule(&names).unwrap();
+        let ordered: Vec<&str> = schedule.order.iter().map(|&i| names[i]).collect();
+        assert_eq!(
+            ordered,
+            vec![
+                ".",
+                "shared-utils",
+                "auth-service",
+                "api-service",
+                "web-app"
+            ]
+        );
+        assert_eq!(
+            schedule.waits_for,
+            vec![vec![], vec![], vec![1], vec![1, 2], vec![1, 2, 3]]
+        );
+
+        // Filtered-out projects still order their transitive dependencies
+        let names = ["web-app", "shared-utils"];
+        let schedule = graph.schedule(&names).unwrap();
+        assert_eq!(schedule.order, vec![1, 0]);
+        assert_eq!(schedule.waits_for, vec![vec![], vec![0]]);
+    }
+
+    #[test]
+    fn test_schedule_rejects_cycles() {
+        let mut projects = create_test_projects();
+        projects[0].depends_on = vec!["web-app".to_string()];
+        let graph = DependencyGraph::build(projects).unwrap();
+
+        let err = graph.schedule(&["shared-utils", "web-app"]).unwrap_err();
+        assert!(err.to_string().contains("cycle"));
+    }
+
+    #[test]
+    fn test_neighborhood() {
+        let mut projects = create_test_projects();
+        projects.push(ProjectDependencies {
+            name: "docs".to_string(),
+            path: "docs".to_string(),
+            repo: None,
+            tags: vec![],
+            provides: vec![],
+            depends_on: vec![],
+        });
+        let graph = DependencyGraph::build(projects).unwrap();
+
+        let mut around_auth: Vec<&str> = graph.neighborhood("auth-service").into_iter().collect();
+        around_auth.sort_unstable();
+        assert_eq!(
+            around_auth,
+            vec!["api-service", "auth-service", "shared-utils", "web-app"]
+        );
+        assert_eq!(graph.neighborhood("docs").len(), 1);
+        assert!(graph.neighborhood("missing").is_empty());
+        assert_eq!(graph.edges().len(), 4);
+    }
+
     #[test]
     fn test_provided_item_resolution() {
         let projects = create_test_projects();
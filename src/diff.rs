@@ -0,0 +1,408 @@
+//! `meta diff`: what changed between two recorded workspace states.
+//!
+//! A state is a freeze lockfile (`meta freeze`), a release recorded in
+//! `releases.json` (`meta release tag`), or `HEAD` for the repos as they are
+//! now. For every repo the diff lists the commit range, the commits in it
+//! and file stats, which is what changelog tooling needs from `--json`.
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::freeze::Lockfile;
+use crate::git::Target;
+use crate::git_utils::git;
+use crate::release::Releases;
+
+/// Commits listed per repo in the text output before summarizing the rest.
+const MAX_LISTED_COMMITS: usize = 10;
+
+/// The state meaning "the repos as they are now".
+pub const HEAD: &str = "HEAD";
+
+// ── Types ───────────────────────────────────────────────
+
+/// Project name → (path, commit) for one workspace state.
+pub type State = BTreeMap<String, (PathBuf, String)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffStatus {
+    Unchanged,
+    Changed,
+    /// Only in the `to` state
+    Added,
+    /// Only in the `from` state
+    Removed,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub author: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoDiff {
+    pub name: String,
+    pub status: DiffStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// Commits in `from..to`, newest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<CommitInfo>,
+    /// Commits in `from` that `to` no longer has (history was rewound)
+    #[serde(skip_serializing_if = "is_zero")]
+    pub removed_commits: usize,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta diff --from <state> [--to <state>]`.
+pub fn handle_diff(
+    meta_dir: &Path,
+    targets: &[Target],
+    from: &str,
+    to: &str,
+    json: bool,
+) -> Result<()> {
+    let from_state = resolve_state(meta_dir, targets, from)?;
+    let to_state = resolve_state(meta_dir, targets, to)?;
+    let diffs = diff_states(&from_state, &to_state);
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "from": from,
+                "to": to,
+                "repos": diffs,
+            }))?
+        );
+    } else {
+        print!("{}", format_diffs(&diffs));
+    }
+    Ok(())
+}
+
+/// Resolve `spec` to a state: [`HEAD`], a lockfile path, or a release
+/// version from `releases.json`.
+pub fn resolve_state(meta_dir: &Path, targets: &[Target], spec: &str) -> Result<State> {
+    let path_of = |name: &str| {
+        targets
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, p)| p.clone())
+    };
+
+    if spec == HEAD {
+        let mut state = State::new();
+        for (name, path) in targets {
+            // Only repo roots count, otherwise git reports the enclosing (meta) repo
+            if path.join(".git").exists() {
+                if let Ok(commit) = git(path, &["rev-parse", "--verify", "-q", "HEAD"]) {
+                    state.insert(name.clone(), (path.clone(), commit));
+                }
+            }
+        }
+        return Ok(state);
+    }
+
+    let file = Path::new(spec);
+    if file.is_file() {
+        let lock = Lockfile::load(file)?;
+        return Ok(lock
+            .repos
+            .into_iter()
+            .map(|(name, pinned)| {
+                let path = path_of(&name).unwrap_or_else(|| meta_dir.join(&pinned.path));
+                (name, (path, pinned.commit))
+            })
+            .collect());
+    }
+
+    let releases = Releases::load(meta_dir)?;
+    let release = releases.find(spec).with_context(|| {
+        format!("'{spec}' is not HEAD, a lockfile or a release in releases.json")
+    })?;
+    Ok(release
+        .repos
+        .iter()
+        .map(|(name, commit)| {
+            let path = path_of(name).unwrap_or_else(|| meta_dir.join(name));
+            (name.clone(), (path, commit.clone()))
+        })
+        .collect())
+}
+
+/// Compare two states repo by repo.
+pub fn diff_states(from: &State, to: &State) -> Vec<RepoDiff> {
+    let names: BTreeSet<&String> = from.keys().chain(to.keys()).collect();
+    names
+        .into_iter()
+        .map(|name| {
+            let old = from.get(name);
+            let new = to.get(name);
+            let mut diff = RepoDiff {
+                name: name.clone(),
+                status: DiffStatus::Unchanged,
+                from: old.map(|(_, c)| c.clone()),
+                to: new.map(|(_, c)| c.clone()),
+                commits: Vec::new(),
+                removed_commits: 0,
+                files_changed: 0,
+                insertions: 0,
+                deletions: 0,
+                error: None,
+            };
+            match (old, new) {
+                (None, _) => diff.status = DiffStatus::Added,
+                (_, None) => diff.status = DiffStatus::Removed,
+                (Some((_, a)), Some((path, b))) if a != b => match compare(path, a, b, &mut diff) {
+                    Ok(()) => diff.status = DiffStatus::Changed,
+                    Err(e) => {
+                        diff.status = DiffStatus::Error;
+                        diff.error = Some(format!("{e:#}"));
+                    }
+                },
+                _ => {}
+            }
+            diff
+        })
+        .collect()
+}
+
+/// Render diffs as one block per changed repo, then a summary.
+pub fn format_diffs(diffs: &[RepoDiff]) -> String {
+    let mut out = String::new();
+    for d in diffs {
+        let range = |a: &Option<String>, b: &Option<String>| {
+            format!(
+                "{}..{}",
+                a.as_deref().map_or("-", short),
+                b.as_deref().map_or("-", short)
+            )
+        };
+        match d.status {
+            DiffStatus::Unchanged => continue,
+            DiffStatus::Added => {
+                out.push_str(&format!("{} {}\n", d.name.bold(), "(added)".green()));
+            }
+            DiffStatus::Removed => {
+                out.push_str(&format!("{} {}\n", d.name.bold(), "(removed)".red()));
+            }
+            DiffStatus::Error => out.push_str(&format!(
+                "{} {}  {}\n",
+                d.name.bold(),
+                range(&d.from, &d.to).dimmed(),
+                d.error.as_deref().unwrap_or_default().red()
+            )),
+            DiffStatus::Changed => {
+                out.push_str(&format!(
+                    "{} {}  {} commit(s), {} file(s) changed, {} insertion(s), {} deletion(s)\n",
+                    d.name.bold(),
+                    range(&d.from, &d.to).dimmed(),
+                    d.commits.len(),
+                    d.files_changed,
+                    format!("+{}", d.insertions).green(),
+                    format!("-{}", d.deletions).red(),
+                ));
+                if d.removed_commits > 0 {
+                    out.push_str(&format!(
+                        "  {}\n",
+                        format!("{} commit(s) no longer present", d.removed_commits).yellow()
+                    ));
+                }
+                for c in d.commits.iter().take(MAX_LISTED_COMMITS) {
+                    out.push_str(&format!(
+                        "  {} {} ({})\n",
+                        c.hash.dimmed(),
+                        c.subject,
+                        c.author
+                    ));
+                }
+                if d.commits.len() > MAX_LISTED_COMMITS {
+                    out.push_str(&format!(
+                        "  … and {} more\n",
+                        d.commits.len() - MAX_LISTED_COMMITS
+                    ));
+                }
+            }
+        }
+    }
+
+    let count = |status| diffs.iter().filter(|d| d.status == status).count();
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "{} changed, {} unchanged",
+        count(DiffStatus::Changed),
+        count(DiffStatus::Unchanged)
+    ));
+    for (status, label) in [
+        (DiffStatus::Added, "added"),
+        (DiffStatus::Removed, "removed"),
+        (DiffStatus::Error, "failed"),
+    ] {
+        let n = count(status);
+        if n > 0 {
+            out.push_str(&format!(", {n} {label}"));
+        }
+    }
+    out.push('\n');
+    out
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn compare(path: &Path, from: &str, to: &str, diff: &mut RepoDiff) -> Result<()> {
+    anyhow::ensure!(path.join(".git").exists(), "repository not found");
+    for commit in [from, to] {
+        git(path, &["cat-file", "-e", &format!("{commit}^{{commit}}")])
+            .map_err(|_| anyhow::anyhow!("commit {} not found (fetch first?)", short(commit)))?;
+    }
+
+    let log = git(
+        path,
+        &["log", "--format=%h%x09%an%x09%s", &format!("{from}..{to}")],
+    )?;
+    diff.commits = log
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            Some(CommitInfo {
+                hash: parts.next()?.to_string(),
+                author: parts.next()?.to_string(),
+                subject: parts.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect();
+    diff.removed_commits = git(path, &["rev-list", "--count", &format!("{to}..{from}")])?
+        .parse()
+        .unwrap_or(0);
+
+    for line in git(path, &["diff", "--numstat", from, to])?.lines() {
+        let mut cols = line.split('\t');
+        // Binary files show "-" for both counts
+        let added = cols.next().and_then(|n| n.parse::<usize>().ok());
+        let deleted = cols.next().and_then(|n| n.parse::<usize>().ok());
+        diff.files_changed += 1;
+        diff.insertions += added.unwrap_or(0);
+        diff.deletions += deleted.unwrap_or(0);
+    }
+    Ok(())
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(dir: &Path, file: &str, content: &str) -> String {
+        std::fs::write(dir.join(file), content).unwrap();
+        git(dir, &["add", "."]).unwrap();
+        git(dir, &["commit", "-q", "-m", &format!("edit {file}")]).unwrap();
+        git(dir, &["rev-parse", "HEAD"]).unwrap()
+    }
+
+    fn init_repo(dir: &Path) {
+        crate::git_utils::testing::init_repo(dir);
+        commit(dir, "README.md", "one\ntwo\n");
+    }
+
+    #[test]
+    fn diff_against_a_lockfile_lists_commits_and_stats() {
+        let tmp = tempfile::tempdir().unwrap();
+        let api = tmp.path().join("api");
+        let web = tmp.path().join("web");
+        init_repo(&api);
+        init_repo(&web);
+        let targets = vec![("api".to_string(), api.clone()), ("web".to_string(), web)];
+
+        let lock = crate::freeze::freeze(tmp.path(), &targets).unwrap();
+        let file = tmp.path().join("before.json");
+        std::fs::write(&file, serde_json::to_string(&lock).unwrap()).unwrap();
+
+        commit(&api, "README.md", "one\n2\nthree\n");
+        commit(&api, "new.txt", "new\n");
+
+        let from = resolve_state(tmp.path(), &targets, file.to_str().unwrap()).unwrap();
+        let to = resolve_state(tmp.path(), &targets, HEAD).unwrap();
+        let diffs = diff_states(&from, &to);
+
+        assert_eq!(diffs[0].name, "api");
+        assert_eq!(diffs[0].status, DiffStatus::Changed);
+        let subjects: Vec<_> = diffs[0]
+            .commits
+            .iter()
+            .map(|c| c.subject.as_str())
+            .collect();
+        assert_eq!(subjects, ["edit new.txt", "edit README.md"]);
+        assert_eq!(diffs[0].commits[0].author, "Test");
+        assert_eq!(
+            (
+                diffs[0].files_changed,
+                diffs[0].insertions,
+                diffs[0].deletions
+            ),
+            (2, 3, 1)
+        );
+        assert_eq!(diffs[1].status, DiffStatus::Unchanged);
+
+        let text = console::strip_ansi_codes(&format_diffs(&diffs)).to_string();
+        assert!(text.contains("2 commit(s), 2 file(s) changed, +3 insertion(s), -1 deletion(s)"));
+        assert!(text.ends_with("1 changed, 1 unchanged\n"), "{text}");
+    }
+
+    #[test]
+    fn releases_resolve_and_missing_repos_are_reported() {
+        let tmp = tempfile::tempdir().unwrap();
+        let api = tmp.path().join("api");
+        init_repo(&api);
+        let targets = vec![("api".to_string(), api.clone())];
+        crate::release::tag(
+            tmp.path(),
+            "v1",
+            &targets,
+            &crate::release::TagOptions::default(),
+        )
+        .unwrap();
+        let old = commit(&api, "a.txt", "a\n");
+        git(&api, &["reset", "-q", "--hard", "HEAD~1"]).unwrap();
+
+        let release = resolve_state(tmp.path(), &targets, "v1").unwrap();
+        let mut head = resolve_state(tmp.path(), &targets, HEAD).unwrap();
+        assert_eq!(release, head);
+        assert!(resolve_state(tmp.path(), &targets, "v9").is_err());
+
+        // History rewound: `from` has a commit `to` lacks
+        let mut from = head.clone();
+        from.get_mut("api").unwrap().1 = old;
+        from.insert("gone".to_string(), (tmp.path().join("gone"), "abc".into()));
+        head.insert("new".to_string(), (tmp.path().join("new"), "def".into()));
+        let diffs = diff_states(&from, &head);
+        let by_name: BTreeMap<_, _> = diffs.iter().map(|d| (d.name.as_str(), d)).collect();
+        assert_eq!(by_name["api"].removed_commits, 1);
+        assert!(by_name["api"].commits.is_empty());
+        assert_eq!(by_name["gone"].status, DiffStatus::Removed);
+        assert_eq!(by_name["new"].status, DiffStatus::Added);
+    }
+}
@@ -0,0 +1,494 @@
+//! `meta doctor`: check a workspace end to end.
+//!
+//! Each check yields one line with a status and, when something is wrong,
+//! the command that fixes it. Checks cover the config file, the tools meta
+//! shells out to, every project (cloned, a git repo, origin matching the
+//! config and reachable), plugin binaries, and worktree bookkeeping that
+//! may have drifted from what is on disk.
+
+use anyhow::Result;
+use colored::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::{self, ProjectInfo};
+use crate::error::MetaError;
+use crate::schema::{self, Severity};
+use crate::subprocess_plugins::{CheckStatus, DoctorReport};
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// The outcome of one check.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    /// What was checked: `config`, `tools`, `projects`, `plugins` or `worktrees`
+    pub category: &'static str,
+    /// The file, tool, project or plugin the check is about
+    pub subject: String,
+    pub status: Status,
+    pub message: String,
+    /// How to resolve a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn new(category: &'static str, subject: impl Into<String>, status: Status) -> Self {
+        Self {
+            category,
+            subject: subject.into(),
+            status,
+            message: String::new(),
+            fix: None,
+        }
+    }
+
+    fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DoctorOptions {
+    /// Skip the network checks (`git ls-remote` against every remote)
+    pub offline: bool,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta doctor`. Exits non-zero when any check fails.
+pub fn handle_doctor(
+    config_path: Option<&Path>,
+    plugins: Option<&DoctorReport>,
+    options: &DoctorOptions,
+    json: bool,
+) -> Result<()> {
+    let mut checks = Vec::new();
+    match config_path {
+        Some(path) => checks.extend(check_workspace(path, options)),
+        None => checks.push(
+            Check::new("config", ".meta", Status::Fail)
+                .message("no .meta config found")
+                .fix("meta init"),
+        ),
+    }
+    checks.extend(check_tools());
+    if let Some(report) = plugins {
+        checks.extend(check_plugins(report));
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&checks)?);
+    } else {
+        print!("{}", format_checks(&checks));
+    }
+    if failed > 0 {
+        return Err(MetaError::ChecksFailed(format!("{failed} doctor check(s) failed")).into());
+    }
+    Ok(())
+}
+
+/// Config, project and worktree checks for the workspace at `config_path`.
+pub fn check_workspace(config_path: &Path, options: &DoctorOptions) -> Vec<Check> {
+    let name = config_path
+        .file_name()
+        .map_or_else(|| ".meta".to_string(), |n| n.to_string_lossy().to_string());
+    let mut checks = vec![check_config(config_path, &name)];
+    let Ok((projects, _ignore)) = config::parse_meta_config(config_path) else {
+        return checks;
+    };
+    let meta_dir = config_path.parent().unwrap_or(Path::new("."));
+
+    let project_checks: Vec<Vec<Check>> = projects
+        .par_iter()
+        .map(|p| check_project(meta_dir, p, options))
+        .collect();
+    checks.extend(project_checks.into_iter().flatten());
+    checks.extend(check_worktrees(meta_dir, &projects));
+    checks
+}
+
+/// Render checks grouped by category, then a one-line verdict.
+pub fn format_checks(checks: &[Check]) -> String {
+    let mut out = String::new();
+    let mut category = "";
+    for check in checks {
+        if check.category != category {
+            if !category.is_empty() {
+                out.push('\n');
+            }
+            category = check.category;
+            out.push_str(&format!("{}\n", category.bold()));
+        }
+        let mark = match check.status {
+            Status::Ok => "✓".green(),
+            Status::Warn => "!".yellow(),
+            Status::Fail => "✗".red(),
+        };
+        out.push_str(&format!("  {mark} {}", check.subject));
+        if !check.message.is_empty() {
+            out.push_str(&format!("  {}", check.message.dimmed()));
+        }
+        out.push('\n');
+        if let Some(fix) = &check.fix {
+            out.push_str(&format!("      fix: {}\n", fix.cyan()));
+        }
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let (failed, warned) = (count(Status::Fail), count(Status::Warn));
+    out.push('\n');
+    out.push_str(&match (failed, warned) {
+        (0, 0) => format!("{} Everything looks good\n", "✓".green()),
+        (0, w) => format!("{} {w} warning(s)\n", "!".yellow()),
+        (f, w) => format!("{} {f} problem(s), {w} warning(s)\n", "✗".red()),
+    });
+    out
+}
+
+// ── Checks ──────────────────────────────────────────────
+
+fn check_config(path: &Path, name: &str) -> Check {
+    let diagnostics = match schema::validate_file(path) {
+        Ok(diagnostics) => diagnostics,
+        Err(e) => return Check::new("config", name, Status::Fail).message(format!("{e:#}")),
+    };
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    let status = match (errors, warnings) {
+        (0, 0) => return Check::new("config", name, Status::Ok).message("parses and validates"),
+        (0, _) => Status::Warn,
+        _ => Status::Fail,
+    };
+    Check::new("config", name, status)
+        .message(format!("{errors} error(s), {warnings} warning(s)"))
+        .fix("meta config validate")
+}
+
+fn check_project(meta_dir: &Path, project: &ProjectInfo, options: &DoctorOptions) -> Vec<Check> {
+    let check = |status| Check::new("projects", &project.name, status);
+    let dir = meta_dir.join(&project.path);
+    if !dir.is_dir() {
+        let fix = match &project.repo {
+            Some(_) => "meta git update".to_string(),
+            None => format!("create {} or remove the project from .meta", project.path),
+        };
+        return vec![check(Status::Fail)
+            .message(format!("{} does not exist", project.path))
+            .fix(fix)];
+    }
+    if !dir.join(".git").exists() {
+        return vec![check(Status::Fail)
+            .message(format!("{} is not a git repository", project.path))
+            .fix(format!("git -C {} init, or re-clone it", project.path))];
+    }
+    let Some(url) = &project.repo else {
+        return vec![check(Status::Ok).message("local repository (no remote configured)")];
+    };
+
+    let mut checks = Vec::new();
+    match git(&dir, &["remote", "get-url", "origin"]) {
+        Some(origin) if origin != *url => checks.push(
+            check(Status::Warn)
+                .message(format!("origin is {origin}, .meta says {url}"))
+                .fix(format!(
+                    "git -C {} remote set-url origin {url}",
+                    project.path
+                )),
+        ),
+        Some(_) => {}
+        None => checks.push(
+            check(Status::Warn)
+                .message("no origin remote")
+                .fix(format!("git -C {} remote add origin {url}", project.path)),
+        ),
+    }
+    if !options.offline && !remote_reachable(&dir, url) {
+        checks.push(
+            check(Status::Fail)
+                .message(format!("{url} is not reachable"))
+                .fix("check the URL, your network and your git credentials"),
+        );
+    }
+    if checks.is_empty() {
+        checks.push(check(Status::Ok).message(url.clone()));
+    }
+    checks
+}
+
+fn check_tools() -> Vec<Check> {
+    let version = |program: &str| -> Option<String> {
+        let output = Command::new(program)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        output
+            .status
+            .success()
+            .then(|| text.lines().next().unwrap_or_default().trim().to_string())
+    };
+
+    let git = match version("git") {
+        Some(v) => Check::new("tools", "git", Status::Ok).message(v),
+        None => Check::new("tools", "git", Status::Fail)
+            .message("not found on PATH")
+            .fix("install git: https://git-scm.com/downloads"),
+    };
+    // Only pull requests and releases need gh
+    let gh = match version("gh") {
+        Some(v) => Check::new("tools", "gh", Status::Ok).message(v),
+        None => Check::new("tools", "gh", Status::Warn)
+            .message("not found on PATH (needed for pull requests and releases)")
+            .fix("install the GitHub CLI: https://cli.github.com"),
+    };
+    vec![git, gh]
+}
+
+fn check_plugins(report: &DoctorReport) -> Vec<Check> {
+    let mut checks: Vec<Check> = report
+        .plugins
+        .iter()
+        .filter(|p| !matches!(p.status, CheckStatus::Ignored | CheckStatus::Disabled))
+        .filter_map(|p| {
+            let name = p
+                .name
+                .clone()
+                .unwrap_or_else(|| p.path.display().to_string());
+            match p.status {
+                CheckStatus::Broken => Some(
+                    Check::new("plugins", name, Status::Fail)
+                        .message(p.problems.join("; "))
+                        .fix(format!("reinstall or remove {}", p.path.display())),
+                ),
+                _ if !p.problems.is_empty() => {
+                    Some(Check::new("plugins", name, Status::Warn).message(p.problems.join("; ")))
+                }
+                CheckStatus::Ok => Some(
+                    Check::new("plugins", name, Status::Ok)
+                        .message(p.version.clone().unwrap_or_default()),
+                ),
+                _ => None,
+            }
+        })
+        .collect();
+    checks.extend(report.conflicts.iter().filter(|c| !c.overridden).map(|c| {
+        Check::new("plugins", &c.command, Status::Warn)
+            .message(format!(
+                "registered by {}; {} handles it",
+                c.plugins.join(", "),
+                c.resolved_to
+            ))
+            .fix(format!(
+                "pin it with \"plugin_overrides\": {{\"{}\": \"{}\"}} in .meta",
+                c.command, c.resolved_to
+            ))
+    }));
+    checks
+}
+
+/// Git worktrees whose directories are gone, and locks recorded for
+/// worktree sets that no longer exist.
+fn check_worktrees(meta_dir: &Path, projects: &[ProjectInfo]) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for project in projects {
+        let dir = meta_dir.join(&project.path);
+        if !dir.join(".git").exists() {
+            continue;
+        }
+        let prunable = prunable_worktrees(&dir);
+        if !prunable.is_empty() {
+            checks.push(
+                Check::new("worktrees", &project.name, Status::Warn)
+                    .message(format!(
+                        "{} worktree(s) point at missing directories",
+                        prunable.len()
+                    ))
+                    .fix(format!("git -C {} worktree prune", project.path)),
+            );
+        }
+    }
+
+    let root = crate::worktree::worktrees_root(meta_dir);
+    for task_dir in crate::worktree::locked_sets().keys().map(PathBuf::from) {
+        if task_dir.starts_with(&root) && !task_dir.is_dir() {
+            let name = task_dir
+                .file_name()
+                .map_or_else(String::new, |n| n.to_string_lossy().to_string());
+            checks.push(
+                Check::new("worktrees", &name, Status::Warn)
+                    .message("locked, but the worktree set no longer exists")
+                    .fix(format!("meta worktree unlock {name}")),
+            );
+        }
+    }
+
+    if checks.is_empty() {
+        checks.push(
+            Check::new("worktrees", "store", Status::Ok).message("matches the directories on disk"),
+        );
+    }
+    checks
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `git ls-remote` without prompting for credentials.
+fn remote_reachable(dir: &Path, url: &str) -> bool {
+    Command::new("git")
+        .args(["ls-remote", "--quiet", url, "HEAD"])
+        .current_dir(dir)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Paths of the repo's worktrees that git reports as prunable.
+fn prunable_worktrees(dir: &Path) -> Vec<String> {
+    let Some(list) = git(dir, &["worktree", "list", "--porcelain"]) else {
+        return Vec::new();
+    };
+    let mut prunable = Vec::new();
+    let mut current = None;
+    for line in list.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            current = Some(path.to_string());
+        } else if line.starts_with("prunable") {
+            prunable.extend(current.take());
+        }
+    }
+    prunable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_init(dir: &Path) {
+        Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(dir)
+            .status()
+            .unwrap();
+    }
+
+    #[test]
+    fn missing_and_plain_directories_fail_with_fixes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = tmp.path().join(".meta");
+        std::fs::write(
+            &config,
+            r#"{"projects": {"api": "git@example.com:org/api.git", "docs": {"path": "docs"}, "local": {"path": "local"}}}"#,
+        )
+        .unwrap();
+        std::fs::create_dir(tmp.path().join("docs")).unwrap();
+        std::fs::create_dir(tmp.path().join("local")).unwrap();
+        git_init(&tmp.path().join("local"));
+
+        let checks = check_workspace(&config, &DoctorOptions { offline: true });
+        let find = |subject: &str| checks.iter().find(|c| c.subject == subject).unwrap();
+        assert_eq!(find(".meta").status, Status::Ok);
+        assert_eq!(find("api").status, Status::Fail);
+        assert_eq!(find("api").fix.as_deref(), Some("meta git update"));
+        assert_eq!(find("docs").status, Status::Fail);
+        assert!(find("docs").message.contains("not a git repository"));
+        assert_eq!(find("local").status, Status::Ok);
+        assert_eq!(find("store").status, Status::Ok);
+
+        let text = console::strip_ansi_codes(&format_checks(&checks)).to_string();
+        assert!(text.starts_with("config\n  ✓ .meta"), "{text}");
+        assert!(text.contains("      fix: meta git update\n"), "{text}");
+        assert!(text.ends_with("✗ 2 problem(s), 0 warning(s)\n"), "{text}");
+    }
+
+    #[test]
+    fn origin_mismatch_and_stale_worktrees_warn() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path().join("api");
+        std::fs::create_dir(&repo).unwrap();
+        git_init(&repo);
+        git(
+            &repo,
+            &["remote", "add", "origin", "git@example.com:old/api.git"],
+        )
+        .unwrap();
+        git(
+            &repo,
+            &[
+                "-c",
+                "user.name=t",
+                "-c",
+                "user.email=t@t",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "init",
+            ],
+        )
+        .unwrap();
+        let linked = tmp.path().join("linked");
+        git(&repo, &["worktree", "add", "-q", linked.to_str().unwrap()]).unwrap();
+        std::fs::remove_dir_all(&linked).unwrap();
+
+        let project = ProjectInfo {
+            name: "api".to_string(),
+            path: "api".to_string(),
+            repo: Some("git@example.com:org/api.git".to_string()),
+            tags: vec![],
+            provides: vec![],
+            depends_on: vec![],
+            meta: false,
+        };
+        let checks = check_project(tmp.path(), &project, &DoctorOptions { offline: true });
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, Status::Warn);
+        assert_eq!(
+            checks[0].fix.as_deref(),
+            Some("git -C api remote set-url origin git@example.com:org/api.git")
+        );
+
+        let checks = check_worktrees(tmp.path(), &[project]);
+        assert_eq!(checks[0].status, Status::Warn);
+        assert_eq!(checks[0].fix.as_deref(), Some("git -C api worktree prune"));
+    }
+}
@@ -0,0 +1,313 @@
+//! Dotenv loading for commands run across the workspace.
+//!
+//! Before `meta exec` / `meta run` spawn anything, variables are read from
+//! (lowest to highest precedence):
+//!
+//! 1. `.env` in the workspace root
+//! 2. `.meta.env` in the workspace root
+//! 3. `.env` in each repo (applies to that repo only)
+//!
+//! They sit above the `env` table of `~/.meta/config.yaml` and `.meta`,
+//! which apply even with `--no-dotenv`.
+//!
+//! Variables already set in meta's own environment always win, so
+//! `FOO=1 meta exec ...` overrides every file. `--no-dotenv` skips loading.
+//!
+//! Files use the usual `KEY=value` syntax: blank lines and `#` comments are
+//! ignored, an `export ` prefix is allowed, and values may be single-quoted
+//! (literal) or double-quoted (`\n`, `\t`, `\"` and `\\` escapes). There is
+//! no `${VAR}` interpolation.
+
+use anyhow::{Context, Result};
+use colored::*;
+use loop_lib::DirCommand;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::runner::RunConfig;
+
+/// Workspace-level files, lowest precedence first.
+pub const WORKSPACE_FILES: [&str; 2] = [".env", ".meta.env"];
+
+/// Per-repo file, layered over the workspace files.
+pub const REPO_FILE: &str = ".env";
+
+// ── Parsing ─────────────────────────────────────────────
+
+/// Parse dotenv text into `(key, value)` pairs in file order.
+pub fn parse(text: &str) -> Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+    for (n, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        let Some((key, value)) = line.split_once('=') else {
+            anyhow::bail!("line {}: expected KEY=VALUE", n + 1);
+        };
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!("line {}: invalid variable name '{key}'", n + 1);
+        }
+        let value = parse_value(value.trim())
+            .with_context(|| format!("line {}: bad value for {key}", n + 1))?;
+        vars.push((key.to_string(), value));
+    }
+    Ok(vars)
+}
+
+fn parse_value(value: &str) -> Result<String> {
+    if let Some(rest) = value.strip_prefix('\'') {
+        let end = rest.find('\'').context("unterminated single quote")?;
+        return Ok(rest[..end].to_string());
+    }
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut out = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(out),
+                '\\' => match chars.next() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some(other) => out.push(other),
+                    None => break,
+                },
+                c => out.push(c),
+            }
+        }
+        anyhow::bail!("unterminated double quote");
+    }
+    // Unquoted: an inline comment needs whitespace before the '#'
+    let value = match value.find(" #") {
+        Some(i) => &value[..i],
+        None => value,
+    };
+    Ok(value.trim_end().to_string())
+}
+
+/// Read a dotenv file, or `None` if it does not exist.
+pub fn load_file(path: &Path) -> Result<Option<Vec<(String, String)>>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse(&text)
+        .map(Some)
+        .with_context(|| format!("Invalid dotenv file {}", path.display()))
+}
+
+// ── Layering ────────────────────────────────────────────
+
+/// Merge files in order (later files win), dropping variables that are
+/// already set in the process environment.
+fn layer(paths: &[PathBuf]) -> Result<HashMap<String, String>> {
+    let mut env = HashMap::new();
+    for path in paths {
+        for (key, value) in load_file(path)?.into_iter().flatten() {
+            env.insert(key, value);
+        }
+    }
+    env.retain(|key, _| std::env::var_os(key).is_none());
+    Ok(env)
+}
+
+/// Variables from the workspace root's `.env` and `.meta.env`.
+pub fn workspace_env(root: &Path) -> Result<HashMap<String, String>> {
+    layer(&WORKSPACE_FILES.map(|f| root.join(f)))
+}
+
+/// Apply dotenv files to a run: workspace variables go on the run config,
+/// per-repo variables on each command. Explicitly configured variables are
+/// never overwritten.
+pub fn apply(config: &mut RunConfig, commands: &mut [DirCommand], root: &Path) -> Result<()> {
+    let mut env = workspace_env(root)?;
+    if !env.is_empty() {
+        env.extend(config.env.take().unwrap_or_default());
+        config.env = Some(env);
+    }
+
+    for command in commands {
+        let dir = Path::new(&command.dir);
+        if dir == root {
+            continue;
+        }
+        let mut env = layer(&[dir.join(REPO_FILE)])?;
+        if env.is_empty() {
+            continue;
+        }
+        env.extend(command.env.take().unwrap_or_default());
+        command.env = Some(env);
+    }
+    Ok(())
+}
+
+// ── meta config explain env ─────────────────────────────
+
+/// Print the precedence rules and which dotenv files the workspace has.
+pub fn handle_explain(config_name: Option<&PathBuf>, json: bool) -> Result<()> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let (root, projects) = match config::find_meta_config(&cwd, config_name) {
+        Some((path, _format)) => {
+            let root = path.parent().map(Path::to_path_buf).unwrap_or(cwd);
+            (root, config::parse_meta_config(&path)?.0)
+        }
+        None => (cwd, Vec::new()),
+    };
+
+    let mut files: Vec<(String, PathBuf)> = WORKSPACE_FILES
+        .iter()
+        .map(|f| ("workspace".to_string(), root.join(f)))
+        .collect();
+    files.extend(
+        projects
+            .iter()
+            .map(|p| (p.name.clone(), root.join(&p.path).join(REPO_FILE))),
+    );
+
+    // Variable names only: values are often secrets
+    let mut found = Vec::new();
+    for (scope, path) in files {
+        if let Some(vars) = load_file(&path)? {
+            let names: Vec<String> = vars.into_iter().map(|(k, _)| k).collect();
+            found.push((scope, path, names));
+        }
+    }
+
+    if json {
+        let files: Vec<serde_json::Value> = found
+            .iter()
+            .map(|(scope, path, names)| {
+                serde_json::json!({
+                    "scope": scope,
+                    "path": path.display().to_string(),
+                    "variables": names,
+                })
+            })
+            .collect();
+        let output = serde_json::json!({
+            "precedence": [
+                "env in ~/.meta/config.yaml",
+                "env in .meta",
+                ".env",
+                ".meta.env",
+                "<repo>/.env",
+                "process environment"
+            ],
+            "files": files,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!("{}", "Environment for meta exec / meta run".bold());
+    println!();
+    println!("Loaded before each command, later entries win:");
+    println!("  1. env in ~/.meta/config.yaml, then env in .meta");
+    println!("  2. .env            workspace root");
+    println!("  3. .meta.env       workspace root");
+    println!("  4. <repo>/.env     that repo only");
+    println!("  5. meta's own environment (e.g. FOO=1 meta exec ...)");
+    println!();
+    println!("Use --no-dotenv to skip the files.");
+    println!();
+    if found.is_empty() {
+        println!("No dotenv files found under {}", root.display());
+        return Ok(());
+    }
+    println!("{}", "Files in this workspace:".bold());
+    for (scope, path, names) in &found {
+        let shown = path.strip_prefix(&root).unwrap_or(path);
+        println!(
+            "  {} {:<20} {}",
+            "•".dimmed(),
+            shown.display(),
+            format!("({scope}) {}", names.join(", ")).dimmed()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_common_syntax() {
+        let vars = parse(
+            "# comment\n\
+             PLAIN=value\n\
+             export EXPORTED = spaced \n\
+             SINGLE='a $b #c'\n\
+             DOUBLE=\"line\\nnext \\\"q\\\"\"\n\
+             INLINE=kept#hash # dropped\n\
+             EMPTY=\n",
+        )
+        .unwrap();
+        let get = |k: &str| {
+            vars.iter()
+                .find(|(key, _)| key == k)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("PLAIN"), Some("value"));
+        assert_eq!(get("EXPORTED"), Some("spaced"));
+        assert_eq!(get("SINGLE"), Some("a $b #c"));
+        assert_eq!(get("DOUBLE"), Some("line\nnext \"q\""));
+        assert_eq!(get("INLINE"), Some("kept#hash"));
+        assert_eq!(get("EMPTY"), Some(""));
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        let err = parse("OK=1\nnot a pair\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+        assert!(parse("BAD-NAME=1").is_err());
+        assert!(parse("X=\"open").is_err());
+    }
+
+    #[test]
+    fn layers_files_and_keeps_explicit_values() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let repo = root.join("api");
+        std::fs::create_dir(&repo).unwrap();
+        std::fs::write(
+            root.join(".env"),
+            "META_DOTENV_A=env\nMETA_DOTENV_B=env\nPATH=/nope\n",
+        )
+        .unwrap();
+        std::fs::write(root.join(".meta.env"), "META_DOTENV_B=meta\n").unwrap();
+        std::fs::write(repo.join(".env"), "META_DOTENV_A=repo\n").unwrap();
+
+        let mut config = RunConfig {
+            env: Some(HashMap::from([(
+                "META_DOTENV_B".to_string(),
+                "explicit".to_string(),
+            )])),
+            ..Default::default()
+        };
+        let mut commands = vec![
+            DirCommand {
+                dir: root.display().to_string(),
+                cmd: "true".to_string(),
+                env: None,
+            },
+            DirCommand {
+                dir: repo.display().to_string(),
+                cmd: "true".to_string(),
+                env: None,
+            },
+        ];
+        apply(&mut config, &mut commands, root).unwrap();
+
+        let env = config.env.unwrap();
+        assert_eq!(env["META_DOTENV_A"], "env");
+        assert_eq!(env["META_DOTENV_B"], "explicit");
+        assert!(!env.contains_key("PATH"), "process environment wins");
+        assert!(commands[0].env.is_none());
+        assert_eq!(commands[1].env.as_ref().unwrap()["META_DOTENV_A"], "repo");
+    }
+}
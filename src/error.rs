@@ -0,0 +1,257 @@
+//! Stable, machine-readable error codes.
+//!
+//! Failures a script may want to branch on are raised as a [`MetaError`],
+//! usually wrapped in an `anyhow::Error` and possibly under added context.
+//! At the top level [`classify`] finds it again: meta exits with its exit
+//! code and, with `--json`, prints `{"error": {"code", "message",
+//! "exit_code"}}` on stdout. Anything else is `ERROR` with exit code 1.
+//!
+//! Codes and exit codes never change once released; `meta help
+//! exit-codes` lists them.
+
+use serde::Serialize;
+
+/// Exit code for errors without a more specific code.
+pub const GENERIC_EXIT_CODE: i32 = 1;
+
+/// Every code with its exit code and meaning, for `meta help exit-codes`.
+pub const CODES: &[(&str, i32, &str)] = &[
+    (
+        "ERROR",
+        GENERIC_EXIT_CODE,
+        "Any error without a more specific code",
+    ),
+    ("USAGE", 2, "Invalid arguments or flags"),
+    ("COMMAND_FAILED", 3, "A command failed in at least one repo"),
+    (
+        "CONFIG_NOT_FOUND",
+        4,
+        "No .meta config in this directory or above",
+    ),
+    ("CONFIG_INVALID", 5, "meta config validate found errors"),
+    (
+        "PROJECT_NOT_FOUND",
+        6,
+        "A named project is not in the config",
+    ),
+    (
+        "REPO_DIRTY",
+        7,
+        "A repo has uncommitted changes that would be lost",
+    ),
+    ("PLUGIN_NOT_FOUND", 8, "A plugin is not in any registry"),
+    (
+        "PLUGIN_VALIDATION_FAILED",
+        9,
+        "A plugin did not answer --meta-plugin-info with valid JSON",
+    ),
+    ("HOOK_REJECTED", 10, "A blocking hook vetoed the operation"),
+    (
+        "POLICY_DENIED",
+        11,
+        "The .meta policy denied a command, or it was not confirmed",
+    ),
+    ("PLUGIN_FAILED", 12, "A plugin exited with an error"),
+    (
+        "CHECKS_FAILED",
+        13,
+        "doctor or check-branches found problems",
+    ),
+    ("NO_MATCHES", 14, "grep or replace found nothing to match"),
+];
+
+/// Ends meta with this exit code after its reason was already shown, e.g.
+/// a `--passthrough` command's own exit code or an interrupt. Returned up
+/// to `main` instead of calling `std::process::exit`, so destructors and
+/// telemetry still run; nothing more is printed.
+#[derive(Debug, thiserror::Error)]
+#[error("exit code {0}")]
+pub struct Exit(pub i32);
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetaError {
+    #[error("{0}")]
+    Usage(String),
+    #[error("At least one command failed")]
+    CommandFailed,
+    #[error("Not a meta workspace (no .meta config found)")]
+    ConfigNotFound,
+    #[error("Config validation failed")]
+    ConfigInvalid,
+    #[error("Project '{name}' not found in {config}")]
+    ProjectNotFound { name: String, config: String },
+    #[error("Cannot {action}: {}", dirty_list(.repos))]
+    RepoDirty { action: String, repos: Vec<String> },
+    #[error("Plugin '{0}' not found in any registry")]
+    PluginNotFound(String),
+    #[error("Plugin validation failed: {0}")]
+    PluginValidationFailed(String),
+    #[error("{event} hook rejected the operation: {reason} (use --no-verify to skip)")]
+    HookRejected { event: String, reason: String },
+    #[error("Blocked by policy: {0}")]
+    PolicyDenied(String),
+    #[error("Plugin '{plugin}' exited with status {status}")]
+    PluginFailed { plugin: String, status: i32 },
+    #[error("{0}")]
+    ChecksFailed(String),
+    #[error("{0}")]
+    NoMatches(String),
+}
+
+impl MetaError {
+    /// The stable code, e.g. `CONFIG_NOT_FOUND`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            MetaError::Usage(_) => "USAGE",
+            MetaError::CommandFailed => "COMMAND_FAILED",
+            MetaError::ConfigNotFound => "CONFIG_NOT_FOUND",
+            MetaError::ConfigInvalid => "CONFIG_INVALID",
+            MetaError::ProjectNotFound { .. } => "PROJECT_NOT_FOUND",
+            MetaError::RepoDirty { .. } => "REPO_DIRTY",
+            MetaError::PluginNotFound(_) => "PLUGIN_NOT_FOUND",
+            MetaError::PluginValidationFailed(_) => "PLUGIN_VALIDATION_FAILED",
+            MetaError::HookRejected { .. } => "HOOK_REJECTED",
+            MetaError::PolicyDenied(_) => "POLICY_DENIED",
+            MetaError::PluginFailed { .. } => "PLUGIN_FAILED",
+            MetaError::ChecksFailed(_) => "CHECKS_FAILED",
+            MetaError::NoMatches(_) => "NO_MATCHES",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        exit_code_of(self.code())
+    }
+}
+
+/// How an error is reported at the top level.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorReport {
+    pub code: &'static str,
+    pub message: String,
+    pub exit_code: i32,
+}
+
+/// The [`Exit`] code `err` asks for, if it is (or wraps) one.
+pub fn exit_request(err: &anyhow::Error) -> Option<i32> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<Exit>())
+        .map(|exit| exit.0)
+}
+
+/// Find the [`MetaError`] anywhere in `err`'s chain, falling back to the
+/// generic code.
+pub fn classify(err: &anyhow::Error) -> ErrorReport {
+    let code = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<MetaError>())
+        .map_or("ERROR", MetaError::code);
+    ErrorReport {
+        code,
+        message: format!("{err:#}"),
+        exit_code: exit_code_of(code),
+    }
+}
+
+/// The `meta help exit-codes` topic body.
+pub fn exit_codes_help() -> String {
+    let mut out = String::from(
+        "meta exits 0 on success. Otherwise the exit code, and the \"code\"\n\
+         field of the {\"error\": ...} object printed with --json, say what\n\
+         went wrong:\n\n",
+    );
+    for (code, exit_code, meaning) in CODES {
+        out.push_str(&format!("    {exit_code:>3}  {code:<26}{meaning}\n"));
+    }
+    out.push_str(
+        "\nA run stopped by Ctrl-C exits 128 + the signal number (130).\n\
+         These codes are stable; new ones are only ever added.\n",
+    );
+    out
+}
+
+fn exit_code_of(code: &str) -> i32 {
+    CODES
+        .iter()
+        .find(|(c, _, _)| *c == code)
+        .map_or(GENERIC_EXIT_CODE, |(_, exit_code, _)| *exit_code)
+}
+
+fn dirty_list(repos: &[String]) -> String {
+    repos
+        .iter()
+        .map(|repo| format!("{repo} (uncommitted changes)"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn classifies_through_context_and_falls_back_to_error() {
+        let err = Err::<(), _>(MetaError::ConfigNotFound)
+            .context("Failed to load workspace")
+            .unwrap_err();
+        let report = classify(&err);
+        assert_eq!(report.code, "CONFIG_NOT_FOUND");
+        assert_eq!(report.exit_code, 4);
+        assert_eq!(
+            report.message,
+            "Failed to load workspace: Not a meta workspace (no .meta config found)"
+        );
+
+        let report = classify(&anyhow::anyhow!("something else"));
+        assert_eq!((report.code, report.exit_code), ("ERROR", 1));
+    }
+
+    #[test]
+    fn exit_requests_are_found_through_context() {
+        let err = Err::<(), _>(Exit(130)).context("run").unwrap_err();
+        assert_eq!(exit_request(&err), Some(130));
+        assert_eq!(exit_request(&MetaError::CommandFailed.into()), None);
+    }
+
+    #[test]
+    fn every_variant_has_a_distinct_documented_exit_code() {
+        let variants = [
+            MetaError::Usage("unexpected argument".to_string()),
+            MetaError::CommandFailed,
+            MetaError::ConfigNotFound,
+            MetaError::ConfigInvalid,
+            MetaError::ProjectNotFound {
+                name: "a".to_string(),
+                config: ".meta".to_string(),
+            },
+            MetaError::RepoDirty {
+                action: "thaw".to_string(),
+                repos: vec!["api".to_string(), "web".to_string()],
+            },
+            MetaError::PluginNotFound("x".to_string()),
+            MetaError::PluginValidationFailed("bad JSON".to_string()),
+            MetaError::HookRejected {
+                event: "pre-exec".to_string(),
+                reason: "exit 1".to_string(),
+            },
+            MetaError::PolicyDenied("rm -rf".to_string()),
+            MetaError::PluginFailed {
+                plugin: "git".to_string(),
+                status: 128,
+            },
+            MetaError::ChecksFailed("2 problem(s) found".to_string()),
+            MetaError::NoMatches("No matches".to_string()),
+        ];
+        let mut seen = std::collections::HashSet::new();
+        for variant in &variants {
+            assert!(CODES.iter().any(|(code, _, _)| *code == variant.code()));
+            assert_ne!(variant.exit_code(), GENERIC_EXIT_CODE, "{}", variant.code());
+            assert!(seen.insert(variant.exit_code()));
+        }
+        assert_eq!(
+            variants[5].to_string(),
+            "Cannot thaw: api (uncommitted changes), web (uncommitted changes)"
+        );
+        assert!(exit_codes_help().contains("  7  REPO_DIRTY"));
+    }
+}
@@ -0,0 +1,251 @@
+//! `--include` / `--exclude` patterns.
+//!
+//! Each pattern is one of:
+//!
+//! - `tag:frontend` — projects with that tag
+//! - `re:^lib-` — a regex, searched in the project name and the path
+//! - `if:<expr>` — a [`Predicate`] over the repo's files and git state
+//! - `services/*` — a glob (contains `*`, `?` or `[`), matched against the
+//!   project name or the trailing components of the path. `*` and `?` stay
+//!   within one component; `**` crosses them
+//! - anything else — a substring of the path, as `loop_lib` has always done
+//!
+//! A directory is kept if it matches any include (or there are none), no
+//! exclude, and every `--if` predicate.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::predicate::Predicate;
+
+/// A directory being filtered, with what is known about its project.
+#[derive(Debug, Clone, Copy)]
+pub struct Target<'a> {
+    pub path: &'a str,
+    /// Project name, or the directory's basename if it is not a project.
+    pub name: &'a str,
+    pub tags: &'a [String],
+}
+
+/// One parsed filter pattern.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Substring(String),
+    Glob(Regex),
+    Regex(Regex),
+    Tag(String),
+    Predicate(Predicate),
+}
+
+impl Pattern {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        if let Some(tag) = pattern.strip_prefix("tag:") {
+            return Ok(Pattern::Tag(tag.to_string()));
+        }
+        if let Some(expr) = pattern.strip_prefix("if:") {
+            return Ok(Pattern::Predicate(Predicate::parse(expr)?));
+        }
+        if let Some(re) = pattern.strip_prefix("re:") {
+            let re = Regex::new(re).with_context(|| format!("Invalid regex in '{pattern}'"))?;
+            return Ok(Pattern::Regex(re));
+        }
+        if pattern.contains(['*', '?', '[']) {
+            let re = Regex::new(&glob_to_regex(pattern.trim_end_matches('/')))
+                .with_context(|| format!("Invalid glob '{pattern}'"))?;
+            return Ok(Pattern::Glob(re));
+        }
+        Ok(Pattern::Substring(pattern.to_string()))
+    }
+
+    fn matches(&self, target: &Target, exclude: bool) -> bool {
+        match self {
+            // Excludes ignore a trailing slash, matching loop_lib
+            Pattern::Substring(s) if exclude => target.path.contains(s.trim_end_matches('/')),
+            Pattern::Substring(s) => target.path.contains(s.as_str()),
+            Pattern::Regex(re) => re.is_match(target.name) || re.is_match(target.path),
+            Pattern::Glob(re) => {
+                re.is_match(target.name) || path_suffixes(target.path).any(|s| re.is_match(s))
+            }
+            Pattern::Tag(tag) => target.tags.iter().any(|t| t == tag),
+            Pattern::Predicate(predicate) => predicate.matches(target),
+        }
+    }
+}
+
+/// Parsed include and exclude patterns, and `--if` predicates.
+#[derive(Debug, Clone, Default)]
+pub struct DirFilter {
+    pub include: Vec<Pattern>,
+    pub exclude: Vec<Pattern>,
+    pub conditions: Vec<Predicate>,
+}
+
+impl DirFilter {
+    pub fn parse(include: &[String], exclude: &[String]) -> Result<Self> {
+        Self::with_conditions(include, exclude, &[])
+    }
+
+    /// Like [`DirFilter::parse`], also requiring each `--if` expression.
+    pub fn with_conditions(
+        include: &[String],
+        exclude: &[String],
+        conditions: &[String],
+    ) -> Result<Self> {
+        Ok(DirFilter {
+            include: include
+                .iter()
+                .map(|p| Pattern::parse(p))
+                .collect::<Result<_>>()?,
+            exclude: exclude
+                .iter()
+                .map(|p| Pattern::parse(p))
+                .collect::<Result<_>>()?,
+            conditions: conditions
+                .iter()
+                .map(|e| Predicate::parse(e))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty() && self.conditions.is_empty()
+    }
+
+    pub fn matches(&self, target: &Target) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches(target, false)))
+            && !self.exclude.iter().any(|p| p.matches(target, true))
+            && self.conditions.iter().all(|c| c.matches(target))
+    }
+}
+
+/// Whether a project's tags satisfy a `--tag` filter: a comma-separated
+/// list, any of which may match.
+pub fn matches_tags(tags: &[String], filter: &str) -> bool {
+    filter
+        .split(',')
+        .map(str::trim)
+        .any(|wanted| tags.iter().any(|t| t == wanted))
+}
+
+/// The path itself and every suffix that starts after a `/`.
+fn path_suffixes(path: &str) -> impl Iterator<Item = &str> {
+    let path = path.trim_end_matches('/');
+    std::iter::once(path).chain(path.match_indices('/').map(move |(i, _)| &path[i + 1..]))
+}
+
+/// Translate a glob into an anchored regex.
+pub(crate) fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                re.push_str(".*");
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            '[' => {
+                re.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    re.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' {
+                        re.push('\\');
+                    }
+                    re.push(c);
+                }
+                re.push(']');
+            }
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target<'a>(path: &'a str, name: &'a str, tags: &'a [String]) -> Target<'a> {
+        Target { path, name, tags }
+    }
+
+    fn keeps(include: &[&str], exclude: &[&str], t: &Target) -> bool {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        DirFilter::parse(&strings(include), &strings(exclude))
+            .unwrap()
+            .matches(t)
+    }
+
+    #[test]
+    fn substrings_keep_loop_lib_semantics() {
+        let t = target("/ws/services/api", "api", &[]);
+        assert!(keeps(&["services"], &[], &t));
+        assert!(!keeps(&["web"], &[], &t));
+        assert!(!keeps(&[], &["api/"], &t));
+        assert!(keeps(&[], &[], &t));
+    }
+
+    #[test]
+    fn globs_match_names_and_path_suffixes() {
+        let api = target("/ws/services/api", "api", &[]);
+        let lib = target("/ws/libs/lib-core", "lib-core", &[]);
+        assert!(keeps(&["services/*"], &[], &api));
+        assert!(!keeps(&["services/*"], &[], &lib));
+        assert!(keeps(&["lib-*"], &[], &lib));
+        assert!(keeps(&["**/lib-c?re"], &[], &lib));
+        assert!(keeps(&["[a-b]pi"], &[], &api));
+        assert!(!keeps(&["*"], &["services/*"], &api));
+    }
+
+    #[test]
+    fn regex_and_tag_patterns() {
+        let tags = vec!["frontend".to_string()];
+        let web = target("/ws/web", "web", &tags);
+        let lib = target("/ws/lib-x", "lib-x", &[]);
+        assert!(keeps(&["re:^lib-"], &[], &lib));
+        assert!(!keeps(&["re:^lib-"], &[], &web));
+        assert!(keeps(&["tag:frontend"], &[], &web));
+        assert!(!keeps(&["tag:frontend"], &[], &lib));
+        assert!(!keeps(&[], &["tag:frontend"], &web));
+    }
+
+    #[test]
+    fn tag_filter_matches_any_listed_tag() {
+        let tags = vec!["backend".to_string(), "rust".to_string()];
+        assert!(matches_tags(&tags, "backend"));
+        assert!(matches_tags(&tags, "frontend, rust"));
+        assert!(!matches_tags(&tags, "frontend"));
+        assert!(!matches_tags(&[], "backend"));
+    }
+
+    #[test]
+    fn predicates_filter_like_patterns() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("go.mod"), "module x").unwrap();
+        let path = tmp.path().display().to_string();
+        let t = target(&path, "svc", &[]);
+        assert!(keeps(&["if:exists(go.mod)"], &[], &t));
+        assert!(!keeps(&[], &["if:lang==go"], &t));
+
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let filter =
+            DirFilter::with_conditions(&[], &[], &strings(&["exists(go.mod)", "name==api"]))
+                .unwrap();
+        assert!(!filter.is_empty());
+        assert!(!filter.matches(&t));
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        let err = DirFilter::parse(&["re:(".to_string()], &[]).unwrap_err();
+        assert!(err.to_string().contains("re:("));
+    }
+}
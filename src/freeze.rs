@@ -0,0 +1,364 @@
+//! Reproducible workspace states for `meta freeze` and `meta thaw`.
+//!
+//! `meta freeze` writes a lockfile pinning every repo to the commit it is
+//! on; `meta thaw <lockfile>` checks those commits out again, detached or on
+//! a named branch. Lockfiles are plain JSON, small enough to attach to a bug
+//! report or commit next to a CI job:
+//!
+//! ```json
+//! {
+//!   "created": "2026-01-01T12:00:00Z",
+//!   "repos": {
+//!     "api": { "path": "api", "commit": "3f2c…", "branch": "main" }
+//!   }
+//! }
+//! ```
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::MetaError;
+use crate::git::{self, RepoResult, Target};
+use crate::git_utils;
+
+/// Lockfile written when no file is given.
+pub const DEFAULT_LOCKFILE: &str = "meta.lock.json";
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub created: DateTime<Utc>,
+    /// Project name → pinned state
+    pub repos: BTreeMap<String, PinnedRepo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PinnedRepo {
+    /// Relative to the meta root
+    pub path: PathBuf,
+    pub commit: String,
+    /// Branch at freeze time, for information
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    /// Uncommitted changes existed and are not part of the pin
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dirty: bool,
+}
+
+impl Lockfile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read lockfile {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse lockfile {}", path.display()))
+    }
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta freeze`. `output` of `-` prints the lockfile.
+pub fn handle_freeze(
+    meta_dir: &Path,
+    targets: &[Target],
+    output: Option<&Path>,
+    json: bool,
+) -> Result<()> {
+    let lock = freeze(meta_dir, targets)?;
+    for (name, repo) in lock.repos.iter().filter(|(_, r)| r.dirty) {
+        eprintln!(
+            "{}: {name} has uncommitted changes; only its commit {} is pinned",
+            "warning".yellow().bold(),
+            short(&repo.commit)
+        );
+    }
+
+    let text = serde_json::to_string_pretty(&lock)? + "\n";
+    if output == Some(Path::new("-")) {
+        print!("{text}");
+        return Ok(());
+    }
+    let path = match output {
+        Some(path) => path.to_path_buf(),
+        None => meta_dir.join(DEFAULT_LOCKFILE),
+    };
+    std::fs::write(&path, text)
+        .with_context(|| format!("Failed to write lockfile {}", path.display()))?;
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "lockfile": path, "repos": lock.repos.len() })
+        );
+    } else {
+        println!("Pinned {} repos in {}", lock.repos.len(), path.display());
+    }
+    Ok(())
+}
+
+/// Entry point for `meta thaw <lockfile>`.
+pub fn handle_thaw(
+    meta_dir: &Path,
+    lockfile: &Path,
+    branch: Option<&str>,
+    json: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let lock = Lockfile::load(lockfile)?;
+    let results = thaw(meta_dir, &lock, branch, dry_run)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print!("{}", git::format_table(&results));
+    }
+    let failed = results.iter().filter(|r| !r.success).count();
+    anyhow::ensure!(
+        failed == 0,
+        "thaw failed in {failed} of {} repos",
+        results.len()
+    );
+    Ok(())
+}
+
+/// Pin every target at its current commit.
+pub fn freeze(meta_dir: &Path, targets: &[Target]) -> Result<Lockfile> {
+    let mut repos = BTreeMap::new();
+    for (name, path) in targets {
+        // Only repo roots count, otherwise git reports the enclosing (meta) repo
+        if !path.join(".git").exists() {
+            log::warn!("Skipping {name}: not a git repository");
+            continue;
+        }
+        let commit = git_utils::git(path, &["rev-parse", "--verify", "-q", "HEAD"])
+            .map_err(|_| anyhow::anyhow!("Cannot freeze {name}: no commits yet"))?;
+        let relative = path.strip_prefix(meta_dir).unwrap_or(path);
+        repos.insert(
+            name.clone(),
+            PinnedRepo {
+                path: if relative.as_os_str().is_empty() {
+                    PathBuf::from(".")
+                } else {
+                    relative.to_path_buf()
+                },
+                commit,
+                branch: git_utils::current_branch(path),
+                remote: git_utils::remote_url(path),
+                dirty: git_utils::is_dirty(path).unwrap_or(false),
+            },
+        );
+    }
+    Ok(Lockfile {
+        created: Utc::now(),
+        repos,
+    })
+}
+
+/// Check out every pinned commit, after checking that no repo would lose
+/// uncommitted changes. With `branch`, each repo's `branch` is (re)set to
+/// the pinned commit instead of detaching.
+pub fn thaw(
+    meta_dir: &Path,
+    lock: &Lockfile,
+    branch: Option<&str>,
+    dry_run: bool,
+) -> Result<Vec<RepoResult>> {
+    for (name, pinned) in &lock.repos {
+        anyhow::ensure!(
+            inside_workspace(&pinned.path),
+            "Cannot thaw: {name} has path '{}', which is not inside the workspace",
+            pinned.path.display()
+        );
+    }
+    let mut problems = Vec::new();
+    let mut dirty = Vec::new();
+    for (name, pinned) in &lock.repos {
+        let path = meta_dir.join(&pinned.path);
+        match check(&path) {
+            Ok(true) => {}
+            Ok(false) => dirty.push(name.clone()),
+            Err(e) => problems.push(format!("{name} ({e:#})")),
+        }
+    }
+    if problems.is_empty() && !dirty.is_empty() {
+        return Err(MetaError::RepoDirty {
+            action: "thaw".to_string(),
+            repos: dirty,
+        }
+        .into());
+    }
+    problems.extend(
+        dirty
+            .iter()
+            .map(|name| format!("{name} (uncommitted changes)")),
+    );
+    anyhow::ensure!(problems.is_empty(), "Cannot thaw: {}", problems.join(", "));
+
+    let results = lock
+        .repos
+        .iter()
+        .map(|(name, pinned)| {
+            let path = meta_dir.join(&pinned.path);
+            let target = short(&pinned.commit);
+            let (success, summary) = if dry_run {
+                let how = branch.map_or_else(|| "detached".to_string(), |b| format!("on {b}"));
+                (true, format!("would check out {target} {how}"))
+            } else {
+                match checkout(&path, &pinned.commit, branch) {
+                    Ok(()) => (true, format!("checked out {target}")),
+                    Err(e) => (false, format!("{e:#}")),
+                }
+            };
+            RepoResult {
+                name: name.clone(),
+                path: path.clone(),
+                success,
+                branch: git_utils::current_branch(&path),
+                summary,
+                output: String::new(),
+            }
+        })
+        .collect();
+    Ok(results)
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+/// Whether a lockfile `path` names a directory inside the workspace: `.`
+/// for the meta root, otherwise only plain relative components.
+fn inside_workspace(path: &Path) -> bool {
+    path == Path::new(".")
+        || (!path.as_os_str().is_empty()
+            && path.components().all(|c| matches!(c, Component::Normal(_))))
+}
+
+/// Whether the repo at `path` is free of uncommitted changes; an error if
+/// it cannot be thawed at all.
+fn check(path: &Path) -> Result<bool> {
+    anyhow::ensure!(path.exists(), "not cloned");
+    anyhow::ensure!(path.join(".git").exists(), "not a git repository");
+    let changed = git_utils::git(path, &["status", "--porcelain", "--untracked-files=no"])?;
+    Ok(changed.is_empty())
+}
+
+fn checkout(path: &Path, commit: &str, branch: Option<&str>) -> Result<()> {
+    let object = format!("{commit}^{{commit}}");
+    if git_utils::git(path, &["cat-file", "-e", &object]).is_err() {
+        git_utils::git(path, &["fetch", "-q", "origin"])
+            .context("commit not found and fetch failed")?;
+        git_utils::git(path, &["cat-file", "-e", &object]).map_err(|_| {
+            anyhow::anyhow!("commit {} not found, even after fetching", short(commit))
+        })?;
+    }
+    match branch {
+        Some(branch) => git_utils::git(path, &["checkout", "-q", "-B", branch, commit])?,
+        None => git_utils::git(path, &["checkout", "-q", "--detach", commit])?,
+    };
+    Ok(())
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::testing::init_repo;
+
+    fn commit(dir: &Path, file: &str) -> String {
+        std::fs::write(dir.join(file), file).unwrap();
+        git_utils::git(dir, &["add", "."]).unwrap();
+        git_utils::git(dir, &["commit", "-q", "-m", file]).unwrap();
+        git_utils::git(dir, &["rev-parse", "HEAD"]).unwrap()
+    }
+
+    #[test]
+    fn freeze_then_thaw_restores_pinned_commits() {
+        let tmp = tempfile::tempdir().unwrap();
+        let api = tmp.path().join("api");
+        let web = tmp.path().join("libs/web");
+        init_repo(&api);
+        init_repo(&web);
+        let targets = vec![
+            ("api".to_string(), api.clone()),
+            ("web".to_string(), web.clone()),
+            ("docs".to_string(), tmp.path().join("docs")),
+        ];
+
+        let lock = freeze(tmp.path(), &targets).unwrap();
+        assert_eq!(lock.repos.len(), 2, "uncloned repos are skipped");
+        assert_eq!(lock.repos["web"].path, Path::new("libs/web"));
+        assert_eq!(lock.repos["api"].branch.as_deref(), Some("main"));
+        let pinned = lock.repos["api"].commit.clone();
+
+        let file = tmp.path().join("lock.json");
+        std::fs::write(&file, serde_json::to_string(&lock).unwrap()).unwrap();
+        let lock = Lockfile::load(&file).unwrap();
+
+        commit(&api, "later.txt");
+        let results = thaw(tmp.path(), &lock, None, false).unwrap();
+        assert!(results.iter().all(|r| r.success), "{results:?}");
+        assert_eq!(
+            git_utils::git(&api, &["rev-parse", "HEAD"]).unwrap(),
+            pinned
+        );
+        assert_eq!(git_utils::current_branch(&api), None);
+
+        let results = thaw(tmp.path(), &lock, Some("repro"), false).unwrap();
+        assert!(results.iter().all(|r| r.success));
+        assert_eq!(git_utils::current_branch(&api).as_deref(), Some("repro"));
+    }
+
+    #[test]
+    fn thaw_refuses_dirty_repos_and_reports_unknown_commits() {
+        let tmp = tempfile::tempdir().unwrap();
+        let api = tmp.path().join("api");
+        init_repo(&api);
+        let mut lock = freeze(tmp.path(), &[("api".to_string(), api.clone())]).unwrap();
+
+        std::fs::write(api.join("README.md"), "changed").unwrap();
+        let err = thaw(tmp.path(), &lock, None, false).unwrap_err();
+        assert!(
+            err.to_string().contains("api (uncommitted changes)"),
+            "{err}"
+        );
+        git_utils::git(&api, &["checkout", "--", "."]).unwrap();
+
+        lock.repos.get_mut("api").unwrap().commit = "0".repeat(40);
+        let results = thaw(tmp.path(), &lock, None, false).unwrap();
+        assert!(!results[0].success);
+        assert!(
+            results[0].summary.contains("fetch failed"),
+            "{}",
+            results[0].summary
+        );
+    }
+
+    #[test]
+    fn thaw_rejects_paths_outside_the_workspace() {
+        let tmp = tempfile::tempdir().unwrap();
+        for path in ["../elsewhere", "/etc", "api/../../x"] {
+            let mut lock = Lockfile::default();
+            lock.repos.insert(
+                "api".to_string(),
+                PinnedRepo {
+                    path: PathBuf::from(path),
+                    commit: "0".repeat(40),
+                    ..Default::default()
+                },
+            );
+            let err = thaw(tmp.path(), &lock, None, true).unwrap_err();
+            assert!(
+                err.to_string().contains("not inside the workspace"),
+                "{err}"
+            );
+        }
+        assert!(inside_workspace(Path::new(".")));
+        assert!(inside_workspace(Path::new("apps/web")));
+    }
+}
@@ -0,0 +1,487 @@
+//! Built-in `meta git` fallback for workspaces without the git plugin.
+//!
+//! Covers the everyday subcommands (`status`, `pull`, `push`, `checkout`,
+//! `branch`) natively: git runs in every repo, and the results are printed
+//! as one aligned row per repo (or as JSON) instead of raw interleaved
+//! output. `snapshot` is delegated to [`crate::snapshot`]. Anything else
+//! still needs the plugin or `meta exec git ...`.
+
+use anyhow::Result;
+use colored::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::git_utils;
+use crate::runner::shell_quote;
+
+/// Subcommands handled without a plugin.
+pub const SUBCOMMANDS: &[&str] = &["status", "pull", "push", "checkout", "branch", "snapshot"];
+
+/// Whether `args` (starting with `git`) is a command this module handles.
+pub fn handles(args: &[String]) -> bool {
+    args.first().is_some_and(|a| a == "git")
+        && args
+            .get(1)
+            .is_some_and(|sub| SUBCOMMANDS.contains(&sub.as_str()))
+}
+
+/// Help for `meta git` when no plugin provides it.
+pub fn usage() -> String {
+    let mut out = String::from(
+        "Usage: meta git <command> [args...]\n\n\
+         Built-in commands (install the git plugin for more):\n",
+    );
+    for (sub, about) in [
+        ("status", "Branch, changes and sync state of every repo"),
+        ("pull", "Pull every repo and report new commits"),
+        ("push", "Push every repo"),
+        ("checkout <branch>", "Switch every repo to a branch"),
+        ("branch [args...]", "List or manage branches in every repo"),
+        (
+            "snapshot <cmd>",
+            "create, list, restore or delete workspace snapshots",
+        ),
+    ] {
+        out.push_str(&format!("    {sub:<18}  {about}\n"));
+    }
+    out.push_str(
+        "\nAdd --json for machine-readable results. Other git commands: meta exec git ...\n",
+    );
+    out
+}
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default)]
+pub struct GitOptions {
+    pub json: bool,
+    pub parallel: bool,
+    pub dry_run: bool,
+    /// Print each repo's git output below the table
+    pub verbose: bool,
+    /// `.meta` config whose command `policy` applies
+    pub config: Option<PathBuf>,
+}
+
+/// Outcome of a git subcommand in one repo.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoResult {
+    pub name: String,
+    pub path: PathBuf,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// One-line description of what happened
+    pub summary: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub output: String,
+}
+
+/// The repos a command runs in: (display name, path).
+pub type Target = (String, PathBuf);
+
+/// Name each directory relative to `root`, `.` for the root itself; without
+/// a root, by its directory name.
+pub fn targets(dirs: &[String], root: Option<&Path>) -> Vec<Target> {
+    dirs.iter()
+        .map(|dir| {
+            let path = PathBuf::from(dir);
+            let name = match root.and_then(|r| path.strip_prefix(r).ok()) {
+                Some(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+                Some(rel) => rel.display().to_string(),
+                None => path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| dir.clone()),
+            };
+            (name, path)
+        })
+        .collect()
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Run `meta git <sub> <args>...` (`args` without the leading `git`) in
+/// every target of `workspace` and print the results. Fails if any repo
+/// failed.
+pub fn handle_git(
+    args: &[String],
+    targets: &[Target],
+    workspace: &Path,
+    options: &GitOptions,
+) -> Result<()> {
+    let Some((sub, rest)) = args.split_first() else {
+        anyhow::bail!("Usage: meta git <{}> [args...]", SUBCOMMANDS.join("|"));
+    };
+    if !options.dry_run {
+        let command = std::iter::once("git")
+            .chain(args.iter().map(String::as_str))
+            .map(shell_quote)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let commands = vec![command.as_str(); targets.len()];
+        crate::policy::enforce(options.config.as_deref(), &commands)?;
+    }
+    if sub == "snapshot" {
+        return crate::snapshot::handle_snapshot(
+            rest,
+            targets,
+            workspace,
+            options.json,
+            options.dry_run,
+        );
+    }
+    if sub == "checkout" && rest.is_empty() {
+        anyhow::bail!("Usage: meta git checkout <branch>");
+    }
+
+    let run = |(name, path): &Target| run_in(sub, rest, name, path, options.dry_run);
+    let results: Vec<RepoResult> = if options.parallel {
+        targets.par_iter().map(run).collect()
+    } else {
+        targets.iter().map(run).collect()
+    };
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print!("{}", format_table(&results));
+        if options.verbose {
+            for r in results.iter().filter(|r| !r.output.is_empty()) {
+                println!("\n{}:\n{}", r.name.bold(), r.output.trim_end());
+            }
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.success).count();
+    if failed > 0 {
+        anyhow::bail!("git {sub} failed in {failed} of {} repos", results.len());
+    }
+    Ok(())
+}
+
+/// Render results as an aligned table.
+pub fn format_table(results: &[RepoResult]) -> String {
+    let name_width = results
+        .iter()
+        .map(|r| r.name.chars().count())
+        .chain(std::iter::once(4))
+        .max()
+        .unwrap_or(0);
+    let branch_width = results
+        .iter()
+        .map(|r| r.branch.as_deref().unwrap_or("-").chars().count())
+        .chain(std::iter::once(6))
+        .max()
+        .unwrap_or(0);
+
+    let mut out = format!(
+        "{}\n",
+        format!(
+            "  {:<name_width$}  {:<branch_width$}  RESULT",
+            "REPO", "BRANCH"
+        )
+        .bold()
+    );
+    for r in results {
+        let mark = if r.success {
+            "✓".green()
+        } else {
+            "✗".red()
+        };
+        let summary = if r.success {
+            r.summary.normal()
+        } else {
+            r.summary.red()
+        };
+        out.push_str(&format!(
+            "{mark} {:<name_width$}  {:<branch_width$}  {summary}\n",
+            r.name,
+            r.branch.as_deref().unwrap_or("-"),
+        ));
+    }
+    out
+}
+
+// ── Per-repo Execution ──────────────────────────────────
+
+fn run_in(sub: &str, rest: &[String], name: &str, path: &Path, dry_run: bool) -> RepoResult {
+    let mut result = RepoResult {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        success: false,
+        branch: None,
+        summary: String::new(),
+        output: String::new(),
+    };
+    if !path.exists() {
+        result.summary = "not cloned".to_string();
+        return result;
+    }
+    // Only query repo roots, otherwise git reports the enclosing (meta) repo
+    if !path.join(".git").exists() {
+        result.summary = "not a git repository".to_string();
+        return result;
+    }
+
+    // Status and a bare `branch` only read, so they run even in dry-run mode
+    let read_only = sub == "status" || (sub == "branch" && rest.is_empty());
+    if dry_run && !read_only {
+        result.success = true;
+        result.branch = git_utils::current_branch(path);
+        result.summary = format!("would run: git {sub} {}", rest.join(" "))
+            .trim_end()
+            .to_string();
+        return result;
+    }
+
+    let before = head(path);
+    let (success, output) = if read_only {
+        (true, String::new())
+    } else {
+        git(path, sub, rest)
+    };
+    result.success = success;
+    result.branch = git_utils::current_branch(path);
+    result.summary = if !success {
+        first_line(&output).unwrap_or("failed").to_string()
+    } else {
+        match sub {
+            "status" => status_summary(path),
+            "pull" => pull_summary(path, before.as_deref()),
+            "checkout" => match &result.branch {
+                Some(branch) => format!("on {branch}"),
+                None => "detached HEAD".to_string(),
+            },
+            "branch" if rest.is_empty() => branch_summary(path),
+            _ => last_line(&output).unwrap_or("done").to_string(),
+        }
+    };
+    result.output = output;
+    result
+}
+
+/// Run git in `path`, returning success and the combined output.
+fn git(path: &Path, sub: &str, rest: &[String]) -> (bool, String) {
+    let output = Command::new("git")
+        .arg(sub)
+        .args(rest)
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .output();
+    match output {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            (output.status.success(), text)
+        }
+        Err(e) => (false, format!("failed to run git: {e}")),
+    }
+}
+
+fn head(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(path)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn status_summary(path: &Path) -> String {
+    let mut parts = Vec::new();
+    match git_utils::dirty_file_count(path) {
+        Some(0) | None => parts.push("clean".to_string()),
+        Some(n) => parts.push(format!("{n} modified")),
+    }
+    match git_utils::ahead_behind(path) {
+        Some((0, 0)) => {}
+        Some((ahead, behind)) => {
+            if ahead > 0 {
+                parts.push(format!("↑{ahead}"));
+            }
+            if behind > 0 {
+                parts.push(format!("↓{behind}"));
+            }
+        }
+        None => parts.push("no upstream".to_string()),
+    }
+    parts.join(", ")
+}
+
+fn branch_summary(path: &Path) -> String {
+    let (_, output) = git(path, "branch", &["--format=%(refname:short)".to_string()]);
+    match output.lines().filter(|l| !l.trim().is_empty()).count() {
+        1 => "1 local branch".to_string(),
+        n => format!("{n} local branches"),
+    }
+}
+
+fn pull_summary(path: &Path, before: Option<&str>) -> String {
+    let after = head(path);
+    match (before, after.as_deref()) {
+        (Some(before), Some(after)) if before == after => "already up to date".to_string(),
+        (Some(before), Some(after)) => {
+            let count = Command::new("git")
+                .args(["rev-list", "--count", &format!("{before}..{after}")])
+                .current_dir(path)
+                .output()
+                .ok()
+                .and_then(|o| {
+                    String::from_utf8_lossy(&o.stdout)
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                });
+            match count {
+                Some(1) => "pulled 1 commit".to_string(),
+                Some(n) => format!("pulled {n} commits"),
+                None => "updated".to_string(),
+            }
+        }
+        _ => "updated".to_string(),
+    }
+}
+
+fn first_line(text: &str) -> Option<&str> {
+    text.lines().map(str::trim).find(|l| !l.is_empty())
+}
+
+fn last_line(text: &str) -> Option<&str> {
+    text.lines().map(str::trim).rfind(|l| !l.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::testing::init_repo;
+
+    fn git_in(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn handles_only_native_subcommands() {
+        assert!(handles(&args(&["git", "status"])));
+        assert!(handles(&args(&["git", "checkout", "main"])));
+        assert!(!handles(&args(&["git", "rebase"])));
+        assert!(!handles(&args(&["git"])));
+        assert!(!handles(&args(&["cargo", "status"])));
+    }
+
+    #[test]
+    fn targets_are_named_relative_to_the_root() {
+        let dirs = args(&["/ws", "/ws/libs/core", "/elsewhere/api"]);
+        let names: Vec<_> = targets(&dirs, Some(Path::new("/ws")))
+            .into_iter()
+            .map(|t| t.0)
+            .collect();
+        assert_eq!(names, [".", "libs/core", "api"]);
+    }
+
+    #[test]
+    fn status_reports_changes_and_missing_repos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let api = tmp.path().join("api");
+        init_repo(&api);
+        std::fs::write(api.join("new.txt"), "x").unwrap();
+
+        let clean = run_in("status", &[], "api", &api, false);
+        assert!(clean.success);
+        assert_eq!(clean.branch.as_deref(), Some("main"));
+        assert_eq!(clean.summary, "1 modified, no upstream");
+
+        let missing = run_in("status", &[], "web", &tmp.path().join("web"), false);
+        assert!(!missing.success);
+        assert_eq!(missing.summary, "not cloned");
+    }
+
+    #[test]
+    fn checkout_switches_and_reports_failures() {
+        let tmp = tempfile::tempdir().unwrap();
+        let api = tmp.path().join("api");
+        init_repo(&api);
+        git_in(&api, &["branch", "feature"]);
+
+        let dry = run_in("checkout", &args(&["feature"]), "api", &api, true);
+        assert_eq!(dry.summary, "would run: git checkout feature");
+        assert_eq!(git_utils::current_branch(&api).as_deref(), Some("main"));
+
+        let ok = run_in("checkout", &args(&["feature"]), "api", &api, false);
+        assert!(ok.success);
+        assert_eq!(ok.summary, "on feature");
+
+        let bad = run_in("checkout", &args(&["nope"]), "api", &api, false);
+        assert!(!bad.success);
+        assert!(bad.summary.contains("nope"), "{}", bad.summary);
+        assert_eq!(bad.branch.as_deref(), Some("feature"));
+
+        let branches = run_in("branch", &[], "api", &api, true);
+        assert_eq!(branches.summary, "2 local branches");
+    }
+
+    #[test]
+    fn pull_counts_new_commits() {
+        let tmp = tempfile::tempdir().unwrap();
+        let origin = tmp.path().join("origin");
+        init_repo(&origin);
+        git_in(
+            tmp.path(),
+            &["clone", "-q", origin.to_str().unwrap(), "clone"],
+        );
+        let clone = tmp.path().join("clone");
+
+        let up_to_date = run_in("pull", &[], "clone", &clone, false);
+        assert!(up_to_date.success, "{}", up_to_date.output);
+        assert_eq!(up_to_date.summary, "already up to date");
+
+        std::fs::write(origin.join("a.txt"), "a").unwrap();
+        git_in(&origin, &["add", "."]);
+        git_in(&origin, &["commit", "-q", "-m", "a"]);
+        std::fs::write(origin.join("b.txt"), "b").unwrap();
+        git_in(&origin, &["add", "."]);
+        git_in(&origin, &["commit", "-q", "-m", "b"]);
+
+        let pulled = run_in("pull", &[], "clone", &clone, false);
+        assert!(pulled.success, "{}", pulled.output);
+        assert_eq!(pulled.summary, "pulled 2 commits");
+    }
+
+    #[test]
+    fn table_aligns_columns() {
+        let row = |name: &str, branch: Option<&str>, success: bool| RepoResult {
+            name: name.to_string(),
+            path: PathBuf::new(),
+            success,
+            branch: branch.map(str::to_string),
+            summary: "clean".to_string(),
+            output: String::new(),
+        };
+        let table = format_table(&[
+            row(".", Some("main"), true),
+            row("services/api", None, false),
+        ]);
+        let table = console::strip_ansi_codes(&table);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines[0], "  REPO          BRANCH  RESULT");
+        assert_eq!(lines[1], "✓ .             main    clean");
+        assert_eq!(lines[2], "✗ services/api  -       clean");
+    }
+}
@@ -1,11 +1,53 @@
 //! Shared git primitives used across crates.
 //!
 //! Lightweight functions that shell out to `git` for common queries.
-//! All functions gracefully handle missing repos or git failures.
+//! The queries gracefully handle missing repos or git failures; [`git`]
+//! is for commands whose failure the caller reports.
 
-use std::path::Path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
+/// Run `git <args>` in `dir` and return its trimmed stdout, or an error
+/// with git's message.
+pub fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    run(git_command(dir, args))
+}
+
+/// `git <args>` in `dir` with stdin closed, for callers that need to add
+/// environment variables before running it with [`run`].
+pub fn git_command(dir: &Path, args: &[&str]) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.args(args).current_dir(dir).stdin(Stdio::null());
+    cmd
+}
+
+/// Run a command from [`git_command`]: its trimmed stdout, or an error
+/// naming the git subcommand with git's stderr (or the first line of
+/// stdout when stderr is empty, e.g. `nothing to commit`).
+pub fn run(mut cmd: Command) -> Result<String> {
+    let dir = cmd
+        .get_current_dir()
+        .unwrap_or(Path::new("."))
+        .to_path_buf();
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to run git in {}", dir.display()))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let detail = if stderr.trim().is_empty() {
+            stdout.trim().lines().next().unwrap_or_default().to_string()
+        } else {
+            stderr.trim().to_string()
+        };
+        let subcommand = cmd.get_args().next().unwrap_or_default().to_string_lossy();
+        anyhow::bail!("git {subcommand} failed: {detail}");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 /// Helper to run a git command and return stdout as a String.
 /// Returns None if command fails or output is invalid UTF-8.
 fn run_git_command(repo_path: &Path, args: &[&str]) -> Option<String> {
@@ -85,6 +127,89 @@ pub fn ahead_behind(repo_path: &Path) -> Option<(usize, usize)> {
     }
 }
 
+/// Short summary of a commit, as shown in status tables.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitSummary {
+    /// Abbreviated commit hash.
+    pub sha: String,
+    pub subject: String,
+    /// Human-readable commit age (e.g. "2 hours ago").
+    pub relative_date: String,
+}
+
+/// Returns a summary of the HEAD commit, or `None` if there are no commits or git fails.
+pub fn last_commit(repo_path: &Path) -> Option<CommitSummary> {
+    // Unit separator keeps subjects containing spaces or tabs intact
+    let text = run_git_command(repo_path, &["log", "-1", "--format=%h%x1f%s%x1f%cr"])?;
+    let mut parts = text.splitn(3, '\u{1f}');
+    Some(CommitSummary {
+        sha: parts.next()?.to_string(),
+        subject: parts.next()?.to_string(),
+        relative_date: parts.next()?.to_string(),
+    })
+}
+
+/// Returns the URL of the `origin` remote, or `None` if there is no such remote.
+pub fn remote_url(repo_path: &Path) -> Option<String> {
+    run_git_command(repo_path, &["config", "--get", "remote.origin.url"]).filter(|s| !s.is_empty())
+}
+
+/// Find git repos under `root`, at most `max_depth` directories down.
+///
+/// Returns paths relative to `root`, sorted. The walk does not descend
+/// into repos it finds (so submodules and nested checkouts stay with their
+/// parent), hidden directories, or directories `root`'s own repo ignores,
+/// such as `node_modules` or `vendor`. A repo root is kept even when it is
+/// ignored, since child repos usually are.
+pub fn find_repos(root: &Path, max_depth: usize) -> Vec<PathBuf> {
+    let mut repos = Vec::new();
+    let mut walker = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter();
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else { continue };
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            walker.skip_current_dir();
+        } else if entry.path().join(".git").exists() {
+            repos.push(rel.to_path_buf());
+            walker.skip_current_dir();
+        } else if is_ignored(root, rel) {
+            walker.skip_current_dir();
+        }
+    }
+    repos
+}
+
+/// Whether `root`'s repo ignores `rel`. False when `root` is not in a repo.
+fn is_ignored(root: &Path, rel: &Path) -> bool {
+    run_git_command(root, &["check-ignore", "-q", "--", &rel.to_string_lossy()]).is_some()
+}
+
+/// Fixtures for tests that work on real repos.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::git;
+    use std::path::Path;
+
+    /// Create a repo at `dir` on `main`, with a test identity and one
+    /// commit adding `README.md`.
+    pub fn init_repo(dir: &Path) {
+        std::fs::create_dir_all(dir).unwrap();
+        git(dir, &["init", "-q", "-b", "main"]).unwrap();
+        git(dir, &["config", "user.email", "test@example.com"]).unwrap();
+        git(dir, &["config", "user.name", "Test"]).unwrap();
+        std::fs::write(dir.join("README.md"), "hello\n").unwrap();
+        git(dir, &["add", "."]).unwrap();
+        git(dir, &["commit", "-q", "-m", "init"]).unwrap();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -350,4 +475,63 @@ mod tests {
         assert_eq!(ahead, 0);
         assert_eq!(behind, 0);
     }
+
+    #[test]
+    fn last_commit_returns_sha_and_subject() {
+        let tmp = init_git_repo();
+        make_initial_commit(tmp.path());
+        let commit = last_commit(tmp.path()).unwrap();
+        assert_eq!(commit.subject, "initial");
+        assert!(commit.sha.len() >= 7);
+        assert!(!commit.relative_date.is_empty());
+    }
+
+    #[test]
+    fn remote_url_reads_origin() {
+        let dir = init_git_repo();
+        assert_eq!(remote_url(dir.path()), None);
+
+        Command::new("git")
+            .args(["remote", "add", "origin", "git@github.com:org/api.git"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert_eq!(
+            remote_url(dir.path()).as_deref(),
+            Some("git@github.com:org/api.git")
+        );
+    }
+
+    #[test]
+    fn last_commit_none_without_commits() {
+        let tmp = init_git_repo();
+        assert!(last_commit(tmp.path()).is_none());
+    }
+
+    #[test]
+    fn find_repos_respects_depth_and_gitignore() {
+        let tmp = init_git_repo();
+        let root = tmp.path();
+        std::fs::write(root.join(".gitignore"), "node_modules/\napi/\n").unwrap();
+        for dir in [
+            "api",
+            "services/web",
+            "services/web/vendor/lib",
+            "node_modules/pkg",
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            Command::new("git")
+                .args(["init", "-q"])
+                .current_dir(root.join(dir))
+                .status()
+                .unwrap();
+        }
+        std::fs::create_dir_all(root.join("docs/guide")).unwrap();
+
+        assert_eq!(find_repos(root, 1), vec![PathBuf::from("api")]);
+        assert_eq!(
+            find_repos(root, 3),
+            vec![PathBuf::from("api"), PathBuf::from("services/web")]
+        );
+    }
 }
@@ -0,0 +1,328 @@
+//! `meta graph`: render the cross-repo dependency graph.
+//!
+//! Edges come from `depends_on` in .meta (resolved through `provides`) and
+//! point from a project to what it depends on. Edges that close a dependency
+//! cycle are highlighted in every format, and `--focus` narrows the graph to
+//! one project plus everything it transitively depends on or is depended on by.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+use crate::config;
+use crate::dependency_graph::DependencyGraph;
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+    Json,
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            "json" => Ok(GraphFormat::Json),
+            other => {
+                anyhow::bail!("Unknown graph format '{other}' (expected dot, mermaid or json)")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphNode {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub in_cycle: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphEdge {
+    /// The depending project
+    pub from: String,
+    /// The project it depends on
+    pub to: String,
+    pub in_cycle: bool,
+}
+
+/// The part of the dependency graph to render.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphView {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus: Option<String>,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    /// Each cycle as a list of projects, starting at its smallest name
+    pub cycles: Vec<Vec<String>>,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta graph`.
+pub fn handle_graph(config_path: &Path, format: GraphFormat, focus: Option<&str>) -> Result<()> {
+    let (projects, _ignore) = config::parse_meta_config(config_path)?;
+    let graph = DependencyGraph::build(projects.into_iter().map(Into::into).collect())?;
+    let view = build_view(&graph, focus)?;
+
+    for cycle in &view.cycles {
+        eprintln!(
+            "warning: dependency cycle: {} -> {}",
+            cycle.join(" -> "),
+            cycle[0]
+        );
+    }
+
+    match format {
+        GraphFormat::Dot => print!("{}", render_dot(&view)),
+        GraphFormat::Mermaid => print!("{}", render_mermaid(&view)),
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&view)?),
+    }
+    Ok(())
+}
+
+/// Select the nodes and edges to render, marking the ones in a cycle.
+pub fn build_view(graph: &DependencyGraph, focus: Option<&str>) -> Result<GraphView> {
+    let included: HashSet<&str> = match focus {
+        Some(name) => {
+            if graph.get_project(name).is_none() {
+                return Err(crate::error::MetaError::ProjectNotFound {
+                    name: name.to_string(),
+                    config: ".meta".to_string(),
+                }
+                .into());
+            }
+            graph.neighborhood(name)
+        }
+        None => graph
+            .all_projects()
+            .into_iter()
+            .map(|p| p.name.as_str())
+            .collect(),
+    };
+
+    let cycles: BTreeSet<Vec<String>> = graph
+        .detect_cycles()
+        .into_iter()
+        .filter(|cycle| cycle.iter().all(|n| included.contains(n.as_str())))
+        .map(normalize_cycle)
+        .collect();
+    let cycle_edges: HashSet<(&str, &str)> = cycles
+        .iter()
+        .flat_map(|cycle| {
+            cycle
+                .iter()
+                .zip(cycle.iter().cycle().skip(1))
+                .map(|(a, b)| (a.as_str(), b.as_str()))
+        })
+        .collect();
+    let cycle_nodes: HashSet<&str> = cycle_edges.iter().map(|(a, _)| *a).collect();
+
+    let mut names: Vec<&str> = included.into_iter().collect();
+    names.sort_unstable();
+    let nodes = names
+        .iter()
+        .map(|name| GraphNode {
+            name: name.to_string(),
+            tags: graph
+                .get_project(name)
+                .map(|p| p.tags.clone())
+                .unwrap_or_default(),
+            in_cycle: cycle_nodes.contains(name),
+        })
+        .collect();
+    let edges = graph
+        .edges()
+        .into_iter()
+        .filter(|(from, to)| names.contains(from) && names.contains(to))
+        .map(|(from, to)| GraphEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+            in_cycle: cycle_edges.contains(&(from, to)),
+        })
+        .collect();
+
+    Ok(GraphView {
+        focus: focus.map(str::to_string),
+        nodes,
+        edges,
+        cycles: cycles.into_iter().collect(),
+    })
+}
+
+/// Render as a Graphviz digraph; cycles are red, the focused project bold.
+pub fn render_dot(view: &GraphView) -> String {
+    let mut out = String::from("digraph meta {\n    rankdir=LR;\n    node [shape=box];\n");
+    for node in &view.nodes {
+        let mut attrs = Vec::new();
+        if node.in_cycle {
+            attrs.push("color=red");
+        }
+        if view.focus.as_deref() == Some(node.name.as_str()) {
+            attrs.push("style=bold");
+        }
+        if attrs.is_empty() {
+            out.push_str(&format!("    {:?};\n", node.name));
+        } else {
+            out.push_str(&format!("    {:?} [{}];\n", node.name, attrs.join(", ")));
+        }
+    }
+    for edge in &view.edges {
+        let attrs = if edge.in_cycle { " [color=red]" } else { "" };
+        out.push_str(&format!("    {:?} -> {:?}{attrs};\n", edge.from, edge.to));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render as a Mermaid flowchart; cycles are red, the focused project bold.
+pub fn render_mermaid(view: &GraphView) -> String {
+    // Project names may contain characters Mermaid ids can't, so use indices
+    let id = |name: &str| {
+        view.nodes
+            .iter()
+            .position(|n| n.name == name)
+            .map(|i| format!("n{i}"))
+            .unwrap_or_default()
+    };
+
+    let mut out = String::from("graph LR\n");
+    for (i, node) in view.nodes.iter().enumerate() {
+        out.push_str(&format!(
+            "    n{i}[\"{}\"]\n",
+            node.name.replace('"', "#quot;")
+        ));
+    }
+    for edge in &view.edges {
+        out.push_str(&format!("    {} --> {}\n", id(&edge.from), id(&edge.to)));
+    }
+
+    let cycle_links: Vec<String> = view
+        .edges
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.in_cycle)
+        .map(|(i, _)| i.to_string())
+        .collect();
+    if !cycle_links.is_empty() {
+        out.push_str(&format!(
+            "    linkStyle {} stroke:red,stroke-width:2px\n",
+            cycle_links.join(",")
+        ));
+    }
+    let cycle_nodes: Vec<String> = view
+        .nodes
+        .iter()
+        .filter(|n| n.in_cycle)
+        .map(|n| id(&n.name))
+        .collect();
+    if !cycle_nodes.is_empty() {
+        out.push_str("    classDef cycle stroke:red,stroke-width:2px\n");
+        out.push_str(&format!("    class {} cycle\n", cycle_nodes.join(",")));
+    }
+    if let Some(focus) = &view.focus {
+        out.push_str("    classDef focus font-weight:bold\n");
+        out.push_str(&format!("    class {} focus\n", id(focus)));
+    }
+    out
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+/// Rotate a cycle to start at its smallest name, so the same cycle found
+/// from different starting points compares equal.
+fn normalize_cycle(mut cycle: Vec<String>) -> Vec<String> {
+    if let Some(start) = (0..cycle.len()).min_by_key(|&i| &cycle[i]) {
+        cycle.rotate_left(start);
+    }
+    cycle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dependency_graph::ProjectDependencies;
+
+    fn project(name: &str, depends_on: &[&str]) -> ProjectDependencies {
+        ProjectDependencies {
+            name: name.to_string(),
+            path: name.to_string(),
+            repo: None,
+            tags: vec![],
+            provides: vec![],
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    fn graph() -> DependencyGraph {
+        DependencyGraph::build(vec![
+            project("web", &["api"]),
+            project("api", &["auth", "utils"]),
+            project("auth", &["utils"]),
+            project("utils", &[]),
+            project("docs", &[]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn renders_the_whole_graph_as_dot() {
+        let view = build_view(&graph(), None).unwrap();
+        assert!(view.cycles.is_empty());
+        assert_eq!(view.nodes.len(), 5);
+
+        let dot = render_dot(&view);
+        assert!(dot.starts_with("digraph meta {\n"));
+        assert!(dot.contains("    \"docs\";\n"));
+        assert!(dot.contains("    \"web\" -> \"api\";\n"));
+        assert!(!dot.contains("color=red"));
+    }
+
+    #[test]
+    fn focus_keeps_the_transitive_neighborhood() {
+        let view = build_view(&graph(), Some("auth")).unwrap();
+        let names: Vec<&str> = view.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["api", "auth", "utils", "web"]);
+        assert_eq!(view.edges.len(), 4);
+
+        let mermaid = render_mermaid(&view);
+        assert!(mermaid.contains("    n1[\"auth\"]\n"));
+        assert!(mermaid.contains("    n3 --> n0\n"));
+        assert!(mermaid.ends_with("    class n1 focus\n"), "{mermaid}");
+
+        assert!(build_view(&graph(), Some("nope")).is_err());
+    }
+
+    #[test]
+    fn cycles_are_highlighted() {
+        let graph = DependencyGraph::build(vec![
+            project("a", &["b"]),
+            project("b", &["c"]),
+            project("c", &["a"]),
+            project("d", &["a"]),
+        ])
+        .unwrap();
+        let view = build_view(&graph, None).unwrap();
+        assert_eq!(view.cycles, vec![vec!["a", "b", "c"]]);
+        let in_cycle: Vec<bool> = view.edges.iter().map(|e| e.in_cycle).collect();
+        // a->b, b->c, c->a, d->a
+        assert_eq!(in_cycle, [true, true, true, false]);
+        assert!(!view.nodes[3].in_cycle);
+
+        assert!(render_dot(&view).contains("    \"c\" -> \"a\" [color=red];\n"));
+        let mermaid = render_mermaid(&view);
+        assert!(mermaid.contains("    linkStyle 0,1,2 stroke:red,stroke-width:2px\n"));
+        assert!(mermaid.contains("    class n0,n1,n2 cycle\n"));
+
+        let json = serde_json::to_value(&view).unwrap();
+        assert!(json.get("focus").is_none());
+        assert_eq!(json["cycles"][0][2], "c");
+    }
+}
@@ -0,0 +1,254 @@
+//! `meta grep`: search every project at once.
+//!
+//! Each repo is searched with `git grep` in parallel, so tracked and
+//! untracked files are searched and anything `.gitignore` excludes is
+//! skipped. A project that is not a git repo is searched with
+//! `git grep --no-index`, which honours its `.gitignore` files too.
+//! Matches are printed as `<repo>:<file>:<line>:<text>`, or one
+//! `<repo>:<file>` per file with `--files-with-matches`.
+
+use anyhow::{Context, Result};
+use colored::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::error::MetaError;
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    pub ignore_case: bool,
+    /// Treat the pattern as a literal string rather than a regex
+    pub fixed_strings: bool,
+    /// Match whole words only
+    pub word: bool,
+    /// List matching files instead of matching lines
+    pub files_with_matches: bool,
+    /// Limit the search to these paths inside each repo
+    pub paths: Vec<String>,
+}
+
+/// A project to search.
+#[derive(Debug, Clone)]
+pub struct GrepTarget {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrepMatch {
+    pub repo: String,
+    /// Relative to the repo
+    pub file: String,
+    /// `None` with `--files-with-matches`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Search every target in parallel, returning matches in target order.
+pub fn search(
+    targets: &[GrepTarget],
+    pattern: &str,
+    options: &GrepOptions,
+) -> Result<Vec<GrepMatch>> {
+    let results: Vec<Result<Vec<GrepMatch>>> = targets
+        .par_iter()
+        .map(|target| search_repo(target, pattern, options))
+        .collect();
+    let mut matches = Vec::new();
+    for result in results {
+        matches.extend(result?);
+    }
+    Ok(matches)
+}
+
+/// Entry point for `meta grep`. Fails with `NO_MATCHES` when nothing
+/// matched, much like grep exiting 1.
+pub fn handle_grep(
+    targets: &[GrepTarget],
+    pattern: &str,
+    options: &GrepOptions,
+    json: bool,
+) -> Result<()> {
+    let matches = search(targets, pattern, options)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+    } else {
+        print!("{}", format_matches(&matches));
+    }
+    if matches.is_empty() {
+        return Err(MetaError::NoMatches(format!("No matches for '{pattern}'")).into());
+    }
+    Ok(())
+}
+
+/// One line per match (or per file), prefixed with the repo name.
+pub fn format_matches(matches: &[GrepMatch]) -> String {
+    let mut out = String::new();
+    for m in matches {
+        let location = format!("{}:{}", m.repo.cyan().bold(), m.file.magenta());
+        match (m.line, &m.text) {
+            (Some(line), Some(text)) => {
+                out.push_str(&format!("{location}:{}:{text}\n", line.to_string().green()))
+            }
+            _ => out.push_str(&format!("{location}\n")),
+        }
+    }
+    out
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn search_repo(
+    target: &GrepTarget,
+    pattern: &str,
+    options: &GrepOptions,
+) -> Result<Vec<GrepMatch>> {
+    let output = Command::new("git")
+        .args(grep_args(&target.dir, pattern, options))
+        .current_dir(&target.dir)
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run git grep")?;
+    // 1 means no matches
+    if !output.status.success() && output.status.code() != Some(1) {
+        anyhow::bail!(
+            "git grep failed in {}: {}",
+            target.name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(parse_output(
+        &target.name,
+        &String::from_utf8_lossy(&output.stdout),
+        options.files_with_matches,
+    ))
+}
+
+fn grep_args(dir: &Path, pattern: &str, options: &GrepOptions) -> Vec<String> {
+    let mut args: Vec<String> = ["grep", "-z", "-I", "--no-color", "--full-name"]
+        .map(String::from)
+        .to_vec();
+    if dir.join(".git").exists() {
+        args.push("--untracked".to_string());
+    } else {
+        args.extend(["--no-index", "--exclude-standard"].map(String::from));
+    }
+    args.push(
+        if options.files_with_matches {
+            "-l"
+        } else {
+            "-n"
+        }
+        .to_string(),
+    );
+    args.push(if options.fixed_strings { "-F" } else { "-E" }.to_string());
+    if options.ignore_case {
+        args.push("-i".to_string());
+    }
+    if options.word {
+        args.push("-w".to_string());
+    }
+    args.extend(["-e".to_string(), pattern.to_string(), "--".to_string()]);
+    args.extend(options.paths.iter().cloned());
+    args
+}
+
+/// Parse `git grep -z` output: `file\0line\0text` per line, or `file\0`
+/// per file with `-l`.
+fn parse_output(repo: &str, stdout: &str, files_only: bool) -> Vec<GrepMatch> {
+    if files_only {
+        return stdout
+            .split('\0')
+            .map(|f| f.trim_start_matches('\n'))
+            .filter(|f| !f.is_empty())
+            .map(|file| GrepMatch {
+                repo: repo.to_string(),
+                file: file.to_string(),
+                line: None,
+                text: None,
+            })
+            .collect();
+    }
+    stdout
+        .lines()
+        .filter_map(|record| {
+            let mut fields = record.splitn(3, '\0');
+            let file = fields.next()?;
+            let line = fields.next()?.parse().ok()?;
+            Some(GrepMatch {
+                repo: repo.to_string(),
+                file: file.to_string(),
+                line: Some(line),
+                text: Some(fields.next().unwrap_or_default().to_string()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::{git, testing::init_repo};
+
+    #[test]
+    fn searches_repos_and_skips_ignored_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let api = tmp.path().join("api");
+        std::fs::create_dir_all(api.join("src")).unwrap();
+        init_repo(&api);
+        std::fs::write(api.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(api.join("src/lib.rs"), "fn old_name() {}\nfn other() {}\n").unwrap();
+        std::fs::create_dir_all(api.join("target")).unwrap();
+        std::fs::write(api.join("target/gen.rs"), "old_name").unwrap();
+        git(&api, &["add", "src/lib.rs"]).unwrap();
+        std::fs::write(api.join("notes: draft.md"), "call old_name()\n").unwrap();
+
+        let docs = tmp.path().join("docs");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(docs.join("guide.md"), "Use OLD_NAME here\n").unwrap();
+
+        let targets = vec![
+            GrepTarget {
+                name: "api".to_string(),
+                dir: api,
+            },
+            GrepTarget {
+                name: "docs".to_string(),
+                dir: docs,
+            },
+        ];
+        let matches = search(&targets, "old_name", &GrepOptions::default()).unwrap();
+        let found: Vec<(&str, &str, Option<usize>)> = matches
+            .iter()
+            .map(|m| (m.repo.as_str(), m.file.as_str(), m.line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("api", "notes: draft.md", Some(1)),
+                ("api", "src/lib.rs", Some(1))
+            ]
+        );
+        assert_eq!(matches[1].text.as_deref(), Some("fn old_name() {}"));
+
+        let options = GrepOptions {
+            ignore_case: true,
+            files_with_matches: true,
+            ..Default::default()
+        };
+        let matches = search(&targets, "old_name", &options).unwrap();
+        let text = console::strip_ansi_codes(&format_matches(&matches)).to_string();
+        assert_eq!(text, "api:notes: draft.md\napi:src/lib.rs\ndocs:guide.md\n");
+
+        let err = search(&targets, "(", &GrepOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("git grep failed in api"), "{err}");
+    }
+}
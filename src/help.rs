@@ -0,0 +1,228 @@
+//! Long-form help topics for `meta help <topic>`.
+//!
+//! Built-in topics cover concepts that span several commands; plugins add
+//! their own through `help.topics` in `--meta-plugin-info`. Long text is
+//! shown through a pager when stdout is a terminal.
+
+use console::Term;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// One help topic.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct Topic {
+    pub name: String,
+    /// One line, shown in the topic list.
+    pub summary: String,
+    pub body: String,
+}
+
+/// Topics that ship with meta.
+pub fn builtin_topics() -> Vec<Topic> {
+    let topic = |name: &str, summary: &str, body: &str| Topic {
+        name: name.to_string(),
+        summary: summary.to_string(),
+        body: body.trim_start_matches('\n').to_string(),
+    };
+    vec![
+        topic(
+            "config",
+            "The .meta file: projects, tags, defaults and tasks",
+            r#"
+A workspace is described by a .meta file (JSON, or .meta.yaml/.meta.toml)
+at its root:
+
+    {
+      "projects": {
+        "api": "git@github.com:acme/api.git",
+        "web": { "repo": "git@github.com:acme/web.git", "tags": ["frontend"] }
+      },
+      "defaults": { "parallel": true, "jobs": 8, "timeout": "10m" },
+      "tasks": { "ci": "cargo test --workspace" }
+    }
+
+Projects are either a repo URL or an object with repo, path, tags,
+provides, depends_on and meta. Run `meta config validate` to check the
+file against the schema.
+"#,
+        ),
+        topic(
+            "filters",
+            "Selecting repos with --tag, --include and --exclude",
+            r#"
+Most commands run in every project. Narrow the set with:
+
+    --tag backend,api        projects with any of these tags
+    --include services/*     substring, glob, re:<regex> or tag:<name>
+    --exclude docs           same patterns, removed from the set
+
+Filters combine: tags and includes select, excludes remove. Use
+--dry-run to see which repos a command would touch.
+"#,
+        ),
+        topic(
+            "output",
+            "Output modes: grouped, --stream, --ui tui, --summary-only, JSON",
+            r#"
+Command output is grouped per repo by default, printed as each repo
+finishes, followed by a summary.
+
+    --stream          live output, each line prefixed with the repo name
+    --ui tui          a live per-repo progress view
+    --summary-only    only a repo × result matrix
+    --json            one JSON document at the end
+    --output ndjson   one JSON event per line
+
+The exit status is non-zero when any repo fails (see exit-codes).
+"#,
+        ),
+        topic(
+            "exit-codes",
+            "Exit codes and the error codes in --json output",
+            &crate::error::exit_codes_help(),
+        ),
+        topic(
+            "looprc",
+            "Per-directory .looprc files: aliases, shell, env and skip",
+            r#"
+A .looprc in a project directory customizes how commands run there:
+
+    {
+      "aliases": { "t": "cargo test --workspace" },
+      "shell": "bash -lc",
+      "env": { "RUST_LOG": "debug" },
+      "skip": ["npm", "yarn"]
+    }
+
+skip is true (never run here) or a list of programs to skip.
+"#,
+        ),
+        topic(
+            "plugins",
+            "How plugins extend meta and how to manage them",
+            r#"
+Plugins are executables named meta-<name>, found in .meta/plugins/,
+~/.meta/plugins/ and PATH, in that order (see plugin_priority in .meta).
+
+    meta plugin list                 installed plugins
+    meta plugin install <name>       from the registry, user/repo, a URL,
+                                     --path or --git
+    meta plugin outdated             available upgrades
+    meta plugin doctor               broken, shadowed or conflicting plugins
+    meta plugin lock / sync          pin plugins in .meta/plugins.lock
+
+Plugins may add their own topics to `meta help`.
+"#,
+        ),
+    ]
+}
+
+/// Find a topic by exact name, ignoring case.
+pub fn find<'a>(topics: &'a [Topic], name: &str) -> Option<&'a Topic> {
+    topics.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+}
+
+/// The "Help topics:" section of `meta help`. `plugin_topics` pairs each
+/// topic with the plugin that registered it; built-in names take precedence.
+pub fn render_topic_list(builtin: &[Topic], plugin_topics: &[(String, Topic)]) -> String {
+    let mut rows: Vec<(&str, String)> = builtin
+        .iter()
+        .map(|t| (t.name.as_str(), t.summary.clone()))
+        .collect();
+    for (plugin, topic) in plugin_topics {
+        if find(builtin, &topic.name).is_none() {
+            rows.push((&topic.name, format!("{} [plugin: {plugin}]", topic.summary)));
+        }
+    }
+    rows.sort_by(|a, b| a.0.cmp(b.0));
+
+    let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+    let mut out = String::from("Help topics:\n");
+    for (name, summary) in rows {
+        out.push_str(&format!("  {name:<width$}  {summary}\n"));
+    }
+    out.push_str("\nRun 'meta help <topic>' or 'meta help <command>' for details.\n");
+    out
+}
+
+/// Print `text` to stdout, through a pager when stdout is a terminal and
+/// the text does not fit on one screen. The pager is `$META_PAGER`, then
+/// `$PAGER`, then `less -FRX`; if it cannot be started the text is printed.
+pub fn page(text: &str, use_pager: bool) {
+    let term = Term::stdout();
+    let fits = text.lines().count() < usize::from(term.size().0);
+    if !use_pager || !term.is_term() || fits || !spawn_pager(text) {
+        print!("{text}");
+        if !text.ends_with('\n') {
+            println!();
+        }
+    }
+}
+
+fn spawn_pager(text: &str) -> bool {
+    let spec = std::env::var("META_PAGER")
+        .or_else(|_| std::env::var("PAGER"))
+        .unwrap_or_else(|_| "less -FRX".to_string());
+    let mut parts = spec.split_whitespace();
+    let Some(program) = parts.next() else {
+        return false;
+    };
+    let Ok(mut child) = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closing early (q) is not an error
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    let _ = child.wait();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_topics_are_unique_and_findable() {
+        let topics = builtin_topics();
+        let mut names: Vec<_> = topics.iter().map(|t| t.name.as_str()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), topics.len());
+        assert_eq!(find(&topics, "Filters").unwrap().name, "filters");
+        assert!(find(&topics, "nope").is_none());
+        assert!(topics.iter().all(|t| !t.body.starts_with('\n')));
+    }
+
+    #[test]
+    fn topic_list_marks_plugin_topics_and_keeps_builtins() {
+        let plugin = |name: &str| Topic {
+            name: name.to_string(),
+            summary: format!("{name} from a plugin"),
+            body: String::new(),
+        };
+        let list = render_topic_list(
+            &builtin_topics(),
+            &[
+                ("mig".to_string(), plugin("migrations")),
+                ("mig".to_string(), plugin("config")),
+            ],
+        );
+        assert!(list.contains("  migrations  migrations from a plugin [plugin: mig]\n"));
+        assert!(list.contains("  config      The .meta file"));
+        assert!(!list.contains("config from a plugin"));
+        let names: Vec<_> = list
+            .lines()
+            .skip(1)
+            .take_while(|l| !l.is_empty())
+            .map(|l| l.split_whitespace().next().unwrap())
+            .collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+    }
+}
@@ -0,0 +1,335 @@
+//! Run history for `meta history`.
+//!
+//! Every multi-repo run through the runner (`meta exec`, `meta run`, git
+//! commands) adds a record to `~/.meta/history.json` with the
+//! invocation, the filters in effect, and each repo's command, status, exit
+//! code and duration. `meta commit` records its commits the same way, with
+//! each repo's commit hash. `meta history rerun <id>` replays the exact
+//! command set of a recorded run. Only the newest [`MAX_ENTRIES`] runs are
+//! kept.
+
+use anyhow::{Context, Result};
+use colored::*;
+use loop_lib::DirCommand;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::runner::{RepoStatus, RunReport};
+
+/// Number of runs kept in the history file.
+pub const MAX_ENTRIES: usize = 500;
+
+/// One recorded run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub id: u64,
+    /// RFC 3339 start time.
+    pub started_at: String,
+    /// Directory meta was invoked from.
+    pub cwd: String,
+    /// Arguments after `meta`.
+    pub args: Vec<String>,
+    /// Selection options in effect, e.g. `--tag backend`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+    pub success: bool,
+    pub duration_ms: u64,
+    pub repos: Vec<RepoRecord>,
+}
+
+/// One repo's part of a recorded run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoRecord {
+    pub name: String,
+    pub directory: String,
+    pub command: String,
+    pub status: RepoStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// The commit made by `meta commit`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+impl RunRecord {
+    /// Build a record (with id 0, assigned on append) from a finished run.
+    pub fn from_report(
+        report: &RunReport,
+        args: Vec<String>,
+        filters: Vec<String>,
+        duration: Duration,
+    ) -> Self {
+        RunRecord {
+            id: 0,
+            started_at: (chrono::Local::now() - duration).to_rfc3339(),
+            cwd: std::env::current_dir()
+                .map(|d| d.display().to_string())
+                .unwrap_or_default(),
+            args,
+            filters,
+            success: report.is_success(),
+            duration_ms: millis(duration),
+            repos: report
+                .results
+                .iter()
+                .map(|r| RepoRecord {
+                    name: r.name.clone(),
+                    directory: r.directory.display().to_string(),
+                    command: r.command.clone(),
+                    status: r.status,
+                    exit_code: r.exit_code,
+                    duration_ms: millis(r.duration),
+                    commit: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// The commands to replay this run, optionally only the repos that did
+    /// not succeed.
+    pub fn commands(&self, failed_only: bool) -> Vec<DirCommand> {
+        self.repos
+            .iter()
+            .filter(|r| !failed_only || r.status != RepoStatus::Ok)
+            .map(|r| DirCommand {
+                dir: r.directory.clone(),
+                cmd: r.command.clone(),
+                env: None,
+            })
+            .collect()
+    }
+
+    fn count(&self, status: RepoStatus) -> usize {
+        self.repos.iter().filter(|r| r.status == status).count()
+    }
+}
+
+fn millis(d: Duration) -> u64 {
+    u64::try_from(d.as_millis()).unwrap_or(u64::MAX)
+}
+
+// ── Storage ─────────────────────────────────────────────
+
+/// The history file: recorded runs, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct History {
+    #[serde(default)]
+    runs: Vec<RunRecord>,
+}
+
+fn history_path() -> PathBuf {
+    meta_core::data_dir::data_file("history")
+}
+
+fn lock_path() -> PathBuf {
+    meta_core::meta_dir().join("history.lock")
+}
+
+/// Load every record, oldest first.
+pub fn load_from(path: &Path) -> Result<Vec<RunRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let history: History = meta_core::store::read(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(history.runs)
+}
+
+/// Append a record, assigning the next id and trimming old entries.
+/// Returns the assigned id.
+pub fn append_to(path: &Path, lock: &Path, mut record: RunRecord) -> Result<u64> {
+    let mut id = 0;
+    meta_core::store::update(path, lock, |history: &mut History| {
+        id = history.runs.last().map_or(1, |r| r.id + 1);
+        record.id = id;
+        let excess = (history.runs.len() + 1).saturating_sub(MAX_ENTRIES);
+        history.runs.drain(..excess);
+        history.runs.push(record.clone());
+    })?;
+    Ok(id)
+}
+
+/// Record a run in `~/.meta/history.json`.
+pub fn record(record: RunRecord) -> Result<u64> {
+    meta_core::data_dir::ensure_meta_dir()?;
+    if let Err(e) = crate::metrics::record_run(&record) {
+        log::warn!("Failed to update metrics: {e:#}");
+    }
+    append_to(&history_path(), &lock_path(), record)
+}
+
+/// Look up a recorded run by id.
+pub fn find(id: u64) -> Result<RunRecord> {
+    load_from(&history_path())?
+        .into_iter()
+        .find(|r| r.id == id)
+        .with_context(|| format!("No run with id {id} in history (see 'meta history list')"))
+}
+
+// ── meta history list / show ────────────────────────────
+
+/// Print the most recent runs, newest first.
+pub fn handle_list(limit: usize, json: bool) -> Result<()> {
+    let records = load_from(&history_path())?;
+    let recent: Vec<&RunRecord> = records.iter().rev().take(limit).collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&recent)?);
+        return Ok(());
+    }
+    if recent.is_empty() {
+        println!("No runs recorded yet.");
+        return Ok(());
+    }
+
+    println!(
+        "  {:>4}  {:<16}  {:<14}  {}",
+        "ID".bold(),
+        "WHEN".bold(),
+        "RESULT".bold(),
+        "COMMAND".bold()
+    );
+    for record in recent {
+        let result = if record.success {
+            format!("✓ {} ok", record.repos.len()).green()
+        } else {
+            let failed = record.repos.len() - record.count(RepoStatus::Ok);
+            format!("✗ {failed}/{} failed", record.repos.len()).red()
+        };
+        println!(
+            "  {:>4}  {:<16}  {:<14}  meta {}",
+            record.id,
+            format_time(&record.started_at),
+            result,
+            record.args.join(" ")
+        );
+    }
+    Ok(())
+}
+
+/// Print one run with its per-repo results.
+pub fn handle_show(id: u64, json: bool) -> Result<()> {
+    let record = find(id)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&record)?);
+        return Ok(());
+    }
+
+    println!("{} {}", "Run".bold(), record.id.to_string().bold());
+    println!("  Command:   meta {}", record.args.join(" "));
+    println!("  Started:   {}", format_time(&record.started_at));
+    println!("  Directory: {}", record.cwd);
+    if !record.filters.is_empty() {
+        println!("  Filters:   {}", record.filters.join(", "));
+    }
+    println!(
+        "  Duration:  {:.1}s",
+        Duration::from_millis(record.duration_ms).as_secs_f64()
+    );
+    println!();
+
+    let width = record.repos.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    for repo in &record.repos {
+        let mark = match repo.status {
+            RepoStatus::Ok => "✓".green(),
+            RepoStatus::Skipped => "-".dimmed(),
+            _ => "✗".red(),
+        };
+        let detail = match (repo.status, repo.exit_code, &repo.commit) {
+            (RepoStatus::Failed, Some(code), _) => format!("exit {code}"),
+            (RepoStatus::Ok, _, Some(sha)) => sha.chars().take(7).collect(),
+            (status, _, _) => status.label().to_string(),
+        };
+        println!(
+            "  {mark} {:<width$}  {detail:<9}  {:>7}ms  {}",
+            repo.name,
+            repo.duration_ms,
+            repo.command.dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// `2026-10-14T09:30:12.123+02:00` → `2026-10-14 09:30`.
+fn format_time(rfc3339: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(rfc3339)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| rfc3339.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::RepoResult;
+
+    fn report() -> RunReport {
+        let result = |name: &str, status, exit_code| RepoResult {
+            directory: PathBuf::from(format!("/ws/{name}")),
+            name: name.to_string(),
+            command: "make".to_string(),
+            status,
+            exit_code,
+            duration: Duration::from_millis(20),
+            stdout: "not recorded".to_string(),
+            stderr: String::new(),
+        };
+        RunReport {
+            results: vec![
+                result("api", RepoStatus::Ok, Some(0)),
+                result("web", RepoStatus::Failed, Some(2)),
+            ],
+        }
+    }
+
+    fn record() -> RunRecord {
+        RunRecord::from_report(
+            &report(),
+            vec!["exec".to_string(), "make".to_string()],
+            vec!["--tag backend".to_string()],
+            Duration::from_secs(1),
+        )
+    }
+
+    #[test]
+    fn appends_with_increasing_ids() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (path, lock) = (tmp.path().join("h.json"), tmp.path().join("h.lock"));
+        assert_eq!(append_to(&path, &lock, record()).unwrap(), 1);
+        assert_eq!(append_to(&path, &lock, record()).unwrap(), 2);
+
+        let records = load_from(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].id, 2);
+        assert!(!records[0].success);
+        assert_eq!(records[0].repos[1].exit_code, Some(2));
+        assert_eq!(records[0].filters, vec!["--tag backend"]);
+    }
+
+    #[test]
+    fn trims_to_max_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (path, lock) = (tmp.path().join("h.json"), tmp.path().join("h.lock"));
+        let runs = (1..=MAX_ENTRIES as u64)
+            .map(|id| RunRecord { id, ..record() })
+            .collect();
+        meta_core::store::write_atomic(&path, &History { runs }).unwrap();
+
+        let id = append_to(&path, &lock, record()).unwrap();
+        assert_eq!(id, MAX_ENTRIES as u64 + 1);
+        let records = load_from(&path).unwrap();
+        assert_eq!(records.len(), MAX_ENTRIES);
+        assert_eq!(records[0].id, 2);
+    }
+
+    #[test]
+    fn rerun_commands_can_be_limited_to_failures() {
+        let record = record();
+        assert_eq!(record.commands(false).len(), 2);
+        let failed = record.commands(true);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].dir, "/ws/web");
+        assert_eq!(failed[0].cmd, "make");
+    }
+}
@@ -0,0 +1,644 @@
+//! Lifecycle hooks configured in `.meta`: worktree hooks under
+//! `worktree.hooks`, and `pre-exec`/`post-exec` under the top-level `hooks`,
+//! which run around every multi-repo command (`meta exec`, `meta run`, git
+//! commands).
+//!
+//! ```json
+//! "worktree": {
+//!   "hooks": {
+//!     "pre-create": "policy check-create",
+//!     "pre-destroy": { "run": "gh-open-prs --fail-if-any", "timeout": "30s" },
+//!     "post-create": ["harmony worktree on-create", "notify-team"]
+//!   }
+//! }
+//! ```
+//!
+//! A hook is a command, a list of commands run in order, or an object with
+//! `run` (either of those) and a `timeout`. Commands run through the shell
+//! in the workspace root with the event's JSON payload on stdin and
+//! `META_HOOK_EVENT` set. `pre-*` hooks run before the operation and can
+//! veto it: a non-zero exit (or timeout) aborts it, with the hook's stderr
+//! as the reason, unless the caller passes `--no-verify`. Other hooks run
+//! after the operation; their failures are printed as warnings and never
+//! block.
+//!
+//! # Payload
+//!
+//! The payload is a [`HookPayload`], versioned by `schema_version`
+//! ([`PAYLOAD_SCHEMA_VERSION`]). Fields are only ever added within a
+//! version; renames and removals bump it.
+//!
+//! ```json
+//! {
+//!   "schema_version": 2,
+//!   "event": "post-create",
+//!   "name": "auth-fix",
+//!   "path": "/ws/.worktrees/auth-fix",
+//!   "started_at": "2026-01-05T10:00:00+00:00",
+//!   "duration_ms": 1840,
+//!   "success": false,
+//!   "repos": [
+//!     { "alias": "api", "path": "/ws/.worktrees/auth-fix/api", "branch": "auth-fix",
+//!       "success": true, "duration_ms": 900 },
+//!     { "alias": "web", "path": "/ws/.worktrees/auth-fix/web", "branch": "auth-fix",
+//!       "success": false, "error": "branch already checked out", "duration_ms": 12 }
+//!   ]
+//! }
+//! ```
+//!
+//! `duration_ms` and `success` are absent for `pre-*` events, which fire
+//! before anything ran. Plugins may add event-specific fields (such as the
+//! store entry) alongside these.
+//!
+//! `pre-exec` gets `schema_version`, `event`, `cwd`, `args` (after `meta`)
+//! and `repos` (`name`, `directory`, `command`); `post-exec` gets the run as
+//! `meta history` records it: the same fields plus `started_at`,
+//! `duration_ms`, `success`, and each repo's `status`, `exit_code` and
+//! `duration_ms`.
+
+use anyhow::{Context, Result};
+use colored::*;
+use loop_lib::DirCommand;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::history::RunRecord;
+
+/// Version of the [`HookPayload`] layout.
+pub const PAYLOAD_SCHEMA_VERSION: u32 = 2;
+
+/// Events meta fires around the worktree commands.
+pub const WORKTREE_EVENTS: &[&str] = &[
+    "pre-create",
+    "post-create",
+    "pre-destroy",
+    "post-destroy",
+    "post-prune",
+    "post-rename",
+];
+
+/// Events fired around every multi-repo run.
+pub const EXEC_EVENTS: &[&str] = &["pre-exec", "post-exec"];
+
+/// How often a running hook is checked against its timeout.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How long a finished hook's stderr is still read: something it started
+/// in the background (`code . &`) can hold the pipe open indefinitely.
+const STDERR_GRACE: Duration = Duration::from_millis(200);
+
+// ── Types ───────────────────────────────────────────────
+
+/// One configured hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hook {
+    /// Run in order; a failure stops the rest
+    pub commands: Vec<String>,
+    /// Per command
+    pub timeout: Option<Duration>,
+}
+
+/// Event name → hook.
+pub type Hooks = BTreeMap<String, Hook>;
+
+/// What one hook command did.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HookResult {
+    pub event: String,
+    pub command: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+/// The JSON a hook receives on stdin; see the module docs for the schema.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookPayload {
+    pub schema_version: u32,
+    pub event: String,
+    /// Worktree set name
+    pub name: String,
+    pub path: PathBuf,
+    /// RFC 3339 time the operation started
+    pub started_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+    pub repos: Vec<RepoOutcome>,
+    /// Event-specific fields added by the caller
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// How the operation went in one repo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RepoOutcome {
+    pub alias: String,
+    pub path: PathBuf,
+    pub branch: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl HookPayload {
+    /// Payload for an event on the set `name` at `path`, started now.
+    pub fn new(event: &str, name: &str, path: &Path) -> Self {
+        Self {
+            schema_version: PAYLOAD_SCHEMA_VERSION,
+            event: event.to_string(),
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            started_at: chrono::Local::now().to_rfc3339(),
+            duration_ms: None,
+            success: None,
+            repos: Vec::new(),
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Record the operation's outcome: its repos, overall success, and the
+    /// time since `started`.
+    pub fn finish(&mut self, repos: Vec<RepoOutcome>, started: Instant) {
+        self.success = Some(repos.iter().all(|r| r.success));
+        self.repos = repos;
+        self.duration_ms = Some(millis(started.elapsed()));
+    }
+
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+}
+
+// ── Config ──────────────────────────────────────────────
+
+/// Whether `event` runs before its operation and can veto it.
+pub fn is_blocking(event: &str) -> bool {
+    event.starts_with("pre-")
+}
+
+/// The `worktree.hooks` of the workspace at `meta_dir`; empty when it has
+/// none or no config.
+pub fn load_worktree_hooks(meta_dir: &Path) -> Result<Hooks> {
+    let Some((path, _)) = config::find_meta_config_in(meta_dir) else {
+        return Ok(Hooks::new());
+    };
+    let value = config::read_meta_config_value(&path)?;
+    parse_hooks(value.get("worktree").and_then(|w| w.get("hooks")))
+        .with_context(|| format!("Invalid worktree.hooks in {}", path.display()))
+}
+
+/// The top-level `hooks` of the workspace at `meta_dir`, fired around every
+/// multi-repo run; empty when it has none or no config.
+pub fn load_exec_hooks(meta_dir: &Path) -> Result<Hooks> {
+    let Some((path, _)) = config::find_meta_config_in(meta_dir) else {
+        return Ok(Hooks::new());
+    };
+    let value = config::read_meta_config_value(&path)?;
+    parse_hooks(value.get("hooks")).with_context(|| format!("Invalid hooks in {}", path.display()))
+}
+
+/// Parse a hooks section: an object mapping event names to hooks.
+pub fn parse_hooks(section: Option<&serde_json::Value>) -> Result<Hooks> {
+    let Some(section) = section else {
+        return Ok(Hooks::new());
+    };
+    let map = section
+        .as_object()
+        .context("expected an object mapping events to commands")?;
+    map.iter()
+        .map(|(event, spec)| {
+            let hook = parse_hook(spec).with_context(|| format!("Invalid hook '{event}'"))?;
+            Ok((event.clone(), hook))
+        })
+        .collect()
+}
+
+/// A command, a list of commands, or `{"run": ..., "timeout": ...}`.
+pub fn parse_hook(spec: &serde_json::Value) -> Result<Hook> {
+    use serde_json::Value;
+
+    let commands = |value: &Value| -> Result<Vec<String>> {
+        match value {
+            Value::String(command) => Ok(vec![command.clone()]),
+            Value::Array(items) => items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .map(str::to_string)
+                        .context("expected a list of command strings")
+                })
+                .collect(),
+            _ => anyhow::bail!("expected a command or a list of commands"),
+        }
+    };
+    match spec {
+        Value::Object(fields) => {
+            if let Some(key) = fields
+                .keys()
+                .find(|k| !["run", "timeout"].contains(&k.as_str()))
+            {
+                anyhow::bail!("unknown key '{key}' (expected run or timeout)");
+            }
+            let run = fields.get("run").context("missing 'run'")?;
+            let timeout = match fields.get("timeout") {
+                Some(t) => Some(
+                    config::parse_timeout(t)
+                        .context("timeout must be seconds or a duration like \"30s\"")?,
+                ),
+                None => None,
+            };
+            Ok(Hook {
+                commands: commands(run)?,
+                timeout,
+            })
+        }
+        other => Ok(Hook {
+            commands: commands(other)?,
+            timeout: None,
+        }),
+    }
+}
+
+// ── Running ─────────────────────────────────────────────
+
+/// Run the hook for `event`, if one is configured, in `dir` with `payload`
+/// on stdin, one command after another until one fails.
+///
+/// A failing blocking hook is an error naming the hook's reason, so the
+/// caller aborts; with `no_verify`, blocking hooks are skipped entirely.
+/// A failing non-blocking hook only prints a warning.
+pub fn fire(
+    hooks: &Hooks,
+    event: &str,
+    payload: &serde_json::Value,
+    dir: &Path,
+    no_verify: bool,
+) -> Result<Vec<HookResult>> {
+    let Some(hook) = hooks.get(event) else {
+        return Ok(Vec::new());
+    };
+    let blocking = is_blocking(event);
+    if blocking && no_verify {
+        return Ok(Vec::new());
+    }
+    let mut results = Vec::new();
+    for command in &hook.commands {
+        let result = run_hook(event, command, payload, dir, hook.timeout)?;
+        let failed = !result.success;
+        if failed {
+            let reason = failure_reason(&result, hook.timeout);
+            if blocking {
+                return Err(crate::error::MetaError::HookRejected {
+                    event: event.to_string(),
+                    reason,
+                }
+                .into());
+            }
+            eprintln!(
+                "{} {event} hook failed: {reason}",
+                "warning:".yellow().bold()
+            );
+        }
+        results.push(result);
+        if failed {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+/// Run one hook command through the shell and wait for it, killing it
+/// (and anything it started) after `timeout`.
+pub fn run_hook(
+    event: &str,
+    command: &str,
+    payload: &serde_json::Value,
+    dir: &Path,
+    timeout: Option<Duration>,
+) -> Result<HookResult> {
+    let config_path = config::find_meta_config_in(dir).map(|(path, _)| path);
+    let shell = config::load_meta_shell(config_path.as_deref()).unwrap_or_else(|| {
+        let (shell, flag) = crate::runner::shell_and_flag();
+        vec![shell, flag.to_string()]
+    });
+    let mut cmd = Command::new(&shell[0]);
+    cmd.args(&shell[1..])
+        .arg(command)
+        .current_dir(dir)
+        .env("META_HOOK_EVENT", event)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    if timeout.is_some() {
+        use std::os::unix::process::CommandExt;
+        // So a timeout can take down what the hook started too
+        cmd.process_group(0);
+    }
+    let start = Instant::now();
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to run {event} hook '{command}'"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // On its own thread, so a hook that never reads a payload larger
+        // than the pipe can still time out; one that exits closes the pipe
+        let payload = payload.to_string();
+        std::thread::spawn(move || {
+            let _ = writeln!(stdin, "{payload}");
+        });
+    }
+
+    let stderr = Arc::new(Mutex::new(Vec::new()));
+    let (eof_tx, eof_rx) = mpsc::channel();
+    if let Some(mut pipe) = child.stderr.take() {
+        let stderr = Arc::clone(&stderr);
+        std::thread::spawn(move || {
+            let mut chunk = [0u8; 4096];
+            while let Ok(n @ 1..) = pipe.read(&mut chunk) {
+                stderr.lock().unwrap().extend_from_slice(&chunk[..n]);
+            }
+            let _ = eof_tx.send(());
+        });
+    }
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if timeout.is_some_and(|t| start.elapsed() >= t) {
+            crate::runner::kill_group(&mut child);
+            let _ = child.wait();
+            timed_out = true;
+            break None;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    let _ = eof_rx.recv_timeout(STDERR_GRACE);
+    let stderr = String::from_utf8_lossy(&stderr.lock().unwrap()).to_string();
+    Ok(HookResult {
+        event: event.to_string(),
+        command: command.to_string(),
+        success: status.is_some_and(|s| s.success()),
+        exit_code: status.and_then(|s| s.code()),
+        timed_out,
+        stderr,
+        duration_ms: millis(start.elapsed()),
+    })
+}
+
+/// `pre-exec` payload: the commands about to run.
+pub fn pre_exec_payload(args: &[String], commands: &[DirCommand]) -> serde_json::Value {
+    let repos: Vec<serde_json::Value> = commands
+        .iter()
+        .map(|c| {
+            let name = Path::new(&c.dir)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| c.dir.clone());
+            serde_json::json!({"name": name, "directory": c.dir, "command": c.cmd})
+        })
+        .collect();
+    serde_json::json!({
+        "schema_version": PAYLOAD_SCHEMA_VERSION,
+        "event": "pre-exec",
+        "cwd": std::env::current_dir().unwrap_or_default(),
+        "args": args,
+        "repos": repos,
+    })
+}
+
+/// `post-exec` payload: the finished run as history records it.
+pub fn post_exec_payload(record: &RunRecord) -> serde_json::Value {
+    let mut value = serde_json::json!({
+        "schema_version": PAYLOAD_SCHEMA_VERSION,
+        "event": "post-exec",
+    });
+    if let (Some(payload), serde_json::Value::Object(run)) = (
+        value.as_object_mut(),
+        serde_json::to_value(record).unwrap_or_default(),
+    ) {
+        // The id is only assigned once the record is appended
+        payload.extend(run.into_iter().filter(|(key, _)| key != "id"));
+    }
+    value
+}
+
+fn failure_reason(result: &HookResult, timeout: Option<Duration>) -> String {
+    if result.timed_out {
+        let secs = timeout.map_or(0.0, |t| t.as_secs_f64());
+        return format!("timed out after {secs}s");
+    }
+    match result.stderr.trim() {
+        "" => match result.exit_code {
+            Some(code) => format!("exited with status {code}"),
+            None => "killed by a signal".to_string(),
+        },
+        stderr => stderr.to_string(),
+    }
+}
+
+fn millis(d: Duration) -> u64 {
+    u64::try_from(d.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn hooks(pairs: &[(&str, serde_json::Value)]) -> Hooks {
+        pairs
+            .iter()
+            .map(|(e, spec)| (e.to_string(), parse_hook(spec).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn pre_hooks_veto_and_post_hooks_only_warn() {
+        let tmp = tempfile::tempdir().unwrap();
+        let hooks = hooks(&[
+            ("pre-destroy", json!("echo 'open PR #12' >&2; exit 1")),
+            ("pre-create", json!("cat > payload.json")),
+            ("post-destroy", json!(["exit 3", "touch never-ran"])),
+        ]);
+        let payload = json!({"name": "auth-fix"});
+
+        let err = fire(&hooks, "pre-destroy", &payload, tmp.path(), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "pre-destroy hook rejected the operation: open PR #12 (use --no-verify to skip)"
+        );
+        assert!(fire(&hooks, "pre-destroy", &payload, tmp.path(), true)
+            .unwrap()
+            .is_empty());
+
+        let results = fire(&hooks, "pre-create", &payload, tmp.path(), false).unwrap();
+        assert!(results[0].success);
+        let written = std::fs::read_to_string(tmp.path().join("payload.json")).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&written).unwrap(),
+            payload
+        );
+
+        let results = fire(&hooks, "post-destroy", &payload, tmp.path(), false).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].exit_code, Some(3));
+        assert!(!tmp.path().join("never-ran").exists());
+        assert!(fire(&hooks, "post-prune", &payload, tmp.path(), false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn hooks_time_out() {
+        let tmp = tempfile::tempdir().unwrap();
+        let hooks = hooks(&[(
+            "pre-create",
+            json!({"run": ["true", "sleep 5"], "timeout": 0.2}),
+        )]);
+        let start = Instant::now();
+        let err = fire(&hooks, "pre-create", &json!({}), tmp.path(), false).unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(4));
+        assert!(err.to_string().contains("timed out after 0.2s"), "{err}");
+    }
+
+    #[test]
+    fn hooks_that_leave_something_running_dont_hang() {
+        let tmp = tempfile::tempdir().unwrap();
+        let start = Instant::now();
+        let result = run_hook(
+            "post-create",
+            "echo opening >&2; sleep 5 &",
+            &json!({}),
+            tmp.path(),
+            None,
+        )
+        .unwrap();
+        assert!(result.success);
+        assert_eq!(result.stderr.trim(), "opening");
+
+        // A payload the pipe can't hold, for a hook that never reads it
+        let payload = json!({"padding": "x".repeat(1 << 20)});
+        let result = run_hook(
+            "pre-create",
+            "sleep 5",
+            &payload,
+            tmp.path(),
+            Some(Duration::from_millis(200)),
+        )
+        .unwrap();
+        assert!(result.timed_out);
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn parses_hook_specs() {
+        let parsed = parse_hooks(Some(&json!({
+            "pre-create": "check",
+            "post-create": {"run": ["a", "b"], "timeout": "30s"}
+        })))
+        .unwrap();
+        assert_eq!(parsed["pre-create"].commands, vec!["check"]);
+        assert_eq!(parsed["post-create"].commands, vec!["a", "b"]);
+        assert_eq!(parsed["post-create"].timeout, Some(Duration::from_secs(30)));
+        assert!(parse_hooks(None).unwrap().is_empty());
+
+        let err = |spec| format!("{:#}", parse_hooks(Some(&spec)).unwrap_err());
+        assert!(err(json!({"pre-create": 1})).contains("expected a command or a list"));
+        assert!(
+            err(json!({"pre-create": {"run": "x", "every": 2}})).contains("unknown key 'every'")
+        );
+        assert!(
+            err(json!({"pre-create": {"run": "x", "timeout": "soon"}})).contains("timeout must be")
+        );
+    }
+
+    #[test]
+    fn exec_payloads_describe_the_run() {
+        let commands = vec![DirCommand {
+            dir: "/ws/api".to_string(),
+            cmd: "cargo test".to_string(),
+            env: None,
+        }];
+        let args = vec![
+            "exec".to_string(),
+            "--".to_string(),
+            "cargo test".to_string(),
+        ];
+        let pre = pre_exec_payload(&args, &commands);
+        assert_eq!(pre["event"], "pre-exec");
+        assert_eq!(pre["args"][0], "exec");
+        assert_eq!(
+            pre["repos"][0],
+            json!({"name": "api", "directory": "/ws/api", "command": "cargo test"})
+        );
+
+        let report = crate::runner::RunReport {
+            results: vec![crate::runner::RepoResult {
+                directory: PathBuf::from("/ws/api"),
+                name: "api".to_string(),
+                command: "cargo test".to_string(),
+                status: crate::runner::RepoStatus::Failed,
+                exit_code: Some(101),
+                duration: Duration::from_millis(1500),
+                stdout: String::new(),
+                stderr: String::new(),
+            }],
+        };
+        let record = RunRecord::from_report(&report, args, vec![], Duration::from_secs(2));
+        let post = post_exec_payload(&record);
+        assert_eq!(post["schema_version"], PAYLOAD_SCHEMA_VERSION);
+        assert_eq!(post["event"], "post-exec");
+        assert_eq!(post["success"], false);
+        assert_eq!(post["duration_ms"], 2000);
+        assert_eq!(post["repos"][0]["exit_code"], 101);
+        assert!(post.get("id").is_none());
+    }
+
+    #[test]
+    fn payload_carries_version_repos_and_timing() {
+        let mut payload = HookPayload::new(
+            "post-create",
+            "auth-fix",
+            Path::new("/ws/.worktrees/auth-fix"),
+        );
+        payload.extra.insert("ephemeral".to_string(), json!(true));
+        let pre = payload.to_value();
+        assert_eq!(pre["schema_version"], PAYLOAD_SCHEMA_VERSION);
+        assert!(pre.get("duration_ms").is_none() && pre.get("success").is_none());
+
+        let repo = |alias: &str, error: Option<&str>| RepoOutcome {
+            alias: alias.to_string(),
+            path: PathBuf::from(format!("/ws/.worktrees/auth-fix/{alias}")),
+            branch: "auth-fix".to_string(),
+            success: error.is_none(),
+            error: error.map(str::to_string),
+            duration_ms: Some(5),
+        };
+        payload.finish(
+            vec![repo("api", None), repo("web", Some("checked out"))],
+            Instant::now(),
+        );
+        let value = payload.to_value();
+        assert_eq!(value["success"], false);
+        assert_eq!(value["ephemeral"], true);
+        assert_eq!(value["repos"][1]["error"], "checked out");
+        assert!(value["duration_ms"].is_u64());
+        assert!(value["repos"][0].get("error").is_none());
+    }
+}
@@ -0,0 +1,248 @@
+//! `meta impact`: which repos are affected by a change.
+//!
+//! The changed repos are named on the command line (by project name or by
+//! any path inside one) or detected with `--changed-since`. Every project
+//! that transitively depends on a changed one is affected too, so the result
+//! is the minimal set CI has to rebuild and test, listed dependencies first.
+
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::changes;
+use crate::config::{self, ProjectInfo};
+use crate::dependency_graph::DependencyGraph;
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AffectedRepo {
+    pub name: String,
+    pub path: String,
+    /// Changed itself, rather than only through a dependency
+    pub changed: bool,
+    /// Affected dependencies that put this repo in the set
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub via: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImpactReport {
+    pub changed: Vec<String>,
+    /// Changed repos and their transitive dependents, dependencies first
+    pub affected: Vec<AffectedRepo>,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta impact [<repo-or-path>...] [--changed-since <ref>]`.
+pub fn handle_impact(
+    config_path: &Path,
+    repos: &[String],
+    changed_since: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    anyhow::ensure!(
+        !repos.is_empty() || changed_since.is_some(),
+        "Name the changed repos or paths, or pass --changed-since <ref>"
+    );
+    let meta_dir = config_path.parent().unwrap_or(Path::new("."));
+    let (projects, _ignore) = config::parse_meta_config(config_path)?;
+
+    let mut changed = BTreeSet::new();
+    for spec in repos {
+        changed.insert(resolve_project(meta_dir, &projects, spec)?);
+    }
+    if let Some(base) = changed_since {
+        anyhow::ensure!(
+            base != changes::LAST_SUCCESS,
+            "`meta impact` needs a git ref for --changed-since ({} is tied to a command)",
+            changes::LAST_SUCCESS
+        );
+        changed.extend(
+            projects
+                .iter()
+                .filter(|p| changes::has_changes(&meta_dir.join(&p.path), base))
+                .map(|p| p.name.clone()),
+        );
+    }
+    let graph = DependencyGraph::build(projects.into_iter().map(Into::into).collect())?;
+    let report = analyze(&graph, &changed);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", format_report(&report));
+    }
+    Ok(())
+}
+
+/// Expand `changed` to everything that depends on it.
+pub fn analyze(graph: &DependencyGraph, changed: &BTreeSet<String>) -> ImpactReport {
+    let mut affected: BTreeSet<String> = changed.clone();
+    for name in changed {
+        let impact = graph.analyze_impact(name);
+        affected.extend(impact.direct_dependents);
+        affected.extend(impact.transitive_dependents);
+    }
+
+    let names: Vec<&str> = affected.iter().map(String::as_str).collect();
+    // A cycle leaves no valid build order; fall back to name order
+    let order: Vec<usize> = graph
+        .schedule(&names)
+        .map(|s| s.order)
+        .unwrap_or_else(|_| (0..names.len()).collect());
+
+    let affected = order
+        .into_iter()
+        .map(|i| {
+            let name = names[i];
+            let via = graph
+                .get_dependencies(name)
+                .into_iter()
+                .filter(|dep| affected.contains(*dep))
+                .map(str::to_string)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            AffectedRepo {
+                name: name.to_string(),
+                path: graph
+                    .get_project(name)
+                    .map_or_else(|| name.to_string(), |p| p.path.clone()),
+                changed: changed.contains(name),
+                via,
+            }
+        })
+        .collect();
+
+    ImpactReport {
+        changed: changed.iter().cloned().collect(),
+        affected,
+    }
+}
+
+/// Render the affected repos, one per line, with why each is included.
+pub fn format_report(report: &ImpactReport) -> String {
+    if report.changed.is_empty() {
+        return "No changed repos\n".to_string();
+    }
+
+    let width = report
+        .affected
+        .iter()
+        .map(|r| r.name.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for repo in &report.affected {
+        let reason = if repo.changed {
+            "changed".yellow().to_string()
+        } else {
+            format!("via {}", repo.via.join(", ")).dimmed().to_string()
+        };
+        out.push_str(&format!("{:<width$}  {reason}\n", repo.name));
+    }
+    out.push_str(&format!(
+        "\n{} changed, {} affected\n",
+        report.changed.len(),
+        report.affected.len()
+    ));
+    out
+}
+
+/// Map a project name, or a path inside a project, to the project name.
+pub fn resolve_project(meta_dir: &Path, projects: &[ProjectInfo], spec: &str) -> Result<String> {
+    if let Some(p) = projects.iter().find(|p| p.name == spec) {
+        return Ok(p.name.clone());
+    }
+
+    let absolute = |path: PathBuf| path.canonicalize().unwrap_or(path);
+    let target = absolute(std::env::current_dir()?.join(spec));
+    projects
+        .iter()
+        .map(|p| (p, absolute(meta_dir.join(&p.path))))
+        .filter(|(_, root)| target.starts_with(root))
+        // Nested projects: the innermost one owns the path
+        .max_by_key(|(_, root)| root.components().count())
+        .map(|(p, _)| p.name.clone())
+        .ok_or_else(|| anyhow::anyhow!("'{spec}' is not a project or a path inside one"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dependency_graph::ProjectDependencies;
+
+    fn project(name: &str, depends_on: &[&str]) -> ProjectDependencies {
+        ProjectDependencies {
+            name: name.to_string(),
+            path: format!("libs/{name}"),
+            repo: None,
+            tags: vec![],
+            provides: vec![],
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    fn changed(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn dependents_are_affected_in_build_order() {
+        let graph = DependencyGraph::build(vec![
+            project("web", &["api"]),
+            project("api", &["auth", "utils"]),
+            project("auth", &["utils"]),
+            project("utils", &[]),
+            project("docs", &[]),
+        ])
+        .unwrap();
+
+        let report = analyze(&graph, &changed(&["auth"]));
+        let names: Vec<&str> = report.affected.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["auth", "api", "web"]);
+        assert!(report.affected[0].changed);
+        assert_eq!(report.affected[1].via, ["auth"]);
+        assert_eq!(report.affected[1].path, "libs/api");
+
+        let text = console::strip_ansi_codes(&format_report(&report)).to_string();
+        assert!(text.contains("api   via auth\n"), "{text}");
+        assert!(text.ends_with("1 changed, 3 affected\n"));
+
+        let report = analyze(&graph, &changed(&["docs"]));
+        assert_eq!(report.affected.len(), 1);
+        assert_eq!(
+            format_report(&analyze(&graph, &changed(&[]))),
+            "No changed repos\n"
+        );
+    }
+
+    #[test]
+    fn paths_resolve_to_the_innermost_project() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tmp.path().join("api/src")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("api/plugins/auth")).unwrap();
+        let info = |name: &str, path: &str| ProjectInfo {
+            name: name.to_string(),
+            path: path.to_string(),
+            repo: None,
+            tags: vec![],
+            provides: vec![],
+            depends_on: vec![],
+            meta: false,
+        };
+        let projects = vec![info("api", "api"), info("auth", "api/plugins/auth")];
+        let resolve = |spec: &Path| resolve_project(tmp.path(), &projects, spec.to_str().unwrap());
+
+        assert_eq!(resolve(Path::new("auth")).unwrap(), "auth");
+        assert_eq!(resolve(&tmp.path().join("api/src")).unwrap(), "api");
+        assert_eq!(
+            resolve(&tmp.path().join("api/plugins/auth")).unwrap(),
+            "auth"
+        );
+        assert!(resolve(&tmp.path().join("elsewhere")).is_err());
+    }
+}
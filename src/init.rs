@@ -1,14 +1,40 @@
-//! Initialize Claude Code integration for meta repositories.
+//! Initialize meta workspaces and integrations.
 //!
-//! This module provides the `meta init claude` command which installs
-//! Claude Code skill files, rules, and hook configuration into the current
-//! project's `.claude/` directory.
+//! `meta init` scaffolds a new workspace in the current directory: a starter
+//! config file (optionally pre-populated with git repos already on disk) and
+//! a `.gitignore` that keeps worktrees and child repos out of the meta repo.
+//!
+//! `meta init claude` installs Claude Code skill files, rules, and hook
+//! configuration into the current project's `.claude/` directory.
+//!
+//! `meta agent guard install` sets up just the destructive-command guard: the
+//! PreToolUse hook, a default `.claude/agent-guard.toml`, and a self-test.
+//! `meta agent init` adds a compact SessionStart context and recommended
+//! permissions on top of it.
 
 use anyhow::{Context, Result};
 use colored::*;
+use meta_cli::config::{self, ConfigFormat};
+use meta_cli::{git_utils, project};
 use serde_json::{json, Map, Value};
 use std::fs;
+use std::io::Write;
 use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Read-only meta commands agents may run without a permission prompt.
+const AGENT_ALLOWED_COMMANDS: &[&str] = &[
+    "Bash(meta context:*)",
+    "Bash(meta status:*)",
+    "Bash(meta project list:*)",
+    "Bash(meta git status:*)",
+    "Bash(meta graph:*)",
+    "Bash(meta impact:*)",
+    "Bash(meta check-branches)",
+];
+
+/// Worktrees live here unless `worktrees_dir` says otherwise; never commit them.
+const WORKTREES_IGNORE: &str = ".worktrees/";
 
 /// Embedded skill files from the meta repository
 const SKILL_META_WORKSPACE: &str = include_str!("../.claude/skills/meta-workspace.md");
@@ -42,8 +68,19 @@ const RULES: &[(&str, &str)] = &[
 
 /// Typed init subcommand, mirroring the clap-parsed structure from main.
 pub enum InitCommand {
-    /// No subcommand — show help
+    /// Show help
     None,
+    /// Create a new workspace in the current directory
+    Workspace {
+        /// Format of the config file to write
+        format: ConfigFormat,
+        /// Add git repos found in subdirectories to `projects`
+        import: bool,
+        /// How many directory levels `import` searches
+        max_depth: usize,
+        /// Replace an existing config file
+        force: bool,
+    },
     /// Install Claude Code skills, rules, and hooks
     Claude {
         /// Overwrite all existing files including settings
@@ -60,29 +97,152 @@ pub fn handle_init_command(command: InitCommand, verbose: bool) -> Result<()> {
             print_init_help();
             Ok(())
         }
+        InitCommand::Workspace {
+            format,
+            import,
+            max_depth,
+            force,
+        } => {
+            let current_dir = std::env::current_dir()?;
+            let import = import.then_some(max_depth);
+            init_workspace(&current_dir, format, import, force, verbose)
+        }
         InitCommand::Claude { force, update } => install_claude_integration(force, update, verbose),
     }
 }
 
 fn print_init_help() {
-    println!("meta init - Initialize meta integrations");
+    println!("meta init - Initialize a meta workspace or integrations");
     println!();
     println!("USAGE:");
+    println!("    meta init [OPTIONS]");
     println!("    meta init <command>");
     println!();
     println!("COMMANDS:");
     println!("    claude    Install Claude Code skills, rules, and hooks for this meta repo");
     println!();
     println!("OPTIONS:");
+    println!("    --format <json|yaml|toml>    Config file format (default: json)");
+    println!("    --import                     Add git repos found in subdirectories to projects");
+    println!("    --max-depth <N>              Directory levels --import searches (default: 1)");
+    println!("    --force                      Replace an existing config file");
+    println!();
+    println!("CLAUDE OPTIONS:");
     println!("    -f, --force     Overwrite all existing files including settings");
     println!("    -u, --update    Update skills and rules only, preserve settings");
     println!();
     println!("EXAMPLES:");
+    println!("    meta init                    Create a .meta file in this directory");
+    println!("    meta init --import           Create .meta listing the repos already here");
+    println!("    meta init --import --max-depth 3   Also find repos nested in subfolders");
+    println!("    meta init --format yaml      Create a .meta.yaml file instead");
     println!("    meta init claude             Install Claude integration");
     println!("    meta init claude --update    Update skills/rules, keep settings");
     println!("    meta init claude --force     Overwrite everything");
 }
 
+// ── Workspace Scaffolding ───────────────────────────────
+
+/// Create a workspace config and `.gitignore` entries in `target_dir`.
+/// `import` is the depth to search for existing repos, if importing.
+fn init_workspace(
+    target_dir: &Path,
+    format: ConfigFormat,
+    import: Option<usize>,
+    force: bool,
+    verbose: bool,
+) -> Result<()> {
+    if let Some((existing, _)) = config::find_meta_config_in(target_dir) {
+        // Only an exact match is replaced; a config in another format would shadow the new one
+        if !force || existing.file_name() != Some(format.file_name().as_ref()) {
+            anyhow::bail!(
+                "{} already exists. Use --force to replace it.",
+                existing.display()
+            );
+        }
+    }
+
+    let projects = match import {
+        Some(depth) => discover_repos(target_dir, depth),
+        None => Map::new(),
+    };
+
+    let config_path = target_dir.join(format.file_name());
+    let document = json!({ "projects": projects });
+    config::write_meta_config_value(&config_path, &document)?;
+    println!("{} Created {}", "✓".green(), format.file_name());
+
+    if import.is_some() {
+        if projects.is_empty() {
+            println!("{} No git repos found to import", "•".yellow());
+        } else {
+            println!(
+                "{} Imported {} repo(s): {}",
+                "✓".green(),
+                projects.len(),
+                projects.keys().cloned().collect::<Vec<_>>().join(", ")
+            );
+        }
+    }
+
+    // Child repos are tracked by .meta, not by the meta repo's own index
+    let mut ignores = vec![WORKTREES_IGNORE.to_string()];
+    ignores.extend(projects.iter().map(|(name, entry)| {
+        let path = entry.get("path").and_then(Value::as_str).unwrap_or(name);
+        format!("{path}/")
+    }));
+    let added = project::append_gitignore(&target_dir.join(".gitignore"), &ignores)?;
+    if !added.is_empty() {
+        println!("{} Added {} to .gitignore", "✓".green(), added.join(", "));
+    } else if verbose {
+        println!("{} .gitignore already up to date", "Skipped".yellow());
+    }
+
+    println!();
+    println!("Next steps:");
+    if import.is_none() {
+        println!(
+            "  Add projects to {} or re-run with --import",
+            format.file_name()
+        );
+    }
+    println!("  meta exec -- git status    Run a command in every repo");
+    Ok(())
+}
+
+/// Find git repos under `dir`, up to `max_depth` levels down.
+///
+/// Each becomes a project named after its directory (or its relative path,
+/// if two share a name): the `origin` URL when one is configured, otherwise
+/// an extended entry with just a path (a local-only project). Nested repos
+/// record their path in the extended form.
+fn discover_repos(dir: &Path, max_depth: usize) -> Map<String, Value> {
+    let paths = git_utils::find_repos(dir, max_depth);
+    let basename = |p: &Path| p.file_name().map(|n| n.to_string_lossy().to_string());
+    let mut projects = Map::new();
+    for path in &paths {
+        let Some(base) = basename(path) else { continue };
+        let rel = path.to_string_lossy().replace('\\', "/");
+        let shared = paths
+            .iter()
+            .filter(|p| basename(p) == Some(base.clone()))
+            .count()
+            > 1;
+        let name = if shared { rel.clone() } else { base };
+
+        let url = git_utils::remote_url(&dir.join(path));
+        let entry = match (url, rel == name) {
+            (Some(url), true) => Value::String(url),
+            (Some(url), false) => json!({ "repo": url, "path": rel }),
+            (None, _) => json!({ "path": rel }),
+        };
+        projects.insert(name, entry);
+    }
+    projects
+}
+
+// ── Claude Integration ──────────────────────────────────
+
 /// Install Claude Code skills and hook configuration
 fn install_claude_integration(force: bool, update: bool, verbose: bool) -> Result<()> {
     let current_dir = std::env::current_dir()?;
@@ -101,9 +261,7 @@ fn install_claude_integration_to(
     let rules_dir = claude_dir.join("rules");
 
     // Check if this looks like a meta repo
-    let has_meta_config = target_dir.join(".meta").exists()
-        || target_dir.join(".meta.yaml").exists()
-        || target_dir.join(".meta.yml").exists();
+    let has_meta_config = meta_cli::config::find_meta_config_in(target_dir).is_some();
 
     if !has_meta_config {
         println!(
@@ -299,17 +457,7 @@ fn build_meta_hooks() -> Map<String, Value> {
     );
 
     // PreToolUse: block destructive Bash commands
-    hooks.insert(
-        "PreToolUse".to_string(),
-        json!([{
-            "matcher": "Bash",
-            "hooks": [{
-                "type": "command",
-                "command": "meta agent guard",
-                "timeout": 5
-            }]
-        }]),
-    );
+    hooks.insert("PreToolUse".to_string(), guard_hook());
 
     // PreCompact: capture workspace state before context compaction
     hooks.insert(
@@ -326,6 +474,18 @@ fn build_meta_hooks() -> Map<String, Value> {
     hooks
 }
 
+/// The PreToolUse entry that runs `meta agent guard` on Bash commands
+fn guard_hook() -> Value {
+    json!([{
+        "matcher": "Bash",
+        "hooks": [{
+            "type": "command",
+            "command": "meta agent guard",
+            "timeout": 5
+        }]
+    }])
+}
+
 /// Merge meta hooks into existing settings, preserving other settings
 fn merge_hooks_into_settings(mut existing: Value, meta_hooks: Map<String, Value>) -> Value {
     // Ensure existing has a hooks object
@@ -448,6 +608,182 @@ fn register_marketplace(verbose: bool) {
     }
 }
 
+// ── Agent Guard Hook ────────────────────────────────────
+
+/// Handle `meta agent guard install`: install the hook and config into the
+/// current project, then check that the installed hook denies and allows.
+pub fn handle_guard_install(force: bool, verbose: bool) -> Result<()> {
+    let current_dir = std::env::current_dir()?;
+    install_guard_to(&current_dir, force, verbose)?;
+    self_test_guard(&current_dir)?;
+    println!(
+        "{} Self-test passed: 'meta agent guard' denies git push --force and allows git status",
+        "✓".green()
+    );
+    Ok(())
+}
+
+/// Merge the guard hook into `.claude/settings.json` and write the default
+/// `.claude/agent-guard.toml`, keeping an existing one unless `force`.
+fn install_guard_to(target_dir: &Path, force: bool, verbose: bool) -> Result<()> {
+    let claude_dir = target_dir.join(".claude");
+    fs::create_dir_all(&claude_dir)
+        .with_context(|| format!("Failed to create {}", claude_dir.display()))?;
+
+    let settings_path = claude_dir.join("settings.json");
+    let existing = read_settings(&settings_path)?;
+    let mut hooks = Map::new();
+    hooks.insert("PreToolUse".to_string(), guard_hook());
+    let merged = merge_hooks_into_settings(existing, hooks);
+    write_file(
+        &settings_path,
+        &serde_json::to_string_pretty(&merged)?,
+        verbose,
+    )?;
+
+    let config_path = claude_dir.join("agent-guard.toml");
+    if config_path.exists() && !force {
+        println!(
+            "  {} agent-guard.toml (already exists, use --force to reset it)",
+            "•".yellow()
+        );
+    } else {
+        write_file(&config_path, meta_cli::agent_guard::DEFAULT_CONFIG, verbose)?;
+    }
+    Ok(())
+}
+
+/// `settings.json` as JSON, or an empty object if it does not exist.
+fn read_settings(settings_path: &Path) -> Result<Value> {
+    if !settings_path.exists() {
+        return Ok(json!({}));
+    }
+    let content = fs::read_to_string(settings_path)
+        .with_context(|| format!("Failed to read {}", settings_path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", settings_path.display()))
+}
+
+/// Feed the hook what Claude Code sends it, through the `meta` on PATH.
+fn self_test_guard(dir: &Path) -> Result<()> {
+    let run = |command: &str| -> Result<String> {
+        let mut child = Command::new("meta")
+            .args(["agent", "guard"])
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Self-test failed: could not run 'meta agent guard'; is meta on PATH?")?;
+        let input = json!({ "tool_name": "Bash", "tool_input": { "command": command } });
+        child
+            .stdin
+            .take()
+            .context("Failed to open the hook's stdin")?
+            .write_all(input.to_string().as_bytes())?;
+        let output = child.wait_with_output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let denied = run("git push --force origin main")?;
+    anyhow::ensure!(
+        denied.contains(r#""permissionDecision":"deny""#),
+        "Self-test failed: the hook did not deny 'git push --force' (check .claude/agent-guard.toml)"
+    );
+    let allowed = run("git status")?;
+    anyhow::ensure!(
+        allowed.is_empty(),
+        "Self-test failed: the hook did not allow 'git status': {allowed}"
+    );
+    Ok(())
+}
+
+// ── Agent Setup ─────────────────────────────────────────
+
+/// Handle `meta agent init`: the guard (as `meta agent guard install`), a
+/// SessionStart hook with the minimal context profile, and permissions for
+/// read-only meta commands.
+pub fn handle_agent_init(force: bool, verbose: bool) -> Result<()> {
+    let current_dir = std::env::current_dir()?;
+    if meta_cli::config::find_meta_config_in(&current_dir).is_none() {
+        println!(
+            "{}",
+            "Warning: No .meta config found in current directory.".yellow()
+        );
+    }
+    install_guard_to(&current_dir, force, verbose)?;
+    install_agent_settings_to(&current_dir, verbose)?;
+    self_test_guard(&current_dir)?;
+
+    println!();
+    println!("{} Workspace is ready for agents:", "✓".green());
+    println!("  SessionStart  meta context --profile minimal");
+    println!("  PreToolUse    meta agent guard (self-test passed)");
+    println!(
+        "  Permissions   {} read-only meta commands allowed",
+        AGENT_ALLOWED_COMMANDS.len()
+    );
+    Ok(())
+}
+
+/// Merge the agent SessionStart hook and permissions into
+/// `.claude/settings.json`. A `meta context` SessionStart hook installed
+/// by `meta init claude` is replaced, so context is injected once.
+fn install_agent_settings_to(target_dir: &Path, verbose: bool) -> Result<()> {
+    let settings_path = target_dir.join(".claude").join("settings.json");
+    let mut settings = read_settings(&settings_path)?;
+
+    if let Some(groups) = settings
+        .pointer_mut("/hooks/SessionStart")
+        .and_then(Value::as_array_mut)
+    {
+        groups.retain(|group| !runs_meta_context(group));
+    }
+    let mut hooks = Map::new();
+    hooks.insert(
+        "SessionStart".to_string(),
+        json!([{
+            "hooks": [{
+                "type": "command",
+                "command": "meta context --profile minimal 2>/dev/null",
+                "timeout": 10
+            }]
+        }]),
+    );
+    let mut settings = merge_hooks_into_settings(settings, hooks);
+
+    if let Some(obj) = settings.as_object_mut() {
+        let permissions = obj.entry("permissions").or_insert_with(|| json!({}));
+        if let Some(permissions) = permissions.as_object_mut() {
+            let allow = permissions.entry("allow").or_insert_with(|| json!([]));
+            if let Some(allow) = allow.as_array_mut() {
+                for command in AGENT_ALLOWED_COMMANDS {
+                    if !allow.iter().any(|a| a == command) {
+                        allow.push(json!(command));
+                    }
+                }
+            }
+        }
+    }
+
+    write_file(
+        &settings_path,
+        &serde_json::to_string_pretty(&settings)?,
+        verbose,
+    )
+}
+
+/// Whether a hook group runs `meta context`.
+fn runs_meta_context(group: &Value) -> bool {
+    group["hooks"].as_array().is_some_and(|hooks| {
+        hooks.iter().any(|h| {
+            h["command"]
+                .as_str()
+                .is_some_and(|c| c.starts_with("meta context"))
+        })
+    })
+}
+
 fn write_file(path: &Path, content: &str, verbose: bool) -> Result<()> {
     fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
 
@@ -759,4 +1095,179 @@ mod tests {
             .unwrap()
             .contains("meta context"));
     }
+
+    #[test]
+    fn test_guard_install_merges_hook_and_keeps_config() {
+        let dir = tempdir().unwrap();
+        let claude_dir = dir.path().join(".claude");
+        fs::create_dir_all(&claude_dir).unwrap();
+        fs::write(
+            claude_dir.join("settings.json"),
+            r#"{"model": "opus", "hooks": {"Stop": [{"hooks": [{"type": "prompt", "prompt": "x"}]}]}}"#,
+        )
+        .unwrap();
+
+        install_guard_to(dir.path(), false, false).unwrap();
+        install_guard_to(dir.path(), false, false).unwrap();
+
+        let settings: Value =
+            serde_json::from_str(&fs::read_to_string(claude_dir.join("settings.json")).unwrap())
+                .unwrap();
+        assert_eq!(settings["model"], "opus");
+        assert!(settings["hooks"]["Stop"].is_array());
+        assert!(settings["hooks"].get("SessionStart").is_none());
+        let pre_tool = settings["hooks"]["PreToolUse"].as_array().unwrap();
+        assert_eq!(pre_tool.len(), 1, "installing twice adds the hook once");
+        assert_eq!(pre_tool[0]["hooks"][0]["command"], "meta agent guard");
+
+        let config_path = claude_dir.join("agent-guard.toml");
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            meta_cli::agent_guard::DEFAULT_CONFIG
+        );
+        fs::write(&config_path, "schema_version = \"1.0\"\n").unwrap();
+        install_guard_to(dir.path(), false, false).unwrap();
+        assert!(!fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("[[patterns]]"));
+        install_guard_to(dir.path(), true, false).unwrap();
+        assert!(fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("[[patterns]]"));
+    }
+
+    #[test]
+    fn test_agent_settings_replace_full_context_hook() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        install_claude_integration_to(dir.path(), false, false, false).unwrap();
+        let settings_path = dir.path().join(".claude/settings.json");
+        let mut settings: Value =
+            serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        settings["permissions"] = json!({ "allow": ["Bash(cargo test:*)", "Bash(meta status:*)"] });
+        fs::write(&settings_path, settings.to_string()).unwrap();
+
+        install_guard_to(dir.path(), false, false).unwrap();
+        install_agent_settings_to(dir.path(), false).unwrap();
+        install_agent_settings_to(dir.path(), false).unwrap();
+
+        let settings: Value =
+            serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        let session = settings["hooks"]["SessionStart"].as_array().unwrap();
+        assert_eq!(session.len(), 1);
+        assert_eq!(
+            session[0]["hooks"][0]["command"],
+            "meta context --profile minimal 2>/dev/null"
+        );
+        assert_eq!(settings["hooks"]["PreToolUse"].as_array().unwrap().len(), 1);
+        assert!(settings["hooks"]["PreCompact"].is_array());
+
+        let allow: Vec<&str> = settings["permissions"]["allow"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        assert_eq!(allow[0], "Bash(cargo test:*)");
+        assert_eq!(allow.len(), 1 + AGENT_ALLOWED_COMMANDS.len());
+        assert!(dir.path().join(".claude/agent-guard.toml").exists());
+    }
+
+    fn git_init(dir: &Path, remote: Option<&str>) {
+        fs::create_dir_all(dir).unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        if let Some(url) = remote {
+            git(&["remote", "add", "origin", url]);
+        }
+    }
+
+    #[test]
+    fn test_init_workspace_writes_starter_config() {
+        let dir = tempdir().unwrap();
+        init_workspace(dir.path(), ConfigFormat::Json, None, false, false).unwrap();
+
+        let (path, format) = config::find_meta_config_in(dir.path()).unwrap();
+        assert_eq!(format, ConfigFormat::Json);
+        let (projects, _) = config::parse_meta_config(&path).unwrap();
+        assert!(projects.is_empty());
+
+        let gitignore = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert_eq!(gitignore, ".worktrees/\n");
+    }
+
+    #[test]
+    fn test_init_workspace_imports_git_repos() {
+        let dir = tempdir().unwrap();
+        git_init(&dir.path().join("api"), Some("git@github.com:org/api.git"));
+        git_init(&dir.path().join("scratch"), None);
+        fs::create_dir(dir.path().join("docs")).unwrap();
+
+        init_workspace(dir.path(), ConfigFormat::Toml, Some(1), false, false).unwrap();
+
+        let (projects, _) = config::parse_meta_config(&dir.path().join(".meta.toml")).unwrap();
+        let names: Vec<_> = projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["api", "scratch"]);
+        assert_eq!(
+            projects[0].repo.as_deref(),
+            Some("git@github.com:org/api.git")
+        );
+        assert!(projects[1].repo.is_none());
+
+        let gitignore = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert_eq!(gitignore, ".worktrees/\napi/\nscratch/\n");
+    }
+
+    #[test]
+    fn test_init_workspace_refuses_existing_config() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".meta"), r#"{"projects": {"a": "x"}}"#).unwrap();
+
+        let err = init_workspace(dir.path(), ConfigFormat::Json, None, false, false).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        // A different format would be shadowed by the existing file, even with --force
+        assert!(init_workspace(dir.path(), ConfigFormat::Yaml, None, true, false).is_err());
+
+        init_workspace(dir.path(), ConfigFormat::Json, None, true, false).unwrap();
+        let (projects, _) = config::parse_meta_config(&dir.path().join(".meta")).unwrap();
+        assert!(projects.is_empty());
+    }
+
+    #[test]
+    fn test_init_workspace_imports_nested_repos() {
+        let dir = tempdir().unwrap();
+        git_init(&dir.path().join("api"), None);
+        git_init(
+            &dir.path().join("services/api"),
+            Some("git@github.com:org/services-api.git"),
+        );
+        git_init(&dir.path().join("services/web"), None);
+
+        init_workspace(dir.path(), ConfigFormat::Json, Some(2), false, false).unwrap();
+
+        let (projects, _) = config::parse_meta_config(&dir.path().join(".meta")).unwrap();
+        let found: Vec<_> = projects
+            .iter()
+            .map(|p| (p.name.as_str(), p.path.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("api", "api"),
+                ("services/api", "services/api"),
+                ("web", "services/web")
+            ]
+        );
+        let gitignore = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert_eq!(
+            gitignore,
+            ".worktrees/\napi/\nservices/api/\nservices/web/\n"
+        );
+    }
 }
@@ -0,0 +1,593 @@
+//! Operation journal for `meta undo`.
+//!
+//! Operations that change several repos — `meta branch`, `meta commit`,
+//! `meta release tag` and worktree merge-back — write a journal to
+//! `~/.meta/journal/<id>.json` as they go: one step per repo they changed,
+//! with the git actions that revert it. The journal is saved after every
+//! step, so it is there even when the operation stops halfway.
+//!
+//! `meta undo <id>` reverts the steps not yet undone, newest first, and can
+//! be rerun to finish a partial undo; `meta undo` lists recent operations.
+//! Every action checks that the repo is still where the operation left it
+//! (the branch tip, tag or HEAD it recorded) and refuses otherwise, so an
+//! undo never throws away work done since. Only the newest [`MAX_JOURNALS`]
+//! operations are kept.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::git_utils::git;
+
+/// Number of operations kept in the journal directory.
+pub const MAX_JOURNALS: usize = 100;
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpStatus {
+    /// Still running, or stopped without finishing (a crash)
+    Running,
+    Completed,
+    /// Stopped partway; the steps recorded so far are still applied
+    Failed,
+    /// Failed and reverted by the operation itself
+    RolledBack,
+    Undone,
+    /// `meta undo` reverted some steps but not all
+    PartiallyUndone,
+}
+
+impl OpStatus {
+    fn label(self) -> &'static str {
+        match self {
+            OpStatus::Running => "running",
+            OpStatus::Completed => "completed",
+            OpStatus::Failed => "failed",
+            OpStatus::RolledBack => "rolled back",
+            OpStatus::Undone => "undone",
+            OpStatus::PartiallyUndone => "partly undone",
+        }
+    }
+}
+
+/// A git action that reverts part of a step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum UndoAction {
+    /// Check out the branch (or commit) that was checked out before
+    Checkout { to: String },
+    /// Delete a created branch, if it still points at `at`
+    DeleteBranch { name: String, at: String },
+    /// Recreate a deleted branch at the commit it pointed at
+    RestoreBranch { name: String, at: String },
+    /// Delete a created tag, if it still points at `at`
+    DeleteTag { name: String, at: String },
+    /// Move HEAD from `from` back to `to`, keeping the changes staged
+    ResetSoft { from: String, to: String },
+    /// Move HEAD from `from` back to `to` with `git reset --keep`
+    ResetKeep { from: String, to: String },
+    /// Remove a release from `releases.json` in the step's directory
+    ForgetRelease { version: String },
+}
+
+/// One repo's change within an operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Step {
+    pub repo: String,
+    pub path: PathBuf,
+    /// What the operation did, e.g. `created branch feature-x`
+    pub description: String,
+    /// Run in order to revert the step
+    pub undo: Vec<UndoAction>,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: String,
+    /// e.g. `branch-create`, `commit`, `release-tag`, `merge-back`
+    pub kind: String,
+    /// The command as the user would type it
+    pub summary: String,
+    pub started_at: DateTime<Local>,
+    pub status: OpStatus,
+    pub steps: Vec<Step>,
+}
+
+/// An operation being recorded. Saving is best effort: if the journal
+/// cannot be written, a warning is printed once and the operation goes on.
+#[derive(Debug)]
+pub struct Journal {
+    path: Option<PathBuf>,
+    pub op: Operation,
+    warned: bool,
+}
+
+/// The result of reverting one step.
+#[derive(Debug, Clone, Serialize)]
+pub struct UndoResult {
+    pub repo: String,
+    pub description: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Journal {
+    /// Start recording an operation in `~/.meta/journal`.
+    pub fn begin(kind: &str, summary: &str) -> Self {
+        match journal_dir() {
+            Ok(dir) => Self::begin_in(&dir, kind, summary),
+            Err(e) => {
+                warn(&e);
+                let mut journal = Self::disabled(kind, summary);
+                journal.warned = true;
+                journal
+            }
+        }
+    }
+
+    /// Start recording an operation in `dir`, pruning old journals.
+    pub fn begin_in(dir: &Path, kind: &str, summary: &str) -> Self {
+        let started_at = Local::now();
+        let stamp = started_at.format("%Y%m%d-%H%M%S");
+        let mut id = format!("{stamp}-{kind}");
+        let mut n = 2;
+        while dir.join(format!("{id}.json")).exists() {
+            id = format!("{stamp}-{kind}-{n}");
+            n += 1;
+        }
+        prune(dir, MAX_JOURNALS - 1);
+        let mut journal = Journal {
+            path: Some(dir.join(format!("{id}.json"))),
+            op: Operation {
+                id,
+                kind: kind.to_string(),
+                summary: summary.to_string(),
+                started_at,
+                status: OpStatus::Running,
+                steps: Vec::new(),
+            },
+            warned: false,
+        };
+        journal.save();
+        journal
+    }
+
+    /// A journal that records in memory only.
+    pub fn disabled(kind: &str, summary: &str) -> Self {
+        Journal {
+            path: None,
+            op: Operation {
+                id: String::new(),
+                kind: kind.to_string(),
+                summary: summary.to_string(),
+                started_at: Local::now(),
+                status: OpStatus::Running,
+                steps: Vec::new(),
+            },
+            warned: false,
+        }
+    }
+
+    /// The id to pass to `meta undo`, if the journal is being saved.
+    pub fn id(&self) -> Option<&str> {
+        self.path.as_ref().map(|_| self.op.id.as_str())
+    }
+
+    /// Record a change to one repo and save.
+    pub fn step(&mut self, repo: &str, path: &Path, description: &str, undo: Vec<UndoAction>) {
+        self.op.steps.push(Step {
+            repo: repo.to_string(),
+            path: path.to_path_buf(),
+            description: description.to_string(),
+            undo,
+            undone: false,
+        });
+        self.save();
+    }
+
+    /// Mark `repo`'s steps as reverted by the operation itself.
+    pub fn mark_undone(&mut self, repo: &str) {
+        for step in self.op.steps.iter_mut().filter(|s| s.repo == repo) {
+            step.undone = true;
+        }
+        self.save();
+    }
+
+    /// Record how the operation ended and save. A journal with nothing to
+    /// revert is dropped, unless it records a rollback.
+    pub fn finish(&mut self, status: OpStatus) {
+        self.op.status = status;
+        let rolled_back = status == OpStatus::RolledBack && !self.op.steps.is_empty();
+        if self.op.steps.iter().all(|s| s.undone) && !rolled_back {
+            if let Some(path) = self.path.take() {
+                let _ = std::fs::remove_file(path);
+            }
+            return;
+        }
+        self.save();
+    }
+
+    /// The id, if the journal is saved and has steps left to undo.
+    pub fn undo_id(&self) -> Option<&str> {
+        self.id()
+            .filter(|_| self.op.steps.iter().any(|s| !s.undone))
+    }
+
+    /// `Undo with: meta undo <id>`, for output after the operation.
+    pub fn hint(&self) -> Option<String> {
+        self.undo_id()
+            .map(|id| format!("Undo with: meta undo {id}"))
+    }
+
+    fn save(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = serde_json::to_string_pretty(&self.op)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| {
+                std::fs::write(path, text)
+                    .with_context(|| format!("Failed to write {}", path.display()))
+            });
+        if let Err(e) = result {
+            if !self.warned {
+                warn(&e);
+                self.warned = true;
+            }
+        }
+    }
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Every readable journal in `dir`, newest first.
+pub fn list_in(dir: &Path) -> Vec<Operation> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut ops: Vec<Operation> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+        .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+        .filter_map(|text| serde_json::from_str(&text).ok())
+        .collect();
+    ops.sort_by(|a, b| b.started_at.cmp(&a.started_at).then(b.id.cmp(&a.id)));
+    ops
+}
+
+/// Find an operation by id, unique id prefix, or `last`.
+pub fn find_in(dir: &Path, id: &str) -> Result<Operation> {
+    let ops = list_in(dir);
+    if id == "last" {
+        return ops.into_iter().next().context("No operations recorded yet");
+    }
+    if let Some(op) = ops.iter().find(|op| op.id == id) {
+        return Ok(op.clone());
+    }
+    let matches: Vec<&Operation> = ops.iter().filter(|op| op.id.starts_with(id)).collect();
+    match matches.as_slice() {
+        [op] => Ok((*op).clone()),
+        [] => anyhow::bail!("No operation '{id}' in the journal (see 'meta undo')"),
+        _ => anyhow::bail!(
+            "'{id}' matches {} operations: {}",
+            matches.len(),
+            matches
+                .iter()
+                .map(|op| op.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Revert every step of `op` not yet undone, newest first, and save the
+/// new state to `dir`. A step that cannot be reverted is reported and left
+/// marked as applied, so the undo can be retried.
+pub fn undo_in(dir: &Path, op: &mut Operation) -> Result<Vec<UndoResult>> {
+    let mut results = Vec::new();
+    for step in op.steps.iter_mut().rev().filter(|s| !s.undone) {
+        let outcome = step
+            .undo
+            .iter()
+            .try_for_each(|action| revert(&step.path, action));
+        step.undone = outcome.is_ok();
+        results.push(UndoResult {
+            repo: step.repo.clone(),
+            description: step.description.clone(),
+            success: outcome.is_ok(),
+            error: outcome.err().map(|e| format!("{e:#}")),
+        });
+    }
+    op.status = if op.steps.iter().all(|s| s.undone) {
+        OpStatus::Undone
+    } else {
+        OpStatus::PartiallyUndone
+    };
+    let path = dir.join(format!("{}.json", op.id));
+    std::fs::write(&path, serde_json::to_string_pretty(op)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(results)
+}
+
+/// Entry point for `meta undo [<id>]`.
+pub fn handle_undo(id: Option<&str>, dry_run: bool, json: bool) -> Result<()> {
+    let dir = journal_dir()?;
+    let Some(id) = id else {
+        let ops = list_in(&dir);
+        if json {
+            println!("{}", serde_json::to_string_pretty(&ops)?);
+        } else {
+            print!("{}", format_list(&ops));
+        }
+        return Ok(());
+    };
+
+    let mut op = find_in(&dir, id)?;
+    if dry_run {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&op)?);
+        } else {
+            print!("{}", format_plan(&op));
+        }
+        return Ok(());
+    }
+    anyhow::ensure!(
+        op.steps.iter().any(|s| !s.undone),
+        "Nothing to undo: {} is {}",
+        op.id,
+        op.status.label()
+    );
+    let results = undo_in(&dir, &mut op)?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "operation": op.id,
+                "status": op.status,
+                "steps": results,
+            }))?
+        );
+    } else {
+        print!("{}", format_results(&results));
+    }
+    let failed = results.iter().filter(|r| !r.success).count();
+    anyhow::ensure!(
+        failed == 0,
+        "Could not undo {failed} of {} steps; fix them and rerun 'meta undo {}'",
+        results.len(),
+        op.id
+    );
+    Ok(())
+}
+
+/// Recent operations, newest first.
+pub fn format_list(ops: &[Operation]) -> String {
+    if ops.is_empty() {
+        return "No operations recorded yet.\n".to_string();
+    }
+    let width = ops.iter().map(|op| op.id.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for op in ops {
+        let status = format!("{:<13}", op.status.label());
+        let status = match op.status {
+            OpStatus::Completed => status.green(),
+            OpStatus::Failed | OpStatus::Running | OpStatus::PartiallyUndone => status.red(),
+            OpStatus::RolledBack | OpStatus::Undone => status.dimmed(),
+        };
+        let repos = op.steps.len();
+        out.push_str(&format!(
+            "{:<width$}  {status}  {repos} repo{}  {}\n",
+            op.id,
+            if repos == 1 { "" } else { "s" },
+            op.summary
+        ));
+    }
+    out
+}
+
+/// What `meta undo <id>` would revert.
+pub fn format_plan(op: &Operation) -> String {
+    let mut out = format!("{} ({})\n", op.summary.bold(), op.status.label());
+    let width = op.steps.iter().map(|s| s.repo.len()).max().unwrap_or(0);
+    for step in op.steps.iter().rev() {
+        let (mark, detail) = if step.undone {
+            ("-".dimmed(), "already undone".dimmed().to_string())
+        } else {
+            let actions: Vec<String> = step.undo.iter().map(describe).collect();
+            ("→".cyan(), actions.join(", "))
+        };
+        out.push_str(&format!(
+            "{mark} {:<width$}  {}: {detail}\n",
+            step.repo, step.description
+        ));
+    }
+    out
+}
+
+/// One line per reverted step.
+pub fn format_results(results: &[UndoResult]) -> String {
+    let width = results.iter().map(|r| r.repo.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for r in results {
+        let (mark, detail) = match &r.error {
+            None => ("✓".green(), format!("undid: {}", r.description)),
+            Some(error) => ("✗".red(), format!("{}: {error}", r.description)),
+        };
+        out.push_str(&format!("{mark} {:<width$}  {detail}\n", r.repo));
+    }
+    out
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn journal_dir() -> Result<PathBuf> {
+    meta_core::data_dir::data_subdir("journal")
+}
+
+fn warn(e: &anyhow::Error) {
+    eprintln!(
+        "{}: failed to write the operation journal: {e:#}",
+        "warning".yellow().bold()
+    );
+}
+
+/// Delete the oldest journals until at most `keep` remain.
+fn prune(dir: &Path, keep: usize) {
+    for op in list_in(dir).iter().skip(keep) {
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", op.id)));
+    }
+}
+
+fn describe(action: &UndoAction) -> String {
+    match action {
+        UndoAction::Checkout { to } => format!("check out {to}"),
+        UndoAction::DeleteBranch { name, .. } => format!("delete branch {name}"),
+        UndoAction::RestoreBranch { name, at } => {
+            format!("recreate branch {name} at {}", short(at))
+        }
+        UndoAction::DeleteTag { name, .. } => format!("delete tag {name}"),
+        UndoAction::ResetSoft { to, .. } => format!("reset --soft to {}", short(to)),
+        UndoAction::ResetKeep { to, .. } => format!("reset --keep to {}", short(to)),
+        UndoAction::ForgetRelease { version } => format!("remove {version} from releases"),
+    }
+}
+
+fn revert(path: &Path, action: &UndoAction) -> Result<()> {
+    match action {
+        UndoAction::Checkout { to } => {
+            git(path, &["checkout", "-q", to])?;
+        }
+        UndoAction::DeleteBranch { name, at } => {
+            let tip = git(path, &["rev-parse", &format!("refs/heads/{name}")])?;
+            anyhow::ensure!(
+                tip == *at,
+                "branch {name} has moved to {} since; not deleting it",
+                short(&tip)
+            );
+            git(path, &["branch", "-D", name])?;
+        }
+        UndoAction::RestoreBranch { name, at } => {
+            git(path, &["branch", name, at])?;
+        }
+        UndoAction::DeleteTag { name, at } => {
+            let target = git(
+                path,
+                &["rev-parse", &format!("refs/tags/{name}^{{commit}}")],
+            )?;
+            anyhow::ensure!(
+                target == *at,
+                "tag {name} now points at {}; not deleting it",
+                short(&target)
+            );
+            git(path, &["tag", "-d", name])?;
+        }
+        UndoAction::ResetSoft { from, to } | UndoAction::ResetKeep { from, to } => {
+            let head = git(path, &["rev-parse", "HEAD"])?;
+            anyhow::ensure!(
+                head == *from,
+                "HEAD moved to {} since; not resetting over newer commits",
+                short(&head)
+            );
+            let mode = match action {
+                UndoAction::ResetSoft { .. } => "--soft",
+                _ => "--keep",
+            };
+            git(path, &["reset", "-q", mode, to])?;
+        }
+        UndoAction::ForgetRelease { version } => crate::release::forget(path, version)?,
+    }
+    Ok(())
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::{git, testing::init_repo};
+
+    #[test]
+    fn undo_reverts_steps_and_refuses_moved_repos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journals = tmp.path().join("journal");
+        std::fs::create_dir_all(&journals).unwrap();
+        let repo = tmp.path().join("api");
+        init_repo(&repo);
+        let base = git(&repo, &["rev-parse", "HEAD"]).unwrap();
+
+        let mut journal = Journal::begin_in(&journals, "commit", "meta commit -m wip");
+        std::fs::write(repo.join("a.txt"), "a").unwrap();
+        git(&repo, &["add", "-A"]).unwrap();
+        git(&repo, &["commit", "-q", "-m", "wip"]).unwrap();
+        let head = git(&repo, &["rev-parse", "HEAD"]).unwrap();
+        journal.step(
+            "api",
+            &repo,
+            "committed",
+            vec![UndoAction::ResetSoft {
+                from: head.clone(),
+                to: base.clone(),
+            }],
+        );
+        git(&repo, &["tag", "-a", "v1", "-m", "v1"]).unwrap();
+        journal.step(
+            "api",
+            &repo,
+            "tagged v1",
+            vec![UndoAction::DeleteTag {
+                name: "v1".to_string(),
+                at: head.clone(),
+            }],
+        );
+        journal.finish(OpStatus::Completed);
+        let id = journal.id().unwrap().to_string();
+        assert_eq!(journal.hint(), Some(format!("Undo with: meta undo {id}")));
+
+        // A commit made since blocks the reset; the tag is still removed
+        git(&repo, &["commit", "-q", "--allow-empty", "-m", "later"]).unwrap();
+        let mut op = find_in(&journals, "last").unwrap();
+        assert_eq!(op.id, id);
+        let results = undo_in(&journals, &mut op).unwrap();
+        assert_eq!(results[0].description, "tagged v1");
+        assert!(results[0].success);
+        assert!(!results[1].success);
+        assert!(results[1].error.as_deref().unwrap().contains("HEAD moved"));
+        assert_eq!(op.status, OpStatus::PartiallyUndone);
+
+        // Once the repo is back where the operation left it, a rerun finishes
+        git(&repo, &["reset", "-q", "--hard", "HEAD~1"]).unwrap();
+        let mut op = find_in(&journals, &id[..8]).unwrap();
+        let results = undo_in(&journals, &mut op).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].success, "{:?}", results[0].error);
+        assert_eq!(op.status, OpStatus::Undone);
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]).unwrap(), base);
+        assert_eq!(git(&repo, &["status", "--porcelain"]).unwrap(), "A  a.txt");
+
+        let text = console::strip_ansi_codes(&format_list(&list_in(&journals))).to_string();
+        assert!(text.contains("undone"), "{text}");
+    }
+
+    #[test]
+    fn finished_journals_without_steps_are_dropped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut journal = Journal::begin_in(tmp.path(), "branch-create", "meta branch create x");
+        assert_eq!(list_in(tmp.path()).len(), 1);
+        journal.finish(OpStatus::Failed);
+        assert!(list_in(tmp.path()).is_empty());
+        assert_eq!(journal.hint(), None);
+
+        let mut disabled = Journal::disabled("commit", "meta commit");
+        disabled.step("api", tmp.path(), "committed", vec![]);
+        assert_eq!(disabled.hint(), None);
+    }
+}
@@ -0,0 +1,89 @@
+//! Toolchain detection for implicit language tags.
+//!
+//! Each cloned repo is classified by the manifest at its root, so
+//! `meta --tag rust exec -- cargo clippy` works without tagging projects
+//! by hand. The detected language behaves like a tag in `--tag`, `tag:`
+//! patterns and `--if 'tag==rust'`, and is shown by `meta status` and
+//! `meta context`. Tags set in `.meta` are never removed.
+
+use std::path::Path;
+
+use crate::config::ProjectInfo;
+
+/// Languages in priority order, with the root files that identify them. A
+/// repo with both a `Cargo.toml` and a `package.json` is a Rust repo.
+const MARKERS: &[(&str, &[&str])] = &[
+    ("rust", &["Cargo.toml"]),
+    ("go", &["go.mod"]),
+    (
+        "python",
+        &["pyproject.toml", "setup.py", "requirements.txt"],
+    ),
+    ("java", &["pom.xml", "build.gradle", "build.gradle.kts"]),
+    ("typescript", &["tsconfig.json"]),
+    ("javascript", &["package.json"]),
+];
+
+/// The primary language of the repo at `repo`, if one is recognized.
+pub fn detect(repo: &Path) -> Option<&'static str> {
+    MARKERS
+        .iter()
+        .find(|(_, files)| files.iter().any(|f| repo.join(f).is_file()))
+        .map(|(lang, _)| *lang)
+}
+
+/// Add each project's detected language to its tags, for tag filtering.
+pub fn add_language_tags(projects: &mut [ProjectInfo], meta_dir: &Path) {
+    for project in projects {
+        if let Some(lang) = detect(&meta_dir.join(&project.path)) {
+            if !project.tags.iter().any(|t| t == lang) {
+                project.tags.push(lang.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_primary_toolchain() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = |name: &str, files: &[&str]| {
+            let dir = tmp.path().join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            for f in files {
+                std::fs::write(dir.join(f), "").unwrap();
+            }
+            dir
+        };
+
+        assert_eq!(
+            detect(&repo("api", &["Cargo.toml", "package.json"])),
+            Some("rust")
+        );
+        assert_eq!(
+            detect(&repo("web", &["package.json", "tsconfig.json"])),
+            Some("typescript")
+        );
+        assert_eq!(detect(&repo("ui", &["package.json"])), Some("javascript"));
+        assert_eq!(detect(&repo("ml", &["requirements.txt"])), Some("python"));
+        assert_eq!(detect(&repo("docs", &["README.md"])), None);
+
+        let mut projects = vec![ProjectInfo {
+            name: "api".to_string(),
+            path: "api".to_string(),
+            repo: None,
+            tags: vec!["backend".to_string(), "rust".to_string()],
+            provides: vec![],
+            depends_on: vec![],
+            meta: false,
+        }];
+        add_language_tags(&mut projects, tmp.path());
+        assert_eq!(projects[0].tags, ["backend", "rust"]);
+        projects[0].path = "web".to_string();
+        add_language_tags(&mut projects, tmp.path());
+        assert_eq!(projects[0].tags, ["backend", "rust", "typescript"]);
+    }
+}
@@ -1,9 +1,58 @@
 pub mod agent_guard;
 pub mod agent_score;
+pub mod branch;
+pub mod branch_check;
+pub mod changes;
+pub mod clone;
+pub mod commit;
 pub mod config;
 pub mod context;
+pub mod daemon;
 pub mod dependency_graph;
+pub mod diff;
+pub mod doctor;
+pub mod dotenv;
+pub mod error;
+pub mod filter;
+pub mod freeze;
+pub mod git;
 pub mod git_utils;
+pub mod graph;
+pub mod grep;
+pub mod help;
+pub mod history;
+pub mod hooks;
+pub mod impact;
+pub mod journal;
+pub mod lang;
+pub mod looprc;
+pub mod metrics;
+pub mod notify;
+pub mod open;
+pub mod picker;
+pub mod placeholders;
+pub mod plugin_cache;
+pub mod plugin_permissions;
+pub mod policy;
+pub mod predicate;
+pub mod progress;
+pub mod project;
 pub mod query;
+pub mod release;
+pub mod replace;
+pub mod report;
+pub mod runner;
+pub mod schema;
+pub mod script;
+pub mod snapshot;
+pub mod status;
 pub mod subprocess_plugins;
+pub mod sync;
+pub mod tasks;
+pub mod telemetry;
+pub mod wasm_plugins;
+pub mod watch;
+pub mod workdir;
+pub mod workspaces;
 pub mod worktree;
+pub mod worktree_template;
@@ -0,0 +1,187 @@
+//! Per-directory `.looprc` files.
+//!
+//! A `.looprc` in a target directory customizes how commands run there:
+//!
+//! ```json
+//! {
+//!   "aliases": { "t": "cargo test --workspace" },
+//!   "shell": "bash -lc",
+//!   "env": { "RUST_LOG": "debug" },
+//!   "skip": ["npm", "yarn"]
+//! }
+//! ```
+//!
+//! `skip` is either `true` (never run commands here) or a list of programs
+//! whose commands are skipped in this directory. Other keys are ignored, so
+//! the file can be shared with `loop`.
+//!
+//! Precedence, lowest first:
+//!
+//! - aliases: `~/.looprc`, then `.looprc` in the invocation directory (as in
+//!   `loop_lib`), then the target directory's `.looprc`
+//! - shell: `$SHELL -c`, then `shell` in `.meta` or `--shell`, then the
+//!   target directory's `.looprc`
+//! - env: `--env` and workspace variables, then the target directory's
+//!   `.looprc`, then per-project variables (`.env` files, project `env`)
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// File name looked up in each target directory.
+pub const FILE_NAME: &str = ".looprc";
+
+/// Settings from one directory's `.looprc`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirConfig {
+    pub aliases: HashMap<String, String>,
+    /// Shell program and flags, parsed like `shell` in `.meta`.
+    pub shell: Option<Vec<String>>,
+    pub env: HashMap<String, String>,
+    pub skip: Skip,
+}
+
+/// Which commands a directory opts out of.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Skip {
+    #[default]
+    Nothing,
+    Everything,
+    /// Commands whose program (first word) is listed.
+    Programs(Vec<String>),
+}
+
+impl DirConfig {
+    /// Load `dir/.looprc`; a missing file gives the defaults.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(FILE_NAME);
+        if !path.is_file() {
+            return Ok(DirConfig::default());
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let value: Value = serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Self::from_value(&value).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let strings = |key: &str| -> Result<HashMap<String, String>> {
+            match value.get(key) {
+                None => Ok(HashMap::new()),
+                // Numbers and booleans are accepted in their JSON spelling
+                Some(Value::Object(map)) => Ok(map
+                    .iter()
+                    .map(|(k, v)| match v {
+                        Value::String(s) => (k.clone(), s.clone()),
+                        other => (k.clone(), other.to_string()),
+                    })
+                    .collect()),
+                Some(_) => anyhow::bail!("'{key}' must be an object"),
+            }
+        };
+
+        let shell = match value.get("shell") {
+            None => None,
+            Some(Value::String(spec)) => {
+                Some(crate::runner::parse_shell(spec).context("'shell' must not be empty")?)
+            }
+            Some(Value::Array(items)) => {
+                let parts: Option<Vec<String>> = items
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_string))
+                    .collect();
+                let parts = parts.filter(|p| !p.is_empty());
+                Some(parts.context("'shell' must be a string or a non-empty array of strings")?)
+            }
+            Some(_) => anyhow::bail!("'shell' must be a string or an array of strings"),
+        };
+
+        let skip = match value.get("skip") {
+            None | Some(Value::Bool(false)) => Skip::Nothing,
+            Some(Value::Bool(true)) => Skip::Everything,
+            Some(Value::Array(items)) => Skip::Programs(
+                items
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_string))
+                    .collect::<Option<_>>()
+                    .context("'skip' must be true or an array of program names")?,
+            ),
+            Some(_) => anyhow::bail!("'skip' must be true or an array of program names"),
+        };
+
+        Ok(DirConfig {
+            aliases: strings("aliases")?,
+            shell,
+            env: strings("env")?,
+            skip,
+        })
+    }
+
+    /// Whether `command` (after alias resolution) should not run here.
+    pub fn skips(&self, command: &str) -> bool {
+        match &self.skip {
+            Skip::Nothing => false,
+            Skip::Everything => true,
+            Skip::Programs(programs) => command
+                .split_whitespace()
+                .next()
+                .is_some_and(|program| programs.iter().any(|p| p == program)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_every_section() {
+        let config = DirConfig::from_value(&json!({
+            "aliases": {"t": "cargo test"},
+            "shell": "bash -lc",
+            "env": {"RUST_LOG": "debug", "PORT": 8080},
+            "skip": ["npm"],
+            "directories": ["ignored"]
+        }))
+        .unwrap();
+        assert_eq!(config.aliases["t"], "cargo test");
+        assert_eq!(
+            config.shell,
+            Some(vec!["bash".to_string(), "-lc".to_string()])
+        );
+        assert_eq!(config.env["PORT"], "8080");
+        assert!(config.skips("npm install"));
+        assert!(!config.skips("cargo build"));
+    }
+
+    #[test]
+    fn skip_true_skips_everything() {
+        let config = DirConfig::from_value(&json!({"skip": true})).unwrap();
+        assert!(config.skips("anything"));
+        assert!(!DirConfig::default().skips("anything"));
+    }
+
+    #[test]
+    fn rejects_malformed_values() {
+        for bad in [
+            json!({"shell": 3}),
+            json!({"shell": []}),
+            json!({"skip": "npm"}),
+            json!({"aliases": ["x"]}),
+        ] {
+            assert!(DirConfig::from_value(&bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn load_defaults_without_a_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(DirConfig::load(tmp.path()).unwrap(), DirConfig::default());
+        std::fs::write(tmp.path().join(FILE_NAME), "{not json").unwrap();
+        let err = DirConfig::load(tmp.path()).unwrap_err();
+        assert!(format!("{err:#}").contains(".looprc"));
+    }
+}
@@ -1,17 +1,17 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Args, CommandFactory, Parser, Subcommand};
 use colored::*;
-use loop_lib::run;
-use meta_core::config::{
+use meta_cli::config::{
     self, find_meta_config, parse_meta_config, ConfigFormat, MetaTreeNode, ProjectInfo,
 };
+use meta_cli::error::MetaError;
 use std::io::Write;
 use std::path::PathBuf;
 
 mod init;
+mod publish;
 mod registry;
-mod subprocess_plugins;
-use meta_cli::worktree;
+use meta_cli::{help, plugin_permissions, runner, subprocess_plugins, wasm_plugins, worktree};
 use subprocess_plugins::{PluginRequestOptions, SubprocessPluginManager};
 
 // === CLI Structs ===
@@ -19,7 +19,7 @@ use subprocess_plugins::{PluginRequestOptions, SubprocessPluginManager};
 const VERSION: &str = include_str!("../../VERSION");
 
 #[derive(Parser)]
-#[command(author, version = VERSION.trim(), about, long_about = None, disable_help_flag = true)]
+#[command(author, version = VERSION.trim(), about, long_about = None, disable_help_flag = true, disable_help_subcommand = true)]
 struct Cli {
     /// Print help
     #[arg(short = 'h', long, global = true, action = clap::ArgAction::SetTrue)]
@@ -35,12 +35,31 @@ struct Cli {
     #[arg(short, long, global = true, value_name = "FILE")]
     config: Option<PathBuf>,
 
+    #[arg(
+        short = 'C',
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Run as if meta was started in PATH (or set META_CWD)"
+    )]
+    cwd: Option<PathBuf>,
+
+    #[arg(
+        short = 'w',
+        long,
+        global = true,
+        value_name = "NAME",
+        conflicts_with = "cwd",
+        help = "Run in the named workspace from `meta workspaces list`"
+    )]
+    workspace: Option<String>,
+
     #[arg(
         short,
         long,
         global = true,
         value_delimiter = ',',
-        help = "Specify directories to exclude"
+        help = "Directories to exclude: substring, glob (services/*), re:<regex>, tag:<name>, or if:<expr>"
     )]
     exclude: Option<Vec<String>>,
 
@@ -49,7 +68,7 @@ struct Cli {
         long,
         global = true,
         value_delimiter = ',',
-        help = "Specify directories to include"
+        help = "Directories to include: substring, glob (services/*), re:<regex>, tag:<name>, or if:<expr>"
     )]
     include: Option<Vec<String>>,
 
@@ -87,6 +106,15 @@ struct Cli {
     )]
     depth: Option<usize>,
 
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Expand directories that are not git repos into the repos up to N levels below them (init --import: default 1)"
+    )]
+    max_depth: Option<u64>,
+
     #[arg(
         long,
         global = true,
@@ -118,6 +146,171 @@ struct Cli {
     )]
     strict: bool,
 
+    #[arg(
+        long,
+        global = true,
+        help = "Stream output live, prefixing each line with the repo name"
+    )]
+    stream: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "FORMAT",
+        value_parser = ["text", "json", "ndjson"],
+        help = "Output format: text, json (same as --json), or ndjson (one event per line)"
+    )]
+    output: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "MODE",
+        value_parser = ["plain", "tui"],
+        help = "Show a live per-repo progress view (tui) instead of plain output"
+    )]
+    ui: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Hide command output and print only a repo × result matrix"
+    )]
+    summary_only: bool,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["json", "stream", "summary_only", "output", "ui", "parallel"],
+        help = "Run in exactly one repo as a transparent wrapper: unmodified output, the command's own exit code"
+    )]
+    passthrough: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "inherit|file:PATH|tee",
+        help = "Give commands stdin: meta's own (sequential only), a file, or a copy of meta's for every repo"
+    )]
+    stdin: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Show each repo's command and ask y/N/a(ll)/q(uit) before running it"
+    )]
+    confirm: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "REL",
+        help = "Run in this subdirectory of each repo (overrides the projects' workdir in .meta)"
+    )]
+    workdir: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Use only cached registry data and plugin downloads (~/.meta/cache); doctor skips remote checks"
+    )]
+    offline: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "FORMAT=PATH",
+        help = "Write a report file after exec/run, e.g. junit=report.xml (repeatable)"
+    )]
+    report: Vec<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "slack|json|URL",
+        help = "Post a summary when a run longer than notify.threshold finishes"
+    )]
+    notify: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "URL",
+        help = "Export OpenTelemetry traces to this OTLP/HTTP endpoint (or set OTEL_EXPORTER_OTLP_ENDPOINT)"
+    )]
+    trace_endpoint: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Stop at the first failing repo instead of running all of them"
+    )]
+    fail_fast: bool,
+
+    #[arg(
+        short = 'j',
+        long,
+        global = true,
+        value_name = "N",
+        help = "Maximum number of repos to run at once (default: .meta defaults.jobs, then CPU count)"
+    )]
+    jobs: Option<usize>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Pick the target repos from an interactive list before running"
+    )]
+    interactive: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "SHELL",
+        help = "Shell to run commands with, e.g. \"bash -lc\" (default: .meta shell, then $SHELL -c)"
+    )]
+    shell: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        help = "Kill a repo's command after this long, e.g. 90s, 10m (default: .meta defaults.timeout)"
+    )]
+    timeout: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Don't load .env / .meta.env files before running commands"
+    )]
+    no_dotenv: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "REF",
+        help = "Only run in repos changed since REF (a git ref, or last-success for the last successful run)"
+    )]
+    changed_since: Option<String>,
+
+    #[arg(
+        long = "if",
+        global = true,
+        value_name = "EXPR",
+        help = "Only run in repos matching EXPR, e.g. 'exists(package.json)' or 'branch==main && !dirty' (repeatable)"
+    )]
+    r#if: Vec<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "ORDER",
+        value_parser = ["config", "topo"],
+        help = "Run order for exec/run: config (file order) or topo (dependencies first)"
+    )]
+    order: Option<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -126,18 +319,169 @@ struct Cli {
 enum Commands {
     /// Agent integration commands
     Agent(AgentArgs),
+    /// Create, switch or delete a branch in every repo, all or nothing
+    Branch(BranchArgs),
+    /// Report repos that are not on the branch .meta expects
+    CheckBranches(CheckBranchesArgs),
+    /// Clone a meta repo and all of its projects
+    Clone(CloneArgs),
+    /// Stage and commit every dirty repo with one message
+    Commit(CommitArgs),
+    /// Inspect and validate workspace configuration
+    Config(ConfigArgs),
     /// Show workspace context summary
     Context(ContextArgs),
+    /// Run a background daemon that keeps plugin, config and git lookups warm
+    Daemon(DaemonArgs),
+    /// Show per-repo commits and file stats between two workspace states
+    Diff(DiffArgs),
+    /// Check the workspace, its repos, tools and plugins, and suggest fixes
+    Doctor,
     /// Execute a command across all repos
     Exec(ExecArgs),
-    /// Initialize meta integrations
+    /// Write a lockfile pinning every repo to its current commit
+    Freeze(FreezeArgs),
+    /// Print the cross-repo dependency graph (DOT, Mermaid or JSON)
+    Graph(GraphArgs),
+    /// Search every repo for a pattern, respecting .gitignore
+    Grep(GrepArgs),
+    /// Print this message, or help for a command or topic
+    Help(HelpArgs),
+    /// List, inspect, and re-run previous multi-repo runs
+    History(HistoryArgs),
+    /// List the repos affected by a change, dependencies first
+    Impact(ImpactArgs),
+    /// Create a new workspace or install integrations
     Init(InitArgs),
+    /// Print run, plugin and worktree metrics in the OpenMetrics format
+    Metrics(MetricsArgs),
+    /// Open a project in your editor, or its remote or PR page in the browser
+    Open(OpenArgs),
     /// Manage plugins
     Plugin(PluginArgs),
+    /// Add, remove, or rename projects in the .meta file
+    Project(ProjectArgs),
+    /// Tag a release across repos and inspect recorded releases
+    Release(ReleaseArgs),
+    /// Preview, then apply, a search-and-replace across repos
+    #[command(visible_alias = "sed")]
+    Replace(ReplaceArgs),
+    /// Run a task from the .meta tasks section in every repo
+    Run(RunArgs),
+    /// Show branch, sync, and dirty state for every repo
+    Status,
+    /// Clone projects missing from the workspace and report repos not in .meta
+    Sync(SyncArgs),
+    /// Check out the commits pinned in a lockfile
+    Thaw(ThawArgs),
+    /// Revert a recorded multi-repo operation, or list recent ones
+    Undo(UndoArgs),
+    /// Rerun a command in each repo whenever its files change
+    Watch(WatchArgs),
+    /// List the workspaces meta has seen and switch between them
+    Workspaces(WorkspacesArgs),
+    /// Manage worktree sets: isolated checkouts of several repos for one task
+    Worktree(WorktreeArgs),
     #[command(external_subcommand)]
     External(Vec<String>),
 }
 
+/// Arguments for `meta commit`
+#[derive(Args)]
+struct CommitArgs {
+    /// Commit message; `{repo}` and `{branch}` expand per repo
+    #[arg(short, long)]
+    message: String,
+}
+
+/// Arguments for `meta grep`
+///
+/// The global `--include`, `--exclude`, `--tag` and `--if` filters choose
+/// the repos searched.
+#[derive(Args)]
+struct GrepArgs {
+    /// Extended regular expression to search for
+    pattern: String,
+
+    /// Only search these paths inside each repo
+    paths: Vec<String>,
+
+    /// Match case-insensitively
+    #[arg(long)]
+    ignore_case: bool,
+
+    /// Treat the pattern as a literal string
+    #[arg(short = 'F', long)]
+    fixed_strings: bool,
+
+    /// Match whole words only
+    #[arg(long)]
+    word_regexp: bool,
+
+    /// Print only the names of files with matches
+    #[arg(short = 'l', long)]
+    files_with_matches: bool,
+
+    /// Only search these projects (comma-separated names)
+    #[arg(long, value_delimiter = ',', value_name = "NAMES")]
+    include_only: Vec<String>,
+}
+
+/// Arguments for `meta replace`
+#[derive(Args)]
+struct ReplaceArgs {
+    /// Regular expression to replace
+    pattern: String,
+
+    /// Replacement text; `$1` or `${name}` inserts a capture group
+    replacement: String,
+
+    /// Only replace in these paths inside each repo
+    paths: Vec<String>,
+
+    /// Treat the pattern and replacement as literal strings
+    #[arg(short = 'F', long)]
+    fixed_strings: bool,
+
+    /// Match case-insensitively
+    #[arg(long)]
+    ignore_case: bool,
+
+    /// Write the changes instead of previewing them
+    #[arg(long)]
+    write: bool,
+
+    /// Create this branch in each changed repo before writing
+    #[arg(long, value_name = "NAME")]
+    branch: Option<String>,
+
+    /// Commit the changed files in each repo with this message
+    #[arg(short, long)]
+    message: Option<String>,
+
+    /// Only replace in these projects (comma-separated names)
+    #[arg(long, value_delimiter = ',', value_name = "NAMES")]
+    include_only: Vec<String>,
+}
+
+/// Arguments for `meta undo`
+#[derive(Args)]
+struct UndoArgs {
+    /// Operation id (or a unique prefix, or `last`); omit to list operations
+    id: Option<String>,
+}
+
+/// Arguments for `meta help`
+#[derive(Args)]
+struct HelpArgs {
+    /// Topic or command (e.g. `filters`, `plugin install`)
+    topic: Vec<String>,
+
+    /// Print without a pager even on a terminal
+    #[arg(long)]
+    no_pager: bool,
+}
+
 /// Arguments for `meta agent`
 #[derive(Args)]
 struct AgentArgs {
@@ -148,7 +492,16 @@ struct AgentArgs {
 #[derive(Subcommand)]
 enum AgentCommands {
     /// Evaluate a command for destructive patterns (PreToolUse hook)
-    Guard,
+    Guard {
+        #[command(subcommand)]
+        command: Option<GuardCommands>,
+    },
+    /// Set up this workspace for agents: context and guard hooks, and permissions
+    Init {
+        /// Replace an existing .claude/agent-guard.toml with the defaults
+        #[arg(short, long)]
+        force: bool,
+    },
     /// Score Claude Code sessions for agent effectiveness
     Score {
         /// Specific session ID to score
@@ -161,6 +514,312 @@ enum AgentCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum GuardCommands {
+    /// Add the guard hook to .claude/settings.json, write a default config and test it
+    Install {
+        /// Replace an existing .claude/agent-guard.toml with the defaults
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+/// Arguments for `meta branch`
+#[derive(Args)]
+struct BranchArgs {
+    #[command(subcommand)]
+    command: Option<BranchCommands>,
+}
+
+#[derive(Subcommand)]
+enum BranchCommands {
+    /// Create a branch at HEAD and switch to it
+    Create { name: String },
+    /// Switch to an existing branch (repos must be clean)
+    Switch { name: String },
+    /// Delete a branch that is not checked out
+    Delete {
+        name: String,
+
+        /// Delete even if the branch is not merged
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Arguments for `meta diff`
+#[derive(Args)]
+struct DiffArgs {
+    /// Starting state: a lockfile, a release version, or HEAD
+    #[arg(long)]
+    from: String,
+
+    /// Ending state (same forms as --from)
+    #[arg(long, default_value = meta_cli::diff::HEAD)]
+    to: String,
+}
+
+/// Arguments for `meta freeze`
+#[derive(Args)]
+struct FreezeArgs {
+    /// Lockfile to write (`-` for stdout; default meta.lock.json at the meta root)
+    file: Option<PathBuf>,
+}
+
+/// Arguments for `meta graph`
+#[derive(Args)]
+struct GraphArgs {
+    /// Output format (--json is the same as --format json)
+    #[arg(long, value_parser = ["dot", "mermaid", "json"], default_value = "dot")]
+    format: String,
+
+    /// Only show this project and its transitive dependencies and dependents
+    #[arg(long, value_name = "REPO")]
+    focus: Option<String>,
+}
+
+/// Arguments for `meta check-branches`
+#[derive(Args)]
+struct CheckBranchesArgs {
+    /// Switch clean repos back to their expected branch
+    #[arg(long)]
+    fix: bool,
+}
+
+/// Arguments for `meta impact`
+#[derive(Args)]
+struct ImpactArgs {
+    /// Changed projects, by name or by a path inside them (or use --changed-since)
+    #[arg(value_name = "REPO_OR_PATH")]
+    repos: Vec<String>,
+}
+
+/// Arguments for `meta sync`
+#[derive(Args)]
+struct SyncArgs {
+    /// Move git repos that are not in .meta to ~/.meta/archive/
+    #[arg(long)]
+    prune: bool,
+}
+
+/// Arguments for `meta thaw`
+#[derive(Args)]
+struct ThawArgs {
+    /// Lockfile written by `meta freeze`
+    lockfile: PathBuf,
+
+    /// Put each repo on this branch (created or reset) instead of detaching
+    #[arg(long)]
+    branch: Option<String>,
+}
+
+/// Arguments for `meta watch`
+#[derive(Args)]
+struct WatchArgs {
+    /// Quiet period after the last change before rerunning (e.g. 500ms, 2s)
+    #[arg(long, value_name = "DURATION", default_value = "300ms")]
+    debounce: String,
+
+    /// Command and arguments to run (use -- to separate from meta flags)
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+/// Arguments for `meta workspaces`
+#[derive(Args)]
+struct WorkspacesArgs {
+    #[command(subcommand)]
+    command: Option<WorkspacesCommands>,
+}
+
+#[derive(Subcommand)]
+enum WorkspacesCommands {
+    /// List known workspaces, most recently used first
+    List,
+    /// Print a workspace's root, for `cd "$(meta workspaces switch <name>)"`
+    Switch {
+        /// Workspace name from `meta workspaces list`
+        name: String,
+    },
+}
+
+/// Arguments for `meta worktree`
+#[derive(Args)]
+struct WorktreeArgs {
+    #[command(subcommand)]
+    command: Option<WorktreeCommands>,
+}
+
+#[derive(Subcommand)]
+enum WorktreeCommands {
+    /// Rename a worktree set, moving its directory and repairing git's links
+    #[command(visible_alias = "mv")]
+    Rename {
+        /// Current set name
+        name: String,
+        /// New set name
+        new_name: String,
+    },
+    /// Merge a set's branches back into their primary checkouts
+    MergeBack {
+        /// Set name
+        name: String,
+        /// How to integrate each branch: merge, rebase or squash
+        #[arg(long, default_value = "merge")]
+        strategy: String,
+        /// Branch to merge into (defaults to each primary checkout's branch)
+        #[arg(long)]
+        base: Option<String>,
+        /// Destroy the set once every repo merged cleanly
+        #[arg(long)]
+        delete: bool,
+    },
+    /// Push a set's branches, optionally opening a PR per repo
+    Push {
+        /// Set name
+        name: String,
+        /// Branch the task branches are compared against and PRs target
+        #[arg(long, default_value = "main")]
+        base: String,
+        /// Open a pull request (via `gh`) for every pushed repo
+        #[arg(long)]
+        create_pr: bool,
+        /// PR title (defaults to the branch name)
+        #[arg(long)]
+        title: Option<String>,
+        /// File holding the PR body
+        #[arg(long)]
+        body_file: Option<PathBuf>,
+    },
+    /// Print a set's path, or one repo's, for `cd "$(meta worktree switch <name>)"`
+    Switch {
+        /// Set name
+        #[arg(required_unless_present = "clear")]
+        name: Option<String>,
+        /// Repo inside the set
+        repo: Option<String>,
+        /// Also scope commands run outside .worktrees to this set until cleared
+        #[arg(long)]
+        set_current: bool,
+        /// Forget the current set
+        #[arg(long, conflicts_with_all = ["name", "repo", "set_current"])]
+        clear: bool,
+    },
+    /// Protect a set from destroy, rename and prune until it is unlocked
+    Lock {
+        /// Set name
+        name: String,
+        /// Why the set is locked, shown by `meta worktree list`
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Remove a set's lock
+    Unlock {
+        /// Set name
+        name: String,
+    },
+    /// Report disk used per set (per repo with --verbose), largest first
+    Du,
+    /// Other worktree commands are provided by the worktree plugin (create, list, ...)
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+/// Arguments for `meta release`
+#[derive(Args)]
+struct ReleaseArgs {
+    #[command(subcommand)]
+    command: Option<ReleaseCommands>,
+}
+
+#[derive(Subcommand)]
+enum ReleaseCommands {
+    /// Create an annotated tag in every repo and record it in releases.json
+    Tag {
+        /// Tag name, e.g. v1.2.0
+        version: String,
+
+        /// Tag message (defaults to "Release <version>")
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// Create GPG-signed tags
+        #[arg(long)]
+        sign: bool,
+
+        /// Push the tag to origin in every repo
+        #[arg(long)]
+        push: bool,
+    },
+    /// List recorded releases
+    List,
+    /// Show the commit each repo was tagged at
+    Show { version: String },
+}
+
+/// Arguments for `meta clone`
+///
+/// The global `--depth` is passed to git as the shallow-clone depth here.
+#[derive(Args)]
+struct CloneArgs {
+    /// URL of the meta repo
+    url: String,
+
+    /// Directory to clone into (defaults to the repo name)
+    directory: Option<PathBuf>,
+
+    /// Only clone these projects (comma-separated names)
+    #[arg(long, value_delimiter = ',', value_name = "NAMES")]
+    include_only: Vec<String>,
+
+    /// Extra attempts per repo before giving up
+    #[arg(long, default_value_t = meta_cli::clone::DEFAULT_RETRIES)]
+    retries: usize,
+}
+
+/// Arguments for `meta config`
+#[derive(Args)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    command: Option<ConfigCommands>,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Check the .meta file for errors and unknown keys
+    Validate,
+    /// Show the effective configuration (~/.meta/config.yaml beneath .meta)
+    Show {
+        /// Show which file each value came from
+        #[arg(long)]
+        origin: bool,
+    },
+    /// Print the value of a key, e.g. projects.api.path
+    Get {
+        /// Dotted key path
+        key: String,
+        /// Read ~/.meta/config.yaml only
+        #[arg(long)]
+        user: bool,
+    },
+    /// Set a key in .meta, keeping its format (validated before writing)
+    Set {
+        /// Dotted key path, e.g. worktrees_dir or defaults.jobs
+        key: String,
+        /// New value: JSON (4, true, ["a"]) or a plain string
+        value: String,
+        /// Write ~/.meta/config.yaml instead of .meta
+        #[arg(long)]
+        user: bool,
+    },
+    /// Explain how a part of the configuration is resolved
+    Explain {
+        /// What to explain
+        #[arg(value_parser = ["env"])]
+        topic: String,
+    },
+}
+
 /// Arguments for `meta context`
 #[derive(Args)]
 struct ContextArgs {
@@ -171,21 +830,112 @@ struct ContextArgs {
     /// Bypass cache and force fresh context generation
     #[arg(long)]
     no_cache: bool,
+
+    /// How much to include: minimal, standard, or full (adds changed files and diff stats)
+    #[arg(long, value_parser = ["minimal", "standard", "full"], default_value = "standard")]
+    profile: String,
+
+    /// Trim the output to fit, e.g. 2000 (tokens), 2000tokens, 8000bytes or 8kb
+    #[arg(long, value_name = "SIZE")]
+    budget: Option<String>,
+
+    /// Keep running and print the context as a JSON line whenever it changes
+    #[arg(long)]
+    watch: bool,
 }
 
 /// Arguments for `meta exec`
 #[derive(Args)]
 struct ExecArgs {
+    /// Run this script file in every repo; the command becomes its arguments
+    #[arg(long, value_name = "FILE")]
+    script: Option<PathBuf>,
+
     /// Command and arguments to execute (use -- to separate from meta flags)
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     command: Vec<String>,
 }
 
+/// Arguments for `meta run`
+#[derive(Args)]
+struct RunArgs {
+    /// Task name (lists the available tasks when omitted)
+    task: Option<String>,
+
+    /// Extra arguments appended to the task's command (after --)
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+/// Arguments for `meta daemon`
+#[derive(Args)]
+struct DaemonArgs {
+    #[command(subcommand)]
+    command: DaemonCommands,
+}
+
+#[derive(Subcommand)]
+enum DaemonCommands {
+    /// Start the daemon in the background
+    Start {
+        /// Stay attached to the terminal instead of detaching
+        #[arg(long)]
+        foreground: bool,
+    },
+    /// Stop the running daemon
+    Stop,
+    /// Show whether the daemon is running and what it has cached
+    Status,
+}
+
+/// Arguments for `meta history`
+#[derive(Args)]
+struct HistoryArgs {
+    #[command(subcommand)]
+    command: Option<HistoryCommands>,
+}
+
+#[derive(Subcommand)]
+enum HistoryCommands {
+    /// List recent runs, newest first
+    List {
+        /// Number of runs to show
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show the per-repo results of a run
+    Show {
+        /// Run id from `meta history list`
+        id: u64,
+    },
+    /// Run the exact command set of a previous run again
+    Rerun {
+        /// Run id from `meta history list`
+        id: u64,
+        /// Only the repos that did not succeed last time
+        #[arg(long)]
+        failed: bool,
+    },
+}
+
 /// Arguments for `meta init`
 #[derive(Args)]
 struct InitArgs {
     #[command(subcommand)]
     command: Option<InitCommands>,
+
+    /// Config file format for the new workspace
+    #[arg(long, value_parser = ["json", "yaml", "toml"], default_value = "json")]
+    format: String,
+
+    /// Add git repos found in subdirectories (up to --max-depth levels down)
+    /// to the new config
+    #[arg(long)]
+    import: bool,
+
+    /// Replace an existing config file
+    #[arg(long)]
+    force: bool,
 }
 
 #[derive(Subcommand)]
@@ -202,6 +952,93 @@ enum InitCommands {
     },
 }
 
+/// Arguments for `meta project`
+#[derive(Args)]
+struct ProjectArgs {
+    #[command(subcommand)]
+    command: Option<ProjectCommands>,
+}
+
+#[derive(Subcommand)]
+enum ProjectCommands {
+    /// Add a project to the .meta file
+    Add {
+        /// Project name (key in the projects map)
+        alias: String,
+        /// Directory of the project, relative to the current directory
+        path: String,
+        /// Git remote URL (defaults to the existing clone's origin)
+        #[arg(long)]
+        url: Option<String>,
+        /// Tags for targeting the project with --tag (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+    },
+    /// Remove a project from the .meta file
+    Remove {
+        /// Project name
+        alias: String,
+        /// Also delete the project's directory (refused if it has uncommitted changes)
+        #[arg(long)]
+        delete_dir: bool,
+    },
+    /// Rename a project, moving its directory when the path follows the name
+    #[command(visible_alias = "mv")]
+    Rename {
+        /// Current project name
+        alias: String,
+        /// New project name
+        new_alias: String,
+    },
+    /// Other project commands are provided by plugins (list, check, sync, ...)
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+/// Arguments for `meta open`
+#[derive(Args)]
+struct OpenArgs {
+    /// Project alias from .meta
+    alias: String,
+
+    /// Open the project directory in $VISUAL, $EDITOR or VS Code (default)
+    #[arg(long, conflicts_with_all = ["remote", "pr"])]
+    editor: bool,
+
+    /// Open the repo's web page
+    #[arg(long, conflicts_with = "pr")]
+    remote: bool,
+
+    /// Open the page for a pull request from the current branch
+    #[arg(long)]
+    pr: bool,
+}
+
+/// Arguments for `meta metrics`
+#[derive(Args)]
+struct MetricsArgs {
+    #[command(subcommand)]
+    command: Option<MetricsCommands>,
+}
+
+#[derive(Subcommand)]
+enum MetricsCommands {
+    /// Serve the metrics over HTTP for Prometheus to scrape
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = meta_cli::metrics::DEFAULT_PORT)]
+        port: u16,
+        /// Address to bind
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+    },
+    /// Write the metrics to a file for node_exporter's textfile collector
+    Write {
+        /// File to write, e.g. /var/lib/node_exporter/meta.prom
+        path: PathBuf,
+    },
+}
+
 /// Arguments for `meta plugin`
 #[derive(Args)]
 struct PluginArgs {
@@ -215,11 +1052,22 @@ enum PluginCommands {
     Search {
         /// Search query
         query: String,
+
+        /// Revalidate the cached registry index even if it is still fresh
+        #[arg(long)]
+        refresh: bool,
     },
     /// Install a plugin from the registry
     Install {
         /// Plugin name
-        name: String,
+        #[arg(required_unless_present_any = ["path", "git"])]
+        name: Option<String>,
+        /// Install a local plugin executable, or build a cargo project with cargo
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["name", "git"])]
+        path: Option<PathBuf>,
+        /// Clone a git repository and build it with cargo
+        #[arg(long, value_name = "URL", conflicts_with = "name")]
+        git: Option<String>,
         /// Install plugin locally to project (.meta/plugins/) instead of globally
         #[arg(long)]
         local: bool,
@@ -249,6 +1097,49 @@ enum PluginCommands {
         #[arg(long)]
         check: bool,
     },
+    /// List installed plugins with newer versions available
+    Outdated {
+        /// Check only project-local plugins
+        #[arg(long)]
+        local: bool,
+    },
+    /// Check every discovered plugin for problems
+    Doctor,
+    /// Forget cached plugin info so every plugin is asked again
+    Refresh,
+    /// Package a plugin binary for a GitHub release and generate its registry entry
+    Publish {
+        /// Plugin binary, named meta-<name>
+        binary: PathBuf,
+
+        /// GitHub repository the release is published from (user/repo)
+        #[arg(long)]
+        repo: String,
+
+        /// Platform the binary is built for (defaults to this machine's)
+        #[arg(long)]
+        platform: Option<String>,
+
+        /// Release version (defaults to the version the plugin reports)
+        #[arg(long = "release-version", value_name = "VERSION")]
+        release_version: Option<String>,
+
+        /// Author for the registry metadata (defaults to the repository owner)
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Directory for the archive and registry files
+        #[arg(long, default_value = "dist")]
+        out: PathBuf,
+
+        /// Open a pull request adding the plugin to the registry (needs gh)
+        #[arg(long)]
+        pr: bool,
+    },
+    /// Write .meta/plugins.lock from the project-local plugins
+    Lock,
+    /// Install the plugins pinned in .meta/plugins.lock
+    Sync,
 }
 
 // === Help Utilities ===
@@ -313,13 +1204,22 @@ fn write_help_with_plugin_commands(
         ));
     }
 
+    // Without a git plugin, `meta git` falls back to the built-in subset
+    if !commands.iter().any(|c| c.0 == "git") {
+        commands.push((
+            "git".to_string(),
+            "Run status, pull, push, checkout, branch or snapshot in every repo".to_string(),
+            None,
+        ));
+    }
+
     // Sort alphabetically
     commands.sort_by(|a, b| a.0.cmp(&b.0));
 
     // Add help at the end (standard convention)
     commands.push((
         "help".to_string(),
-        "Print this message or the help of the given subcommand(s)".to_string(),
+        "Print this message, or help for a command or topic".to_string(),
         None,
     ));
 
@@ -362,152 +1262,1161 @@ fn write_help_with_plugin_commands(
             flag_str.push_str(&format!("--{}", long));
         }
 
-        // Add value name if this arg takes a value (not a flag)
-        // A flag has action SetTrue/SetFalse/Count - check num_args or action
-        let is_flag = matches!(
-            arg.get_action(),
-            clap::ArgAction::SetTrue | clap::ArgAction::SetFalse | clap::ArgAction::Count
-        );
-        if !is_flag {
-            if let Some(value_names) = arg.get_value_names() {
-                if !value_names.is_empty() {
-                    flag_str.push_str(&format!(" <{}>", value_names[0]));
+        // Add value name if this arg takes a value (not a flag)
+        // A flag has action SetTrue/SetFalse/Count - check num_args or action
+        let is_flag = matches!(
+            arg.get_action(),
+            clap::ArgAction::SetTrue | clap::ArgAction::SetFalse | clap::ArgAction::Count
+        );
+        if !is_flag {
+            if let Some(value_names) = arg.get_value_names() {
+                if !value_names.is_empty() {
+                    flag_str.push_str(&format!(" <{}>", value_names[0]));
+                }
+            }
+        }
+
+        let help = arg.get_help().map(|s| s.to_string()).unwrap_or_default();
+
+        if !flag_str.is_empty() {
+            writeln!(w, "  {:<38}  {}", flag_str, help)?;
+        }
+    }
+
+    // Standard help/version
+    writeln!(w, "  {:<38}  Print help", "-h, --help")?;
+    writeln!(w, "  {:<38}  Print version", "-V, --version")?;
+
+    Ok(())
+}
+
+// === Main Entry Point ===
+
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => exit_with_usage_error(e, &args),
+    };
+    if cli.output.as_deref() == Some("json") {
+        cli.json = true;
+    }
+
+    meta_cli::telemetry::init(cli.trace_endpoint.as_deref(), "meta", &args);
+    let json = cli.json;
+    let result = run_cli(cli);
+    meta_cli::telemetry::shutdown(result.as_ref().err());
+
+    let Err(e) = result else {
+        return;
+    };
+    // The reason was already shown, e.g. by a --passthrough command
+    if let Some(code) = meta_cli::error::exit_request(&e) {
+        std::process::exit(code);
+    }
+    // Errors carry a stable code: the exit code, and `{"error": ...}` with --json
+    let report = meta_cli::error::classify(&e);
+    if json {
+        print_json_error(&report);
+    } else {
+        eprintln!("Error: {e:?}");
+    }
+    std::process::exit(report.exit_code);
+}
+
+/// Report a command line clap rejected as a `USAGE` error; help and
+/// version requests print as usual and exit 0.
+fn exit_with_usage_error(e: clap::Error, args: &[String]) -> ! {
+    use clap::error::ErrorKind;
+    if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) {
+        e.exit();
+    }
+    let json = args
+        .iter()
+        .take_while(|a| *a != "--")
+        .any(|a| a == "--json" || a == "--output=json");
+    if !json {
+        let _ = e.print();
+    }
+    let usage = MetaError::Usage(e.render().to_string().trim().to_string());
+    let report = meta_cli::error::classify(&usage.into());
+    if json {
+        print_json_error(&report);
+    }
+    std::process::exit(report.exit_code);
+}
+
+fn print_json_error(report: &meta_cli::error::ErrorReport) {
+    let output = serde_json::json!({ "error": report });
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&output).unwrap_or_default()
+    );
+}
+
+fn run_cli(mut cli: Cli) -> Result<()> {
+    // Like `git -C`: config, plugin and worktree discovery all start from
+    // the current directory, so switching it up front covers all of them
+    let cwd = match &cli.workspace {
+        Some(name) => Some(meta_cli::workspaces::resolve(name)?),
+        None => cli.cwd.clone().or_else(|| {
+            std::env::var_os("META_CWD")
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        }),
+    };
+    if let Some(dir) = cwd {
+        std::env::set_current_dir(&dir)
+            .map_err(|e| anyhow::anyhow!("Cannot change to '{}': {e}", dir.display()))?;
+    }
+    if let Some((config_path, _)) = find_meta_config(&std::env::current_dir()?, cli.config.as_ref())
+    {
+        if let Some(root) = config_path.parent() {
+            meta_cli::workspaces::record(root);
+        }
+    }
+
+    log::debug!("cli.json = {}", cli.json);
+    registry::set_offline(cli.offline);
+
+    // Check for orphaned nested meta repo and warn the user
+    check_and_warn_orphan();
+
+    // Resolve the concurrency limit once so every execution path sees the same value
+    cli.jobs = Some(resolve_jobs(cli.jobs)?);
+
+    // Discover plugins early to handle --help requests and plugin listing
+    let mut subprocess_plugins = SubprocessPluginManager::new();
+    subprocess_plugins.discover_plugins(cli.verbose)?;
+    subprocess_plugins.set_max_parallel(cli.jobs);
+    subprocess_plugins.set_command_policy(
+        find_meta_config(&std::env::current_dir()?, cli.config.as_ref()).map(|(path, _)| path),
+    );
+
+    // Handle --help flag at top level
+    if cli.help && cli.command.is_none() {
+        print_help_with_plugins(&subprocess_plugins, false);
+        return Ok(());
+    }
+
+    // Take command out so we can move subcommand args while still borrowing cli
+    let command = cli.command.take();
+    match command {
+        None => {
+            print_help_with_plugins(&subprocess_plugins, false);
+            Ok(())
+        }
+        Some(Commands::Agent(args)) => match args.command {
+            Some(AgentCommands::Guard { command: None }) => meta_cli::agent_guard::handle_guard(),
+            Some(AgentCommands::Guard {
+                command: Some(GuardCommands::Install { force }),
+            }) => init::handle_guard_install(force, cli.verbose),
+            Some(AgentCommands::Init { force }) => init::handle_agent_init(force, cli.verbose),
+            Some(AgentCommands::Score { session, recent }) => {
+                meta_cli::agent_score::handle_score(session, recent, cli.json, cli.verbose)
+            }
+            None => {
+                eprintln!("Usage: meta agent <command>");
+                eprintln!();
+                eprintln!("Commands:");
+                eprintln!(
+                    "  guard   Evaluate a command for destructive patterns (PreToolUse hook)"
+                );
+                eprintln!("          'meta agent guard install' sets up the hook");
+                eprintln!("  init    Set up this workspace for agents: context and guard hooks, and permissions");
+                eprintln!("  score   Score Claude Code sessions for agent effectiveness");
+                Ok(())
+            }
+        },
+        Some(Commands::Branch(args)) => {
+            let (op, name) = match args.command {
+                Some(BranchCommands::Create { name }) => (meta_cli::branch::BranchOp::Create, name),
+                Some(BranchCommands::Switch { name }) => (meta_cli::branch::BranchOp::Switch, name),
+                Some(BranchCommands::Delete { name, force }) => {
+                    (meta_cli::branch::BranchOp::Delete { force }, name)
+                }
+                None => {
+                    eprintln!("Usage: meta branch <command> <name>");
+                    eprintln!();
+                    eprintln!("Commands:");
+                    eprintln!("  create   Create a branch at HEAD and switch to it");
+                    eprintln!("  switch   Switch to an existing branch (repos must be clean)");
+                    eprintln!("  delete   Delete a branch that is not checked out");
+                    eprintln!();
+                    eprintln!("If the operation fails in any repo, the others are rolled back.");
+                    return Ok(());
+                }
+            };
+            let (_, targets) = workspace_git_targets(&cli)?;
+            meta_cli::branch::handle_branch(op, &name, &targets, cli.json, cli.dry_run)
+        }
+        Some(Commands::Clone(args)) => {
+            let mut options = meta_cli::clone::CloneOptions::new(args.url);
+            options.directory = args.directory;
+            options.depth = cli.depth;
+            options.include_only = args.include_only;
+            options.tags = cli
+                .tag
+                .as_deref()
+                .map(|t| t.split(',').map(|s| s.trim().to_string()).collect())
+                .unwrap_or_default();
+            options.retries = args.retries;
+            options.parallel = !cli.sequential;
+            options.jobs = cli.jobs;
+            options.recursive = cli.recursive;
+            options.dry_run = cli.dry_run;
+            meta_cli::clone::handle_clone(&options, cli.json)
+        }
+        Some(Commands::Config(args)) => match args.command {
+            Some(ConfigCommands::Validate) => {
+                meta_cli::schema::handle_validate(cli.config.as_ref(), cli.json, cli.strict)
+            }
+            Some(ConfigCommands::Show { origin }) => {
+                config::handle_show(cli.config.as_ref(), origin, cli.json)
+            }
+            Some(ConfigCommands::Get { key, user }) => {
+                config::handle_get(cli.config.as_ref(), &key, user, cli.json)
+            }
+            Some(ConfigCommands::Set { key, value, user }) => {
+                config::handle_set(cli.config.as_ref(), &key, &value, user, cli.json)
+            }
+            Some(ConfigCommands::Explain { .. }) => {
+                meta_cli::dotenv::handle_explain(cli.config.as_ref(), cli.json)
+            }
+            None => {
+                eprintln!("Usage: meta config <command>");
+                eprintln!();
+                eprintln!("Commands:");
+                eprintln!("  validate   Check the .meta file for errors and unknown keys");
+                eprintln!("  show       Show the effective configuration (--origin for sources)");
+                eprintln!("  get        Print the value of a key");
+                eprintln!("  set        Set a key in .meta (--user for ~/.meta/config.yaml)");
+                eprintln!("  explain    Explain how settings are resolved (env)");
+                Ok(())
+            }
+        },
+        Some(Commands::Context(args)) => {
+            let options = meta_cli::context::ContextOptions {
+                no_status: args.no_status,
+                no_cache: args.no_cache,
+                tag_filter: cli.tag.clone(),
+                profile: args.profile.parse()?,
+                budget: args.budget.as_deref().map(str::parse).transpose()?,
+                watch: args.watch,
+            };
+            meta_cli::context::handle_context(&options, cli.json, cli.verbose)
+        }
+        Some(Commands::Daemon(args)) => match args.command {
+            DaemonCommands::Start { foreground } => meta_cli::daemon::handle_start(foreground),
+            DaemonCommands::Stop => meta_cli::daemon::handle_stop(),
+            DaemonCommands::Status => meta_cli::daemon::handle_status(cli.json),
+        },
+        Some(Commands::History(args)) => match args.command {
+            None => meta_cli::history::handle_list(20, cli.json),
+            Some(HistoryCommands::List { limit }) => {
+                meta_cli::history::handle_list(limit, cli.json)
+            }
+            Some(HistoryCommands::Show { id }) => meta_cli::history::handle_show(id, cli.json),
+            Some(HistoryCommands::Rerun { id, failed }) => handle_history_rerun(id, failed, &cli),
+        },
+        Some(Commands::Init(args)) => {
+            let cmd = match args.command {
+                None if cli.help => init::InitCommand::None,
+                None => init::InitCommand::Workspace {
+                    format: args.format.parse()?,
+                    import: args.import,
+                    max_depth: cli.max_depth.unwrap_or(1) as usize,
+                    force: args.force,
+                },
+                Some(InitCommands::Claude { force, update }) => {
+                    init::InitCommand::Claude { force, update }
+                }
+            };
+            init::handle_init_command(cmd, cli.verbose)
+        }
+        Some(Commands::Metrics(args)) => match args.command {
+            None => meta_cli::metrics::handle_show(cli.json),
+            Some(MetricsCommands::Serve { port, bind }) => {
+                meta_cli::metrics::handle_serve(&bind, port)
+            }
+            Some(MetricsCommands::Write { path }) => meta_cli::metrics::handle_write(&path),
+        },
+        Some(Commands::Open(args)) => {
+            let cwd = std::env::current_dir()?;
+            let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+                return Err(MetaError::ConfigNotFound.into());
+            };
+            let target = if args.remote {
+                meta_cli::open::Target::Remote
+            } else if args.pr {
+                meta_cli::open::Target::Pr
+            } else {
+                meta_cli::open::Target::Editor
+            };
+            meta_cli::open::handle_open(&config_path, &args.alias, target)
+        }
+        Some(Commands::Plugin(args)) => {
+            handle_plugin_command(args.command, cli.verbose, cli.json, &subprocess_plugins)
+        }
+        Some(Commands::Status) => {
+            meta_cli::status::handle_status(cli.json, cli.tag.as_deref(), cli.primary, cli.verbose)
+        }
+        Some(Commands::Sync(args)) => {
+            let cwd = std::env::current_dir()?;
+            let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+                return Err(MetaError::ConfigNotFound.into());
+            };
+            let options = meta_cli::sync::SyncOptions {
+                prune: args.prune,
+                dry_run: cli.dry_run,
+                parallel: !cli.sequential,
+                jobs: cli.jobs,
+            };
+            meta_cli::sync::handle_sync(&config_path, &options, cli.json)
+        }
+        Some(Commands::Freeze(args)) => {
+            let (meta_dir, targets) = workspace_git_targets(&cli)?;
+            meta_cli::freeze::handle_freeze(&meta_dir, &targets, args.file.as_deref(), cli.json)
+        }
+        Some(Commands::Diff(args)) => {
+            let (meta_dir, targets) = workspace_git_targets(&cli)?;
+            meta_cli::diff::handle_diff(&meta_dir, &targets, &args.from, &args.to, cli.json)
+        }
+        Some(Commands::Thaw(args)) => {
+            let (meta_dir, _) = workspace_git_targets(&cli)?;
+            meta_cli::freeze::handle_thaw(
+                &meta_dir,
+                &args.lockfile,
+                args.branch.as_deref(),
+                cli.json,
+                cli.dry_run,
+            )
+        }
+        Some(Commands::CheckBranches(args)) => {
+            let cwd = std::env::current_dir()?;
+            let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+                return Err(MetaError::ConfigNotFound.into());
+            };
+            meta_cli::branch_check::handle_check_branches(&config_path, args.fix, cli.json)
+        }
+        Some(Commands::Doctor) => {
+            let cwd = std::env::current_dir()?;
+            let config_path = find_meta_config(&cwd, cli.config.as_ref()).map(|(path, _)| path);
+            let plugins = subprocess_plugins.doctor().ok();
+            meta_cli::doctor::handle_doctor(
+                config_path.as_deref(),
+                plugins.as_ref(),
+                &meta_cli::doctor::DoctorOptions {
+                    offline: cli.offline,
+                },
+                cli.json,
+            )
+        }
+        Some(Commands::Graph(args)) => {
+            let cwd = std::env::current_dir()?;
+            let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+                return Err(MetaError::ConfigNotFound.into());
+            };
+            let format = if cli.json {
+                meta_cli::graph::GraphFormat::Json
+            } else {
+                args.format.parse()?
+            };
+            meta_cli::graph::handle_graph(&config_path, format, args.focus.as_deref())
+        }
+        Some(Commands::Grep(args)) => handle_grep(args, &cli),
+        Some(Commands::Undo(args)) => {
+            meta_cli::journal::handle_undo(args.id.as_deref(), cli.dry_run, cli.json)
+        }
+        Some(Commands::Commit(args)) => handle_commit(args, &cli),
+        Some(Commands::Impact(args)) => {
+            let cwd = std::env::current_dir()?;
+            let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+                return Err(MetaError::ConfigNotFound.into());
+            };
+            meta_cli::impact::handle_impact(
+                &config_path,
+                &args.repos,
+                cli.changed_since.as_deref(),
+                cli.json,
+            )
+        }
+        Some(Commands::Watch(args)) => handle_watch(args, &cli),
+        Some(Commands::Workspaces(args)) => match args.command {
+            None | Some(WorkspacesCommands::List) => meta_cli::workspaces::handle_list(cli.json),
+            Some(WorkspacesCommands::Switch { name }) => {
+                meta_cli::workspaces::handle_switch(&name, cli.json)
+            }
+        },
+        Some(Commands::Help(args)) => {
+            handle_help(&args.topic, !args.no_pager, cli.json, &subprocess_plugins)
+        }
+        Some(Commands::Exec(args)) => {
+            // Handle help flag for exec command specifically
+            if cli.help {
+                println!("meta exec - Run any command across all repos");
+                println!();
+                println!("Usage: meta exec [OPTIONS] -- <COMMAND>...");
+                println!("       meta exec --script <FILE> [-- <ARGS>...]");
+                println!();
+                println!("Arguments:");
+                println!("  <COMMAND>...    Command and arguments to run");
+                println!();
+                println!("Options:");
+                println!("  --script <FILE>  Run a script file in every repo (its #! line picks the interpreter, sh otherwise)");
+                println!();
+                println!("The command runs in each project directory defined in .meta.");
+                println!("Use this for any command not explicitly handled by a plugin.");
+                println!();
+                println!("Examples:");
+                println!("  meta exec -- npm install");
+                println!("  meta exec -- git fetch --all");
+                println!("  meta exec -- make clean");
+                println!("  meta exec --include api,web -- docker-compose up -d");
+                println!("  meta --include 'services/*' --exclude re:-legacy$ exec -- make");
+                println!("  meta --stream exec -- cargo build");
+                println!("  meta --ui tui exec -- cargo build");
+                println!("  meta --summary-only exec -- git fetch");
+                println!("  meta --output ndjson exec -- cargo test");
+                println!("  meta --report junit=meta-junit.xml exec -- cargo test");
+                println!("  meta --fail-fast --sequential exec -- cargo test");
+                println!("  meta -j 4 exec -- npm ci");
+                println!("  meta --dry-run --tag backend exec -- make deploy");
+                println!("  meta --interactive exec -- git pull");
+                println!("  meta --order topo exec -- cargo build");
+                println!("  meta --shell \"bash -lc\" exec -- nvm use");
+                println!("  meta --timeout 10m exec -- npm test");
+                println!("  meta --changed-since origin/main exec -- cargo test");
+                println!("  meta --changed-since last-success exec -- npm test");
+                println!("  meta exec --script scripts/bump-deps.sh -- --minor");
+                return Ok(());
+            }
+            match args.script {
+                Some(path) => {
+                    // Kept alive until every repo has run the copy
+                    let script = meta_cli::script::Script::prepare(&path)?;
+                    let command = vec![script.command(&args.command)];
+                    handle_command_dispatch(command, &cli, &subprocess_plugins, true)
+                }
+                None => handle_command_dispatch(args.command, &cli, &subprocess_plugins, true),
+            }
+        }
+        Some(Commands::Project(args)) => match args.command {
+            Some(_) if cli.help => {
+                print_project_help();
+                Ok(())
+            }
+            Some(ProjectCommands::Add {
+                alias,
+                path,
+                url,
+                tags,
+            }) => meta_cli::project::handle_add(
+                cli.config.as_ref(),
+                &alias,
+                &path,
+                url.as_deref(),
+                &tags,
+            ),
+            Some(ProjectCommands::Remove { alias, delete_dir }) => {
+                meta_cli::project::handle_remove(cli.config.as_ref(), &alias, delete_dir)
+            }
+            Some(ProjectCommands::Rename { alias, new_alias }) => {
+                meta_cli::project::handle_rename(cli.config.as_ref(), &alias, &new_alias)
+            }
+            // Everything else belongs to the project plugin
+            Some(ProjectCommands::External(rest)) => {
+                let args = std::iter::once("project".to_string()).chain(rest).collect();
+                handle_external(args, &mut cli, &subprocess_plugins)
+            }
+            None => {
+                print_project_help();
+                if let Some(help_text) = subprocess_plugins.get_plugin_help("project") {
+                    println!();
+                    println!("{help_text}");
+                }
+                Ok(())
+            }
+        },
+        Some(Commands::Replace(args)) => handle_replace(args, &cli),
+        Some(Commands::Release(args)) => match args.command {
+            Some(ReleaseCommands::Tag {
+                version,
+                message,
+                sign,
+                push,
+            }) => {
+                let (meta_dir, targets) = workspace_git_targets(&cli)?;
+                let options = meta_cli::release::TagOptions {
+                    message,
+                    sign,
+                    push,
+                    dry_run: cli.dry_run,
+                    notify: cli.notify.clone(),
+                };
+                meta_cli::release::handle_tag(&meta_dir, &version, &targets, &options, cli.json)
+            }
+            Some(ReleaseCommands::List) => {
+                meta_cli::release::handle_list(&workspace_git_targets(&cli)?.0, cli.json)
+            }
+            Some(ReleaseCommands::Show { version }) => {
+                meta_cli::release::handle_show(&workspace_git_targets(&cli)?.0, &version, cli.json)
+            }
+            None => {
+                eprintln!("Usage: meta release <command>");
+                eprintln!();
+                eprintln!("Commands:");
+                eprintln!("  tag    Create an annotated tag in every repo and record it");
+                eprintln!("  list   List recorded releases");
+                eprintln!("  show   Show the commit each repo was tagged at");
+                Ok(())
+            }
+        },
+        Some(Commands::Run(args)) => {
+            if cli.help {
+                print_run_help();
+                return Ok(());
+            }
+            handle_run(args, &cli)
+        }
+        Some(Commands::Worktree(args)) => handle_worktree(args, &mut cli, &subprocess_plugins),
+        Some(Commands::External(args)) => handle_external(args, &mut cli, &subprocess_plugins),
+    }
+}
+
+// === Help Topics ===
+
+/// `meta help [topic...]`: the command list plus topics, or one topic.
+///
+/// A name is looked up as a built-in topic, a plugin topic, a built-in
+/// command (`meta help plugin install`), then a plugin command.
+fn handle_help(
+    topic: &[String],
+    use_pager: bool,
+    json: bool,
+    plugins: &SubprocessPluginManager,
+) -> Result<()> {
+    let builtin = help::builtin_topics();
+    let plugin_topics = plugins.help_topics();
+
+    if topic.is_empty() {
+        if json {
+            let mut topics: Vec<serde_json::Value> = builtin
+                .iter()
+                .map(|t| serde_json::json!({"name": t.name, "summary": t.summary, "source": "builtin"}))
+                .collect();
+            for (plugin, t) in &plugin_topics {
+                if help::find(&builtin, &t.name).is_none() {
+                    topics.push(
+                        serde_json::json!({"name": t.name, "summary": t.summary, "source": plugin}),
+                    );
+                }
+            }
+            println!("{}", serde_json::to_string_pretty(&topics)?);
+            return Ok(());
+        }
+        let mut text = Vec::new();
+        write_help_with_plugin_commands(plugins, &mut text)?;
+        let mut text = String::from_utf8_lossy(&text).into_owned();
+        text.push('\n');
+        text.push_str(&help::render_topic_list(&builtin, &plugin_topics));
+        help::page(&text, use_pager);
+        return Ok(());
+    }
+
+    let name = topic.join(" ");
+    if let Some(t) = help::find(&builtin, &name).or_else(|| {
+        plugin_topics
+            .iter()
+            .map(|(_, t)| t)
+            .find(|t| t.name.eq_ignore_ascii_case(&name))
+    }) {
+        help::page(&t.body, use_pager);
+        return Ok(());
+    }
+
+    // Built-in commands, down to the deepest subcommand named
+    let mut cmd = Cli::command();
+    // Building fills in each subcommand's full usage ("meta plugin install")
+    cmd.build();
+    if let Some(mut current) = cmd.find_subcommand(&topic[0]).cloned() {
+        for part in &topic[1..] {
+            match current.find_subcommand(part) {
+                Some(sub) => current = sub.clone(),
+                None => break,
+            }
+        }
+        let rendered = current.render_long_help();
+        let text = if console::Term::stdout().is_term() {
+            rendered.ansi().to_string()
+        } else {
+            rendered.to_string()
+        };
+        help::page(&text, use_pager);
+        return Ok(());
+    }
+
+    if let Some(mut text) = plugins.get_plugin_help(&topic[0]) {
+        let examples = plugins.command_examples(&name);
+        // Fallback help already lists them
+        let listed = text.contains(&format!("EXAMPLES ({name}):"));
+        if !examples.is_empty() && !listed {
+            text.push_str(&format!("\nEXAMPLES ({name}):\n"));
+            for example in examples {
+                text.push_str(&format!("    {example}\n"));
+            }
+        }
+        help::page(&text, use_pager);
+        return Ok(());
+    }
+
+    anyhow::bail!("No help topic or command named '{name}'. Run 'meta help' to list topics.")
+}
+
+fn print_run_help() {
+    println!("meta run - Run a named task from the .meta file across all repos");
+    println!();
+    println!("USAGE:");
+    println!("    meta run [task] [-- <extra args>...]");
+    println!();
+    println!("Tasks map a name to a default command plus per-project overrides.");
+    println!("A null override skips that project; \".\" runs the task in the meta repo.");
+    println!();
+    println!("    \"tasks\": {{");
+    println!(
+        "      \"build\": {{ \"default\": \"make\", \"api\": \"cargo build\", \"docs\": null }},"
+    );
+    println!("      \"lint\": \"npm run lint\"");
+    println!("    }}");
+    println!();
+    println!("EXAMPLES:");
+    println!("    meta run                      List tasks");
+    println!("    meta run build");
+    println!("    meta --parallel run test -- --verbose");
+    println!("    meta --json --tag backend run build");
+    println!("    meta --order topo run build    Build dependencies first");
+}
+
+/// Run a `tasks` entry from .meta in every matching project.
+fn handle_run(args: RunArgs, cli: &Cli) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+        return Err(MetaError::ConfigNotFound.into());
+    };
+    let tasks = meta_cli::tasks::load_tasks(&config_path)?;
+
+    let Some(task_name) = args.task else {
+        if cli.json {
+            let names: Vec<&str> = tasks.iter().map(|t| t.name.as_str()).collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({ "tasks": names }))?
+            );
+        } else if tasks.is_empty() {
+            println!(
+                "No tasks defined. Add a \"tasks\" section to {}.",
+                config_path.display()
+            );
+        } else {
+            println!("Tasks:");
+            for task in &tasks {
+                match &task.default {
+                    Some(default) => println!("  {:<16} {}", task.name, default.dimmed()),
+                    None => println!("  {}", task.name),
                 }
             }
         }
+        return Ok(());
+    };
 
-        let help = arg.get_help().map(|s| s.to_string()).unwrap_or_default();
+    let Some(task) = tasks.iter().find(|t| t.name == task_name) else {
+        let names: Vec<&str> = tasks.iter().map(|t| t.name.as_str()).collect();
+        anyhow::bail!(
+            "Unknown task '{task_name}'. Available tasks: {}",
+            if names.is_empty() {
+                "(none)".to_string()
+            } else {
+                names.join(", ")
+            }
+        );
+    };
 
-        if !flag_str.is_empty() {
-            writeln!(w, "  {:<38}  {}", flag_str, help)?;
+    let meta_dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+    let (mut meta_projects, _ignore) = parse_meta_config(&config_path)?;
+    meta_cli::lang::add_language_tags(&mut meta_projects, meta_dir);
+    let projects: Vec<&ProjectInfo> = match cli.tag {
+        Some(ref tag_filter) => meta_projects
+            .iter()
+            .filter(|p| meta_cli::filter::matches_tags(&p.tags, tag_filter))
+            .collect(),
+        None => meta_projects.iter().collect(),
+    };
+
+    let mut plan = meta_cli::tasks::plan(task, meta_dir, &projects, &args.args);
+    let dirs: Vec<String> = plan.commands.iter().map(|c| c.dir.clone()).collect();
+    let kept = filter_dirs(dirs, &meta_projects, Some(meta_dir), cli)?;
+    plan.commands.retain(|c| kept.contains(&c.dir));
+
+    if cli.verbose && !cli.json {
+        for name in &plan.skipped {
+            eprintln!("{} {name} (no '{task_name}' command)", "skip".dimmed());
         }
     }
+    if plan.commands.is_empty() {
+        eprintln!(
+            "{}: task '{task_name}' has no commands for the selected projects",
+            "warning".yellow().bold()
+        );
+        return Ok(());
+    }
 
-    // Standard help/version
-    writeln!(w, "  {:<38}  Print help", "-h, --help")?;
-    writeln!(w, "  {:<38}  Print version", "-V, --version")?;
-
-    Ok(())
+    let run_config = cli_run_config(cli, Some(meta_dir.to_path_buf()));
+    run_commands(&run_config, plan.commands, cli)
 }
 
-// === Main Entry Point ===
-
-fn main() -> Result<()> {
-    env_logger::init();
+/// Replay the commands recorded for a previous run.
+fn handle_history_rerun(id: u64, failed_only: bool, cli: &Cli) -> Result<()> {
+    let record = meta_cli::history::find(id)?;
+    let commands = record.commands(failed_only);
+    if commands.is_empty() {
+        println!("Nothing to re-run: every repo in run {id} succeeded");
+        return Ok(());
+    }
+    if !cli.silent && !cli.json {
+        eprintln!(
+            "Re-running run {id}: meta {} ({} repo(s))",
+            record.args.join(" "),
+            commands.len()
+        );
+    }
 
-    let mut cli = Cli::parse();
+    let cwd = std::env::current_dir()?;
+    let root_dir = find_meta_config(&cwd, cli.config.as_ref())
+        .and_then(|(path, _)| path.parent().map(|p| p.to_path_buf()));
+    run_commands(&cli_run_config(cli, root_dir), commands, cli)
+}
 
-    log::debug!("cli.json = {}", cli.json);
+/// Output mode for a non-JSON run: `--passthrough`, `--output ndjson`,
+/// `--summary-only`, `--stream`, or the `--ui tui` view when stderr is a
+/// terminal.
+fn text_output_mode(cli: &Cli) -> runner::OutputMode {
+    if cli.passthrough {
+        runner::OutputMode::Passthrough
+    } else if cli.output.as_deref() == Some("ndjson") {
+        runner::OutputMode::Ndjson
+    } else if cli.summary_only {
+        runner::OutputMode::Summary
+    } else if cli.stream {
+        runner::OutputMode::Stream
+    } else if cli.ui.as_deref() == Some("tui") && meta_cli::progress::supported() {
+        runner::OutputMode::Tui
+    } else {
+        runner::OutputMode::Grouped
+    }
+}
 
-    // Check for orphaned nested meta repo and warn the user
-    check_and_warn_orphan();
+/// Runner options from the global flags, for commands meta runs itself.
+fn cli_run_config(cli: &Cli, root_dir: Option<PathBuf>) -> runner::RunConfig {
+    runner::RunConfig {
+        parallel: resolve_parallel(cli),
+        max_parallel: cli.jobs,
+        dry_run: cli.dry_run,
+        silent: cli.silent,
+        verbose: cli.verbose,
+        fail_fast: cli.fail_fast,
+        output: if cli.json {
+            runner::OutputMode::Json
+        } else {
+            text_output_mode(cli)
+        },
+        root_dir,
+        env: None,
+        shell: None,
+        timeout: None,
+        stdin: runner::StdinMode::Null,
+        confirm: false,
+        workdirs: std::collections::HashMap::new(),
+    }
+}
 
-    // Discover plugins early to handle --help requests and plugin listing
-    let mut subprocess_plugins = SubprocessPluginManager::new();
-    subprocess_plugins.discover_plugins(cli.verbose)?;
+fn print_project_help() {
+    println!("meta project - Manage the projects in the .meta file");
+    println!();
+    println!("USAGE:");
+    println!("    meta project <command>");
+    println!();
+    println!("COMMANDS:");
+    println!("    add <alias> <path> [--url <git-url>] [--tags <a,b>]");
+    println!("                                            Add a project");
+    println!("    remove <alias> [--delete-dir]           Remove a project");
+    println!("    rename <alias> <new-alias>              Rename a project (alias: mv)");
+    println!();
+    println!("Other project commands (list, check, sync, ...) come from the project plugin.");
+    println!();
+    println!("EXAMPLES:");
+    println!("    meta project add api ./api");
+    println!("    meta project add web apps/web --url git@github.com:org/web.git");
+    println!("    meta project add api ./api --tags backend,rust");
+    println!("    meta project remove old-service --delete-dir");
+    println!("    meta project rename api backend");
+}
 
-    // Handle --help flag at top level
-    if cli.help && cli.command.is_none() {
-        print_help_with_plugins(&subprocess_plugins, false);
-        std::process::exit(0);
+fn handle_worktree(
+    args: WorktreeArgs,
+    cli: &mut Cli,
+    subprocess_plugins: &SubprocessPluginManager,
+) -> Result<()> {
+    match args.command {
+        Some(WorktreeCommands::External(rest)) => {
+            handle_worktree_plugin(rest, cli, subprocess_plugins)
+        }
+        Some(_) if cli.help => {
+            print_worktree_help();
+            Ok(())
+        }
+        Some(WorktreeCommands::Rename { name, new_name }) => {
+            worktree::handle_rename(&worktree_meta_dir(cli)?, &name, &new_name, cli.json)
+        }
+        Some(WorktreeCommands::MergeBack {
+            name,
+            strategy,
+            base,
+            delete,
+        }) => {
+            let meta_dir = worktree_meta_dir(cli)?;
+            let strategy = strategy.parse()?;
+            if delete {
+                // Refuse before merging rather than after
+                worktree::ensure_unlocked(&worktree::set_dir(&meta_dir, &name)?)?;
+            }
+            worktree::handle_merge_back(&meta_dir, &name, base.as_deref(), strategy, cli.json)?;
+            if !delete {
+                return Ok(());
+            }
+            let rest = ["destroy", &name].map(str::to_string).to_vec();
+            handle_worktree_destroy(rest, cli, subprocess_plugins)
+        }
+        Some(WorktreeCommands::Lock { name, reason }) => {
+            worktree::handle_lock(&worktree_meta_dir(cli)?, &name, reason.as_deref(), cli.json)
+        }
+        Some(WorktreeCommands::Du) => {
+            worktree::handle_du(&worktree_meta_dir(cli)?, cli.verbose, cli.json)
+        }
+        Some(WorktreeCommands::Unlock { name }) => {
+            worktree::handle_unlock(&worktree_meta_dir(cli)?, &name, cli.json)
+        }
+        Some(WorktreeCommands::Switch {
+            name,
+            repo,
+            set_current,
+            clear,
+        }) => {
+            let meta_dir = worktree_meta_dir(cli)?;
+            match name {
+                Some(name) if !clear => worktree::handle_switch(
+                    &meta_dir,
+                    &name,
+                    repo.as_deref(),
+                    set_current,
+                    cli.json,
+                ),
+                _ => worktree::handle_clear_current(&meta_dir),
+            }
+        }
+        Some(WorktreeCommands::Push {
+            name,
+            base,
+            create_pr,
+            title,
+            body_file,
+        }) => {
+            let body = body_file
+                .map(|path| {
+                    std::fs::read_to_string(&path)
+                        .with_context(|| format!("Failed to read {}", path.display()))
+                })
+                .transpose()?;
+            let options = worktree::PushOptions {
+                base: &base,
+                create_pr,
+                title: title.as_deref(),
+                body: body.as_deref(),
+            };
+            worktree::handle_push(&worktree_meta_dir(cli)?, &name, &options, cli.json)
+        }
+        None => {
+            print_worktree_help();
+            if let Some(help_text) = subprocess_plugins.get_plugin_help("worktree") {
+                println!();
+                println!("{help_text}");
+            }
+            Ok(())
+        }
     }
+}
 
-    // Take command out so we can move subcommand args while still borrowing cli
-    let command = cli.command.take();
-    match command {
-        None => {
-            print_help_with_plugins(&subprocess_plugins, false);
-            std::process::exit(0);
+/// Worktree commands the plugin provides. meta wraps a few of them:
+/// `create` applies the template afterwards, `destroy` refuses locked sets
+/// and `list` ends with the locks and the last scheduled prune; meta
+/// answers `status --all`, `diff --all` and `exec` across several sets
+/// itself.
+fn handle_worktree_plugin(
+    mut rest: Vec<String>,
+    cli: &mut Cli,
+    subprocess_plugins: &SubprocessPluginManager,
+) -> Result<()> {
+    extract_global_flags(&mut rest, cli);
+    let wants_help = rest.iter().any(|a| a == "--help" || a == "-h");
+    let all = rest.iter().any(|a| a == "--all");
+    match rest.first().map(String::as_str) {
+        Some(command @ ("status" | "diff")) if all && !wants_help => {
+            worktree::handle_all_sets(&worktree_meta_dir(cli)?, command == "diff", cli.json)
         }
-        Some(Commands::Agent(args)) => match args.command {
-            Some(AgentCommands::Guard) => meta_cli::agent_guard::handle_guard(),
-            Some(AgentCommands::Score { session, recent }) => {
-                meta_cli::agent_score::handle_score(session, recent, cli.json, cli.verbose)
-            }
+        Some("exec") if !wants_help => match worktree::MultiSetExec::parse(&rest[1..])? {
+            Some(exec) => handle_multi_set_exec(exec, cli),
             None => {
-                eprintln!("Usage: meta agent <command>");
-                eprintln!();
-                eprintln!("Commands:");
-                eprintln!(
-                    "  guard   Evaluate a command for destructive patterns (PreToolUse hook)"
-                );
-                eprintln!("  score   Score Claude Code sessions for agent effectiveness");
-                Ok(())
+                let args = std::iter::once("worktree".to_string())
+                    .chain(rest)
+                    .collect();
+                handle_external(args, cli, subprocess_plugins)
             }
         },
-        Some(Commands::Context(args)) => {
-            meta_cli::context::handle_context(cli.json, args.no_status, args.no_cache, cli.verbose)
-        }
-        Some(Commands::Init(args)) => {
-            let cmd = match args.command {
-                None => init::InitCommand::None,
-                Some(InitCommands::Claude { force, update }) => {
-                    init::InitCommand::Claude { force, update }
+        Some("create") if !wants_help => handle_worktree_create(rest, cli, subprocess_plugins),
+        Some("destroy") if !wants_help => handle_worktree_destroy(rest, cli, subprocess_plugins),
+        Some("prune") if !wants_help => handle_worktree_prune(rest, cli, subprocess_plugins),
+        Some("list") if !wants_help => {
+            let args = std::iter::once("worktree".to_string())
+                .chain(rest)
+                .collect();
+            handle_external(args, cli, subprocess_plugins)?;
+            if !cli.json {
+                let meta_dir = worktree_meta_dir(cli)?;
+                let root = worktree::worktrees_root(&meta_dir);
+                print!(
+                    "{}",
+                    worktree::format_locks(&worktree::locked_sets(), &root)
+                );
+                if let Some(run) = worktree::last_prune(&meta_dir) {
+                    print!("\n{}", worktree::format_last_prune(&run));
                 }
-            };
-            init::handle_init_command(cmd, cli.verbose)
+            }
+            Ok(())
         }
-        Some(Commands::Plugin(args)) => {
-            handle_plugin_command(args.command, cli.verbose, cli.json, &subprocess_plugins)
+        _ => {
+            let args = std::iter::once("worktree".to_string())
+                .chain(rest)
+                .collect();
+            handle_external(args, cli, subprocess_plugins)
         }
-        Some(Commands::Exec(args)) => {
-            // Handle help flag for exec command specifically
-            if cli.help {
-                println!("meta exec - Run any command across all repos");
-                println!();
-                println!("Usage: meta exec [OPTIONS] -- <COMMAND>...");
-                println!();
-                println!("Arguments:");
-                println!("  <COMMAND>...    Command and arguments to run");
-                println!();
-                println!("The command runs in each project directory defined in .meta.");
-                println!("Use this for any command not explicitly handled by a plugin.");
-                println!();
-                println!("Examples:");
-                println!("  meta exec -- npm install");
-                println!("  meta exec -- git fetch --all");
-                println!("  meta exec -- make clean");
-                println!("  meta exec --include api,web -- docker-compose up -d");
-                std::process::exit(0);
-            }
-            handle_command_dispatch(args.command, &cli, &subprocess_plugins, true)
-        }
-        Some(Commands::External(args)) => {
-            // clap doesn't capture global flags that appear after an external
-            // subcommand name. Extract long-form global flags here so they
-            // work in both positions (before and after the subcommand).
-            let mut args = args;
-            extract_global_flags(&mut args, &mut cli);
-
-            // Check for plugin help request (explicit --help flag)
-            // For bare commands like "worktree", let them pass through to plugin execution
-            // so the plugin can show command-specific help (e.g., worktree options)
-            if let Some(first) = args.first() {
-                let wants_help = args.iter().any(|a| a == "--help" || a == "-h");
-                let is_bare = args.len() == 1;
-
-                // Only intercept with get_plugin_help for:
-                // 1. Explicit --help requests (e.g., "meta git --help")
-                // 2. Bare plugin names (e.g., "meta git"), NOT bare promoted commands
-                let promoted_commands: Vec<String> = subprocess_plugins
-                    .get_promoted_commands()
-                    .iter()
-                    .map(|(name, _, _)| name.clone())
-                    .collect();
-                let is_promoted = promoted_commands.contains(&first.to_string());
+    }
+}
 
-                // For promoted commands (like "worktree"), let them execute normally
-                // so the plugin can display command-specific help
-                if wants_help || (is_bare && !is_promoted) {
-                    if let Some(help_text) = subprocess_plugins.get_plugin_help(first) {
-                        println!("{help_text}");
-                        return Ok(());
-                    }
-                }
+/// `meta worktree exec --all-sets` or `exec a,b`: the command in every repo
+/// of every set, through the usual runner.
+fn handle_multi_set_exec(exec: worktree::MultiSetExec, cli: &Cli) -> Result<()> {
+    let meta_dir = worktree_meta_dir(cli)?;
+    let sets = worktree::resolve_sets(&worktree::worktrees_root(&meta_dir), &exec.names, exec.all)?;
+    let commands = worktree::multi_set_commands(&sets, &exec.command, &exec.include)?;
+    let mut run_config = cli_run_config(cli, Some(meta_dir));
+    run_config.parallel |= exec.parallel;
+    run_commands(&run_config, commands, cli)
+}
+
+/// `meta worktree create`: meta clones the set's projects that are not
+/// checked out yet, the plugin creates the set, then meta applies the
+/// workspace's `worktree.template` and runs its setup commands.
+fn handle_worktree_create(
+    mut rest: Vec<String>,
+    cli: &mut Cli,
+    subprocess_plugins: &SubprocessPluginManager,
+) -> Result<()> {
+    let clone_mode = worktree::take_clone_mode(&mut rest)?;
+    let no_verify = worktree::take_no_verify(&mut rest);
+    let name = worktree::name_arg(&rest[1..]).map(str::to_string);
+    let dry_run = cli.dry_run || rest.iter().any(|a| a == "--dry-run");
+    let meta_dir = worktree_meta_dir(cli)?;
+    if !dry_run {
+        let aliases = worktree::create_aliases(&meta_dir, &rest[1..])?;
+        if let Some(name) = &name {
+            worktree::fire_pre_create(&meta_dir, name, &aliases, no_verify)?;
+        }
+        worktree::clone_missing_repos(&meta_dir, &aliases, cli.silent || cli.json)?;
+    }
+    let post_create = name.filter(|_| !dry_run).map(|name| {
+        let hook = worktree::PostHook::start(&meta_dir, "post-create", &name);
+        (name, hook)
+    });
+    let args = std::iter::once("worktree".to_string())
+        .chain(rest)
+        .collect();
+    let created = handle_external(args, cli, subprocess_plugins);
+    let Some((name, post_create)) = post_create else {
+        return created;
+    };
+
+    let result = created.and_then(|()| {
+        let commands = worktree::apply_template(&meta_dir, &name, clone_mode)?;
+        if commands.is_empty() {
+            return Ok(());
+        }
+        run_commands(&cli_run_config(cli, Some(meta_dir.clone())), commands, cli)
+    });
+    post_create.fire_created(&meta_dir, result.as_ref().err().map(|e| format!("{e:#}")));
+    result
+}
+
+/// `meta worktree destroy`: refuse locked sets and fire `pre-destroy`,
+/// then let the plugin remove the set.
+fn handle_worktree_destroy(
+    mut rest: Vec<String>,
+    cli: &mut Cli,
+    subprocess_plugins: &SubprocessPluginManager,
+) -> Result<()> {
+    let no_verify = worktree::take_no_verify(&mut rest);
+    let dry_run = cli.dry_run || rest.iter().any(|a| a == "--dry-run");
+    let mut post_destroy = None;
+    if let Some(name) = worktree::name_arg(&rest[1..]) {
+        let meta_dir = worktree_meta_dir(cli)?;
+        worktree::ensure_unlocked(&worktree::worktrees_root(&meta_dir).join(name))?;
+        if !dry_run {
+            worktree::fire_pre_destroy(&meta_dir, name, no_verify)?;
+            let hook = worktree::PostHook::start(&meta_dir, "post-destroy", name);
+            post_destroy = Some((meta_dir, hook));
+        }
+    }
+    let args = std::iter::once("worktree".to_string())
+        .chain(rest)
+        .collect();
+    let destroyed = handle_external(args, cli, subprocess_plugins);
+    if let Some((meta_dir, hook)) = post_destroy {
+        hook.fire_removed(&meta_dir);
+    }
+    destroyed
+}
+
+/// `meta worktree prune`: the plugin removes expired and orphaned sets,
+/// then `post-prune` fires for each set it removed repos from. Locked sets
+/// are kept out of the plugin's store meanwhile, so they survive.
+fn handle_worktree_prune(
+    rest: Vec<String>,
+    cli: &mut Cli,
+    subprocess_plugins: &SubprocessPluginManager,
+) -> Result<()> {
+    let dry_run = cli.dry_run || rest.iter().any(|a| a == "--dry-run");
+    let meta_dir = worktree_meta_dir(cli).ok();
+    let shielded = meta_dir
+        .as_deref()
+        .map(worktree::shield_locked_sets)
+        .transpose()?;
+    let locked = shielded.as_ref().map_or(&[][..], |s| &s.names[..]);
+    if !cli.silent {
+        for name in locked {
+            eprintln!(
+                "{}",
+                format!("Skipping locked worktree set '{name}'").dimmed()
+            );
+        }
+    }
+    let hooks: Vec<_> = meta_dir
+        .iter()
+        .filter(|_| !dry_run)
+        .flat_map(|meta_dir| {
+            worktree::list_sets(&worktree::worktrees_root(meta_dir))
+                .into_iter()
+                .filter(|(name, _)| !locked.contains(name))
+                .map(|(name, _)| worktree::PostHook::start(meta_dir, "post-prune", &name))
+        })
+        .collect();
+    let args = std::iter::once("worktree".to_string())
+        .chain(rest)
+        .collect();
+    let pruned = handle_external(args, cli, subprocess_plugins);
+    if let Some(shielded) = shielded {
+        shielded.restore()?;
+    }
+    if let Some(meta_dir) = &meta_dir {
+        for hook in hooks {
+            hook.fire_removed(meta_dir);
+        }
+    }
+    pruned
+}
+
+fn print_worktree_help() {
+    println!("meta worktree - Manage worktree sets");
+    println!();
+    println!("USAGE:");
+    println!("    meta worktree <command>");
+    println!();
+    println!("COMMANDS:");
+    println!("    rename <name> <new-name>                Rename a set (alias: mv)");
+    println!("    merge-back <name> [--strategy merge|rebase|squash] [--base <branch>] [--delete]");
+    println!("                                            Merge a set's branches back");
+    println!("    push <name> [--base <branch>] [--create-pr] [--title <t>] [--body-file <f>]");
+    println!("                                            Push a set's branches");
+    println!(
+        "    switch <name> [repo] [--set-current]    Print a set's path (and make it current)"
+    );
+    println!("    switch --clear                          Forget the current set");
+    println!("    lock <name> [--reason <text>]           Protect a set from destroy and prune");
+    println!("    unlock <name>                           Remove a set's lock");
+    println!("    du [--verbose]                          Disk used per set (and per repo)");
+    println!("    status --all | diff --all               Summarize every set");
+    println!();
+    println!("Other worktree commands (create, list, destroy, ...) come from the worktree plugin.");
+    println!();
+    println!("EXAMPLES:");
+    println!("    cd \"$(meta worktree switch auth-fix api)\"");
+    println!("    meta worktree rename auth-fix auth-rework");
+    println!("    meta worktree merge-back auth-fix --strategy squash --delete");
+    println!("    meta worktree push auth-fix --create-pr --title \"Fix auth\"");
+    println!("    meta worktree lock auth-fix --reason \"agent running\"");
+}
+
+/// Run an external (plugin-provided) command.
+fn handle_external(
+    mut args: Vec<String>,
+    cli: &mut Cli,
+    subprocess_plugins: &SubprocessPluginManager,
+) -> Result<()> {
+    // clap doesn't capture global flags that appear after an external
+    // subcommand name. Extract long-form global flags here so they
+    // work in both positions (before and after the subcommand).
+    extract_global_flags(&mut args, cli);
+
+    // Check for plugin help request (explicit --help flag)
+    // For bare commands like "worktree", let them pass through to plugin execution
+    // so the plugin can show command-specific help (e.g., worktree options)
+    if let Some(first) = args.first() {
+        let wants_help = args.iter().any(|a| a == "--help" || a == "-h");
+        let is_bare = args.len() == 1;
+
+        // Only intercept with get_plugin_help for:
+        // 1. Explicit --help requests (e.g., "meta git --help")
+        // 2. Bare plugin names (e.g., "meta git"), NOT bare promoted commands
+        let promoted_commands: Vec<String> = subprocess_plugins
+            .get_promoted_commands()
+            .iter()
+            .map(|(name, _, _)| name.clone())
+            .collect();
+        let is_promoted = promoted_commands.contains(&first.to_string());
+
+        // For promoted commands (like "worktree"), let them execute normally
+        // so the plugin can display command-specific help
+        if wants_help || (is_bare && !is_promoted) {
+            if let Some(help_text) = subprocess_plugins.get_plugin_help(first) {
+                println!("{help_text}");
+                return Ok(());
+            }
+            if first == "git" {
+                print!("{}", meta_cli::git::usage());
+                return Ok(());
             }
-            handle_command_dispatch(args, &cli, &subprocess_plugins, false)
         }
     }
+    handle_command_dispatch(args, cli, subprocess_plugins, false)
 }
 
 // === Command Dispatch (shared by exec and external) ===
@@ -523,41 +2432,20 @@ fn handle_command_dispatch(
     is_explicit_exec: bool,
 ) -> Result<()> {
     if command_args.is_empty() {
-        if is_explicit_exec {
-            // Show usage error (help is handled at the caller level via cli.help)
-            eprintln!("Usage: meta exec <command> [args...]");
-        } else {
+        // Help is handled at the caller level via cli.help
+        if !is_explicit_exec {
             print_help_with_plugins(plugins, true);
         }
-        std::process::exit(1);
+        return Err(MetaError::Usage("Usage: meta exec <command> [args...]".to_string()).into());
     }
 
     // All meta flags come from clap globals (before the command).
     // Command args pass through untouched to avoid collisions with
     // identically-named flags (e.g., grep --include, git clone --depth).
-    let include_filters: Vec<String> = cli.include.clone().unwrap_or_default();
-    let exclude_filters: Vec<String> = cli.exclude.clone().unwrap_or_default();
     let recursive = cli.recursive;
     let dry_run = cli.dry_run;
     let depth = cli.depth;
-    // Determine parallel mode: --parallel wins, then --sequential, then config default (true)
-    let parallel = if cli.parallel {
-        log::debug!("parallel=true (--parallel flag)");
-        true
-    } else if cli.sequential {
-        log::debug!("parallel=false (--sequential flag)");
-        false
-    } else {
-        // Load default from .meta config (defaults to parallel: true if not specified)
-        let cwd = std::env::current_dir().unwrap_or_default();
-        let defaults = config::load_meta_defaults(&cwd);
-        log::debug!(
-            "parallel={} (from config defaults, cwd={})",
-            defaults.parallel,
-            cwd.display()
-        );
-        defaults.parallel
-    };
+    let parallel = resolve_parallel(cli);
 
     let command_str = command_args.join(" ");
 
@@ -586,20 +2474,27 @@ fn handle_command_dispatch(
                 println!("{}", "Git clone handled by subprocess plugin.".green());
             }
             return Ok(());
-        } else {
-            eprintln!("Error: No plugin available to handle 'git clone'");
-            eprintln!("Make sure meta-git plugin is installed.");
-            std::process::exit(1);
         }
+
+        // No plugin: fall back to the built-in workspace clone
+        let mut options = meta_cli::clone::CloneOptions::from_git_args(&command_args[2..])?;
+        options.parallel = !cli.sequential;
+        options.jobs = cli.jobs;
+        options.recursive = recursive;
+        options.dry_run = dry_run;
+        return meta_cli::clone::handle_clone(&options, cli.json);
     }
 
     let current_dir = std::env::current_dir()?;
 
-    // Context detection: if cwd is inside a worktree, auto-scope to its repos
+    // Context detection: if cwd is inside a worktree (or the workspace has a
+    // current worktree from `meta worktree switch`), auto-scope to its repos
     if !cli.primary {
-        if let Some((task_name, task_dir, wt_paths)) =
-            worktree::detect_worktree_context(&current_dir)
-        {
+        let context = worktree::detect_worktree_context(&current_dir).or_else(|| {
+            let (config_path, _) = find_meta_config(&current_dir, cli.config.as_ref())?;
+            worktree::current_worktree_context(config_path.parent()?)
+        });
+        if let Some((task_name, task_dir, wt_paths)) = context {
             if cli.verbose {
                 eprintln!(
                     "Detected worktree context: '{}' ({} repos)",
@@ -610,7 +2505,10 @@ fn handle_command_dispatch(
 
             // Try to find .meta config for full feature support
             if let Some((config_path, _format)) = find_meta_config(&task_dir, cli.config.as_ref()) {
-                let (meta_projects, ignore_list) = parse_meta_config(&config_path)?;
+                let (mut meta_projects, ignore_list) = parse_meta_config(&config_path)?;
+                if let Some(meta_dir) = config_path.parent() {
+                    meta_cli::lang::add_language_tags(&mut meta_projects, meta_dir);
+                }
 
                 // Build name→info lookup for tag filtering
                 let project_map: std::collections::HashMap<&str, &ProjectInfo> =
@@ -626,7 +2524,7 @@ fn handle_command_dispatch(
                                 .map(|n| n.to_string_lossy().to_string())
                                 .unwrap_or_else(|| ".".to_string());
                             if let Some(info) = project_map.get(alias.as_str()) {
-                                matches_tag_filter(&info.tags, tag_filter)
+                                meta_cli::filter::matches_tags(&info.tags, tag_filter)
                             } else {
                                 true // Unknown projects pass through
                             }
@@ -636,12 +2534,12 @@ fn handle_command_dispatch(
                     })
                     .map(|p| p.display().to_string())
                     .collect();
+                let wt_directories = filter_dirs(wt_directories, &meta_projects, None, cli)?;
 
                 if wt_directories.is_empty() {
                     eprintln!(
-                        "{}: no projects match tag filter '{}' in worktree '{}'",
+                        "{}: no projects match the filters in worktree '{}'",
                         "warning".yellow().bold(),
-                        cli.tag.as_deref().unwrap_or(""),
                         task_name
                     );
                     return Ok(());
@@ -656,14 +2554,11 @@ fn handle_command_dispatch(
                     );
                 }
 
-                let include_opt = none_if_empty(include_filters.clone());
-                let exclude_opt = none_if_empty(exclude_filters.clone());
-
                 let config = loop_lib::LoopConfig {
                     directories: wt_directories.clone(),
                     ignore: ignore_list,
-                    include_filters: include_opt.clone(),
-                    exclude_filters: exclude_opt.clone(),
+                    include_filters: None,
+                    exclude_filters: None,
                     verbose: cli.verbose,
                     silent: cli.silent,
                     parallel,
@@ -672,7 +2567,7 @@ fn handle_command_dispatch(
                     add_aliases_to_global_looprc: false,
                     spawn_stagger_ms: 0,
                     env: None,
-                    max_parallel: None,
+                    max_parallel: cli.jobs,
                     root_dir: None, // Worktree paths don't use "." convention
                 };
 
@@ -685,8 +2580,8 @@ fn handle_command_dispatch(
                     silent: cli.silent,
                     recursive,
                     depth,
-                    include_filters: include_opt,
-                    exclude_filters: exclude_opt,
+                    include_filters: None,
+                    exclude_filters: None,
                     strict: cli.strict,
                 };
 
@@ -703,9 +2598,16 @@ fn handle_command_dispatch(
                         );
                     }
                 } else if is_explicit_exec {
-                    run(&config, &command_str)?;
+                    run_loop(&config, &command_str, cli)?;
+                } else if meta_cli::git::handles(&command_args) {
+                    let targets = meta_cli::git::targets(&wt_directories, None);
+                    run_native_git(&command_args, &targets, &task_dir, cli, parallel)?;
                 } else {
-                    unrecognized_command_error(&command_args, &command_str, plugins);
+                    return Err(unrecognized_command_error(
+                        &command_args,
+                        &command_str,
+                        plugins,
+                    ));
                 }
                 return Ok(());
             }
@@ -721,15 +2623,13 @@ fn handle_command_dispatch(
 
             let directories: Vec<String> =
                 wt_paths.iter().map(|p| p.display().to_string()).collect();
-
-            let include_opt = none_if_empty(include_filters);
-            let exclude_opt = none_if_empty(exclude_filters);
+            let directories = filter_dirs(directories, &[], None, cli)?;
 
             let config = loop_lib::LoopConfig {
                 directories,
                 ignore: vec![],
-                include_filters: include_opt,
-                exclude_filters: exclude_opt,
+                include_filters: None,
+                exclude_filters: None,
                 verbose: cli.verbose,
                 silent: cli.silent,
                 parallel, // Use the determined parallel mode, not hardcoded false
@@ -738,11 +2638,11 @@ fn handle_command_dispatch(
                 add_aliases_to_global_looprc: false,
                 spawn_stagger_ms: 0,
                 env: None,
-                max_parallel: None,
+                max_parallel: cli.jobs,
                 root_dir: None, // Worktree paths don't use "." convention
             };
 
-            run(&config, &command_str)?;
+            run_loop(&config, &command_str, cli)?;
             return Ok(());
         }
     }
@@ -754,10 +2654,13 @@ fn handle_command_dispatch(
                 .config
                 .as_ref()
                 .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| ".meta / .meta.yaml / .meta.yml".to_string());
-            eprintln!("Error: Could not find meta config file '{config_name}'");
-            eprintln!("Searched from {} up to root", current_dir.display());
-            std::process::exit(1);
+                .unwrap_or_else(|| ".meta / .meta.yaml / .meta.yml / .meta.toml".to_string());
+            return Err(
+                anyhow::Error::new(MetaError::ConfigNotFound).context(format!(
+                    "Could not find meta config file '{config_name}' (searched from {} up to root)",
+                    current_dir.display()
+                )),
+            );
         }
     };
 
@@ -769,7 +2672,8 @@ fn handle_command_dispatch(
         println!("Executing command: {command_str}");
     }
 
-    let (meta_projects, ignore_list) = parse_meta_config(&absolute_path)?;
+    let (mut meta_projects, ignore_list) = parse_meta_config(&absolute_path)?;
+    meta_cli::lang::add_language_tags(&mut meta_projects, meta_dir);
 
     // Filter projects by tags if --tag is specified
     let filtered_projects: Vec<&ProjectInfo> = if let Some(ref tag_filter) = cli.tag {
@@ -781,7 +2685,7 @@ fn handle_command_dispatch(
         }
         meta_projects
             .iter()
-            .filter(|p| matches_tag_filter(&p.tags, tag_filter))
+            .filter(|p| meta_cli::filter::matches_tags(&p.tags, tag_filter))
             .collect()
     } else {
         meta_projects.iter().collect()
@@ -810,16 +2714,16 @@ fn handle_command_dispatch(
         );
     }
 
-    // Prepare filter options (shared by both LoopConfig and PluginRequestOptions)
-    let include_opt = none_if_empty(include_filters);
-    let exclude_opt = none_if_empty(exclude_filters);
+    // Include/exclude patterns are resolved here (with project names and
+    // tags), so loop_lib and plugins receive the final directory list
+    let project_paths = filter_dirs(project_paths, &meta_projects, Some(meta_dir), cli)?;
 
     let config = loop_lib::LoopConfig {
         add_aliases_to_global_looprc: cli.add_aliases_to_global_looprc,
         directories: project_paths.clone(),
         ignore: ignore_list,
-        include_filters: include_opt.clone(),
-        exclude_filters: exclude_opt.clone(),
+        include_filters: None,
+        exclude_filters: None,
         verbose: cli.verbose,
         silent: cli.silent,
         parallel,
@@ -827,7 +2731,7 @@ fn handle_command_dispatch(
         json_output: cli.json,
         spawn_stagger_ms: 0,
         env: None,
-        max_parallel: None,
+        max_parallel: cli.jobs,
         root_dir: Some(meta_dir.to_path_buf()),
     };
 
@@ -840,8 +2744,8 @@ fn handle_command_dispatch(
         silent: cli.silent,
         recursive,
         depth,
-        include_filters: include_opt,
-        exclude_filters: exclude_opt,
+        include_filters: None,
+        exclude_filters: None,
         strict: cli.strict,
     };
 
@@ -861,12 +2765,233 @@ fn handle_command_dispatch(
         if cli.verbose {
             println!("{}", "Running command via loop (explicit exec).".green());
         }
-        run(&config, &command_str)?;
+        run_loop(&config, &command_str, cli)?;
+    } else if meta_cli::git::handles(&command_args) {
+        let targets = meta_cli::git::targets(&project_paths, Some(meta_dir));
+        run_native_git(&command_args, &targets, meta_dir, cli, parallel)?;
     } else {
-        unrecognized_command_error(&command_args, &command_str, plugins);
+        return Err(unrecognized_command_error(
+            &command_args,
+            &command_str,
+            plugins,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Run `meta commit`: commit every dirty project, filling in `{repo}` and
+/// `{branch}` in the message.
+fn handle_commit(args: CommitArgs, cli: &Cli) -> Result<()> {
+    let targets = project_targets(cli, &[])?;
+    let filters = applied_filters(cli)
+        .into_iter()
+        .map(|(flag, value)| format!("{flag} {value}"))
+        .collect();
+    meta_cli::commit::handle_commit(&targets, &args.message, filters, cli.dry_run, cli.json)
+}
+
+fn handle_grep(args: GrepArgs, cli: &Cli) -> Result<()> {
+    let targets = grep_targets(cli, &args.include_only)?;
+    let options = meta_cli::grep::GrepOptions {
+        ignore_case: args.ignore_case,
+        fixed_strings: args.fixed_strings,
+        word: args.word_regexp,
+        files_with_matches: args.files_with_matches,
+        paths: args.paths,
+    };
+    meta_cli::grep::handle_grep(&targets, &args.pattern, &options, cli.json)
+}
+
+fn handle_replace(args: ReplaceArgs, cli: &Cli) -> Result<()> {
+    let targets = grep_targets(cli, &args.include_only)?;
+    let options = meta_cli::replace::ReplaceOptions {
+        fixed_strings: args.fixed_strings,
+        ignore_case: args.ignore_case,
+        paths: args.paths,
+        write: args.write,
+        branch: args.branch,
+        message: args.message,
+    };
+    meta_cli::replace::handle_replace(
+        &targets,
+        &args.pattern,
+        &args.replacement,
+        &options,
+        cli.json,
+    )
+}
+
+fn grep_targets(cli: &Cli, include_only: &[String]) -> Result<Vec<meta_cli::grep::GrepTarget>> {
+    Ok(project_targets(cli, include_only)?
+        .into_iter()
+        .map(|(name, dir)| meta_cli::grep::GrepTarget { name, dir })
+        .collect())
+}
+
+/// The cloned projects selected by the global filters and `--include-only`,
+/// by project name.
+fn project_targets(cli: &Cli, include_only: &[String]) -> Result<Vec<meta_cli::git::Target>> {
+    let cwd = std::env::current_dir()?;
+    let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+        return Err(MetaError::ConfigNotFound.into());
+    };
+    let meta_dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+    let (mut meta_projects, _ignore) = parse_meta_config(&config_path)?;
+    meta_cli::lang::add_language_tags(&mut meta_projects, meta_dir);
+    let dirs: Vec<String> = meta_projects
+        .iter()
+        .filter(|p| {
+            cli.tag
+                .as_deref()
+                .is_none_or(|tags| meta_cli::filter::matches_tags(&p.tags, tags))
+        })
+        .map(|p| meta_dir.join(&p.path))
+        .filter(|path| path.is_dir())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    let dirs = filter_dirs(dirs, &meta_projects, Some(meta_dir), cli)?;
+    let targets: Vec<meta_cli::git::Target> = meta_projects
+        .iter()
+        .map(|p| (meta_dir.join(&p.path), p))
+        .filter(|(dir, _)| dirs.iter().any(|d| std::path::Path::new(d) == dir))
+        .filter(|(_, p)| include_only.is_empty() || include_only.contains(&p.name))
+        .map(|(dir, p)| (p.name.clone(), dir))
+        .collect();
+    Ok(targets)
+}
+
+/// Run `meta watch`: rerun the command in each project directory whose
+/// files changed, streaming prefixed output by default.
+fn handle_watch(args: WatchArgs, cli: &Cli) -> Result<()> {
+    if args.command.is_empty() {
+        anyhow::bail!("No command given. Usage: meta watch -- <command>");
+    }
+    let debounce = runner::parse_duration(&args.debounce)
+        .ok_or_else(|| anyhow::anyhow!("Invalid --debounce '{}'", args.debounce))?;
+
+    let cwd = std::env::current_dir()?;
+    let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+        return Err(MetaError::ConfigNotFound.into());
+    };
+    let meta_dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+    let (mut meta_projects, ignore_list) = parse_meta_config(&config_path)?;
+    meta_cli::lang::add_language_tags(&mut meta_projects, meta_dir);
+    let dirs: Vec<String> = meta_projects
+        .iter()
+        .filter(|p| {
+            cli.tag
+                .as_deref()
+                .is_none_or(|tags| meta_cli::filter::matches_tags(&p.tags, tags))
+        })
+        .map(|p| meta_dir.join(&p.path))
+        .filter(|path| path.is_dir())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    let dirs = filter_dirs(dirs, &meta_projects, Some(meta_dir), cli)?;
+    if dirs.is_empty() {
+        anyhow::bail!("No project directories to watch");
+    }
+
+    let mut run_config = cli_run_config(cli, Some(meta_dir.to_path_buf()));
+    if run_config.output == runner::OutputMode::Grouped {
+        run_config.output = runner::OutputMode::Stream;
+    }
+    let command = args.command.join(" ");
+    let options = meta_cli::watch::WatchOptions {
+        debounce,
+        ignore: ignore_list.clone(),
+        git_state: false,
+    };
+    if !cli.silent {
+        eprintln!(
+            "Watching {} repo(s) for changes, running `{command}` (Ctrl-C to stop)",
+            dirs.len()
+        );
     }
+    meta_cli::watch::watch(&dirs, &options, |changed| {
+        let config = loop_lib::LoopConfig {
+            add_aliases_to_global_looprc: false,
+            directories: changed.to_vec(),
+            ignore: ignore_list.clone(),
+            include_filters: None,
+            exclude_filters: None,
+            verbose: cli.verbose,
+            silent: cli.silent,
+            parallel: run_config.parallel,
+            dry_run: cli.dry_run,
+            json_output: cli.json,
+            spawn_stagger_ms: 0,
+            env: None,
+            max_parallel: cli.jobs,
+            root_dir: Some(meta_dir.to_path_buf()),
+        };
+        run_commands(&run_config, runner::commands_for(&config, &command), cli)
+    })
+}
+
+/// The workspace root for `meta worktree` commands, from the primary
+/// checkout even when run inside a set.
+fn worktree_meta_dir(cli: &Cli) -> Result<PathBuf> {
+    let cwd = worktree::primary_dir(&std::env::current_dir()?);
+    let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+        return Err(MetaError::ConfigNotFound.into());
+    };
+    Ok(config_path
+        .parent()
+        .unwrap_or(std::path::Path::new("."))
+        .to_path_buf())
+}
+
+/// The meta root and its git repos after `--tag`, `--include` and
+/// `--exclude`: the meta repo itself (when it is one) followed by the
+/// projects.
+fn workspace_git_targets(cli: &Cli) -> Result<(PathBuf, Vec<meta_cli::git::Target>)> {
+    let cwd = std::env::current_dir()?;
+    let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+        return Err(MetaError::ConfigNotFound.into());
+    };
+    let meta_dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+    let (mut meta_projects, _ignore) = parse_meta_config(&config_path)?;
+    meta_cli::lang::add_language_tags(&mut meta_projects, meta_dir);
+
+    let mut dirs = Vec::new();
+    if cli.tag.is_none() && meta_dir.join(".git").exists() {
+        dirs.push(meta_dir.to_string_lossy().to_string());
+    }
+    dirs.extend(
+        meta_projects
+            .iter()
+            .filter(|p| {
+                cli.tag
+                    .as_deref()
+                    .is_none_or(|tags| meta_cli::filter::matches_tags(&p.tags, tags))
+            })
+            .map(|p| meta_dir.join(&p.path).to_string_lossy().to_string()),
+    );
+    let dirs = filter_dirs(dirs, &meta_projects, Some(meta_dir), cli)?;
+    let targets = meta_cli::git::targets(&dirs, Some(meta_dir));
+    Ok((meta_dir.to_path_buf(), targets))
+}
 
-    Ok(())
+/// Run `meta git <sub>` with the built-in fallback (no git plugin handled it).
+fn run_native_git(
+    command_args: &[String],
+    targets: &[meta_cli::git::Target],
+    workspace: &std::path::Path,
+    cli: &Cli,
+    parallel: bool,
+) -> Result<()> {
+    log::info!("No plugin handled the git command, using the built-in fallback");
+    let options = meta_cli::git::GitOptions {
+        json: cli.json,
+        parallel,
+        dry_run: cli.dry_run,
+        verbose: cli.verbose,
+        config: find_meta_config(&std::env::current_dir()?, cli.config.as_ref())
+            .map(|(path, _)| path),
+    };
+    meta_cli::git::handle_git(&command_args[1..], targets, workspace, &options)
 }
 
 // === Plugin Management ===
@@ -904,17 +3029,29 @@ fn handle_plugin_command(
             println!("Usage: meta plugin <command>");
             println!();
             println!("Commands:");
-            println!("  search <query>        Search for plugins in the registry");
+            println!("  search <query>        Search for plugins in the registry (--refresh)");
             println!("  install <name>        Install a plugin (add --local for project-local)");
+            println!("  install --path <path> Install a local build or cargo project");
+            println!("  install --git <url>   Build and install from a git repository");
             println!("  list                  List installed plugins (add --local for project-local only)");
             println!("  uninstall <name>      Uninstall a plugin (add --local for project-local)");
+            println!("  update [name]         Update one plugin, or all of them, to the latest");
+            println!("  outdated              List plugins with newer versions available");
+            println!("  doctor                Check discovered plugins for problems");
+            println!("  refresh               Forget cached plugin info");
+            println!(
+                "  publish <binary>      Package a plugin for release (--repo user/repo, --pr)"
+            );
+            println!("  lock                  Pin project-local plugins in .meta/plugins.lock");
+            println!("  sync                  Install the plugins pinned in .meta/plugins.lock");
             return Ok(());
         }
     };
 
     match command {
-        PluginCommands::Search { query } => {
-            let client = RegistryClient::new(verbose)?;
+        PluginCommands::Search { query, refresh } => {
+            let mut client = RegistryClient::new(verbose)?;
+            client.set_refresh(refresh);
             let results = client.search(&query)?;
 
             if json {
@@ -932,11 +3069,35 @@ fn handle_plugin_command(
                 }
             }
         }
-        PluginCommands::Install { name, local } => {
+        PluginCommands::Install {
+            name,
+            path,
+            git,
+            local,
+        } => {
             use registry::GitHubShorthand;
             let installer = create_installer(local, verbose)?;
             let location = format_plugin_location(local);
 
+            let local_source = match (&path, &git) {
+                (Some(path), _) => Some((
+                    installer.install_from_path(path)?,
+                    path.display().to_string(),
+                )),
+                (None, Some(url)) => Some((installer.install_from_git(url)?, url.clone())),
+                (None, None) => None,
+            };
+            if let Some((installed, source)) = local_source {
+                if !json {
+                    println!(
+                        "Successfully installed {} from {source} to {location}",
+                        installed.join(", ")
+                    );
+                }
+                return Ok(());
+            }
+            let name = name.unwrap_or_default();
+
             // Detect input type and route accordingly
             if name.starts_with("http://") || name.starts_with("https://") {
                 // Direct URL install
@@ -1068,7 +3229,14 @@ fn handle_plugin_command(
                 match installer.check_update(&plugin_name)? {
                     Some((current, latest)) => {
                         if check {
-                            if !json {
+                            if json {
+                                println!(
+                                    "{}",
+                                    serde_json::json!({
+                                        "name": plugin_name, "current": current, "latest": latest
+                                    })
+                                );
+                            } else {
                                 println!(
                                     "Update available for {}: {} → {}",
                                     plugin_name, current, latest
@@ -1076,7 +3244,14 @@ fn handle_plugin_command(
                             }
                         } else {
                             let updated = installer.update_plugin(&plugin_name)?;
-                            if !json {
+                            if json {
+                                println!(
+                                    "{}",
+                                    serde_json::json!({
+                                        "name": updated, "from": current, "to": latest
+                                    })
+                                );
+                            } else {
                                 println!(
                                     "Successfully updated {} from {} to {} in {}",
                                     updated, current, latest, location
@@ -1092,53 +3267,295 @@ fn handle_plugin_command(
                 }
             } else {
                 // Update all plugins
-                let plugins = installer.list_plugins_detailed()?;
-                let mut updates_available = Vec::new();
+                let updates_available: Vec<_> = installer
+                    .outdated()?
+                    .into_iter()
+                    .filter(|status| status.update_available())
+                    .collect();
+                let mut results = Vec::new();
                 let mut updated_count = 0;
 
-                for plugin in plugins {
-                    let name = plugin
-                        .name
-                        .strip_prefix(PLUGIN_PREFIX)
-                        .unwrap_or(&plugin.name);
-                    if let Ok(Some((current, latest))) = installer.check_update(name) {
-                        updates_available.push((name.to_string(), current, latest));
-                    }
-                }
-
                 if updates_available.is_empty() {
                     if !json {
                         println!("All plugins are up to date");
                     }
                 } else if check {
-                    if !json {
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&updates_available)?);
+                    } else {
                         println!("Updates available:");
-                        for (name, current, latest) in &updates_available {
-                            println!("  {} {} → {}", name, current, latest);
+                        for status in &updates_available {
+                            println!(
+                                "  {} {} → {}",
+                                status
+                                    .name
+                                    .strip_prefix(PLUGIN_PREFIX)
+                                    .unwrap_or(&status.name),
+                                status.current.as_deref().unwrap_or("unknown"),
+                                status.latest.as_deref().unwrap_or("unknown")
+                            );
                         }
                     }
                 } else {
-                    for (name, current, latest) in &updates_available {
+                    for status in &updates_available {
+                        let name = status
+                            .name
+                            .strip_prefix(PLUGIN_PREFIX)
+                            .unwrap_or(&status.name);
+                        let current = status.current.as_deref().unwrap_or("unknown");
+                        let latest = status.latest.as_deref().unwrap_or("unknown");
                         match installer.update_plugin(name) {
                             Ok(_) => {
                                 if !json {
                                     println!("Updated {} from {} to {}", name, current, latest);
                                 }
+                                results.push(serde_json::json!({
+                                    "name": status.name, "from": current, "to": latest
+                                }));
                                 updated_count += 1;
                             }
                             Err(e) => {
                                 if !json {
                                     eprintln!("Failed to update {}: {}", name, e);
                                 }
+                                results.push(serde_json::json!({
+                                    "name": status.name, "from": current, "error": format!("{e:#}")
+                                }));
                             }
                         }
                     }
-                    if !json {
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&results)?);
+                    } else {
                         println!("\nUpdated {} plugin(s) in {}", updated_count, location);
                     }
                 }
             }
         }
+        PluginCommands::Outdated { local } => {
+            let installer = create_installer(local, verbose)?;
+            let statuses = installer.outdated()?;
+
+            if json {
+                let outdated: Vec<_> = statuses
+                    .iter()
+                    .filter(|status| status.update_available())
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&outdated)?);
+                return Ok(());
+            }
+
+            let outdated: Vec<_> = statuses.iter().filter(|s| s.update_available()).collect();
+            if outdated.is_empty() {
+                println!("All plugins are up to date");
+            } else {
+                let name_of = |s: &registry::PluginStatus| {
+                    s.name
+                        .strip_prefix(PLUGIN_PREFIX)
+                        .unwrap_or(&s.name)
+                        .to_string()
+                };
+                let width = outdated
+                    .iter()
+                    .map(|s| name_of(s).len())
+                    .max()
+                    .unwrap_or(0)
+                    .max(6);
+                let current_width = outdated
+                    .iter()
+                    .map(|s| s.current.as_deref().unwrap_or("unknown").len())
+                    .max()
+                    .unwrap_or(0)
+                    .max(7);
+                println!(
+                    "{:<width$}  {:<current_width$}  {}",
+                    "PLUGIN".bold(),
+                    "CURRENT".bold(),
+                    "LATEST".bold()
+                );
+                for status in &outdated {
+                    println!(
+                        "{:<width$}  {:<current_width$}  {}",
+                        name_of(status),
+                        status.current.as_deref().unwrap_or("unknown"),
+                        status.latest.as_deref().unwrap_or("unknown").green()
+                    );
+                }
+            }
+            for status in statuses.iter().filter(|s| s.error.is_some()) {
+                eprintln!(
+                    "{}: could not check {}: {}",
+                    "warning".yellow().bold(),
+                    status.name,
+                    status.error.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        PluginCommands::Publish {
+            binary,
+            repo,
+            platform,
+            release_version,
+            author,
+            out,
+            pr,
+        } => {
+            let published = publish::publish(&publish::PublishOptions {
+                binary,
+                repo,
+                platform,
+                version: release_version,
+                author,
+                out,
+                pr,
+            })?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&published)?);
+            } else {
+                println!(
+                    "{} Packaged {} v{} for {}",
+                    "✓".green(),
+                    published.name,
+                    published.version,
+                    published.platform
+                );
+                println!(
+                    "  archive   {} ({})",
+                    published.archive.display(),
+                    published.checksum
+                );
+                println!("  upload to {}", published.release_url);
+                println!("  metadata  {}", published.metadata.display());
+                println!("  source    {}", published.source.display());
+                if let Some(url) = &published.pull_request {
+                    println!("  registry PR {url}");
+                }
+            }
+        }
+        PluginCommands::Refresh => meta_cli::plugin_cache::handle_refresh(json),
+        PluginCommands::Doctor => {
+            use subprocess_plugins::CheckStatus;
+
+            let report = subprocess_plugins.doctor()?;
+            let problems = report.problem_count();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                if report.plugins.is_empty() {
+                    println!("No plugins found");
+                }
+                for check in &report.plugins {
+                    let mark = match check.status {
+                        CheckStatus::Ok if check.problems.is_empty() => "✓".green(),
+                        CheckStatus::Ok => "✗".yellow(),
+                        CheckStatus::Shadowed | CheckStatus::Ignored | CheckStatus::Disabled => {
+                            "•".dimmed()
+                        }
+                        CheckStatus::Broken => "✗".red(),
+                    };
+                    let origin = format!("{:?}", check.origin).to_lowercase();
+                    println!(
+                        "{mark} {} {}  {} ({origin})",
+                        check.name.as_deref().unwrap_or_else(|| {
+                            let file = check.path.file_name().and_then(|n| n.to_str());
+                            file.unwrap_or("?")
+                        }),
+                        check.version.as_deref().unwrap_or_default(),
+                        check.path.display().to_string().dimmed()
+                    );
+                    if let Some(winner) = &check.shadowed_by {
+                        println!("    shadowed by {}", winner.display());
+                    }
+                    if check.status == CheckStatus::Disabled {
+                        println!("    disabled by the plugins settings in .meta");
+                    }
+                    for problem in &check.problems {
+                        println!("    {problem}");
+                    }
+                }
+                if !report.conflicts.is_empty() {
+                    println!();
+                    println!("{}", "Command conflicts:".bold());
+                    for conflict in &report.conflicts {
+                        let how = if conflict.overridden {
+                            "plugin_overrides"
+                        } else {
+                            "plugin_priority"
+                        };
+                        println!(
+                            "  {} is registered by {}; {} handles it ({how})",
+                            conflict.command,
+                            conflict.plugins.join(", "),
+                            conflict.resolved_to
+                        );
+                    }
+                }
+                println!();
+                if problems == 0 {
+                    println!("{} No problems found", "✓".green());
+                } else {
+                    println!("{} {problems} problem(s) found", "✗".red());
+                }
+            }
+            if problems > 0 {
+                return Err(MetaError::ChecksFailed(format!(
+                    "plugin doctor found {problems} problem(s)"
+                ))
+                .into());
+            }
+        }
+        PluginCommands::Lock => {
+            let installer = create_installer(true, verbose)?;
+            let lock = installer.lock()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&lock)?);
+            } else {
+                for (name, locked) in &lock.plugins {
+                    println!(
+                        "  {} {} {}",
+                        "✓".green(),
+                        name,
+                        locked.version.as_deref().unwrap_or(&locked.source).dimmed()
+                    );
+                }
+                println!(
+                    "Locked {} plugin(s) in {}",
+                    lock.plugins.len(),
+                    installer.lock_path().display()
+                );
+            }
+        }
+        PluginCommands::Sync => {
+            let installer = create_installer(true, verbose)?;
+            let synced = installer.sync()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&synced)?);
+            } else {
+                for plugin in &synced {
+                    let state = if plugin.installed {
+                        "installed"
+                    } else {
+                        "up to date"
+                    };
+                    println!(
+                        "  {} {} {} {}",
+                        "✓".green(),
+                        plugin.name,
+                        plugin.version.as_deref().unwrap_or_default(),
+                        state.dimmed()
+                    );
+                }
+                let installed = synced.iter().filter(|p| p.installed).count();
+                println!("Synced {} plugin(s), {installed} installed", synced.len());
+                for name in installer.unlocked_plugins()? {
+                    eprintln!(
+                        "{}: {name} is installed but not in {}",
+                        "warning".yellow().bold(),
+                        installer.lock_path().display()
+                    );
+                }
+            }
+        }
     }
 
     Ok(())
@@ -1183,44 +3600,484 @@ fn extract_global_flags(args: &mut Vec<String>, cli: &mut Cli) {
                 cli.strict = true;
                 false
             }
+            "--offline" => {
+                cli.offline = true;
+                registry::set_offline(true);
+                false
+            }
             _ => true, // keep in args
         }
     });
 }
 
-/// Check whether a project's tags match a comma-separated tag filter string.
-fn matches_tag_filter(tags: &[String], filter: &str) -> bool {
-    let requested: Vec<&str> = filter.split(',').map(|s| s.trim()).collect();
-    tags.iter().any(|t| requested.contains(&t.as_str()))
+/// Run a command across the configured directories.
+///
+/// Every repo runs to completion (unless `--fail-fast`) and the run ends with
+/// a per-repo summary; the global `.looprc` alias installer still goes
+/// through loop_lib.
+fn run_loop(config: &loop_lib::LoopConfig, command: &str, cli: &Cli) -> Result<()> {
+    if config.add_aliases_to_global_looprc {
+        return loop_lib::run(config, command);
+    }
+
+    let mut run_config = runner::RunConfig::from(config);
+    run_config.fail_fast = cli.fail_fast;
+    if !config.json_output {
+        run_config.output = text_output_mode(cli);
+    }
+    let mut commands = runner::commands_for(config, command);
+    if let Some(depth) = cli.max_depth {
+        commands = runner::expand_directories(commands, depth as usize);
+    }
+    run_commands(&run_config, commands, cli)
+}
+
+/// Run commands through the runner: skip unchanged repos with
+/// `--changed-since`, expand `{name}`-style placeholders, load dotenv
+/// files, and schedule by `depends_on` with `--order topo`.
+fn run_commands(
+    run_config: &runner::RunConfig,
+    mut commands: Vec<loop_lib::DirCommand>,
+    cli: &Cli,
+) -> Result<()> {
+    let reports = cli
+        .report
+        .iter()
+        .map(|spec| meta_cli::report::ReportSpec::parse(spec))
+        .collect::<Result<Vec<_>>>()?;
+
+    if let Some(base) = cli.changed_since.as_deref() {
+        let filter = meta_cli::changes::filter_changed(commands, base)?;
+        if !filter.unchanged.is_empty() && !cli.silent && !quiet_output(run_config) {
+            eprintln!(
+                "Skipping {} unchanged repo(s) (no changes since {base})",
+                filter.unchanged.len()
+            );
+            if cli.verbose {
+                for dir in &filter.unchanged {
+                    eprintln!("  {} {dir}", "-".dimmed());
+                }
+            }
+        }
+        commands = filter.changed;
+    }
+
+    if !cli.silent && !quiet_output(run_config) {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        if let Some((config_path, _)) = find_meta_config(&cwd, cli.config.as_ref()) {
+            if let Some(banner) = meta_cli::sync::banner(&config_path) {
+                eprintln!("{banner}");
+            }
+            let dirs: Vec<String> = commands.iter().map(|c| c.dir.clone()).collect();
+            for warning in meta_cli::branch_check::warnings(&config_path, &dirs) {
+                eprintln!("{warning}");
+            }
+        }
+    }
+
+    if cli.interactive {
+        let repos: Vec<(String, String)> = commands
+            .iter()
+            .map(|c| {
+                let name = runner::display_name(
+                    std::path::Path::new(&c.dir),
+                    run_config.root_dir.as_deref(),
+                );
+                (name, c.dir.clone())
+            })
+            .collect();
+        let Some(selected) = meta_cli::picker::pick(meta_cli::picker::items_for(&repos))? else {
+            eprintln!("Cancelled");
+            return Ok(());
+        };
+        if selected.is_empty() {
+            eprintln!("No repos selected");
+            return Ok(());
+        }
+        commands = selected.into_iter().map(|i| commands[i].clone()).collect();
+    }
+
+    if run_config.output == runner::OutputMode::Passthrough && commands.len() != 1 {
+        anyhow::bail!(
+            "--passthrough needs filters that select exactly one repo ({} selected)",
+            commands.len()
+        );
+    }
+
+    if commands
+        .iter()
+        .any(|c| meta_cli::placeholders::has_placeholders(&c.cmd))
+    {
+        let cwd = std::env::current_dir()?;
+        let config_path = find_meta_config(&cwd, cli.config.as_ref()).map(|(path, _)| path);
+        let projects = match &config_path {
+            Some(path) => parse_meta_config(path)?.0,
+            None => Vec::new(),
+        };
+        let meta_dir = config_path.as_deref().and_then(|p| p.parent());
+        meta_cli::placeholders::apply(&mut commands, meta_dir, &projects);
+    }
+
+    let mut run_config = run_config.clone();
+    if run_config.shell.is_none() {
+        run_config.shell = resolve_shell(cli)?;
+    }
+    if run_config.timeout.is_none() {
+        run_config.timeout = resolve_timeout(cli)?;
+    }
+    run_config.stdin = resolve_stdin(cli, &run_config, commands.len())?;
+    run_config.confirm = cli.confirm && confirm_available(&run_config);
+    let config_path =
+        find_meta_config(&std::env::current_dir()?, cli.config.as_ref()).map(|(path, _)| path);
+    run_config.workdirs =
+        meta_cli::workdir::resolve(&commands, config_path.as_deref(), cli.workdir.as_deref())?;
+    let root = match &run_config.root_dir {
+        Some(root) => Some(root.clone()),
+        None => {
+            let cwd = std::env::current_dir()?;
+            find_meta_config(&cwd, cli.config.as_ref())
+                .and_then(|(path, _)| path.parent().map(|p| p.to_path_buf()))
+        }
+    };
+    if !cli.no_dotenv {
+        if let Some(root) = &root {
+            meta_cli::dotenv::apply(&mut run_config, &mut commands, root)?;
+        }
+    }
+    // `env` from the config sits beneath the dotenv files
+    let mut env = config::load_meta_env(&std::env::current_dir().unwrap_or_default());
+    env.retain(|key, _| std::env::var_os(key).is_none());
+    if !env.is_empty() {
+        env.extend(run_config.env.take().unwrap_or_default());
+        run_config.env = Some(env);
+    }
+    let run_config = &run_config;
+
+    let (commands, waits_for) = match dependency_schedule(&commands, cli)? {
+        Some(schedule) => {
+            let ordered = schedule
+                .order
+                .iter()
+                .map(|&i| commands[i].clone())
+                .collect();
+            (ordered, Some(schedule.waits_for))
+        }
+        None => (commands, None),
+    };
+
+    if run_config.dry_run {
+        return runner::print_plan(run_config, &commands, &applied_filters(cli));
+    }
+
+    let cmds: Vec<&str> = commands.iter().map(|c| c.cmd.as_str()).collect();
+    meta_cli::policy::enforce(config_path.as_deref(), &cmds)?;
+
+    // `pre-exec` can veto the run; `post-exec` failures only warn
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let exec_hooks = match &root {
+        Some(root) => meta_cli::hooks::load_exec_hooks(root)?,
+        None => meta_cli::hooks::Hooks::new(),
+    };
+    if let Some(root) = &root {
+        let payload = meta_cli::hooks::pre_exec_payload(&args, &commands);
+        meta_cli::hooks::fire(&exec_hooks, "pre-exec", &payload, root, false)?;
+    }
+
+    let started = std::time::Instant::now();
+    let report = match waits_for {
+        Some(waits_for) => runner::execute_with_dependencies(run_config, &commands, &waits_for)?,
+        None => runner::execute(run_config, &commands)?,
+    };
+
+    let record = meta_cli::history::RunRecord::from_report(
+        &report,
+        args,
+        applied_filters(cli)
+            .into_iter()
+            .map(|(flag, value)| format!("{flag} {value}"))
+            .collect(),
+        started.elapsed(),
+    );
+    if let Some(root) = &root {
+        let payload = meta_cli::hooks::post_exec_payload(&record);
+        if let Err(e) = meta_cli::hooks::fire(&exec_hooks, "post-exec", &payload, root, false) {
+            eprintln!("{}: {e:#}", "warning".yellow().bold());
+        }
+    }
+    if let Some(root) = &root {
+        let summary = meta_cli::notify::Summary::from_record(&record);
+        meta_cli::notify::after_run(root, cli.notify.as_deref(), &summary);
+    }
+    if let Err(e) = meta_cli::history::record(record) {
+        eprintln!(
+            "{}: failed to record run history: {e}",
+            "warning".yellow().bold()
+        );
+    }
+
+    if cli.changed_since.is_some() && !run_config.dry_run {
+        if let Err(e) = meta_cli::changes::record_successes(&report) {
+            eprintln!(
+                "{}: failed to record run state: {e}",
+                "warning".yellow().bold()
+            );
+        }
+    }
+    let suite = meta_cli::report::suite_name(run_config.root_dir.as_deref());
+    for spec in &reports {
+        if let Err(e) = spec.write(&report, &suite) {
+            eprintln!("{}: {e:#}", "warning".yellow().bold());
+        }
+    }
+    runner::finish(run_config, report)
+}
+
+/// Whether meta's own notes (skipped repos, sync banners) stay off the
+/// terminal: JSON output must stay parseable and `--passthrough` must only
+/// show the command's output.
+fn quiet_output(run_config: &runner::RunConfig) -> bool {
+    matches!(
+        run_config.output,
+        runner::OutputMode::Json | runner::OutputMode::Passthrough
+    )
+}
+
+/// Per-repo timeout from `--timeout`, falling back to `defaults.timeout`.
+fn resolve_timeout(cli: &Cli) -> Result<Option<std::time::Duration>> {
+    if let Some(spec) = &cli.timeout {
+        return match runner::parse_duration(spec) {
+            Some(timeout) => Ok(Some(timeout)),
+            None => anyhow::bail!("Invalid --timeout '{spec}': expected e.g. 90s, 10m or 1h"),
+        };
+    }
+    let cwd = std::env::current_dir().unwrap_or_default();
+    Ok(config::load_meta_timeout(&cwd))
+}
+
+/// Selection options in effect, for the `--dry-run` plan.
+fn applied_filters(cli: &Cli) -> Vec<(&'static str, String)> {
+    let mut filters = Vec::new();
+    if let Some(include) = &cli.include {
+        filters.push(("--include", include.join(",")));
+    }
+    if let Some(exclude) = &cli.exclude {
+        filters.push(("--exclude", exclude.join(",")));
+    }
+    if let Some(tag) = &cli.tag {
+        filters.push(("--tag", tag.clone()));
+    }
+    for expr in &cli.r#if {
+        filters.push(("--if", expr.clone()));
+    }
+    if let Some(base) = &cli.changed_since {
+        filters.push(("--changed-since", base.clone()));
+    }
+    if cli.recursive {
+        let depth = cli.depth.map_or("unlimited".to_string(), |d| d.to_string());
+        filters.push(("--recursive", format!("depth {depth}")));
+    }
+    if let Some(depth) = cli.max_depth {
+        filters.push(("--max-depth", depth.to_string()));
+    }
+    if let Some(order) = &cli.order {
+        filters.push(("--order", order.clone()));
+    }
+    filters
+}
+
+/// With `--order topo`, schedule commands so dependencies run first.
+fn dependency_schedule(
+    commands: &[loop_lib::DirCommand],
+    cli: &Cli,
+) -> Result<Option<meta_cli::dependency_graph::Schedule>> {
+    if cli.order.as_deref() != Some("topo") {
+        return Ok(None);
+    }
+
+    let cwd = std::env::current_dir()?;
+    let Some((config_path, _format)) = find_meta_config(&cwd, cli.config.as_ref()) else {
+        eprintln!(
+            "{}: --order topo needs a .meta config; running in config order",
+            "warning".yellow().bold()
+        );
+        return Ok(None);
+    };
+    let meta_dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+    let (projects, _ignore) = parse_meta_config(&config_path)?;
+
+    // Match each directory to its project: by path, then by name for worktree checkouts
+    let names: Vec<String> = commands
+        .iter()
+        .map(|c| {
+            let dir = std::path::Path::new(&c.dir);
+            projects
+                .iter()
+                .find(|p| meta_dir.join(&p.path) == dir)
+                .or_else(|| {
+                    let base = dir.file_name()?.to_str()?;
+                    projects.iter().find(|p| p.name == base)
+                })
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| c.dir.clone())
+        })
+        .collect();
+
+    let graph = meta_cli::dependency_graph::DependencyGraph::build(
+        projects.into_iter().map(Into::into).collect(),
+    )?;
+    let name_refs: Vec<&str> = names.iter().map(String::as_str).collect();
+    let schedule = graph.schedule(&name_refs)?;
+    if cli.verbose {
+        let ordered: Vec<&str> = schedule.order.iter().map(|&i| name_refs[i]).collect();
+        eprintln!("Dependency order: {}", ordered.join(" -> "));
+    }
+
+    Ok(Some(schedule))
+}
+
+/// Determine parallel mode: --parallel wins, then --sequential, then config default (true)
+fn resolve_parallel(cli: &Cli) -> bool {
+    if cli.parallel {
+        log::debug!("parallel=true (--parallel flag)");
+        true
+    } else if cli.sequential {
+        log::debug!("parallel=false (--sequential flag)");
+        false
+    } else {
+        // Load default from .meta config (defaults to parallel: true if not specified)
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let defaults = config::load_meta_defaults(&cwd);
+        log::debug!(
+            "parallel={} (from config defaults, cwd={})",
+            defaults.parallel,
+            cwd.display()
+        );
+        defaults.parallel
+    }
+}
+
+/// Shell for commands: `--shell`, then `shell` in .meta, then the runner default.
+fn resolve_shell(cli: &Cli) -> Result<Option<Vec<String>>> {
+    if let Some(spec) = cli.shell.as_deref() {
+        return match runner::parse_shell(spec) {
+            Some(shell) => Ok(Some(shell)),
+            None => anyhow::bail!("--shell must name a shell program"),
+        };
+    }
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let config_path = find_meta_config(&cwd, cli.config.as_ref()).map(|(path, _)| path);
+    Ok(config::load_meta_shell(config_path.as_deref()))
+}
+
+/// Stdin for every command from `--stdin`; `--passthrough` inherits meta's
+/// by default. Concurrent commands cannot share meta's stdin.
+fn resolve_stdin(
+    cli: &Cli,
+    run_config: &runner::RunConfig,
+    repos: usize,
+) -> Result<runner::StdinMode> {
+    let mode = match cli.stdin.as_deref() {
+        Some(spec) => match runner::StdinMode::parse(spec) {
+            Some(mode) => mode,
+            None => anyhow::bail!("Invalid --stdin '{spec}': expected inherit, file:<path> or tee"),
+        },
+        None if cli.passthrough => runner::StdinMode::Inherit,
+        None => runner::StdinMode::Null,
+    };
+    if mode == runner::StdinMode::Inherit && run_config.parallel && repos > 1 {
+        anyhow::bail!("--stdin inherit only works sequentially; use --stdin tee or file:<path> with --parallel");
+    }
+    Ok(mode)
 }
 
-/// Convert an empty Vec into None, non-empty into Some.
-fn none_if_empty(v: Vec<String>) -> Option<Vec<String>> {
-    if v.is_empty() {
-        None
+/// Whether `--confirm` can prompt: not while commands run concurrently or
+/// own the live view, and only with a terminal to ask on. Otherwise it is
+/// dropped with a warning.
+fn confirm_available(run_config: &runner::RunConfig) -> bool {
+    use std::io::IsTerminal;
+    let reason = if run_config.parallel {
+        "commands run in parallel"
+    } else if run_config.output == runner::OutputMode::Tui {
+        "the tui view is on"
+    } else if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        "there is no terminal to ask on"
     } else {
-        Some(v)
+        return true;
+    };
+    eprintln!(
+        "{}: --confirm disabled because {reason}",
+        "warning".yellow().bold()
+    );
+    false
+}
+
+/// Concurrency limit: `--jobs`, then `defaults.jobs` in .meta, then the CPU count.
+fn resolve_jobs(flag: Option<usize>) -> Result<usize> {
+    if flag == Some(0) {
+        anyhow::bail!("--jobs must be at least 1");
+    }
+    let cwd = std::env::current_dir().unwrap_or_default();
+    Ok(flag
+        .or_else(|| config::load_meta_jobs(&cwd))
+        .unwrap_or_else(runner::default_jobs))
+}
+
+/// Apply `--include` / `--exclude` patterns and `--if` predicates to
+/// directories. Project names and tags come from `projects`, matched by
+/// path under `meta_dir` and then by directory name (for worktree checkouts
+/// and nested repos).
+fn filter_dirs(
+    dirs: Vec<String>,
+    projects: &[ProjectInfo],
+    meta_dir: Option<&std::path::Path>,
+    cli: &Cli,
+) -> Result<Vec<String>> {
+    let filter = meta_cli::filter::DirFilter::with_conditions(
+        cli.include.as_deref().unwrap_or_default(),
+        cli.exclude.as_deref().unwrap_or_default(),
+        &cli.r#if,
+    )?;
+    if filter.is_empty() {
+        return Ok(dirs);
     }
+
+    Ok(dirs
+        .into_iter()
+        .filter(|dir| {
+            let path = std::path::Path::new(dir);
+            let base = path.file_name().and_then(|n| n.to_str()).unwrap_or(".");
+            let project = projects
+                .iter()
+                .find(|p| meta_dir.is_some_and(|m| m.join(&p.path) == path))
+                .or_else(|| projects.iter().find(|p| p.name == base));
+            filter.matches(&meta_cli::filter::Target {
+                path: dir,
+                name: project.map_or(base, |p| p.name.as_str()),
+                tags: project.map_or(&[], |p| p.tags.as_slice()),
+            })
+        })
+        .collect())
 }
 
-/// Print unrecognized command error with suggestion and help, then exit.
+/// Print a suggestion and help for an unrecognized command, and return
+/// the `USAGE` error to end with.
 fn unrecognized_command_error(
     command_args: &[String],
     command_str: &str,
     plugins: &SubprocessPluginManager,
-) -> ! {
+) -> anyhow::Error {
     let first_cmd = command_args.first().map(|s| s.as_str()).unwrap_or("");
-    eprintln!(
-        "{}: unrecognized command '{}'",
-        "error".red().bold(),
-        first_cmd
-    );
-    eprintln!();
+    if let Some(plugin) = plugins.disabled_plugin_for(command_str) {
+        return MetaError::Usage(format!(
+            "'{command_str}' is provided by the plugin '{plugin}', which is disabled by the plugins settings in .meta"
+        ))
+        .into();
+    }
     eprintln!("To run '{command_str}' across all repos:");
     eprintln!("    meta exec {command_str}");
     eprintln!();
     print_help_with_plugins(plugins, true);
-    std::process::exit(1);
+    MetaError::Usage(format!("unrecognized command '{first_cmd}'")).into()
 }
 
 /// Check if current directory is in an orphaned nested meta repo and warn the user.
@@ -1254,10 +4111,7 @@ fn check_and_warn_orphan() {
         eprintln!();
 
         // Show format-appropriate syntax
-        let config_file = match warning.parent_format {
-            ConfigFormat::Json => ".meta",
-            ConfigFormat::Yaml => ".meta.yaml",
-        };
+        let config_file = warning.parent_format.file_name();
         eprintln!(
             "  To include it, add to {}/{}:",
             warning.parent.display(),
@@ -1275,6 +4129,11 @@ fn check_and_warn_orphan() {
                 eprintln!("      repo: <git-url>");
                 eprintln!("      meta: true");
             }
+            ConfigFormat::Toml => {
+                eprintln!("    [projects.{}]", warning.suggested_key);
+                eprintln!("    repo = \"<git-url>\"");
+                eprintln!("    meta = true");
+            }
         }
         eprintln!();
     }
@@ -1296,7 +4155,7 @@ fn flatten_filtered_inner(
 ) {
     for node in nodes {
         let matches = match tag_filter {
-            Some(ref tag_str) => matches_tag_filter(&node.info.tags, tag_str),
+            Some(ref tag_str) => meta_cli::filter::matches_tags(&node.info.tags, tag_str),
             None => true,
         };
 
@@ -0,0 +1,529 @@
+//! Prometheus/OpenMetrics metrics for `meta metrics`.
+//!
+//! Every recorded run (see [`crate::history`]) and every plugin dispatch
+//! adds to cumulative counters in `~/.meta/metrics.json`, so the counters
+//! keep growing after the history file is trimmed. Worktree set counts are
+//! read live from each workspace in `~/.meta/workspaces.json`.
+//!
+//! `meta metrics` prints everything in the OpenMetrics text format,
+//! `meta metrics serve` answers scrapes on `/metrics`, and
+//! `meta metrics write <file>` writes a file for node_exporter's textfile
+//! collector.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::history::RunRecord;
+use crate::runner::RepoStatus;
+
+/// Upper bounds, in seconds, of the duration histogram buckets.
+pub const BUCKETS: [f64; 10] = [0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0];
+
+/// Default port for `meta metrics serve`.
+pub const DEFAULT_PORT: u16 = 9300;
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// How long a scrape may take to send its request or read the answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ── Types ───────────────────────────────────────────────
+
+/// A cumulative histogram over [`BUCKETS`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    /// Observations at or below each bucket bound
+    #[serde(default)]
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum: f64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, seconds: f64) {
+        self.buckets.resize(BUCKETS.len(), 0);
+        for (bound, count) in BUCKETS.iter().zip(self.buckets.iter_mut()) {
+            if seconds <= *bound {
+                *count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunCounts {
+    pub success: u64,
+    pub failure: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RepoStats {
+    /// Status (`ok`, `failed`, `skipped`, ...) → count
+    #[serde(default)]
+    pub statuses: BTreeMap<String, u64>,
+    /// Durations of the runs that started
+    #[serde(default)]
+    pub duration: Histogram,
+}
+
+/// Everything `meta metrics` reports except the live worktree counts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    /// Program run (`git`, `cargo`, ...) → outcomes
+    #[serde(default)]
+    pub runs: BTreeMap<String, RunCounts>,
+    #[serde(default)]
+    pub run_duration: Histogram,
+    /// Repo name → per-repo counts and durations
+    #[serde(default)]
+    pub repos: BTreeMap<String, RepoStats>,
+    /// Plugin name → dispatches
+    #[serde(default)]
+    pub plugin_dispatches: BTreeMap<String, u64>,
+}
+
+impl Stats {
+    pub fn observe_run(&mut self, record: &RunRecord) {
+        let counts = self.runs.entry(run_command(record)).or_default();
+        if record.success {
+            counts.success += 1;
+        } else {
+            counts.failure += 1;
+        }
+        self.run_duration.observe(seconds(record.duration_ms));
+        for repo in &record.repos {
+            let stats = self.repos.entry(repo.name.clone()).or_default();
+            *stats
+                .statuses
+                .entry(status_label(repo.status).to_string())
+                .or_default() += 1;
+            if repo.status != RepoStatus::Skipped {
+                stats.duration.observe(seconds(repo.duration_ms));
+            }
+        }
+    }
+
+    pub fn observe_dispatch(&mut self, plugin: &str) {
+        *self
+            .plugin_dispatches
+            .entry(plugin.to_string())
+            .or_default() += 1;
+    }
+}
+
+// ── Storage ─────────────────────────────────────────────
+
+fn stats_path() -> PathBuf {
+    meta_core::data_dir::data_file("metrics")
+}
+
+fn lock_path() -> PathBuf {
+    meta_core::meta_dir().join("metrics.lock")
+}
+
+pub fn load() -> Result<Stats> {
+    meta_core::store::read(&stats_path())
+}
+
+/// Add a finished run to the counters.
+pub fn record_run(record: &RunRecord) -> Result<()> {
+    meta_core::data_dir::ensure_meta_dir()?;
+    meta_core::store::update(&stats_path(), &lock_path(), |stats: &mut Stats| {
+        stats.observe_run(record)
+    })
+}
+
+/// Count one dispatch to `plugin`.
+pub fn record_dispatch(plugin: &str) -> Result<()> {
+    meta_core::data_dir::ensure_meta_dir()?;
+    meta_core::store::update(&stats_path(), &lock_path(), |stats: &mut Stats| {
+        stats.observe_dispatch(plugin)
+    })
+}
+
+/// Worktree sets per registered workspace. Workspaces that no longer
+/// exist are left out.
+pub fn worktree_counts() -> BTreeMap<String, usize> {
+    let Ok(registry) = crate::workspaces::Registry::load() else {
+        return BTreeMap::new();
+    };
+    registry
+        .workspaces
+        .into_iter()
+        .filter(|(_, entry)| entry.path.is_dir())
+        .map(|(name, entry)| {
+            let root = crate::worktree::worktrees_root(&entry.path);
+            (name, crate::worktree::list_sets(&root).len())
+        })
+        .collect()
+}
+
+// ── Rendering ───────────────────────────────────────────
+
+/// The OpenMetrics text exposition of `stats` and `worktrees`.
+pub fn render(stats: &Stats, worktrees: &BTreeMap<String, usize>) -> String {
+    let mut out = String::new();
+
+    family(
+        &mut out,
+        "meta_runs",
+        "counter",
+        "Multi-repo runs by program and result",
+    );
+    for (command, counts) in &stats.runs {
+        for (result, count) in [("success", counts.success), ("failure", counts.failure)] {
+            sample(
+                &mut out,
+                "meta_runs_total",
+                &[("command", command.as_str()), ("result", result)],
+                count,
+            );
+        }
+    }
+
+    family(
+        &mut out,
+        "meta_run_duration_seconds",
+        "histogram",
+        "Wall time of multi-repo runs",
+    );
+    histogram(
+        &mut out,
+        "meta_run_duration_seconds",
+        &[],
+        &stats.run_duration,
+    );
+
+    family(
+        &mut out,
+        "meta_repo_runs",
+        "counter",
+        "Per-repo command results by status",
+    );
+    for (repo, repo_stats) in &stats.repos {
+        for (status, count) in &repo_stats.statuses {
+            sample(
+                &mut out,
+                "meta_repo_runs_total",
+                &[("repo", repo.as_str()), ("status", status.as_str())],
+                count,
+            );
+        }
+    }
+
+    family(
+        &mut out,
+        "meta_repo_duration_seconds",
+        "histogram",
+        "Per-repo command durations",
+    );
+    for (repo, repo_stats) in &stats.repos {
+        histogram(
+            &mut out,
+            "meta_repo_duration_seconds",
+            &[("repo", repo.as_str())],
+            &repo_stats.duration,
+        );
+    }
+
+    family(
+        &mut out,
+        "meta_plugin_dispatches",
+        "counter",
+        "Commands dispatched to each plugin",
+    );
+    for (plugin, count) in &stats.plugin_dispatches {
+        sample(
+            &mut out,
+            "meta_plugin_dispatches_total",
+            &[("plugin", plugin.as_str())],
+            count,
+        );
+    }
+
+    family(
+        &mut out,
+        "meta_worktree_sets",
+        "gauge",
+        "Worktree sets in each known workspace",
+    );
+    for (workspace, count) in worktrees {
+        sample(
+            &mut out,
+            "meta_worktree_sets",
+            &[("workspace", workspace.as_str())],
+            count,
+        );
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+// ── Entry Points ────────────────────────────────────────
+
+/// Entry point for `meta metrics`.
+pub fn handle_show(json: bool) -> Result<()> {
+    let stats = load()?;
+    let worktrees = worktree_counts();
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "stats": stats,
+                "worktree_sets": worktrees,
+            }))?
+        );
+    } else {
+        print!("{}", render(&stats, &worktrees));
+    }
+    Ok(())
+}
+
+/// Entry point for `meta metrics write <file>`. The file is replaced
+/// atomically so a collector never reads half of it.
+pub fn handle_write(path: &Path) -> Result<()> {
+    let text = render(&load()?, &worktree_counts());
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, text).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+/// Entry point for `meta metrics serve`. Renders fresh metrics for every
+/// scrape of `/metrics`, each connection on its own thread so a slow or
+/// idle client can't hold up the others; runs until killed.
+pub fn handle_serve(bind: &str, port: u16) -> Result<()> {
+    let listener = TcpListener::bind((bind, port))
+        .with_context(|| format!("Failed to listen on {bind}:{port}"))?;
+    println!("Serving metrics on http://{bind}:{port}/metrics");
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        std::thread::spawn(move || {
+            if let Err(e) = respond(stream, || Ok(render(&load()?, &worktree_counts()))) {
+                log::warn!("Failed to answer metrics request: {e:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+/// Answer one HTTP request: the metrics for `GET /metrics` (or `/`), 404
+/// for anything else.
+fn respond(stream: TcpStream, body: impl FnOnce() -> Result<String>) -> Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers so the client sees a clean response
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics" | "/")) => match body() {
+            Ok(body) => ("200 OK", CONTENT_TYPE, body),
+            Err(e) => (
+                "500 Internal Server Error",
+                "text/plain",
+                format!("{e:#}\n"),
+            ),
+        },
+        _ => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+    };
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    Ok(())
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    out.push_str(&format!("# TYPE {name} {kind}\n# HELP {name} {help}.\n"));
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    out.push_str(&format!("{name}{} {value}\n", format_labels(labels)));
+}
+
+fn histogram(out: &mut String, name: &str, labels: &[(&str, &str)], histogram: &Histogram) {
+    for (i, bound) in BUCKETS.iter().enumerate() {
+        // Canonical float form, `1.0` rather than `1`
+        let le = format!("{bound:?}");
+        let mut bucket_labels = labels.to_vec();
+        bucket_labels.push(("le", &le));
+        let count = histogram.buckets.get(i).copied().unwrap_or(0);
+        sample(out, &format!("{name}_bucket"), &bucket_labels, count);
+    }
+    let mut inf_labels = labels.to_vec();
+    inf_labels.push(("le", "+Inf"));
+    sample(out, &format!("{name}_bucket"), &inf_labels, histogram.count);
+    sample(out, &format!("{name}_count"), labels, histogram.count);
+    sample(out, &format!("{name}_sum"), labels, histogram.sum);
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{key}=\"{value}\"")
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// The program a run executed, from its first repo's command.
+fn run_command(record: &RunRecord) -> String {
+    record
+        .repos
+        .first()
+        .and_then(|r| r.command.split_whitespace().next())
+        .unwrap_or("none")
+        .to_string()
+}
+
+fn status_label(status: RepoStatus) -> &'static str {
+    match status {
+        RepoStatus::Ok => "ok",
+        RepoStatus::Failed => "failed",
+        RepoStatus::Skipped => "skipped",
+        RepoStatus::Cancelled => "cancelled",
+        RepoStatus::TimedOut => "timed_out",
+    }
+}
+
+fn seconds(millis: u64) -> f64 {
+    millis as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::RepoRecord;
+    use std::io::Read;
+
+    fn repo(name: &str, status: RepoStatus, duration_ms: u64) -> RepoRecord {
+        RepoRecord {
+            name: name.to_string(),
+            directory: format!("/ws/{name}"),
+            command: "cargo test --all".to_string(),
+            status,
+            exit_code: None,
+            duration_ms,
+            commit: None,
+        }
+    }
+
+    fn run(success: bool, repos: Vec<RepoRecord>) -> RunRecord {
+        RunRecord {
+            id: 1,
+            started_at: String::new(),
+            cwd: "/ws".to_string(),
+            args: vec!["exec".to_string()],
+            filters: Vec::new(),
+            success,
+            duration_ms: 4_000,
+            repos,
+        }
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let mut h = Histogram::default();
+        h.observe(0.25);
+        h.observe(7.0);
+        assert_eq!(h.buckets, [0, 1, 1, 1, 1, 2, 2, 2, 2, 2]);
+        assert_eq!(h.count, 2);
+        assert_eq!(h.sum, 7.25);
+    }
+
+    #[test]
+    fn renders_runs_repos_plugins_and_worktrees() {
+        let mut stats = Stats::default();
+        stats.observe_run(&run(
+            false,
+            vec![
+                repo("api", RepoStatus::Ok, 1_500),
+                repo("web", RepoStatus::Failed, 200),
+                repo("docs", RepoStatus::Skipped, 0),
+            ],
+        ));
+        stats.observe_run(&run(true, vec![repo("api", RepoStatus::Ok, 1_000)]));
+        stats.observe_dispatch("git");
+        stats.observe_dispatch("git");
+        assert_eq!(
+            stats.runs["cargo"],
+            RunCounts {
+                success: 1,
+                failure: 1
+            }
+        );
+        assert_eq!(stats.repos["docs"].duration.count, 0);
+
+        let worktrees = BTreeMap::from([("my \"ws\"".to_string(), 3)]);
+        let text = render(&stats, &worktrees);
+        for line in [
+            "# TYPE meta_runs counter",
+            "meta_runs_total{command=\"cargo\",result=\"failure\"} 1",
+            "meta_run_duration_seconds_bucket{le=\"5.0\"} 2",
+            "meta_run_duration_seconds_count 2",
+            "meta_repo_runs_total{repo=\"api\",status=\"ok\"} 2",
+            "meta_repo_runs_total{repo=\"web\",status=\"failed\"} 1",
+            "meta_repo_duration_seconds_bucket{repo=\"api\",le=\"1.0\"} 1",
+            "meta_repo_duration_seconds_bucket{repo=\"api\",le=\"0.1\"} 0",
+            "meta_repo_duration_seconds_bucket{repo=\"api\",le=\"+Inf\"} 2",
+            "meta_repo_duration_seconds_sum{repo=\"api\"} 2.5",
+            "meta_plugin_dispatches_total{plugin=\"git\"} 2",
+            "meta_worktree_sets{workspace=\"my \\\"ws\\\"\"} 3",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {line}:\n{text}");
+        }
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn serves_metrics_and_404s_other_paths() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().unwrap();
+                respond(stream, || Ok("meta_up 1\n# EOF\n".to_string())).unwrap();
+            }
+        });
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let ok = get("/metrics");
+        assert!(ok.starts_with("HTTP/1.1 200 OK"), "{ok}");
+        assert!(ok.contains("application/openmetrics-text"));
+        assert!(ok.ends_with("meta_up 1\n# EOF\n"));
+        assert!(get("/other").starts_with("HTTP/1.1 404"));
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,418 @@
+//! Post a message when a long run finishes.
+//!
+//! `meta exec --notify slack` (or a `notify.url` in `.meta`) posts a
+//! summary of the run to a Slack incoming webhook, or as JSON to any URL,
+//! once the run has taken longer than `notify.threshold`:
+//!
+//! ```json
+//! "notify": {
+//!   "url": "https://hooks.slack.com/services/T000/B000/XXXX",
+//!   "format": "slack",
+//!   "threshold": "5m"
+//! }
+//! ```
+//!
+//! Webhook URLs are secrets, so `META_NOTIFY_URL` overrides `notify.url`
+//! and can stand in for it. Delivery is best-effort: a failed post only
+//! warns. exec, release and the worktree plugin all notify through here.
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::config;
+use crate::history::RunRecord;
+use crate::runner::RepoStatus;
+
+/// Runs shorter than this finish without a notification.
+pub const DEFAULT_THRESHOLD: Duration = Duration::from_secs(60);
+
+/// Environment variable that overrides `notify.url`.
+pub const URL_ENV: &str = "META_NOTIFY_URL";
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ── Types ───────────────────────────────────────────────
+
+/// Body shape to post.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// `{"text": ...}`, as Slack incoming webhooks expect
+    #[default]
+    Slack,
+    /// The [`Summary`] fields plus `text`
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "slack" => Ok(Format::Slack),
+            "json" => Ok(Format::Json),
+            other => anyhow::bail!(
+                "unknown notify format '{other}' (expected slack, json or a webhook URL)"
+            ),
+        }
+    }
+}
+
+/// The `notify` section of the meta config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotifyConfig {
+    pub url: Option<String>,
+    pub format: Format,
+    pub threshold: Duration,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        NotifyConfig {
+            url: None,
+            format: Format::default(),
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+}
+
+/// Where a notification goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub url: String,
+    pub format: Format,
+}
+
+/// What a finished run looks like to a notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    /// The command line, e.g. `meta exec -- cargo test`
+    pub title: String,
+    pub success: bool,
+    pub ok: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub duration_ms: u64,
+    /// Names of the repos that failed, in run order
+    pub failed_repos: Vec<String>,
+}
+
+impl Summary {
+    /// Summarise a recorded `meta exec` run. Timeouts count as failures,
+    /// cancelled repos as skipped.
+    pub fn from_record(record: &RunRecord) -> Self {
+        let failed_repos: Vec<String> = record
+            .repos
+            .iter()
+            .filter(|r| matches!(r.status, RepoStatus::Failed | RepoStatus::TimedOut))
+            .map(|r| r.name.clone())
+            .collect();
+        let ok = record
+            .repos
+            .iter()
+            .filter(|r| r.status == RepoStatus::Ok)
+            .count();
+        Summary {
+            title: format!("meta {}", record.args.join(" ")),
+            success: record.success,
+            ok,
+            failed: failed_repos.len(),
+            skipped: record.repos.len() - ok - failed_repos.len(),
+            duration_ms: record.duration_ms,
+            failed_repos,
+        }
+    }
+
+    /// Summarise a git-style run such as `meta release tag`.
+    pub fn from_results(
+        title: &str,
+        results: &[crate::git::RepoResult],
+        elapsed: Duration,
+    ) -> Self {
+        let failed_repos: Vec<String> = results
+            .iter()
+            .filter(|r| !r.success)
+            .map(|r| r.name.clone())
+            .collect();
+        Summary {
+            title: title.to_string(),
+            success: failed_repos.is_empty(),
+            ok: results.len() - failed_repos.len(),
+            failed: failed_repos.len(),
+            skipped: 0,
+            duration_ms: elapsed.as_millis() as u64,
+            failed_repos,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Load the `notify` section from the meta config in `meta_dir`, with
+/// `META_NOTIFY_URL` applied.
+pub fn load(meta_dir: &Path) -> Result<NotifyConfig> {
+    let mut notify = match config::find_meta_config_in(meta_dir) {
+        Some((path, _)) => {
+            let value = config::read_meta_config_value(&path)?;
+            parse_config(value.get("notify"))
+                .with_context(|| format!("Invalid notify in {}", path.display()))?
+        }
+        None => NotifyConfig::default(),
+    };
+    if let Some(url) = std::env::var(URL_ENV).ok().filter(|url| !url.is_empty()) {
+        notify.url = Some(url);
+    }
+    Ok(notify)
+}
+
+/// Parse a `notify` section: `{"url", "format", "threshold"}`, all optional.
+pub fn parse_config(section: Option<&serde_json::Value>) -> Result<NotifyConfig> {
+    let mut notify = NotifyConfig::default();
+    let Some(section) = section else {
+        return Ok(notify);
+    };
+    let map = section.as_object().context("expected an object")?;
+    if let Some(url) = map.get("url") {
+        notify.url = Some(url.as_str().context("url must be a string")?.to_string());
+    }
+    if let Some(format) = map.get("format") {
+        notify.format = format
+            .as_str()
+            .context("format must be a string")?
+            .parse()?;
+    }
+    if let Some(threshold) = map.get("threshold") {
+        // 0 notifies on every run
+        notify.threshold = match threshold.as_u64() {
+            Some(0) => Duration::ZERO,
+            _ => config::parse_timeout(threshold)
+                .context("threshold must be seconds or a duration like \"5m\"")?,
+        };
+    }
+    Ok(notify)
+}
+
+/// Work out where to post. `--notify slack` or `--notify json` picks the
+/// format for the configured URL, and `--notify <url>` posts there
+/// directly. Without the flag, a configured URL turns notifications on.
+pub fn resolve(flag: Option<&str>, notify: &NotifyConfig) -> Result<Option<Target>> {
+    let Some(flag) = flag else {
+        return Ok(notify.url.clone().map(|url| Target {
+            url,
+            format: notify.format,
+        }));
+    };
+    if flag.starts_with("https://") || flag.starts_with("http://") {
+        let format = if flag.contains("hooks.slack.com") {
+            Format::Slack
+        } else {
+            Format::Json
+        };
+        return Ok(Some(Target {
+            url: flag.to_string(),
+            format,
+        }));
+    }
+    let format = flag.parse()?;
+    let url = notify.url.clone().with_context(|| {
+        format!("--notify {flag} needs a webhook URL: set notify.url in .meta or {URL_ENV}")
+    })?;
+    Ok(Some(Target { url, format }))
+}
+
+/// One-line description of the run, e.g.
+/// `meta exec -- cargo test failed after 4m 12s: 8 ok, 2 failed (api, web)`.
+pub fn message(summary: &Summary) -> String {
+    let outcome = if summary.success {
+        "finished"
+    } else {
+        "failed"
+    };
+    let mut counts = vec![format!("{} ok", summary.ok)];
+    if summary.failed > 0 {
+        counts.push(format!(
+            "{} failed ({})",
+            summary.failed,
+            summary.failed_repos.join(", ")
+        ));
+    }
+    if summary.skipped > 0 {
+        counts.push(format!("{} skipped", summary.skipped));
+    }
+    format!(
+        "{} {outcome} after {}: {}",
+        summary.title,
+        format_elapsed(summary.duration()),
+        counts.join(", ")
+    )
+}
+
+/// The request body for `format`.
+pub fn payload(format: Format, summary: &Summary) -> serde_json::Value {
+    match format {
+        Format::Slack => {
+            let icon = if summary.success {
+                ":white_check_mark:"
+            } else {
+                ":x:"
+            };
+            let title = format!("`{}`", summary.title);
+            let text = message(summary).replacen(&summary.title, &title, 1);
+            serde_json::json!({ "text": format!("{icon} {text}") })
+        }
+        Format::Json => {
+            let mut value = serde_json::to_value(summary).unwrap_or_default();
+            value["text"] = message(summary).into();
+            value
+        }
+    }
+}
+
+/// Post the summary to `target`.
+pub fn send(target: &Target, summary: &Summary) -> Result<()> {
+    ureq::post(&target.url)
+        .set("User-Agent", "meta-cli")
+        .set("Content-Type", "application/json")
+        .timeout(SEND_TIMEOUT)
+        .send_string(&payload(target.format, summary).to_string())
+        .context("Failed to post notification")?;
+    Ok(())
+}
+
+/// Notify about a finished run if notifications are on and it ran longer
+/// than the threshold. Never fails the run; problems are printed as
+/// warnings.
+pub fn after_run(meta_dir: &Path, flag: Option<&str>, summary: &Summary) {
+    let target = load(meta_dir).and_then(|notify| {
+        let target = resolve(flag, &notify)?;
+        Ok(target.filter(|_| summary.duration() >= notify.threshold))
+    });
+    let result = match target {
+        Ok(Some(target)) => send(&target, summary),
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("{}: {e:#}", "warning".yellow().bold());
+    }
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+/// `"45s"`, `"4m 12s"` or `"1h 3m"`.
+fn format_elapsed(d: Duration) -> String {
+    let secs = d.as_secs();
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary() -> Summary {
+        Summary {
+            title: "meta exec -- cargo test".to_string(),
+            success: false,
+            ok: 8,
+            failed: 2,
+            skipped: 1,
+            duration_ms: 252_000,
+            failed_repos: vec!["api".to_string(), "web".to_string()],
+        }
+    }
+
+    #[test]
+    fn parses_config_section() {
+        let notify = parse_config(Some(&json!({
+            "url": "https://example.com/hook",
+            "format": "json",
+            "threshold": "5m"
+        })))
+        .unwrap();
+        assert_eq!(notify.url.as_deref(), Some("https://example.com/hook"));
+        assert_eq!(notify.format, Format::Json);
+        assert_eq!(notify.threshold, Duration::from_secs(300));
+
+        let notify = parse_config(Some(&json!({"threshold": 0}))).unwrap();
+        assert_eq!(notify.threshold, Duration::ZERO);
+        assert_eq!(parse_config(None).unwrap(), NotifyConfig::default());
+        assert!(parse_config(Some(&json!({"format": "email"}))).is_err());
+    }
+
+    #[test]
+    fn resolves_flag_against_config() {
+        let configured = NotifyConfig {
+            url: Some("https://hooks.slack.com/services/x".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve(None, &configured).unwrap().unwrap().format,
+            Format::Slack
+        );
+        assert_eq!(
+            resolve(Some("json"), &configured).unwrap().unwrap(),
+            Target {
+                url: "https://hooks.slack.com/services/x".to_string(),
+                format: Format::Json,
+            }
+        );
+
+        let unset = NotifyConfig::default();
+        assert_eq!(resolve(None, &unset).unwrap(), None);
+        let err = resolve(Some("slack"), &unset).unwrap_err();
+        assert!(err.to_string().contains("set notify.url"), "{err}");
+        assert_eq!(
+            resolve(Some("https://ci.example.com/done"), &unset)
+                .unwrap()
+                .unwrap()
+                .format,
+            Format::Json
+        );
+    }
+
+    #[test]
+    fn message_lists_counts_and_failed_repos() {
+        assert_eq!(
+            message(&summary()),
+            "meta exec -- cargo test failed after 4m 12s: 8 ok, 2 failed (api, web), 1 skipped"
+        );
+        let passed = Summary {
+            success: true,
+            failed: 0,
+            skipped: 0,
+            failed_repos: Vec::new(),
+            duration_ms: 61_000,
+            ..summary()
+        };
+        assert_eq!(
+            message(&passed),
+            "meta exec -- cargo test finished after 1m 1s: 8 ok"
+        );
+    }
+
+    #[test]
+    fn payload_matches_format() {
+        let slack = payload(Format::Slack, &summary());
+        assert_eq!(
+            slack["text"],
+            ":x: `meta exec -- cargo test` failed after 4m 12s: 8 ok, 2 failed (api, web), 1 skipped"
+        );
+        let body = payload(Format::Json, &summary());
+        assert_eq!(body["failed_repos"], json!(["api", "web"]));
+        assert_eq!(body["duration_ms"], 252_000);
+        assert!(body["text"].as_str().unwrap().starts_with("meta exec"));
+    }
+}
@@ -0,0 +1,240 @@
+//! `meta open <alias>`: open a project in an editor or in the browser.
+//!
+//! - `--editor` (the default) opens the project directory in `$VISUAL`,
+//!   then `$EDITOR`, then VS Code (`code`).
+//! - `--remote` opens the repo's web page, from its `origin` remote, or the
+//!   `.meta` URL when it is not cloned yet.
+//! - `--pr` opens the page for a new pull request from the current branch
+//!   (a merge request on GitLab, a pull request on Bitbucket, and the
+//!   GitHub form on any other host).
+//!
+//! Web pages open with `$BROWSER`, or the platform's opener (`open`,
+//! `xdg-open`, `url.dll` on Windows).
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use crate::config;
+use crate::error::MetaError;
+use crate::git_utils;
+
+/// Editor for when neither `$VISUAL` nor `$EDITOR` is set.
+pub const DEFAULT_EDITOR: &str = "code";
+
+/// What `meta open` opens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Editor,
+    Remote,
+    Pr,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Open project `alias` of the workspace configured at `config_path`.
+pub fn handle_open(config_path: &Path, alias: &str, target: Target) -> Result<()> {
+    let meta_dir = config_path.parent().unwrap_or(Path::new("."));
+    let (projects, _ignore) = config::parse_meta_config(config_path)?;
+    let Some(project) = projects.iter().find(|p| p.name == alias) else {
+        return Err(MetaError::ProjectNotFound {
+            name: alias.to_string(),
+            config: config_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        }
+        .into());
+    };
+    let dir = meta_dir.join(&project.path);
+
+    match target {
+        Target::Editor => open_in_editor(&dir),
+        Target::Remote | Target::Pr => {
+            let remote = git_utils::remote_url(&dir)
+                .or_else(|| project.repo.clone())
+                .with_context(|| format!("'{alias}' has no remote URL"))?;
+            let web = web_url(&remote)
+                .with_context(|| format!("Cannot turn remote '{remote}' into a web URL"))?;
+            let url = if target == Target::Pr {
+                let branch = git_utils::current_branch(&dir).with_context(|| {
+                    format!("'{alias}' is not cloned or not on a branch, so there is no PR to open")
+                })?;
+                pr_url(&web, &branch)
+            } else {
+                web
+            };
+            open_in_browser(&url)
+        }
+    }
+}
+
+/// The web page of a git remote: `git@host:org/repo.git`,
+/// `ssh://git@host/org/repo.git` and `https://user@host/org/repo.git` all
+/// become `https://host/org/repo`. `None` for local paths.
+pub fn web_url(remote: &str) -> Option<String> {
+    let remote = remote.trim().trim_end_matches('/');
+    let remote = remote.strip_suffix(".git").unwrap_or(remote);
+    let (host, path) = if let Some((_, rest)) = remote.split_once("://") {
+        rest.split_once('/')?
+    } else {
+        // scp-like syntax, user@host:path
+        let (host, path) = remote.split_once(':')?;
+        // `C:\repos\api` is a Windows path
+        if host.contains('/') || host.len() == 1 {
+            return None;
+        }
+        (host, path)
+    };
+    let host = host.rsplit('@').next()?;
+    // An ssh port is not the web port
+    let host = match host.split_once(':') {
+        Some((name, _port)) if remote.starts_with("ssh://") => name,
+        _ => host,
+    };
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some(format!("https://{host}/{}", path.trim_start_matches('/')))
+}
+
+/// The page for opening a pull request from `branch` on the forge at `web`.
+pub fn pr_url(web: &str, branch: &str) -> String {
+    let branch = &percent_encode(branch);
+    if web.contains("gitlab") {
+        format!("{web}/-/merge_requests/new?merge_request%5Bsource_branch%5D={branch}")
+    } else if web.contains("bitbucket") {
+        format!("{web}/pull-requests/new?source={branch}")
+    } else {
+        format!("{web}/pull/new/{branch}")
+    }
+}
+
+/// The editor command: `$VISUAL`, `$EDITOR`, or [`DEFAULT_EDITOR`], split
+/// into program and arguments.
+pub fn editor_command() -> Vec<String> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|value| {
+            value
+                .split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .find(|words| !words.is_empty())
+        .unwrap_or_else(|| vec![DEFAULT_EDITOR.to_string()])
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+/// Percent-encode everything in `value` but unreserved characters and `/`,
+/// which branch names use as a separator and URLs allow as is.
+fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+fn open_in_editor(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        anyhow::bail!(
+            "{} does not exist (run meta git clone first)",
+            dir.display()
+        );
+    }
+    let editor = editor_command();
+    let status = Command::new(&editor[0])
+        .args(&editor[1..])
+        .arg(dir)
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", editor[0]))?;
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {status}", editor[0]);
+    }
+    Ok(())
+}
+
+fn open_in_browser(url: &str) -> Result<()> {
+    let (program, args) = browser_command();
+    let status = Command::new(&program)
+        .args(&args)
+        .arg(url)
+        .status()
+        .with_context(|| format!("Failed to start '{program}' to open {url}"))?;
+    if !status.success() {
+        anyhow::bail!("'{program}' could not open {url}");
+    }
+    Ok(())
+}
+
+/// `$BROWSER`, or the platform's URL opener.
+fn browser_command() -> (String, Vec<String>) {
+    if let Some(browser) = std::env::var("BROWSER").ok().filter(|b| !b.is_empty()) {
+        return (browser, Vec::new());
+    }
+    if cfg!(target_os = "macos") {
+        ("open".to_string(), Vec::new())
+    } else if cfg!(windows) {
+        // Unlike `cmd /c start`, no shell parses the URL's `&` and `%`
+        let args = vec!["url.dll,FileProtocolHandler".to_string()];
+        ("rundll32".to_string(), args)
+    } else {
+        ("xdg-open".to_string(), Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remotes_become_web_urls() {
+        let expected = Some("https://github.com/org/api".to_string());
+        assert_eq!(web_url("git@github.com:org/api.git"), expected);
+        assert_eq!(web_url("ssh://git@github.com/org/api.git"), expected);
+        assert_eq!(web_url("https://github.com/org/api.git"), expected);
+        assert_eq!(web_url("https://user@github.com/org/api/"), expected);
+        assert_eq!(
+            web_url("ssh://git@gitlab.example.com:2222/group/sub/web.git"),
+            Some("https://gitlab.example.com/group/sub/web".to_string())
+        );
+        assert_eq!(web_url("/srv/git/api.git"), None);
+        assert_eq!(web_url("../api"), None);
+        assert_eq!(web_url(r"C:\repos\api"), None);
+    }
+
+    #[test]
+    fn pr_pages_follow_the_forge() {
+        assert_eq!(
+            pr_url("https://github.com/org/api", "feat/x"),
+            "https://github.com/org/api/pull/new/feat/x"
+        );
+        assert_eq!(
+            pr_url("https://gitlab.com/org/api", "fix"),
+            "https://gitlab.com/org/api/-/merge_requests/new?merge_request%5Bsource_branch%5D=fix"
+        );
+        assert_eq!(
+            pr_url("https://bitbucket.org/org/api", "fix"),
+            "https://bitbucket.org/org/api/pull-requests/new?source=fix"
+        );
+    }
+
+    #[test]
+    fn pr_pages_encode_the_branch() {
+        assert_eq!(
+            pr_url("https://gitlab.com/org/api", "fix#1&x=y"),
+            "https://gitlab.com/org/api/-/merge_requests/new?merge_request%5Bsource_branch%5D=fix%231%26x%3Dy"
+        );
+        assert_eq!(
+            pr_url("https://github.com/org/api", "feat/ü+1"),
+            "https://github.com/org/api/pull/new/feat/%C3%BC%2B1"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Interactive repo picker for `--interactive`.
+//!
+//! Shows every target repo with its branch and dirty state and lets the user
+//! toggle which ones the command runs in:
+//!
+//! ```text
+//! Select repos  ↑/↓ move · space toggle · a all · enter run · esc cancel
+//! > [x] . (meta)   main            clean
+//!   [x] api        feature/login   3 changed
+//!   [ ] web        main            clean
+//! ```
+//!
+//! The list is drawn on stderr so stdout stays clean for `--json`.
+
+use anyhow::Result;
+use colored::*;
+use console::{Key, Term};
+use rayon::prelude::*;
+use std::path::Path;
+
+use crate::git_utils;
+
+/// One selectable repo.
+#[derive(Debug, Clone)]
+pub struct PickerItem {
+    pub name: String,
+    pub branch: Option<String>,
+    /// Number of changed files, or `None` if git status failed.
+    pub dirty: Option<usize>,
+}
+
+impl PickerItem {
+    /// Look up branch and dirty state for a directory.
+    pub fn for_dir(name: &str, dir: &Path) -> Self {
+        PickerItem {
+            name: name.to_string(),
+            branch: git_utils::current_branch(dir),
+            dirty: git_utils::dirty_file_count(dir),
+        }
+    }
+}
+
+/// What a key press did to the picker.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Continue,
+    Confirm,
+    Cancel,
+}
+
+/// Selection state, kept separate from the terminal so it can be tested.
+#[derive(Debug)]
+pub struct Picker {
+    pub items: Vec<PickerItem>,
+    pub selected: Vec<bool>,
+    pub cursor: usize,
+}
+
+impl Picker {
+    /// A picker with every item selected.
+    pub fn new(items: Vec<PickerItem>) -> Self {
+        let selected = vec![true; items.len()];
+        Picker {
+            items,
+            selected,
+            cursor: 0,
+        }
+    }
+
+    pub fn handle(&mut self, key: &Key) -> Outcome {
+        let last = self.items.len().saturating_sub(1);
+        match key {
+            Key::ArrowUp | Key::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            Key::ArrowDown | Key::Char('j') => self.cursor = (self.cursor + 1).min(last),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = last,
+            Key::Char(' ') => {
+                if let Some(s) = self.selected.get_mut(self.cursor) {
+                    *s = !*s;
+                }
+            }
+            Key::Char('a') => {
+                let all = self.selected.iter().all(|&s| s);
+                self.selected.iter_mut().for_each(|s| *s = !all);
+            }
+            Key::Enter => return Outcome::Confirm,
+            Key::Escape | Key::Char('q') => return Outcome::Cancel,
+            _ => {}
+        }
+        Outcome::Continue
+    }
+
+    /// Indices of the selected items, in list order.
+    pub fn selection(&self) -> Vec<usize> {
+        (0..self.items.len())
+            .filter(|&i| self.selected[i])
+            .collect()
+    }
+
+    fn render(&self) -> Vec<String> {
+        let name_width = self.items.iter().map(|i| i.name.len()).max().unwrap_or(0);
+        let branch_width = self
+            .items
+            .iter()
+            .map(|i| i.branch.as_deref().map_or(1, str::len))
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = vec![format!(
+            "{}  {}",
+            "Select repos".bold(),
+            "↑/↓ move · space toggle · a all · enter run · esc cancel".dimmed()
+        )];
+        for (i, item) in self.items.iter().enumerate() {
+            let pointer = if i == self.cursor {
+                ">".cyan()
+            } else {
+                " ".normal()
+            };
+            let check = if self.selected[i] {
+                "[x]".green()
+            } else {
+                "[ ]".dimmed()
+            };
+            let branch = format!("{:<branch_width$}", item.branch.as_deref().unwrap_or("-"));
+            let state = match item.dirty {
+                Some(0) => "clean".dimmed(),
+                Some(n) => format!("{n} changed").yellow(),
+                None => "no git".dimmed(),
+            };
+            lines.push(format!(
+                "{pointer} {check} {:<name_width$}  {}  {state}",
+                item.name,
+                branch.cyan()
+            ));
+        }
+        lines
+    }
+}
+
+/// Let the user choose among `items`. Returns the selected indices, or
+/// `None` if they cancelled.
+pub fn pick(items: Vec<PickerItem>) -> Result<Option<Vec<usize>>> {
+    let term = Term::stderr();
+    if !term.is_term() {
+        anyhow::bail!("--interactive needs a terminal");
+    }
+
+    let mut picker = Picker::new(items);
+    let mut drawn = 0;
+    term.hide_cursor()?;
+    let outcome = loop {
+        term.clear_last_lines(drawn)?;
+        let lines = picker.render();
+        for line in &lines {
+            term.write_line(line)?;
+        }
+        drawn = lines.len();
+
+        match picker.handle(&term.read_key()?) {
+            Outcome::Continue => {}
+            outcome => break outcome,
+        }
+    };
+    term.clear_last_lines(drawn)?;
+    term.show_cursor()?;
+
+    Ok((outcome == Outcome::Confirm).then(|| picker.selection()))
+}
+
+/// Build picker items for `(name, dir)` pairs, querying git in parallel.
+pub fn items_for(repos: &[(String, String)]) -> Vec<PickerItem> {
+    repos
+        .par_iter()
+        .map(|(name, dir)| PickerItem::for_dir(name, Path::new(dir)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker(n: usize) -> Picker {
+        Picker::new(
+            (0..n)
+                .map(|i| PickerItem {
+                    name: format!("repo{i}"),
+                    branch: Some("main".to_string()),
+                    dirty: Some(i),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn starts_fully_selected_and_toggles() {
+        let mut p = picker(3);
+        assert_eq!(p.selection(), vec![0, 1, 2]);
+
+        p.handle(&Key::ArrowDown);
+        p.handle(&Key::Char(' '));
+        assert_eq!(p.selection(), vec![0, 2]);
+
+        p.handle(&Key::Char('a'));
+        assert_eq!(p.selection(), vec![0, 1, 2]);
+        p.handle(&Key::Char('a'));
+        assert!(p.selection().is_empty());
+    }
+
+    #[test]
+    fn cursor_stays_in_bounds() {
+        let mut p = picker(2);
+        p.handle(&Key::ArrowUp);
+        assert_eq!(p.cursor, 0);
+        for _ in 0..5 {
+            p.handle(&Key::Char('j'));
+        }
+        assert_eq!(p.cursor, 1);
+    }
+
+    #[test]
+    fn enter_confirms_and_escape_cancels() {
+        let mut p = picker(1);
+        assert_eq!(p.handle(&Key::Enter), Outcome::Confirm);
+        assert_eq!(p.handle(&Key::Escape), Outcome::Cancel);
+        assert_eq!(p.handle(&Key::Char('x')), Outcome::Continue);
+    }
+
+    #[test]
+    fn render_shows_branch_and_dirty_state() {
+        let lines = picker(2).render();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("> [x] repo0"));
+        assert!(lines[1].ends_with("main  clean"));
+        assert!(lines[2].ends_with("1 changed"));
+    }
+}
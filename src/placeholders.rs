@@ -0,0 +1,155 @@
+//! Per-repo placeholders in commands run across repos.
+//!
+//! `meta exec -- echo '{name} on {branch} at {path}'` expands each
+//! placeholder for the repo the command runs in, just before it is spawned:
+//!
+//! | Placeholder | Value |
+//! |-------------|-------|
+//! | `{name}`    | project name from `.meta` (directory name otherwise) |
+//! | `{path}`    | absolute path of the checkout |
+//! | `{branch}`  | current branch (`HEAD` when detached) |
+//! | `{repo}`    | remote URL from `.meta` (empty when unset) |
+//! | `{tags}`    | the project's tags, comma-separated |
+//!
+//! Only these names are placeholders; any other braces (`${HOME}`,
+//! `awk '{print $1}'`) are left alone. Doubling the braces of a
+//! placeholder escapes it: `{{name}}` becomes a literal `{name}`. Values
+//! are inserted as-is, so quote them when they may contain spaces.
+
+use loop_lib::DirCommand;
+use std::path::Path;
+
+use crate::config::ProjectInfo;
+use crate::git_utils;
+
+/// The names that expand.
+pub const VARIABLES: &[&str] = &["name", "path", "branch", "repo", "tags"];
+
+/// Whether `cmd` contains a placeholder (escaped ones included).
+pub fn has_placeholders(cmd: &str) -> bool {
+    VARIABLES.iter().any(|v| cmd.contains(&format!("{{{v}}}")))
+}
+
+/// Expand each placeholder in `cmd` with `value(name)`, which is only
+/// called for names that occur.
+pub fn expand(cmd: &str, mut value: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(cmd.len());
+    let mut rest = cmd;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(name) = escaped_at(rest) {
+            out.push_str(&format!("{{{name}}}"));
+            rest = &rest[name.len() + 4..];
+        } else if let Some(name) = placeholder_at(rest) {
+            out.push_str(&value(name));
+            rest = &rest[name.len() + 2..];
+        } else {
+            out.push('{');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Expand the placeholders of every command for the repo it runs in.
+/// Projects are matched to directories by path under `meta_dir`, then by
+/// name for worktree checkouts.
+pub fn apply(commands: &mut [DirCommand], meta_dir: Option<&Path>, projects: &[ProjectInfo]) {
+    for command in commands.iter_mut() {
+        if !has_placeholders(&command.cmd) {
+            continue;
+        }
+        let dir = Path::new(&command.dir);
+        let base = dir.file_name().and_then(|n| n.to_str()).unwrap_or(".");
+        let project = projects
+            .iter()
+            .find(|p| meta_dir.is_some_and(|m| m.join(&p.path) == dir))
+            .or_else(|| projects.iter().find(|p| p.name == base));
+        command.cmd = expand(&command.cmd, |name| match name {
+            "name" => project.map_or(base, |p| p.name.as_str()).to_string(),
+            "path" => command.dir.clone(),
+            "branch" => git_utils::current_branch(dir).unwrap_or_else(|| "HEAD".to_string()),
+            "repo" => project.and_then(|p| p.repo.clone()).unwrap_or_default(),
+            "tags" => project.map(|p| p.tags.join(",")).unwrap_or_default(),
+            _ => unreachable!("only known placeholders are expanded"),
+        });
+    }
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+/// `{name}...` → `name`, for a known placeholder.
+fn placeholder_at(text: &str) -> Option<&'static str> {
+    VARIABLES
+        .iter()
+        .find(|v| {
+            text.strip_prefix('{')
+                .and_then(|t| t.strip_prefix(*v))
+                .is_some_and(|t| t.starts_with('}'))
+        })
+        .copied()
+}
+
+/// `{{name}}...` → `name`, for an escaped placeholder.
+fn escaped_at(text: &str) -> Option<&'static str> {
+    let name = placeholder_at(text.strip_prefix('{')?)?;
+    text[name.len() + 3..].starts_with('}').then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, path: &str, tags: &[&str]) -> ProjectInfo {
+        ProjectInfo {
+            name: name.to_string(),
+            path: path.to_string(),
+            repo: Some(format!("git@x:{name}.git")),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            provides: vec![],
+            depends_on: vec![],
+            meta: false,
+        }
+    }
+
+    #[test]
+    fn expands_known_names_and_leaves_other_braces() {
+        let lookup = |name: &str| name.to_uppercase();
+        assert_eq!(
+            expand("echo '{name} on {branch} at {path}'", lookup),
+            "echo 'NAME on BRANCH at PATH'"
+        );
+        assert_eq!(
+            expand("awk '{print $1}' ${HOME} {} {nam", lookup),
+            "awk '{print $1}' ${HOME} {} {nam"
+        );
+        assert_eq!(
+            expand("echo {{name}} is {name}; {{other}}", lookup),
+            "echo {name} is NAME; {{other}}"
+        );
+        assert!(has_placeholders("x {{tags}}"));
+        assert!(!has_placeholders("jq '.name' {}"));
+    }
+
+    #[test]
+    fn apply_uses_the_project_entry() {
+        let tmp = tempfile::tempdir().unwrap();
+        let api = tmp.path().join("services/api");
+        let scratch = tmp.path().join("scratch");
+        let projects = vec![project("api", "services/api", &["backend", "rust"])];
+        let mut commands: Vec<DirCommand> = [&api, &scratch]
+            .iter()
+            .map(|dir| DirCommand {
+                dir: dir.display().to_string(),
+                cmd: "echo {name} {repo} {tags} {branch}".to_string(),
+                env: None,
+            })
+            .collect();
+
+        apply(&mut commands, Some(tmp.path()), &projects);
+        assert_eq!(commands[0].cmd, "echo api git@x:api.git backend,rust HEAD");
+        assert_eq!(commands[1].cmd, "echo scratch   HEAD");
+    }
+}
@@ -0,0 +1,155 @@
+//! On-disk cache of plugin `--meta-plugin-info` output.
+//!
+//! Discovery would otherwise spawn every `meta-*` binary on each
+//! invocation. Answers are kept in `~/.meta/cache/plugin-info.json`, keyed
+//! by the binary's path and valid while its mtime and size are unchanged.
+//! Binaries that turned out not to be plugins are cached too (as `null`),
+//! so they are not probed again. `meta plugin refresh` drops the cache.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Cached answer for one plugin binary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    /// Modification time as seconds and nanoseconds since the epoch
+    mtime: (u64, u32),
+    size: u64,
+    /// Raw plugin info, or `null` when the binary is not a plugin
+    info: serde_json::Value,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PluginInfoCache {
+    #[serde(default)]
+    plugins: BTreeMap<PathBuf, Entry>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+/// Where the cache lives.
+pub fn cache_path() -> PathBuf {
+    meta_core::meta_dir().join("cache").join("plugin-info.json")
+}
+
+impl PluginInfoCache {
+    /// Load the cache, starting empty when it is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        meta_core::store::read(path).unwrap_or_default()
+    }
+
+    /// Cached info for `plugin` if the binary is unchanged since it was
+    /// stored. `Some(Null)` means it is known not to be a plugin.
+    pub fn get(&self, plugin: &Path) -> Option<&serde_json::Value> {
+        let entry = self.plugins.get(plugin)?;
+        let (mtime, size) = fingerprint(plugin)?;
+        (entry.mtime == mtime && entry.size == size).then_some(&entry.info)
+    }
+
+    /// Remember the answer `plugin` gave (`Null` for "not a plugin").
+    pub fn insert(&mut self, plugin: &Path, info: serde_json::Value) {
+        let Some((mtime, size)) = fingerprint(plugin) else {
+            return;
+        };
+        let entry = Entry { mtime, size, info };
+        if self.plugins.get(plugin) != Some(&entry) {
+            self.plugins.insert(plugin.to_path_buf(), entry);
+            self.changed = true;
+        }
+    }
+
+    /// Write the cache back if anything was added, dropping entries for
+    /// binaries that no longer exist.
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        self.plugins.retain(|plugin, _| plugin.exists());
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        meta_core::store::write_atomic(path, &*self)?;
+        self.changed = false;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+}
+
+/// `meta plugin refresh`: forget every cached answer so the next
+/// invocation asks each plugin again.
+pub fn handle_refresh(json: bool) -> Result<()> {
+    let path = cache_path();
+    let cleared = PluginInfoCache::load(&path).len();
+    match std::fs::remove_file(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    if json {
+        println!("{}", serde_json::json!({ "cleared": cleared }));
+    } else {
+        println!("Cleared cached info for {cleared} plugin binaries");
+    }
+    Ok(())
+}
+
+fn fingerprint(plugin: &Path) -> Option<((u64, u32), u64)> {
+    let meta = std::fs::metadata(plugin).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(((mtime.as_secs(), mtime.subsec_nanos()), meta.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_valid_until_the_binary_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let plugin = tmp.path().join("meta-demo");
+        let store = tmp.path().join("cache/plugin-info.json");
+        std::fs::write(&plugin, "v1").unwrap();
+
+        let mut cache = PluginInfoCache::load(&store);
+        assert!(cache.get(&plugin).is_none());
+        cache.insert(&plugin, serde_json::json!({"name": "demo"}));
+        // Binaries that are gone aren't cached
+        cache.insert(&tmp.path().join("meta-gone"), serde_json::Value::Null);
+        cache.save(&store).unwrap();
+
+        let cache = PluginInfoCache::load(&store);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&plugin).unwrap()["name"], "demo");
+
+        std::fs::write(&plugin, "version 2").unwrap();
+        assert!(cache.get(&plugin).is_none());
+    }
+
+    #[test]
+    fn non_plugins_are_remembered_as_null() {
+        let tmp = tempfile::tempdir().unwrap();
+        let binary = tmp.path().join("meta-mcp");
+        std::fs::write(&binary, "#!/bin/sh\n").unwrap();
+
+        let mut cache = PluginInfoCache::default();
+        cache.insert(&binary, serde_json::Value::Null);
+        assert_eq!(cache.get(&binary), Some(&serde_json::Value::Null));
+
+        // Unchanged inserts don't mark the cache for writing
+        let store = tmp.path().join("plugin-info.json");
+        cache.save(&store).unwrap();
+        let mut cache = PluginInfoCache::load(&store);
+        cache.insert(&binary, serde_json::Value::Null);
+        assert!(!cache.changed);
+    }
+}
@@ -0,0 +1,338 @@
+//! Opt-in permission checks for plugins.
+//!
+//! Plugins declare what they need in `--meta-plugin-info`:
+//!
+//! ```json
+//! { "name": "mig", "permissions": ["network", "env"] }
+//! ```
+//!
+//! A workspace turns the checks on with `plugin_permissions` in `.meta`:
+//!
+//! ```json
+//! "plugin_permissions": {
+//!   "mode": "prompt",
+//!   "allow": { "mig": ["network"] }
+//! }
+//! ```
+//!
+//! Before a plugin runs, each permission it declares must be granted under
+//! `allow`. In `prompt` mode (the default) meta asks on the terminal for the
+//! rest; in `enforce` mode, and whenever there is no terminal, the plugin is
+//! refused. Without `plugin_permissions` nothing is checked.
+//!
+//! What a grant covers:
+//!
+//! - `env`: the plugin sees the full environment. Without it a sandboxed
+//!   plugin only gets [`BASE_ENV`] and `META_*` variables.
+//! - `write`: plans may run commands outside the workspace. Without it such
+//!   plans are rejected.
+//! - `network`: declared and approved only; meta cannot stop a native
+//!   plugin from opening connections. WASM plugins have no network access
+//!   either way.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+/// Something a plugin may need beyond reading the workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Permission {
+    Network,
+    /// Writing outside the workspace
+    Write,
+    /// Reading environment variables
+    Env,
+}
+
+impl Permission {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "network" => Some(Permission::Network),
+            "write" => Some(Permission::Write),
+            "env" => Some(Permission::Env),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Permission::Network => "network",
+            Permission::Write => "write",
+            Permission::Env => "env",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Permission::Network => "network access",
+            Permission::Write => "write access outside the workspace",
+            Permission::Env => "access to environment variables",
+        }
+    }
+}
+
+/// Variables a sandboxed plugin keeps without the `env` permission, besides
+/// `META_*`.
+pub const BASE_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "TERM",
+    "LANG",
+    "TMPDIR",
+    "SYSTEMROOT",
+];
+
+/// Permissions declared in raw plugin info, and any names not understood.
+pub fn declared(raw: &Value) -> (Vec<Permission>, Vec<String>) {
+    let mut known = Vec::new();
+    let mut unknown = Vec::new();
+    for item in raw
+        .get("permissions")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        match item.as_str().and_then(Permission::parse) {
+            Some(p) if !known.contains(&p) => known.push(p),
+            Some(_) => {}
+            None => unknown.push(
+                item.as_str()
+                    .map_or_else(|| item.to_string(), str::to_string),
+            ),
+        }
+    }
+    known.sort();
+    (known, unknown)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Ask for permissions not granted in `.meta`
+    #[default]
+    Prompt,
+    /// Refuse plugins needing permissions not granted in `.meta`
+    Enforce,
+}
+
+/// The `plugin_permissions` section of `.meta`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PermissionPolicy {
+    pub mode: Mode,
+    /// Plugin name → granted permissions
+    pub allow: HashMap<String, Vec<Permission>>,
+}
+
+impl PermissionPolicy {
+    /// Read `plugin_permissions` from a config document; `None` when the
+    /// workspace has not opted in.
+    pub fn from_value(config: &Value) -> Result<Option<Self>> {
+        let Some(section) = config.get("plugin_permissions") else {
+            return Ok(None);
+        };
+        let section = section
+            .as_object()
+            .context("plugin_permissions must be an object with mode and allow")?;
+        let mode = match section.get("mode").map(|m| m.as_str()) {
+            None | Some(Some("prompt")) => Mode::Prompt,
+            Some(Some("enforce")) => Mode::Enforce,
+            Some(_) => anyhow::bail!("plugin_permissions.mode must be \"prompt\" or \"enforce\""),
+        };
+        let mut allow = HashMap::new();
+        if let Some(grants) = section.get("allow") {
+            let grants = grants
+                .as_object()
+                .context("plugin_permissions.allow must map plugin names to permissions")?;
+            for (plugin, list) in grants {
+                let perms = list
+                    .as_array()
+                    .and_then(|items| {
+                        items
+                            .iter()
+                            .map(|p| p.as_str().and_then(Permission::parse))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .with_context(|| {
+                        format!("plugin_permissions.allow.{plugin} must list network, write or env")
+                    })?;
+                allow.insert(plugin.clone(), perms);
+            }
+        }
+        Ok(Some(PermissionPolicy { mode, allow }))
+    }
+
+    /// Declared permissions not granted to `plugin` in `.meta`.
+    pub fn missing(&self, plugin: &str, declared: &[Permission]) -> Vec<Permission> {
+        let granted = self.allow.get(plugin).map(Vec::as_slice).unwrap_or(&[]);
+        declared
+            .iter()
+            .copied()
+            .filter(|p| !granted.contains(p))
+            .collect()
+    }
+
+    /// Check that `plugin` may run with the `declared` permissions, asking
+    /// through `ask` in prompt mode. Returns the permissions it runs with.
+    pub fn authorize(
+        &self,
+        plugin: &str,
+        declared: &[Permission],
+        ask: impl FnOnce(&str, &[Permission]) -> Result<bool>,
+    ) -> Result<Vec<Permission>> {
+        let missing = self.missing(plugin, declared);
+        if missing.is_empty() {
+            return Ok(declared.to_vec());
+        }
+        let names: Vec<_> = missing.iter().map(|p| p.name()).collect();
+        let approved = match self.mode {
+            Mode::Enforce => false,
+            Mode::Prompt => ask(plugin, &missing)?,
+        };
+        if !approved {
+            anyhow::bail!(
+                "Plugin '{plugin}' needs {} permission, which is not granted \
+                 (add it to plugin_permissions.allow.{plugin} in .meta)",
+                names.join(", ")
+            );
+        }
+        Ok(declared.to_vec())
+    }
+}
+
+/// Ask on the terminal whether `plugin` may use `missing` for this run.
+/// Without a terminal the answer is no.
+pub fn prompt(plugin: &str, missing: &[Permission]) -> Result<bool> {
+    if !console::Term::stderr().is_term() || !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    let wanted: Vec<_> = missing.iter().map(|p| p.description()).collect();
+    eprint!(
+        "Plugin '{plugin}' asks for {}. Allow for this run? [y/N] ",
+        wanted.join(", ")
+    );
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Drop everything but [`BASE_ENV`] and `META_*` from `cmd`'s environment.
+pub fn restrict_env(cmd: &mut Command) {
+    cmd.env_clear();
+    for (key, value) in std::env::vars_os() {
+        let name = key.to_string_lossy();
+        if name.starts_with("META_") || BASE_ENV.contains(&name.as_ref()) {
+            cmd.env(&key, value);
+        }
+    }
+}
+
+/// Plan directories outside `workspace` (relative ones resolve against it).
+pub fn dirs_outside<'a>(workspace: &Path, dirs: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let root = workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf());
+    dirs.into_iter()
+        .filter(|dir| {
+            let path = root.join(dir);
+            let path = path.canonicalize().unwrap_or_else(|_| normalize(&path));
+            !path.starts_with(&root)
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// Resolve `.` and `..` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn declared_permissions_are_deduplicated_and_unknowns_kept() {
+        let (known, unknown) = declared(&json!({
+            "permissions": ["network", "env", "network", "camera", 3]
+        }));
+        assert_eq!(known, vec![Permission::Network, Permission::Env]);
+        assert_eq!(unknown, vec!["camera", "3"]);
+        assert_eq!(declared(&json!({})), (vec![], vec![]));
+    }
+
+    #[test]
+    fn policy_is_opt_in_and_validated() {
+        assert_eq!(PermissionPolicy::from_value(&json!({})).unwrap(), None);
+        let policy = PermissionPolicy::from_value(&json!({
+            "plugin_permissions": {"mode": "enforce", "allow": {"mig": ["network"]}}
+        }))
+        .unwrap()
+        .unwrap();
+        assert_eq!(policy.mode, Mode::Enforce);
+        assert_eq!(policy.allow["mig"], vec![Permission::Network]);
+
+        for bad in [
+            json!({"plugin_permissions": []}),
+            json!({"plugin_permissions": {"mode": "ask"}}),
+            json!({"plugin_permissions": {"allow": {"mig": ["camera"]}}}),
+        ] {
+            assert!(PermissionPolicy::from_value(&bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn authorize_grants_asks_or_refuses() {
+        let mut policy = PermissionPolicy::default();
+        policy
+            .allow
+            .insert("mig".to_string(), vec![Permission::Network]);
+        let wanted = [Permission::Network, Permission::Env];
+        let never = |_: &str, _: &[Permission]| -> Result<bool> { panic!("asked") };
+
+        assert!(policy
+            .authorize("mig", &[Permission::Network], never)
+            .is_ok());
+
+        let mut asked = Vec::new();
+        let granted = policy
+            .authorize("mig", &wanted, |_, missing| {
+                asked = missing.to_vec();
+                Ok(true)
+            })
+            .unwrap();
+        assert_eq!(asked, vec![Permission::Env]);
+        assert_eq!(granted, wanted);
+
+        let err = policy
+            .authorize("mig", &wanted, |_, _| Ok(false))
+            .unwrap_err();
+        assert!(err.to_string().contains("needs env permission"));
+
+        policy.mode = Mode::Enforce;
+        assert!(policy.authorize("mig", &wanted, never).is_err());
+    }
+
+    #[test]
+    fn dirs_outside_the_workspace_are_reported() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir(tmp.path().join("api")).unwrap();
+        let api = tmp.path().join("api").to_string_lossy().to_string();
+        let outside = dirs_outside(tmp.path(), ["api", ".", api.as_str(), "../elsewhere", "/"]);
+        assert_eq!(outside, vec!["../elsewhere", "/"]);
+    }
+}
@@ -0,0 +1,509 @@
+//! Command policy for multi-repo runs.
+//!
+//! The human counterpart of [`crate::agent_guard`]: a `policy` section in
+//! `.meta` denies commands, or asks before running them, once they would
+//! run in `min_repos` (default 2) or more repos at once:
+//!
+//! ```json
+//! "policy": {
+//!   "min_repos": 2,
+//!   "rules": [
+//!     { "match": "rm -rf", "action": "deny", "message": "clean up one repo at a time" },
+//!     { "match": "git push --force", "action": "confirm" },
+//!     { "regex": "^npm publish", "action": "confirm", "min_repos": 1 }
+//!   ]
+//! }
+//! ```
+//!
+//! `match` is a sequence of words that must appear in one of the command's
+//! simple commands (split at `&&`, `||`, `;`, `|` and `&`) in that order,
+//! not necessarily adjacent, so `git push --force` also catches
+//! `git push origin main --force`. Both sides are split into words like a
+//! shell would and normalized first: short flags can come in any order or
+//! cluster (`rm -rf`, `rm -fr` and `rm -f -r` are the same), flags only
+//! need to come after the word before them in the rule, `--flag=value`
+//! counts as `--flag`, and `/bin/rm` as `rm`. `regex` is matched anywhere in
+//! the raw command.
+//!
+//! [`enforce`] runs before anything is spawned, for `meta exec` and other
+//! loop commands, for plugin execution plans and for the built-in
+//! `meta git`; `--dry-run` skips it.
+
+use anyhow::{Context, Result};
+use colored::*;
+use regex::Regex;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use crate::config;
+use crate::error::MetaError;
+
+/// Rules apply from this many repos unless `min_repos` says otherwise.
+pub const DEFAULT_MIN_REPOS: usize = 2;
+
+// ── Types ───────────────────────────────────────────────
+
+/// What happens when a rule matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Refuse to run.
+    Deny,
+    /// Run only once the user confirms on the terminal.
+    Confirm,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Words(Vec<WordGroup>),
+    Regex(Regex),
+}
+
+/// A word of a `match` rule with the flags that follow it; `word` is `None`
+/// for flags at the start of the rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WordGroup {
+    word: Option<String>,
+    flags: Vec<String>,
+}
+
+impl Matcher {
+    fn is_match(&self, command: &str) -> bool {
+        match self {
+            Matcher::Words(groups) => normalize(command)
+                .iter()
+                .any(|words| matches_words(groups, words)),
+            Matcher::Regex(regex) => regex.is_match(command),
+        }
+    }
+}
+
+/// One entry of `policy.rules`.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// The `match` or `regex` value, for messages.
+    pub pattern: String,
+    matcher: Matcher,
+    pub action: Action,
+    pub message: Option<String>,
+    pub min_repos: usize,
+}
+
+/// The `policy` section of `.meta`.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    pub rules: Vec<Rule>,
+}
+
+/// A rule that applies to a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub action: Action,
+    pub pattern: String,
+    pub message: Option<String>,
+    /// The first matching command.
+    pub command: String,
+    /// How many repos would run a matching command.
+    pub repos: usize,
+}
+
+impl Violation {
+    fn describe(&self) -> String {
+        let mut text = format!(
+            "'{}' in {} repos matches policy rule '{}'",
+            self.command, self.repos, self.pattern
+        );
+        if let Some(message) = &self.message {
+            text.push_str(&format!(" ({message})"));
+        }
+        text
+    }
+}
+
+impl Policy {
+    /// Rules that apply when each of `commands` runs in its own repo,
+    /// denials first.
+    pub fn check(&self, commands: &[&str]) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = self
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                let matching: Vec<&str> = commands
+                    .iter()
+                    .copied()
+                    .filter(|command| rule.matcher.is_match(command))
+                    .collect();
+                (matching.len() >= rule.min_repos).then(|| Violation {
+                    action: rule.action,
+                    pattern: rule.pattern.clone(),
+                    message: rule.message.clone(),
+                    command: matching[0].to_string(),
+                    repos: matching.len(),
+                })
+            })
+            .collect();
+        violations.sort_by_key(|v| v.action != Action::Deny);
+        violations
+    }
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Load the policy from the `.meta` config at `config_path`.
+pub fn load(config_path: &Path) -> Result<Policy> {
+    let value = config::read_meta_config_value(config_path)?;
+    parse_config(value.get("policy"))
+        .with_context(|| format!("Invalid policy in {}", config_path.display()))
+}
+
+/// Parse a `policy` section: `{"min_repos", "rules"}`, both optional.
+pub fn parse_config(section: Option<&serde_json::Value>) -> Result<Policy> {
+    let Some(section) = section else {
+        return Ok(Policy::default());
+    };
+    let map = section.as_object().context("expected an object")?;
+    let min_repos = match map.get("min_repos") {
+        Some(value) => parse_min_repos(value)?,
+        None => DEFAULT_MIN_REPOS,
+    };
+    let rules = match map.get("rules") {
+        Some(rules) => rules.as_array().context("rules must be an array")?,
+        None => return Ok(Policy::default()),
+    };
+    let rules = rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| parse_rule(rule, min_repos).with_context(|| format!("rules[{i}]")))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Policy { rules })
+}
+
+/// Check `commands` (one per repo) against the policy of the `.meta`
+/// config at `config_path`; without a config there is nothing to check.
+/// Denied commands fail with [`MetaError::PolicyDenied`]; commands that
+/// need confirmation are asked about on the terminal, and fail the same way
+/// when declined or when there is no terminal to ask on.
+pub fn enforce(config_path: Option<&Path>, commands: &[&str]) -> Result<()> {
+    let Some(config_path) = config_path else {
+        return Ok(());
+    };
+    let policy = load(config_path)?;
+    for violation in policy.check(commands) {
+        match violation.action {
+            Action::Deny => {
+                return Err(MetaError::PolicyDenied(violation.describe()).into());
+            }
+            Action::Confirm => {
+                if !confirm(&violation)? {
+                    let reason = format!("{}, not confirmed", violation.describe());
+                    return Err(MetaError::PolicyDenied(reason).into());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn parse_rule(value: &serde_json::Value, min_repos: usize) -> Result<Rule> {
+    let map = value.as_object().context("expected an object")?;
+    let (pattern, matcher) = match (map.get("match"), map.get("regex")) {
+        (Some(words), None) => {
+            let words = words.as_str().context("match must be a string")?;
+            let groups = word_groups(words)?;
+            (words.to_string(), Matcher::Words(groups))
+        }
+        (None, Some(regex)) => {
+            let regex = regex.as_str().context("regex must be a string")?;
+            let compiled = Regex::new(regex).with_context(|| format!("invalid regex '{regex}'"))?;
+            (regex.to_string(), Matcher::Regex(compiled))
+        }
+        _ => anyhow::bail!("expected exactly one of match or regex"),
+    };
+    let action = match map.get("action").and_then(|a| a.as_str()) {
+        Some("deny") => Action::Deny,
+        Some("confirm") => Action::Confirm,
+        _ => anyhow::bail!("action must be \"deny\" or \"confirm\""),
+    };
+    let message = match map.get("message") {
+        Some(message) => Some(
+            message
+                .as_str()
+                .context("message must be a string")?
+                .to_string(),
+        ),
+        None => None,
+    };
+    let min_repos = match map.get("min_repos") {
+        Some(value) => parse_min_repos(value)?,
+        None => min_repos,
+    };
+    Ok(Rule {
+        pattern,
+        matcher,
+        action,
+        message,
+        min_repos,
+    })
+}
+
+/// Group the normalized words of a `match` value by the word each flag
+/// follows.
+fn word_groups(words: &str) -> Result<Vec<WordGroup>> {
+    let mut commands = normalize(words);
+    anyhow::ensure!(commands.len() <= 1, "match must be a single command");
+    let words = commands.pop().unwrap_or_default();
+    anyhow::ensure!(!words.is_empty(), "match must not be empty");
+    let mut groups: Vec<WordGroup> = Vec::new();
+    for word in words {
+        if !is_flag(&word) {
+            groups.push(WordGroup {
+                word: Some(word),
+                flags: Vec::new(),
+            });
+        } else if let Some(group) = groups.last_mut() {
+            group.flags.push(word);
+        } else {
+            groups.push(WordGroup {
+                word: None,
+                flags: vec![word],
+            });
+        }
+    }
+    Ok(groups)
+}
+
+/// Whether the rule's words appear in `words` (one simple command) in
+/// order, each group's flags anywhere after its word.
+fn matches_words(groups: &[WordGroup], words: &[String]) -> bool {
+    let mut start = 0;
+    for group in groups {
+        if let Some(word) = &group.word {
+            match words[start..].iter().position(|w| w == word) {
+                Some(i) => start += i + 1,
+                None => return false,
+            }
+        }
+        if !group.flags.iter().all(|flag| words[start..].contains(flag)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Split `command` into its simple commands, each a list of normalized
+/// words: quotes and backslashes are resolved as in `sh`, clustered short
+/// flags are split (`-rf` becomes `-r`, `-f`), `--flag=value` becomes
+/// `--flag`, and the program (the first word that is not a `VAR=value`
+/// assignment) loses its directory.
+fn normalize(command: &str) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    for words in split_words(command) {
+        let mut normalized = Vec::new();
+        let mut program_seen = false;
+        for word in words {
+            if let Some(long) = word.strip_prefix("--").filter(|long| !long.is_empty()) {
+                let name = long.split('=').next().unwrap_or(long);
+                normalized.push(format!("--{name}"));
+            } else if is_flag(&word) && word[1..].chars().all(|c| c.is_ascii_alphabetic()) {
+                normalized.extend(word[1..].chars().map(|c| format!("-{c}")));
+            } else if !program_seen && !is_flag(&word) && !word.contains('=') {
+                program_seen = true;
+                let program = word.rsplit('/').next().filter(|name| !name.is_empty());
+                normalized.push(program.unwrap_or(&word).to_string());
+            } else {
+                normalized.push(word);
+            }
+        }
+        if !normalized.is_empty() {
+            commands.push(normalized);
+        }
+    }
+    commands
+}
+
+fn is_flag(word: &str) -> bool {
+    word.len() > 1 && word.starts_with('-')
+}
+
+/// Split `command` into simple commands and those into words, resolving
+/// quotes and backslash escapes. `&` in a redirection such as `2>&1` does
+/// not end a command.
+fn split_words(command: &str) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    let mut words = Vec::new();
+    // `None` between words, so `''` still counts as a word
+    let mut word: Option<String> = None;
+    let mut prev = ' ';
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let quoted = chars.by_ref().take_while(|&c| c != '\'');
+                word.get_or_insert_with(String::new).extend(quoted);
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(next @ ('"' | '\\' | '$' | '`')) => word.push(next),
+                            Some(next) => word.extend(['\\', next]),
+                            None => word.push('\\'),
+                        },
+                        _ => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            '&' if matches!(prev, '>' | '<') => word.get_or_insert_with(String::new).push(c),
+            ';' | '|' | '&' | '\n' => {
+                words.extend(word.take());
+                if !words.is_empty() {
+                    commands.push(std::mem::take(&mut words));
+                }
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            _ => word.get_or_insert_with(String::new).push(c),
+        }
+        prev = c;
+    }
+    words.extend(word);
+    if !words.is_empty() {
+        commands.push(words);
+    }
+    commands
+}
+
+fn parse_min_repos(value: &serde_json::Value) -> Result<usize> {
+    value
+        .as_u64()
+        .filter(|n| *n >= 1)
+        .and_then(|n| usize::try_from(n).ok())
+        .context("min_repos must be a whole number of at least 1")
+}
+
+/// Ask whether to go ahead with `violation`. Without a terminal the answer
+/// is no.
+fn confirm(violation: &Violation) -> Result<bool> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Ok(false);
+    }
+    eprint!(
+        "{} {}. Run anyway? [y/N] ",
+        "policy:".yellow().bold(),
+        violation.describe()
+    );
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy() -> Policy {
+        parse_config(Some(&json!({
+            "rules": [
+                { "match": "rm -rf", "action": "deny", "message": "one repo at a time" },
+                { "match": "git push --force", "action": "confirm" },
+                { "regex": "^npm publish", "action": "confirm", "min_repos": 1 }
+            ]
+        })))
+        .unwrap()
+    }
+
+    #[test]
+    fn rules_apply_from_min_repos() {
+        let policy = policy();
+        assert!(policy.check(&["rm -rf node_modules"]).is_empty());
+
+        let violations = policy.check(&["rm -rf node_modules", "rm -rf node_modules"]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].action, Action::Deny);
+        assert_eq!(violations[0].repos, 2);
+        assert_eq!(
+            violations[0].describe(),
+            "'rm -rf node_modules' in 2 repos matches policy rule 'rm -rf' (one repo at a time)"
+        );
+
+        let violations = policy.check(&["npm publish --tag next"]);
+        assert_eq!(violations[0].action, Action::Confirm);
+    }
+
+    #[test]
+    fn words_match_in_order_not_adjacent() {
+        let policy = policy();
+        let pushes = [
+            "git push origin main --force",
+            "git push origin main --force",
+        ];
+        assert_eq!(policy.check(&pushes)[0].pattern, "git push --force");
+        assert!(policy
+            .check(&["git --force push", "git --force push"])
+            .is_empty());
+    }
+
+    #[test]
+    fn commands_are_compared_as_normalized_words() {
+        let policy = policy();
+        let caught = |command: &str| !policy.check(&[command, command]).is_empty();
+        assert!(caught("rm -fr dist"));
+        assert!(caught("rm -f -r dist"));
+        assert!(caught("rm -r dist -f"));
+        assert!(caught("/bin/rm -rf dist"));
+        assert!(caught("cd web && FORCE=1 rm -rf dist"));
+        assert!(caught("git push --force=true"));
+        assert!(caught(r#""rm" '-rf' dist"#));
+        assert!(!caught("rm -r dist"));
+        assert!(!caught("echo 'rm -rf dist'"));
+        assert!(!caught("rm dist; echo -rf"));
+        assert!(!caught("rm -r dist 2>&1 | grep -f patterns"));
+    }
+
+    #[test]
+    fn enforce_reads_the_given_config() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let config_path = tmp.path().join("team.meta");
+        std::fs::write(
+            &config_path,
+            r#"{"projects": {}, "policy": {"rules": [{"match": "rm -rf", "action": "deny"}]}}"#,
+        )
+        .unwrap();
+        let err = enforce(Some(&config_path), &["rm -fr dist"; 2]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MetaError>(),
+            Some(MetaError::PolicyDenied(_))
+        ));
+        assert!(enforce(Some(&config_path), &["rm -fr dist"]).is_ok());
+        assert!(enforce(None, &["rm -fr dist"; 2]).is_ok());
+    }
+
+    #[test]
+    fn denials_come_first() {
+        let commands = ["git push --force && rm -rf dist"; 2];
+        let actions: Vec<Action> = policy().check(&commands).iter().map(|v| v.action).collect();
+        assert_eq!(actions, vec![Action::Deny, Action::Confirm]);
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let err = parse_config(Some(&json!({
+            "rules": [{ "match": "rm", "regex": "rm", "action": "deny" }]
+        })))
+        .unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "rules[0]: expected exactly one of match or regex"
+        );
+        assert!(parse_config(Some(
+            &json!({ "rules": [{ "match": "rm", "action": "ask" }] })
+        ))
+        .is_err());
+        assert!(parse_config(Some(&json!({ "min_repos": 0 }))).is_err());
+    }
+}
@@ -0,0 +1,342 @@
+//! `--if` predicates over a repo's files, git state and project metadata.
+//!
+//! ```text
+//! meta --if 'exists(package.json)' exec -- npm test
+//! meta --if 'branch==main && !dirty' git pull
+//! meta --if 'tag==backend || lang==rust' exec -- make
+//! ```
+//!
+//! Terms:
+//!
+//! - `exists(<path>)` — a file or directory relative to the repo
+//! - `dirty`, `clean` — uncommitted changes, or none
+//! - `ahead`, `behind` — commits not yet pushed, or not yet pulled
+//! - `branch`, `name`, `tag`, `lang` compared with `==` or `!=`. A value
+//!   containing `*`, `?` or `[` is a glob (`branch==release/*`); `tag`
+//!   matches if any of the repo's tags do, and `lang` is the language
+//!   [`crate::lang`] detects
+//!
+//! Terms combine with `!`, `&&`, `||` and parentheses; values may be quoted
+//! with `'` or `"`. The same expressions work in `--include` / `--exclude`
+//! as `if:<expr>`.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::path::Path;
+
+use crate::filter::{glob_to_regex, Target};
+use crate::git_utils;
+
+/// A parsed predicate.
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    Exists(String),
+    Dirty,
+    Clean,
+    Ahead,
+    Behind,
+    Compare {
+        field: Field,
+        value: Value,
+        negated: bool,
+    },
+    Not(Box<Predicate>),
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Branch,
+    Name,
+    Tag,
+    Lang,
+}
+
+/// The right-hand side of a comparison.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Exact(String),
+    Glob(Regex),
+}
+
+impl Value {
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Value::Exact(v) => v == text,
+            Value::Glob(re) => re.is_match(text),
+        }
+    }
+}
+
+impl Predicate {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let tokens = tokenize(expr).with_context(|| format!("Invalid predicate '{expr}'"))?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let predicate = parser
+            .or()
+            .and_then(|p| match parser.peek() {
+                None => Ok(p),
+                Some(t) => anyhow::bail!("unexpected {}", t.describe()),
+            })
+            .with_context(|| format!("Invalid predicate '{expr}'"))?;
+        Ok(predicate)
+    }
+
+    /// Whether the repo at `target.path` satisfies the predicate.
+    pub fn matches(&self, target: &Target) -> bool {
+        let path = Path::new(target.path);
+        match self {
+            Predicate::Exists(file) => path.join(file).exists(),
+            Predicate::Dirty => git_utils::is_dirty(path) == Some(true),
+            Predicate::Clean => git_utils::is_dirty(path) == Some(false),
+            Predicate::Ahead => git_utils::ahead_behind(path).is_some_and(|(a, _)| a > 0),
+            Predicate::Behind => git_utils::ahead_behind(path).is_some_and(|(_, b)| b > 0),
+            Predicate::Compare {
+                field,
+                value,
+                negated,
+            } => {
+                let found = match field {
+                    Field::Branch => {
+                        value.matches(&git_utils::current_branch(path).unwrap_or_default())
+                    }
+                    Field::Name => value.matches(target.name),
+                    Field::Tag => target.tags.iter().any(|t| value.matches(t)),
+                    Field::Lang => crate::lang::detect(path).is_some_and(|l| value.matches(l)),
+                };
+                found != *negated
+            }
+            Predicate::Not(inner) => !inner.matches(target),
+            Predicate::And(a, b) => a.matches(target) && b.matches(target),
+            Predicate::Or(a, b) => a.matches(target) || b.matches(target),
+        }
+    }
+}
+
+// ── Parsing ─────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Eq,
+    Ne,
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(w) => format!("'{w}'"),
+            Token::Eq => "'=='".to_string(),
+            Token::Ne => "'!='".to_string(),
+            Token::Not => "'!'".to_string(),
+            Token::And => "'&&'".to_string(),
+            Token::Or => "'||'".to_string(),
+            Token::Open => "'('".to_string(),
+            Token::Close => "')'".to_string(),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '=' | '!' | '&' | '|' => {
+                chars.next();
+                let doubled = match c {
+                    '!' => '=',
+                    c => c,
+                };
+                if chars.peek() == Some(&doubled) {
+                    chars.next();
+                    tokens.push(match c {
+                        '=' => Token::Eq,
+                        '!' => Token::Ne,
+                        '&' => Token::And,
+                        _ => Token::Or,
+                    });
+                } else if c == '!' {
+                    tokens.push(Token::Not);
+                } else {
+                    anyhow::bail!("expected '{c}{c}'");
+                }
+            }
+            '\'' | '"' => {
+                chars.next();
+                let word: String = chars.by_ref().take_while(|&ch| ch != c).collect();
+                tokens.push(Token::Word(word));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()=!&|'\"".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(t) if t == expected => Ok(()),
+            Some(t) => anyhow::bail!("expected {}, found {}", expected.describe(), t.describe()),
+            None => anyhow::bail!("expected {} at the end", expected.describe()),
+        }
+    }
+
+    fn or(&mut self) -> Result<Predicate> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = Predicate::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Predicate> {
+        let mut left = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            left = Predicate::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Predicate> {
+        match self.next() {
+            Some(Token::Not) => Ok(Predicate::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let inner = self.or()?;
+                self.expect(Token::Close)?;
+                Ok(inner)
+            }
+            Some(Token::Word(word)) => self.term(&word),
+            Some(t) => anyhow::bail!("unexpected {}", t.describe()),
+            None => anyhow::bail!("expected a term at the end"),
+        }
+    }
+
+    fn term(&mut self, word: &str) -> Result<Predicate> {
+        let field = match word {
+            "exists" => {
+                self.expect(Token::Open)?;
+                let Some(Token::Word(path)) = self.next() else {
+                    anyhow::bail!("exists() needs a path");
+                };
+                self.expect(Token::Close)?;
+                return Ok(Predicate::Exists(path));
+            }
+            "dirty" => return Ok(Predicate::Dirty),
+            "clean" => return Ok(Predicate::Clean),
+            "ahead" => return Ok(Predicate::Ahead),
+            "behind" => return Ok(Predicate::Behind),
+            "branch" => Field::Branch,
+            "name" => Field::Name,
+            "tag" => Field::Tag,
+            "lang" => Field::Lang,
+            other => anyhow::bail!(
+                "unknown term '{other}' (expected exists(), dirty, clean, ahead, behind, branch, name, tag or lang)"
+            ),
+        };
+        let negated = match self.next() {
+            Some(Token::Eq) => false,
+            Some(Token::Ne) => true,
+            _ => anyhow::bail!("expected '==' or '!=' after '{word}'"),
+        };
+        let Some(Token::Word(text)) = self.next() else {
+            anyhow::bail!("expected a value after '{word}'");
+        };
+        let value = if text.contains(['*', '?', '[']) {
+            Value::Glob(Regex::new(&glob_to_regex(&text)).context("invalid glob")?)
+        } else {
+            Value::Exact(text)
+        };
+        Ok(Predicate::Compare {
+            field,
+            value,
+            negated,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holds(expr: &str, target: &Target) -> bool {
+        Predicate::parse(expr).unwrap().matches(target)
+    }
+
+    #[test]
+    fn evaluates_files_metadata_and_operators() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("package.json"), "{}").unwrap();
+        let path = tmp.path().display().to_string();
+        let tags = vec!["frontend".to_string(), "web-app".to_string()];
+        let web = Target {
+            path: &path,
+            name: "web",
+            tags: &tags,
+        };
+
+        assert!(holds("exists(package.json)", &web));
+        assert!(!holds("exists('Cargo.toml')", &web));
+        assert!(holds("lang==javascript && tag==frontend", &web));
+        assert!(holds("tag==web-* && name!=api", &web));
+        assert!(holds("!(name==api || tag==backend)", &web));
+        assert!(holds(
+            "name==api || exists(package.json) && !exists(go.mod)",
+            &web
+        ));
+        // Not a git repo: neither dirty nor clean, and on no branch
+        assert!(!holds("dirty", &web));
+        assert!(!holds("clean", &web));
+        assert!(holds("branch!=main", &web));
+    }
+
+    #[test]
+    fn parse_errors_name_the_problem() {
+        let err = |expr: &str| format!("{:#}", Predicate::parse(expr).unwrap_err());
+        assert!(err("colour==red").contains("unknown term 'colour'"));
+        assert!(err("branch main").contains("expected '==' or '!='"));
+        assert!(err("(dirty").contains("expected ')' at the end"));
+        assert!(err("dirty & clean").contains("expected '&&'"));
+        assert!(err("dirty clean").contains("unexpected 'clean'"));
+    }
+}
@@ -0,0 +1,248 @@
+//! Live progress view for `--ui tui`.
+//!
+//! One row per repo with a spinner, elapsed time and the last line of
+//! output, redrawn in place while the run progresses:
+//!
+//! ```text
+//! ⠹ api      12.3s  Compiling serde v1.0.200
+//! ✓ web       4.1s
+//! · docs            waiting
+//! ```
+//!
+//! The view is drawn with ratatui in an inline viewport on stderr and
+//! cleared when the run ends, leaving the usual summary. Callers fall back
+//! to plain output when stderr is not a terminal.
+
+use ratatui::backend::CrosstermBackend;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+use ratatui::{Terminal, TerminalOptions, Viewport};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::runner::RepoStatus;
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// How often the view is redrawn.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Where one repo is in the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowState {
+    Waiting,
+    Running(Instant),
+    Finished(RepoStatus, Duration),
+}
+
+#[derive(Debug, Clone)]
+struct Row {
+    name: String,
+    state: RowState,
+    last_line: String,
+}
+
+/// Progress of every repo in a run, kept separate from the terminal so it
+/// can be tested.
+#[derive(Debug)]
+pub struct Progress {
+    rows: Vec<Row>,
+    created: Instant,
+}
+
+impl Progress {
+    pub fn new(names: &[String]) -> Self {
+        Progress {
+            rows: names
+                .iter()
+                .map(|name| Row {
+                    name: name.clone(),
+                    state: RowState::Waiting,
+                    last_line: String::new(),
+                })
+                .collect(),
+            created: Instant::now(),
+        }
+    }
+
+    pub fn start(&mut self, i: usize) {
+        self.rows[i].state = RowState::Running(Instant::now());
+    }
+
+    /// Record a line of output; blank lines keep the previous one.
+    pub fn line(&mut self, i: usize, line: &str) {
+        let line = console::strip_ansi_codes(line);
+        if !line.trim().is_empty() {
+            self.rows[i].last_line = line.trim_end().replace('\t', " ");
+        }
+    }
+
+    pub fn finish(&mut self, i: usize, status: RepoStatus, duration: Duration) {
+        self.rows[i].state = RowState::Finished(status, duration);
+    }
+
+    /// The view as of `now`, at most `height` lines of at most `columns`
+    /// characters. Rows that don't fit are counted on the last line.
+    pub fn render(&self, now: Instant, columns: usize, height: usize) -> Vec<Line<'static>> {
+        let width = self
+            .rows
+            .iter()
+            .map(|r| console::measure_text_width(&r.name))
+            .max()
+            .unwrap_or(0);
+        let ticks = now.saturating_duration_since(self.created).as_millis() / 100;
+        let frame = SPINNER[ticks as usize % SPINNER.len()];
+        let dim = Style::new().add_modifier(Modifier::DIM);
+
+        let shown = if self.rows.len() > height {
+            height.saturating_sub(1)
+        } else {
+            self.rows.len()
+        };
+        let mut lines: Vec<Line> = self.rows[..shown]
+            .iter()
+            .map(|row| {
+                let (mark, style, elapsed, detail) = match row.state {
+                    RowState::Waiting => ('·', dim, String::new(), "waiting".to_string()),
+                    RowState::Running(start) => (
+                        frame,
+                        Style::new().fg(Color::Cyan),
+                        seconds(now.saturating_duration_since(start)),
+                        row.last_line.clone(),
+                    ),
+                    RowState::Finished(status, duration) => {
+                        let (mark, style) = match status {
+                            RepoStatus::Ok => ('✓', Style::new().fg(Color::Green)),
+                            RepoStatus::Failed => ('✗', Style::new().fg(Color::Red)),
+                            RepoStatus::Skipped => ('-', dim),
+                            RepoStatus::Cancelled | RepoStatus::TimedOut => {
+                                ('✗', Style::new().fg(Color::Yellow))
+                            }
+                        };
+                        let detail = match status {
+                            RepoStatus::Ok => String::new(),
+                            other => other.label().to_string(),
+                        };
+                        (mark, style, seconds(duration), detail)
+                    }
+                };
+                let head = format!(" {:<width$}  {elapsed:>6}", row.name);
+                let room = columns.saturating_sub(3 + console::measure_text_width(&head));
+                let mut spans = vec![styled(mark.to_string(), style), Span::raw(head)];
+                if !detail.is_empty() {
+                    let detail = console::truncate_str(&detail, room, "…").to_string();
+                    spans.extend([Span::raw("  "), styled(detail, dim)]);
+                }
+                Line::from(spans)
+            })
+            .collect();
+        if shown < self.rows.len() {
+            let more = format!("… {} more", self.rows.len() - shown);
+            lines.push(Line::from(styled(more, dim)));
+        }
+        lines
+    }
+}
+
+fn seconds(d: Duration) -> String {
+    format!("{:.1}s", d.as_secs_f64())
+}
+
+/// `text` in `style`, unless colors are off (`NO_COLOR`, `--no-color`).
+fn styled(text: String, style: Style) -> Span<'static> {
+    if colored::control::SHOULD_COLORIZE.should_colorize() {
+        Span::styled(text, style)
+    } else {
+        Span::raw(text)
+    }
+}
+
+/// Whether the view can be drawn here.
+pub fn supported() -> bool {
+    std::io::stderr().is_terminal()
+}
+
+/// Redraw `progress` on stderr until `done` is set, then clear it. The
+/// view takes an inline viewport below the cursor, one line per repo as
+/// far as the terminal allows; the summary is printed where it was.
+pub fn draw_until(progress: &Mutex<Progress>, done: &AtomicBool) {
+    let rows = progress
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .rows
+        .len();
+    let screen = ratatui::crossterm::terminal::size().map_or(24, |(_, rows)| rows);
+    let height = rows.min(usize::from(screen.saturating_sub(1)).max(1));
+    let options = TerminalOptions {
+        viewport: Viewport::Inline(height as u16),
+    };
+    let Ok(mut terminal) =
+        Terminal::with_options(CrosstermBackend::new(std::io::stderr()), options)
+    else {
+        // Without a view the run still goes on; only the summary is shown
+        return;
+    };
+    let _ = terminal.hide_cursor();
+    while !done.load(Ordering::SeqCst) {
+        let _ = terminal.draw(|frame| {
+            let area = frame.area();
+            let lines = progress.lock().unwrap_or_else(|e| e.into_inner()).render(
+                Instant::now(),
+                usize::from(area.width),
+                usize::from(area.height),
+            );
+            frame.render_widget(Paragraph::new(lines), area);
+        });
+        std::thread::sleep(REDRAW_INTERVAL);
+    }
+    let _ = terminal.clear();
+    let _ = terminal.show_cursor();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress() -> Progress {
+        Progress::new(&["api".to_string(), "web".to_string(), "docs".to_string()])
+    }
+
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn rows_follow_each_repo() {
+        let mut p = progress();
+        p.start(0);
+        p.line(0, "\u{1b}[32mCompiling\u{1b}[0m serde");
+        p.line(0, "   ");
+        p.start(1);
+        p.finish(1, RepoStatus::Failed, Duration::from_millis(4100));
+
+        let lines = text(&p.render(Instant::now(), 80, 10));
+        assert!(lines[0].contains(" api "));
+        assert!(lines[0].ends_with("0.0s  Compiling serde"));
+        assert_eq!(lines[1], "✗ web     4.1s  fail");
+        assert_eq!(lines[2], "· docs          waiting");
+    }
+
+    #[test]
+    fn long_lines_are_truncated_to_the_terminal() {
+        let mut p = progress();
+        p.start(0);
+        p.line(0, &"x".repeat(200));
+        let line = &text(&p.render(Instant::now(), 40, 10))[0];
+        assert_eq!(console::measure_text_width(line), 40);
+        assert!(line.ends_with('…'));
+    }
+
+    #[test]
+    fn rows_beyond_the_viewport_are_counted() {
+        let lines = text(&progress().render(Instant::now(), 80, 2));
+        assert_eq!(lines, ["· api           waiting", "… 2 more"]);
+    }
+}
@@ -0,0 +1,550 @@
+//! `meta project add/remove/rename`: manage the `projects` map of a workspace.
+//!
+//! Edits go through [`ConfigDocument`], so key order, indentation, and (for
+//! TOML) comments survive. Child repo directories are listed in the meta
+//! repo's `.gitignore`, which these commands keep in step with the config.
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::{self, ConfigDocument};
+use crate::git_utils;
+
+// ── Entry Points ────────────────────────────────────────
+
+/// Entry point for `meta project add <alias> <path> [--url <git-url>]`.
+pub fn handle_add(
+    config_name: Option<&PathBuf>,
+    alias: &str,
+    path: &str,
+    url: Option<&str>,
+    tags: &[String],
+) -> Result<()> {
+    let (mut doc, meta_dir) = load_workspace(config_name)?;
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let project_path = workspace_relative(&meta_dir, &cwd.join(path))?;
+
+    let entry = add_project(&mut doc, &meta_dir, alias, &project_path, url, tags)?;
+    save(&doc)?;
+    update_gitignore(&meta_dir, None, Some(&project_path))?;
+
+    println!(
+        "{} Added project '{}' ({})",
+        "✓".green(),
+        alias,
+        project_path
+    );
+    if !meta_dir.join(&project_path).exists() {
+        if let Some(url) = entry_repo(&entry) {
+            println!("  Not cloned yet: git clone {url} {project_path}");
+        }
+    }
+    Ok(())
+}
+
+/// Entry point for `meta project remove <alias> [--delete-dir]`.
+pub fn handle_remove(config_name: Option<&PathBuf>, alias: &str, delete_dir: bool) -> Result<()> {
+    let (mut doc, meta_dir) = load_workspace(config_name)?;
+    let project_path = remove_project(&mut doc, &meta_dir, alias, delete_dir)?;
+    // Only delete once the config no longer lists the project, so a failed
+    // save never leaves an entry pointing at a deleted directory
+    save(&doc)?;
+    if delete_dir {
+        delete_project_dir(&meta_dir, &project_path)?;
+    }
+    update_gitignore(&meta_dir, Some(&project_path), None)?;
+
+    println!("{} Removed project '{}'", "✓".green(), alias);
+    if delete_dir {
+        println!("  Deleted {project_path}/");
+    } else if meta_dir.join(&project_path).exists() {
+        println!("  {project_path}/ was left on disk (use --delete-dir to remove it)");
+    }
+    Ok(())
+}
+
+/// Entry point for `meta project rename <alias> <new-alias>`.
+pub fn handle_rename(config_name: Option<&PathBuf>, alias: &str, new_alias: &str) -> Result<()> {
+    let (mut doc, meta_dir) = load_workspace(config_name)?;
+    let moved = rename_project(&mut doc, &meta_dir, alias, new_alias)?;
+    save(&doc)?;
+
+    println!(
+        "{} Renamed project '{}' to '{}'",
+        "✓".green(),
+        alias,
+        new_alias
+    );
+    if let Some((from, to)) = moved {
+        update_gitignore(&meta_dir, Some(&from), Some(&to))?;
+        println!("  Moved {from}/ to {to}/");
+    }
+    Ok(())
+}
+
+// ── Operations ──────────────────────────────────────────
+
+/// Add a project entry, returning what was written.
+///
+/// The entry is the short `alias = url` form when the path matches the alias
+/// and a URL is known (given, or read from an existing clone's `origin`);
+/// otherwise it is an extended entry with `repo` and/or `path`. Tags always
+/// need the extended form.
+pub fn add_project(
+    doc: &mut ConfigDocument,
+    meta_dir: &Path,
+    alias: &str,
+    project_path: &str,
+    url: Option<&str>,
+    tags: &[String],
+) -> Result<Value> {
+    anyhow::ensure!(!alias.trim().is_empty(), "Project alias cannot be empty");
+    anyhow::ensure!(
+        doc.get(&["projects", alias]).is_none(),
+        "Project '{}' already exists in {}",
+        alias,
+        file_name(doc)
+    );
+    if let Some(owner) = project_paths(doc)
+        .into_iter()
+        .find(|(_, path)| path == project_path)
+        .map(|(name, _)| name)
+    {
+        anyhow::bail!("Path '{project_path}' is already used by project '{owner}'");
+    }
+
+    let dir = meta_dir.join(project_path);
+    let url = url
+        .map(str::to_string)
+        .or_else(|| git_utils::remote_url(&dir));
+    if url.is_none() && !dir.exists() {
+        anyhow::bail!("'{project_path}' does not exist and no --url was given");
+    }
+
+    let mut entry = match url {
+        Some(url) if project_path == alias && tags.is_empty() => Value::String(url),
+        Some(url) if project_path == alias => json!({ "repo": url }),
+        Some(url) => json!({ "repo": url, "path": project_path }),
+        None => json!({ "path": project_path }),
+    };
+    if !tags.is_empty() {
+        entry["tags"] = json!(tags);
+    }
+    doc.set(&["projects", alias], &entry)?;
+    Ok(entry)
+}
+
+/// Remove a project entry. Returns the project's path.
+///
+/// With `delete_dir`, a directory with uncommitted changes is refused up
+/// front; deleting it is left to [`delete_project_dir`] once the config has
+/// been saved.
+pub fn remove_project(
+    doc: &mut ConfigDocument,
+    meta_dir: &Path,
+    alias: &str,
+    delete_dir: bool,
+) -> Result<String> {
+    let entry = find_project(doc, alias)?;
+    let project_path = entry_path(alias, &entry);
+
+    let dir = meta_dir.join(&project_path);
+    if delete_dir && dir.exists() && git_utils::is_dirty(&dir) == Some(true) {
+        anyhow::bail!(
+            "'{project_path}' has uncommitted changes; commit or discard them before deleting"
+        );
+    }
+
+    doc.remove(&["projects", alias]);
+    Ok(project_path)
+}
+
+/// Delete a removed project's directory, if it exists.
+pub fn delete_project_dir(meta_dir: &Path, project_path: &str) -> Result<()> {
+    let dir = meta_dir.join(project_path);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).with_context(|| format!("Failed to delete {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+/// Rename a project's alias.
+///
+/// A project whose path is implied by its alias moves on disk along with the
+/// rename; one with an explicit `path` stays where it is. Returns the
+/// `(from, to)` paths when the directory was moved.
+pub fn rename_project(
+    doc: &mut ConfigDocument,
+    meta_dir: &Path,
+    alias: &str,
+    new_alias: &str,
+) -> Result<Option<(String, String)>> {
+    anyhow::ensure!(
+        !new_alias.trim().is_empty(),
+        "Project alias cannot be empty"
+    );
+    let entry = find_project(doc, alias)?;
+    anyhow::ensure!(
+        doc.get(&["projects", new_alias]).is_none(),
+        "Project '{}' already exists in {}",
+        new_alias,
+        file_name(doc)
+    );
+
+    let implicit_path = entry.get("path").is_none();
+    let from = meta_dir.join(alias);
+    let moved = if implicit_path && from.exists() {
+        let to = meta_dir.join(new_alias);
+        anyhow::ensure!(
+            !to.exists(),
+            "Cannot move {alias}/ to {new_alias}/: destination already exists"
+        );
+        fs::rename(&from, &to)
+            .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+        Some((alias.to_string(), new_alias.to_string()))
+    } else {
+        None
+    };
+
+    doc.rename_key(&["projects"], alias, new_alias)?;
+    Ok(moved)
+}
+
+// ── .gitignore ──────────────────────────────────────────
+
+/// Append any of `entries` not already present to a `.gitignore`, creating it if needed.
+/// Returns the entries that were added.
+pub fn append_gitignore(path: &Path, entries: &[String]) -> Result<Vec<String>> {
+    let existing = read_gitignore(path)?;
+    let added: Vec<String> = entries
+        .iter()
+        .filter(|e| !existing.lines().any(|line| gitignore_matches(line, e)))
+        .cloned()
+        .collect();
+    if added.is_empty() {
+        return Ok(added);
+    }
+
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for entry in &added {
+        content.push_str(entry);
+        content.push('\n');
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(added)
+}
+
+/// Remove lines matching `entry` from a `.gitignore`. Returns true if any were removed.
+pub fn remove_gitignore_entry(path: &Path, entry: &str) -> Result<bool> {
+    let existing = read_gitignore(path)?;
+    let kept: Vec<&str> = existing
+        .lines()
+        .filter(|line| !gitignore_matches(line, entry))
+        .collect();
+    if kept.len() == existing.lines().count() {
+        return Ok(false);
+    }
+
+    let mut content = kept.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+fn read_gitignore(path: &Path) -> Result<String> {
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// True if a `.gitignore` line ignores `entry`, ignoring leading and trailing slashes.
+fn gitignore_matches(line: &str, entry: &str) -> bool {
+    let normalize = |s: &str| s.trim().trim_matches('/').to_string();
+    normalize(line) == normalize(entry)
+}
+
+fn update_gitignore(meta_dir: &Path, remove: Option<&str>, add: Option<&str>) -> Result<()> {
+    let gitignore = meta_dir.join(".gitignore");
+    if let Some(path) = remove {
+        remove_gitignore_entry(&gitignore, &format!("{path}/"))?;
+    }
+    if let Some(path) = add {
+        append_gitignore(&gitignore, &[format!("{path}/")])?;
+    }
+    Ok(())
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn load_workspace(config_name: Option<&PathBuf>) -> Result<(ConfigDocument, PathBuf)> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let (config_path, _format) = config::find_meta_config(&cwd, config_name)
+        .ok_or(crate::error::MetaError::ConfigNotFound)?;
+    let meta_dir = config_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    Ok((ConfigDocument::load(&config_path)?, meta_dir))
+}
+
+fn save(doc: &ConfigDocument) -> Result<()> {
+    if doc.drops_comments() {
+        eprintln!(
+            "{}: comments in {} are not preserved when it is rewritten",
+            "warning".yellow().bold(),
+            file_name(doc)
+        );
+    }
+    doc.save()
+}
+
+fn find_project(doc: &ConfigDocument, alias: &str) -> Result<Value> {
+    doc.get(&["projects", alias]).ok_or_else(|| {
+        crate::error::MetaError::ProjectNotFound {
+            name: alias.to_string(),
+            config: file_name(doc),
+        }
+        .into()
+    })
+}
+
+fn file_name(doc: &ConfigDocument) -> String {
+    doc.path()
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// `(alias, path)` for every project in the document.
+fn project_paths(doc: &ConfigDocument) -> Vec<(String, String)> {
+    match doc.get(&["projects"]) {
+        Some(Value::Object(projects)) => projects
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry_path(name, entry)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The directory of a project entry, relative to the workspace root.
+fn entry_path(alias: &str, entry: &Value) -> String {
+    entry
+        .get("path")
+        .and_then(Value::as_str)
+        .unwrap_or(alias)
+        .replace('\\', "/")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+fn entry_repo(entry: &Value) -> Option<&str> {
+    match entry {
+        Value::String(url) => Some(url),
+        other => other.get("repo").and_then(Value::as_str),
+    }
+}
+
+/// Express `path` relative to the workspace root, rejecting paths outside it.
+fn workspace_relative(meta_dir: &Path, path: &Path) -> Result<String> {
+    let canonical_root = meta_dir
+        .canonicalize()
+        .unwrap_or_else(|_| meta_dir.to_path_buf());
+    let normalized = lexical_normalize(path);
+    let relative = normalized
+        .strip_prefix(&canonical_root)
+        .map_err(|_| anyhow::anyhow!("'{}' is outside the workspace", path.display()))?;
+
+    let relative = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    anyhow::ensure!(
+        !relative.is_empty(),
+        "A project cannot be the workspace root itself"
+    );
+    Ok(relative)
+}
+
+/// Resolve `.` and `..` without requiring the target to exist yet.
+fn lexical_normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+
+    // Canonicalize the longest existing ancestor so symlinked dirs compare equal
+    let mut existing = out.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return out.clone(),
+        }
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .unwrap_or_else(|_| existing.to_path_buf());
+    resolved.extend(missing.iter().rev());
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn workspace(config: &str) -> (tempfile::TempDir, ConfigDocument) {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".meta");
+        fs::write(&path, config).unwrap();
+        let doc = ConfigDocument::load(&path).unwrap();
+        (dir, doc)
+    }
+
+    #[test]
+    fn add_uses_short_form_when_path_matches_alias() {
+        let (dir, mut doc) = workspace(r#"{"projects": {}}"#);
+        let entry = add_project(
+            &mut doc,
+            dir.path(),
+            "api",
+            "api",
+            Some("git@x:api.git"),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(entry, json!("git@x:api.git"));
+
+        let entry = add_project(
+            &mut doc,
+            dir.path(),
+            "web",
+            "apps/web",
+            Some("git@x:web.git"),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(entry, json!({"repo": "git@x:web.git", "path": "apps/web"}));
+    }
+
+    #[test]
+    fn add_records_tags_in_extended_form() {
+        let (dir, mut doc) = workspace(r#"{"projects": {}}"#);
+        let tags = vec!["backend".to_string(), "rust".to_string()];
+        let entry = add_project(&mut doc, dir.path(), "api", "api", Some("u"), &tags).unwrap();
+        assert_eq!(entry, json!({"repo": "u", "tags": ["backend", "rust"]}));
+        assert_eq!(
+            doc.get(&["projects", "api", "tags"]),
+            Some(json!(["backend", "rust"]))
+        );
+    }
+
+    #[test]
+    fn add_rejects_duplicates_and_unknown_dirs() {
+        let (dir, mut doc) = workspace(r#"{"projects": {"api": "git@x:api.git"}}"#);
+        let err = add_project(&mut doc, dir.path(), "api", "other", Some("u"), &[]).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+
+        let err = add_project(&mut doc, dir.path(), "api2", "api", Some("u"), &[]).unwrap_err();
+        assert!(err.to_string().contains("already used by project 'api'"));
+
+        let err = add_project(&mut doc, dir.path(), "local", "local", None, &[]).unwrap_err();
+        assert!(err.to_string().contains("no --url was given"));
+
+        fs::create_dir(dir.path().join("local")).unwrap();
+        let entry = add_project(&mut doc, dir.path(), "local", "local", None, &[]).unwrap();
+        assert_eq!(entry, json!({"path": "local"}));
+    }
+
+    #[test]
+    fn remove_leaves_deleting_the_dir_to_the_caller() {
+        let (dir, mut doc) =
+            workspace(r#"{"projects": {"a": "git@x:a.git", "b": {"path": "libs/b"}}}"#);
+        fs::create_dir_all(dir.path().join("a")).unwrap();
+        fs::create_dir_all(dir.path().join("libs/b")).unwrap();
+
+        assert_eq!(
+            remove_project(&mut doc, dir.path(), "a", false).unwrap(),
+            "a"
+        );
+        assert!(dir.path().join("a").exists());
+
+        assert_eq!(
+            remove_project(&mut doc, dir.path(), "b", true).unwrap(),
+            "libs/b"
+        );
+        assert!(dir.path().join("libs/b").exists());
+        delete_project_dir(dir.path(), "libs/b").unwrap();
+        assert!(!dir.path().join("libs/b").exists());
+        assert_eq!(doc.get(&["projects"]), Some(json!({})));
+
+        let err = remove_project(&mut doc, dir.path(), "a", false).unwrap_err();
+        assert!(err.to_string().contains("Project 'a' not found in .meta"));
+    }
+
+    #[test]
+    fn rename_moves_implicit_paths_only() {
+        let (dir, mut doc) =
+            workspace(r#"{"projects": {"a": "git@x:a.git", "b": {"path": "libs/b"}}}"#);
+        fs::create_dir_all(dir.path().join("a")).unwrap();
+        fs::create_dir_all(dir.path().join("libs/b")).unwrap();
+
+        let moved = rename_project(&mut doc, dir.path(), "a", "alpha").unwrap();
+        assert_eq!(moved, Some(("a".to_string(), "alpha".to_string())));
+        assert!(dir.path().join("alpha").exists());
+
+        let moved = rename_project(&mut doc, dir.path(), "b", "beta").unwrap();
+        assert_eq!(moved, None);
+        assert!(dir.path().join("libs/b").exists());
+        assert_eq!(
+            doc.get(&["projects"]),
+            Some(json!({"alpha": "git@x:a.git", "beta": {"path": "libs/b"}}))
+        );
+    }
+
+    #[test]
+    fn workspace_relative_normalizes_and_bounds_paths() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        assert_eq!(
+            workspace_relative(root, &root.join("./apps/../libs/x")).unwrap(),
+            "libs/x"
+        );
+        assert!(workspace_relative(root, &root.join("../elsewhere")).is_err());
+        assert!(workspace_relative(root, root).is_err());
+    }
+
+    #[test]
+    fn gitignore_entries_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".gitignore");
+        fs::write(&path, "target\n/api\n").unwrap();
+
+        assert!(append_gitignore(&path, &["api/".to_string()])
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            append_gitignore(&path, &["web/".to_string()]).unwrap(),
+            vec!["web/"]
+        );
+        assert!(remove_gitignore_entry(&path, "api/").unwrap());
+        assert!(!remove_gitignore_entry(&path, "api/").unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "target\nweb/\n");
+    }
+}
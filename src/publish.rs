@@ -0,0 +1,446 @@
+//! `meta plugin publish`: package a plugin the way `meta plugin install`
+//! expects to find it.
+//!
+//! For a binary `meta-<name>` and a platform this writes, under the output
+//! directory:
+//!
+//! - `<repo>-<platform>.tar.gz`, the binary at the archive root, to attach
+//!   to the GitHub release `v<version>` (the asset name `meta plugin install
+//!   user/repo` looks for)
+//! - `plugin.json`, registry metadata with the release URL for each
+//!   platform published so far (run once per platform to fill it in)
+//! - `<name>.source`, the simple registry entry (`user/repo@v<version>`)
+//!
+//! With `--pr` the simple entry is proposed to the registry repository as
+//! a pull request through `gh`.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::registry::{
+    self, ArchiveFormat, GitHubShorthand, PlatformReleases, PluginMetadata, PLUGIN_PREFIX,
+};
+
+/// Platforms the registry has release slots for
+pub const PLATFORMS: &[&str] = &[
+    "darwin-arm64",
+    "darwin-x64",
+    "linux-x64",
+    "linux-arm64",
+    "windows-x64",
+];
+
+/// What to publish
+#[derive(Debug, Clone)]
+pub struct PublishOptions {
+    pub binary: PathBuf,
+    /// GitHub repository hosting the releases (`user/repo`)
+    pub repo: String,
+    /// Defaults to the current platform
+    pub platform: Option<String>,
+    /// Defaults to the version the plugin reports
+    pub version: Option<String>,
+    /// Defaults to the repository owner
+    pub author: Option<String>,
+    pub out: PathBuf,
+    pub pr: bool,
+}
+
+/// What was written
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Published {
+    pub name: String,
+    pub version: String,
+    pub platform: String,
+    pub archive: PathBuf,
+    pub checksum: String,
+    pub release_url: String,
+    pub metadata: PathBuf,
+    pub source: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<String>,
+}
+
+/// The registry name for `platform`, accepting the aliases the installer
+/// tries (`macos-arm64`, `linux-amd64`, `linux-aarch64`, …)
+pub fn canonical_platform(platform: &str) -> Option<&'static str> {
+    PLATFORMS.iter().copied().find(|known| {
+        registry::PluginInstaller::platform_aliases(known)
+            .iter()
+            .any(|alias| alias == platform)
+    })
+}
+
+/// `<owner>/<repo>` of a registry served from raw.githubusercontent.com
+pub fn registry_repo(registry_url: &str) -> Option<String> {
+    let path = registry_url.strip_prefix("https://raw.githubusercontent.com/")?;
+    let mut parts = path.split('/');
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    let repo = parts.next().filter(|s| !s.is_empty())?;
+    Some(format!("{owner}/{repo}"))
+}
+
+pub fn publish(options: &PublishOptions) -> Result<Published> {
+    let shorthand = GitHubShorthand::parse(&options.repo)
+        .filter(|s| s.version.is_none())
+        .with_context(|| format!("--repo must be user/repo, got '{}'", options.repo))?;
+
+    let file_name = options
+        .binary
+        .file_name()
+        .and_then(|n| n.to_str())
+        .with_context(|| format!("{} is not a file", options.binary.display()))?;
+    let stem = file_name.strip_suffix(".exe").unwrap_or(file_name);
+    anyhow::ensure!(
+        stem.starts_with(PLUGIN_PREFIX),
+        "Plugin binaries must be named {PLUGIN_PREFIX}<name>, got '{file_name}'"
+    );
+
+    let info = plugin_info(&options.binary)?;
+    let name = info["name"]
+        .as_str()
+        .context("--meta-plugin-info has no \"name\"")?
+        .to_string();
+    anyhow::ensure!(
+        stem == format!("{PLUGIN_PREFIX}{name}"),
+        "The plugin reports its name as '{name}', so the binary must be named {PLUGIN_PREFIX}{name}, not '{file_name}'"
+    );
+    let version = match &options.version {
+        Some(v) => v.trim_start_matches('v').to_string(),
+        None => info["version"]
+            .as_str()
+            .context("--meta-plugin-info has no \"version\"; pass --release-version")?
+            .trim_start_matches('v')
+            .to_string(),
+    };
+
+    let requested = options
+        .platform
+        .clone()
+        .unwrap_or_else(registry::RegistryClient::current_platform);
+    let platform = canonical_platform(&requested).with_context(|| {
+        format!(
+            "Unknown platform '{requested}' (expected one of {})",
+            PLATFORMS.join(", ")
+        )
+    })?;
+
+    std::fs::create_dir_all(&options.out)
+        .with_context(|| format!("Failed to create {}", options.out.display()))?;
+    let archive_name = format!("{}-{platform}.tar.gz", shorthand.repo);
+    let archive = options.out.join(&archive_name);
+    write_archive(&options.binary, file_name, &archive)?;
+
+    // Same checks the installer applies to what it downloads
+    let bytes = std::fs::read(&archive)?;
+    anyhow::ensure!(
+        ArchiveFormat::from_url(&archive_name) == Some(ArchiveFormat::TarGz)
+            && ArchiveFormat::from_bytes(&bytes) == Some(ArchiveFormat::TarGz),
+        "{} is not a valid tar.gz archive",
+        archive.display()
+    );
+    let checksum = registry::file_checksum(&archive)?;
+
+    let release_url = format!(
+        "https://github.com/{}/{}/releases/download/v{version}/{archive_name}",
+        shorthand.user, shorthand.repo
+    );
+    let metadata = options.out.join("plugin.json");
+    let mut entry = load_metadata(&metadata, &name)?.unwrap_or_else(|| PluginMetadata {
+        name: name.clone(),
+        description: String::new(),
+        version: version.clone(),
+        author: String::new(),
+        repository: String::new(),
+        releases: HashMap::new(),
+        checksum: None,
+    });
+    entry.description = info["description"]
+        .as_str()
+        .unwrap_or(&entry.description)
+        .to_string();
+    entry.author = options
+        .author
+        .clone()
+        .unwrap_or_else(|| shorthand.user.clone());
+    entry.repository = format!("https://github.com/{}/{}", shorthand.user, shorthand.repo);
+    entry.version = version.clone();
+    set_platform_url(
+        entry.releases.entry(version.clone()).or_default(),
+        platform,
+        release_url.clone(),
+    );
+    // One checksum slot; it describes the archive published last
+    entry.checksum = Some(checksum.clone());
+    std::fs::write(&metadata, serde_json::to_string_pretty(&entry)? + "\n")
+        .with_context(|| format!("Failed to write {}", metadata.display()))?;
+
+    let source = options.out.join(format!("{name}.source"));
+    let source_line = format!("{}/{}@v{version}\n", shorthand.user, shorthand.repo);
+    std::fs::write(&source, &source_line)
+        .with_context(|| format!("Failed to write {}", source.display()))?;
+
+    let pull_request = if options.pr {
+        Some(open_registry_pr(&name, &version, &source_line)?)
+    } else {
+        None
+    };
+
+    Ok(Published {
+        name,
+        version,
+        platform: platform.to_string(),
+        archive,
+        checksum,
+        release_url,
+        metadata,
+        source,
+        pull_request,
+    })
+}
+
+fn plugin_info(binary: &Path) -> Result<serde_json::Value> {
+    let output = Command::new(binary)
+        .arg("--meta-plugin-info")
+        .output()
+        .with_context(|| format!("Failed to run {}", binary.display()))?;
+    anyhow::ensure!(
+        output.status.success(),
+        "{} --meta-plugin-info failed",
+        binary.display()
+    );
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("{} --meta-plugin-info is not JSON", binary.display()))
+}
+
+fn write_archive(binary: &Path, name: &str, archive: &Path) -> Result<()> {
+    let file = std::fs::File::create(archive)
+        .with_context(|| format!("Failed to create {}", archive.display()))?;
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    let mut source = std::fs::File::open(binary)
+        .with_context(|| format!("Failed to read {}", binary.display()))?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(source.metadata()?.len());
+    header.set_mode(0o755);
+    header.set_cksum();
+    builder.append_data(&mut header, name, &mut source)?;
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// An existing `plugin.json` for the same plugin, to add a platform to
+fn load_metadata(path: &Path, name: &str) -> Result<Option<PluginMetadata>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(path)?;
+    let existing: PluginMetadata = serde_json::from_str(&text)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    anyhow::ensure!(
+        existing.name == name,
+        "{} describes plugin '{}', not '{name}'; use another --out directory",
+        path.display(),
+        existing.name
+    );
+    Ok(Some(existing))
+}
+
+fn set_platform_url(releases: &mut PlatformReleases, platform: &str, url: String) {
+    let slot = match platform {
+        "darwin-arm64" => &mut releases.darwin_arm64,
+        "darwin-x64" => &mut releases.darwin_x64,
+        "linux-x64" => &mut releases.linux_x64,
+        "linux-arm64" => &mut releases.linux_arm64,
+        _ => &mut releases.windows_x64,
+    };
+    *slot = Some(url);
+}
+
+/// Propose `plugins/<name>` to the default registry through `gh`
+fn open_registry_pr(name: &str, version: &str, source_line: &str) -> Result<String> {
+    let upstream = registry_repo(registry::DEFAULT_REGISTRY)
+        .context("The default registry is not a GitHub repository")?;
+    // Private and uniquely named, and removed again when dropped
+    let checkout = tempfile::Builder::new()
+        .prefix("meta-publish-")
+        .tempdir()
+        .context("Failed to create a temporary directory for the registry checkout")?;
+    propose(&upstream, checkout.path(), name, version, source_line)
+}
+
+fn propose(
+    upstream: &str,
+    checkout: &Path,
+    name: &str,
+    version: &str,
+    source_line: &str,
+) -> Result<String> {
+    let dir = checkout.join("registry");
+    let dir_arg = dir.to_string_lossy().to_string();
+    run("gh", &["repo", "clone", upstream, &dir_arg], checkout)?;
+
+    let branch = format!("add-{name}-v{version}");
+    run("git", &["checkout", "-b", &branch], &dir)?;
+    let entry = dir.join("plugins").join(name);
+    std::fs::create_dir_all(entry.parent().unwrap_or(&dir))?;
+    std::fs::write(&entry, source_line)?;
+    let message = format!("Add {name} v{version}");
+    run("git", &["add", "--", &format!("plugins/{name}")], &dir)?;
+    run("git", &["commit", "-m", &message], &dir)?;
+    // Makes `origin` the user's fork, keeping the registry as `upstream`
+    run("gh", &["repo", "fork", "--remote"], &dir)?;
+    run("git", &["push", "-u", "origin", &branch], &dir)?;
+    let output = Command::new("gh")
+        .args(["pr", "create", "--repo", upstream, "--title", &message])
+        .args([
+            "--body",
+            &format!("Registers `{name}` as `{}`.", source_line.trim()),
+        ])
+        .current_dir(&dir)
+        .output()
+        .context("Failed to run gh; install the GitHub CLI to use --pr")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "gh pr create failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn run(program: &str, args: &[&str], dir: &Path) -> Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .with_context(|| format!("Failed to run {program}"))?;
+    anyhow::ensure!(status.success(), "{program} {} failed", args.join(" "));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platforms_are_matched_through_aliases() {
+        assert_eq!(canonical_platform("linux-x64"), Some("linux-x64"));
+        assert_eq!(canonical_platform("macos-arm64"), Some("darwin-arm64"));
+        assert_eq!(canonical_platform("linux-amd64"), Some("linux-x64"));
+        assert_eq!(canonical_platform("linux-aarch64"), Some("linux-arm64"));
+        assert_eq!(canonical_platform("freebsd-x64"), None);
+    }
+
+    #[test]
+    fn registry_repo_comes_from_raw_github_urls() {
+        assert_eq!(
+            registry_repo(registry::DEFAULT_REGISTRY).as_deref(),
+            Some("harmony-labs/meta-plugins")
+        );
+        assert_eq!(registry_repo("https://example.com/registry"), None);
+    }
+
+    #[cfg(unix)]
+    fn fake_plugin(dir: &Path, file: &str, name: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join(file);
+        std::fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\necho '{{\"name\":\"{name}\",\"version\":\"1.2.0\",\"commands\":[],\"description\":\"Migrations\"}}'\n"
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn publish_writes_the_archive_metadata_and_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let out = tmp.path().join("dist");
+        let mut options = PublishOptions {
+            binary: fake_plugin(tmp.path(), "meta-mig", "mig"),
+            repo: "acme/meta-mig".to_string(),
+            platform: Some("linux-amd64".to_string()),
+            version: None,
+            author: None,
+            out: out.clone(),
+            pr: false,
+        };
+        let published = publish(&options).unwrap();
+        assert_eq!(published.platform, "linux-x64");
+        assert_eq!(published.archive, out.join("meta-mig-linux-x64.tar.gz"));
+        assert_eq!(
+            published.release_url,
+            "https://github.com/acme/meta-mig/releases/download/v1.2.0/meta-mig-linux-x64.tar.gz"
+        );
+        assert_eq!(
+            std::fs::read_to_string(out.join("mig.source")).unwrap(),
+            "acme/meta-mig@v1.2.0\n"
+        );
+
+        let bytes = std::fs::read(&published.archive).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&bytes[..]));
+        let names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().display().to_string())
+            .collect();
+        assert_eq!(names, vec!["meta-mig"]);
+
+        // A second platform is added to the same metadata
+        options.platform = Some("macos-arm64".to_string());
+        publish(&options).unwrap();
+        let metadata: PluginMetadata =
+            serde_json::from_str(&std::fs::read_to_string(out.join("plugin.json")).unwrap())
+                .unwrap();
+        assert_eq!(metadata.author, "acme");
+        assert_eq!(metadata.description, "Migrations");
+        let releases = &metadata.releases["1.2.0"];
+        assert!(releases.linux_x64.is_some());
+        assert!(releases
+            .darwin_arm64
+            .as_deref()
+            .unwrap()
+            .ends_with("darwin-arm64.tar.gz"));
+
+        // Assets are named after the repository, as the installer expects
+        options.repo = "acme/mig-tools".to_string();
+        options.out = tmp.path().join("other");
+        let published = publish(&options).unwrap();
+        assert!(published.archive.ends_with("mig-tools-darwin-arm64.tar.gz"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn publish_checks_naming_conventions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let options = |binary: PathBuf| PublishOptions {
+            binary,
+            repo: "acme/meta-mig".to_string(),
+            platform: Some("linux-x64".to_string()),
+            version: None,
+            author: None,
+            out: tmp.path().join("dist"),
+            pr: false,
+        };
+        let err = publish(&options(fake_plugin(tmp.path(), "mig", "mig"))).unwrap_err();
+        assert!(err.to_string().contains("must be named meta-<name>"));
+        let err = publish(&options(fake_plugin(tmp.path(), "meta-migrate", "mig"))).unwrap_err();
+        assert!(err.to_string().contains("must be named meta-mig"));
+
+        let mut bad_platform = options(fake_plugin(tmp.path(), "meta-mig", "mig"));
+        bad_platform.platform = Some("plan9-x64".to_string());
+        assert!(publish(&bad_platform)
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown platform"));
+        let mut bad_repo = options(fake_plugin(tmp.path(), "meta-mig", "mig"));
+        bad_repo.repo = "acme".to_string();
+        assert!(publish(&bad_repo).is_err());
+    }
+}
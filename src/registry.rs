@@ -7,9 +7,13 @@
 use anyhow::{Context, Result};
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use meta_cli::error::MetaError;
 
 /// Default registry URL
 pub const DEFAULT_REGISTRY: &str =
@@ -27,6 +31,9 @@ const LOCAL_PLUGINS_DIR: &str = ".meta/plugins";
 /// Global plugins directory name (under ~/.meta/)
 const GLOBAL_PLUGINS_DIR: &str = "plugins";
 
+/// Lockfile name, next to the plugins directory (.meta/plugins.lock)
+const LOCK_FILE: &str = "plugins.lock";
+
 /// Ensure a plugin name has the required prefix
 pub fn ensure_plugin_prefix(name: &str) -> String {
     if name.starts_with(PLUGIN_PREFIX) {
@@ -43,15 +50,22 @@ fn normalize_version(version: &str) -> &str {
 
 /// Compare two version strings
 ///
-/// Returns true if new_version is newer than current_version.
-/// Simple lexicographic comparison after normalizing (removing 'v' prefix).
+/// Returns true if new_version is newer than current_version. Dot-separated
+/// numeric parts are compared as numbers (so 1.10.0 is newer than 1.9.0);
+/// anything else falls back to string comparison.
 pub fn is_newer_version(current: &str, new: &str) -> bool {
+    let parts = |v: &str| -> Option<Vec<u64>> {
+        // Pre-release and build suffixes are ignored: 1.2.0-rc.1 compares as 1.2.0
+        let core = v.split(['-', '+']).next().unwrap_or(v);
+        core.split('.').map(|p| p.parse().ok()).collect()
+    };
     let current = normalize_version(current);
     let new = normalize_version(new);
 
-    // Simple string comparison works for most semver cases
-    // e.g., "1.0.0" < "1.1.0" < "2.0.0"
-    new > current
+    match (parts(current), parts(new)) {
+        (Some(current), Some(new)) => new > current,
+        _ => new > current,
+    }
 }
 
 /// Check if a filename is a plugin binary (has prefix, no excluded extension)
@@ -59,10 +73,196 @@ fn is_plugin_binary(name: &str) -> bool {
     name.starts_with(PLUGIN_PREFIX) && !EXCLUDED_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
 }
 
+/// Set by `--offline`: serve registry responses and downloads from the cache only
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Make every registry request and download use only the cache
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::SeqCst);
+}
+
+/// Whether network access is disabled (`--offline` or `META_OFFLINE=1`)
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::SeqCst)
+        || std::env::var("META_OFFLINE").is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Cache for registry responses and downloaded archives (~/.meta/cache/)
+fn cache_dir() -> PathBuf {
+    meta_core::meta_dir().join("cache")
+}
+
+/// Cache file for a URL: a hash of the URL, keeping the file name for
+/// readability (`3f2a…-meta-git-linux-x64.tar.gz`)
+fn cache_path(dir: &Path, url: &str) -> PathBuf {
+    let digest = Sha256::digest(url.as_bytes());
+    let hash: String = digest.iter().take(8).map(|b| format!("{b:02x}")).collect();
+    let name = url
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .collect::<String>();
+    if name.is_empty() {
+        dir.join(hash)
+    } else {
+        dir.join(format!("{hash}-{name}"))
+    }
+}
+
+/// GET a URL, caching the response body. Online, the network is always
+/// used and the cache refreshed; offline, only the cache is read.
+fn cached_get(url: &str) -> Result<Vec<u8>> {
+    cached_get_in(&cache_dir(), url, is_offline())
+}
+
+fn cached_get_in(dir: &Path, url: &str, offline: bool) -> Result<Vec<u8>> {
+    let path = cache_path(dir, url);
+    if offline {
+        return std::fs::read(&path)
+            .with_context(|| format!("{url} is not cached; run without --offline to fetch it"));
+    }
+
+    let response = ureq::get(url)
+        .set("User-Agent", "meta-cli")
+        .call()
+        .with_context(|| format!("Failed to fetch {url}"))?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .with_context(|| "Failed to read response body")?;
+
+    // The cache is best-effort; a read-only home must not break installs
+    if std::fs::create_dir_all(dir).is_ok() {
+        if let Err(e) = std::fs::write(&path, &bytes) {
+            debug!("Could not cache {}: {}", url, e);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Registry index cache (~/.meta/cache/registry/), revalidated with ETags
+fn registry_cache_dir() -> PathBuf {
+    cache_dir().join("registry")
+}
+
+/// How long an index served without `Cache-Control: max-age` stays fresh
+const DEFAULT_INDEX_MAX_AGE: u64 = 300;
+
+/// Validators kept next to a cached registry response
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    /// Unix time the response was fetched or last revalidated
+    fetched: u64,
+    max_age: u64,
+}
+
+/// `max-age` from a `Cache-Control` header
+fn parse_max_age(cache_control: &str) -> Option<u64> {
+    cache_control.split(',').find_map(|directive| {
+        let (key, value) = directive.trim().split_once('=')?;
+        key.eq_ignore_ascii_case("max-age")
+            .then(|| value.trim().trim_matches('"').parse().ok())?
+    })
+}
+
+/// GET a registry URL through the conditional cache: a fresh copy is
+/// served as is, a stale one is revalidated with `If-None-Match`, and on
+/// network or server errors the cached copy is used with a warning.
+/// `refresh` revalidates even a fresh copy.
+fn conditional_get(url: &str, refresh: bool) -> Result<Vec<u8>> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    conditional_get_in(&registry_cache_dir(), url, is_offline(), refresh, now)
+}
+
+fn conditional_get_in(
+    dir: &Path,
+    url: &str,
+    offline: bool,
+    refresh: bool,
+    now: u64,
+) -> Result<Vec<u8>> {
+    let path = cache_path(dir, url);
+    let meta_path = PathBuf::from(format!("{}.cache.json", path.display()));
+    let cached = std::fs::read(&path).ok();
+    let meta: CacheMeta = std::fs::read(&meta_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+
+    if offline {
+        return cached
+            .with_context(|| format!("{url} is not cached; run without --offline to fetch it"));
+    }
+    if let Some(body) = &cached {
+        if !refresh && now < meta.fetched.saturating_add(meta.max_age) {
+            debug!("Using fresh cached {}", url);
+            return Ok(body.clone());
+        }
+    }
+
+    let mut request = ureq::get(url).set("User-Agent", "meta-cli");
+    if let (Some(_), Some(etag)) = (&cached, &meta.etag) {
+        request = request.set("If-None-Match", etag);
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(e) => {
+            let server_error = matches!(&e, ureq::Error::Status(code, _) if *code >= 500);
+            match cached {
+                Some(body) if server_error || matches!(e, ureq::Error::Transport(_)) => {
+                    log::warn!("Could not reach {url} ({e}); using the cached copy");
+                    return Ok(body);
+                }
+                _ => return Err(e).with_context(|| format!("Failed to fetch {url}")),
+            }
+        }
+    };
+
+    let max_age = response
+        .header("Cache-Control")
+        .and_then(parse_max_age)
+        .unwrap_or(DEFAULT_INDEX_MAX_AGE);
+    let (body, etag) = match (response.status(), cached) {
+        (304, Some(body)) => (body, meta.etag),
+        _ => {
+            let etag = response.header("ETag").map(str::to_string);
+            let mut bytes = Vec::new();
+            response
+                .into_reader()
+                .read_to_end(&mut bytes)
+                .with_context(|| "Failed to read response body")?;
+            (bytes, etag)
+        }
+    };
+
+    // Best-effort, as for cached_get
+    let meta = CacheMeta {
+        etag,
+        fetched: now,
+        max_age,
+    };
+    if std::fs::create_dir_all(dir).is_ok() {
+        let written = std::fs::write(&path, &body)
+            .and_then(|_| std::fs::write(&meta_path, serde_json::to_vec(&meta)?));
+        if let Err(e) = written {
+            debug!("Could not cache {}: {}", url, e);
+        }
+    }
+    Ok(body)
+}
+
 /// Plugin manifest entry tracking installation metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginManifestEntry {
-    /// Installation source (URL, GitHub shorthand, or registry name)
+    /// Installation source (URL, GitHub shorthand, registry name, absolute
+    /// path, or `git+<url>`)
     pub source: String,
     /// Plugin version (if known)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -244,6 +444,8 @@ pub struct RegistryClient {
     registries: Vec<String>,
     #[allow(dead_code)] // Reserved for future debug output implementation
     verbose: bool,
+    /// Revalidate the cached index even when it is still fresh
+    refresh: bool,
 }
 
 impl RegistryClient {
@@ -253,6 +455,7 @@ impl RegistryClient {
         Ok(Self {
             registries: config.get_registries(),
             verbose,
+            refresh: false,
         })
     }
 
@@ -262,21 +465,33 @@ impl RegistryClient {
         Self {
             registries,
             verbose,
+            refresh: false,
         }
     }
 
+    /// Revalidate the cached index on the next fetch (`--refresh`)
+    pub fn set_refresh(&mut self, refresh: bool) {
+        self.refresh = refresh;
+    }
+
     /// Fetch the registry index
     pub fn fetch_index(&self) -> Result<RegistryIndex> {
         let mut combined_index = RegistryIndex::default();
+        let mut fetched = false;
 
         for registry_url in &self.registries {
             let index_url = format!("{registry_url}/plugins/index.json");
             debug!("Fetching registry index from: {}", index_url);
 
-            match self.fetch_json::<RegistryIndex>(&index_url) {
+            let index = conditional_get(&index_url, self.refresh).and_then(|body| {
+                serde_json::from_slice::<RegistryIndex>(&body)
+                    .with_context(|| "Failed to parse JSON response")
+            });
+            match index {
                 Ok(index) => {
                     // Merge plugins (later registries override earlier ones)
                     combined_index.plugins.extend(index.plugins);
+                    fetched = true;
                 }
                 Err(e) => {
                     log::warn!("Failed to fetch from {}: {}", registry_url, e);
@@ -284,6 +499,10 @@ impl RegistryClient {
             }
         }
 
+        if !fetched && is_offline() {
+            anyhow::bail!("No registry index is cached; run without --offline to fetch it");
+        }
+
         Ok(combined_index)
     }
 
@@ -296,11 +515,10 @@ impl RegistryClient {
             let plugin_url = format!("{registry_url}/plugins/{name}");
             debug!("Resolving plugin source from: {}", plugin_url);
 
-            match ureq::get(&plugin_url).call() {
-                Ok(response) => {
-                    let source = response
-                        .into_string()
-                        .with_context(|| "Failed to read response body")?;
+            match cached_get(&plugin_url) {
+                Ok(bytes) => {
+                    let source =
+                        String::from_utf8(bytes).with_context(|| "Failed to read response body")?;
                     let source = source.trim().to_string();
 
                     if source.is_empty() {
@@ -317,7 +535,12 @@ impl RegistryClient {
             }
         }
 
-        anyhow::bail!("Plugin '{name}' not found in any registry")
+        if is_offline() {
+            anyhow::bail!(
+                "Plugin '{name}' is not in the offline cache; run without --offline to look it up"
+            );
+        }
+        Err(MetaError::PluginNotFound(name.to_string()).into())
     }
 
     /// Fetch plugin metadata (complex registry format)
@@ -337,7 +560,12 @@ impl RegistryClient {
             }
         }
 
-        anyhow::bail!("Plugin '{name}' not found in any registry")
+        if is_offline() {
+            anyhow::bail!(
+                "Plugin '{name}' is not in the offline cache; run without --offline to look it up"
+            );
+        }
+        Err(MetaError::PluginNotFound(name.to_string()).into())
     }
 
     /// Search for plugins matching a query
@@ -359,15 +587,8 @@ impl RegistryClient {
 
     /// Fetch JSON from a URL
     fn fetch_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
-        let response = ureq::get(url)
-            .call()
-            .with_context(|| format!("Failed to fetch {url}"))?;
-
-        let body = response
-            .into_string()
-            .with_context(|| "Failed to read response body")?;
-
-        serde_json::from_str(&body).with_context(|| "Failed to parse JSON response")
+        let body = cached_get(url)?;
+        serde_json::from_slice(&body).with_context(|| "Failed to parse JSON response")
     }
 
     /// Check for the latest version of a plugin from GitHub
@@ -392,18 +613,12 @@ impl RegistryClient {
 
         debug!("Checking latest version: {}", api_url);
 
-        let response = ureq::get(&api_url)
-            .set("User-Agent", "meta-cli")
-            .call()
+        let body = cached_get(&api_url)
             .with_context(|| format!("Failed to fetch latest release for {}/{}", user, repo))?;
 
-        let body = response
-            .into_string()
-            .with_context(|| "Failed to read response body")?;
-
         // Parse JSON to extract tag_name
         let json: serde_json::Value =
-            serde_json::from_str(&body).with_context(|| "Failed to parse GitHub API response")?;
+            serde_json::from_slice(&body).with_context(|| "Failed to parse GitHub API response")?;
 
         let tag_name = json["tag_name"]
             .as_str()
@@ -555,6 +770,80 @@ impl GitHubShorthand {
     }
 }
 
+/// Prefix marking a git repository source in the manifest (`git+https://…`)
+const GIT_SOURCE_PREFIX: &str = "git+";
+
+/// Build a cargo project in release mode and return its `meta-*` executables
+fn cargo_build(dir: &Path) -> Result<Vec<PathBuf>> {
+    use std::process::{Command, Stdio};
+
+    anyhow::ensure!(
+        dir.join("Cargo.toml").is_file(),
+        "{} is neither a plugin executable nor a cargo project",
+        dir.display()
+    );
+    info!("Building {}", dir.display());
+
+    let mut cmd = Command::new("cargo");
+    cmd.args([
+        "build",
+        "--release",
+        "--message-format=json-render-diagnostics",
+    ])
+    .current_dir(dir)
+    .stdin(Stdio::null())
+    .stderr(Stdio::inherit());
+    if is_offline() {
+        cmd.arg("--offline");
+    }
+    let output = cmd.output().context("Failed to run cargo")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "cargo build failed in {}",
+        dir.display()
+    );
+
+    // Artifacts are read from cargo's own messages, so workspaces and
+    // custom target directories work
+    let binaries: Vec<PathBuf> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|msg| msg["reason"] == "compiler-artifact")
+        .filter_map(|msg| msg["executable"].as_str().map(PathBuf::from))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(is_plugin_binary)
+        })
+        .collect();
+    anyhow::ensure!(
+        !binaries.is_empty(),
+        "cargo build in {} produced no {PLUGIN_PREFIX}* executables",
+        dir.display()
+    );
+    Ok(binaries)
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .context("Failed to run git")?;
+    anyhow::ensure!(status.success(), "git {} failed", args.join(" "));
+    Ok(())
+}
+
+/// Version reported by a plugin's `--meta-plugin-info`, if any
+fn plugin_version(path: &Path) -> Option<String> {
+    let output = std::process::Command::new(path)
+        .arg("--meta-plugin-info")
+        .output()
+        .ok()?;
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    info["version"].as_str().map(str::to_string)
+}
+
 /// Make a file executable on Unix systems (chmod 755)
 #[cfg(unix)]
 fn make_executable(path: &Path) -> Result<()> {
@@ -619,7 +908,7 @@ impl PluginInstaller {
     ///
     /// Searches for:
     /// 1. `.meta/` directory (new format, preferred)
-    /// 2. `.meta.yaml`, `.meta.yml`, or `.meta` config file (legacy formats)
+    /// 2. `.meta.yaml`, `.meta.yml`, `.meta.toml`, or `.meta` config file (legacy formats)
     ///
     /// Walks up the directory tree until one is found or filesystem root is reached.
     ///
@@ -711,19 +1000,9 @@ impl PluginInstaller {
         Ok(())
     }
 
-    /// Download bytes from a URL
+    /// Download bytes from a URL (or the cache when offline)
     fn download(&self, url: &str) -> Result<Vec<u8>> {
-        let response = ureq::get(url)
-            .call()
-            .with_context(|| format!("Failed to download {url}"))?;
-
-        let mut bytes = Vec::new();
-        response
-            .into_reader()
-            .read_to_end(&mut bytes)
-            .with_context(|| "Failed to read download")?;
-
-        Ok(bytes)
+        cached_get(url).with_context(|| format!("Failed to download {url}"))
     }
 
     /// Install a plugin from the registry
@@ -863,6 +1142,89 @@ impl PluginInstaller {
         )
     }
 
+    /// Install plugins from the local filesystem
+    ///
+    /// `path` is either a `meta-*` executable, copied as is, or a cargo
+    /// project, built with `cargo build --release` first. The manifest
+    /// records the absolute path, so `meta plugin update` rebuilds from it.
+    pub fn install_from_path(&self, path: &Path) -> Result<Vec<String>> {
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Plugin path {} does not exist", path.display()))?;
+        let binaries = if path.is_dir() {
+            cargo_build(&path)?
+        } else {
+            vec![path.clone()]
+        };
+        let installed = self.install_binaries(&binaries)?;
+        for plugin_name in &installed {
+            let version = plugin_version(&self.plugins_dir.join(plugin_name));
+            self.record_installation(plugin_name, path.display().to_string(), version)?;
+        }
+        Ok(installed)
+    }
+
+    /// Build and install plugins from a git repository
+    ///
+    /// The repository is cloned into `~/.meta/cache/git/` and refreshed on
+    /// later installs; with `--offline` the existing checkout is built
+    /// without fetching.
+    pub fn install_from_git(&self, url: &str) -> Result<Vec<String>> {
+        let checkout = cache_path(&cache_dir().join("git"), url);
+        if checkout.join(".git").exists() {
+            if !is_offline() {
+                info!("Updating {}", url);
+                run_git(&checkout, &["fetch", "--depth", "1", "origin", "HEAD"])?;
+                run_git(&checkout, &["reset", "--hard", "FETCH_HEAD"])?;
+            }
+        } else {
+            anyhow::ensure!(
+                !is_offline(),
+                "{url} is not cached; run without --offline to clone it"
+            );
+            info!("Cloning {}", url);
+            std::fs::create_dir_all(checkout.parent().unwrap_or(&checkout))?;
+            let checkout_arg = checkout.display().to_string();
+            run_git(
+                Path::new("."),
+                &["clone", "--depth", "1", url, checkout_arg.as_str()],
+            )?;
+        }
+
+        let binaries = cargo_build(&checkout)?;
+        let installed = self.install_binaries(&binaries)?;
+        for plugin_name in &installed {
+            let version = plugin_version(&self.plugins_dir.join(plugin_name));
+            self.record_installation(plugin_name, format!("{GIT_SOURCE_PREFIX}{url}"), version)?;
+        }
+        Ok(installed)
+    }
+
+    /// Copy plugin executables into the plugins directory and validate them
+    fn install_binaries(&self, binaries: &[PathBuf]) -> Result<Vec<String>> {
+        self.ensure_plugins_dir()?;
+        let mut installed = Vec::new();
+        for binary in binaries {
+            let name = binary
+                .file_name()
+                .and_then(|n| n.to_str())
+                .filter(|n| is_plugin_binary(n))
+                .with_context(|| {
+                    format!(
+                        "{} is not a plugin: executables must be named {PLUGIN_PREFIX}*",
+                        binary.display()
+                    )
+                })?;
+            let dest = self.plugins_dir.join(name);
+            std::fs::copy(binary, &dest)
+                .with_context(|| format!("Failed to copy {}", binary.display()))?;
+            make_executable(&dest)?;
+            installed.push(name.to_string());
+        }
+        self.validate_installed(&installed)?;
+        Ok(installed)
+    }
+
     /// Construct possible GitHub release URLs for a shorthand
     fn construct_github_urls(&self, shorthand: &GitHubShorthand, platform: &str) -> Vec<String> {
         let mut urls = Vec::new();
@@ -923,7 +1285,7 @@ impl PluginInstaller {
     }
 
     /// Get platform naming aliases (darwin ↔ macos, x64 ↔ amd64, etc.)
-    fn platform_aliases(platform: &str) -> Vec<String> {
+    pub(crate) fn platform_aliases(platform: &str) -> Vec<String> {
         let mut aliases = vec![platform.to_string()];
 
         // Common platform naming variations
@@ -1053,16 +1415,21 @@ impl PluginInstaller {
             .with_context(|| format!("Failed to execute {}", plugin_path.display()))?;
 
         if !output.status.success() {
-            anyhow::bail!(
-                "Plugin did not respond to --meta-plugin-info (exit code: {:?})",
+            return Err(MetaError::PluginValidationFailed(format!(
+                "plugin did not respond to --meta-plugin-info (exit code: {:?})",
                 output.status.code()
-            );
+            ))
+            .into());
         }
 
         // Try to parse the output as JSON to verify it's valid
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let _: serde_json::Value = serde_json::from_str(&stdout)
-            .with_context(|| "Plugin --meta-plugin-info output is not valid JSON")?;
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(&stdout) {
+            return Err(MetaError::PluginValidationFailed(format!(
+                "plugin --meta-plugin-info output is not valid JSON: {e}"
+            ))
+            .into());
+        }
 
         Ok(())
     }
@@ -1135,6 +1502,35 @@ impl PluginInstaller {
     /// Returns Some((current_version, latest_version)) if an update is available,
     /// None if already up to date or if version info is unavailable.
     pub fn check_update(&self, plugin_name: &str) -> Result<Option<(String, String)>> {
+        let status = self.plugin_status(plugin_name)?;
+        Ok(match (status.update_available(), status.latest) {
+            (true, Some(latest)) => Some((
+                status.current.unwrap_or_else(|| "unknown".to_string()),
+                latest,
+            )),
+            _ => None,
+        })
+    }
+
+    /// Installed and latest versions of every plugin in the manifest, sorted
+    /// by name. Plugins whose source cannot be queried are listed without a
+    /// latest version and with the reason in `error`.
+    pub fn outdated(&self) -> Result<Vec<PluginStatus>> {
+        let manifest = self.load_manifest()?;
+        let mut names: Vec<&String> = manifest
+            .plugins
+            .keys()
+            .filter(|name| self.plugins_dir.join(name).exists())
+            .collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| self.plugin_status(name))
+            .collect()
+    }
+
+    /// Installed and latest version of one plugin
+    pub fn plugin_status(&self, plugin_name: &str) -> Result<PluginStatus> {
         let manifest = self.load_manifest()?;
         let plugin_name = ensure_plugin_prefix(plugin_name);
 
@@ -1142,65 +1538,337 @@ impl PluginInstaller {
             .get_plugin(&plugin_name)
             .ok_or_else(|| anyhow::anyhow!("Plugin {} not found in manifest", plugin_name))?;
 
-        // Check if source is a GitHub shorthand
-        if !entry.source.contains('/') {
-            debug!(
-                "Plugin {} source is not a GitHub shorthand, skipping update check",
-                plugin_name
-            );
-            return Ok(None);
-        }
-
-        // Get current version
-        let current_version = match &entry.version {
-            Some(v) => v.clone(),
-            None => {
-                debug!(
-                    "Plugin {} has no version in manifest, cannot check for updates",
-                    plugin_name
-                );
-                return Ok(None);
+        let source = UpdateSource::parse(&entry.source);
+        let current = entry.version.clone().or_else(|| match &source {
+            UpdateSource::GitHub(shorthand) => shorthand.version.clone(),
+            _ => None,
+        });
+        let (latest, error) = match self.latest_version(&source) {
+            Ok(latest) => (latest, None),
+            Err(e) => {
+                debug!("Could not check {} for updates: {:#}", plugin_name, e);
+                (None, Some(format!("{e:#}")))
             }
         };
 
-        // Get latest version from GitHub
-        let latest_version = RegistryClient::get_latest_version(&entry.source)?;
+        Ok(PluginStatus {
+            name: plugin_name,
+            source: entry.source.clone(),
+            current,
+            latest,
+            error,
+        })
+    }
 
-        // Compare versions
-        if is_newer_version(&current_version, &latest_version) {
-            Ok(Some((current_version, latest_version)))
-        } else {
-            Ok(None)
+    /// Latest version offered by a source, or None if it has no versions
+    fn latest_version(&self, source: &UpdateSource) -> Result<Option<String>> {
+        match source {
+            UpdateSource::GitHub(shorthand) => {
+                let repo = format!("{}/{}", shorthand.user, shorthand.repo);
+                RegistryClient::get_latest_version(&repo).map(Some)
+            }
+            UpdateSource::Registry(name) => {
+                let metadata = RegistryClient::new(self.verbose)?.fetch_plugin_metadata(name)?;
+                Ok(Some(metadata.version))
+            }
+            UpdateSource::Url(_) | UpdateSource::Path(_) | UpdateSource::Git(_) => Ok(None),
         }
     }
 
     /// Update a plugin to the latest version
+    ///
+    /// The new version is installed over the old one, so a failed download
+    /// leaves the current version in place. Plugins installed from a pinned
+    /// GitHub shorthand (`user/repo@v1.0.0`) move to the latest release and
+    /// are recorded unpinned.
     pub fn update_plugin(&self, plugin_name: &str) -> Result<String> {
+        let status = self.plugin_status(plugin_name)?;
+
+        info!(
+            "Updating {} from {} to latest",
+            status.name,
+            status.current.as_deref().unwrap_or("unknown")
+        );
+
+        match UpdateSource::parse(&status.source) {
+            UpdateSource::GitHub(shorthand) => {
+                let latest = match status.latest {
+                    Some(latest) => latest,
+                    None => anyhow::bail!(
+                        "Cannot update {}: {}",
+                        status.name,
+                        status.error.as_deref().unwrap_or("no release found")
+                    ),
+                };
+                let shorthand = GitHubShorthand {
+                    version: Some(latest.clone()),
+                    ..shorthand
+                };
+                let installed_name = self.install_from_github(&shorthand)?;
+                let source = format!("{}/{}", shorthand.user, shorthand.repo);
+                self.record_installation(&installed_name, source, Some(latest))?;
+                Ok(installed_name)
+            }
+            UpdateSource::Registry(name) => {
+                let metadata = RegistryClient::new(self.verbose)?.fetch_plugin_metadata(&name)?;
+                let installed = self.install(&metadata)?;
+                self.first_installed(installed, &status)
+            }
+            UpdateSource::Url(url) => anyhow::bail!(
+                "Cannot update {}: it was installed from a URL; reinstall it with `meta plugin install <url>` (was {url})",
+                status.name
+            ),
+            UpdateSource::Path(path) => self.first_installed(self.install_from_path(&path)?, &status),
+            UpdateSource::Git(url) => self.first_installed(self.install_from_git(&url)?, &status),
+        }
+    }
+
+    fn first_installed(&self, installed: Vec<String>, status: &PluginStatus) -> Result<String> {
+        Ok(installed
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| status.name.clone()))
+    }
+}
+
+impl PluginInstaller {
+    /// Path of the lockfile for this installer's plugins directory
+    pub fn lock_path(&self) -> PathBuf {
+        self.plugins_dir.with_file_name(LOCK_FILE)
+    }
+
+    /// Record every installed plugin's source, version and checksum in the
+    /// lockfile, replacing its previous contents
+    pub fn lock(&self) -> Result<PluginLock> {
         let manifest = self.load_manifest()?;
-        let plugin_name = ensure_plugin_prefix(plugin_name);
+        let mut lock = PluginLock::default();
+        for (name, entry) in &manifest.plugins {
+            let path = self.plugins_dir.join(name);
+            if !path.exists() {
+                continue;
+            }
+            lock.plugins.insert(
+                name.clone(),
+                LockedPlugin {
+                    source: entry.source.clone(),
+                    version: entry.version.clone(),
+                    checksum: file_checksum(&path)?,
+                },
+            );
+        }
+        lock.save(&self.lock_path())?;
+        Ok(lock)
+    }
 
-        let entry = manifest
-            .get_plugin(&plugin_name)
-            .ok_or_else(|| anyhow::anyhow!("Plugin {} not installed", plugin_name))?;
+    /// Install every plugin in the lockfile at its pinned version
+    ///
+    /// Plugins whose binary already matches the locked checksum are left
+    /// alone. A freshly installed binary that does not match is removed and
+    /// the sync fails, since the source no longer serves the locked build.
+    pub fn sync(&self) -> Result<Vec<SyncedPlugin>> {
+        let lock_path = self.lock_path();
+        anyhow::ensure!(
+            lock_path.exists(),
+            "No {} found; run `meta plugin lock` to create one",
+            lock_path.display()
+        );
+        let lock = PluginLock::load(&lock_path)?;
+        let mut synced = Vec::new();
+
+        for (name, locked) in &lock.plugins {
+            let path = self.plugins_dir.join(name);
+            // An archive can hold several plugins; earlier installs may
+            // already have brought this one in
+            if path.exists() && file_checksum(&path)? == locked.checksum {
+                synced.push(SyncedPlugin {
+                    name: name.clone(),
+                    version: locked.version.clone(),
+                    installed: false,
+                });
+                continue;
+            }
 
-        // Parse source as GitHub shorthand
-        let shorthand = GitHubShorthand::parse(&entry.source).ok_or_else(|| {
-            anyhow::anyhow!("Cannot update plugin: source is not a GitHub shorthand")
-        })?;
+            self.install_locked(name, locked)?;
+            let checksum = file_checksum(&path)
+                .with_context(|| format!("{} did not provide {name}", locked.source))?;
+            if checksum != locked.checksum {
+                let _ = std::fs::remove_file(&path);
+                anyhow::bail!(
+                    "Checksum mismatch for {name} from {}: expected {}, got {checksum}",
+                    locked.source,
+                    locked.checksum
+                );
+            }
+            synced.push(SyncedPlugin {
+                name: name.clone(),
+                version: locked.version.clone(),
+                installed: true,
+            });
+        }
+        Ok(synced)
+    }
 
+    /// Installed plugins that are not in the lockfile
+    pub fn unlocked_plugins(&self) -> Result<Vec<String>> {
+        let lock = PluginLock::load(&self.lock_path())?;
+        Ok(self
+            .list_plugins_detailed()?
+            .into_iter()
+            .map(|p| p.name)
+            .filter(|name| !lock.plugins.contains_key(name))
+            .collect())
+    }
+
+    fn install_locked(&self, name: &str, locked: &LockedPlugin) -> Result<()> {
         info!(
-            "Updating {} from {} to latest",
-            plugin_name,
-            entry.version.as_deref().unwrap_or("unknown")
+            "Installing {} {} from {}",
+            name,
+            locked.version.as_deref().unwrap_or(""),
+            locked.source
         );
+        match UpdateSource::parse(&locked.source) {
+            UpdateSource::GitHub(shorthand) => {
+                let shorthand = GitHubShorthand {
+                    version: locked.version.clone().or(shorthand.version),
+                    ..shorthand
+                };
+                self.install_from_github(&shorthand)?;
+            }
+            UpdateSource::Registry(registry_name) => {
+                let mut metadata =
+                    RegistryClient::new(self.verbose)?.fetch_plugin_metadata(&registry_name)?;
+                if let Some(version) = &locked.version {
+                    metadata.version = version.clone();
+                }
+                self.install(&metadata)?;
+            }
+            UpdateSource::Url(url) => {
+                self.install_from_url(&url)?;
+            }
+            UpdateSource::Path(path) => {
+                self.install_from_path(&path)?;
+            }
+            UpdateSource::Git(url) => {
+                self.install_from_git(&url)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `.meta/plugins.lock`: the exact plugin builds a workspace expects
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PluginLock {
+    pub plugins: BTreeMap<String, LockedPlugin>,
+}
 
-        // Uninstall current version
-        self.uninstall(&plugin_name)?;
+/// One pinned plugin in the lockfile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedPlugin {
+    /// Installation source, as in the manifest
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// `sha256:<hex>` of the plugin binary
+    pub checksum: String,
+}
 
-        // Install latest version
-        let installed_name = self.install_from_github(&shorthand)?;
+/// Result of syncing one locked plugin
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncedPlugin {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// False when the installed binary already matched the lockfile
+    pub installed: bool,
+}
 
-        Ok(installed_name)
+impl PluginLock {
+    /// Load a lockfile, or return an empty lock if not found
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Save the lockfile
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self)? + "\n";
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// SHA-256 of a file, as `sha256:<hex>`
+pub fn file_checksum(path: &Path) -> Result<String> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let digest = Sha256::digest(&bytes);
+    let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    Ok(format!("sha256:{hex}"))
+}
+
+/// Where an installed plugin came from, as recorded in the manifest
+#[derive(Debug, Clone, PartialEq)]
+enum UpdateSource {
+    /// `user/repo` or `user/repo@version`
+    GitHub(GitHubShorthand),
+    /// A plugin name resolved through the registry's `plugin.json`
+    Registry(String),
+    /// A direct download URL; carries no version information
+    Url(String),
+    /// A local executable or cargo project, rebuilt on update
+    Path(PathBuf),
+    /// A git repository built with cargo, rebuilt on update
+    Git(String),
+}
+
+impl UpdateSource {
+    fn parse(source: &str) -> Self {
+        if let Some(url) = source.strip_prefix(GIT_SOURCE_PREFIX) {
+            UpdateSource::Git(url.to_string())
+        } else if Path::new(source).is_absolute() {
+            UpdateSource::Path(PathBuf::from(source))
+        } else if source.starts_with("http://") || source.starts_with("https://") {
+            UpdateSource::Url(source.to_string())
+        } else if let Some(shorthand) = GitHubShorthand::parse(source) {
+            UpdateSource::GitHub(shorthand)
+        } else {
+            UpdateSource::Registry(source.to_string())
+        }
+    }
+}
+
+/// Installed and latest version of a plugin, for `meta plugin outdated`
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginStatus {
+    pub name: String,
+    pub source: String,
+    /// Installed version, if it was recorded
+    pub current: Option<String>,
+    /// Latest version offered by the source
+    pub latest: Option<String>,
+    /// Why the source could not be queried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PluginStatus {
+    /// Whether updating would install a different version. A plugin with no
+    /// recorded version counts as outdated when the source has a release.
+    pub fn update_available(&self) -> bool {
+        match (&self.current, &self.latest) {
+            (Some(current), Some(latest)) => is_newer_version(current, latest),
+            (None, Some(_)) => true,
+            _ => false,
+        }
     }
 }
 
@@ -1315,6 +1983,184 @@ mod tests {
         assert!(!dir.path().join("meta-test").exists());
     }
 
+    #[test]
+    fn test_parse_max_age() {
+        assert_eq!(parse_max_age("public, max-age=300"), Some(300));
+        assert_eq!(parse_max_age("Max-Age=\"60\""), Some(60));
+        assert_eq!(parse_max_age("no-cache"), None);
+        assert_eq!(parse_max_age("max-age=soon"), None);
+    }
+
+    /// Serve `responses` (raw HTTP) to successive connections, returning
+    /// the URL and the requests received
+    fn serve(responses: Vec<&'static str>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::{BufRead, BufReader, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/plugins/index.json",
+            listener.local_addr().unwrap()
+        );
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    request.push_str(&line);
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_conditional_get_revalidates_with_etags() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nCache-Control: max-age=60\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}",
+            "HTTP/1.1 304 Not Modified\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+
+        assert_eq!(
+            conditional_get_in(dir.path(), &url, false, false, 1000).unwrap(),
+            b"{}"
+        );
+        // Fresh: served from the cache without a request
+        assert_eq!(
+            conditional_get_in(dir.path(), &url, false, false, 1030).unwrap(),
+            b"{}"
+        );
+        // Refresh revalidates; the 304 keeps the cached body
+        assert_eq!(
+            conditional_get_in(dir.path(), &url, false, true, 1030).unwrap(),
+            b"{}"
+        );
+        // Stale, and the registry is down: the cache is used
+        assert_eq!(
+            conditional_get_in(dir.path(), &url, false, false, 2000).unwrap(),
+            b"{}"
+        );
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(!requests[0].to_lowercase().contains("if-none-match"));
+        assert!(requests[1].contains("\"v1\""));
+        assert!(requests[2].contains("\"v1\""));
+    }
+
+    #[test]
+    fn test_conditional_get_fails_without_a_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        assert!(conditional_get_in(dir.path(), &url, false, false, 0).is_err());
+        let err = conditional_get_in(dir.path(), &url, true, false, 0).unwrap_err();
+        assert!(err.to_string().contains("not cached"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_cached_get_offline_reads_only_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = "https://example.invalid/plugins/index.json";
+
+        let err = cached_get_in(dir.path(), url, true).unwrap_err();
+        assert!(err.to_string().contains("not cached"));
+
+        let path = cache_path(dir.path(), url);
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .ends_with("-index.json"));
+        std::fs::write(&path, "{}").unwrap();
+        assert_eq!(cached_get_in(dir.path(), url, true).unwrap(), b"{}");
+    }
+
+    #[test]
+    fn test_cache_path_is_per_url() {
+        let dir = Path::new("/cache");
+        let a = cache_path(dir, "https://a.example/meta-git.tar.gz");
+        let b = cache_path(dir, "https://b.example/meta-git.tar.gz");
+        assert_ne!(a, b);
+        assert_eq!(a, cache_path(dir, "https://a.example/meta-git.tar.gz"));
+    }
+
+    #[test]
+    fn test_file_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("meta-abc");
+        std::fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            file_checksum(&path).unwrap(),
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_lock_records_installed_plugins_and_sync_keeps_matches() {
+        let root = tempfile::tempdir().unwrap();
+        let plugins_dir = root.path().join(".meta/plugins");
+        std::fs::create_dir_all(&plugins_dir).unwrap();
+        std::fs::write(plugins_dir.join("meta-git"), "git build").unwrap();
+        let installer = PluginInstaller {
+            plugins_dir: plugins_dir.clone(),
+            verbose: false,
+            scope: InstallScope::Local,
+        };
+        installer
+            .record_installation(
+                "meta-git",
+                "harmony-labs/meta-git@v1.2.0".to_string(),
+                Some("v1.2.0".to_string()),
+            )
+            .unwrap();
+
+        let lock = installer.lock().unwrap();
+        assert_eq!(
+            installer.lock_path(),
+            root.path().join(".meta/plugins.lock")
+        );
+        assert_eq!(PluginLock::load(&installer.lock_path()).unwrap(), lock);
+        let git = &lock.plugins["meta-git"];
+        assert_eq!(git.version.as_deref(), Some("v1.2.0"));
+        assert_eq!(
+            git.checksum,
+            file_checksum(&plugins_dir.join("meta-git")).unwrap()
+        );
+
+        // Everything already matches, so nothing is downloaded
+        let synced = installer.sync().unwrap();
+        assert_eq!(synced.len(), 1);
+        assert!(!synced[0].installed);
+
+        std::fs::write(plugins_dir.join("meta-extra"), "x").unwrap();
+        assert_eq!(installer.unlocked_plugins().unwrap(), vec!["meta-extra"]);
+    }
+
+    #[test]
+    fn test_sync_without_lockfile_fails() {
+        let root = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller {
+            plugins_dir: root.path().join(".meta/plugins"),
+            verbose: false,
+            scope: InstallScope::Local,
+        };
+        let err = installer.sync().unwrap_err();
+        assert!(err.to_string().contains("meta plugin lock"));
+    }
+
     #[test]
     fn test_plugin_installer_uninstall_not_installed() {
         let dir = tempfile::tempdir().unwrap();
@@ -2088,4 +2934,124 @@ mod tests {
         assert!(!is_newer_version("2.0.0", "1.0.0"));
         assert!(!is_newer_version("1.1.0", "1.0.0"));
     }
+
+    #[test]
+    fn test_is_newer_version_compares_numerically() {
+        assert!(is_newer_version("1.9.0", "1.10.0"));
+        assert!(!is_newer_version("v0.10.2", "v0.9.9"));
+    }
+
+    #[test]
+    fn test_update_source_parse() {
+        assert_eq!(
+            UpdateSource::parse("https://example.com/meta-foo.tar.gz"),
+            UpdateSource::Url("https://example.com/meta-foo.tar.gz".to_string())
+        );
+        assert_eq!(
+            UpdateSource::parse("harmony-labs/meta-git@v1.0.0"),
+            UpdateSource::GitHub(GitHubShorthand::parse("harmony-labs/meta-git@v1.0.0").unwrap())
+        );
+        assert_eq!(
+            UpdateSource::parse("docker"),
+            UpdateSource::Registry("docker".to_string())
+        );
+        assert_eq!(
+            UpdateSource::parse("git+https://github.com/me/meta-foo"),
+            UpdateSource::Git("https://github.com/me/meta-foo".to_string())
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            UpdateSource::parse("/home/me/meta-foo"),
+            UpdateSource::Path(PathBuf::from("/home/me/meta-foo"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_from_path_copies_and_records_the_binary() {
+        let src = tempfile::tempdir().unwrap();
+        let plugin = src.path().join("meta-foo");
+        std::fs::write(
+            &plugin,
+            "#!/bin/sh\necho '{\"name\":\"foo\",\"version\":\"0.3.0\",\"commands\":[\"foo\"]}'\n",
+        )
+        .unwrap();
+        make_executable(&plugin).unwrap();
+        let plugins = tempfile::tempdir().unwrap();
+        let installer = PluginInstaller {
+            plugins_dir: plugins.path().to_path_buf(),
+            verbose: false,
+            scope: InstallScope::Global,
+        };
+
+        assert_eq!(
+            installer.install_from_path(&plugin).unwrap(),
+            vec!["meta-foo"]
+        );
+        assert!(plugins.path().join("meta-foo").exists());
+        let manifest = installer.load_manifest().unwrap();
+        let entry = manifest.get_plugin("meta-foo").unwrap();
+        assert_eq!(entry.version.as_deref(), Some("0.3.0"));
+        assert_eq!(
+            UpdateSource::parse(&entry.source),
+            UpdateSource::Path(plugin.canonicalize().unwrap())
+        );
+
+        let not_a_plugin = src.path().join("foo");
+        std::fs::write(&not_a_plugin, "").unwrap();
+        let err = installer.install_from_path(&not_a_plugin).unwrap_err();
+        assert!(err.to_string().contains("must be named meta-*"));
+        let err = installer.install_from_path(src.path()).unwrap_err();
+        assert!(err.to_string().contains("nor a cargo project"));
+    }
+
+    #[test]
+    fn test_outdated_lists_url_installs_without_a_latest_version() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("meta-foo"), "fake binary").unwrap();
+        let installer = PluginInstaller {
+            plugins_dir: dir.path().to_path_buf(),
+            verbose: false,
+            scope: InstallScope::Global,
+        };
+        installer
+            .record_installation(
+                "meta-foo",
+                "https://example.com/meta-foo.tar.gz".to_string(),
+                Some("1.0.0".to_string()),
+            )
+            .unwrap();
+        // Recorded but no longer on disk: not listed
+        installer
+            .record_installation("meta-gone", "https://example.com/x".to_string(), None)
+            .unwrap();
+
+        let statuses = installer.outdated().unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].name, "meta-foo");
+        assert_eq!(statuses[0].current.as_deref(), Some("1.0.0"));
+        assert_eq!(statuses[0].latest, None);
+        assert!(!statuses[0].update_available());
+        assert_eq!(installer.check_update("foo").unwrap(), None);
+        assert!(installer
+            .update_plugin("foo")
+            .unwrap_err()
+            .to_string()
+            .contains("installed from a URL"));
+    }
+
+    #[test]
+    fn test_plugin_status_update_available() {
+        let status = |current: Option<&str>, latest: Option<&str>| PluginStatus {
+            name: "meta-x".to_string(),
+            source: "user/meta-x".to_string(),
+            current: current.map(str::to_string),
+            latest: latest.map(str::to_string),
+            error: None,
+        };
+        assert!(status(Some("v1.0.0"), Some("v1.1.0")).update_available());
+        assert!(!status(Some("v1.1.0"), Some("v1.1.0")).update_available());
+        assert!(status(None, Some("v1.1.0")).update_available());
+        assert!(!status(Some("v1.0.0"), None).update_available());
+    }
 }
@@ -0,0 +1,485 @@
+//! Coordinated releases for `meta release`.
+//!
+//! `meta release tag <version>` creates the same annotated tag in every
+//! selected repo and records which commit each tag points at in
+//! `releases.json` at the meta root, so a release of the whole workspace can
+//! be inspected (and reproduced) later with `meta release list/show`.
+//!
+//! Tagging is all or nothing: every repo is checked first, and if creating a
+//! tag still fails somewhere, the tags already created are deleted again.
+//! Pushing happens afterwards and is reported per repo. The tags and the
+//! `releases.json` entry are recorded in the operation journal, so
+//! `meta undo` can remove a release again (pushed tags stay on the remote).
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::git::{self, RepoResult, Target};
+use crate::git_utils;
+use crate::journal::{Journal, OpStatus, UndoAction};
+
+/// File at the meta root recording every release.
+pub const RELEASES_FILE: &str = "releases.json";
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Releases {
+    #[serde(default)]
+    pub releases: Vec<Release>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Release {
+    /// Also the tag name
+    pub version: String,
+    pub created: DateTime<Utc>,
+    pub message: String,
+    #[serde(default)]
+    pub signed: bool,
+    /// Project name → tagged commit
+    pub repos: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TagOptions {
+    pub message: Option<String>,
+    pub sign: bool,
+    pub push: bool,
+    pub dry_run: bool,
+    /// `--notify` target, see [`crate::notify`]
+    pub notify: Option<String>,
+}
+
+impl Releases {
+    pub fn load(meta_dir: &Path) -> Result<Self> {
+        meta_core::store::read(&releases_path(meta_dir))
+    }
+
+    pub fn find(&self, version: &str) -> Option<&Release> {
+        self.releases.iter().find(|r| r.version == version)
+    }
+}
+
+pub fn releases_path(meta_dir: &Path) -> PathBuf {
+    meta_dir.join(RELEASES_FILE)
+}
+
+/// Remove `version` from `releases.json`, if it is recorded.
+pub fn forget(meta_dir: &Path, version: &str) -> Result<()> {
+    let mut releases = Releases::load(meta_dir)?;
+    let before = releases.releases.len();
+    releases.releases.retain(|r| r.version != version);
+    if releases.releases.len() != before {
+        meta_core::store::write_atomic(&releases_path(meta_dir), &releases)?;
+    }
+    Ok(())
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta release tag`.
+pub fn handle_tag(
+    meta_dir: &Path,
+    version: &str,
+    targets: &[Target],
+    options: &TagOptions,
+    json: bool,
+) -> Result<()> {
+    let started = std::time::Instant::now();
+    let mut journal = (!options.dry_run)
+        .then(|| Journal::begin("release-tag", &format!("meta release tag {version}")));
+    let (release, results) = match journal.as_mut() {
+        Some(journal) => tag_with_journal(meta_dir, version, targets, options, journal)?,
+        None => tag(meta_dir, version, targets, options)?,
+    };
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "release": release,
+                "repos": results,
+            }))?
+        );
+    } else {
+        print!("{}", git::format_table(&results));
+        if !options.dry_run {
+            println!(
+                "\nTagged {} in {} repos (recorded in {RELEASES_FILE})",
+                release.version,
+                release.repos.len()
+            );
+        }
+        if let Some(hint) = journal.as_ref().and_then(Journal::hint) {
+            println!("{}", hint.dimmed());
+        }
+    }
+    if !options.dry_run {
+        let title = format!("meta release tag {version}");
+        let summary = crate::notify::Summary::from_results(&title, &results, started.elapsed());
+        crate::notify::after_run(meta_dir, options.notify.as_deref(), &summary);
+    }
+    let failed = results.iter().filter(|r| !r.success).count();
+    anyhow::ensure!(
+        failed == 0,
+        "push failed in {failed} of {} repos",
+        results.len()
+    );
+    Ok(())
+}
+
+/// Entry point for `meta release list`.
+pub fn handle_list(meta_dir: &Path, json: bool) -> Result<()> {
+    let releases = Releases::load(meta_dir)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&releases.releases)?);
+    } else if releases.releases.is_empty() {
+        println!("No releases recorded. Create one with 'meta release tag <version>'.");
+    } else {
+        print!("{}", format_list(&releases.releases));
+    }
+    Ok(())
+}
+
+/// Entry point for `meta release show <version>`.
+pub fn handle_show(meta_dir: &Path, version: &str, json: bool) -> Result<()> {
+    let releases = Releases::load(meta_dir)?;
+    let release = releases
+        .find(version)
+        .with_context(|| format!("No release '{version}' in {RELEASES_FILE}"))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(release)?);
+        return Ok(());
+    }
+    let created = release.created.with_timezone(&chrono::Local);
+    println!("{} {}", "Release".bold(), release.version.bold());
+    println!("Created: {}", created.format("%Y-%m-%d %H:%M"));
+    println!("Message: {}", release.message);
+    if release.signed {
+        println!("Signed:  yes");
+    }
+    println!();
+    let width = release.repos.keys().map(|n| n.len()).max().unwrap_or(0);
+    for (repo, commit) in &release.repos {
+        println!("  {repo:<width$}  {}", short(commit));
+    }
+    Ok(())
+}
+
+/// Create tag `version` in every target and record the release.
+pub fn tag(
+    meta_dir: &Path,
+    version: &str,
+    targets: &[Target],
+    options: &TagOptions,
+) -> Result<(Release, Vec<RepoResult>)> {
+    let mut journal = Journal::disabled("release-tag", version);
+    tag_with_journal(meta_dir, version, targets, options, &mut journal)
+}
+
+/// [`tag`], recording each tag and the release entry in `journal`.
+pub fn tag_with_journal(
+    meta_dir: &Path,
+    version: &str,
+    targets: &[Target],
+    options: &TagOptions,
+    journal: &mut Journal,
+) -> Result<(Release, Vec<RepoResult>)> {
+    let result = tag_inner(meta_dir, version, targets, options, journal);
+    journal.finish(match &result {
+        Ok(_) => OpStatus::Completed,
+        Err(_) if journal.op.steps.iter().all(|s| s.undone) => OpStatus::RolledBack,
+        Err(_) => OpStatus::Failed,
+    });
+    result
+}
+
+fn tag_inner(
+    meta_dir: &Path,
+    version: &str,
+    targets: &[Target],
+    options: &TagOptions,
+    journal: &mut Journal,
+) -> Result<(Release, Vec<RepoResult>)> {
+    validate_version(version)?;
+    let mut releases = Releases::load(meta_dir)?;
+    anyhow::ensure!(
+        releases.find(version).is_none(),
+        "Release '{version}' is already recorded in {RELEASES_FILE}"
+    );
+    anyhow::ensure!(!targets.is_empty(), "No repos selected");
+
+    // Check everything before creating any tag
+    let mut commits = Vec::new();
+    let mut problems = Vec::new();
+    for (name, path) in targets {
+        match check(path, version) {
+            Ok(commit) => commits.push(commit),
+            Err(e) => problems.push(format!("{name} ({e:#})")),
+        }
+    }
+    anyhow::ensure!(
+        problems.is_empty(),
+        "Cannot tag {version}: {}",
+        problems.join(", ")
+    );
+
+    let message = options
+        .message
+        .clone()
+        .unwrap_or_else(|| format!("Release {version}"));
+    let release = Release {
+        version: version.to_string(),
+        created: Utc::now(),
+        message: message.clone(),
+        signed: options.sign,
+        repos: targets
+            .iter()
+            .zip(&commits)
+            .map(|((name, _), commit)| (name.clone(), commit.clone()))
+            .collect(),
+    };
+
+    let result = |name: &str, path: &Path, success: bool, summary: String| RepoResult {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        success,
+        branch: crate::git_utils::current_branch(path),
+        summary,
+        output: String::new(),
+    };
+
+    if options.dry_run {
+        let results = targets
+            .iter()
+            .zip(&commits)
+            .map(|((name, path), commit)| {
+                result(
+                    name,
+                    path,
+                    true,
+                    format!("would tag {} as {version}", short(commit)),
+                )
+            })
+            .collect();
+        return Ok((release, results));
+    }
+
+    let mut created: Vec<(&String, &PathBuf)> = Vec::new();
+    for ((name, path), commit) in targets.iter().zip(&commits) {
+        let flag = if options.sign { "-s" } else { "-a" };
+        if let Err(e) = git_utils::git(path, &["tag", flag, version, "-m", &message, commit]) {
+            for (repo, repo_path) in &created {
+                if git_utils::git(repo_path, &["tag", "-d", version]).is_ok() {
+                    journal.mark_undone(repo);
+                }
+            }
+            anyhow::bail!(
+                "Tagging {name} failed, removed {version} from the {} repos already tagged: {e:#}",
+                created.len()
+            );
+        }
+        journal.step(
+            name,
+            path,
+            &format!("tagged {} as {version}", short(commit)),
+            vec![UndoAction::DeleteTag {
+                name: version.to_string(),
+                at: commit.clone(),
+            }],
+        );
+        created.push((name, path));
+    }
+
+    releases.releases.push(release.clone());
+    meta_core::store::write_atomic(&releases_path(meta_dir), &releases)?;
+    journal.step(
+        ".",
+        meta_dir,
+        &format!("recorded {version} in {RELEASES_FILE}"),
+        vec![UndoAction::ForgetRelease {
+            version: version.to_string(),
+        }],
+    );
+
+    let results = targets
+        .iter()
+        .zip(&commits)
+        .map(|((name, path), commit)| {
+            let tagged = format!("tagged {}", short(commit));
+            if !options.push {
+                return result(name, path, true, tagged);
+            }
+            match git_utils::git(path, &["push", "origin", &format!("refs/tags/{version}")]) {
+                Ok(_) => result(name, path, true, format!("{tagged}, pushed")),
+                Err(e) => result(name, path, false, format!("{tagged}, push failed: {e:#}")),
+            }
+        })
+        .collect();
+    Ok((release, results))
+}
+
+/// Releases as an aligned table, oldest first.
+pub fn format_list(releases: &[Release]) -> String {
+    let width = releases
+        .iter()
+        .map(|r| r.version.chars().count())
+        .chain(std::iter::once(7))
+        .max()
+        .unwrap_or(0);
+    let mut out = format!(
+        "{}\n",
+        format!(
+            "{:<width$}  {:<16}  {:>5}  MESSAGE",
+            "VERSION", "CREATED", "REPOS"
+        )
+        .bold()
+    );
+    for r in releases {
+        let created = r.created.with_timezone(&chrono::Local);
+        out.push_str(&format!(
+            "{:<width$}  {:<16}  {:>5}  {}\n",
+            r.version,
+            created.format("%Y-%m-%d %H:%M"),
+            r.repos.len(),
+            r.message,
+        ));
+    }
+    out
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+/// The commit to tag in `path`, or why it cannot be tagged.
+fn check(path: &Path, version: &str) -> Result<String> {
+    anyhow::ensure!(path.exists(), "not cloned");
+    // Only repo roots count, otherwise git reports the enclosing (meta) repo
+    anyhow::ensure!(path.join(".git").exists(), "not a git repository");
+    let commit = git_utils::git(path, &["rev-parse", "--verify", "-q", "HEAD"])
+        .map_err(|_| anyhow::anyhow!("no commits yet"))?;
+    let exists = git_utils::git(
+        path,
+        &[
+            "rev-parse",
+            "--verify",
+            "-q",
+            &format!("refs/tags/{version}"),
+        ],
+    )
+    .is_ok();
+    anyhow::ensure!(!exists, "tag {version} already exists");
+    Ok(commit)
+}
+
+fn validate_version(version: &str) -> Result<()> {
+    let valid = !version.starts_with('-')
+        && Command::new("git")
+            .args(["check-ref-format", &format!("refs/tags/{version}")])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+    anyhow::ensure!(valid, "Invalid tag name '{version}'");
+    Ok(())
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::testing::init_repo;
+
+    fn workspace(names: &[&str]) -> (tempfile::TempDir, Vec<Target>) {
+        let tmp = tempfile::tempdir().unwrap();
+        let targets = names
+            .iter()
+            .map(|name| {
+                let path = tmp.path().join(name);
+                init_repo(&path);
+                (name.to_string(), path)
+            })
+            .collect();
+        (tmp, targets)
+    }
+
+    #[test]
+    fn tag_creates_annotated_tags_and_records_the_release() {
+        let (tmp, targets) = workspace(&["api", "web"]);
+        let (release, results) =
+            tag(tmp.path(), "v1.0.0", &targets, &TagOptions::default()).unwrap();
+        assert!(results.iter().all(|r| r.success));
+        assert_eq!(release.message, "Release v1.0.0");
+
+        for (name, path) in &targets {
+            let head = git_utils::git(path, &["rev-parse", "HEAD"]).unwrap();
+            assert_eq!(release.repos[name], head);
+            let kind = git_utils::git(path, &["cat-file", "-t", "v1.0.0"]).unwrap();
+            assert_eq!(kind, "tag");
+        }
+
+        let recorded = Releases::load(tmp.path()).unwrap();
+        assert_eq!(recorded.find("v1.0.0").unwrap().repos, release.repos);
+        assert!(tag(tmp.path(), "v1.0.0", &targets, &TagOptions::default()).is_err());
+    }
+
+    #[test]
+    fn existing_tag_anywhere_stops_the_release() {
+        let (tmp, targets) = workspace(&["api", "web"]);
+        git_utils::git(&targets[1].1, &["tag", "v2"]).unwrap();
+        let err = tag(tmp.path(), "v2", &targets, &TagOptions::default()).unwrap_err();
+        assert!(
+            err.to_string().contains("web (tag v2 already exists)"),
+            "{err}"
+        );
+        assert!(git_utils::git(
+            &targets[0].1,
+            &["rev-parse", "-q", "--verify", "refs/tags/v2"]
+        )
+        .is_err());
+        assert!(Releases::load(tmp.path()).unwrap().releases.is_empty());
+        assert!(tag(tmp.path(), "bad..tag", &targets, &TagOptions::default()).is_err());
+    }
+
+    #[test]
+    fn dry_run_and_push() {
+        let (tmp, targets) = workspace(&["api"]);
+        let dry = TagOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let (_, results) = tag(tmp.path(), "v3", &targets, &dry).unwrap();
+        assert!(results[0].summary.starts_with("would tag"));
+        assert!(!releases_path(tmp.path()).exists());
+
+        let remote = tmp.path().join("remote.git");
+        git_utils::git(
+            tmp.path(),
+            &["init", "-q", "--bare", remote.to_str().unwrap()],
+        )
+        .unwrap();
+        git_utils::git(
+            &targets[0].1,
+            &["remote", "add", "origin", remote.to_str().unwrap()],
+        )
+        .unwrap();
+        let push = TagOptions {
+            push: true,
+            ..Default::default()
+        };
+        let (_, results) = tag(tmp.path(), "v3", &targets, &push).unwrap();
+        assert!(
+            results[0].summary.ends_with("pushed"),
+            "{}",
+            results[0].summary
+        );
+        assert!(git_utils::git(&remote, &["rev-parse", "-q", "--verify", "refs/tags/v3"]).is_ok());
+    }
+}
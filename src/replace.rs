@@ -0,0 +1,416 @@
+//! `meta replace` (alias `meta sed`): one search-and-replace across repos.
+//!
+//! ```text
+//! meta replace 'old_name\(' 'new_name(' src         # preview the diff
+//! meta replace -F OldType NewType --write           # apply it
+//! meta replace -F v1 v2 --write --branch bump-v2 -m "Bump to v2"
+//! ```
+//!
+//! The files searched are the ones `meta grep` would search: tracked and
+//! untracked text files that `.gitignore` does not exclude. The pattern is a
+//! Rust regex in which `^` and `$` match at line ends, and the replacement
+//! may use `$1` / `${name}`; with `--fixed-strings` both are literal.
+//! Nothing is written without `--write`. With `--branch`, each changed repo
+//! switches to a new branch before its files are written, and with
+//! `--message` the changed files are committed in each repo with that
+//! message.
+
+use anyhow::{Context, Result};
+use colored::*;
+use rayon::prelude::*;
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::error::MetaError;
+use crate::git_utils::git;
+use crate::grep::{self, GrepOptions, GrepTarget};
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceOptions {
+    /// Treat the pattern and the replacement as literal strings
+    pub fixed_strings: bool,
+    pub ignore_case: bool,
+    /// Limit the replacement to these paths inside each repo
+    pub paths: Vec<String>,
+    /// Write the files; otherwise only preview
+    pub write: bool,
+    /// Branch to create in each changed repo before writing
+    pub branch: Option<String>,
+    /// Commit the changed files in each repo with this message
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChange {
+    /// Relative to the repo
+    pub file: String,
+    pub replacements: usize,
+    /// Unified diff of the change
+    pub diff: String,
+    #[serde(skip)]
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoChange {
+    pub repo: String,
+    pub files: Vec<FileChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Short hash of the commit made with `--message`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RepoChange {
+    pub fn replacements(&self) -> usize {
+        self.files.iter().map(|f| f.replacements).sum()
+    }
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// The regex `pattern` compiles to under `options`.
+pub fn compile(pattern: &str, options: &ReplaceOptions) -> Result<Regex> {
+    let source = if options.fixed_strings {
+        regex::escape(pattern)
+    } else {
+        pattern.to_string()
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(options.ignore_case)
+        .multi_line(true)
+        .build()
+        .with_context(|| format!("Invalid pattern '{pattern}'"))
+}
+
+/// Compute the change to every file of every target, in target order.
+/// Repos without a match are left out.
+pub fn plan(
+    targets: &[GrepTarget],
+    pattern: &str,
+    replacement: &str,
+    options: &ReplaceOptions,
+) -> Result<Vec<RepoChange>> {
+    let regex = compile(pattern, options)?;
+    // Every non-empty text file that is not ignored
+    let listing = GrepOptions {
+        files_with_matches: true,
+        paths: options.paths.clone(),
+        ..Default::default()
+    };
+    let files = grep::search(targets, "^", &listing)?;
+    let changes: Vec<Option<RepoChange>> = targets
+        .par_iter()
+        .map(|target| {
+            let files: Vec<FileChange> = files
+                .iter()
+                .filter(|m| m.repo == target.name)
+                .filter_map(|m| {
+                    change_file(&target.dir, &m.file, &regex, replacement, options).transpose()
+                })
+                .collect::<Result<_>>()?;
+            Ok((!files.is_empty()).then(|| RepoChange {
+                repo: target.name.clone(),
+                files,
+                branch: None,
+                commit: None,
+                error: None,
+            }))
+        })
+        .collect::<Result<_>>()?;
+    Ok(changes.into_iter().flatten().collect())
+}
+
+/// Write the planned changes, creating the branch and commit `options`
+/// asks for. A failure is recorded on its repo; the other repos go ahead.
+pub fn apply(targets: &[GrepTarget], changes: &mut [RepoChange], options: &ReplaceOptions) {
+    changes.par_iter_mut().for_each(|change| {
+        let Some(target) = targets.iter().find(|t| t.name == change.repo) else {
+            return;
+        };
+        if let Err(e) = apply_repo(&target.dir, change, options) {
+            change.error = Some(format!("{e:#}"));
+        }
+    });
+}
+
+/// Entry point for `meta replace`. Fails with `NO_MATCHES` when nothing
+/// matched and `COMMAND_FAILED` when a repo failed.
+pub fn handle_replace(
+    targets: &[GrepTarget],
+    pattern: &str,
+    replacement: &str,
+    options: &ReplaceOptions,
+    json: bool,
+) -> Result<()> {
+    if options.message.is_some() || options.branch.is_some() {
+        anyhow::ensure!(options.write, "--branch and --message need --write");
+    }
+    let mut changes = plan(targets, pattern, replacement, options)?;
+    if options.write {
+        apply(targets, &mut changes, options);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+    } else if options.write {
+        print!("{}", format_applied(&changes));
+    } else {
+        print!("{}", format_preview(&changes));
+    }
+    if changes.is_empty() {
+        return Err(MetaError::NoMatches(format!("No matches for '{pattern}'")).into());
+    }
+    if changes.iter().any(|c| c.error.is_some()) {
+        return Err(MetaError::CommandFailed.into());
+    }
+    Ok(())
+}
+
+/// The diff of every change, grouped by repo, and what `--write` would do.
+pub fn format_preview(changes: &[RepoChange]) -> String {
+    if changes.is_empty() {
+        return "No matches\n".to_string();
+    }
+    let mut out = String::new();
+    for change in changes {
+        out.push_str(&format!("{}\n", change.repo.cyan().bold()));
+        for file in &change.files {
+            for (i, line) in file.diff.lines().enumerate() {
+                let line = match line.chars().next() {
+                    // The `---` / `+++` header
+                    _ if i < 2 => line.bold().to_string(),
+                    Some('+') => line.green().to_string(),
+                    Some('-') => line.red().to_string(),
+                    Some('@') => line.cyan().to_string(),
+                    _ => line.to_string(),
+                };
+                out.push_str(&format!("{line}\n"));
+            }
+        }
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "{} {} (pass --write to apply)\n",
+        "→".cyan(),
+        summary(changes)
+    ));
+    out
+}
+
+/// One line per changed repo after `--write`.
+pub fn format_applied(changes: &[RepoChange]) -> String {
+    if changes.is_empty() {
+        return "No matches\n".to_string();
+    }
+    let width = changes.iter().map(|c| c.repo.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for change in changes {
+        let (mark, detail) = match &change.error {
+            Some(error) => ("✗".red(), error.clone()),
+            None => {
+                let mut detail = counts(change.files.len(), change.replacements());
+                if let Some(branch) = &change.branch {
+                    detail.push_str(&format!(" on '{branch}'"));
+                }
+                if let Some(commit) = &change.commit {
+                    detail.push_str(&format!(", committed {commit}"));
+                }
+                ("✓".green(), detail)
+            }
+        };
+        out.push_str(&format!("{mark} {:<width$}  {detail}\n", change.repo));
+    }
+    out
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn summary(changes: &[RepoChange]) -> String {
+    let files = changes.iter().map(|c| c.files.len()).sum();
+    let replacements = changes.iter().map(RepoChange::replacements).sum();
+    let repos = changes.len();
+    format!(
+        "{} across {repos} repo{}",
+        counts(files, replacements),
+        if repos == 1 { "" } else { "s" }
+    )
+}
+
+fn counts(files: usize, replacements: usize) -> String {
+    format!(
+        "{replacements} replacement{} in {files} file{}",
+        if replacements == 1 { "" } else { "s" },
+        if files == 1 { "" } else { "s" }
+    )
+}
+
+/// The change to one file, or `None` when it has no match or is not UTF-8.
+fn change_file(
+    repo: &Path,
+    file: &str,
+    regex: &Regex,
+    replacement: &str,
+    options: &ReplaceOptions,
+) -> Result<Option<FileChange>> {
+    let path = repo.join(file);
+    let Ok(before) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let replacements = regex.find_iter(&before).count();
+    if replacements == 0 {
+        return Ok(None);
+    }
+    let after = if options.fixed_strings {
+        regex.replace_all(&before, NoExpand(replacement))
+    } else {
+        regex.replace_all(&before, replacement)
+    }
+    .into_owned();
+    if after == before {
+        return Ok(None);
+    }
+    Ok(Some(FileChange {
+        diff: unified_diff(repo, file, &after)?,
+        file: file.to_string(),
+        replacements,
+        after,
+    }))
+}
+
+/// `git diff --no-index` of the file against `after`, with the headers
+/// naming the file inside the repo.
+fn unified_diff(repo: &Path, file: &str, after: &str) -> Result<String> {
+    let mut child = Command::new("git")
+        .args(["diff", "--no-index", "--no-color", "--", file, "-"])
+        .current_dir(repo)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run git diff")?;
+    child
+        .stdin
+        .take()
+        .context("git diff has no stdin")?
+        .write_all(after.as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let hunks = stdout.find("\n@@").map_or("", |start| &stdout[start + 1..]);
+    Ok(format!("--- a/{file}\n+++ b/{file}\n{hunks}"))
+}
+
+fn apply_repo(repo: &Path, change: &mut RepoChange, options: &ReplaceOptions) -> Result<()> {
+    if let Some(branch) = &options.branch {
+        git(repo, &["switch", "--quiet", "--create", branch])?;
+        change.branch = Some(branch.clone());
+    }
+    for file in &change.files {
+        std::fs::write(repo.join(&file.file), &file.after)
+            .with_context(|| format!("Failed to write {}", file.file))?;
+    }
+    if let Some(message) = &options.message {
+        let files: Vec<&str> = change.files.iter().map(|f| f.file.as_str()).collect();
+        git(repo, &[&["add", "--"][..], &files[..]].concat())?;
+        let commit = ["commit", "--quiet", "-m", message.as_str(), "--"];
+        git(repo, &[&commit[..], &files[..]].concat())?;
+        change.commit = Some(git(repo, &["rev-parse", "--short", "HEAD"])?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::{git, testing::init_repo};
+
+    #[test]
+    fn previews_then_writes_branches_and_commits() {
+        let tmp = tempfile::tempdir().unwrap();
+        let api = tmp.path().join("api");
+        init_repo(&api);
+        std::fs::create_dir_all(api.join("src")).unwrap();
+        std::fs::write(api.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(api.join("src/lib.rs"), "fn old_name() {}\nfn keep() {}\n").unwrap();
+        std::fs::create_dir_all(api.join("target")).unwrap();
+        std::fs::write(api.join("target/gen.rs"), "old_name").unwrap();
+        git(&api, &["add", "."]).unwrap();
+        git(&api, &["commit", "-q", "-m", "sources"]).unwrap();
+
+        let docs = tmp.path().join("docs");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(docs.join("guide.md"), "Say hi\n").unwrap();
+
+        let targets = vec![
+            GrepTarget {
+                name: "api".to_string(),
+                dir: api.clone(),
+            },
+            GrepTarget {
+                name: "docs".to_string(),
+                dir: docs,
+            },
+        ];
+        let mut options = ReplaceOptions::default();
+        let changes = plan(&targets, r"old_(\w+)", "new_$1", &options).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].files[0].file, "src/lib.rs");
+        assert!(
+            changes[0].files[0]
+                .diff
+                .contains("--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n-fn old_name() {}\n+fn new_name() {}\n"),
+            "{}",
+            changes[0].files[0].diff
+        );
+        let text = console::strip_ansi_codes(&format_preview(&changes)).to_string();
+        assert!(text.ends_with("→ 1 replacement in 1 file across 1 repo (pass --write to apply)\n"));
+        // Planning writes nothing
+        assert!(std::fs::read_to_string(api.join("src/lib.rs"))
+            .unwrap()
+            .contains("old_name"));
+
+        options.write = true;
+        options.branch = Some("rename".to_string());
+        options.message = Some("Rename old_name".to_string());
+        let mut changes = plan(&targets, r"old_(\w+)", "new_$1", &options).unwrap();
+        apply(&targets, &mut changes, &options);
+        assert_eq!(changes[0].error, None);
+        assert_eq!(
+            std::fs::read_to_string(api.join("src/lib.rs")).unwrap(),
+            "fn new_name() {}\nfn keep() {}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(api.join("target/gen.rs")).unwrap(),
+            "old_name"
+        );
+        assert_eq!(
+            crate::git_utils::current_branch(&api).as_deref(),
+            Some("rename")
+        );
+        assert_eq!(crate::git_utils::is_dirty(&api), Some(false));
+        let text = console::strip_ansi_codes(&format_applied(&changes)).to_string();
+        assert!(
+            text.starts_with("✓ api  1 replacement in 1 file on 'rename', committed "),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn fixed_strings_are_literal_on_both_sides() {
+        let options = ReplaceOptions {
+            fixed_strings: true,
+            ..Default::default()
+        };
+        let regex = compile("a.b(", &options).unwrap();
+        assert_eq!(regex.replace_all("a.b( axb(", NoExpand("$1")), "$1 axb(");
+        assert!(compile("(", &ReplaceOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+//! Run reports written to files with `--report <format>=<path>`.
+//!
+//! The only format is `junit`: an aggregated JUnit XML file with one
+//! `<testcase>` per repo, so CI systems (GitLab, Jenkins, ...) can show a
+//! multi-repo run natively:
+//!
+//! ```text
+//! meta --report junit=target/meta.xml exec -- cargo test
+//! ```
+//!
+//! Output tails are only available when output was captured, i.e. for
+//! parallel, `--stream` and `--json` runs; sequential runs hand the terminal
+//! to each command.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::runner::{RepoResult, RepoStatus, RunReport};
+
+/// Lines of stdout/stderr kept per repo.
+const TAIL_LINES: usize = 100;
+
+/// Report file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Junit,
+}
+
+/// A requested report: format and destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportSpec {
+    pub format: ReportFormat,
+    pub path: PathBuf,
+}
+
+impl ReportSpec {
+    /// Parse `junit=<path>`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let Some((format, path)) = spec.split_once('=') else {
+            anyhow::bail!(
+                "Invalid --report '{spec}': expected <format>=<path>, e.g. junit=report.xml"
+            );
+        };
+        let format = match format {
+            "junit" => ReportFormat::Junit,
+            other => anyhow::bail!("Unknown report format '{other}' (supported: junit)"),
+        };
+        anyhow::ensure!(!path.is_empty(), "Invalid --report '{spec}': missing path");
+        Ok(ReportSpec {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+
+    /// Render the report and write it, creating parent directories.
+    pub fn write(&self, report: &RunReport, suite: &str) -> Result<()> {
+        let content = match self.format {
+            ReportFormat::Junit => junit_xml(report, suite),
+        };
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write report {}", self.path.display()))
+    }
+}
+
+// ── JUnit ───────────────────────────────────────────────
+
+/// Render a run as a JUnit XML document with a single test suite.
+pub fn junit_xml(report: &RunReport, suite: &str) -> String {
+    let failures = report
+        .results
+        .iter()
+        .filter(|r| matches!(r.status, RepoStatus::Failed | RepoStatus::TimedOut))
+        .count();
+    let errors = report.count(RepoStatus::Cancelled);
+    let skipped = report.count(RepoStatus::Skipped);
+    let time: f64 = report
+        .results
+        .iter()
+        .map(|r| r.duration.as_secs_f64())
+        .sum();
+
+    let counts = format!(
+        "tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" skipped=\"{skipped}\" time=\"{time:.3}\"",
+        report.results.len()
+    );
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<testsuites {counts}>\n"));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" {counts}>\n",
+        escape(suite)
+    ));
+    for result in &report.results {
+        push_testcase(&mut xml, result);
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn push_testcase(xml: &mut String, result: &RepoResult) {
+    xml.push_str(&format!(
+        "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">\n",
+        escape(&result.name),
+        escape(&result.command),
+        result.duration.as_secs_f64()
+    ));
+    let message = match (result.status, result.exit_code) {
+        (RepoStatus::Ok, _) => None,
+        (RepoStatus::Skipped, _) => {
+            xml.push_str("      <skipped/>\n");
+            None
+        }
+        (RepoStatus::Failed, Some(code)) => Some(("failure", format!("exited with code {code}"))),
+        (RepoStatus::Failed, None) => Some(("failure", "failed".to_string())),
+        (RepoStatus::TimedOut, _) => Some(("failure", "timed out".to_string())),
+        (RepoStatus::Cancelled, _) => Some(("error", "cancelled".to_string())),
+    };
+    if let Some((element, message)) = message {
+        xml.push_str(&format!(
+            "      <{element} message=\"{}\">{}</{element}>\n",
+            escape(&message),
+            escape(&tail(&result.stderr))
+        ));
+    }
+    for (element, output) in [
+        ("system-out", &result.stdout),
+        ("system-err", &result.stderr),
+    ] {
+        if !output.trim().is_empty() {
+            xml.push_str(&format!(
+                "      <{element}>{}</{element}>\n",
+                escape(&tail(output))
+            ));
+        }
+    }
+    xml.push_str("    </testcase>\n");
+}
+
+/// The last [`TAIL_LINES`] lines of captured output.
+fn tail(output: &str) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    lines[lines.len().saturating_sub(TAIL_LINES)..].join("\n")
+}
+
+/// Escape text for XML content and attributes, dropping control characters
+/// XML 1.0 cannot represent.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' | '\t' | '\r' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Suite name for a run: the workspace directory name, or `meta`.
+pub fn suite_name(root: Option<&Path>) -> String {
+    root.and_then(|r| r.file_name())
+        .map(|n| format!("meta ({})", n.to_string_lossy()))
+        .unwrap_or_else(|| "meta".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn result(name: &str, status: RepoStatus, exit_code: Option<i32>) -> RepoResult {
+        RepoResult {
+            directory: PathBuf::from(format!("/ws/{name}")),
+            name: name.to_string(),
+            command: "make test".to_string(),
+            status,
+            exit_code,
+            duration: Duration::from_millis(1500),
+            stdout: String::new(),
+            stderr: String::new(),
+        }
+    }
+
+    #[test]
+    fn parses_specs() {
+        let spec = ReportSpec::parse("junit=out/report.xml").unwrap();
+        assert_eq!(spec.format, ReportFormat::Junit);
+        assert_eq!(spec.path, PathBuf::from("out/report.xml"));
+        assert!(ReportSpec::parse("junit").is_err());
+        assert!(ReportSpec::parse("html=x.html").is_err());
+        assert!(ReportSpec::parse("junit=").is_err());
+    }
+
+    #[test]
+    fn junit_has_one_testcase_per_repo() {
+        let mut failed = result("web", RepoStatus::Failed, Some(2));
+        failed.stderr = "error: <bad> & worse\n".to_string();
+        failed.stdout = "building\n".to_string();
+        let report = RunReport {
+            results: vec![
+                result("api", RepoStatus::Ok, Some(0)),
+                failed,
+                result("docs", RepoStatus::Skipped, None),
+            ],
+        };
+
+        let xml = junit_xml(&report, "meta (ws)");
+        assert!(xml.contains(
+            r#"<testsuite name="meta (ws)" tests="3" failures="1" errors="0" skipped="1" time="4.500">"#
+        ));
+        assert!(xml.contains(r#"<testcase name="api" classname="make test" time="1.500">"#));
+        assert!(xml.contains(
+            r#"<failure message="exited with code 2">error: &lt;bad&gt; &amp; worse</failure>"#
+        ));
+        assert!(xml.contains("<system-out>building</system-out>"));
+        assert!(xml.contains("<skipped/>"));
+    }
+
+    #[test]
+    fn tail_keeps_the_last_lines() {
+        let output: String = (0..150).map(|i| format!("line {i}\n")).collect();
+        let tail = tail(&output);
+        assert!(tail.starts_with("line 50\n"));
+        assert!(tail.ends_with("line 149"));
+    }
+
+    #[test]
+    fn write_creates_parent_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("reports/junit.xml");
+        let spec = ReportSpec::parse(&format!("junit={}", path.display())).unwrap();
+        spec.write(&RunReport::default(), "meta").unwrap();
+        let xml = std::fs::read_to_string(path).unwrap();
+        assert!(xml.contains(r#"tests="0""#));
+    }
+}
@@ -0,0 +1,2270 @@
+//! Multi-repo command runner used by `meta exec`.
+//!
+//! Executes one command per directory, sequentially or in parallel, and
+//! collects a per-repo result so the run can end with an aggregate
+//! pass/fail summary. Three output modes are supported:
+//!
+//! - **Grouped** (default): sequential runs inherit the terminal; parallel
+//!   runs capture each repo's output and print it as one block when that
+//!   repo finishes.
+//! - **Stream** (`--stream`): every stdout/stderr line is printed as it
+//!   arrives, prefixed with the repo name in the style of `docker compose`:
+//!
+//!   ```text
+//!   api    | Compiling serde v1.0.200
+//!   web    | > vite build
+//!   api    | Finished dev [unoptimized] target(s) in 4.2s
+//!   ```
+//!
+//! - **Json** (`--json`): output is captured and emitted as a single JSON
+//!   document in the same shape as `loop_lib`, extended with per-repo status.
+//! - **Ndjson** (`--output ndjson`): one JSON event per line as the run
+//!   progresses, for consumers that want progress without waiting:
+//!
+//!   ```text
+//!   {"event":"repo_started","repo":"api","directory":"/ws/api","command":"make"}
+//!   {"event":"stdout_line","repo":"api","line":"ok"}
+//!   {"event":"repo_finished","repo":"api","status":"ok","exit_code":0,"duration_ms":412}
+//!   {"event":"run_summary","total":1,"succeeded":1,"failed":0,"skipped":0,"dry_run":false,"success":true}
+//!   ```
+//!
+//!   Stderr lines arrive as `stderr_line` events.
+//! - **Summary** (`--summary-only`): output is captured and discarded; the
+//!   run prints only a matrix of repo × result, for cron jobs and scripts
+//!   that only need status:
+//!
+//!   ```text
+//!   REPO       RESULT   EXIT  TIME
+//!   . (ws)     ok       0     310ms
+//!   api        fail     2     1.2s
+//!   web        timeout  -     10.0s
+//!   ```
+//!
+//! - **Tui** (`--ui tui`): a live view with one row per repo (see
+//!   [`crate::progress`]); output is captured, and the output of repos that
+//!   did not succeed is printed after the run, followed by the summary.
+//! - **Passthrough** (`--passthrough`): for exactly one repo. The command
+//!   gets meta's stdin, stdout and stderr untouched, nothing else is
+//!   printed, and meta exits with the command's own exit code (see
+//!   [`passthrough_exit_code`]), so `meta --include api --passthrough exec
+//!   -- make test` can stand in for the bare command in scripts.
+//!
+//! Every repo runs to completion by default. With `--fail-fast` the first
+//! failure stops new repos from starting and kills the ones still running,
+//! together with everything they started (their process group on Unix,
+//! their process tree on Windows).
+//! With `--order topo` a repo only starts once its dependencies succeeded.
+//! With `--timeout` a command still running after the limit is killed (with
+//! its process group on Unix) and reported as timed out; the other repos
+//! carry on.
+//!
+//! On Unix, SIGINT and SIGTERM cancel the run instead of killing meta: no
+//! new repos start, the signal is forwarded to every running command's
+//! process group, and commands still running after [`INTERRUPT_GRACE`] are
+//! killed. The summary then shows what completed, and meta exits with
+//! 128 + the signal number. A second signal skips the grace period. When
+//! commands share the terminal (sequential grouped runs) they stay in
+//! meta's process group so interactive programs keep working; Ctrl-C
+//! reaches them from the terminal directly.
+//!
+//! Commands get a closed stdin unless `--stdin` says otherwise: `inherit`
+//! hands them meta's own stdin (sequential runs only, since concurrent
+//! readers would steal each other's input), `file:<path>` opens the file
+//! afresh for every command, and `tee` reads meta's stdin once and writes a
+//! copy to every command. See [`StdinMode`].
+//!
+//! A repo's command runs in the subdirectory listed for it in
+//! [`RunConfig::workdirs`] (see [`crate::workdir`]), and at its root
+//! otherwise.
+//!
+//! With `--confirm` each repo's command is shown before it runs and the
+//! user answers y/N/a(ll)/q(uit) (see [`Answer`]). Declined repos are
+//! skipped; quitting skips the rest.
+//!
+//! Directory display, shell selection, alias resolution and include/exclude
+//! filtering follow the same conventions as `loop_lib`. A `.looprc` in a
+//! target directory can add aliases, shell, env and skip rules for that
+//! directory; see [`crate::looprc`].
+
+use anyhow::{Context, Result};
+use colored::*;
+use loop_lib::{DirCommand, LoopConfig};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::looprc::DirConfig;
+use crate::progress::{self, Progress};
+
+/// Colors cycled through for repo prefixes so adjacent repos are distinguishable.
+const PREFIX_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Yellow,
+    Color::Green,
+    Color::Magenta,
+    Color::Blue,
+    Color::BrightRed,
+];
+
+/// How often a running child is polled for exit or cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How long commands get to exit after an interrupt before being killed.
+pub const INTERRUPT_GRACE: Duration = Duration::from_secs(5);
+
+/// Exit code for a `--passthrough` command killed by `--timeout`, as with
+/// coreutils `timeout`.
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+// ── Types ───────────────────────────────────────────────
+
+/// How command output is rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    #[default]
+    Grouped,
+    Stream,
+    Json,
+    Ndjson,
+    Summary,
+    Tui,
+    Passthrough,
+}
+
+/// Where each command's stdin comes from (`--stdin`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StdinMode {
+    /// Closed: reads see end-of-file at once.
+    #[default]
+    Null,
+    /// Meta's own stdin, handed to each command in turn.
+    Inherit,
+    /// The file, opened afresh for every command.
+    File(PathBuf),
+    /// Meta's stdin, read once up front and copied to every command.
+    Tee,
+}
+
+impl StdinMode {
+    /// Parse `inherit`, `file:<path>` or `tee`.
+    pub fn parse(spec: &str) -> Option<StdinMode> {
+        match spec {
+            "inherit" => Some(StdinMode::Inherit),
+            "tee" => Some(StdinMode::Tee),
+            _ => spec
+                .strip_prefix("file:")
+                .filter(|path| !path.is_empty())
+                .map(|path| StdinMode::File(PathBuf::from(path))),
+        }
+    }
+}
+
+/// An answer to the `--confirm` prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Yes,
+    /// The default: skip this repo.
+    No,
+    /// Run this repo and every remaining one without asking.
+    All,
+    /// Skip this repo and every remaining one.
+    Quit,
+}
+
+impl Answer {
+    pub fn parse(input: &str) -> Answer {
+        match input.trim().to_lowercase().as_str() {
+            "y" | "yes" => Answer::Yes,
+            "a" | "all" => Answer::All,
+            "q" | "quit" => Answer::Quit,
+            _ => Answer::No,
+        }
+    }
+}
+
+/// Options controlling how commands are executed.
+#[derive(Debug, Clone, Default)]
+pub struct RunConfig {
+    /// Run all directories concurrently instead of one after another.
+    pub parallel: bool,
+    /// Upper bound on concurrently running commands (parallel mode only).
+    pub max_parallel: Option<usize>,
+    /// Print what would run without spawning anything.
+    pub dry_run: bool,
+    /// Suppress command output and the final summary.
+    pub silent: bool,
+    pub verbose: bool,
+    /// Stop starting new repos (and kill running ones) after the first failure.
+    pub fail_fast: bool,
+    pub output: OutputMode,
+    /// Workspace root, displayed as ". (basename)" and sorted first.
+    pub root_dir: Option<PathBuf>,
+    /// Environment variables applied to every command.
+    pub env: Option<HashMap<String, String>>,
+    /// Shell program and flags each command is passed to, e.g.
+    /// `["bash", "-lc"]`. Defaults to `$SHELL -c` (`%COMSPEC% /c` on Windows).
+    pub shell: Option<Vec<String>>,
+    /// Kill a repo's command if it runs longer than this.
+    pub timeout: Option<Duration>,
+    pub stdin: StdinMode,
+    /// Ask before running each repo's command. Only sensible for
+    /// sequential runs on a terminal.
+    pub confirm: bool,
+    /// Subdirectory to run in, relative to the repo, keyed by
+    /// `DirCommand::dir`.
+    pub workdirs: HashMap<String, PathBuf>,
+}
+
+impl From<&LoopConfig> for RunConfig {
+    fn from(config: &LoopConfig) -> Self {
+        RunConfig {
+            parallel: config.parallel,
+            max_parallel: config.max_parallel,
+            dry_run: config.dry_run,
+            silent: config.silent,
+            verbose: config.verbose,
+            fail_fast: false,
+            output: if config.json_output {
+                OutputMode::Json
+            } else {
+                OutputMode::Grouped
+            },
+            root_dir: config.root_dir.clone(),
+            env: config.env.clone(),
+            shell: None,
+            timeout: None,
+            stdin: StdinMode::Null,
+            confirm: false,
+            workdirs: HashMap::new(),
+        }
+    }
+}
+
+/// Final state of a repo after a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepoStatus {
+    Ok,
+    Failed,
+    /// Never started because an earlier failure aborted the run or a
+    /// dependency did not succeed.
+    Skipped,
+    /// Killed while running because another repo failed.
+    Cancelled,
+    /// Killed because it ran longer than `--timeout`.
+    TimedOut,
+}
+
+impl RepoStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RepoStatus::Ok => "ok",
+            RepoStatus::Failed => "fail",
+            RepoStatus::Skipped => "skip",
+            RepoStatus::Cancelled => "cancelled",
+            RepoStatus::TimedOut => "timeout",
+        }
+    }
+}
+
+/// Outcome of running a command in a single directory.
+#[derive(Debug, Clone)]
+pub struct RepoResult {
+    pub directory: PathBuf,
+    /// Display name used for prefixes and summaries.
+    pub name: String,
+    pub command: String,
+    pub status: RepoStatus,
+    /// Process exit code; `None` if the command never ran or was killed.
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+    /// Captured output (empty when the terminal was inherited).
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl RepoResult {
+    pub fn success(&self) -> bool {
+        self.status == RepoStatus::Ok
+    }
+}
+
+/// Results of a multi-repo run, in input order.
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    pub results: Vec<RepoResult>,
+}
+
+impl RunReport {
+    pub fn count(&self, status: RepoStatus) -> usize {
+        self.results.iter().filter(|r| r.status == status).count()
+    }
+
+    /// True if nothing failed, was cancelled, or was skipped.
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|r| r.success())
+    }
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Concurrency used when neither `--jobs` nor `defaults.jobs` is set.
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Build one command per directory of a `LoopConfig`, applying its
+/// include/exclude filters exactly like `loop_lib::run`.
+pub fn commands_for(config: &LoopConfig, command: &str) -> Vec<DirCommand> {
+    filter_directories(
+        &config.directories,
+        config.include_filters.as_deref(),
+        config.exclude_filters.as_deref(),
+    )
+    .into_iter()
+    .map(|dir| DirCommand {
+        dir,
+        cmd: command.to_string(),
+        env: config.env.clone(),
+    })
+    .collect()
+}
+
+/// Replace each command whose directory is not a git repo with one command
+/// per repo found up to `max_depth` levels below it (see
+/// [`crate::git_utils::find_repos`]: hidden, ignored and nested directories
+/// are skipped). Directories with no repos below them are kept as they are.
+pub fn expand_directories(commands: Vec<DirCommand>, max_depth: usize) -> Vec<DirCommand> {
+    commands
+        .into_iter()
+        .flat_map(|command| {
+            let dir = PathBuf::from(&command.dir);
+            let repos = if dir.join(".git").exists() {
+                Vec::new()
+            } else {
+                crate::git_utils::find_repos(&dir, max_depth)
+            };
+            if repos.is_empty() {
+                return vec![command];
+            }
+            repos
+                .into_iter()
+                .map(|rel| DirCommand {
+                    dir: dir.join(rel).display().to_string(),
+                    cmd: command.cmd.clone(),
+                    env: command.env.clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Run the commands, print the summary (or JSON document), and return an
+/// error if any repo did not succeed.
+pub fn run(config: &RunConfig, commands: &[DirCommand]) -> Result<()> {
+    finish(config, execute(config, commands)?)
+}
+
+/// Like [`run`], but each command only starts once the commands listed in
+/// `waits_for[i]` (indices into `commands`) have succeeded.
+pub fn run_with_dependencies(
+    config: &RunConfig,
+    commands: &[DirCommand],
+    waits_for: &[Vec<usize>],
+) -> Result<()> {
+    finish(
+        config,
+        execute_with_dependencies(config, commands, waits_for)?,
+    )
+}
+
+/// Print the summary (or JSON document) for a finished run and return an
+/// error if any repo did not succeed.
+///
+/// A `--passthrough` run ends with [`passthrough_exit_code`] instead, and
+/// an interrupted run with 128 + the signal, as a [`crate::error::Exit`].
+pub fn finish(config: &RunConfig, report: RunReport) -> Result<()> {
+    if config.output == OutputMode::Passthrough {
+        // Only meta's own messages were captured, e.g. a missing directory
+        for result in &report.results {
+            eprint!("{}", result.stderr);
+        }
+        let code = passthrough_exit_code(&report);
+        if code != 0 {
+            return Err(crate::error::Exit(code).into());
+        }
+        return Ok(());
+    }
+    if config.output == OutputMode::Json {
+        print_json(&report, config.dry_run)?;
+    } else if config.output == OutputMode::Ndjson {
+        let summary = json_output(&report, config.dry_run).summary;
+        let mut event = serde_json::to_value(&summary)?;
+        event["success"] = report.is_success().into();
+        write_event("run_summary", event);
+    } else if config.output == OutputMode::Summary {
+        if !config.dry_run {
+            print!("{}", summary_matrix(&report));
+        }
+    } else if !config.silent {
+        if config.output == OutputMode::Tui {
+            print_failed_output(&report);
+        }
+        print_summary(&report, config.dry_run);
+    }
+
+    if let Some(signal) = interrupted() {
+        return Err(crate::error::Exit(128 + signal).into());
+    }
+    if !report.is_success() && !config.dry_run {
+        return Err(crate::error::MetaError::CommandFailed.into());
+    }
+    Ok(())
+}
+
+/// Meta's whole stdin under `--stdin tee`, read before any command starts.
+fn read_tee_input(config: &RunConfig) -> Result<Option<Vec<u8>>> {
+    if config.stdin != StdinMode::Tee || config.dry_run {
+        return Ok(None);
+    }
+    let mut input = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut input)
+        .context("Failed to read stdin for --stdin tee")?;
+    Ok(Some(input))
+}
+
+/// The exit code a `--passthrough` run ends with: the command's own, 124
+/// if it timed out, 128 + the signal number if meta was interrupted before
+/// it exited, and 1 if it never ran.
+pub fn passthrough_exit_code(report: &RunReport) -> i32 {
+    match report.results.as_slice() {
+        [result] => match (result.exit_code, result.status) {
+            (Some(code), _) => code,
+            (None, RepoStatus::TimedOut) => TIMEOUT_EXIT_CODE,
+            (None, _) => interrupted().map_or(crate::error::GENERIC_EXIT_CODE, |s| 128 + s),
+        },
+        _ if report.is_success() => 0,
+        _ => crate::error::MetaError::CommandFailed.exit_code(),
+    }
+}
+
+/// Run each command in its directory and collect the results without
+/// printing a summary.
+pub fn execute(config: &RunConfig, commands: &[DirCommand]) -> Result<RunReport> {
+    if commands.is_empty() {
+        return Ok(RunReport::default());
+    }
+
+    install_interrupt_handler();
+    let mut runner = Runner::new(config, commands);
+    runner.tee_input = read_tee_input(config)?;
+    let results = runner.with_progress(|| -> Result<Vec<RepoResult>> {
+        if !config.parallel {
+            return Ok((0..commands.len()).map(|i| runner.run_one(i)).collect());
+        }
+        let run_all = || {
+            (0..commands.len())
+                .into_par_iter()
+                .map(|i| runner.run_one(i))
+                .collect::<Vec<_>>()
+        };
+        Ok(match config.max_parallel {
+            Some(max) => rayon::ThreadPoolBuilder::new()
+                .num_threads(max)
+                .build()?
+                .install(run_all),
+            None => run_all(),
+        })
+    })?;
+
+    Ok(RunReport { results })
+}
+
+/// Run commands in dependency order and collect the results.
+///
+/// Independent commands run concurrently (up to `max_parallel`) in parallel
+/// mode. A command whose dependency failed is skipped, as is everything
+/// downstream of it.
+pub fn execute_with_dependencies(
+    config: &RunConfig,
+    commands: &[DirCommand],
+    waits_for: &[Vec<usize>],
+) -> Result<RunReport> {
+    let n = commands.len();
+    let workers = if config.parallel {
+        config.max_parallel.unwrap_or(n).max(1)
+    } else {
+        1
+    };
+
+    install_interrupt_handler();
+    let mut runner = Runner::new(config, commands);
+    runner.tee_input = read_tee_input(config)?;
+    let mut dependents = vec![Vec::new(); n];
+    let mut pending = vec![0; n];
+    for (i, deps) in waits_for.iter().enumerate().take(n) {
+        pending[i] = deps.len();
+        for &dep in deps {
+            dependents[dep].push(i);
+        }
+    }
+
+    let mut results: Vec<Option<RepoResult>> = vec![None; n];
+    let mut ready: VecDeque<usize> = (0..n).filter(|&i| pending[i] == 0).collect();
+    let (tx, rx) = mpsc::channel();
+
+    runner.with_progress(|| {
+        std::thread::scope(|s| {
+            let mut running = 0;
+            loop {
+                while running < workers {
+                    let Some(i) = ready.pop_front() else { break };
+                    let (tx, runner) = (tx.clone(), &runner);
+                    s.spawn(move || {
+                        // The receiver outlives every worker
+                        let _ = tx.send((i, runner.run_one(i)));
+                    });
+                    running += 1;
+                }
+                if running == 0 {
+                    break;
+                }
+
+                let Ok((i, result)) = rx.recv() else { break };
+                running -= 1;
+                let succeeded = result.success();
+                results[i] = Some(result);
+
+                if succeeded {
+                    for &next in &dependents[i] {
+                        pending[next] -= 1;
+                        if pending[next] == 0 && results[next].is_none() {
+                            ready.push_back(next);
+                        }
+                    }
+                } else {
+                    // Skip everything downstream of the failure
+                    let mut stack = dependents[i].clone();
+                    while let Some(next) = stack.pop() {
+                        if results[next].is_none() {
+                            results[next] = Some(runner.pending_result(next));
+                            stack.extend(&dependents[next]);
+                        }
+                    }
+                }
+            }
+        })
+    });
+
+    let results = results
+        .into_iter()
+        .enumerate()
+        .map(|(i, r)| r.unwrap_or_else(|| runner.pending_result(i)))
+        .collect();
+    Ok(RunReport { results })
+}
+
+/// Display name for a directory: ". (basename)" for the workspace root,
+/// otherwise its basename.
+pub fn display_name(dir: &Path, root_dir: Option<&Path>) -> String {
+    let base = dir.file_name().and_then(|n| n.to_str());
+    if root_dir.is_some_and(|r| r == dir) {
+        return match base {
+            Some(base) => format!(". ({base})"),
+            None => ".".to_string(),
+        };
+    }
+    base.unwrap_or(".").to_string()
+}
+
+/// Apply include/exclude substring filters, matching `loop_lib` semantics.
+pub fn filter_directories(
+    dirs: &[String],
+    include: Option<&[String]>,
+    exclude: Option<&[String]>,
+) -> Vec<String> {
+    let mut dirs = dirs.to_vec();
+    if let Some(includes) = include {
+        if !includes.is_empty() {
+            dirs.retain(|p| includes.iter().any(|f| p.contains(f.as_str())));
+        }
+    }
+    if let Some(excludes) = exclude {
+        if !excludes.is_empty() {
+            dirs.retain(|p| !excludes.iter().any(|f| p.contains(f.trim_end_matches('/'))));
+        }
+    }
+    dirs
+}
+
+/// Print the end-of-run summary.
+///
+/// A clean run prints a single line; otherwise every repo is listed with its
+/// status, exit code, and duration so failures are visible at a glance.
+pub fn print_summary(report: &RunReport, dry_run: bool) {
+    let total = report.results.len();
+
+    if dry_run {
+        println!(
+            "\n{} Would run {} command(s) across {} directories",
+            "[DRY RUN]".cyan(),
+            total.to_string().yellow(),
+            total.to_string().yellow()
+        );
+        return;
+    }
+
+    if report.is_success() {
+        println!("{} commands complete", total.to_string().green());
+        return;
+    }
+
+    let width = report
+        .results
+        .iter()
+        .map(|r| r.name.len())
+        .max()
+        .unwrap_or(0);
+
+    println!("\n{}", "Summary:".bold());
+    for result in &report.results {
+        let (mark, name) = match result.status {
+            RepoStatus::Ok => ("✓".green(), format!("{:<width$}", result.name).green()),
+            RepoStatus::Failed => ("✗".red(), format!("{:<width$}", result.name).red()),
+            RepoStatus::Cancelled | RepoStatus::TimedOut => {
+                ("✗".yellow(), format!("{:<width$}", result.name).yellow())
+            }
+            RepoStatus::Skipped => ("-".dimmed(), format!("{:<width$}", result.name).dimmed()),
+        };
+        let detail = match (result.status, result.exit_code) {
+            (RepoStatus::Failed, Some(code)) => format!("exit {code}"),
+            (RepoStatus::Ok, _) => String::new(),
+            (status, _) => status.label().to_string(),
+        };
+        let duration = if result.status == RepoStatus::Skipped {
+            String::new()
+        } else {
+            format_duration(result.duration)
+        };
+        println!("  {mark} {name}  {detail:<9}  {duration}");
+    }
+
+    let mut counts = vec![
+        format!("{} passed", report.count(RepoStatus::Ok)).green(),
+        format!("{} failed", report.count(RepoStatus::Failed)).red(),
+    ];
+    for (status, word) in [
+        (RepoStatus::TimedOut, "timed out"),
+        (RepoStatus::Cancelled, "cancelled"),
+        (RepoStatus::Skipped, "skipped"),
+    ] {
+        let n = report.count(status);
+        if n > 0 {
+            counts.push(format!("{n} {word}").yellow());
+        }
+    }
+    let counts: Vec<String> = counts.iter().map(|c| c.to_string()).collect();
+    println!("\n{} {} ({total} repos)", "✗".red(), counts.join(", "));
+}
+
+/// The `--summary-only` matrix: one line per repo with its result, exit
+/// code (`-` if it never exited on its own) and duration.
+pub fn summary_matrix(report: &RunReport) -> String {
+    let width = report
+        .results
+        .iter()
+        .map(|r| r.name.len())
+        .chain([4])
+        .max()
+        .unwrap_or(0);
+    let mut out = format!("{:<width$}  {:<7}  {:<4}  TIME\n", "REPO", "RESULT", "EXIT");
+    for result in &report.results {
+        let exit = match (result.status, result.exit_code) {
+            (RepoStatus::Ok | RepoStatus::Failed, Some(code)) => code.to_string(),
+            _ => "-".to_string(),
+        };
+        let time = match result.status {
+            RepoStatus::Skipped => "-".to_string(),
+            _ => format_duration(result.duration),
+        };
+        out.push_str(&format!(
+            "{:<width$}  {:<7}  {exit:<4}  {time}\n",
+            result.name,
+            result.status.label()
+        ));
+    }
+    out
+}
+
+/// Print the captured output of every repo that did not succeed, as
+/// grouped mode would have while the run was going.
+fn print_failed_output(report: &RunReport) {
+    for result in report.results.iter().filter(|r| !r.success()) {
+        if result.stdout.trim().is_empty() && result.stderr.trim().is_empty() {
+            continue;
+        }
+        println!("{} {}:", "✗".red(), result.name.red());
+        print!("{}", result.stdout);
+        eprint!("{}", result.stderr);
+        println!();
+    }
+}
+
+/// Print the run as a JSON document compatible with `loop_lib`'s output.
+pub fn print_json(report: &RunReport, dry_run: bool) -> Result<()> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json_output(report, dry_run))?
+    );
+    Ok(())
+}
+
+// ── Dry Run ─────────────────────────────────────────────
+
+/// A command as it would be spawned, for `--dry-run`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedCommand {
+    pub name: String,
+    pub directory: String,
+    /// The command after alias resolution.
+    pub command: String,
+    /// Full argv: shell, shell flags, command.
+    pub argv: Vec<String>,
+    /// Names of the variables set on top of the inherited environment.
+    pub env: Vec<String>,
+    /// False if the directory is missing (the real run would fail there).
+    pub exists: bool,
+}
+
+/// Resolve every command to the exact process that would be spawned.
+pub fn plan(config: &RunConfig, commands: &[DirCommand]) -> Vec<PlannedCommand> {
+    let runner = Runner::new(config, commands);
+    (0..commands.len())
+        .map(|i| {
+            let command = runner.resolved_command(i);
+            let (shell, shell_args) = runner.shell(i);
+            let mut argv = vec![shell];
+            argv.extend(shell_args);
+            argv.push(command.clone());
+            let mut env: Vec<String> = runner
+                .env_layers(i)
+                .flat_map(|e| e.keys().cloned())
+                .collect();
+            env.sort();
+            env.dedup();
+            let dir = runner.working_dir(i);
+            PlannedCommand {
+                name: runner.names[i].clone(),
+                directory: dir.display().to_string(),
+                command,
+                argv,
+                env,
+                exists: dir.is_dir(),
+            }
+        })
+        .collect()
+}
+
+/// Print the dry-run plan as a table, or as JSON in JSON mode. `filters`
+/// lists the selection options that produced these directories.
+pub fn print_plan(
+    config: &RunConfig,
+    commands: &[DirCommand],
+    filters: &[(&str, String)],
+) -> Result<()> {
+    let planned = plan(config, commands);
+
+    if matches!(config.output, OutputMode::Json | OutputMode::Ndjson) {
+        let filters: serde_json::Map<String, serde_json::Value> = filters
+            .iter()
+            .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.clone())))
+            .collect();
+        let output = serde_json::json!({
+            "dry_run": true,
+            "parallel": config.parallel,
+            "max_parallel": config.max_parallel,
+            "filters": filters,
+            "commands": planned,
+        });
+        if config.output == OutputMode::Ndjson {
+            println!("{}", serde_json::to_string(&output)?);
+        } else {
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        return Ok(());
+    }
+
+    let mode = match (config.parallel, config.max_parallel) {
+        (true, Some(n)) => format!("parallel, {n} at a time"),
+        (true, None) => "parallel".to_string(),
+        (false, _) => "sequential".to_string(),
+    };
+    println!(
+        "{} {} command(s), {mode}",
+        "[DRY RUN]".cyan(),
+        planned.len().to_string().yellow()
+    );
+    if !filters.is_empty() {
+        let applied: Vec<String> = filters.iter().map(|(k, v)| format!("{k} {v}")).collect();
+        println!("Filters: {}", applied.join(", "));
+    }
+    if planned.is_empty() {
+        return Ok(());
+    }
+
+    let argvs: Vec<String> = planned
+        .iter()
+        .map(|p| {
+            p.argv
+                .iter()
+                .map(|a| shell_quote(a))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+    let name_width = planned
+        .iter()
+        .map(|p| p.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let cmd_width = argvs.iter().map(|a| a.len()).max().unwrap_or(0).max(7);
+
+    println!();
+    println!(
+        "  {}  {}  {}",
+        format!("{:<name_width$}", "REPO").bold(),
+        format!("{:<cmd_width$}", "COMMAND").bold(),
+        "ENV".bold()
+    );
+    for (p, argv) in planned.iter().zip(&argvs) {
+        let name = format!("{:<name_width$}", p.name);
+        let name = if p.exists { name.normal() } else { name.red() };
+        println!(
+            "  {name}  {argv:<cmd_width$}  {}",
+            p.env.join(", ").dimmed()
+        );
+    }
+    if let Some(missing) = planned.iter().find(|p| !p.exists) {
+        println!("\n{} {} does not exist", "✗".red(), missing.directory);
+    }
+    Ok(())
+}
+
+/// Quote an argument for display the way a POSIX shell would need it.
+pub(crate) fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+// ── JSON Output ─────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+struct JsonOutput {
+    success: bool,
+    results: Vec<JsonRepoResult>,
+    summary: JsonSummary,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonRepoResult {
+    directory: String,
+    command: String,
+    success: bool,
+    status: RepoStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+    duration_ms: u128,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    stdout: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    stderr: String,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSummary {
+    total: usize,
+    succeeded: usize,
+    failed: usize,
+    skipped: usize,
+    dry_run: bool,
+}
+
+fn json_output(report: &RunReport, dry_run: bool) -> JsonOutput {
+    let total = report.results.len();
+    let succeeded = report.count(RepoStatus::Ok);
+    let skipped = report.count(RepoStatus::Skipped);
+    JsonOutput {
+        success: report.is_success(),
+        results: report
+            .results
+            .iter()
+            .map(|r| JsonRepoResult {
+                directory: r.directory.display().to_string(),
+                command: r.command.clone(),
+                success: r.success(),
+                status: r.status,
+                exit_code: r.exit_code,
+                duration_ms: r.duration.as_millis(),
+                stdout: r.stdout.clone(),
+                stderr: r.stderr.clone(),
+            })
+            .collect(),
+        summary: JsonSummary {
+            total,
+            succeeded,
+            failed: total - succeeded - skipped,
+            skipped,
+            dry_run,
+        },
+    }
+}
+
+// ── Execution ───────────────────────────────────────────
+
+/// How a child process ended.
+enum Exit {
+    Code(i32),
+    Cancelled,
+    TimedOut,
+    Interrupted,
+}
+
+/// Shared state for one run.
+struct Runner<'a> {
+    config: &'a RunConfig,
+    commands: &'a [DirCommand],
+    names: Vec<String>,
+    width: usize,
+    aliases: HashMap<String, String>,
+    /// Each directory's `.looprc`, or why it could not be read.
+    dir_configs: Vec<Result<DirConfig, String>>,
+    /// The live view in TUI mode.
+    progress: Option<Mutex<Progress>>,
+    /// Set on the first failure under `--fail-fast`.
+    cancelled: AtomicBool,
+    /// Serializes terminal writes so lines and blocks never interleave.
+    output_lock: Mutex<()>,
+    /// Meta's stdin under `--stdin tee`, written to every command.
+    tee_input: Option<Vec<u8>>,
+    /// Set once the user answers "all" to the `--confirm` prompt.
+    confirmed_all: AtomicBool,
+}
+
+impl<'a> Runner<'a> {
+    fn new(config: &'a RunConfig, commands: &'a [DirCommand]) -> Self {
+        let names: Vec<String> = commands
+            .iter()
+            .map(|c| display_name(Path::new(&c.dir), config.root_dir.as_deref()))
+            .collect();
+        let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+        let progress = (config.output == OutputMode::Tui && !config.dry_run)
+            .then(|| Mutex::new(Progress::new(&names)));
+        Runner {
+            config,
+            commands,
+            names,
+            width,
+            aliases: loop_lib::get_aliases(),
+            dir_configs: commands
+                .iter()
+                .map(|c| DirConfig::load(Path::new(&c.dir)).map_err(|e| format!("{e:#}")))
+                .collect(),
+            progress,
+            cancelled: AtomicBool::new(false),
+            output_lock: Mutex::new(()),
+            tee_input: None,
+            confirmed_all: AtomicBool::new(false),
+        }
+    }
+
+    /// Run `f`, drawing the live view meanwhile in TUI mode.
+    fn with_progress<T>(&self, f: impl FnOnce() -> T) -> T {
+        let Some(view) = &self.progress else {
+            return f();
+        };
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            s.spawn(|| progress::draw_until(view, &done));
+            let result = f();
+            done.store(true, Ordering::SeqCst);
+            result
+        })
+    }
+
+    fn update_progress(&self, update: impl FnOnce(&mut Progress)) {
+        if let Some(view) = &self.progress {
+            update(&mut view.lock().unwrap_or_else(|e| e.into_inner()));
+        }
+    }
+
+    /// Whether the terminal is handed directly to the child.
+    fn inherits_terminal(&self) -> bool {
+        match self.config.output {
+            OutputMode::Grouped => !self.config.parallel,
+            OutputMode::Passthrough => true,
+            _ => false,
+        }
+    }
+
+    fn prints_output(&self) -> bool {
+        !self.config.silent
+            && !matches!(
+                self.config.output,
+                OutputMode::Json
+                    | OutputMode::Ndjson
+                    | OutputMode::Summary
+                    | OutputMode::Passthrough
+            )
+    }
+
+    fn emits_events(&self) -> bool {
+        self.config.output == OutputMode::Ndjson && !self.config.dry_run
+    }
+
+    /// Write one NDJSON event for repo `i`, never interleaved with another.
+    fn event(&self, event: &str, i: usize, fields: serde_json::Value) {
+        let mut repo = serde_json::json!({ "repo": self.names[i] });
+        if let (Some(repo), serde_json::Value::Object(fields)) = (repo.as_object_mut(), fields) {
+            repo.extend(fields);
+        }
+        let _guard = self.output_lock.lock().unwrap_or_else(|e| e.into_inner());
+        write_event(event, repo);
+    }
+
+    fn dir_config(&self, i: usize) -> Option<&DirConfig> {
+        self.dir_configs[i].as_ref().ok()
+    }
+
+    /// Command `i` after alias resolution; the directory's own aliases win.
+    fn resolved_command(&self, i: usize) -> String {
+        let cmd = &self.commands[i].cmd;
+        match self.dir_config(i).filter(|c| !c.aliases.is_empty()) {
+            Some(dir) => {
+                let mut aliases = self.aliases.clone();
+                aliases.extend(dir.aliases.clone());
+                resolve_alias(cmd, &aliases)
+            }
+            None => resolve_alias(cmd, &self.aliases),
+        }
+    }
+
+    /// Shell for command `i`: the directory's `.looprc` shell, if set.
+    fn shell(&self, i: usize) -> (String, Vec<String>) {
+        match self.dir_config(i).and_then(|c| c.shell.as_deref()) {
+            Some([program, args @ ..]) => (program.clone(), args.to_vec()),
+            _ => shell_for(self.config),
+        }
+    }
+
+    /// Where command `i` runs: its repo, or the repo's workdir.
+    fn working_dir(&self, i: usize) -> PathBuf {
+        let dir = &self.commands[i].dir;
+        match self.config.workdirs.get(dir) {
+            Some(sub) => Path::new(dir).join(sub),
+            None => PathBuf::from(dir),
+        }
+    }
+
+    /// Environment layers for command `i`, lowest precedence first.
+    fn env_layers(&self, i: usize) -> impl Iterator<Item = &HashMap<String, String>> {
+        [
+            self.config.env.as_ref(),
+            self.dir_config(i).map(|c| &c.env),
+            self.commands[i].env.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Result for a command that has not run (yet).
+    fn pending_result(&self, i: usize) -> RepoResult {
+        let dir_cmd = &self.commands[i];
+        RepoResult {
+            directory: PathBuf::from(&dir_cmd.dir),
+            name: self.names[i].clone(),
+            command: self.resolved_command(i),
+            status: RepoStatus::Skipped,
+            exit_code: None,
+            duration: Duration::ZERO,
+            stdout: String::new(),
+            stderr: String::new(),
+        }
+    }
+
+    fn run_one(&self, i: usize) -> RepoResult {
+        let mut result = self.pending_result(i);
+
+        if self.cancelled.load(Ordering::SeqCst) || interrupted().is_some() {
+            return result;
+        }
+        if self.dir_config(i).is_some_and(|c| c.skips(&result.command)) {
+            if self.config.verbose && self.prints_output() {
+                self.emit_line(i, StreamKind::Stderr, "skipped by .looprc");
+            }
+            return result;
+        }
+        if !self.confirmed(i, &result.command) {
+            return result;
+        }
+
+        if self.emits_events() {
+            let fields = serde_json::json!({
+                "directory": result.directory.display().to_string(),
+                "command": result.command,
+            });
+            self.event("repo_started", i, fields);
+        }
+        self.update_progress(|p| p.start(i));
+        let mut span = crate::telemetry::Span::start("repo.command");
+        span.attr("repo", &result.name);
+        span.attr("directory", result.directory.display());
+        span.attr("command", &result.command);
+        let start = Instant::now();
+        self.execute_into(i, &mut result);
+        result.duration = start.elapsed();
+        span.attr("status", result.status.label());
+        if let Some(code) = result.exit_code {
+            span.attr("exit_code", code);
+        }
+        if !result.success() {
+            span.fail(result.status.label());
+        }
+        drop(span);
+        self.update_progress(|p| p.finish(i, result.status, result.duration));
+        if self.emits_events() {
+            let fields = serde_json::json!({
+                "status": result.status,
+                "exit_code": result.exit_code,
+                "duration_ms": result.duration.as_millis(),
+            });
+            self.event("repo_finished", i, fields);
+        }
+
+        if self.config.fail_fast
+            && matches!(result.status, RepoStatus::Failed | RepoStatus::TimedOut)
+        {
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+        if self.prints_output() && !self.config.dry_run {
+            self.report_finished(i, &result);
+        }
+        result
+    }
+
+    fn execute_into(&self, i: usize, result: &mut RepoResult) {
+        let dir = self.working_dir(i);
+        if !dir.exists() {
+            result.status = RepoStatus::Failed;
+            result.exit_code = Some(1);
+            result.stderr = format!("Directory does not exist: {}\n", dir.display());
+            return;
+        }
+        if let Err(e) = &self.dir_configs[i] {
+            result.status = RepoStatus::Failed;
+            result.exit_code = Some(1);
+            result.stderr = format!("{e}\n");
+            return;
+        }
+
+        if self.config.dry_run {
+            if self.prints_output() {
+                println!(
+                    "{} Would execute in {}:\n  {}",
+                    "[DRY RUN]".cyan(),
+                    dir.display().to_string().yellow(),
+                    result.command
+                );
+            }
+            result.status = RepoStatus::Ok;
+            result.exit_code = Some(0);
+            return;
+        }
+
+        if self.config.verbose && self.prints_output() {
+            self.emit_line(i, StreamKind::Stderr, &format!("$ {}", result.command));
+        }
+
+        let stdin = match &self.config.stdin {
+            StdinMode::Null => Stdio::null(),
+            StdinMode::Inherit => Stdio::inherit(),
+            StdinMode::File(path) => match std::fs::File::open(path) {
+                Ok(file) => Stdio::from(file),
+                Err(e) => {
+                    result.status = RepoStatus::Failed;
+                    result.exit_code = Some(1);
+                    result.stderr = format!("Failed to open {}: {e}\n", path.display());
+                    return;
+                }
+            },
+            StdinMode::Tee => Stdio::piped(),
+        };
+
+        let (shell, shell_args) = self.shell(i);
+        let mut cmd = Command::new(&shell);
+        cmd.args(&shell_args)
+            .arg(&result.command)
+            .current_dir(&dir)
+            .stdin(stdin);
+        // A group of its own lets signals, timeouts and cancellation reach
+        // whatever the command started. Terminal-sharing commands stay in
+        // the foreground group so they can still use the terminal.
+        #[cfg(unix)]
+        if self.own_group() {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        for env in self.env_layers(i) {
+            cmd.envs(env);
+        }
+
+        if self.inherits_terminal() {
+            let (out, err) = if self.config.silent {
+                (Stdio::null(), Stdio::null())
+            } else {
+                if self.prints_output() {
+                    println!();
+                }
+                (Stdio::inherit(), Stdio::inherit())
+            };
+            cmd.stdout(out).stderr(err);
+        } else {
+            cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                result.status = RepoStatus::Failed;
+                result.exit_code = Some(1);
+                result.stderr = format!("Failed to spawn {shell}: {e}\n");
+                return;
+            }
+        };
+
+        let start = Instant::now();
+        let child_stdin = child.stdin.take();
+        let child_stdout = child.stdout.take();
+        let child_stderr = child.stderr.take();
+        let exit = std::thread::scope(|s| {
+            if let (Some(mut stdin), Some(input)) = (child_stdin, &self.tee_input) {
+                s.spawn(move || {
+                    // A command that exits without reading it all is fine
+                    let _ = stdin.write_all(input);
+                });
+            }
+            let out = child_stdout.map(|r| s.spawn(move || self.collect(i, StreamKind::Stdout, r)));
+            let err = child_stderr.map(|r| s.spawn(move || self.collect(i, StreamKind::Stderr, r)));
+            let exit = self.wait(&mut child, start);
+            result.stdout = out.and_then(|h| h.join().ok()).unwrap_or_default();
+            result.stderr = err.and_then(|h| h.join().ok()).unwrap_or_default();
+            exit
+        });
+
+        match exit {
+            Exit::Code(code) => {
+                result.exit_code = Some(code);
+                result.status = if code == 0 {
+                    RepoStatus::Ok
+                } else {
+                    RepoStatus::Failed
+                };
+            }
+            Exit::Cancelled => result.status = RepoStatus::Cancelled,
+            Exit::Interrupted => {
+                result.status = RepoStatus::Cancelled;
+                result.stderr.push_str("Interrupted\n");
+            }
+            Exit::TimedOut => {
+                result.status = RepoStatus::TimedOut;
+                if let Some(timeout) = self.config.timeout {
+                    result
+                        .stderr
+                        .push_str(&format!("Timed out after {}\n", format_duration(timeout)));
+                }
+            }
+        }
+    }
+
+    /// Ask whether to run command `i` under `--confirm`. Quitting cancels
+    /// the rest of the run; a prompt that cannot be read counts as quitting.
+    fn confirmed(&self, i: usize, command: &str) -> bool {
+        if !self.config.confirm || self.config.dry_run || self.confirmed_all.load(Ordering::SeqCst)
+        {
+            return true;
+        }
+        let _guard = self.output_lock.lock().unwrap_or_else(|e| e.into_inner());
+        eprint!(
+            "{} {}\n  {}\nRun? [y/N/a/q] ",
+            "?".cyan().bold(),
+            self.names[i].bold(),
+            command
+        );
+        let _ = std::io::stderr().flush();
+        let mut input = String::new();
+        let answer = match std::io::stdin().lock().read_line(&mut input) {
+            Ok(n) if n > 0 => Answer::parse(&input),
+            _ => Answer::Quit,
+        };
+        match answer {
+            Answer::Yes => true,
+            Answer::No => false,
+            Answer::All => {
+                self.confirmed_all.store(true, Ordering::SeqCst);
+                true
+            }
+            Answer::Quit => {
+                self.cancelled.store(true, Ordering::SeqCst);
+                false
+            }
+        }
+    }
+
+    /// Whether commands are spawned in a process group of their own.
+    /// Commands reading meta's stdin stay in the foreground group too, or
+    /// reading from the terminal would stop them.
+    fn own_group(&self) -> bool {
+        let shares_terminal = self.inherits_terminal() || self.config.stdin == StdinMode::Inherit;
+        !shares_terminal || self.config.timeout.is_some()
+    }
+
+    /// Wait for the child, killing it if the run is cancelled or the
+    /// timeout expires meanwhile. After an interrupt the child gets the
+    /// signal and [`INTERRUPT_GRACE`] to exit before it is killed.
+    fn wait(&self, child: &mut Child, start: Instant) -> Exit {
+        let mut interrupted_at: Option<Instant> = None;
+        loop {
+            match child.try_wait() {
+                // Take down anything it left behind holding our pipes
+                Ok(Some(_)) if interrupted_at.is_some() => {
+                    kill_group(child);
+                    return Exit::Interrupted;
+                }
+                Ok(Some(status)) => return Exit::Code(status.code().unwrap_or(-1)),
+                Ok(None) => {}
+                Err(_) => return Exit::Code(-1),
+            }
+            if let Some(at) = interrupted_at {
+                if at.elapsed() >= INTERRUPT_GRACE || interrupt_count() > 1 {
+                    kill_group(child);
+                    let _ = child.wait();
+                    return Exit::Interrupted;
+                }
+            } else if let Some(signal) = interrupted() {
+                forward_signal(child, signal, self.own_group());
+                interrupted_at = Some(Instant::now());
+            }
+            if self.cancelled.load(Ordering::SeqCst) {
+                kill_group(child);
+                let _ = child.wait();
+                return Exit::Cancelled;
+            }
+            if self.config.timeout.is_some_and(|t| start.elapsed() >= t) {
+                kill_group(child);
+                let _ = child.wait();
+                return Exit::TimedOut;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Read a child stream to completion, echoing lines live in stream mode.
+    fn collect<R: Read>(&self, i: usize, kind: StreamKind, reader: R) -> String {
+        let live = (self.config.output == OutputMode::Stream && !self.config.silent)
+            || self.emits_events()
+            || self.progress.is_some();
+        let mut captured = String::new();
+        let mut reader = BufReader::new(reader);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buf);
+                    if live {
+                        self.emit_line(i, kind, line.trim_end_matches(['\n', '\r']));
+                    }
+                    captured.push_str(&line);
+                }
+            }
+        }
+        captured
+    }
+
+    /// Print one prefixed line to the matching terminal stream, or an
+    /// output event in NDJSON mode.
+    fn emit_line(&self, i: usize, kind: StreamKind, line: &str) {
+        if self.progress.is_some() {
+            self.update_progress(|p| p.line(i, line));
+            return;
+        }
+        if self.emits_events() {
+            let event = match kind {
+                StreamKind::Stdout => "stdout_line",
+                StreamKind::Stderr => "stderr_line",
+            };
+            self.event(event, i, serde_json::json!({ "line": line }));
+            return;
+        }
+        let color = PREFIX_COLORS[i % PREFIX_COLORS.len()];
+        let prefix = format_prefix(&self.names[i], self.width).color(color);
+        let _guard = self.output_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = match kind {
+            StreamKind::Stdout => writeln!(std::io::stdout().lock(), "{prefix} {line}"),
+            StreamKind::Stderr => writeln!(std::io::stderr().lock(), "{prefix} {line}"),
+        };
+    }
+
+    /// Per-repo completion output for grouped mode.
+    fn report_finished(&self, i: usize, result: &RepoResult) {
+        if self.config.output != OutputMode::Grouped {
+            return;
+        }
+        let _guard = self.output_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let name = &self.names[i];
+
+        if self.inherits_terminal() {
+            match (result.status, result.exit_code) {
+                (RepoStatus::Ok, _) => println!("{}", format!("\n✓ {name}").green()),
+                (RepoStatus::TimedOut, _) => {
+                    println!("{}", format!("\n✗ {name}: timed out").yellow())
+                }
+                (_, Some(code)) => println!("{}", format!("\n✗ {name}: exited code {code}").red()),
+                _ => println!("{}", format!("\n✗ {name}").red()),
+            }
+            return;
+        }
+
+        let has_output = !result.stdout.trim().is_empty() || !result.stderr.trim().is_empty();
+        if !has_output {
+            return;
+        }
+        if result.success() {
+            println!("{} {}:", "✓".green(), name.green());
+        } else {
+            println!("{} {}:", "✗".red(), name.red());
+        }
+        print!("{}", result.stdout);
+        eprint!("{}", result.stderr);
+        println!();
+    }
+}
+
+/// Which output stream a line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamKind {
+    Stdout,
+    Stderr,
+}
+
+/// Left-aligned prefix column, e.g. `"api    |"`.
+fn format_prefix(name: &str, width: usize) -> String {
+    format!("{name:<width$} |")
+}
+
+/// Write an NDJSON event as one line on stdout.
+fn write_event(name: &str, fields: serde_json::Value) {
+    let mut out = std::io::stdout().lock();
+    let _ = writeln!(out, "{}", event_line(name, fields));
+    let _ = out.flush();
+}
+
+/// `{"event": name, ...fields}` on a single line.
+fn event_line(name: &str, fields: serde_json::Value) -> String {
+    let mut event = serde_json::Map::new();
+    event.insert("event".to_string(), name.into());
+    if let serde_json::Value::Object(fields) = fields {
+        event.extend(fields);
+    }
+    serde_json::Value::Object(event).to_string()
+}
+
+/// Kill a child and everything it started: on Unix the process group it
+/// leads, on Windows its process tree.
+pub(crate) fn kill_group(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill(2) has no memory-safety preconditions
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    #[cfg(windows)]
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    let _ = child.kill();
+}
+
+// ── Interrupts ──────────────────────────────────────────
+
+/// Number of SIGINT/SIGTERM received, and the most recent one.
+static INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+static LAST_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Trap SIGINT and SIGTERM so a run can wind down instead of dying and
+/// orphaning its commands. Safe to call more than once.
+fn install_interrupt_handler() {
+    #[cfg(unix)]
+    {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            extern "C" fn on_signal(signal: libc::c_int) {
+                LAST_SIGNAL.store(signal, Ordering::SeqCst);
+                INTERRUPTS.fetch_add(1, Ordering::SeqCst);
+            }
+            let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            // SAFETY: the handler only touches atomics, which is async-signal-safe
+            unsafe {
+                libc::signal(libc::SIGINT, handler);
+                libc::signal(libc::SIGTERM, handler);
+            }
+        });
+    }
+}
+
+/// The signal that interrupted the run, if any.
+pub fn interrupted() -> Option<i32> {
+    (interrupt_count() > 0).then(|| LAST_SIGNAL.load(Ordering::SeqCst))
+}
+
+fn interrupt_count() -> usize {
+    INTERRUPTS.load(Ordering::SeqCst)
+}
+
+/// Pass an interrupt on to a running command: to its whole group when it
+/// has one. A Ctrl-C already reached terminal-sharing commands, so only
+/// other signals are forwarded to them.
+fn forward_signal(child: &Child, signal: i32, own_group: bool) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill(2) has no memory-safety preconditions
+        unsafe {
+            if own_group {
+                libc::kill(-pid, signal);
+            } else if signal != libc::SIGINT {
+                libc::kill(pid, signal);
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (child, signal, own_group);
+}
+
+/// Parse a duration such as `"90s"`, `"5m"`, `"1h"` or `"500ms"`. A bare
+/// number is seconds.
+pub fn parse_duration(spec: &str) -> Option<Duration> {
+    let spec = spec.trim();
+    let split = spec
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(spec.len());
+    let (number, unit) = spec.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|d| !d.is_zero())
+}
+
+/// Compact human duration, e.g. `"350ms"` or `"4.2s"`.
+fn format_duration(d: Duration) -> String {
+    if d < Duration::from_secs(1) {
+        format!("{}ms", d.as_millis())
+    } else {
+        format!("{:.1}s", d.as_secs_f64())
+    }
+}
+
+/// Replace the first word of a command with its `.looprc` alias, if any.
+fn resolve_alias(command: &str, aliases: &HashMap<String, String>) -> String {
+    command
+        .split_whitespace()
+        .next()
+        .and_then(|first| {
+            aliases
+                .get(first)
+                .map(|alias| command.replacen(first, alias, 1))
+        })
+        .unwrap_or_else(|| command.to_string())
+}
+
+/// Program and leading arguments the command string is passed to.
+fn shell_for(config: &RunConfig) -> (String, Vec<String>) {
+    match config.shell.as_deref() {
+        Some([program, args @ ..]) => (program.clone(), args.to_vec()),
+        _ => {
+            let (shell, flag) = shell_and_flag();
+            (shell, vec![flag.to_string()])
+        }
+    }
+}
+
+/// Parse a shell spec such as `"bash -lc"` into program and flags. A bare
+/// program gets the platform's command flag (`-c`, or `/c` on Windows).
+pub fn parse_shell(spec: &str) -> Option<Vec<String>> {
+    let mut parts: Vec<String> = spec.split_whitespace().map(str::to_string).collect();
+    match parts.len() {
+        0 => None,
+        1 => {
+            parts.push(shell_and_flag().1.to_string());
+            Some(parts)
+        }
+        _ => Some(parts),
+    }
+}
+
+/// Shell used to run commands: `$SHELL -c` on Unix, `%COMSPEC% /c` on Windows.
+pub(crate) fn shell_and_flag() -> (String, &'static str) {
+    #[cfg(windows)]
+    {
+        (
+            std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string()),
+            "/c",
+        )
+    }
+    #[cfg(not(windows))]
+    {
+        (
+            std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string()),
+            "-c",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Captured, non-printing config so tests can inspect output via results.
+    fn quiet() -> RunConfig {
+        RunConfig {
+            output: OutputMode::Json,
+            ..Default::default()
+        }
+    }
+
+    fn dir_cmd(dir: &Path, cmd: &str) -> DirCommand {
+        DirCommand {
+            dir: dir.display().to_string(),
+            cmd: cmd.to_string(),
+            env: None,
+        }
+    }
+
+    fn make_dirs(tmp: &TempDir, names: &[&str]) -> Vec<PathBuf> {
+        names
+            .iter()
+            .map(|n| {
+                let p = tmp.path().join(n);
+                std::fs::create_dir(&p).unwrap();
+                p
+            })
+            .collect()
+    }
+
+    #[test]
+    fn display_name_marks_root() {
+        let root = PathBuf::from("/work/meta");
+        assert_eq!(display_name(&root, Some(&root)), ". (meta)");
+        assert_eq!(display_name(&root.join("api"), Some(&root)), "api");
+        assert_eq!(display_name(&root.join("api"), None), "api");
+    }
+
+    #[test]
+    fn expand_directories_replaces_plain_dirs_with_their_repos() {
+        let tmp = TempDir::new().unwrap();
+        for repo in ["api", "vendor/libs/a", "vendor/libs/b/deep"] {
+            std::fs::create_dir_all(tmp.path().join(repo).join(".git")).unwrap();
+        }
+        let commands = vec![
+            dir_cmd(&tmp.path().join("api"), "make"),
+            dir_cmd(&tmp.path().join("vendor"), "make"),
+        ];
+
+        let dirs = |commands: Vec<DirCommand>| -> Vec<String> {
+            commands.into_iter().map(|c| c.dir).collect()
+        };
+        let api = tmp.path().join("api").display().to_string();
+        let vendor = tmp.path().join("vendor").display().to_string();
+        assert_eq!(
+            dirs(expand_directories(commands, 1)),
+            vec![api.clone(), vendor.clone()]
+        );
+
+        let commands = vec![
+            dir_cmd(&tmp.path().join("api"), "make"),
+            dir_cmd(&tmp.path().join("vendor"), "make"),
+        ];
+        let expanded = expand_directories(commands, 3);
+        assert!(expanded.iter().all(|c| c.cmd == "make"));
+        assert_eq!(
+            dirs(expanded),
+            vec![
+                api,
+                tmp.path().join("vendor/libs/a").display().to_string(),
+                tmp.path().join("vendor/libs/b/deep").display().to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_directories_matches_loop_lib_semantics() {
+        let dirs = vec![
+            "/w/api".to_string(),
+            "/w/web".to_string(),
+            "/w/docs".to_string(),
+        ];
+        let include = vec!["api".to_string(), "web".to_string()];
+        let exclude = vec!["web/".to_string()];
+        assert_eq!(
+            filter_directories(&dirs, Some(&include), Some(&exclude)),
+            vec!["/w/api".to_string()]
+        );
+        assert_eq!(filter_directories(&dirs, None, None), dirs);
+    }
+
+    #[test]
+    fn format_prefix_pads_to_width() {
+        assert_eq!(format_prefix("api", 5), "api   |");
+        assert_eq!(format_prefix("backend", 5), "backend |");
+    }
+
+    #[test]
+    fn format_duration_switches_units() {
+        assert_eq!(format_duration(Duration::from_millis(350)), "350ms");
+        assert_eq!(format_duration(Duration::from_millis(4200)), "4.2s");
+    }
+
+    #[test]
+    fn event_line_puts_the_event_name_first() {
+        let line = event_line(
+            "stdout_line",
+            serde_json::json!({"repo": "api", "line": "a \"quoted\" line"}),
+        );
+        assert_eq!(
+            line,
+            r#"{"event":"stdout_line","repo":"api","line":"a \"quoted\" line"}"#
+        );
+    }
+
+    #[test]
+    fn parse_duration_accepts_units() {
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1.5h"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration("5d"), None);
+    }
+
+    #[test]
+    fn resolve_alias_replaces_first_word_only() {
+        let mut aliases = HashMap::new();
+        aliases.insert("gs".to_string(), "git status".to_string());
+        assert_eq!(resolve_alias("gs -s", &aliases), "git status -s");
+        assert_eq!(resolve_alias("echo gs", &aliases), "echo gs");
+    }
+
+    #[test]
+    fn captures_stdout_and_stderr() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["alpha"]);
+        let report = execute(
+            &quiet(),
+            &[dir_cmd(&dirs[0], "echo one; echo two; echo oops >&2")],
+        )
+        .unwrap();
+
+        let result = &report.results[0];
+        assert!(result.success());
+        assert_eq!(result.name, "alpha");
+        assert_eq!(result.stdout, "one\ntwo\n");
+        assert_eq!(result.stderr, "oops\n");
+    }
+
+    #[test]
+    fn timeout_kills_the_command_and_continues() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["slow", "fast"]);
+        let config = RunConfig {
+            timeout: Some(Duration::from_millis(300)),
+            ..quiet()
+        };
+        let start = Instant::now();
+        // The grandchild `sleep` holds the output pipe open unless its whole
+        // process group is killed
+        let report = execute(
+            &config,
+            &[
+                dir_cmd(&dirs[0], "sleep 10; echo done"),
+                dir_cmd(&dirs[1], "echo quick"),
+            ],
+        )
+        .unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(report.results[0].status, RepoStatus::TimedOut);
+        assert!(report.results[0].stderr.contains("Timed out after 300ms"));
+        assert_eq!(report.results[1].status, RepoStatus::Ok);
+        assert!(!report.is_success());
+    }
+
+    #[test]
+    fn continues_past_failures_and_records_exit_codes() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["a", "b", "c"]);
+        for parallel in [false, true] {
+            let config = RunConfig {
+                parallel,
+                ..quiet()
+            };
+            let report = execute(
+                &config,
+                &[
+                    dir_cmd(&dirs[0], "true"),
+                    dir_cmd(&dirs[1], "exit 3"),
+                    dir_cmd(&dirs[2], "true"),
+                ],
+            )
+            .unwrap();
+
+            let names: Vec<&str> = report.results.iter().map(|r| r.name.as_str()).collect();
+            assert_eq!(names, ["a", "b", "c"]);
+            assert_eq!(report.results[1].status, RepoStatus::Failed);
+            assert_eq!(report.results[1].exit_code, Some(3));
+            assert_eq!(report.count(RepoStatus::Ok), 2);
+            assert!(!report.is_success());
+        }
+    }
+
+    #[test]
+    fn fail_fast_skips_remaining_repos() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["a", "b", "c"]);
+        let commands = [
+            dir_cmd(&dirs[0], "exit 1"),
+            dir_cmd(&dirs[1], "true"),
+            dir_cmd(&dirs[2], "true"),
+        ];
+
+        let sequential = RunConfig {
+            fail_fast: true,
+            ..quiet()
+        };
+        let parallel_one_at_a_time = RunConfig {
+            parallel: true,
+            max_parallel: Some(1),
+            ..sequential.clone()
+        };
+        for config in [sequential, parallel_one_at_a_time] {
+            let report = execute(&config, &commands).unwrap();
+            assert_eq!(report.results[0].status, RepoStatus::Failed);
+            assert_eq!(report.count(RepoStatus::Skipped), 2);
+        }
+    }
+
+    #[test]
+    fn fail_fast_cancels_running_repos() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["fast", "slow"]);
+        let config = RunConfig {
+            parallel: true,
+            max_parallel: Some(2),
+            fail_fast: true,
+            ..quiet()
+        };
+        let start = Instant::now();
+        let report = execute(
+            &config,
+            &[
+                dir_cmd(&dirs[0], "sleep 0.3; exit 1"),
+                // exec so the kill reaches the sleeping process itself
+                dir_cmd(&dirs[1], "exec sleep 5"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(report.results[0].status, RepoStatus::Failed);
+        assert_eq!(report.results[1].status, RepoStatus::Cancelled);
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn fail_fast_kills_the_whole_process_tree() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["fast", "server"]);
+        let config = RunConfig {
+            parallel: true,
+            max_parallel: Some(2),
+            fail_fast: true,
+            ..quiet()
+        };
+        let start = Instant::now();
+        let report = execute(
+            &config,
+            &[
+                dir_cmd(&dirs[0], "sleep 0.3; exit 1"),
+                // A grandchild, like the dev server behind `npm run dev`
+                dir_cmd(&dirs[1], "sleep 30 & echo $! > pid; wait"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(report.results[1].status, RepoStatus::Cancelled);
+        // The grandchild held the output pipe; had it survived, so would we
+        assert!(start.elapsed() < Duration::from_secs(10));
+        let pid = std::fs::read_to_string(dirs[1].join("pid")).unwrap();
+        let alive = || {
+            Command::new("kill")
+                .args(["-0", pid.trim()])
+                .stderr(Stdio::null())
+                .status()
+                .unwrap()
+                .success()
+        };
+        // Give init a moment to reap the orphan
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while alive() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert!(!alive());
+    }
+
+    #[test]
+    fn dependencies_start_after_their_prerequisites() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["lib", "api", "web"]);
+        let log = tmp.path().join("log");
+        let append = |name: &str| format!("sleep 0.1; echo {name} >> {}", log.display());
+        let commands = [
+            dir_cmd(&dirs[0], &append("lib")),
+            dir_cmd(&dirs[1], &append("api")),
+            dir_cmd(&dirs[2], &append("web")),
+        ];
+        let config = RunConfig {
+            parallel: true,
+            max_parallel: Some(3),
+            ..quiet()
+        };
+
+        let report =
+            execute_with_dependencies(&config, &commands, &[vec![], vec![0], vec![0, 1]]).unwrap();
+        assert!(report.is_success());
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "lib\napi\nweb\n");
+    }
+
+    #[test]
+    fn failed_dependency_skips_dependents_only() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["lib", "api", "web", "docs"]);
+        let commands = [
+            dir_cmd(&dirs[0], "exit 1"),
+            dir_cmd(&dirs[1], "true"),
+            dir_cmd(&dirs[2], "true"),
+            dir_cmd(&dirs[3], "true"),
+        ];
+
+        let report =
+            execute_with_dependencies(&quiet(), &commands, &[vec![], vec![0], vec![1], vec![]])
+                .unwrap();
+        let statuses: Vec<RepoStatus> = report.results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                RepoStatus::Failed,
+                RepoStatus::Skipped,
+                RepoStatus::Skipped,
+                RepoStatus::Ok
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_shell_receives_the_command() {
+        let tmp = TempDir::new().unwrap();
+        let config = RunConfig {
+            shell: Some(vec!["sh".into(), "-c".into(), "echo \"via:$0\"".into()]),
+            ..quiet()
+        };
+        let report = execute(&config, &[dir_cmd(tmp.path(), "ignored-arg")]).unwrap();
+        assert_eq!(report.results[0].stdout, "via:ignored-arg\n");
+    }
+
+    #[test]
+    fn passthrough_exit_code_is_the_commands_own() {
+        let tmp = TempDir::new().unwrap();
+        let report = execute(&quiet(), &[dir_cmd(tmp.path(), "exit 7")]).unwrap();
+        assert_eq!(passthrough_exit_code(&report), 7);
+
+        let config = RunConfig {
+            timeout: Some(Duration::from_millis(100)),
+            ..quiet()
+        };
+        let report = execute(&config, &[dir_cmd(tmp.path(), "sleep 5")]).unwrap();
+        assert_eq!(passthrough_exit_code(&report), TIMEOUT_EXIT_CODE);
+
+        let report = execute(&quiet(), &[dir_cmd(&tmp.path().join("nope"), "true")]).unwrap();
+        assert_eq!(passthrough_exit_code(&report), 1);
+    }
+
+    #[test]
+    fn stdin_file_is_read_afresh_by_every_command() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["api", "web"]);
+        let input = tmp.path().join("input.txt");
+        std::fs::write(&input, "hello\n").unwrap();
+        let config = RunConfig {
+            stdin: StdinMode::File(input),
+            ..quiet()
+        };
+        let commands = [dir_cmd(&dirs[0], "cat"), dir_cmd(&dirs[1], "cat")];
+        let report = execute(&config, &commands).unwrap();
+        assert!(report.results.iter().all(|r| r.stdout == "hello\n"));
+
+        let config = RunConfig {
+            stdin: StdinMode::File(tmp.path().join("missing")),
+            ..quiet()
+        };
+        let report = execute(&config, &commands[..1]).unwrap();
+        assert!(report.results[0].stderr.starts_with("Failed to open"));
+    }
+
+    #[test]
+    fn workdir_is_joined_onto_the_repo() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["web", "api"]);
+        std::fs::create_dir(dirs[0].join("frontend")).unwrap();
+        let commands = [dir_cmd(&dirs[0], "pwd"), dir_cmd(&dirs[1], "pwd")];
+        let config = RunConfig {
+            workdirs: HashMap::from([(commands[0].dir.clone(), PathBuf::from("frontend"))]),
+            ..quiet()
+        };
+
+        let report = execute(&config, &commands).unwrap();
+        assert!(report.results[0].stdout.trim_end().ends_with("frontend"));
+        assert!(report.results[1].stdout.trim_end().ends_with("api"));
+        assert_eq!(report.results[0].directory, dirs[0]);
+    }
+
+    #[test]
+    fn confirm_answers_default_to_no() {
+        assert_eq!(Answer::parse("y\n"), Answer::Yes);
+        assert_eq!(Answer::parse(" All "), Answer::All);
+        assert_eq!(Answer::parse("q"), Answer::Quit);
+        assert_eq!(Answer::parse("\n"), Answer::No);
+        assert_eq!(Answer::parse("maybe"), Answer::No);
+    }
+
+    #[test]
+    fn parse_stdin_modes() {
+        assert_eq!(StdinMode::parse("inherit"), Some(StdinMode::Inherit));
+        assert_eq!(StdinMode::parse("tee"), Some(StdinMode::Tee));
+        assert_eq!(
+            StdinMode::parse("file:in.txt"),
+            Some(StdinMode::File(PathBuf::from("in.txt")))
+        );
+        assert_eq!(StdinMode::parse("file:"), None);
+        assert_eq!(StdinMode::parse("pipe"), None);
+    }
+
+    #[test]
+    fn parse_shell_adds_default_flag() {
+        assert_eq!(
+            parse_shell("bash -lc"),
+            Some(vec!["bash".to_string(), "-lc".to_string()])
+        );
+        assert_eq!(parse_shell("zsh").unwrap().len(), 2);
+        assert_eq!(parse_shell("  "), None);
+    }
+
+    #[test]
+    fn missing_directory_is_reported_as_failure() {
+        let tmp = TempDir::new().unwrap();
+        let report = execute(&quiet(), &[dir_cmd(&tmp.path().join("nope"), "true")]).unwrap();
+        assert_eq!(report.results[0].status, RepoStatus::Failed);
+        assert!(report.results[0].stderr.contains("does not exist"));
+    }
+
+    #[test]
+    fn dry_run_does_not_execute() {
+        let tmp = TempDir::new().unwrap();
+        let config = RunConfig {
+            dry_run: true,
+            ..quiet()
+        };
+        let report = execute(&config, &[dir_cmd(tmp.path(), "touch marker")]).unwrap();
+        assert!(report.is_success());
+        assert!(!tmp.path().join("marker").exists());
+    }
+
+    #[test]
+    fn plan_resolves_argv_and_env_without_running() {
+        let tmp = TempDir::new().unwrap();
+        let mut command = dir_cmd(tmp.path(), "touch 'x y'");
+        command.env = Some(HashMap::from([("B".to_string(), "2".to_string())]));
+        let config = RunConfig {
+            dry_run: true,
+            shell: Some(vec!["bash".into(), "-lc".into()]),
+            env: Some(HashMap::from([("A".to_string(), "1".to_string())])),
+            root_dir: Some(tmp.path().to_path_buf()),
+            ..quiet()
+        };
+        let missing = dir_cmd(&tmp.path().join("gone"), "true");
+
+        let planned = plan(&config, &[command, missing]);
+        assert_eq!(planned[0].argv, vec!["bash", "-lc", "touch 'x y'"]);
+        assert_eq!(planned[0].env, vec!["A", "B"]);
+        assert!(planned[0].name.starts_with(". ("));
+        assert!(planned[0].exists);
+        assert!(!planned[1].exists);
+        assert!(!tmp.path().join("x y").exists());
+    }
+
+    #[test]
+    fn shell_quote_only_quotes_when_needed() {
+        assert_eq!(shell_quote("cargo"), "cargo");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn env_is_passed_to_commands() {
+        let tmp = TempDir::new().unwrap();
+        let mut env = HashMap::new();
+        env.insert("META_RUNNER_TEST".to_string(), "hello".to_string());
+        let config = RunConfig {
+            env: Some(env),
+            ..quiet()
+        };
+        let report = execute(&config, &[dir_cmd(tmp.path(), "echo $META_RUNNER_TEST")]).unwrap();
+        assert_eq!(report.results[0].stdout, "hello\n");
+    }
+
+    #[test]
+    fn json_summary_counts_statuses() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["a", "b", "c"]);
+        let config = RunConfig {
+            fail_fast: true,
+            ..quiet()
+        };
+        let report = execute(
+            &config,
+            &[
+                dir_cmd(&dirs[0], "true"),
+                dir_cmd(&dirs[1], "exit 2"),
+                dir_cmd(&dirs[2], "true"),
+            ],
+        )
+        .unwrap();
+
+        let json = serde_json::to_value(json_output(&report, false)).unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["summary"]["succeeded"], 1);
+        assert_eq!(json["summary"]["failed"], 1);
+        assert_eq!(json["summary"]["skipped"], 1);
+        assert_eq!(json["results"][1]["exit_code"], 2);
+        assert_eq!(json["results"][2]["status"], "skipped");
+        assert!(json["results"][2].get("exit_code").is_none());
+    }
+
+    #[test]
+    fn looprc_sets_aliases_env_and_skips_per_directory() {
+        let tmp = TempDir::new().unwrap();
+        let dirs = make_dirs(&tmp, &["alpha", "beta", "gamma"]);
+        std::fs::write(
+            dirs[0].join(".looprc"),
+            r#"{"aliases": {"hi": "echo alpha"}, "env": {"WHO": "looprc"}}"#,
+        )
+        .unwrap();
+        std::fs::write(dirs[1].join(".looprc"), r#"{"skip": ["hi"]}"#).unwrap();
+        std::fs::write(dirs[2].join(".looprc"), r#"{"shell": 1}"#).unwrap();
+
+        let commands: Vec<DirCommand> = dirs.iter().map(|d| dir_cmd(d, "hi $WHO")).collect();
+        let report = execute(&quiet(), &commands).unwrap();
+
+        assert_eq!(report.results[0].status, RepoStatus::Ok);
+        assert_eq!(report.results[0].command, "echo alpha $WHO");
+        assert_eq!(report.results[0].stdout.trim(), "alpha looprc");
+        assert_eq!(report.results[1].status, RepoStatus::Skipped);
+        assert_eq!(report.results[2].status, RepoStatus::Failed);
+        assert!(report.results[2].stderr.contains("Invalid"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn forwarded_signals_reach_the_whole_group() {
+        use std::os::unix::process::CommandExt;
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 30 & wait"])
+            .stdout(Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+
+        forward_signal(&child, libc::SIGTERM, true);
+        assert!(!child.wait().unwrap().success());
+        // EOF only arrives once the backgrounded sleep is gone too
+        let mut rest = String::new();
+        stdout.read_to_string(&mut rest).unwrap();
+    }
+
+    #[test]
+    fn summary_matrix_lists_every_repo() {
+        let result = |name: &str, status, exit_code, ms| RepoResult {
+            directory: PathBuf::from(name),
+            name: name.to_string(),
+            command: "make".to_string(),
+            status,
+            exit_code,
+            duration: Duration::from_millis(ms),
+            stdout: "hidden".to_string(),
+            stderr: String::new(),
+        };
+        let report = RunReport {
+            results: vec![
+                result("api", RepoStatus::Ok, Some(0), 310),
+                result("frontend", RepoStatus::Failed, Some(2), 1200),
+                result("docs", RepoStatus::Skipped, None, 0),
+                result("web", RepoStatus::TimedOut, None, 10_000),
+            ],
+        };
+        assert_eq!(
+            summary_matrix(&report),
+            "REPO      RESULT   EXIT  TIME\n\
+             api       ok       0     310ms\n\
+             frontend  fail     2     1.2s\n\
+             docs      skip     -     -\n\
+             web       timeout  -     10.0s\n"
+        );
+    }
+}
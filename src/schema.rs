@@ -0,0 +1,1253 @@
+//! Strict schema validation for `.meta` config files.
+//!
+//! `parse_meta_config` is deliberately lenient: unknown keys are dropped and
+//! some malformed values (e.g. `projects` as an array) fail with a single,
+//! location-less serde error. `meta config validate` runs this stricter pass
+//! instead, which collects every problem in one go, points at the line and
+//! column where it occurs, and warns about keys meta does not recognize.
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, ConfigFormat, MetaConfig};
+
+/// Keys recognized at the top level of a `.meta` file.
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "projects",
+    "ignore",
+    "defaults",
+    "worktrees_dir",
+    "tasks",
+    "shell",
+    "env",
+    "worktree",
+    "hooks",
+    "notify",
+    "plugin_priority",
+    "plugin_overrides",
+    "plugin_permissions",
+    "plugins",
+    "policy",
+];
+
+/// Keys recognized in an extended project entry.
+const PROJECT_KEYS: &[&str] = &[
+    "repo",
+    "url",
+    "path",
+    "branch",
+    "tags",
+    "provides",
+    "depends_on",
+    "meta",
+    "workdir",
+];
+
+/// Keys recognized in the `defaults` section.
+const DEFAULTS_KEYS: &[&str] = &["parallel", "jobs", "timeout"];
+
+/// Keys recognized in `worktree.template` and its per-repo entries.
+const TEMPLATE_KEYS: &[&str] = &["copy", "symlink", "hardlink", "shared", "setup"];
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single validation problem.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Dotted key path, e.g. `projects.api.tags`. Empty for document-level problems.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub path: String,
+    pub message: String,
+    /// 1-based line number, when the problem could be located in the source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based column number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta config validate`.
+///
+/// Prints every diagnostic and fails if any error was found (or any warning,
+/// when `strict` is set).
+pub fn handle_validate(config_name: Option<&PathBuf>, json: bool, strict: bool) -> Result<()> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let (config_path, _format) = config::find_meta_config(&cwd, config_name)
+        .ok_or(crate::error::MetaError::ConfigNotFound)?;
+
+    let diagnostics = validate_file(&config_path)?;
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error || strict)
+        .count();
+    let warnings = diagnostics.len() - errors;
+
+    if json {
+        let output = serde_json::json!({
+            "file": config_path.display().to_string(),
+            "valid": errors == 0,
+            "diagnostics": diagnostics,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        let file = config_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for d in &diagnostics {
+            println!("{}", format_diagnostic(&file, d, strict));
+        }
+        if diagnostics.is_empty() {
+            println!("{} {} is valid", "✓".green(), config_path.display());
+        } else {
+            println!(
+                "\n{} {}, {} in {}",
+                if errors > 0 {
+                    "✗".red()
+                } else {
+                    "!".yellow()
+                },
+                plural(errors, "error"),
+                plural(warnings, "warning"),
+                config_path.display()
+            );
+        }
+    }
+
+    if errors > 0 {
+        return Err(crate::error::MetaError::ConfigInvalid.into());
+    }
+    Ok(())
+}
+
+/// Validate a config file on disk.
+pub fn validate_file(path: &Path) -> Result<Vec<Diagnostic>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read meta config file: '{}'", path.display()))?;
+    Ok(validate_str(&text, &config::format_for_path(path)))
+}
+
+/// Validate config text, returning all problems found.
+pub fn validate_str(text: &str, format: &ConfigFormat) -> Vec<Diagnostic> {
+    let value = match parse_with_location(text, format) {
+        Ok(value) => value,
+        Err(diagnostic) => return vec![diagnostic],
+    };
+
+    let mut validator = Validator {
+        source: text,
+        format: *format,
+        diagnostics: Vec::new(),
+    };
+    validator.check_root(&value);
+
+    // Whatever the walk missed, the typed deserialization meta_core uses must accept
+    if !validator.has_errors() {
+        if let Err(e) = serde_json::from_value::<MetaConfig>(value) {
+            validator.error(&[], e.to_string());
+        }
+    }
+    validator.diagnostics
+}
+
+/// Render a diagnostic as `severity: file:line:col: path: message`.
+pub fn format_diagnostic(file: &str, d: &Diagnostic, strict: bool) -> String {
+    let label = match d.severity {
+        Severity::Error => "error".red().bold(),
+        Severity::Warning if strict => "error".red().bold(),
+        Severity::Warning => "warning".yellow().bold(),
+    };
+    let location = match (d.line, d.column) {
+        (Some(line), Some(col)) => format!("{file}:{line}:{col}"),
+        (Some(line), None) => format!("{file}:{line}"),
+        _ => file.to_string(),
+    };
+    if d.path.is_empty() {
+        format!("{label}: {location}: {}", d.message)
+    } else {
+        format!("{label}: {location}: {}: {}", d.path, d.message)
+    }
+}
+
+// ── Parsing ─────────────────────────────────────────────
+
+/// Parse the document, turning syntax errors into a located diagnostic.
+fn parse_with_location(text: &str, format: &ConfigFormat) -> Result<Value, Diagnostic> {
+    let (message, line, column) = match format {
+        ConfigFormat::Json => match serde_json::from_str(text) {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                let message = e.to_string();
+                // serde_json appends " at line N column M"; we report that separately
+                let message = match message.rsplit_once(" at line ") {
+                    Some((head, _)) => head.to_string(),
+                    None => message,
+                };
+                (message, Some(e.line()), Some(e.column()))
+            }
+        },
+        ConfigFormat::Yaml => match serde_yaml::from_str(text) {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                let location = e.location();
+                (
+                    e.to_string(),
+                    location.as_ref().map(|l| l.line()),
+                    location.as_ref().map(|l| l.column()),
+                )
+            }
+        },
+        ConfigFormat::Toml => match toml::from_str(text) {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                let (line, column) = e.span().map(|span| line_col(text, span.start)).unzip();
+                (e.message().to_string(), line, column)
+            }
+        },
+    };
+    Err(Diagnostic {
+        severity: Severity::Error,
+        path: String::new(),
+        message: format!("syntax error: {message}"),
+        line,
+        column,
+    })
+}
+
+// ── Validation ──────────────────────────────────────────
+
+struct Validator<'a> {
+    source: &'a str,
+    format: ConfigFormat,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    fn push(&mut self, severity: Severity, path: &[&str], message: String) {
+        let (line, column) = self.locate(path).unzip();
+        self.diagnostics.push(Diagnostic {
+            severity,
+            path: path.join("."),
+            message,
+            line,
+            column,
+        });
+    }
+
+    fn error(&mut self, path: &[&str], message: String) {
+        self.push(Severity::Error, path, message);
+    }
+
+    fn warning(&mut self, path: &[&str], message: String) {
+        self.push(Severity::Warning, path, message);
+    }
+
+    fn check_root(&mut self, value: &Value) {
+        let Some(root) = value.as_object() else {
+            self.error(
+                &[],
+                format!("config must be an object, found {}", type_name(value)),
+            );
+            return;
+        };
+
+        self.check_unknown_keys(root, &[], TOP_LEVEL_KEYS, "top-level key");
+
+        match root.get("projects") {
+            Some(Value::Object(projects)) => self.check_projects(projects),
+            Some(other) => self.error(
+                &["projects"],
+                format!(
+                    "expected an object mapping project names to entries, found {}",
+                    type_name(other)
+                ),
+            ),
+            None => self.warning(&[], "no 'projects' section".to_string()),
+        }
+
+        if let Some(ignore) = root.get("ignore") {
+            self.check_string_array(&["ignore"], ignore);
+        }
+
+        match root.get("defaults") {
+            Some(Value::Object(defaults)) => {
+                self.check_unknown_keys(defaults, &["defaults"], DEFAULTS_KEYS, "key");
+                if let Some(parallel) = defaults.get("parallel") {
+                    self.check_bool(&["defaults", "parallel"], parallel);
+                }
+                if let Some(jobs) = defaults.get("jobs") {
+                    self.check_positive_int(&["defaults", "jobs"], jobs);
+                }
+                if let Some(timeout) = defaults.get("timeout") {
+                    if crate::config::parse_timeout(timeout).is_none() {
+                        self.error(
+                            &["defaults", "timeout"],
+                            "expected seconds or a duration like \"90s\", \"10m\" or \"1h\""
+                                .to_string(),
+                        );
+                    }
+                }
+            }
+            Some(other) => self.error(
+                &["defaults"],
+                format!("expected an object, found {}", type_name(other)),
+            ),
+            None => {}
+        }
+
+        if let Some(dir) = root.get("worktrees_dir") {
+            self.check_string(&["worktrees_dir"], dir);
+        }
+
+        match root.get("shell") {
+            Some(Value::String(spec)) if spec.trim().is_empty() => {
+                self.error(&["shell"], "shell is empty".to_string())
+            }
+            Some(Value::String(_)) | None => {}
+            Some(Value::Array(items)) if items.is_empty() => self.error(
+                &["shell"],
+                "expected the shell program and its flags, found an empty array".to_string(),
+            ),
+            Some(other) => self.check_string_array(&["shell"], other),
+        }
+
+        match root.get("env") {
+            Some(Value::Object(vars)) => {
+                for (key, value) in vars {
+                    self.check_string(&["env", key.as_str()], value);
+                }
+            }
+            Some(other) => self.error(
+                &["env"],
+                format!(
+                    "expected an object mapping variable names to values, found {}",
+                    type_name(other)
+                ),
+            ),
+            None => {}
+        }
+
+        if let Some(priority) = root.get("plugin_priority") {
+            self.check_string_array(&["plugin_priority"], priority);
+            for item in priority.as_array().into_iter().flatten() {
+                if let Some(name) = item.as_str() {
+                    if !["local", "global", "path"].contains(&name) {
+                        self.error(
+                            &["plugin_priority"],
+                            format!("unknown plugin location \"{name}\" (expected local, global or path)"),
+                        );
+                    }
+                }
+            }
+        }
+
+        match root.get("plugin_overrides") {
+            Some(Value::Object(overrides)) => {
+                for (command, plugin) in overrides {
+                    self.check_string(&["plugin_overrides", command.as_str()], plugin);
+                }
+            }
+            Some(other) => self.error(
+                &["plugin_overrides"],
+                format!(
+                    "expected an object mapping commands to plugins, found {}",
+                    type_name(other)
+                ),
+            ),
+            None => {}
+        }
+
+        match root.get("plugins") {
+            Some(Value::Object(plugins)) => {
+                self.check_unknown_keys(plugins, &["plugins"], &["enabled", "disabled"], "key");
+                for key in ["enabled", "disabled"] {
+                    if let Some(list) = plugins.get(key) {
+                        self.check_string_array(&["plugins", key], list);
+                    }
+                }
+            }
+            Some(other) => self.error(
+                &["plugins"],
+                format!(
+                    "expected an object with enabled and/or disabled lists, found {}",
+                    type_name(other)
+                ),
+            ),
+            None => {}
+        }
+
+        if root.contains_key("plugin_permissions") {
+            if let Err(e) = crate::plugin_permissions::PermissionPolicy::from_value(value) {
+                self.error(&["plugin_permissions"], e.to_string());
+            }
+        }
+
+        if let Err(e) = crate::policy::parse_config(root.get("policy")) {
+            self.error(&["policy"], format!("{e:#}"));
+        }
+
+        match root.get("worktree") {
+            Some(Value::Object(worktree)) => self.check_worktree(worktree),
+            Some(other) => self.error(
+                &["worktree"],
+                format!("expected an object, found {}", type_name(other)),
+            ),
+            None => {}
+        }
+
+        if let Some(hooks) = root.get("hooks") {
+            self.check_hooks(&["hooks"], hooks, crate::hooks::EXEC_EVENTS);
+        }
+
+        match root.get("notify") {
+            Some(Value::Object(notify)) => {
+                self.check_unknown_keys(
+                    notify,
+                    &["notify"],
+                    &["url", "format", "threshold"],
+                    "key",
+                );
+                if let Some(url) = notify.get("url") {
+                    self.check_string(&["notify", "url"], url);
+                }
+                if let Some(format) = notify.get("format") {
+                    // The same parser as --notify, so both accept the same names
+                    let parsed = format.as_str().map(str::parse::<crate::notify::Format>);
+                    if !matches!(parsed, Some(Ok(_))) {
+                        self.error(
+                            &["notify", "format"],
+                            "expected \"slack\" or \"json\"".to_string(),
+                        );
+                    }
+                }
+                if let Some(threshold) = notify.get("threshold") {
+                    if threshold.as_u64() != Some(0)
+                        && crate::config::parse_timeout(threshold).is_none()
+                    {
+                        self.error(
+                            &["notify", "threshold"],
+                            "expected seconds or a duration like \"5m\"".to_string(),
+                        );
+                    }
+                }
+            }
+            Some(other) => self.error(
+                &["notify"],
+                format!("expected an object, found {}", type_name(other)),
+            ),
+            None => {}
+        }
+
+        match root.get("tasks") {
+            Some(Value::Object(tasks)) => {
+                let projects = root.get("projects").and_then(Value::as_object);
+                self.check_tasks(tasks, projects);
+            }
+            Some(other) => self.error(
+                &["tasks"],
+                format!(
+                    "expected an object mapping task names to commands, found {}",
+                    type_name(other)
+                ),
+            ),
+            None => {}
+        }
+    }
+
+    fn check_worktree(&mut self, worktree: &serde_json::Map<String, Value>) {
+        self.check_unknown_keys(
+            worktree,
+            &["worktree"],
+            &["template", "prune", "hooks"],
+            "key",
+        );
+        if let Some(hooks) = worktree.get("hooks") {
+            self.check_hooks(&["worktree", "hooks"], hooks, crate::hooks::WORKTREE_EVENTS);
+        }
+        match worktree.get("prune") {
+            Some(Value::Object(prune)) => {
+                self.check_unknown_keys(
+                    prune,
+                    &["worktree", "prune"],
+                    &["auto", "interval"],
+                    "key",
+                );
+                if let Some(auto) = prune.get("auto") {
+                    self.check_bool(&["worktree", "prune", "auto"], auto);
+                }
+                if let Some(interval) = prune.get("interval") {
+                    if crate::config::parse_timeout(interval).is_none() {
+                        self.error(
+                            &["worktree", "prune", "interval"],
+                            "expected seconds or a duration like \"30m\" or \"6h\"".to_string(),
+                        );
+                    }
+                }
+            }
+            Some(other) => self.error(
+                &["worktree", "prune"],
+                format!("expected an object, found {}", type_name(other)),
+            ),
+            None => {}
+        }
+        let Some(template) = worktree.get("template") else {
+            return;
+        };
+        let Value::Object(template) = template else {
+            self.error(
+                &["worktree", "template"],
+                format!("expected an object, found {}", type_name(template)),
+            );
+            return;
+        };
+
+        let mut known = TEMPLATE_KEYS.to_vec();
+        known.extend(["clone_mode", "repos"]);
+        self.check_unknown_keys(template, &["worktree", "template"], &known, "key");
+        self.check_template_lists(&["worktree", "template"], template);
+
+        if let Some(mode) = template.get("clone_mode") {
+            if !matches!(mode.as_str(), Some("copy" | "hardlink" | "reflink")) {
+                self.error(
+                    &["worktree", "template", "clone_mode"],
+                    format!("expected \"copy\", \"hardlink\" or \"reflink\", found {mode}"),
+                );
+            }
+        }
+
+        match template.get("repos") {
+            Some(Value::Object(repos)) => {
+                for (name, entry) in repos {
+                    let path = ["worktree", "template", "repos", name.as_str()];
+                    match entry {
+                        Value::Object(entry) => {
+                            self.check_unknown_keys(entry, &path, TEMPLATE_KEYS, "key");
+                            self.check_template_lists(&path, entry);
+                        }
+                        other => self.error(
+                            &path,
+                            format!("expected an object, found {}", type_name(other)),
+                        ),
+                    }
+                }
+            }
+            Some(other) => self.error(
+                &["worktree", "template", "repos"],
+                format!(
+                    "expected an object mapping project names to entries, found {}",
+                    type_name(other)
+                ),
+            ),
+            None => {}
+        }
+    }
+
+    fn check_hooks(&mut self, path: &[&str], hooks: &Value, events: &[&str]) {
+        let Value::Object(hooks) = hooks else {
+            self.error(
+                path,
+                format!("expected an object, found {}", type_name(hooks)),
+            );
+            return;
+        };
+        self.check_unknown_keys(hooks, path, events, "hook event");
+        for (event, spec) in hooks {
+            if let Err(e) = crate::hooks::parse_hook(spec) {
+                let mut path = path.to_vec();
+                path.push(event);
+                self.error(&path, format!("{e:#}"));
+            }
+        }
+    }
+
+    fn check_template_lists(&mut self, parent: &[&str], entry: &serde_json::Map<String, Value>) {
+        for key in TEMPLATE_KEYS {
+            if let Some(value) = entry.get(*key) {
+                let mut path = parent.to_vec();
+                path.push(key);
+                self.check_string_array(&path, value);
+            }
+        }
+    }
+
+    fn check_tasks(
+        &mut self,
+        tasks: &serde_json::Map<String, Value>,
+        projects: Option<&serde_json::Map<String, Value>>,
+    ) {
+        for (name, task) in tasks {
+            match task {
+                Value::String(_) => {}
+                Value::Object(entries) => {
+                    for (key, command) in entries {
+                        if !(command.is_string() || command.is_null()) {
+                            self.error(
+                                &["tasks", name, key],
+                                format!(
+                                    "expected a command string or null, found {}",
+                                    type_name(command)
+                                ),
+                            );
+                        }
+                        let known = key == crate::tasks::DEFAULT_KEY
+                            || key == crate::tasks::ROOT_KEY
+                            || projects.is_none_or(|p| p.contains_key(key));
+                        if !known {
+                            self.warning(
+                                &["tasks", name, key],
+                                format!("'{key}' is not a project (overrides use project names)"),
+                            );
+                        }
+                    }
+                }
+                other => self.error(
+                    &["tasks", name],
+                    format!(
+                        "expected a command string or an object, found {}",
+                        type_name(other)
+                    ),
+                ),
+            }
+        }
+    }
+
+    fn check_projects(&mut self, projects: &serde_json::Map<String, Value>) {
+        let mut paths: HashMap<String, &str> = HashMap::new();
+        let mut provided: HashSet<&str> = projects.keys().map(String::as_str).collect();
+
+        for (name, entry) in projects {
+            let path = match entry {
+                Value::String(url) => {
+                    if url.trim().is_empty() {
+                        self.error(&["projects", name], "repo URL is empty".to_string());
+                    }
+                    name.clone()
+                }
+                Value::Object(fields) => {
+                    self.check_project_fields(name, fields);
+                    for item in fields
+                        .get("provides")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                    {
+                        provided.insert(item);
+                    }
+                    fields
+                        .get("path")
+                        .and_then(Value::as_str)
+                        .unwrap_or(name)
+                        .to_string()
+                }
+                other => {
+                    self.error(
+                        &["projects", name],
+                        format!(
+                            "expected a repo URL string or a project object, found {}",
+                            type_name(other)
+                        ),
+                    );
+                    continue;
+                }
+            };
+
+            let normalized = path.replace('\\', "/").trim_end_matches('/').to_string();
+            if let Some(previous) = paths.insert(normalized.clone(), name) {
+                self.error(
+                    &["projects", name],
+                    format!("path '{normalized}' is already used by project '{previous}'"),
+                );
+            }
+        }
+
+        // Dependencies must name a project or something a project provides
+        for (name, entry) in projects {
+            let deps = entry
+                .get("depends_on")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str);
+            for dep in deps {
+                if !provided.contains(dep) {
+                    self.warning(
+                        &["projects", name, "depends_on"],
+                        format!("'{dep}' is not a project and is not provided by any project"),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_project_fields(&mut self, name: &str, fields: &serde_json::Map<String, Value>) {
+        self.check_unknown_keys(fields, &["projects", name], PROJECT_KEYS, "project key");
+
+        for key in ["repo", "url", "path", "branch", "workdir"] {
+            if let Some(value) = fields.get(key) {
+                self.check_string(&["projects", name, key], value);
+            }
+        }
+        if let Some(workdir) = fields.get("workdir").and_then(Value::as_str) {
+            if let Err(e) = crate::workdir::parse(workdir) {
+                self.error(&["projects", name, "workdir"], e.to_string());
+            }
+        }
+        if let (Some(repo), Some(url)) = (fields.get("repo"), fields.get("url")) {
+            if repo != url {
+                self.warning(
+                    &["projects", name, "url"],
+                    "differs from 'repo', which takes precedence".to_string(),
+                );
+            }
+        }
+        for key in ["tags", "provides", "depends_on"] {
+            if let Some(value) = fields.get(key) {
+                self.check_string_array(&["projects", name, key], value);
+            }
+        }
+        if let Some(meta) = fields.get("meta") {
+            self.check_bool(&["projects", name, "meta"], meta);
+        }
+
+        if let Some(path) = fields.get("path").and_then(Value::as_str) {
+            if Path::new(path).is_absolute() {
+                self.warning(
+                    &["projects", name, "path"],
+                    "absolute paths make the workspace non-portable; use a path relative to the .meta file"
+                        .to_string(),
+                );
+            }
+        }
+    }
+
+    fn check_unknown_keys(
+        &mut self,
+        map: &serde_json::Map<String, Value>,
+        parent: &[&str],
+        known: &[&str],
+        what: &str,
+    ) {
+        for key in map.keys() {
+            if known.contains(&key.as_str()) {
+                continue;
+            }
+            let hint = suggest(key, known)
+                .map(|s| format!(" (did you mean '{s}'?)"))
+                .unwrap_or_default();
+            let mut path = parent.to_vec();
+            path.push(key);
+            self.warning(&path, format!("unknown {what} '{key}'{hint}"));
+        }
+    }
+
+    fn check_string(&mut self, path: &[&str], value: &Value) {
+        if !value.is_string() {
+            self.error(
+                path,
+                format!("expected a string, found {}", type_name(value)),
+            );
+        }
+    }
+
+    fn check_bool(&mut self, path: &[&str], value: &Value) {
+        if !value.is_boolean() {
+            self.error(
+                path,
+                format!("expected true or false, found {}", type_name(value)),
+            );
+        }
+    }
+
+    fn check_positive_int(&mut self, path: &[&str], value: &Value) {
+        match value.as_u64() {
+            Some(n) if n > 0 => {}
+            Some(_) => self.error(path, "must be at least 1".to_string()),
+            None => self.error(
+                path,
+                format!("expected a positive integer, found {}", type_name(value)),
+            ),
+        }
+    }
+
+    fn check_string_array(&mut self, path: &[&str], value: &Value) {
+        match value {
+            Value::Array(items) => {
+                if let Some(bad) = items.iter().find(|v| !v.is_string()) {
+                    self.error(
+                        path,
+                        format!(
+                            "expected an array of strings, but it contains {}",
+                            type_name(bad)
+                        ),
+                    );
+                }
+            }
+            other => self.error(
+                path,
+                format!("expected an array of strings, found {}", type_name(other)),
+            ),
+        }
+    }
+
+    /// Find the (line, column) of the last key in `path` by scanning the
+    /// source for each key in turn.
+    ///
+    /// This is a heuristic rather than a real span lookup, but config files are
+    /// small and keys are distinctive enough that it points at the right line.
+    fn locate(&self, path: &[&str]) -> Option<(usize, usize)> {
+        let mut offset = 0;
+        let mut found = None;
+        for key in path {
+            let pattern = match self.format {
+                ConfigFormat::Json => format!(r#""{}"\s*:"#, regex::escape(key)),
+                ConfigFormat::Yaml => {
+                    format!(r#"(?m)^[ \t-]*["']?{}["']?\s*:"#, regex::escape(key))
+                }
+                // Either `key = ...` or a segment of a `[table.header]`
+                ConfigFormat::Toml => format!(
+                    r#"(?m)^[ \t]*["']?{0}["']?\s*=|[\[.]["']?{0}["']?[\].]"#,
+                    regex::escape(key)
+                ),
+            };
+            let re = regex::Regex::new(&pattern).ok()?;
+            match re.find_at(self.source, offset) {
+                Some(m) => {
+                    // Point at the key itself, not leading indentation
+                    let indent = m
+                        .as_str()
+                        .find(|c: char| !c.is_whitespace() && !matches!(c, '-' | '[' | '.'));
+                    let start = m.start() + indent.unwrap_or(0);
+                    // A TOML header delimiter (`[projects.api]`) also starts the next segment
+                    offset = match self.format {
+                        ConfigFormat::Toml => m.end() - 1,
+                        _ => m.end(),
+                    };
+                    found = Some(start);
+                }
+                None => break,
+            }
+        }
+        found.map(|pos| line_col(self.source, pos))
+    }
+}
+
+/// 1-based (line, column) of a byte offset.
+fn line_col(source: &str, pos: usize) -> (usize, usize) {
+    let before = &source[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rfind('\n').map_or(pos, |nl| pos - nl - 1) + 1;
+    (line, column)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Closest known key within a small edit distance, for typo hints.
+fn suggest<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|k| (edit_distance(key, k), *k))
+        .filter(|(d, _)| *d <= 2)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+fn plural(n: usize, word: &str) -> String {
+    if n == 1 {
+        format!("{n} {word}")
+    } else {
+        format!("{n} {word}s")
+    }
+}
+
+// ── Tests ───────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(text: &str) -> Vec<Diagnostic> {
+        validate_str(text, &ConfigFormat::Json)
+    }
+
+    fn yaml(text: &str) -> Vec<Diagnostic> {
+        validate_str(text, &ConfigFormat::Yaml)
+    }
+
+    #[test]
+    fn valid_config_has_no_diagnostics() {
+        let diags = json(
+            r#"{
+  "projects": {
+    "api": "git@github.com:org/api.git",
+    "web": {"repo": "git@github.com:org/web.git", "tags": ["frontend"], "depends_on": ["api"]},
+    "docs": {"url": "git@github.com:org/docs.git"}
+  },
+  "ignore": [".git"],
+  "defaults": {"parallel": false, "jobs": 4}
+}"#,
+        );
+        assert!(diags.is_empty(), "{diags:?}");
+    }
+
+    #[test]
+    fn jobs_must_be_a_positive_integer() {
+        for bad in ["0", "-2", "\"4\"", "1.5"] {
+            let diags = json(&format!(
+                r#"{{"projects": {{}}, "defaults": {{"jobs": {bad}}}}}"#
+            ));
+            assert_eq!(diags.len(), 1, "{bad}");
+            assert_eq!(diags[0].path, "defaults.jobs");
+            assert_eq!(diags[0].severity, Severity::Error);
+        }
+    }
+
+    #[test]
+    fn timeout_must_be_a_duration() {
+        for good in ["30", r#""90s""#, r#""10m""#] {
+            let text = format!(r#"{{"projects": {{}}, "defaults": {{"timeout": {good}}}}}"#);
+            assert!(json(&text).is_empty(), "{good}");
+        }
+        for bad in ["0", r#""soon""#, "true"] {
+            let diags = json(&format!(
+                r#"{{"projects": {{}}, "defaults": {{"timeout": {bad}}}}}"#
+            ));
+            assert_eq!(diags.len(), 1, "{bad}");
+            assert_eq!(diags[0].path, "defaults.timeout");
+        }
+    }
+
+    #[test]
+    fn worktree_template_lists_are_checked() {
+        let good = r#"{"projects": {}, "worktree": {"template": {
+            "copy": [".env"], "setup": ["npm ci"], "clone_mode": "reflink",
+            "repos": {"api": {"hardlink": ["node_modules"], "shared": ["target"]}}
+        }}}"#;
+        assert!(json(good).is_empty());
+
+        let diags = json(
+            r#"{"projects": {}, "worktree": {"template": {
+                "copy": ".env", "clone_mode": "symlink", "repos": {"api": {"setpu": []}}
+            }}}"#,
+        );
+        assert_eq!(diags.len(), 3);
+        assert_eq!(diags[0].path, "worktree.template.copy");
+        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(diags[1].path, "worktree.template.clone_mode");
+        assert_eq!(diags[2].path, "worktree.template.repos.api.setpu");
+        assert!(diags[2].message.contains("did you mean 'setup'"));
+    }
+
+    #[test]
+    fn worktree_hooks_are_checked() {
+        let good = r#"{"projects": {}, "worktree": {"hooks": {
+            "pre-destroy": "check-prs",
+            "post-create": {"run": ["a", "b"], "timeout": "30s"}
+        }}}"#;
+        assert!(json(good).is_empty());
+
+        let diags = json(
+            r#"{"projects": {}, "worktree": {"hooks": {"pre-destory": "x", "post-create": 1}}}"#,
+        );
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].path, "worktree.hooks.pre-destory");
+        assert!(diags[0].message.contains("did you mean 'pre-destroy'"));
+        assert_eq!(diags[1].path, "worktree.hooks.post-create");
+        assert_eq!(diags[1].severity, Severity::Error);
+    }
+
+    #[test]
+    fn exec_hooks_are_checked() {
+        let good =
+            r#"{"projects": {}, "hooks": {"pre-exec": "prime-cache", "post-exec": ["notify"]}}"#;
+        assert!(json(good).is_empty());
+        let diags = json(r#"{"projects": {}, "hooks": {"post-create": "x"}}"#);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].path, "hooks.post-create");
+        assert!(diags[0].message.contains("unknown hook event"));
+    }
+
+    #[test]
+    fn notify_is_checked() {
+        let good =
+            r#"{"projects": {}, "notify": {"url": "https://example.com", "threshold": "5m"}}"#;
+        assert!(json(good).is_empty());
+        let diags = json(r#"{"projects": {}, "notify": {"format": "email", "threshold": "soon"}}"#);
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].path, "notify.format");
+        assert_eq!(diags[1].path, "notify.threshold");
+        assert_eq!(diags[1].severity, Severity::Error);
+    }
+
+    #[test]
+    fn project_workdir_must_stay_inside_the_repo() {
+        assert!(json(r#"{"projects": {"web": {"repo": "x", "workdir": "frontend"}}}"#).is_empty());
+        let diags = json(r#"{"projects": {"web": {"repo": "x", "workdir": "../api"}}}"#);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].path, "projects.web.workdir");
+    }
+
+    #[test]
+    fn policy_is_checked() {
+        let good =
+            r#"{"projects": {}, "policy": {"rules": [{"match": "rm -rf", "action": "deny"}]}}"#;
+        assert!(json(good).is_empty());
+        let diags =
+            json(r#"{"projects": {}, "policy": {"rules": [{"regex": "(", "action": "deny"}]}}"#);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].path, "policy");
+        assert!(diags[0].message.starts_with("rules[0]: invalid regex"));
+    }
+
+    #[test]
+    fn worktree_prune_schedule_is_checked() {
+        let good = r#"{"projects": {}, "worktree": {"prune": {"auto": true, "interval": "6h"}}}"#;
+        assert!(json(good).is_empty());
+
+        let diags = json(
+            r#"{"projects": {}, "worktree": {"prune": {"auto": "yes", "interval": "daily"}}}"#,
+        );
+        let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, ["worktree.prune.auto", "worktree.prune.interval"]);
+    }
+
+    #[test]
+    fn plugin_policy_keys_are_checked() {
+        let good = r#"{"projects": {}, "plugin_priority": ["global", "local"],
+            "plugin_overrides": {"worktree": "worktree"}}"#;
+        assert!(json(good).is_empty());
+
+        let diags = json(
+            r#"{"projects": {}, "plugin_priority": ["cloud"], "plugin_overrides": {"git": 1}}"#,
+        );
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].path, "plugin_priority");
+        assert!(diags[0].message.contains("cloud"));
+        assert_eq!(diags[1].path, "plugin_overrides.git");
+
+        let good = r#"{"projects": {}, "plugin_permissions": {"allow": {"mig": ["env"]}}}"#;
+        assert!(json(good).is_empty());
+        let diags = json(r#"{"projects": {}, "plugin_permissions": {"mode": "ask"}}"#);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].path, "plugin_permissions");
+        assert!(diags[0].message.contains("prompt"));
+
+        let good = r#"{"projects": {}, "plugins": {"enabled": ["git"], "disabled": ["deploy"]}}"#;
+        assert!(json(good).is_empty());
+        let diags = json(r#"{"projects": {}, "plugins": {"disabled": "deploy", "allow": []}}"#);
+        assert_eq!(diags.len(), 2);
+        assert!(diags.iter().any(|d| d.path == "plugins.disabled"));
+        assert!(diags.iter().any(|d| d.path == "plugins.allow"));
+    }
+
+    #[test]
+    fn shell_must_be_a_string_or_non_empty_array() {
+        for good in [r#""bash -lc""#, r#"["zsh", "-c"]"#] {
+            assert!(json(&format!(r#"{{"projects": {{}}, "shell": {good}}}"#)).is_empty());
+        }
+        for bad in ["[]", r#""""#, "42", r#"["bash", 1]"#] {
+            let diags = json(&format!(r#"{{"projects": {{}}, "shell": {bad}}}"#));
+            assert_eq!(diags.len(), 1, "{bad}");
+            assert_eq!(diags[0].path, "shell");
+            assert_eq!(diags[0].severity, Severity::Error);
+        }
+    }
+
+    #[test]
+    fn env_must_map_names_to_strings() {
+        assert!(json(r#"{"projects": {}, "env": {"RUST_LOG": "info"}}"#).is_empty());
+        let diags = json(r#"{"projects": {}, "env": {"PORT": 8080}}"#);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].path, "env.PORT");
+        let diags = json(r#"{"projects": {}, "env": ["RUST_LOG=info"]}"#);
+        assert_eq!(diags[0].path, "env");
+    }
+
+    #[test]
+    fn tasks_are_validated() {
+        let diags = json(
+            r#"{
+  "projects": {"api": "git@x:api.git"},
+  "tasks": {
+    "build": {"default": "make", "api": "cargo build", ".": null, "wbe": "npm"},
+    "lint": ["eslint"],
+    "test": {"api": 3}
+  }
+}"#,
+        );
+        let found: Vec<(&str, Severity)> = diags
+            .iter()
+            .map(|d| (d.path.as_str(), d.severity))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("tasks.build.wbe", Severity::Warning),
+                ("tasks.lint", Severity::Error),
+                ("tasks.test.api", Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn projects_as_array_is_an_error_with_location() {
+        let diags = json("{\n  \"projects\": [\"api\"]\n}");
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(diags[0].path, "projects");
+        assert_eq!((diags[0].line, diags[0].column), (Some(2), Some(3)));
+    }
+
+    #[test]
+    fn reports_all_problems_at_once() {
+        let diags = json(
+            r#"{
+  "projects": {
+    "api": {"tags": "backend", "meta": "yes"},
+    "web": 42
+  },
+  "ignore": "node_modules"
+}"#,
+        );
+        let paths: Vec<&str> = diags.iter().map(|d| d.path.as_str()).collect();
+        assert!(paths.contains(&"projects.api.tags"));
+        assert!(paths.contains(&"projects.api.meta"));
+        assert!(paths.contains(&"projects.web"));
+        assert!(paths.contains(&"ignore"));
+        assert!(diags.iter().all(|d| d.severity == Severity::Error));
+    }
+
+    #[test]
+    fn unknown_keys_are_warnings_with_suggestions() {
+        let diags = json(r#"{"projcts": {}, "projects": {"api": {"rep": "x"}}}"#);
+        assert_eq!(diags.len(), 2);
+        assert!(diags.iter().all(|d| d.severity == Severity::Warning));
+        assert!(diags[0].message.contains("did you mean 'projects'"));
+        assert!(diags[1].message.contains("did you mean 'repo'"));
+    }
+
+    #[test]
+    fn syntax_errors_carry_line_and_column() {
+        let diags = json("{\n  \"projects\": {,}\n}");
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.starts_with("syntax error"));
+        assert!(!diags[0].message.contains(" at line "));
+        assert_eq!(diags[0].line, Some(2));
+
+        let diags = yaml("projects:\n  api: [unclosed\n");
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].line.is_some());
+    }
+
+    #[test]
+    fn yaml_errors_are_located() {
+        let diags = yaml("projects:\n  api:\n    repo: x\n    tags: backend\n");
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].path, "projects.api.tags");
+        assert_eq!((diags[0].line, diags[0].column), (Some(4), Some(5)));
+    }
+
+    #[test]
+    fn toml_errors_are_located() {
+        let toml = |text| validate_str(text, &ConfigFormat::Toml);
+
+        let diags = toml("[projects.api]\nrepo = \"x\"\ntags = \"backend\"\n");
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].path, "projects.api.tags");
+        assert_eq!((diags[0].line, diags[0].column), (Some(3), Some(1)));
+
+        let diags = toml("[projects]\napi = \n");
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.starts_with("syntax error"));
+        assert_eq!(diags[0].line, Some(2));
+    }
+
+    #[test]
+    fn duplicate_paths_are_errors() {
+        let diags = json(r#"{"projects": {"a": {"path": "shared"}, "b": {"path": "shared/"}}}"#);
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.contains("already used by project 'a'"));
+    }
+
+    #[test]
+    fn unknown_dependencies_are_warnings() {
+        let diags = json(
+            r#"{"projects": {
+                "api": {"provides": ["rest-api"]},
+                "web": {"depends_on": ["rest-api", "missing"]}
+            }}"#,
+        );
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, Severity::Warning);
+        assert!(diags[0].message.contains("'missing'"));
+    }
+
+    #[test]
+    fn non_object_root_is_an_error() {
+        let diags = json("[1, 2]");
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.contains("must be an object"));
+    }
+
+    #[test]
+    fn format_diagnostic_includes_location() {
+        let d = Diagnostic {
+            severity: Severity::Error,
+            path: "projects".to_string(),
+            message: "bad".to_string(),
+            line: Some(2),
+            column: Some(3),
+        };
+        let text = format_diagnostic(".meta", &d, false);
+        assert!(text.contains(".meta:2:3: projects: bad"));
+    }
+
+    #[test]
+    fn suggest_finds_close_keys_only() {
+        assert_eq!(suggest("projcts", TOP_LEVEL_KEYS), Some("projects"));
+        assert_eq!(suggest("zzz", TOP_LEVEL_KEYS), None);
+    }
+}
@@ -0,0 +1,140 @@
+//! `meta exec --script <file>`: run a local script in every repo.
+//!
+//! Multi-line maintenance tasks don't fit well in one quoted command, so
+//! the script is copied to a temporary file for the length of the run and
+//! each repo runs that copy, from the repo's directory:
+//!
+//! ```text
+//! meta exec --script scripts/bump-deps.sh -- --minor
+//! ```
+//!
+//! A `#!` line picks the interpreter (`#!/usr/bin/env python3` runs
+//! `/usr/bin/env python3 <copy> --minor`); without one the script runs with
+//! `sh`. Like the kernel, everything after the interpreter is passed as one
+//! argument. Working on a copy means a script that lives in one of the
+//! repos keeps running the same code even if a repo's command changes it.
+//! The copy is only readable by the current user.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+
+use crate::runner::shell_quote;
+
+/// Interpreter for scripts without a `#!` line.
+pub const DEFAULT_INTERPRETER: &str = "sh";
+
+/// A script copied to a temporary file, removed again on drop.
+#[derive(Debug)]
+pub struct Script {
+    file: tempfile::NamedTempFile,
+    interpreter: Vec<String>,
+}
+
+impl Script {
+    /// Copy the script at `source` to a temporary file and work out its
+    /// interpreter.
+    pub fn prepare(source: &Path) -> Result<Script> {
+        let contents = std::fs::read(source)
+            .with_context(|| format!("Failed to read script {}", source.display()))?;
+        let name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "script".to_string());
+        // Created exclusively with mode 0600, so no one else can swap it out
+        let mut file = tempfile::Builder::new()
+            .prefix("meta-script-")
+            .suffix(&format!("-{name}"))
+            .tempfile()
+            .context("Failed to create a temporary copy of the script")?;
+        file.write_all(&contents)
+            .and_then(|()| file.flush())
+            .with_context(|| format!("Failed to copy script to {}", file.path().display()))?;
+        Ok(Script {
+            file,
+            interpreter: interpreter(&String::from_utf8_lossy(&contents)),
+        })
+    }
+
+    /// The command each repo runs: interpreter, script copy, then `args`.
+    pub fn command(&self, args: &[String]) -> String {
+        let path = self.file.path().display().to_string();
+        self.interpreter
+            .iter()
+            .chain(std::iter::once(&path))
+            .chain(args)
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The interpreter named by the script's `#!` line, or
+/// [`DEFAULT_INTERPRETER`]. As on Linux, the rest of the line after the
+/// program is a single argument, spaces and all.
+pub fn interpreter(contents: &str) -> Vec<String> {
+    let Some(shebang) = contents
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("#!"))
+        .map(str::trim)
+        .filter(|shebang| !shebang.is_empty())
+    else {
+        return vec![DEFAULT_INTERPRETER.to_string()];
+    };
+    match shebang.split_once([' ', '\t']) {
+        Some((program, arg)) => vec![program.to_string(), arg.trim().to_string()],
+        None => vec![shebang.to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shebang_picks_the_interpreter() {
+        assert_eq!(
+            interpreter("#!/usr/bin/env python3\nprint('hi')\n"),
+            ["/usr/bin/env", "python3"]
+        );
+        assert_eq!(interpreter("#! /bin/bash -eu\n"), ["/bin/bash", "-eu"]);
+        assert_eq!(
+            interpreter("#!/usr/bin/env -S deno run  \r\n"),
+            ["/usr/bin/env", "-S deno run"]
+        );
+        assert_eq!(interpreter("#!/bin/sh\n"), ["/bin/sh"]);
+        assert_eq!(interpreter("echo hi\n"), ["sh"]);
+        assert_eq!(interpreter("#!\necho hi\n"), ["sh"]);
+        assert_eq!(interpreter(""), ["sh"]);
+    }
+
+    #[test]
+    fn copy_runs_with_arguments_and_is_removed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("bump.sh");
+        std::fs::write(&source, "#!/bin/bash -e\necho \"$1\"\n").unwrap();
+
+        let script = Script::prepare(&source).unwrap();
+        let copy = script.file.path().to_path_buf();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&copy).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(
+            std::fs::read_to_string(&copy).unwrap(),
+            "#!/bin/bash -e\necho \"$1\"\n"
+        );
+        assert_eq!(
+            script.command(&["a b".to_string()]),
+            format!(
+                "/bin/bash -e {} 'a b'",
+                shell_quote(&copy.display().to_string())
+            )
+        );
+        drop(script);
+        assert!(!copy.exists());
+    }
+}
@@ -0,0 +1,690 @@
+//! Workspace snapshots for `meta git snapshot`.
+//!
+//! A snapshot records, for every repo, the commit and branch it is on and
+//! any uncommitted work (tracked and untracked, minus ignored files). The
+//! work is saved as a stash-like commit whose parent is `HEAD`, built with a
+//! throwaway index so the working tree and the real index are untouched.
+//! Each repo keeps that commit under `refs/meta/snapshots/<name>` so git
+//! never collects it; the snapshot itself is a JSON file in
+//! `~/.meta/snapshots/<name>.json`.
+//!
+//! Restoring stashes whatever changed since, moves each repo back to its
+//! branch and commit, and puts the saved work back in the working tree,
+//! unstaged. A branch that gained commits the snapshot doesn't have keeps
+//! its old tip under `refs/meta/snapshot-backups/<name>/<branch>`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::git::{self, RepoResult, Target};
+use crate::git_utils;
+
+/// Ref namespace holding each repo's snapshot commits.
+const REF_PREFIX: &str = "refs/meta/snapshots/";
+
+/// Ref namespace keeping branch tips that a restore moved back.
+const BACKUP_PREFIX: &str = "refs/meta/snapshot-backups/";
+
+// ── Types ───────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub created: DateTime<Utc>,
+    /// Root of the workspace the snapshot was taken in
+    pub workspace: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub repos: Vec<RepoSnapshot>,
+}
+
+/// State of one repo at snapshot time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepoSnapshot {
+    pub name: String,
+    pub path: PathBuf,
+    pub head: String,
+    /// `None` for a detached HEAD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Commit holding the uncommitted work, if there was any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stash: Option<String>,
+}
+
+/// Storage directory: `~/.meta/snapshots/`.
+pub fn snapshots_dir() -> PathBuf {
+    meta_core::meta_dir().join("snapshots")
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta git snapshot <create|list|restore|delete>`.
+pub fn handle_snapshot(
+    args: &[String],
+    targets: &[Target],
+    workspace: &Path,
+    json: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let dir = snapshots_dir();
+    let usage = "Usage: meta git snapshot <create [name] [-m <message>] | list | restore <name> | delete <name>>";
+    let (sub, rest) = args.split_first().context(usage)?;
+    match sub.as_str() {
+        "create" => {
+            let mut name = None;
+            let mut message = None;
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "-m" | "--message" => {
+                        message = Some(iter.next().context("--message needs a value")?.clone())
+                    }
+                    other if name.is_none() && !other.starts_with('-') => {
+                        name = Some(other.to_string())
+                    }
+                    other => anyhow::bail!("Unexpected argument '{other}'\n{usage}"),
+                }
+            }
+            let name =
+                name.unwrap_or_else(|| Utc::now().format("snapshot-%Y%m%d-%H%M%S").to_string());
+            let (snapshot, results) = create(&dir, &name, workspace, targets, message)?;
+            report(&results, json)?;
+            if !json {
+                println!(
+                    "\nSaved snapshot '{}' ({} repos)",
+                    snapshot.name,
+                    snapshot.repos.len()
+                );
+            }
+            Ok(())
+        }
+        "list" => {
+            let snapshots: Vec<Snapshot> = list(&dir)?
+                .into_iter()
+                .filter(|s| s.workspace == workspace)
+                .collect();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&snapshots)?);
+            } else if snapshots.is_empty() {
+                println!("No snapshots for this workspace");
+            } else {
+                print!("{}", format_list(&snapshots));
+            }
+            Ok(())
+        }
+        "restore" => {
+            let name = positional(rest).context(usage)?;
+            let snapshot = load(&dir, name)?;
+            anyhow::ensure!(
+                snapshot.workspace == workspace,
+                "Snapshot '{name}' belongs to the workspace at {}, not {}",
+                snapshot.workspace.display(),
+                workspace.display()
+            );
+            report(&restore(&snapshot, dry_run), json)
+        }
+        "delete" => {
+            let name = positional(rest).context(usage)?;
+            let snapshot = delete(&dir, name)?;
+            if json {
+                println!("{}", serde_json::json!({ "deleted": snapshot.name }));
+            } else {
+                println!("Deleted snapshot '{}'", snapshot.name);
+            }
+            Ok(())
+        }
+        other => anyhow::bail!("Unknown snapshot command '{other}'\n{usage}"),
+    }
+}
+
+/// Take a snapshot of `targets` and save it as `dir/<name>.json`.
+pub fn create(
+    dir: &Path,
+    name: &str,
+    workspace: &Path,
+    targets: &[Target],
+    message: Option<String>,
+) -> Result<(Snapshot, Vec<RepoResult>)> {
+    validate_name(name)?;
+    let path = snapshot_path(dir, name);
+    anyhow::ensure!(!path.exists(), "A snapshot named '{name}' already exists");
+
+    let mut snapshot = Snapshot {
+        name: name.to_string(),
+        created: Utc::now(),
+        workspace: workspace.to_path_buf(),
+        message,
+        repos: Vec::new(),
+    };
+    let mut results = Vec::new();
+    for (repo, repo_path) in targets {
+        let mut result = result_for(repo, repo_path);
+        // Nothing to save in repos that are not cloned
+        if !repo_path.join(".git").exists() {
+            result.success = true;
+            result.summary = "skipped (not a git repository)".to_string();
+            results.push(result);
+            continue;
+        }
+        match capture(name, repo, repo_path) {
+            Ok(saved) => {
+                result.success = true;
+                result.summary = match &saved.stash {
+                    Some(_) => format!("saved {} with local changes", short(&saved.head)),
+                    None => format!("saved {}", short(&saved.head)),
+                };
+                snapshot.repos.push(saved);
+            }
+            Err(e) => result.summary = format!("{e:#}"),
+        }
+        results.push(result);
+    }
+
+    let failed: Vec<String> = results
+        .iter()
+        .filter(|r| !r.success)
+        .map(|r| format!("{} ({})", r.name, r.summary))
+        .collect();
+    if !failed.is_empty() {
+        // Leave nothing behind for a snapshot that is not saved
+        for saved in &snapshot.repos {
+            let _ = git_utils::git(&saved.path, &["update-ref", "-d", &snapshot_ref(name)]);
+        }
+        anyhow::bail!("Could not snapshot {}", failed.join(", "));
+    }
+    meta_core::store::write_atomic(&path, &snapshot)?;
+    Ok((snapshot, results))
+}
+
+/// All saved snapshots, oldest first.
+pub fn list(dir: &Path) -> Result<Vec<Snapshot>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            match meta_core::store::read::<Snapshot>(&path) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => log::warn!("Skipping unreadable snapshot: {e:#}"),
+            }
+        }
+    }
+    snapshots.sort_by(|a, b| a.created.cmp(&b.created).then(a.name.cmp(&b.name)));
+    Ok(snapshots)
+}
+
+pub fn load(dir: &Path, name: &str) -> Result<Snapshot> {
+    validate_name(name)?;
+    let path = snapshot_path(dir, name);
+    anyhow::ensure!(path.exists(), "No snapshot named '{name}'");
+    meta_core::store::read(&path)
+}
+
+/// Put every repo in `snapshot` back the way it was. Uncommitted changes
+/// made since are stashed first (`git stash list` shows them), and branch
+/// tips with commits the snapshot lacks are kept under a backup ref; with
+/// `dry_run` only report what would happen.
+pub fn restore(snapshot: &Snapshot, dry_run: bool) -> Vec<RepoResult> {
+    snapshot
+        .repos
+        .iter()
+        .map(|repo| {
+            let mut result = result_for(&repo.name, &repo.path);
+            let target = match &repo.branch {
+                Some(branch) => format!("{branch} at {}", short(&repo.head)),
+                None => format!("detached {}", short(&repo.head)),
+            };
+            let with_changes = if repo.stash.is_some() {
+                " with local changes"
+            } else {
+                ""
+            };
+            if let Some((branch, tip)) = diverged_branch(repo) {
+                let would = if dry_run { "would be " } else { "" };
+                eprintln!(
+                    "{}: {}: {branch} has commits that are not in the snapshot; its tip {} {would}kept at {}",
+                    "warning".yellow().bold(),
+                    repo.name,
+                    short(&tip),
+                    backup_ref(&snapshot.name, branch)
+                );
+            }
+            if dry_run {
+                let dirty = git_utils::is_dirty(&repo.path).unwrap_or(false);
+                result.success = repo.path.join(".git").exists();
+                result.summary = match (result.success, dirty) {
+                    (false, _) => "repository is missing".to_string(),
+                    (true, true) => {
+                        format!("would stash current changes and restore {target}{with_changes}")
+                    }
+                    (true, false) => format!("would restore {target}{with_changes}"),
+                };
+                return result;
+            }
+            match restore_repo(repo, &snapshot.name) {
+                Ok(stashed) => {
+                    result.success = true;
+                    result.summary = format!("restored {target}{with_changes}");
+                    if stashed {
+                        result.summary.push_str(" (current changes stashed)");
+                    }
+                }
+                Err(e) => result.summary = format!("{e:#}"),
+            }
+            result.branch = git_utils::current_branch(&repo.path);
+            result
+        })
+        .collect()
+}
+
+/// Remove a snapshot and the refs it keeps in each repo.
+pub fn delete(dir: &Path, name: &str) -> Result<Snapshot> {
+    let snapshot = load(dir, name)?;
+    for repo in &snapshot.repos {
+        // The repo may be gone; the snapshot is deleted regardless
+        let _ = git_utils::git(&repo.path, &["update-ref", "-d", &snapshot_ref(name)]);
+    }
+    std::fs::remove_file(snapshot_path(dir, name))
+        .with_context(|| format!("Failed to delete snapshot '{name}'"))?;
+    Ok(snapshot)
+}
+
+/// Snapshots as an aligned table.
+pub fn format_list(snapshots: &[Snapshot]) -> String {
+    let width = snapshots
+        .iter()
+        .map(|s| s.name.chars().count())
+        .chain(std::iter::once(4))
+        .max()
+        .unwrap_or(0);
+    let mut out = format!(
+        "{}\n",
+        format!(
+            "{:<width$}  {:<16}  {:>5}  MESSAGE",
+            "NAME", "CREATED", "REPOS"
+        )
+        .bold()
+    );
+    for s in snapshots {
+        let created = s.created.with_timezone(&chrono::Local);
+        out.push_str(&format!(
+            "{:<width$}  {:<16}  {:>5}  {}\n",
+            s.name,
+            created.format("%Y-%m-%d %H:%M"),
+            s.repos.len(),
+            s.message.as_deref().unwrap_or(""),
+        ));
+    }
+    out
+}
+
+// ── Per-repo Operations ─────────────────────────────────
+
+fn capture(name: &str, repo: &str, path: &Path) -> Result<RepoSnapshot> {
+    let head = git_utils::git(path, &["rev-parse", "--verify", "HEAD"])
+        .map_err(|_| anyhow::anyhow!("no commits yet"))?;
+    let stash = stash_commit(path, &head, name)?;
+    git_utils::git(
+        path,
+        &[
+            "update-ref",
+            &snapshot_ref(name),
+            stash.as_deref().unwrap_or(&head),
+        ],
+    )?;
+    Ok(RepoSnapshot {
+        name: repo.to_string(),
+        path: path.to_path_buf(),
+        head,
+        branch: git_utils::current_branch(path),
+        stash,
+    })
+}
+
+/// Commit the whole working tree on top of `head` through a temporary
+/// index. `None` when there is nothing uncommitted.
+fn stash_commit(path: &Path, head: &str, name: &str) -> Result<Option<String>> {
+    let index = git_utils::git(path, &["rev-parse", "--git-path", "meta-snapshot-index"])?;
+    let index = path.join(index);
+    let with_index = |args: &[&str]| {
+        let mut cmd = git_utils::git_command(path, args);
+        cmd.env("GIT_INDEX_FILE", &index);
+        git_utils::run(cmd)
+    };
+    let tree = with_index(&["read-tree", head])
+        .and_then(|_| with_index(&["add", "-A"]))
+        .and_then(|_| with_index(&["write-tree"]));
+    let _ = std::fs::remove_file(&index);
+    let tree = tree?;
+
+    if tree == git_utils::git(path, &["rev-parse", &format!("{head}^{{tree}}")])? {
+        return Ok(None);
+    }
+    let message = format!("meta snapshot {name}");
+    let mut commit =
+        git_utils::git_command(path, &["commit-tree", &tree, "-p", head, "-m", &message]);
+    // The commit is internal; don't fail where no git identity is set up
+    if git_utils::git(path, &["var", "GIT_COMMITTER_IDENT"]).is_err() {
+        for var in ["GIT_AUTHOR", "GIT_COMMITTER"] {
+            commit.env(format!("{var}_NAME"), "meta");
+            commit.env(format!("{var}_EMAIL"), "meta@localhost");
+        }
+    }
+    git_utils::run(commit).map(Some)
+}
+
+/// The branch `repo` was on and its current tip, when moving the branch
+/// back to the snapshot would drop commits from it.
+fn diverged_branch(repo: &RepoSnapshot) -> Option<(&str, String)> {
+    let branch = repo.branch.as_deref()?;
+    if !repo.path.join(".git").exists() {
+        return None;
+    }
+    let tip = git_utils::git(
+        &repo.path,
+        &[
+            "rev-parse",
+            "--verify",
+            "-q",
+            &format!("refs/heads/{branch}"),
+        ],
+    )
+    .ok()?;
+    let contained = git_utils::git(
+        &repo.path,
+        &["merge-base", "--is-ancestor", &tip, &repo.head],
+    )
+    .is_ok();
+    (!contained).then_some((branch, tip))
+}
+
+/// Restore one repo; true if its current changes had to be stashed.
+fn restore_repo(repo: &RepoSnapshot, name: &str) -> Result<bool> {
+    let path = &repo.path;
+    anyhow::ensure!(path.join(".git").exists(), "repository is missing");
+    if let Some((branch, tip)) = diverged_branch(repo) {
+        git_utils::git(path, &["update-ref", &backup_ref(name, branch), &tip])?;
+    }
+    let stashed = git_utils::is_dirty(path).unwrap_or(false);
+    if stashed {
+        let message = format!("meta: before restoring snapshot {name}");
+        git_utils::git(path, &["stash", "push", "-q", "-u", "-m", &message])?;
+    }
+    git_utils::git(path, &["reset", "--hard", "-q"])?;
+    match &repo.branch {
+        Some(branch) => git_utils::git(path, &["checkout", "-q", "-B", branch, &repo.head])?,
+        None => git_utils::git(path, &["checkout", "-q", "--detach", &repo.head])?,
+    };
+    let Some(stash) = &repo.stash else {
+        return Ok(stashed);
+    };
+    // Bring back the saved files, then unstage them
+    git_utils::git(path, &["checkout", stash, "--", "."])?;
+    git_utils::git(path, &["reset", "-q"])?;
+    let deleted = git_utils::git(
+        path,
+        &["diff", "--name-only", "--diff-filter=D", &repo.head, stash],
+    )?;
+    for file in deleted.lines().filter(|l| !l.is_empty()) {
+        let _ = std::fs::remove_file(path.join(file));
+    }
+    Ok(stashed)
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn report(results: &[RepoResult], json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(results)?);
+    } else {
+        print!("{}", git::format_table(results));
+    }
+    let failed = results.iter().filter(|r| !r.success).count();
+    anyhow::ensure!(failed == 0, "failed in {failed} of {} repos", results.len());
+    Ok(())
+}
+
+fn result_for(name: &str, path: &Path) -> RepoResult {
+    RepoResult {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        success: false,
+        // Only query repo roots, otherwise git reports the enclosing (meta) repo
+        branch: path
+            .join(".git")
+            .exists()
+            .then(|| git_utils::current_branch(path))
+            .flatten(),
+        summary: String::new(),
+        output: String::new(),
+    }
+}
+
+fn positional(args: &[String]) -> Option<&str> {
+    args.iter()
+        .map(String::as_str)
+        .find(|a| !a.starts_with('-'))
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    anyhow::ensure!(
+        valid,
+        "Invalid snapshot name '{name}' (use letters, digits, '-', '_' and '.')"
+    );
+    Ok(())
+}
+
+fn snapshot_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.json"))
+}
+
+fn snapshot_ref(name: &str) -> String {
+    format!("{REF_PREFIX}{name}")
+}
+
+fn backup_ref(name: &str, branch: &str) -> String {
+    format!("{BACKUP_PREFIX}{name}/{branch}")
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_in(dir: &Path, args: &[&str]) {
+        git_utils::git(dir, args).unwrap();
+    }
+
+    /// A repo with `README.md` and `old.txt` committed.
+    fn init_repo(dir: &Path) {
+        git_utils::testing::init_repo(dir);
+        std::fs::write(dir.join("old.txt"), "old\n").unwrap();
+        git_in(dir, &["add", "."]);
+        git_in(dir, &["commit", "-q", "-m", "add old.txt"]);
+    }
+
+    fn target(name: &str, path: &Path) -> Target {
+        (name.to_string(), path.to_path_buf())
+    }
+
+    #[test]
+    fn snapshot_round_trip_restores_branch_commit_and_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = tmp.path().join("store");
+        let api = tmp.path().join("api");
+        let web = tmp.path().join("web");
+        init_repo(&api);
+        init_repo(&web);
+
+        // api has uncommitted work, web is clean
+        std::fs::write(api.join("README.md"), "changed\n").unwrap();
+        std::fs::write(api.join("new.txt"), "new\n").unwrap();
+        std::fs::remove_file(api.join("old.txt")).unwrap();
+        let api_head = git_utils::git(&api, &["rev-parse", "HEAD"]).unwrap();
+
+        let targets = [target("api", &api), target("web", &web)];
+        let (snapshot, results) =
+            create(&store, "before", tmp.path(), &targets, Some("test".into())).unwrap();
+        assert!(results.iter().all(|r| r.success));
+        assert!(snapshot.repos[0].stash.is_some());
+        assert!(snapshot.repos[1].stash.is_none());
+        // Taking the snapshot leaves the working tree alone
+        assert_eq!(
+            std::fs::read_to_string(api.join("README.md")).unwrap(),
+            "changed\n"
+        );
+        assert_eq!(git_utils::dirty_file_count(&api), Some(3));
+
+        // Move api somewhere else entirely
+        git_in(&api, &["checkout", "-q", "-b", "other"]);
+        git_in(&api, &["add", "-A"]);
+        git_in(&api, &["commit", "-q", "-m", "wip"]);
+        std::fs::write(api.join("README.md"), "later\n").unwrap();
+
+        let loaded = load(&store, "before").unwrap();
+        let preview = restore(&loaded, true);
+        assert_eq!(
+            preview[0].summary,
+            format!(
+                "would stash current changes and restore main at {} with local changes",
+                short(&api_head)
+            )
+        );
+        assert_eq!(
+            std::fs::read_to_string(api.join("README.md")).unwrap(),
+            "later\n"
+        );
+
+        let results = restore(&loaded, false);
+        assert!(results.iter().all(|r| r.success), "{results:?}");
+        assert!(results[0].summary.ends_with("(current changes stashed)"));
+        assert_eq!(
+            results[1].summary,
+            format!("restored main at {}", short(&loaded.repos[1].head))
+        );
+
+        assert_eq!(git_utils::current_branch(&api).as_deref(), Some("main"));
+        assert_eq!(
+            git_utils::git(&api, &["rev-parse", "HEAD"]).unwrap(),
+            api_head
+        );
+        assert_eq!(
+            std::fs::read_to_string(api.join("README.md")).unwrap(),
+            "changed\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(api.join("new.txt")).unwrap(),
+            "new\n"
+        );
+        assert!(!api.join("old.txt").exists());
+        // Restored changes are unstaged
+        assert_eq!(
+            git_utils::git(&api, &["diff", "--cached", "--name-only"]).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn restore_keeps_branch_commits_the_snapshot_lacks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = tmp.path().join("store");
+        let api = tmp.path().join("api");
+        init_repo(&api);
+        let (snapshot, _) = create(&store, "s", tmp.path(), &[target("api", &api)], None).unwrap();
+
+        std::fs::write(api.join("README.md"), "newer\n").unwrap();
+        git_in(&api, &["commit", "-q", "-am", "newer"]);
+        let tip = git_utils::git(&api, &["rev-parse", "HEAD"]).unwrap();
+        assert_eq!(
+            diverged_branch(&snapshot.repos[0]),
+            Some(("main", tip.clone()))
+        );
+
+        assert!(restore(&snapshot, true)[0].success);
+        assert!(
+            git_utils::git(&api, &["rev-parse", "--verify", &backup_ref("s", "main")]).is_err()
+        );
+
+        assert!(restore(&snapshot, false)[0].success);
+        assert_eq!(
+            git_utils::git(&api, &["rev-parse", "HEAD"]).unwrap(),
+            snapshot.repos[0].head
+        );
+        assert_eq!(
+            git_utils::git(&api, &["rev-parse", &backup_ref("s", "main")]).unwrap(),
+            tip
+        );
+        // Back at the snapshot, nothing more would be lost
+        assert_eq!(diverged_branch(&snapshot.repos[0]), None);
+    }
+
+    #[test]
+    fn create_refuses_duplicates_and_skips_missing_repos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = tmp.path().join("store");
+        let api = tmp.path().join("api");
+        init_repo(&api);
+
+        create(&store, "one", tmp.path(), &[target("api", &api)], None).unwrap();
+        assert!(create(&store, "one", tmp.path(), &[target("api", &api)], None).is_err());
+        assert!(create(&store, "../x", tmp.path(), &[], None).is_err());
+
+        // Repos that are not cloned are skipped, ones without commits fail
+        let (snapshot, _) = create(
+            &store,
+            "skip",
+            tmp.path(),
+            &[target("api", &api), target("web", &tmp.path().join("web"))],
+            None,
+        )
+        .unwrap();
+        assert_eq!(snapshot.repos.len(), 1);
+
+        let empty = tmp.path().join("empty");
+        std::fs::create_dir(&empty).unwrap();
+        git_in(&empty, &["init", "-q"]);
+        let broken = [target("api", &api), target("empty", &empty)];
+        let err = create(&store, "two", tmp.path(), &broken, None).unwrap_err();
+        assert!(err.to_string().contains("empty (no commits yet)"), "{err}");
+        assert!(!snapshot_path(&store, "two").exists());
+        // The ref for the half-made snapshot is removed again
+        assert!(
+            git_utils::git(&api, &["rev-parse", "--verify", "refs/meta/snapshots/two"]).is_err()
+        );
+    }
+
+    #[test]
+    fn list_and_delete() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = tmp.path().join("store");
+        let api = tmp.path().join("api");
+        init_repo(&api);
+        assert!(list(&store).unwrap().is_empty());
+
+        create(&store, "a", tmp.path(), &[target("api", &api)], None).unwrap();
+        create(&store, "b", tmp.path(), &[target("api", &api)], None).unwrap();
+        let names: Vec<_> = list(&store).unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert!(git_utils::git(&api, &["rev-parse", "--verify", "refs/meta/snapshots/a"]).is_ok());
+
+        delete(&store, "a").unwrap();
+        let names: Vec<_> = list(&store).unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["b"]);
+        assert!(git_utils::git(&api, &["rev-parse", "--verify", "refs/meta/snapshots/a"]).is_err());
+        assert!(delete(&store, "a").is_err());
+    }
+}
@@ -0,0 +1,414 @@
+//! Workspace status dashboard for `meta status`.
+//!
+//! Prints one row per repo with the current branch, ahead/behind counts
+//! against upstream, dirty file count, and the last commit. When run from
+//! inside a worktree set, reports on the set's repos instead of the primary
+//! checkouts (unless `--primary` is given).
+
+use anyhow::{Context, Result};
+use colored::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::git_utils::{self, CommitSummary};
+use crate::worktree;
+
+/// Maximum commit subject length shown in the table before truncation.
+const SUBJECT_WIDTH: usize = 50;
+
+// ── Types ───────────────────────────────────────────────
+
+/// Git state of a single repo.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoStatus {
+    pub name: String,
+    pub path: PathBuf,
+    /// False when the project directory has not been cloned yet.
+    pub exists: bool,
+    /// False when the directory exists but is not a git repo root.
+    pub is_repo: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ahead: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub behind: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_commit: Option<CommitSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Detected toolchain, see [`crate::lang`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+impl RepoStatus {
+    /// Query git for the state of the repo at `path`.
+    pub fn collect(name: &str, path: &Path, tags: &[String]) -> Self {
+        let exists = path.exists();
+        // Only query repo roots, otherwise git reports the enclosing (meta) repo
+        let is_repo = path.join(".git").exists();
+        let (ahead, behind) = if is_repo {
+            git_utils::ahead_behind(path).unzip()
+        } else {
+            (None, None)
+        };
+        RepoStatus {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            exists,
+            is_repo,
+            branch: is_repo.then(|| git_utils::current_branch(path)).flatten(),
+            modified_count: is_repo.then(|| git_utils::dirty_file_count(path)).flatten(),
+            ahead,
+            behind,
+            last_commit: is_repo.then(|| git_utils::last_commit(path)).flatten(),
+            tags: tags.to_vec(),
+            lang: crate::lang::detect(path).map(str::to_string),
+        }
+    }
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Entry point for `meta status`.
+pub fn handle_status(
+    json: bool,
+    tag_filter: Option<&str>,
+    primary: bool,
+    verbose: bool,
+) -> Result<()> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let targets = resolve_targets(&cwd, tag_filter, primary, verbose)?;
+
+    let rows: Vec<RepoStatus> = targets
+        .par_iter()
+        .map(|(name, path, tags)| RepoStatus::collect(name, path, tags))
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else {
+        print!("{}", format_table(&rows));
+        if let Some((config_path, _)) = config::find_meta_config(&cwd, None) {
+            let dirs: Vec<String> = rows.iter().map(|r| r.path.display().to_string()).collect();
+            for warning in crate::branch_check::warnings(&config_path, &dirs) {
+                eprintln!("{warning}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Render rows as an aligned table.
+pub fn format_table(rows: &[RepoStatus]) -> String {
+    let cells: Vec<[String; 6]> = rows
+        .iter()
+        .map(|r| {
+            [
+                r.name.clone(),
+                format_branch(r),
+                r.lang.clone().unwrap_or_else(|| "-".to_string()),
+                format_sync(r),
+                format_changes(r),
+                format_commit(r),
+            ]
+        })
+        .collect();
+
+    let header = ["REPO", "BRANCH", "LANG", "SYNC", "CHANGES", "LAST COMMIT"];
+    let mut widths: [usize; 5] = [0; 5];
+    for (i, width) in widths.iter_mut().enumerate() {
+        *width = cells
+            .iter()
+            .map(|c| c[i].chars().count())
+            .chain(std::iter::once(header[i].len()))
+            .max()
+            .unwrap_or(0);
+    }
+
+    let mut out = String::new();
+    let line = |cols: [&str; 6]| {
+        format!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}  {:<w4$}  {}",
+            cols[0],
+            cols[1],
+            cols[2],
+            cols[3],
+            cols[4],
+            cols[5],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+            w4 = widths[4],
+        )
+    };
+    out.push_str(&line(header).bold().to_string());
+    out.push('\n');
+
+    for (row, c) in rows.iter().zip(&cells) {
+        let text = line([&c[0], &c[1], &c[2], &c[3], &c[4], &c[5]]);
+        let text = if !row.exists {
+            text.red()
+        } else if row.modified_count.unwrap_or(0) > 0 {
+            text.yellow()
+        } else {
+            text.normal()
+        };
+        out.push_str(&text.to_string());
+        out.push('\n');
+    }
+    out
+}
+
+// ── Target Resolution ───────────────────────────────────
+
+/// (name, path, tags) for each repo to report on.
+type Target = (String, PathBuf, Vec<String>);
+
+fn resolve_targets(
+    cwd: &Path,
+    tag_filter: Option<&str>,
+    primary: bool,
+    verbose: bool,
+) -> Result<Vec<Target>> {
+    if !primary {
+        let context = worktree::detect_worktree_context(cwd).or_else(|| {
+            let (config_path, _) = config::find_meta_config(cwd, None)?;
+            worktree::current_worktree_context(config_path.parent()?)
+        });
+        if let Some((task_name, task_dir, _)) = context {
+            if verbose {
+                eprintln!("Detected worktree context: '{task_name}'");
+            }
+            let tags_by_name: HashMap<String, Vec<String>> =
+                config::find_meta_config(&task_dir, None)
+                    .and_then(|(path, _)| config::parse_meta_config(&path).ok())
+                    .map(|(projects, _)| projects.into_iter().map(|p| (p.name, p.tags)).collect())
+                    .unwrap_or_default();
+
+            let targets = worktree::discover_worktree_repos(&task_dir)?
+                .into_iter()
+                .map(|r| {
+                    let tags = tags_by_name.get(&r.alias).cloned().unwrap_or_default();
+                    (r.alias, r.path, tags)
+                })
+                // Repos unknown to the config pass through the tag filter
+                .filter(|(name, path, tags)| {
+                    !tags_by_name.contains_key(name) || matches_lang_or_tags(path, tags, tag_filter)
+                })
+                .collect();
+            return Ok(targets);
+        }
+    }
+
+    let (config_path, _format) =
+        config::find_meta_config(cwd, None).ok_or(crate::error::MetaError::ConfigNotFound)?;
+    let meta_dir = config_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Invalid config path"))?;
+    let (projects, _ignore) = config::parse_meta_config(&config_path)?;
+
+    let mut targets = Vec::new();
+    if tag_filter.is_none() && meta_dir.join(".git").exists() {
+        targets.push((".".to_string(), meta_dir.to_path_buf(), Vec::new()));
+    }
+    targets.extend(
+        projects
+            .into_iter()
+            .map(|p| (p.name, meta_dir.join(&p.path), p.tags))
+            .filter(|(_, path, tags)| matches_lang_or_tags(path, tags, tag_filter)),
+    );
+    Ok(targets)
+}
+
+/// True if no filter was given or any tag is in the comma-separated filter.
+fn matches_tags(tags: &[String], filter: Option<&str>) -> bool {
+    filter.is_none_or(|filter| crate::filter::matches_tags(tags, filter))
+}
+
+/// [`matches_tags`], counting the repo's detected language as a tag.
+fn matches_lang_or_tags(path: &Path, tags: &[String], filter: Option<&str>) -> bool {
+    let mut tags = tags.to_vec();
+    tags.extend(crate::lang::detect(path).map(str::to_string));
+    matches_tags(&tags, filter)
+}
+
+// ── Formatting ──────────────────────────────────────────
+
+fn format_branch(r: &RepoStatus) -> String {
+    match (&r.branch, r.is_repo) {
+        (Some(b), _) => b.clone(),
+        (None, true) => "(detached)".to_string(),
+        (None, false) => "-".to_string(),
+    }
+}
+
+fn format_sync(r: &RepoStatus) -> String {
+    match (r.ahead, r.behind) {
+        (Some(0), Some(0)) => "=".to_string(),
+        (Some(a), Some(0)) => format!("↑{a}"),
+        (Some(0), Some(b)) => format!("↓{b}"),
+        (Some(a), Some(b)) => format!("↑{a} ↓{b}"),
+        _ => "-".to_string(),
+    }
+}
+
+fn format_changes(r: &RepoStatus) -> String {
+    if !r.exists {
+        return "missing".to_string();
+    }
+    if !r.is_repo {
+        return "not a repo".to_string();
+    }
+    match r.modified_count {
+        Some(0) => "clean".to_string(),
+        Some(n) => format!("{n} modified"),
+        None => "-".to_string(),
+    }
+}
+
+fn format_commit(r: &RepoStatus) -> String {
+    match &r.last_commit {
+        Some(c) => {
+            let subject = if c.subject.chars().count() > SUBJECT_WIDTH {
+                let cut: String = c.subject.chars().take(SUBJECT_WIDTH - 1).collect();
+                format!("{cut}…")
+            } else {
+                c.subject.clone()
+            };
+            format!("{} {} ({})", c.sha, subject, c.relative_date)
+        }
+        None => "-".to_string(),
+    }
+}
+
+// ── Tests ───────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str) -> RepoStatus {
+        RepoStatus {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            exists: true,
+            is_repo: true,
+            branch: Some("main".to_string()),
+            modified_count: Some(0),
+            ahead: Some(0),
+            behind: Some(0),
+            last_commit: Some(CommitSummary {
+                sha: "abc1234".to_string(),
+                subject: "Initial commit".to_string(),
+                relative_date: "2 days ago".to_string(),
+            }),
+            tags: vec![],
+            lang: None,
+        }
+    }
+
+    #[test]
+    fn sync_formats() {
+        let mut r = row("api");
+        assert_eq!(format_sync(&r), "=");
+        r.ahead = Some(2);
+        assert_eq!(format_sync(&r), "↑2");
+        r.behind = Some(1);
+        assert_eq!(format_sync(&r), "↑2 ↓1");
+        r.ahead = Some(0);
+        assert_eq!(format_sync(&r), "↓1");
+        r.ahead = None;
+        r.behind = None;
+        assert_eq!(format_sync(&r), "-");
+    }
+
+    #[test]
+    fn changes_formats() {
+        let mut r = row("api");
+        assert_eq!(format_changes(&r), "clean");
+        r.modified_count = Some(3);
+        assert_eq!(format_changes(&r), "3 modified");
+        r.is_repo = false;
+        assert_eq!(format_changes(&r), "not a repo");
+        r.exists = false;
+        assert_eq!(format_changes(&r), "missing");
+    }
+
+    #[test]
+    fn collect_skips_git_for_plain_directory() {
+        let tmp = tempfile::tempdir().unwrap();
+        let status = RepoStatus::collect("plain", tmp.path(), &[]);
+        assert!(status.exists);
+        assert!(!status.is_repo);
+        assert!(status.last_commit.is_none());
+    }
+
+    #[test]
+    fn branch_formats_detached_and_missing() {
+        let mut r = row("api");
+        r.branch = None;
+        assert_eq!(format_branch(&r), "(detached)");
+        r.is_repo = false;
+        assert_eq!(format_branch(&r), "-");
+    }
+
+    #[test]
+    fn commit_subject_is_truncated() {
+        let mut r = row("api");
+        r.last_commit.as_mut().unwrap().subject = "x".repeat(80);
+        let text = format_commit(&r);
+        assert!(text.contains('…'));
+        assert!(text.starts_with("abc1234 "));
+        assert!(text.ends_with("(2 days ago)"));
+    }
+
+    #[test]
+    fn table_aligns_columns() {
+        let mut long = row("frontend-app");
+        long.branch = Some("feature/login".to_string());
+        let ansi = regex::Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+        let table = format_table(&[row("api"), long]);
+        let table = ansi.replace_all(&table, "");
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("REPO          BRANCH"));
+        let col = lines[0].find("BRANCH").unwrap();
+        assert_eq!(lines[1].find("main"), Some(col));
+        assert_eq!(lines[2].find("feature/login"), Some(col));
+    }
+
+    #[test]
+    fn matches_tags_filter() {
+        let tags = vec!["backend".to_string(), "rust".to_string()];
+        assert!(matches_tags(&tags, None));
+        assert!(matches_tags(&tags, Some("frontend, rust")));
+        assert!(!matches_tags(&tags, Some("frontend")));
+    }
+
+    #[test]
+    fn collect_reports_missing_directory() {
+        let tmp = tempfile::tempdir().unwrap();
+        let status = RepoStatus::collect("gone", &tmp.path().join("gone"), &[]);
+        assert!(!status.exists);
+        assert!(status.branch.is_none());
+        assert_eq!(format_changes(&status), "missing");
+    }
+
+    #[test]
+    fn json_omits_unknown_fields() {
+        let tmp = tempfile::tempdir().unwrap();
+        let status = RepoStatus::collect("gone", &tmp.path().join("gone"), &[]);
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["exists"], false);
+        assert!(json.get("branch").is_none());
+        assert!(json.get("tags").is_none());
+    }
+}
@@ -2,18 +2,102 @@
 //!
 //! Plugins are standalone executables that communicate via JSON over stdin/stdout.
 //! This approach provides better isolation, language flexibility, and simpler debugging.
+//! `meta-*.wasm` files are run the same way inside a WebAssembly runtime (see
+//! [`crate::wasm_plugins`]).
 
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
+use crate::plugin_cache::{self, PluginInfoCache};
+use crate::plugin_permissions::{self, Permission, PermissionPolicy};
+use crate::wasm_plugins;
+
 #[allow(unused_imports)]
 pub use meta_plugin_protocol::{
     ExecutionPlan, PlanResponse as PluginResponse, PlannedCommand, PluginHelp, PluginInfo,
     PluginRequest, PluginRequestOptions,
 };
 
+/// Environment variable set for `--meta-plugin-exec`, announcing that the
+/// host accepts streamed events
+pub const STREAMING_ENV: &str = "META_PLUGIN_STREAMING";
+
+/// An event a long-running plugin writes to stdout while it works, one JSON
+/// object per line:
+///
+/// ```text
+/// {"event": "progress", "message": "migrating api", "current": 1, "total": 3}
+/// {"event": "log", "level": "warn", "message": "api has uncommitted changes"}
+/// {"event": "plan", "plan": {"commands": [{"dir": "api", "cmd": "make migrate"}]}}
+/// ```
+///
+/// Plans are executed as they arrive while the plugin keeps running. Lines
+/// that are not events are collected and handled like a non-streaming
+/// response when the plugin exits, so a final `{"plan": ...}` still works.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum PluginEvent {
+    Progress {
+        message: String,
+        #[serde(default)]
+        current: Option<u64>,
+        #[serde(default)]
+        total: Option<u64>,
+    },
+    Log {
+        #[serde(default)]
+        level: Option<String>,
+        message: String,
+    },
+    Plan {
+        plan: ExecutionPlan,
+    },
+}
+
+/// Read a plugin's stdout, passing events to `on_event` as they arrive and
+/// returning everything else unchanged. Events of unknown kinds are skipped
+/// so plugins can target newer hosts.
+fn read_plugin_output(
+    mut reader: impl std::io::BufRead,
+    mut on_event: impl FnMut(PluginEvent) -> Result<()>,
+) -> Result<String> {
+    let mut rest = String::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        let value = line
+            .trim_start()
+            .starts_with("{\"event\"")
+            .then(|| serde_json::from_str::<serde_json::Value>(&line).ok())
+            .flatten();
+        match value {
+            Some(value) if value.get("event").is_some() => {
+                match serde_json::from_value::<PluginEvent>(value) {
+                    Ok(event) => on_event(event)?,
+                    Err(e) => log::debug!("Skipping plugin event: {e}"),
+                }
+            }
+            _ => rest.push_str(&line),
+        }
+        line.clear();
+    }
+    Ok(rest)
+}
+
+/// One progress line: `[2/5] migrating web`
+fn format_progress(message: &str, current: Option<u64>, total: Option<u64>) -> String {
+    match (current, total) {
+        (Some(current), Some(total)) => {
+            format!("{} {message}", format!("[{current}/{total}]").dimmed())
+        }
+        (Some(current), None) => format!("{} {message}", format!("[{current}]").dimmed()),
+        _ => format!("{} {message}", "•".cyan()),
+    }
+}
+
 /// A discovered subprocess plugin
 #[derive(Debug, Clone)]
 pub struct SubprocessPlugin {
@@ -21,10 +105,256 @@ pub struct SubprocessPlugin {
     pub info: PluginInfo,
 }
 
+/// Where a plugin executable was found, in discovery order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginOrigin {
+    /// `.meta/plugins/` in the workspace or a parent directory
+    Local,
+    /// `~/.meta/plugins/`
+    Global,
+    /// A directory on PATH
+    Path,
+}
+
+impl PluginOrigin {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "local" => Some(PluginOrigin::Local),
+            "global" => Some(PluginOrigin::Global),
+            "path" => Some(PluginOrigin::Path),
+            _ => None,
+        }
+    }
+}
+
+/// How plugins that collide are resolved, from `.meta`:
+///
+/// ```json
+/// "plugin_priority": ["global", "local", "path"],
+/// "plugin_overrides": { "worktree": "worktree", "git status": "git" }
+/// ```
+///
+/// `plugin_priority` orders the discovery locations; the first plugin of a
+/// given name wins, and when two plugins register the same command the one
+/// from the higher-priority location handles it (ties go to the
+/// alphabetically first plugin). Locations left out keep their default
+/// order after the listed ones. `plugin_overrides` picks the plugin for
+/// specific commands regardless of location. `plugin_permissions` turns on
+/// permission checks (see [`crate::plugin_permissions`]).
+///
+/// `plugins` limits which plugins load at all:
+///
+/// ```json
+/// "plugins": { "enabled": ["git", "project"], "disabled": ["deploy"] }
+/// ```
+///
+/// With `enabled` only the listed plugins load; `disabled` plugins never do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginPolicy {
+    pub priority: Vec<PluginOrigin>,
+    /// Command → plugin name
+    pub overrides: HashMap<String, String>,
+    /// Allowlist of plugin names; `None` allows every plugin not disabled
+    pub enabled: Option<Vec<String>>,
+    pub disabled: Vec<String>,
+    /// `None` unless the workspace opted in to permission checks
+    pub permissions: Option<PermissionPolicy>,
+}
+
+impl Default for PluginPolicy {
+    fn default() -> Self {
+        Self {
+            priority: vec![
+                PluginOrigin::Local,
+                PluginOrigin::Global,
+                PluginOrigin::Path,
+            ],
+            overrides: HashMap::new(),
+            enabled: None,
+            disabled: Vec::new(),
+            permissions: None,
+        }
+    }
+}
+
+impl PluginPolicy {
+    /// Read the policy from a config document; missing keys keep the defaults
+    pub fn from_value(config: &serde_json::Value) -> Result<Self> {
+        let mut policy = Self::default();
+        if let Some(priority) = config.get("plugin_priority") {
+            let names = priority
+                .as_array()
+                .context("plugin_priority must be an array of \"local\", \"global\" or \"path\"")?;
+            let mut listed = Vec::new();
+            for name in names {
+                let origin = name.as_str().and_then(PluginOrigin::parse).with_context(|| {
+                    format!("unknown plugin location {name} in plugin_priority (expected local, global or path)")
+                })?;
+                if !listed.contains(&origin) {
+                    listed.push(origin);
+                }
+            }
+            let rest: Vec<_> = policy
+                .priority
+                .iter()
+                .copied()
+                .filter(|o| !listed.contains(o))
+                .collect();
+            listed.extend(rest);
+            policy.priority = listed;
+        }
+        if let Some(overrides) = config.get("plugin_overrides") {
+            let map = overrides
+                .as_object()
+                .context("plugin_overrides must map commands to plugin names")?;
+            for (command, plugin) in map {
+                let plugin = plugin
+                    .as_str()
+                    .with_context(|| format!("plugin_overrides.{command} must be a plugin name"))?;
+                policy.overrides.insert(command.clone(), plugin.to_string());
+            }
+        }
+        if let Some(plugins) = config.get("plugins") {
+            let plugins = plugins
+                .as_object()
+                .context("plugins must be an object with enabled and/or disabled lists")?;
+            let names = |key: &str| -> Result<Option<Vec<String>>> {
+                plugins
+                    .get(key)
+                    .map(|list| {
+                        list.as_array()
+                            .and_then(|items| {
+                                items
+                                    .iter()
+                                    .map(|v| v.as_str().map(str::to_string))
+                                    .collect()
+                            })
+                            .with_context(|| {
+                                format!("plugins.{key} must be a list of plugin names")
+                            })
+                    })
+                    .transpose()
+            };
+            policy.enabled = names("enabled")?;
+            policy.disabled = names("disabled")?.unwrap_or_default();
+        }
+        policy.permissions = PermissionPolicy::from_value(config)?;
+        Ok(policy)
+    }
+
+    /// Whether the `plugins` settings let the plugin `name` load
+    pub fn allows(&self, name: &str) -> bool {
+        self.enabled
+            .as_ref()
+            .is_none_or(|enabled| enabled.iter().any(|n| n == name))
+            && !self.disabled.iter().any(|n| n == name)
+    }
+
+    /// Load the policy from the nearest `.meta` layered over the user
+    /// config, falling back to the defaults
+    pub fn discover(start_dir: &Path) -> Result<Self> {
+        let workspace = crate::config::find_meta_config(start_dir, None).map(|(path, _)| path);
+        let user = crate::config::user_config_path();
+        let config = crate::config::EffectiveConfig::load(&user, workspace.as_deref())?;
+        Self::from_value(&config.value).with_context(|| match &workspace {
+            Some(path) => format!(
+                "Invalid plugin settings in {} or {}",
+                path.display(),
+                user.display()
+            ),
+            None => format!("Invalid plugin settings in {}", user.display()),
+        })
+    }
+
+    fn rank(&self, origin: PluginOrigin) -> usize {
+        self.priority
+            .iter()
+            .position(|o| *o == origin)
+            .unwrap_or(self.priority.len())
+    }
+
+    /// Pick the plugin handling `command` among `(name, origin)` owners
+    fn resolve(&self, command: &str, owners: &[(String, PluginOrigin)]) -> (String, bool) {
+        if let Some(chosen) = self.overrides.get(command) {
+            if owners.iter().any(|(name, _)| name == chosen) {
+                return (chosen.clone(), true);
+            }
+        }
+        let winner = owners
+            .iter()
+            .min_by(|a, b| (self.rank(a.1), &a.0).cmp(&(self.rank(b.1), &b.0)))
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default();
+        (winner, false)
+    }
+}
+
+/// Help a plugin registers beyond [`PluginHelp`], read from the `help`
+/// object of its `--meta-plugin-info`:
+///
+/// ```json
+/// "help": {
+///   "topics": [{"name": "migrations", "summary": "...", "body": "..."}],
+///   "command_examples": {"mig run": ["meta mig run --to 42"]}
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PluginHelpExtras {
+    pub topics: Vec<crate::help::Topic>,
+    pub command_examples: BTreeMap<String, Vec<String>>,
+}
+
+impl PluginHelpExtras {
+    /// Read the extras from raw plugin info; malformed entries are dropped.
+    pub fn from_info(raw: &serde_json::Value) -> Self {
+        let Some(help) = raw.get("help") else {
+            return Self::default();
+        };
+        let topics = help
+            .get("topics")
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|t| serde_json::from_value(t.clone()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let command_examples = help
+            .get("command_examples")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+        Self {
+            topics,
+            command_examples,
+        }
+    }
+}
+
 /// Manager for subprocess-based plugins
 pub struct SubprocessPluginManager {
     plugins: HashMap<String, SubprocessPlugin>,
     verbose: bool,
+    /// Concurrency cap (`--jobs`) applied to plugin execution plans
+    max_parallel: Option<usize>,
+    /// `.meta` config whose command `policy` execution plans are checked
+    /// against
+    command_policy: Option<PathBuf>,
+    policy: PluginPolicy,
+    /// Where each loaded plugin was found
+    origins: HashMap<String, PluginOrigin>,
+    /// Winner for each command registered by more than one plugin
+    command_owners: HashMap<String, String>,
+    /// Help topics and per-command examples, by plugin name
+    help_extras: HashMap<String, PluginHelpExtras>,
+    /// Permissions each plugin declares, by plugin name
+    permissions: HashMap<String, Vec<Permission>>,
+    /// Commands of plugins the `plugins` settings keep from loading, by
+    /// plugin name
+    disabled: HashMap<String, Vec<String>>,
+    /// `--meta-plugin-info` answers from earlier invocations
+    info_cache: PluginInfoCache,
 }
 
 impl Default for SubprocessPluginManager {
@@ -38,17 +368,128 @@ impl SubprocessPluginManager {
         Self {
             plugins: HashMap::new(),
             verbose: false,
+            max_parallel: None,
+            command_policy: None,
+            policy: PluginPolicy::default(),
+            origins: HashMap::new(),
+            command_owners: HashMap::new(),
+            help_extras: HashMap::new(),
+            permissions: HashMap::new(),
+            disabled: HashMap::new(),
+            info_cache: PluginInfoCache::default(),
         }
     }
 
+    /// Cap the concurrency of plugin execution plans. A plan that sets its own
+    /// `max_parallel` gets the smaller of the two.
+    pub fn set_max_parallel(&mut self, jobs: Option<usize>) {
+        self.max_parallel = jobs;
+    }
+
+    /// Check the main commands of execution plans against the command
+    /// `policy` of the `.meta` config at `config_path`; see [`crate::policy`].
+    pub fn set_command_policy(&mut self, config_path: Option<PathBuf>) {
+        self.command_policy = config_path;
+    }
+
     /// Discover and load all subprocess plugins
     ///
     /// Discovery order (first match wins):
     /// 1. `.meta/plugins/` directories walking up from cwd (project-local)
     /// 2. `~/.meta/plugins/` (global installed)
     /// 3. PATH (bundled/system plugins)
+    ///
+    /// The order can be changed with `plugin_priority` in `.meta`; see
+    /// [`PluginPolicy`]. Commands registered by several plugins are resolved
+    /// by the same policy, with a warning unless `plugin_overrides` settles it.
     pub fn discover_plugins(&mut self, verbose: bool) -> Result<()> {
         self.verbose = verbose;
+        let mut span = crate::telemetry::Span::start("plugins.discover");
+        let cwd = std::env::current_dir()?;
+        self.policy = match PluginPolicy::discover(&cwd) {
+            Ok(policy) => policy,
+            Err(e) => {
+                eprintln!(
+                    "{}: {e:#}; using the default plugin priority",
+                    "warning".yellow().bold()
+                );
+                PluginPolicy::default()
+            }
+        };
+        let cache_path = plugin_cache::cache_path();
+        self.info_cache = PluginInfoCache::load(&cache_path);
+        for (path, origin) in self.plugin_candidates()? {
+            self.try_load_plugin(&path, origin)?;
+        }
+        span.attr("plugins", self.plugins.len());
+        // The cache is best-effort; a read-only home must not break discovery
+        if let Err(e) = self.info_cache.save(&cache_path) {
+            log::debug!("Could not cache plugin info: {e}");
+        }
+
+        for conflict in self.resolve_conflicts() {
+            if !conflict.overridden {
+                eprintln!(
+                    "{}: '{}' is registered by plugins {}; using {} (set plugin_overrides in .meta to choose)",
+                    "warning".yellow().bold(),
+                    conflict.command,
+                    conflict.plugins.join(", "),
+                    conflict.resolved_to
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Find commands registered by more than one loaded plugin and record
+    /// which plugin handles each
+    fn resolve_conflicts(&mut self) -> Vec<CommandConflict> {
+        let owners = self.plugins.values().map(|p| {
+            (
+                p.info.name.clone(),
+                self.origin_of(&p.info.name),
+                p.info.commands.clone(),
+            )
+        });
+        let conflicts = command_conflicts(owners, &self.policy);
+        self.command_owners = conflicts
+            .iter()
+            .map(|c| (c.command.clone(), c.resolved_to.clone()))
+            .collect();
+        conflicts
+    }
+
+    /// The disabled plugin that would have handled `command`, if any
+    pub fn disabled_plugin_for(&self, command: &str) -> Option<&str> {
+        let mut names: Vec<&String> = self.disabled.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .find(|name| {
+                self.disabled[*name]
+                    .iter()
+                    .any(|cmd| command == cmd || command.starts_with(&format!("{cmd} ")))
+            })
+            .map(String::as_str)
+    }
+
+    fn origin_of(&self, name: &str) -> PluginOrigin {
+        self.origins
+            .get(name)
+            .copied()
+            .unwrap_or(PluginOrigin::Path)
+    }
+
+    /// Whether `plugin` handles `command`, i.e. no other plugin won it
+    fn owns(&self, plugin: &SubprocessPlugin, command: &str) -> bool {
+        self.command_owners
+            .get(command)
+            .is_none_or(|owner| *owner == plugin.info.name)
+    }
+
+    /// Every `meta-*` executable in discovery order, with where it was found
+    pub fn plugin_candidates(&self) -> Result<Vec<(PathBuf, PluginOrigin)>> {
+        let mut candidates = Vec::new();
         let mut visited = std::collections::HashSet::new();
 
         // Search in .meta/plugins/ directories walking up from cwd (project-local)
@@ -56,7 +497,7 @@ impl SubprocessPluginManager {
         loop {
             let plugin_dir = current_dir.join(".meta").join("plugins");
             if plugin_dir.exists() && plugin_dir.is_dir() && visited.insert(plugin_dir.clone()) {
-                self.scan_directory(&plugin_dir)?;
+                self.scan_directory(&plugin_dir, PluginOrigin::Local, &mut candidates)?;
             }
             if let Some(parent) = current_dir.parent() {
                 current_dir = parent.to_path_buf();
@@ -68,7 +509,7 @@ impl SubprocessPluginManager {
         // Search in ~/.meta/plugins/ (global installed)
         if let Ok(global_plugins) = meta_core::data_dir::data_subdir("plugins") {
             if global_plugins.exists() && visited.insert(global_plugins.clone()) {
-                self.scan_directory(&global_plugins)?;
+                self.scan_directory(&global_plugins, PluginOrigin::Global, &mut candidates)?;
             }
         }
 
@@ -76,20 +517,28 @@ impl SubprocessPluginManager {
         if let Ok(path_var) = std::env::var("PATH") {
             for path_dir in std::env::split_paths(&path_var) {
                 if path_dir.exists() && visited.insert(path_dir.clone()) {
-                    self.scan_path_directory(&path_dir)?;
+                    scan_path_directory(&path_dir, &mut candidates);
                 }
             }
         }
 
-        Ok(())
+        // Stable, so each location keeps its own search order
+        candidates.sort_by_key(|(_, origin)| self.policy.rank(*origin));
+        Ok(candidates)
     }
 
     /// Scan a .meta-plugins directory for plugin executables
-    fn scan_directory(&mut self, dir: &Path) -> Result<()> {
+    fn scan_directory(
+        &self,
+        dir: &Path,
+        origin: PluginOrigin,
+        candidates: &mut Vec<(PathBuf, PluginOrigin)>,
+    ) -> Result<()> {
         if self.verbose {
             println!("Scanning for subprocess plugins in: {}", dir.display());
         }
 
+        let mut paths = Vec::new();
         for entry in std::fs::read_dir(dir)? {
             let entry = entry?;
             let path = entry.path();
@@ -101,48 +550,40 @@ impl SubprocessPluginManager {
                     && !name.ends_with(".so")
                     && !name.ends_with(".dll")
                 {
-                    self.try_load_plugin(&path)?;
-                }
-            }
-        }
-        Ok(())
-    }
-
-    /// Scan a PATH directory for meta-* executables
-    fn scan_path_directory(&mut self, dir: &Path) -> Result<()> {
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if name.starts_with("meta-") && is_executable(&path) {
-                        self.try_load_plugin(&path)?;
-                    }
+                    paths.push(path);
                 }
             }
         }
+        paths.sort();
+        candidates.extend(paths.into_iter().map(|p| (p, origin)));
         Ok(())
     }
 
     /// Try to load a plugin by querying its info
-    fn try_load_plugin(&mut self, path: &Path) -> Result<()> {
-        if !is_executable(path) {
+    fn try_load_plugin(&mut self, path: &Path, origin: PluginOrigin) -> Result<()> {
+        if !is_runnable(path) {
             return Ok(());
         }
 
-        // Query plugin info
-        let output = Command::new(path)
-            .arg("--meta-plugin-info")
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .output();
-
-        match output {
-            Ok(output) if output.status.success() => {
-                // Try to parse as plugin info - silently skip if invalid JSON
-                // (e.g., meta-mcp is an MCP server, not a meta plugin)
-                let info: PluginInfo = match serde_json::from_slice(&output.stdout) {
+        // Query plugin info, from the on-disk cache or the daemon when possible.
+        // Output that is not plugin info (e.g. meta-mcp is an MCP server,
+        // not a meta plugin) is skipped silently.
+        let raw = match self.info_cache.get(path) {
+            Some(raw) => raw.clone(),
+            None => {
+                let raw = crate::daemon::plugin_info(path)
+                    .or_else(|| query_plugin_info(path))
+                    .unwrap_or_default();
+                self.info_cache.insert(path, raw.clone());
+                raw
+            }
+        };
+        let raw = Some(raw).filter(|r| !r.is_null());
+        match raw {
+            Some(raw) => {
+                let info: PluginInfo = match serde_json::from_value(raw.clone()) {
                     Ok(info) => info,
-                    Err(_) => return Ok(()), // Not a valid plugin, skip silently
+                    Err(_) => return Ok(()),
                 };
 
                 if self.verbose {
@@ -154,8 +595,23 @@ impl SubprocessPluginManager {
                     );
                 }
 
+                if !self.policy.allows(&info.name) {
+                    if self.verbose {
+                        println!("  Skipping plugin {} (disabled in .meta)", info.name);
+                    }
+                    self.disabled
+                        .entry(info.name.clone())
+                        .or_insert(info.commands);
+                    return Ok(());
+                }
+
                 // Don't override if already loaded (first one wins)
                 if !self.plugins.contains_key(&info.name) {
+                    self.origins.insert(info.name.clone(), origin);
+                    self.help_extras
+                        .insert(info.name.clone(), PluginHelpExtras::from_info(&raw));
+                    self.permissions
+                        .insert(info.name.clone(), plugin_permissions::declared(&raw).0);
                     self.plugins.insert(
                         info.name.clone(),
                         SubprocessPlugin {
@@ -165,7 +621,7 @@ impl SubprocessPluginManager {
                     );
                 }
             }
-            _ => {
+            None => {
                 // Not a valid plugin, ignore silently
             }
         }
@@ -182,6 +638,9 @@ impl SubprocessPluginManager {
 
         for plugin in self.plugins.values() {
             for plugin_cmd in &plugin.info.commands {
+                if !self.owns(plugin, plugin_cmd) {
+                    continue;
+                }
                 // Check if the input command starts with this plugin command
                 if command == plugin_cmd || command.starts_with(&format!("{plugin_cmd} ")) {
                     return true;
@@ -214,6 +673,9 @@ impl SubprocessPluginManager {
 
         for plugin in self.plugins.values() {
             for plugin_cmd in &plugin.info.commands {
+                if !self.owns(plugin, plugin_cmd) {
+                    continue;
+                }
                 // Check if the input command starts with this plugin command
                 if command == plugin_cmd || command.starts_with(&format!("{plugin_cmd} ")) {
                     let cmd_len = plugin_cmd.split_whitespace().count();
@@ -274,30 +736,46 @@ impl SubprocessPluginManager {
             );
         }
 
-        let mut child = Command::new(&plugin.path)
-            .arg("--meta-plugin-exec")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped()) // Capture stdout to parse response
-            .stderr(Stdio::inherit()) // Let stderr pass through for error messages
-            .spawn()
-            .with_context(|| format!("Failed to execute plugin {}", plugin.path.display()))?;
-
-        // Send request to plugin's stdin
-        if let Some(mut stdin) = child.stdin.take() {
-            use std::io::Write;
-            stdin.write_all(request_json.as_bytes())?;
+        let mut span = crate::telemetry::Span::start("plugin.dispatch");
+        span.attr("plugin", &plugin.info.name);
+        span.attr("command", command);
+
+        let granted = self.authorize(plugin)?;
+        if let Err(e) = crate::metrics::record_dispatch(&plugin.info.name) {
+            log::warn!("Failed to update metrics: {e:#}");
         }
 
-        let output = child.wait_with_output()?;
+        let handle =
+            |event: PluginEvent| self.handle_event(event, plugin, granted.as_deref(), options);
+        let (stdout_str, code) = if wasm_plugins::is_wasm_plugin(&plugin.path) {
+            // The sandbox only passes meta's environment with the permission
+            let cwd = std::env::current_dir()?;
+            let invocation = wasm_plugins::Invocation {
+                args: &["--meta-plugin-exec"],
+                stdin: request_json.as_bytes(),
+                env: &[(STREAMING_ENV, "1")],
+                inherit_env: granted
+                    .as_ref()
+                    .is_some_and(|g| g.contains(&Permission::Env)),
+                dir: &cwd,
+                stderr: true,
+            };
+            let output = wasm_plugins::run(&plugin.path, &invocation)?;
+            let rest = read_plugin_output(output.stdout.as_slice(), handle)?;
+            (rest, output.code)
+        } else {
+            spawn_plugin(plugin, &request_json, granted.as_deref(), handle)?
+        };
 
-        if !output.status.success() {
-            // Plugin already printed its error to stderr, just propagate the exit code
-            std::process::exit(output.status.code().unwrap_or(1));
+        if code != 0 {
+            // The plugin already printed its error to stderr
+            return Err(crate::error::MetaError::PluginFailed {
+                plugin: plugin.info.name.clone(),
+                status: code,
+            }
+            .into());
         }
 
-        // Try to parse the response as JSON
-        let stdout_str = String::from_utf8_lossy(&output.stdout);
-
         // If stdout is empty, plugin handled execution silently
         if stdout_str.trim().is_empty() {
             return Ok(true);
@@ -313,6 +791,7 @@ impl SubprocessPluginManager {
         match serde_json::from_str::<PluginResponse>(&stdout_str) {
             Ok(response) => {
                 // Plugin returned an execution plan - execute it via loop_lib
+                check_plan_dirs(&plugin.info.name, &response.plan, granted.as_deref())?;
                 self.execute_plan(&response.plan, options)
             }
             Err(_) => {
@@ -323,6 +802,59 @@ impl SubprocessPluginManager {
         }
     }
 
+    /// Check the plugin's declared permissions against the workspace policy.
+    /// Returns the permissions it runs with, or `None` when the workspace has
+    /// not opted in to checks.
+    fn authorize(&self, plugin: &SubprocessPlugin) -> Result<Option<Vec<Permission>>> {
+        let Some(policy) = &self.policy.permissions else {
+            return Ok(None);
+        };
+        let declared = self
+            .permissions
+            .get(&plugin.info.name)
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+        policy
+            .authorize(&plugin.info.name, declared, plugin_permissions::prompt)
+            .map(Some)
+    }
+
+    /// React to one streamed plugin event
+    fn handle_event(
+        &self,
+        event: PluginEvent,
+        plugin: &SubprocessPlugin,
+        granted: Option<&[Permission]>,
+        options: &PluginRequestOptions,
+    ) -> Result<()> {
+        match event {
+            PluginEvent::Progress {
+                message,
+                current,
+                total,
+            } => {
+                if !options.silent {
+                    eprintln!("{}", format_progress(&message, current, total));
+                }
+            }
+            PluginEvent::Log { level, message } => {
+                let level = level.as_deref().unwrap_or("info");
+                match level {
+                    "error" => eprintln!("{}: {message}", "error".red().bold()),
+                    "warn" | "warning" => eprintln!("{}: {message}", "warning".yellow().bold()),
+                    "debug" if !options.verbose => {}
+                    _ if options.silent => {}
+                    _ => eprintln!("{message}"),
+                }
+            }
+            PluginEvent::Plan { plan } => {
+                check_plan_dirs(&plugin.info.name, &plan, granted)?;
+                self.execute_plan(&plan, options)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Execute an execution plan via loop_lib
     fn execute_plan(&self, plan: &ExecutionPlan, options: &PluginRequestOptions) -> Result<bool> {
         use loop_lib::{run_commands, DirCommand, LoopConfig};
@@ -379,6 +911,11 @@ impl SubprocessPluginManager {
                 })
                 .collect();
 
+            if !options.dry_run {
+                let cmds: Vec<&str> = commands.iter().map(|c| c.cmd.as_str()).collect();
+                crate::policy::enforce(self.command_policy.as_deref(), &cmds)?;
+            }
+
             // The first command's directory is the meta root (should display as ".")
             let root_dir = commands.first().map(|c| PathBuf::from(&c.dir));
 
@@ -395,7 +932,10 @@ impl SubprocessPluginManager {
                 json_output: options.json_output,
                 spawn_stagger_ms: plan.spawn_stagger_ms.unwrap_or(0),
                 env: None,
-                max_parallel: plan.max_parallel,
+                max_parallel: match (plan.max_parallel, self.max_parallel) {
+                    (Some(plan_limit), Some(jobs)) => Some(plan_limit.min(jobs)),
+                    (plan_limit, jobs) => plan_limit.or(jobs),
+                },
                 root_dir,
             };
 
@@ -564,14 +1104,8 @@ impl SubprocessPluginManager {
         })?;
 
         // Try to get help by executing plugin with --help
-        let output = Command::new(&plugin.path)
-            .arg("--help")
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .output();
-
-        if let Ok(output) = output {
-            if output.status.success() {
+        if let Ok(output) = plugin_output(&plugin.path, "--help") {
+            if output.success() {
                 return Some(String::from_utf8_lossy(&output.stdout).to_string());
             }
         }
@@ -580,6 +1114,37 @@ impl SubprocessPluginManager {
         Some(self.generate_fallback_help(plugin))
     }
 
+    /// Help topics registered by plugins, as `(plugin, topic)` sorted by
+    /// topic name. A topic name registered twice goes to the first plugin.
+    pub fn help_topics(&self) -> Vec<(String, crate::help::Topic)> {
+        let mut topics: Vec<(String, crate::help::Topic)> = Vec::new();
+        let mut names: Vec<&String> = self.help_extras.keys().collect();
+        names.sort();
+        for name in names {
+            for topic in &self.help_extras[name].topics {
+                let taken = topics
+                    .iter()
+                    .any(|(_, t)| t.name.eq_ignore_ascii_case(&topic.name));
+                if !taken {
+                    topics.push((name.clone(), topic.clone()));
+                }
+            }
+        }
+        topics.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+        topics
+    }
+
+    /// Examples a plugin registered for `command` (e.g. "mig run").
+    pub fn command_examples(&self, command: &str) -> Vec<String> {
+        let command = command.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.help_extras
+            .values()
+            .filter_map(|extras| extras.command_examples.get(&command))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
     /// Generate help text from PluginInfo when plugin doesn't support --help
     fn generate_fallback_help(&self, plugin: &SubprocessPlugin) -> String {
         let info = &plugin.info;
@@ -624,6 +1189,16 @@ impl SubprocessPluginManager {
                 help.push('\n');
             }
 
+            if let Some(extras) = self.help_extras.get(name) {
+                for (cmd, examples) in &extras.command_examples {
+                    help.push_str(&format!("EXAMPLES ({cmd}):\n"));
+                    for example in examples {
+                        help.push_str(&format!("    {example}\n"));
+                    }
+                    help.push('\n');
+                }
+            }
+
             if let Some(ref note) = plugin_help.note {
                 help.push_str(&format!("NOTE:\n    {note}\n"));
             }
@@ -652,6 +1227,400 @@ impl SubprocessPluginManager {
     }
 }
 
+// ── Diagnostics ─────────────────────────────────────────
+
+/// Plugin protocol version this meta speaks. Plugins may report
+/// `protocol_version` in their `--meta-plugin-info`; absent means 1.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+/// Health of one discovered executable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// Loaded and handling its commands
+    Ok,
+    /// A valid plugin hidden by an earlier one with the same name
+    Shadowed,
+    /// Answers `--meta-plugin-info` with something other than JSON, like
+    /// `meta-mcp`; discovery skips these
+    Ignored,
+    /// Cannot be used as a plugin
+    Broken,
+    /// A valid plugin the `plugins` settings in `.meta` keep from loading
+    Disabled,
+}
+
+/// Result of checking one `meta-*` executable
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginCheck {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub path: PathBuf,
+    pub origin: PluginOrigin,
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>,
+    /// The executable that wins over this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shadowed_by: Option<PathBuf>,
+    #[serde(skip)]
+    commands: Vec<String>,
+}
+
+/// A command registered by more than one loaded plugin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandConflict {
+    pub command: String,
+    pub plugins: Vec<String>,
+    /// The plugin that handles the command
+    pub resolved_to: String,
+    /// Whether `plugin_overrides` chose the winner
+    pub overridden: bool,
+}
+
+/// Commands registered by more than one plugin, sorted by command, and the
+/// plugin the policy picks for each
+fn command_conflicts(
+    plugins: impl Iterator<Item = (String, PluginOrigin, Vec<String>)>,
+    policy: &PluginPolicy,
+) -> Vec<CommandConflict> {
+    let mut by_command: std::collections::BTreeMap<String, Vec<(String, PluginOrigin)>> =
+        Default::default();
+    for (name, origin, commands) in plugins {
+        for command in commands {
+            let owners = by_command.entry(command).or_default();
+            if !owners.iter().any(|(n, _)| *n == name) {
+                owners.push((name.clone(), origin));
+            }
+        }
+    }
+    by_command
+        .into_iter()
+        .filter(|(_, owners)| owners.len() > 1)
+        .map(|(command, mut owners)| {
+            owners.sort_by(|a, b| a.0.cmp(&b.0));
+            let (resolved_to, overridden) = policy.resolve(&command, &owners);
+            CommandConflict {
+                plugins: owners.into_iter().map(|(name, _)| name).collect(),
+                command,
+                resolved_to,
+                overridden,
+            }
+        })
+        .collect()
+}
+
+/// Output of `meta plugin doctor`
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub plugins: Vec<PluginCheck>,
+    pub conflicts: Vec<CommandConflict>,
+}
+
+impl DoctorReport {
+    /// Broken executables and command conflicts not settled by
+    /// `plugin_overrides`; shadowing is informational
+    pub fn problem_count(&self) -> usize {
+        let broken = self
+            .plugins
+            .iter()
+            .filter(|p| p.status == CheckStatus::Broken || !p.problems.is_empty())
+            .filter(|p| !matches!(p.status, CheckStatus::Ignored | CheckStatus::Disabled))
+            .count();
+        broken + self.conflicts.iter().filter(|c| !c.overridden).count()
+    }
+}
+
+impl SubprocessPluginManager {
+    /// Check every discovered executable, not just the ones that won
+    pub fn doctor(&self) -> Result<DoctorReport> {
+        let checks = self
+            .plugin_candidates()?
+            .into_iter()
+            .map(|(path, origin)| check_plugin(path, origin))
+            .collect();
+        Ok(build_report(checks, &self.policy))
+    }
+}
+
+/// Mark shadowed plugins and find command conflicts among the winners.
+/// `checks` must be in discovery order.
+fn build_report(mut checks: Vec<PluginCheck>, policy: &PluginPolicy) -> DoctorReport {
+    let mut winners: HashMap<String, PathBuf> = HashMap::new();
+    for check in &mut checks {
+        let Some(name) = &check.name else { continue };
+        if check.status == CheckStatus::Broken {
+            continue;
+        }
+        if !policy.allows(name) {
+            check.status = CheckStatus::Disabled;
+            continue;
+        }
+        match winners.get(name) {
+            Some(winner) => {
+                check.status = CheckStatus::Shadowed;
+                check.shadowed_by = Some(winner.clone());
+            }
+            None => {
+                winners.insert(name.clone(), check.path.clone());
+            }
+        }
+    }
+
+    let loaded = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Ok)
+        .map(|c| {
+            (
+                c.name.clone().unwrap_or_default(),
+                c.origin,
+                c.commands.clone(),
+            )
+        });
+    let conflicts = command_conflicts(loaded, policy);
+
+    DoctorReport {
+        plugins: checks,
+        conflicts,
+    }
+}
+
+fn check_plugin(path: PathBuf, origin: PluginOrigin) -> PluginCheck {
+    let mut check = PluginCheck {
+        name: None,
+        version: None,
+        path,
+        origin,
+        status: CheckStatus::Broken,
+        problems: Vec::new(),
+        shadowed_by: None,
+        commands: Vec::new(),
+    };
+    if !is_runnable(&check.path) {
+        check.problems.push("not executable".to_string());
+        return check;
+    }
+    let output = match plugin_output(&check.path, "--meta-plugin-info") {
+        Ok(output) => output,
+        Err(e) => {
+            check.problems.push(format!("failed to execute: {e:#}"));
+            return check;
+        }
+    };
+    if !output.success() {
+        let code = output
+            .code
+            .map_or_else(|| "a signal".to_string(), |c| format!("code {c}"));
+        check
+            .problems
+            .push(format!("--meta-plugin-info exited with {code}"));
+        return check;
+    }
+
+    let raw: serde_json::Value = match serde_json::from_slice(&output.stdout) {
+        Ok(raw) => raw,
+        Err(_) => {
+            check.status = CheckStatus::Ignored;
+            check
+                .problems
+                .push("--meta-plugin-info output is not JSON (not a meta plugin)".to_string());
+            return check;
+        }
+    };
+    let info: PluginInfo = match serde_json::from_value(raw.clone()) {
+        Ok(info) => info,
+        Err(e) => {
+            check.problems.push(format!(
+                "plugin info does not match the plugin protocol: {e}"
+            ));
+            return check;
+        }
+    };
+
+    check.status = CheckStatus::Ok;
+    check.name = Some(info.name);
+    check.version = Some(info.version);
+    check.commands = info.commands;
+    let (_, unknown) = plugin_permissions::declared(&raw);
+    if !unknown.is_empty() {
+        check.problems.push(format!(
+            "declares unknown permissions: {} (expected network, write or env)",
+            unknown.join(", ")
+        ));
+    }
+    if let Some(version) = raw.get("protocol_version").and_then(|v| v.as_u64()) {
+        if version != PROTOCOL_VERSION {
+            check.problems.push(format!(
+                "speaks plugin protocol v{version}, meta supports v{PROTOCOL_VERSION}"
+            ));
+        }
+    }
+    check
+}
+
+/// Scan a PATH directory for meta-* executables
+fn scan_path_directory(dir: &Path, candidates: &mut Vec<(PathBuf, PluginOrigin)>) {
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|name| name.starts_with("meta-") && is_executable(path))
+            })
+            .collect();
+        paths.sort();
+        candidates.extend(paths.into_iter().map(|p| (p, PluginOrigin::Path)));
+    }
+}
+
+/// Refuse a plan running commands outside the workspace unless the plugin
+/// holds the `write` permission. `granted` is `None` without permission checks.
+fn check_plan_dirs(
+    plugin: &str,
+    plan: &ExecutionPlan,
+    granted: Option<&[Permission]>,
+) -> Result<()> {
+    let Some(granted) = granted else {
+        return Ok(());
+    };
+    if granted.contains(&Permission::Write) {
+        return Ok(());
+    }
+    let cwd = std::env::current_dir()?;
+    let workspace = crate::config::find_meta_config(&cwd, None)
+        .and_then(|(path, _)| path.parent().map(Path::to_path_buf))
+        .unwrap_or(cwd);
+    let dirs = plan
+        .pre_commands
+        .iter()
+        .chain(&plan.commands)
+        .map(|c| c.dir.as_str());
+    let outside = plugin_permissions::dirs_outside(&workspace, dirs);
+    if !outside.is_empty() {
+        anyhow::bail!(
+            "Plugin '{plugin}' planned commands outside the workspace ({}) but lacks write permission",
+            outside.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Run the executable plugin for `--meta-plugin-exec` with `request_json`
+/// on stdin, handling streamed events as they arrive. Returns the rest of
+/// its stdout and its exit code.
+fn spawn_plugin(
+    plugin: &SubprocessPlugin,
+    request_json: &str,
+    granted: Option<&[Permission]>,
+    on_event: impl FnMut(PluginEvent) -> Result<()>,
+) -> Result<(String, i32)> {
+    // STREAMING_ENV tells plugins this host understands streamed events
+    let mut cmd = Command::new(&plugin.path);
+    cmd.env(STREAMING_ENV, "1");
+    if granted.is_some_and(|g| !g.contains(&Permission::Env)) {
+        plugin_permissions::restrict_env(&mut cmd);
+    }
+    let mut child = cmd
+        .arg("--meta-plugin-exec")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped()) // Capture stdout to parse response
+        .stderr(Stdio::inherit()) // Let stderr pass through for error messages
+        .spawn()
+        .with_context(|| format!("Failed to execute plugin {}", plugin.path.display()))?;
+
+    // Send request to plugin's stdin
+    if let Some(mut stdin) = child.stdin.take() {
+        use std::io::Write;
+        stdin.write_all(request_json.as_bytes())?;
+    }
+
+    // Events are handled as they arrive; everything else is collected
+    // and interpreted once the plugin exits, as before
+    let stdout = child
+        .stdout
+        .take()
+        .context("Plugin stdout was not captured")?;
+    let stdout_str = match read_plugin_output(std::io::BufReader::new(stdout), on_event) {
+        Ok(rest) => rest,
+        Err(e) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+    };
+    let status = child.wait()?;
+    let code = if status.success() {
+        0
+    } else {
+        status.code().unwrap_or(1)
+    };
+    Ok((stdout_str, code))
+}
+
+/// What a plugin printed when run with a single argument.
+struct PluginOutput {
+    /// The exit code; `None` when it was killed by a signal
+    code: Option<i32>,
+    stdout: Vec<u8>,
+}
+
+impl PluginOutput {
+    fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// Run the plugin at `path` with just `arg` (`--meta-plugin-info`,
+/// `--help`), its stderr discarded: the executable itself, or a WASM module
+/// in the embedded runtime with the current directory granted.
+fn plugin_output(path: &Path, arg: &str) -> Result<PluginOutput> {
+    if wasm_plugins::is_wasm_plugin(path) {
+        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let output = wasm_plugins::run(
+            path,
+            &wasm_plugins::Invocation {
+                args: &[arg],
+                stdin: &[],
+                env: &[],
+                inherit_env: false,
+                dir: &cwd,
+                stderr: false,
+            },
+        )?;
+        return Ok(PluginOutput {
+            code: Some(output.code),
+            stdout: output.stdout,
+        });
+    }
+    let output = Command::new(path)
+        .arg(arg)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()?;
+    Ok(PluginOutput {
+        code: output.status.code(),
+        stdout: output.stdout,
+    })
+}
+
+/// Run `--meta-plugin-info` for the plugin at `path`. `None` when it fails
+/// or does not answer with JSON.
+pub fn query_plugin_info(path: &Path) -> Option<serde_json::Value> {
+    let output = plugin_output(path, "--meta-plugin-info")
+        .ok()
+        .filter(PluginOutput::success)?;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+/// Whether the plugin at `path` can be run: an executable or a WASM module
+fn is_runnable(path: &Path) -> bool {
+    is_executable(path) || wasm_plugins::is_wasm_plugin(path)
+}
+
 /// Check if a file is executable
 #[cfg(unix)]
 fn is_executable(path: &Path) -> bool {
@@ -794,6 +1763,39 @@ mod tests {
         assert!(!is_executable(path));
     }
 
+    #[test]
+    fn test_wasm_modules_are_discovered_and_run_embedded() {
+        // Prints `{"name":"mig"}` whatever it is asked
+        const INFO: &str = r#"(module
+            (import "wasi_snapshot_preview1" "fd_write"
+                (func $fd_write (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 16) "{\22name\22:\22mig\22}")
+            (func (export "_start")
+                (i32.store (i32.const 0) (i32.const 16))
+                (i32.store (i32.const 4) (i32.const 14))
+                (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))"#;
+        let dir = tempfile::tempdir().unwrap();
+        let module = dir.path().join("meta-mig.wasm");
+        std::fs::write(&module, wasmtime::wat::parse_str(INFO).unwrap()).unwrap();
+        std::fs::write(dir.path().join("meta-lib.so"), "").unwrap();
+
+        let manager = SubprocessPluginManager::new();
+        let mut candidates = Vec::new();
+        manager
+            .scan_directory(dir.path(), PluginOrigin::Local, &mut candidates)
+            .unwrap();
+        assert_eq!(candidates, vec![(module.clone(), PluginOrigin::Local)]);
+        assert!(is_runnable(&module));
+
+        let output = plugin_output(&module, "--meta-plugin-info").unwrap();
+        assert!(output.success());
+        assert_eq!(output.stdout, br#"{"name":"mig"}"#);
+
+        std::fs::write(&module, b"\0asm").unwrap();
+        assert!(plugin_output(&module, "--meta-plugin-info").is_err());
+    }
+
     #[test]
     fn test_generate_fallback_help_without_structured_help() {
         let manager = SubprocessPluginManager::new();
@@ -883,6 +1885,398 @@ mod tests {
         assert!(help.contains("Meta CLI plugin")); // Default description
     }
 
+    fn check(name: &str, path: &str, origin: PluginOrigin, commands: &[&str]) -> PluginCheck {
+        PluginCheck {
+            name: Some(name.to_string()),
+            version: Some("1.0.0".to_string()),
+            path: PathBuf::from(path),
+            origin,
+            status: CheckStatus::Ok,
+            problems: Vec::new(),
+            shadowed_by: None,
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_doctor_report_marks_shadowed_plugins_and_conflicts() {
+        let report = build_report(
+            vec![
+                check(
+                    "git",
+                    "/ws/.meta/plugins/meta-git",
+                    PluginOrigin::Local,
+                    &["git status"],
+                ),
+                check(
+                    "git",
+                    "/usr/bin/meta-git",
+                    PluginOrigin::Path,
+                    &["git status"],
+                ),
+                check(
+                    "worktree",
+                    "/usr/bin/meta-worktree",
+                    PluginOrigin::Path,
+                    &["git status", "worktree"],
+                ),
+            ],
+            &PluginPolicy::default(),
+        );
+
+        assert_eq!(report.plugins[0].status, CheckStatus::Ok);
+        assert_eq!(report.plugins[1].status, CheckStatus::Shadowed);
+        assert_eq!(
+            report.plugins[1].shadowed_by.as_deref(),
+            Some(Path::new("/ws/.meta/plugins/meta-git"))
+        );
+        assert_eq!(
+            report.conflicts,
+            vec![CommandConflict {
+                command: "git status".to_string(),
+                plugins: vec!["git".to_string(), "worktree".to_string()],
+                resolved_to: "git".to_string(),
+                overridden: false,
+            }]
+        );
+        assert_eq!(report.problem_count(), 1);
+    }
+
+    #[test]
+    fn test_read_plugin_output_streams_events() {
+        let stdout = concat!(
+            "{\"event\": \"progress\", \"message\": \"migrating api\", \"current\": 1, \"total\": 2}\n",
+            "{\"event\":\"plan\",\"plan\":{\"commands\":[{\"dir\":\"api\",\"cmd\":\"make migrate\"}]}}\n",
+            "{\"event\":\"telemetry\",\"anything\":1}\n",
+            "{\"plan\":{\"commands\":[]}}\n",
+        );
+        let mut events = Vec::new();
+        let rest = read_plugin_output(stdout.as_bytes(), |event| {
+            events.push(event);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[0],
+            PluginEvent::Progress {
+                current: Some(1),
+                total: Some(2),
+                ..
+            }
+        ));
+        match &events[1] {
+            PluginEvent::Plan { plan } => assert_eq!(plan.commands[0].cmd, "make migrate"),
+            other => panic!("expected a plan, got {other:?}"),
+        }
+        assert_eq!(rest, "{\"plan\":{\"commands\":[]}}\n");
+    }
+
+    #[test]
+    fn test_read_plugin_output_keeps_non_streaming_output() {
+        let stdout = "{\n  \"plan\": {\"commands\": []}\n}\nplain text\n";
+        let rest = read_plugin_output(stdout.as_bytes(), |_| panic!("no events")).unwrap();
+        assert_eq!(rest, stdout);
+    }
+
+    #[test]
+    fn test_format_progress() {
+        let plain = |s: String| console::strip_ansi_codes(&s).to_string();
+        assert_eq!(plain(format_progress("api", Some(2), Some(5))), "[2/5] api");
+        assert_eq!(plain(format_progress("api", None, None)), "• api");
+    }
+
+    #[test]
+    fn test_plugin_policy_from_config() {
+        let policy = PluginPolicy::from_value(&serde_json::json!({
+            "plugin_priority": ["path"],
+            "plugin_overrides": {"worktree": "worktree"}
+        }))
+        .unwrap();
+        assert_eq!(
+            policy.priority,
+            vec![
+                PluginOrigin::Path,
+                PluginOrigin::Local,
+                PluginOrigin::Global
+            ]
+        );
+        assert_eq!(policy.overrides["worktree"], "worktree");
+        assert_eq!(
+            PluginPolicy::from_value(&serde_json::json!({"projects": {}})).unwrap(),
+            PluginPolicy::default()
+        );
+        assert!(
+            PluginPolicy::from_value(&serde_json::json!({"plugin_priority": ["cloud"]})).is_err()
+        );
+        assert!(
+            PluginPolicy::from_value(&serde_json::json!({"plugin_overrides": {"x": 1}})).is_err()
+        );
+    }
+
+    #[test]
+    fn test_plugins_enabled_and_disabled_lists() {
+        let policy = PluginPolicy::from_value(&serde_json::json!({
+            "plugins": {"enabled": ["git", "deploy"], "disabled": ["deploy"]}
+        }))
+        .unwrap();
+        assert!(policy.allows("git"));
+        assert!(!policy.allows("deploy"));
+        assert!(!policy.allows("project"));
+        assert!(PluginPolicy::default().allows("anything"));
+        assert!(PluginPolicy::from_value(&serde_json::json!({"plugins": ["git"]})).is_err());
+        assert!(
+            PluginPolicy::from_value(&serde_json::json!({"plugins": {"disabled": "x"}})).is_err()
+        );
+
+        // Disabled plugins are reported, and take no part in conflicts
+        let report = build_report(
+            vec![
+                check(
+                    "git",
+                    "/usr/bin/meta-git",
+                    PluginOrigin::Path,
+                    &["git status"],
+                ),
+                check(
+                    "deploy",
+                    "/usr/bin/meta-deploy",
+                    PluginOrigin::Path,
+                    &["git status", "deploy"],
+                ),
+            ],
+            &policy,
+        );
+        assert_eq!(report.plugins[1].status, CheckStatus::Disabled);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(report.problem_count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_disabled_plugins_are_not_loaded() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("meta-deploy");
+        std::fs::write(
+            &path,
+            "#!/bin/sh\necho '{\"name\":\"deploy\",\"version\":\"1.0.0\",\"commands\":[\"deploy\"]}'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut manager = SubprocessPluginManager::new();
+        manager.policy.disabled = vec!["deploy".to_string()];
+        manager.try_load_plugin(&path, PluginOrigin::Local).unwrap();
+        assert!(manager.get_plugin("deploy").is_none());
+        assert!(!manager.handles_command("deploy prod"));
+        assert_eq!(manager.disabled_plugin_for("deploy prod"), Some("deploy"));
+        assert_eq!(manager.disabled_plugin_for("deployment"), None);
+    }
+
+    #[test]
+    fn test_command_conflicts_follow_priority_and_overrides() {
+        let plugin = |name: &str, origin| (name.to_string(), origin, vec!["worktree".to_string()]);
+        let plugins = || {
+            vec![
+                plugin("worktree", PluginOrigin::Path),
+                plugin("git", PluginOrigin::Global),
+            ]
+            .into_iter()
+        };
+
+        let conflicts = command_conflicts(plugins(), &PluginPolicy::default());
+        assert_eq!(conflicts[0].resolved_to, "git");
+        assert!(!conflicts[0].overridden);
+
+        let mut policy = PluginPolicy::default();
+        policy
+            .overrides
+            .insert("worktree".to_string(), "worktree".to_string());
+        let conflicts = command_conflicts(plugins(), &policy);
+        assert_eq!(conflicts[0].resolved_to, "worktree");
+        assert!(conflicts[0].overridden);
+    }
+
+    #[test]
+    fn test_execute_routes_conflicting_commands_to_the_winner() {
+        let mut manager = SubprocessPluginManager::new();
+        for (name, origin) in [
+            ("git", PluginOrigin::Path),
+            ("worktree", PluginOrigin::Local),
+        ] {
+            manager.plugins.insert(
+                name.to_string(),
+                SubprocessPlugin {
+                    path: PathBuf::from(format!("/fake/meta-{name}")),
+                    info: PluginInfo {
+                        name: name.to_string(),
+                        version: "1.0.0".to_string(),
+                        commands: vec!["git worktree".to_string()],
+                        description: None,
+                        help: None,
+                    },
+                },
+            );
+            manager.origins.insert(name.to_string(), origin);
+        }
+        let conflicts = manager.resolve_conflicts();
+        assert_eq!(conflicts.len(), 1);
+
+        let worktree = manager.get_plugin("worktree").unwrap();
+        let git = manager.get_plugin("git").unwrap();
+        assert!(manager.owns(worktree, "git worktree"));
+        assert!(!manager.owns(git, "git worktree"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_try_load_plugin_reads_help_topics_and_examples() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("meta-mig");
+        let info = serde_json::json!({
+            "name": "mig",
+            "version": "1.0.0",
+            "commands": ["mig run"],
+            "help": {
+                "usage": "meta mig <command>",
+                "commands": {"run": "Apply migrations"},
+                "topics": [
+                    {"name": "migrations", "summary": "Writing migrations", "body": "Body"},
+                    {"name": "broken"}
+                ],
+                "command_examples": {"mig run": ["meta mig run --to 42"]}
+            }
+        });
+        std::fs::write(&path, format!("#!/bin/sh\necho '{info}'\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut manager = SubprocessPluginManager::new();
+        manager.try_load_plugin(&path, PluginOrigin::Local).unwrap();
+
+        let topics = manager.help_topics();
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0].0, "mig");
+        assert_eq!(topics[0].1.name, "migrations");
+        assert_eq!(
+            manager.command_examples("mig  run"),
+            vec!["meta mig run --to 42"]
+        );
+        assert!(manager.command_examples("mig down").is_empty());
+
+        let help = manager.generate_fallback_help(manager.get_plugin("mig").unwrap());
+        assert!(help.contains("EXAMPLES (mig run):\n    meta mig run --to 42\n"));
+    }
+
+    #[test]
+    fn test_enforced_permissions_refuse_ungranted_plugins() {
+        let mut manager = SubprocessPluginManager::new();
+        manager.plugins.insert(
+            "mig".to_string(),
+            SubprocessPlugin {
+                path: PathBuf::from("/fake/meta-mig"),
+                info: PluginInfo {
+                    name: "mig".to_string(),
+                    version: "1.0.0".to_string(),
+                    commands: vec!["mig run".to_string()],
+                    description: None,
+                    help: None,
+                },
+            },
+        );
+        manager
+            .permissions
+            .insert("mig".to_string(), vec![Permission::Network]);
+        let plugin = manager.get_plugin("mig").unwrap();
+        assert_eq!(manager.authorize(plugin).unwrap(), None);
+
+        manager.policy = PluginPolicy::from_value(&serde_json::json!({
+            "plugin_permissions": {"mode": "enforce"}
+        }))
+        .unwrap();
+        let plugin = manager.get_plugin("mig").unwrap();
+        let err = manager.authorize(plugin).unwrap_err();
+        assert!(err.to_string().contains("needs network permission"));
+
+        manager.policy = PluginPolicy::from_value(&serde_json::json!({
+            "plugin_permissions": {"mode": "enforce", "allow": {"mig": ["network"]}}
+        }))
+        .unwrap();
+        let plugin = manager.get_plugin("mig").unwrap();
+        assert_eq!(
+            manager.authorize(plugin).unwrap(),
+            Some(vec![Permission::Network])
+        );
+    }
+
+    #[test]
+    fn test_plans_outside_the_workspace_need_write_permission() {
+        let plan: ExecutionPlan = serde_json::from_value(serde_json::json!({
+            "commands": [{"dir": ".", "cmd": "ls"}, {"dir": "/", "cmd": "rm -rf tmp"}]
+        }))
+        .unwrap();
+        assert!(check_plan_dirs("mig", &plan, None).is_ok());
+        assert!(check_plan_dirs("mig", &plan, Some(&[Permission::Write])).is_ok());
+        let err = check_plan_dirs("mig", &plan, Some(&[])).unwrap_err();
+        assert!(err.to_string().contains("outside the workspace (/)"));
+    }
+
+    #[test]
+    fn test_help_extras_tolerate_missing_help() {
+        let raw = serde_json::json!({"name": "x", "version": "1", "commands": []});
+        assert_eq!(
+            PluginHelpExtras::from_info(&raw),
+            PluginHelpExtras::default()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_plugin_reports_broken_executables() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let script = |name: &str, body: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+
+        let failing = check_plugin(script("meta-fail", "exit 2"), PluginOrigin::Local);
+        assert_eq!(failing.status, CheckStatus::Broken);
+        assert_eq!(
+            failing.problems,
+            vec!["--meta-plugin-info exited with code 2"]
+        );
+
+        let text = check_plugin(script("meta-mcp", "echo hello"), PluginOrigin::Path);
+        assert_eq!(text.status, CheckStatus::Ignored);
+
+        let incomplete = check_plugin(
+            script("meta-old", r#"echo '{"name":"old"}'"#),
+            PluginOrigin::Local,
+        );
+        assert_eq!(incomplete.status, CheckStatus::Broken);
+        assert!(incomplete.problems[0].contains("plugin protocol"));
+
+        let newer = check_plugin(
+            script(
+                "meta-new",
+                r#"echo '{"name":"new","version":"2.0.0","commands":["new"],"protocol_version":2}'"#,
+            ),
+            PluginOrigin::Global,
+        );
+        assert_eq!(newer.status, CheckStatus::Ok);
+        assert!(newer.problems[0].contains("protocol v2"));
+
+        let plain = dir.path().join("meta-plain");
+        std::fs::write(&plain, "").unwrap();
+        let plain = check_plugin(plain, PluginOrigin::Local);
+        assert_eq!(plain.problems, vec!["not executable"]);
+    }
+
     #[test]
     fn test_list_plugins_sorted() {
         let mut manager = SubprocessPluginManager::new();
@@ -0,0 +1,401 @@
+//! `meta sync`: reconcile the workspace directory with `.meta`.
+//!
+//! The workspace drifts in two ways: projects listed in `.meta` that are
+//! not cloned (missing), and git repos in the workspace that `.meta` does
+//! not list (extra). `meta sync` clones the missing ones; with `--prune`,
+//! extras are moved to `~/.meta/archive/` rather than deleted. Other
+//! commands print a one-line [`banner`] while the workspace is out of sync.
+
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use crate::clone::{self, CloneOptions, CloneStatus};
+use crate::config::{self, ProjectInfo};
+
+// ── Types ───────────────────────────────────────────────
+
+/// A project listed in `.meta` that has no checkout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingRepo {
+    pub name: String,
+    pub path: String,
+    /// `None` when there is nothing to clone from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub missing: Vec<MissingRepo>,
+    /// Git repos in the workspace that are not projects, relative to its root
+    pub extra: Vec<String>,
+}
+
+impl SyncReport {
+    pub fn in_sync(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    /// Archive extra repos
+    pub prune: bool,
+    /// Report what would happen without cloning or moving anything
+    pub dry_run: bool,
+    pub parallel: bool,
+    pub jobs: Option<usize>,
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Compare the projects in `.meta` with what is on disk under `meta_dir`.
+pub fn scan(meta_dir: &Path, projects: &[ProjectInfo], ignore: &[String]) -> SyncReport {
+    let missing = projects
+        .iter()
+        .filter(|p| {
+            let dir = meta_dir.join(&p.path);
+            // A local project without a URL is fine as a plain directory
+            !dir.join(".git").exists() && (p.repo.is_some() || !dir.exists())
+        })
+        .map(|p| MissingRepo {
+            name: p.name.clone(),
+            path: p.path.clone(),
+            url: p.repo.clone(),
+        })
+        .collect();
+    SyncReport {
+        missing,
+        extra: find_extras(meta_dir, projects, ignore),
+    }
+}
+
+/// One-line warning for commands run in an out-of-sync workspace, or
+/// `None` when it is in sync (or the config cannot be read).
+pub fn banner(config_path: &Path) -> Option<String> {
+    let meta_dir = config_path.parent()?;
+    let (projects, ignore) = config::parse_meta_config(config_path).ok()?;
+    let report = scan(meta_dir, &projects, &ignore);
+    if report.in_sync() {
+        return None;
+    }
+    let mut parts = Vec::new();
+    if !report.missing.is_empty() {
+        parts.push(format!("{} not cloned", report.missing.len()));
+    }
+    if !report.extra.is_empty() {
+        parts.push(format!("{} not in .meta", report.extra.len()));
+    }
+    Some(format!(
+        "{}: workspace is out of sync with .meta ({}); run 'meta sync'",
+        "warning".yellow().bold(),
+        parts.join(", ")
+    ))
+}
+
+/// Entry point for `meta sync [--prune]`.
+pub fn handle_sync(config_path: &Path, options: &SyncOptions, json: bool) -> Result<()> {
+    let meta_dir = config_path.parent().unwrap_or(Path::new("."));
+    let (projects, ignore) = config::parse_meta_config(config_path)?;
+    let report = scan(meta_dir, &projects, &ignore);
+
+    let mut archived = Vec::new();
+    if options.prune && !report.extra.is_empty() && !options.dry_run {
+        let root = archive_root(meta_dir)?;
+        for extra in &report.extra {
+            archived.push((extra.clone(), archive(meta_dir, extra, &root)?));
+        }
+    }
+
+    let mut cloned = Vec::new();
+    if report.missing.iter().any(|m| m.url.is_some()) {
+        let mut clone_options = CloneOptions::new(String::new());
+        clone_options.parallel = options.parallel;
+        clone_options.jobs = options.jobs;
+        clone_options.dry_run = options.dry_run;
+        clone_options.quiet = json;
+        cloned = clone::clone_missing(meta_dir, &clone_options)?
+            .into_iter()
+            .filter(|r| r.status != CloneStatus::Present && r.status != CloneStatus::NoRepo)
+            .collect();
+    }
+    let failed = cloned
+        .iter()
+        .filter(|r| r.status == CloneStatus::Failed)
+        .count();
+
+    if json {
+        let archived: Vec<serde_json::Value> = archived
+            .iter()
+            .map(|(from, to)| serde_json::json!({ "path": from, "archived_to": to }))
+            .collect();
+        let output = serde_json::json!({
+            "in_sync": report.in_sync(),
+            "missing": report.missing,
+            "extra": report.extra,
+            "cloned": cloned,
+            "archived": archived,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        print!("{}", format_report(&report, options, &archived));
+    }
+
+    if failed > 0 {
+        anyhow::bail!("Failed to clone {failed} project(s); re-run 'meta sync' to retry them");
+    }
+    Ok(())
+}
+
+/// Describe what is out of sync and what was done about it.
+pub fn format_report(
+    report: &SyncReport,
+    options: &SyncOptions,
+    archived: &[(String, PathBuf)],
+) -> String {
+    if report.in_sync() {
+        return format!("{} Workspace is in sync with .meta\n", "✓".green());
+    }
+
+    let mut out = String::new();
+    let uncloneable: Vec<&MissingRepo> =
+        report.missing.iter().filter(|m| m.url.is_none()).collect();
+    for missing in &uncloneable {
+        out.push_str(&format!(
+            "{} {}: {} does not exist and has no repo URL\n",
+            "✗".red(),
+            missing.name,
+            missing.path
+        ));
+    }
+
+    for extra in &report.extra {
+        match archived.iter().find(|(path, _)| path == extra) {
+            Some((_, to)) => out.push_str(&format!(
+                "{} {extra} archived to {}\n",
+                "→".cyan(),
+                to.display()
+            )),
+            None if options.prune && options.dry_run => {
+                out.push_str(&format!("{} Would archive {extra}\n", "[DRY RUN]".cyan()))
+            }
+            None => out.push_str(&format!("{} {extra} is not in .meta\n", "+".yellow())),
+        }
+    }
+    if !report.extra.is_empty() && !options.prune {
+        out.push_str(&format!(
+            "  {}\n",
+            "add them with 'meta project add <name> <path>', or archive them with 'meta sync --prune'"
+                .dimmed()
+        ));
+    }
+    out
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+/// Git repos under `meta_dir` that are not projects. Only directories on
+/// the way to a project are searched, so unrelated trees are not walked.
+fn find_extras(meta_dir: &Path, projects: &[ProjectInfo], ignore: &[String]) -> Vec<String> {
+    let project_paths: HashSet<PathBuf> = projects.iter().map(|p| normalize(&p.path)).collect();
+    let ancestors: HashSet<PathBuf> = project_paths
+        .iter()
+        .flat_map(|p| p.ancestors().skip(1).map(Path::to_path_buf))
+        .filter(|p| !p.as_os_str().is_empty())
+        .collect();
+    let worktrees = crate::worktree::worktrees_root(meta_dir);
+
+    let mut extras = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(meta_dir.join(&relative)) else {
+            continue;
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| !name.starts_with('.') && !ignore.contains(name))
+            .collect();
+        names.sort();
+        for name in names {
+            let child = relative.join(&name);
+            let dir = meta_dir.join(&child);
+            if project_paths.contains(&child) || dir == worktrees {
+                continue;
+            }
+            if dir.join(".git").exists() {
+                extras.push(child.to_string_lossy().replace('\\', "/"));
+            } else if ancestors.contains(&child) {
+                pending.push(child);
+            }
+        }
+    }
+    extras.sort();
+    extras
+}
+
+/// `./apps/web/` → `apps/web`
+fn normalize(path: &str) -> PathBuf {
+    Path::new(path)
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect()
+}
+
+/// `~/.meta/archive/<workspace>-<timestamp>`, for this run's extras.
+fn archive_root(meta_dir: &Path) -> Result<PathBuf> {
+    let workspace = meta_dir
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "workspace".to_string());
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    Ok(meta_core::data_dir::data_subdir("archive")?.join(format!("{workspace}-{stamp}")))
+}
+
+/// Move `meta_dir/<relative>` under `root`, keeping its relative path.
+fn archive(meta_dir: &Path, relative: &str, root: &Path) -> Result<PathBuf> {
+    let to = root.join(relative);
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let from = meta_dir.join(relative);
+    match std::fs::rename(&from, &to) {
+        Ok(()) => {}
+        // The archive lives under ~/.meta, often on another filesystem
+        Err(err) if crosses_devices(&err) => {
+            copy_tree(&from, &to)
+                .with_context(|| format!("Failed to copy {relative} to {}", to.display()))?;
+            std::fs::remove_dir_all(&from)
+                .with_context(|| format!("Copied {relative} but failed to remove it"))?;
+        }
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed to move {relative} to {}", to.display()))
+        }
+    }
+    Ok(to)
+}
+
+/// Whether a rename failed because source and target are on different
+/// filesystems.
+#[cfg(unix)]
+fn crosses_devices(err: &std::io::Error) -> bool {
+    err.raw_os_error() == Some(libc::EXDEV)
+}
+
+/// Whether a rename failed because source and target are on different
+/// volumes (`ERROR_NOT_SAME_DEVICE`).
+#[cfg(windows)]
+fn crosses_devices(err: &std::io::Error) -> bool {
+    err.raw_os_error() == Some(17)
+}
+
+/// Recreate the tree at `from` under `to`, symlinks included.
+fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry?;
+        let target = match entry.path().strip_prefix(from) {
+            Ok(rel) if !rel.as_os_str().is_empty() => to.join(rel),
+            _ => to.to_path_buf(),
+        };
+        let kind = entry.file_type();
+        if kind.is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else if kind.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(std::fs::read_link(entry.path())?, &target)?;
+            #[cfg(not(unix))]
+            std::fs::copy(entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::testing::init_repo;
+
+    fn project(name: &str, path: &str, repo: Option<&str>) -> ProjectInfo {
+        ProjectInfo {
+            name: name.to_string(),
+            path: path.to_string(),
+            repo: repo.map(str::to_string),
+            tags: vec![],
+            provides: vec![],
+            depends_on: vec![],
+            meta: false,
+        }
+    }
+
+    #[test]
+    fn reports_missing_and_extra_repos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        init_repo(&root.join("api"));
+        init_repo(&root.join("apps/web"));
+        init_repo(&root.join("apps/old-admin"));
+        init_repo(&root.join("scratch"));
+        init_repo(&root.join("node_modules/pkg"));
+        init_repo(&root.join(".worktrees/feature/api"));
+        std::fs::create_dir_all(root.join("notes")).unwrap();
+        std::fs::create_dir_all(root.join("tools/deep/repo/.git")).unwrap();
+
+        let projects = vec![
+            project("api", "api", Some("git@x:api.git")),
+            project("web", "./apps/web", Some("git@x:web.git")),
+            project("cli", "apps/cli", Some("git@x:cli.git")),
+            project("notes", "notes", None),
+            project("docs", "docs", None),
+        ];
+        let report = scan(root, &projects, &["node_modules".to_string()]);
+        let missing: Vec<&str> = report.missing.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(missing, ["cli", "docs"]);
+        assert_eq!(report.extra, ["apps/old-admin", "scratch"]);
+        assert!(!report.in_sync());
+
+        let text = console::strip_ansi_codes(&format_report(&report, &SyncOptions::default(), &[]))
+            .to_string();
+        assert!(text.contains("✗ docs: docs does not exist and has no repo URL"));
+        assert!(text.contains("+ scratch is not in .meta"));
+    }
+
+    #[test]
+    fn archive_keeps_the_relative_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let workspace = tmp.path().join("ws");
+        init_repo(&workspace.join("apps/old-admin"));
+        let root = tmp.path().join("archive/ws-1");
+
+        let to = archive(&workspace, "apps/old-admin", &root).unwrap();
+        assert_eq!(to, root.join("apps/old-admin"));
+        assert!(to.join(".git").is_dir());
+        assert!(!workspace.join("apps/old-admin").exists());
+        assert!(scan(&workspace, &[], &[]).in_sync());
+    }
+
+    #[test]
+    fn copy_tree_keeps_files_and_layout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("old-admin");
+        init_repo(&from);
+        std::fs::create_dir_all(from.join("src")).unwrap();
+        std::fs::write(from.join("src/main.rs"), "fn main() {}").unwrap();
+        let to = tmp.path().join("archive/old-admin");
+
+        copy_tree(&from, &to).unwrap();
+        assert!(to.join(".git").is_dir());
+        assert_eq!(
+            std::fs::read_to_string(to.join("src/main.rs")).unwrap(),
+            "fn main() {}"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Named tasks from the `tasks` section of `.meta`, run with `meta run <task>`.
+//!
+//! ```json
+//! "tasks": {
+//!   "build": { "default": "make", "api": "cargo build", "docs": null },
+//!   "lint": "npm run lint"
+//! }
+//! ```
+//!
+//! A string is shorthand for `{ "default": ... }`. Project keys override the
+//! default and `null` skips that project. The meta repo itself only runs a
+//! task that has a `"."` entry.
+
+use anyhow::{Context, Result};
+use loop_lib::DirCommand;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::config::{self, ProjectInfo};
+
+/// Key holding the command used by projects without an override.
+pub const DEFAULT_KEY: &str = "default";
+
+/// Key for the meta repo's own directory.
+pub const ROOT_KEY: &str = ".";
+
+/// A task: a default command plus per-project overrides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub name: String,
+    pub default: Option<String>,
+    /// Project name → command, or `None` to skip that project. Kept in file order.
+    pub overrides: Vec<(String, Option<String>)>,
+}
+
+impl Task {
+    /// Command to run for a project, or `None` if the task skips it.
+    pub fn command_for(&self, project: &str) -> Option<&str> {
+        match self.overrides.iter().find(|(name, _)| name == project) {
+            Some((_, command)) => command.as_deref(),
+            None if project == ROOT_KEY => None,
+            None => self.default.as_deref(),
+        }
+    }
+
+    fn from_value(name: &str, value: &Value) -> Result<Self> {
+        let mut task = Task {
+            name: name.to_string(),
+            default: None,
+            overrides: Vec::new(),
+        };
+        match value {
+            Value::String(command) => task.default = Some(command.clone()),
+            Value::Object(entries) => {
+                for (key, entry) in entries {
+                    let command = match entry {
+                        Value::String(command) => Some(command.clone()),
+                        Value::Null => None,
+                        _ => anyhow::bail!("tasks.{name}.{key}: expected a command string or null"),
+                    };
+                    if key == DEFAULT_KEY {
+                        task.default = command;
+                    } else {
+                        task.overrides.push((key.clone(), command));
+                    }
+                }
+            }
+            _ => anyhow::bail!("tasks.{name}: expected a command string or an object"),
+        }
+        Ok(task)
+    }
+}
+
+/// Commands planned for one task run.
+#[derive(Debug, Default)]
+pub struct TaskPlan {
+    pub commands: Vec<DirCommand>,
+    /// Projects the task does not apply to.
+    pub skipped: Vec<String>,
+}
+
+// ── Loading ─────────────────────────────────────────────
+
+/// Load the tasks defined in a meta config file, in file order.
+pub fn load_tasks(meta_path: &Path) -> Result<Vec<Task>> {
+    let value = config::read_meta_config_value(meta_path)?;
+    parse_tasks(&value).with_context(|| format!("Invalid tasks in {}", meta_path.display()))
+}
+
+/// Parse the `tasks` section of a config document. A missing section is empty.
+pub fn parse_tasks(config: &Value) -> Result<Vec<Task>> {
+    match config.get("tasks") {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Object(tasks)) => tasks
+            .iter()
+            .map(|(name, value)| Task::from_value(name, value))
+            .collect(),
+        Some(_) => anyhow::bail!("'tasks' must be an object"),
+    }
+}
+
+// ── Planning ────────────────────────────────────────────
+
+/// Resolve the command for every project (and the meta repo, if the task has
+/// a `"."` entry). `extra_args` are appended to each command.
+pub fn plan(
+    task: &Task,
+    meta_dir: &Path,
+    projects: &[&ProjectInfo],
+    extra_args: &[String],
+) -> TaskPlan {
+    let mut plan = TaskPlan::default();
+    let mut add = |name: &str, dir: &Path| match task.command_for(name) {
+        Some(command) => {
+            let mut cmd = command.to_string();
+            for arg in extra_args {
+                cmd.push(' ');
+                cmd.push_str(arg);
+            }
+            plan.commands.push(DirCommand {
+                dir: dir.to_string_lossy().to_string(),
+                cmd,
+                env: None,
+            });
+        }
+        None => plan.skipped.push(name.to_string()),
+    };
+
+    if task.command_for(ROOT_KEY).is_some() {
+        add(ROOT_KEY, meta_dir);
+    }
+    for project in projects {
+        add(&project.name, &meta_dir.join(&project.path));
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn project(name: &str, path: &str) -> ProjectInfo {
+        ProjectInfo {
+            name: name.to_string(),
+            path: path.to_string(),
+            repo: None,
+            tags: vec![],
+            provides: vec![],
+            depends_on: vec![],
+            meta: false,
+        }
+    }
+
+    #[test]
+    fn parses_shorthand_and_overrides() {
+        let tasks = parse_tasks(&json!({
+            "tasks": {
+                "build": {"default": "make", "api": "cargo build", "docs": null},
+                "lint": "npm run lint"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        let build = &tasks[0];
+        assert_eq!(build.name, "build");
+        assert_eq!(build.command_for("api"), Some("cargo build"));
+        assert_eq!(build.command_for("web"), Some("make"));
+        assert_eq!(build.command_for("docs"), None);
+        assert_eq!(build.command_for(ROOT_KEY), None);
+        assert_eq!(tasks[1].command_for("web"), Some("npm run lint"));
+    }
+
+    #[test]
+    fn task_without_default_only_runs_overrides() {
+        let tasks = parse_tasks(&json!({"tasks": {"deploy": {"api": "make deploy"}}})).unwrap();
+        assert_eq!(tasks[0].command_for("api"), Some("make deploy"));
+        assert_eq!(tasks[0].command_for("web"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_tasks() {
+        assert!(parse_tasks(&json!({"tasks": ["build"]})).is_err());
+        let err = parse_tasks(&json!({"tasks": {"build": {"api": 1}}})).unwrap_err();
+        assert!(err.to_string().contains("tasks.build.api"));
+        assert!(parse_tasks(&json!({"projects": {}})).unwrap().is_empty());
+    }
+
+    #[test]
+    fn plan_resolves_dirs_and_appends_args() {
+        let tasks = parse_tasks(&json!({
+            "tasks": {"test": {"default": "cargo test", ".": "make check", "web": null}}
+        }))
+        .unwrap();
+        let api = project("api", "services/api");
+        let web = project("web", "web");
+        let root = Path::new("/ws");
+
+        let plan = plan(&tasks[0], root, &[&api, &web], &["--release".to_string()]);
+        let planned: Vec<(&str, &str)> = plan
+            .commands
+            .iter()
+            .map(|c| (c.dir.as_str(), c.cmd.as_str()))
+            .collect();
+        assert_eq!(
+            planned,
+            vec![
+                ("/ws", "make check --release"),
+                ("/ws/services/api", "cargo test --release"),
+            ]
+        );
+        assert_eq!(plan.skipped, vec!["web"]);
+    }
+}
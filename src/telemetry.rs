@@ -0,0 +1,336 @@
+//! Optional OpenTelemetry tracing.
+//!
+//! With `--trace-endpoint <url>` or `OTEL_EXPORTER_OTLP_ENDPOINT` set, meta
+//! records spans for config loading, plugin discovery, every repo command
+//! and every plugin dispatch under one root span per invocation. When the
+//! command finishes they are posted as OTLP/HTTP JSON to
+//! `<endpoint>/v1/traces` (or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` as is),
+//! which Jaeger, Tempo and the OpenTelemetry Collector all accept.
+//! `OTEL_SERVICE_NAME` overrides the service name `meta`.
+//!
+//! Without an endpoint, [`Span`]s are no-ops. Export is best-effort: a
+//! collector that is down only costs a warning.
+
+use anyhow::{Context, Result};
+use colored::*;
+use std::cell::RefCell;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+pub const TRACES_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT";
+const SERVICE_NAME_ENV: &str = "OTEL_SERVICE_NAME";
+
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// OTLP span kind INTERNAL.
+const KIND_INTERNAL: u8 = 1;
+/// OTLP status code ERROR.
+const STATUS_ERROR: u8 = 2;
+
+static TRACER: Mutex<Option<Tracer>> = Mutex::new(None);
+
+thread_local! {
+    /// Ids of the spans open on this thread, innermost last. Worker threads
+    /// start empty, so their spans hang off the root.
+    static OPEN: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+// ── Types ───────────────────────────────────────────────
+
+/// A finished (or, for the root, still open) span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanData {
+    pub span_id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+    pub start_ns: u64,
+    pub end_ns: u64,
+    pub attributes: Vec<(String, String)>,
+    pub error: Option<String>,
+}
+
+impl SpanData {
+    fn begin(name: &str, parent_id: Option<String>) -> Self {
+        SpanData {
+            span_id: new_id(8),
+            parent_id,
+            name: name.to_string(),
+            start_ns: now_ns(),
+            end_ns: 0,
+            attributes: Vec::new(),
+            error: None,
+        }
+    }
+}
+
+struct Tracer {
+    url: String,
+    trace_id: String,
+    root: SpanData,
+    spans: Vec<SpanData>,
+}
+
+/// A span that ends when dropped. Does nothing unless tracing is on.
+#[must_use = "a span ends as soon as it is dropped"]
+pub struct Span {
+    data: Option<SpanData>,
+}
+
+impl Span {
+    /// Open a span, a child of the innermost span open on this thread (or
+    /// of the root span).
+    pub fn start(name: &str) -> Span {
+        let root_id = match TRACER.lock() {
+            Ok(tracer) => tracer.as_ref().map(|t| t.root.span_id.clone()),
+            Err(_) => None,
+        };
+        let Some(root_id) = root_id else {
+            return Span { data: None };
+        };
+        let parent = OPEN.with(|open| open.borrow().last().cloned());
+        let data = SpanData::begin(name, Some(parent.unwrap_or(root_id)));
+        OPEN.with(|open| open.borrow_mut().push(data.span_id.clone()));
+        Span { data: Some(data) }
+    }
+
+    /// Attach an attribute, e.g. `repo` or `exit_code`.
+    pub fn attr(&mut self, key: &str, value: impl ToString) {
+        if let Some(data) = &mut self.data {
+            data.attributes.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    /// Mark the span as failed.
+    pub fn fail(&mut self, message: impl ToString) {
+        if let Some(data) = &mut self.data {
+            data.error = Some(message.to_string());
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some(mut data) = self.data.take() else {
+            return;
+        };
+        data.end_ns = now_ns();
+        OPEN.with(|open| {
+            let mut open = open.borrow_mut();
+            if let Some(pos) = open.iter().rposition(|id| *id == data.span_id) {
+                open.remove(pos);
+            }
+        });
+        if let Ok(mut tracer) = TRACER.lock() {
+            if let Some(tracer) = tracer.as_mut() {
+                tracer.spans.push(data);
+            }
+        }
+    }
+}
+
+// ── Public API ──────────────────────────────────────────
+
+/// Where spans go: `--trace-endpoint`, then the OTLP environment
+/// variables. `None` turns tracing off.
+pub fn traces_url(flag: Option<&str>) -> Option<String> {
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    if let Some(url) = flag.map(str::to_string).or_else(|| env(ENDPOINT_ENV)) {
+        let url = url.trim_end_matches('/');
+        return Some(if url.ends_with("/v1/traces") {
+            url.to_string()
+        } else {
+            format!("{url}/v1/traces")
+        });
+    }
+    env(TRACES_ENDPOINT_ENV)
+}
+
+/// Start tracing this invocation under a root span called `name`, if an
+/// endpoint is configured. Returns whether tracing is on.
+pub fn init(flag: Option<&str>, name: &str, args: &[String]) -> bool {
+    let Some(url) = traces_url(flag) else {
+        return false;
+    };
+    let mut root = SpanData::begin(name, None);
+    root.attributes
+        .push(("meta.args".to_string(), args.join(" ")));
+    if let Ok(mut tracer) = TRACER.lock() {
+        *tracer = Some(Tracer {
+            url,
+            trace_id: new_id(16),
+            root,
+            spans: Vec::new(),
+        });
+    }
+    true
+}
+
+/// End the root span and export everything recorded. `error` marks the
+/// invocation as failed.
+pub fn shutdown(error: Option<&anyhow::Error>) {
+    let tracer = match TRACER.lock() {
+        Ok(mut tracer) => tracer.take(),
+        Err(_) => None,
+    };
+    let Some(mut tracer) = tracer else {
+        return;
+    };
+    tracer.root.end_ns = now_ns();
+    tracer.root.error = error.map(|e| format!("{e:#}"));
+    tracer.spans.push(tracer.root);
+    if let Err(e) = export(&tracer.url, &tracer.trace_id, &tracer.spans) {
+        eprintln!("{}: {e:#}", "warning".yellow().bold());
+    }
+}
+
+/// The OTLP/HTTP JSON body for `spans`.
+pub fn otlp_json(service: &str, trace_id: &str, spans: &[SpanData]) -> serde_json::Value {
+    let spans: Vec<serde_json::Value> = spans
+        .iter()
+        .map(|span| {
+            let mut value = serde_json::json!({
+                "traceId": trace_id,
+                "spanId": span.span_id,
+                "name": span.name,
+                "kind": KIND_INTERNAL,
+                "startTimeUnixNano": span.start_ns.to_string(),
+                "endTimeUnixNano": span.end_ns.to_string(),
+                "attributes": span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| attribute(key, value))
+                    .collect::<Vec<_>>(),
+            });
+            if let Some(parent) = &span.parent_id {
+                value["parentSpanId"] = parent.as_str().into();
+            }
+            if let Some(message) = &span.error {
+                value["status"] = serde_json::json!({
+                    "code": STATUS_ERROR,
+                    "message": message,
+                });
+            }
+            value
+        })
+        .collect();
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": { "attributes": [attribute("service.name", service)] },
+            "scopeSpans": [{
+                "scope": { "name": "meta", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+// ── Helpers ─────────────────────────────────────────────
+
+fn export(url: &str, trace_id: &str, spans: &[SpanData]) -> Result<()> {
+    let service = std::env::var(SERVICE_NAME_ENV).unwrap_or_else(|_| "meta".to_string());
+    ureq::post(url)
+        .set("User-Agent", "meta-cli")
+        .set("Content-Type", "application/json")
+        .timeout(EXPORT_TIMEOUT)
+        .send_string(&otlp_json(&service, trace_id, spans).to_string())
+        .with_context(|| format!("Failed to export traces to {url}"))?;
+    Ok(())
+}
+
+fn attribute(key: &str, value: &str) -> serde_json::Value {
+    serde_json::json!({ "key": key, "value": { "stringValue": value } })
+}
+
+fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
+
+/// A random id of `bytes` bytes as lowercase hex, as OTLP JSON expects.
+fn new_id(bytes: usize) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut id = String::new();
+    while id.len() < bytes * 2 {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u64(now_ns());
+        hasher.write_u32(std::process::id());
+        id.push_str(&format!("{:016x}", hasher.finish()));
+    }
+    id.truncate(bytes * 2);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_takes_precedence_and_gets_the_traces_path() {
+        assert_eq!(
+            traces_url(Some("http://localhost:4318/")).as_deref(),
+            Some("http://localhost:4318/v1/traces")
+        );
+        assert_eq!(
+            traces_url(Some("http://tempo:4318/v1/traces")).as_deref(),
+            Some("http://tempo:4318/v1/traces")
+        );
+    }
+
+    #[test]
+    fn ids_are_hex_of_the_right_length() {
+        let trace = new_id(16);
+        assert_eq!(trace.len(), 32);
+        assert!(trace.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(new_id(8).len(), 16);
+        assert_ne!(new_id(8), new_id(8));
+    }
+
+    #[test]
+    fn spans_are_noops_without_a_tracer() {
+        let mut span = Span::start("repo.command");
+        span.attr("repo", "api");
+        assert!(span.data.is_none());
+    }
+
+    #[test]
+    fn otlp_json_has_parents_attributes_and_errors() {
+        let root = SpanData {
+            span_id: "aaaaaaaaaaaaaaaa".to_string(),
+            parent_id: None,
+            name: "meta exec".to_string(),
+            start_ns: 10,
+            end_ns: 50,
+            attributes: Vec::new(),
+            error: None,
+        };
+        let repo = SpanData {
+            span_id: "bbbbbbbbbbbbbbbb".to_string(),
+            parent_id: Some(root.span_id.clone()),
+            name: "repo.command".to_string(),
+            start_ns: 20,
+            end_ns: 40,
+            attributes: vec![("repo".to_string(), "api".to_string())],
+            error: Some("exit code 1".to_string()),
+        };
+        let body = otlp_json("meta", &"c".repeat(32), &[repo, root]);
+        let resource = &body["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "meta"
+        );
+        let spans = &resource["scopeSpans"][0]["spans"];
+        assert_eq!(spans[0]["parentSpanId"], "aaaaaaaaaaaaaaaa");
+        assert_eq!(spans[0]["startTimeUnixNano"], "20");
+        assert_eq!(spans[0]["attributes"][0]["key"], "repo");
+        assert_eq!(spans[0]["status"]["code"], 2);
+        assert_eq!(spans[0]["status"]["message"], "exit code 1");
+        assert!(spans[1].get("parentSpanId").is_none());
+        assert!(spans[1].get("status").is_none());
+        assert_eq!(spans[1]["traceId"], "c".repeat(32));
+    }
+}
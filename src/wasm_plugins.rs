@@ -0,0 +1,322 @@
+//! WebAssembly plugins.
+//!
+//! A `meta-<name>.wasm` file in a plugin directory is a plugin compiled to
+//! WASI, either as a component (`wasi:cli/command`) or as a preview 1
+//! module. It speaks the same protocol as a subprocess plugin (arguments,
+//! JSON on stdin/stdout) but runs inside wasmtime, embedded in meta, so one
+//! build works on every platform and the plugin only sees what is granted
+//! to it:
+//!
+//! - the current directory (the workspace), mounted at the same path
+//! - the environment variables meta passes explicitly, or meta's whole
+//!   environment for plugins granted the `env` permission
+//! - no network, and no other part of the file system
+//!
+//! A run is also bounded in memory ([`MEMORY_LIMIT`]) and in work
+//! ([`FUEL`]), so a broken plugin fails instead of hanging meta. Plans a
+//! plugin returns still run natively, as for any other plugin.
+//!
+//! The guest's stdout is collected and handed back when it exits, so
+//! streamed events are handled once the plugin is done.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::sync::OnceLock;
+use wasmtime::component::{Component, Linker as ComponentLinker, ResourceTable};
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtx, WasiCtxBuilder, WasiView};
+
+/// File extension of WASM plugins.
+pub const EXTENSION: &str = "wasm";
+
+/// Linear memory a plugin may use.
+pub const MEMORY_LIMIT: usize = 512 * 1024 * 1024;
+
+/// Fuel a plugin run may burn, roughly one unit per WebAssembly
+/// instruction.
+pub const FUEL: u64 = 50_000_000_000;
+
+/// Output a plugin may write to stdout.
+const MAX_OUTPUT: usize = 64 * 1024 * 1024;
+
+/// Whether `path` is a WASM plugin module.
+pub fn is_wasm_plugin(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == EXTENSION) && path.is_file()
+}
+
+/// One run of a plugin.
+#[derive(Debug, Clone, Copy)]
+pub struct Invocation<'a> {
+    /// Arguments after the program name, e.g. `--meta-plugin-exec`
+    pub args: &'a [&'a str],
+    pub stdin: &'a [u8],
+    /// Variables set in the guest
+    pub env: &'a [(&'a str, &'a str)],
+    /// Pass meta's whole environment too (the `env` permission)
+    pub inherit_env: bool,
+    /// The directory the plugin may read and write
+    pub dir: &'a Path,
+    /// Let the plugin write to meta's stderr; otherwise it is discarded
+    pub stderr: bool,
+}
+
+/// How a plugin run ended.
+#[derive(Debug)]
+pub struct Output {
+    /// The guest's exit code; 0 on success
+    pub code: i32,
+    pub stdout: Vec<u8>,
+}
+
+impl Output {
+    pub fn success(&self) -> bool {
+        self.code == 0
+    }
+}
+
+/// Run the plugin at `module` in the sandbox described by `invocation`.
+/// Errors are failures to run it at all: an invalid module, a trap, or
+/// running out of fuel.
+pub fn run(module: &Path, invocation: &Invocation) -> Result<Output> {
+    run_with_fuel(module, invocation, FUEL)
+}
+
+fn run_with_fuel(module: &Path, invocation: &Invocation, fuel: u64) -> Result<Output> {
+    let bytes =
+        std::fs::read(module).with_context(|| format!("Failed to read {}", module.display()))?;
+    let name = module
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stdout = MemoryOutputPipe::new(MAX_OUTPUT);
+    let wasi = context(&name, invocation, &stdout)?;
+
+    let engine = engine()?;
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(MEMORY_LIMIT)
+        .instances(100)
+        .build();
+    let result = if is_component(&bytes) {
+        run_component(engine, &bytes, wasi, limits, fuel)
+    } else {
+        run_module(engine, &bytes, wasi, limits, fuel)
+    };
+    let code = match result {
+        Ok(code) => code,
+        Err(e) => match e.downcast_ref::<I32Exit>() {
+            Some(exit) => exit.0,
+            None if matches!(e.downcast_ref::<Trap>(), Some(Trap::OutOfFuel)) => {
+                anyhow::bail!("WASM plugin {name} ran out of fuel (stopped after {fuel} units)")
+            }
+            None => return Err(e.context(format!("WASM plugin {name} failed"))),
+        },
+    };
+    Ok(Output {
+        code,
+        stdout: stdout.contents().to_vec(),
+    })
+}
+
+/// Whether `bytes` is a component rather than a core module: both start
+/// with `\0asm`, then a component has its own version and layer.
+pub fn is_component(bytes: &[u8]) -> bool {
+    bytes.get(4..8) == Some(&[0x0d, 0x00, 0x01, 0x00])
+}
+
+/// The engine shared by every plugin run, with fuel metering on and
+/// compiled code cached on disk where wasmtime's cache can be used.
+fn engine() -> Result<&'static Engine> {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    if let Some(engine) = ENGINE.get() {
+        return Ok(engine);
+    }
+    let mut config = Config::new();
+    config.consume_fuel(true).wasm_component_model(true);
+    if let Err(e) = config.cache_config_load_default() {
+        log::debug!("WASM compilation cache unavailable: {e:#}");
+    }
+    let engine = Engine::new(&config).context("Failed to set up the WASM runtime")?;
+    Ok(ENGINE.get_or_init(|| engine))
+}
+
+/// The guest's WASI context: arguments, pipes, environment and the one
+/// directory it may use. Sockets are not granted.
+fn context(
+    name: &str,
+    invocation: &Invocation,
+    stdout: &MemoryOutputPipe,
+) -> Result<WasiCtxBuilder> {
+    let mut builder = WasiCtxBuilder::new();
+    builder
+        .arg(name)
+        .args(invocation.args)
+        .stdin(MemoryInputPipe::new(invocation.stdin.to_vec()))
+        .stdout(stdout.clone());
+    if invocation.stderr {
+        builder.inherit_stderr();
+    }
+    if invocation.inherit_env {
+        builder.inherit_env();
+    }
+    for (key, value) in invocation.env {
+        builder.env(key, value);
+    }
+    let guest_dir = invocation.dir.to_string_lossy();
+    builder
+        .preopened_dir(
+            invocation.dir,
+            &*guest_dir,
+            DirPerms::all(),
+            FilePerms::all(),
+        )
+        .with_context(|| format!("Failed to grant {} to the plugin", invocation.dir.display()))?;
+    Ok(builder)
+}
+
+struct ComponentHost {
+    ctx: WasiCtx,
+    table: ResourceTable,
+    limits: StoreLimits,
+}
+
+impl WasiView for ComponentHost {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.ctx
+    }
+}
+
+fn run_component(
+    engine: &Engine,
+    bytes: &[u8],
+    mut wasi: WasiCtxBuilder,
+    limits: StoreLimits,
+    fuel: u64,
+) -> Result<i32> {
+    let component = Component::new(engine, bytes)?;
+    let mut linker = ComponentLinker::new(engine);
+    wasmtime_wasi::add_to_linker_sync(&mut linker)?;
+    let host = ComponentHost {
+        ctx: wasi.build(),
+        table: ResourceTable::new(),
+        limits,
+    };
+    let mut store = Store::new(engine, host);
+    store.limiter(|host| &mut host.limits);
+    store.set_fuel(fuel)?;
+    let command =
+        wasmtime_wasi::bindings::sync::Command::instantiate(&mut store, &component, &linker)?;
+    // `run` only says ok or not; an explicit exit code arrives as I32Exit
+    match command.wasi_cli_run().call_run(&mut store)? {
+        Ok(()) => Ok(0),
+        Err(()) => Ok(1),
+    }
+}
+
+struct ModuleHost {
+    ctx: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+fn run_module(
+    engine: &Engine,
+    bytes: &[u8],
+    mut wasi: WasiCtxBuilder,
+    limits: StoreLimits,
+    fuel: u64,
+) -> Result<i32> {
+    let module = Module::new(engine, bytes)?;
+    let mut linker: Linker<ModuleHost> = Linker::new(engine);
+    preview1::add_to_linker_sync(&mut linker, |host| &mut host.ctx)?;
+    let host = ModuleHost {
+        ctx: wasi.build_p1(),
+        limits,
+    };
+    let mut store = Store::new(engine, host);
+    store.limiter(|host| &mut host.limits);
+    store.set_fuel(fuel)?;
+    linker.module(&mut store, "", &module)?;
+    linker
+        .get_default(&mut store, "")?
+        .typed::<(), ()>(&store)?
+        .call(&mut store, ())?;
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_wasm_files_are_wasm_plugins() {
+        let dir = tempfile::tempdir().unwrap();
+        let module = dir.path().join("meta-mig.wasm");
+        std::fs::write(&module, b"\0asm").unwrap();
+        std::fs::write(dir.path().join("meta-mig"), "").unwrap();
+        assert!(is_wasm_plugin(&module));
+        assert!(!is_wasm_plugin(&dir.path().join("meta-mig")));
+        assert!(!is_wasm_plugin(&dir.path().join("missing.wasm")));
+    }
+
+    #[test]
+    fn components_are_told_from_core_modules() {
+        assert!(is_component(b"\0asm\x0d\0\x01\0"));
+        assert!(!is_component(b"\0asm\x01\0\0\0"));
+        assert!(!is_component(b"\0asm"));
+    }
+
+    /// A preview 1 module that writes its stdin back to stdout.
+    const ECHO: &str = r#"(module
+        (import "wasi_snapshot_preview1" "fd_read"
+            (func $fd_read (param i32 i32 i32 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 64))
+            (i32.store (i32.const 4) (i32.const 256))
+            (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (i32.store (i32.const 4) (i32.load (i32.const 8)))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))"#;
+
+    const SPIN: &str = r#"(module
+        (memory (export "memory") 1)
+        (func (export "_start") (loop (br 0))))"#;
+
+    fn invocation(dir: &Path) -> Invocation<'_> {
+        Invocation {
+            args: &["--meta-plugin-exec"],
+            stdin: b"{\"command\":\"mig\"}",
+            env: &[],
+            inherit_env: false,
+            dir,
+            stderr: false,
+        }
+    }
+
+    #[test]
+    fn modules_run_with_piped_stdio() {
+        let dir = tempfile::tempdir().unwrap();
+        let module = dir.path().join("meta-echo.wasm");
+        std::fs::write(&module, wasmtime::wat::parse_str(ECHO).unwrap()).unwrap();
+
+        let output = run(&module, &invocation(dir.path())).unwrap();
+        assert!(output.success());
+        assert_eq!(output.stdout, b"{\"command\":\"mig\"}");
+    }
+
+    #[test]
+    fn runaway_plugins_run_out_of_fuel() {
+        let dir = tempfile::tempdir().unwrap();
+        let module = dir.path().join("meta-spin.wasm");
+        std::fs::write(&module, wasmtime::wat::parse_str(SPIN).unwrap()).unwrap();
+
+        let err = run_with_fuel(&module, &invocation(dir.path()), 1_000_000).unwrap_err();
+        assert!(err.to_string().contains("ran out of fuel"));
+    }
+}
@@ -0,0 +1,260 @@
+//! File watching for `meta watch -- <cmd>`.
+//!
+//! Every project directory is watched recursively. Events are collected
+//! until nothing has changed for the debounce interval, then the command
+//! reruns in just the directories that saw a change. Changes inside `.git`,
+//! under a directory named in the .meta `ignore` list, or ignored by git
+//! (`.gitignore` and friends) do not count, so build output written by the
+//! command itself does not trigger another run. With
+//! [`WatchOptions::git_state`], moves of HEAD, the index and refs inside
+//! `.git` count too, for watchers that follow commits and checkouts.
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::runner;
+
+/// Default quiet period before a batch of changes triggers a run.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How often the loop wakes up to check the debounce deadline and Ctrl-C.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Options for [`watch`].
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    pub debounce: Duration,
+    /// Directory names from the .meta `ignore` list
+    pub ignore: Vec<String>,
+    /// Also count changes to `.git/HEAD`, `.git/index` and `.git/refs`
+    pub git_state: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            debounce: DEFAULT_DEBOUNCE,
+            ignore: Vec::new(),
+            git_state: false,
+        }
+    }
+}
+
+/// Watch `dirs` and call `on_change` with the directories that changed,
+/// in `dirs` order, after each quiet period. Runs until interrupted; an
+/// error from `on_change` is reported and watching continues.
+pub fn watch(
+    dirs: &[String],
+    options: &WatchOptions,
+    mut on_change: impl FnMut(&[String]) -> Result<()>,
+) -> Result<()> {
+    let roots: Vec<PathBuf> = dirs.iter().map(|d| canonical(Path::new(d))).collect();
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if !matches!(event.kind, EventKind::Access(_)) {
+                let _ = tx.send(event.paths);
+            }
+        }
+    })
+    .context("Failed to start the file watcher")?;
+    for root in &roots {
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", root.display()))?;
+    }
+
+    // Owning directory index → changed paths in it
+    let mut pending: BTreeMap<usize, BTreeSet<PathBuf>> = BTreeMap::new();
+    let mut deadline: Option<Instant> = None;
+    loop {
+        if let Some(signal) = runner::interrupted() {
+            return Err(crate::error::Exit(128 + signal).into());
+        }
+
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(paths) => {
+                for path in paths {
+                    let Some(i) = owning_dir(&roots, &path) else {
+                        continue;
+                    };
+                    let git_state = options.git_state && is_git_state(&roots[i], &path);
+                    if git_state || !in_ignored_dir(&roots[i], &path, &options.ignore) {
+                        pending.entry(i).or_default().insert(path);
+                        deadline = Some(Instant::now() + options.debounce);
+                    }
+                }
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                anyhow::bail!("The file watcher stopped unexpectedly")
+            }
+        }
+
+        if deadline.is_none_or(|d| Instant::now() < d) {
+            continue;
+        }
+        deadline = None;
+        let changed: Vec<String> = std::mem::take(&mut pending)
+            .into_iter()
+            .filter(|(i, paths)| !git_ignores_all(&roots[*i], paths))
+            .map(|(i, _)| dirs[i].clone())
+            .collect();
+        if changed.is_empty() {
+            continue;
+        }
+        match on_change(&changed) {
+            // The run already reported its own exit code
+            Err(e) if crate::error::exit_request(&e).is_some() => {}
+            Err(e) => eprintln!("{e:#}"),
+            Ok(()) => {}
+        }
+    }
+}
+
+/// Index of the innermost root containing `path`.
+fn owning_dir(roots: &[PathBuf], path: &Path) -> Option<usize> {
+    roots
+        .iter()
+        .enumerate()
+        .filter(|(_, root)| path.starts_with(root))
+        .max_by_key(|(_, root)| root.components().count())
+        .map(|(i, _)| i)
+}
+
+/// Whether `path` is inside `.git` or an ignored directory below `root`.
+fn in_ignored_dir(root: &Path, path: &Path, ignore: &[String]) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    relative.components().any(|c| match c {
+        Component::Normal(name) => name
+            .to_str()
+            .is_some_and(|name| name == ".git" || ignore.iter().any(|i| i == name)),
+        _ => false,
+    })
+}
+
+/// Whether `path` is the HEAD, index or a ref of the repo at `root`.
+fn is_git_state(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root.join(".git")) else {
+        return false;
+    };
+    relative == Path::new("HEAD") || relative == Path::new("index") || relative.starts_with("refs")
+}
+
+/// Whether git ignores every one of `paths` in the repo at `root`. Outside
+/// a git repo nothing is ignored.
+fn git_ignores_all(root: &Path, paths: &BTreeSet<PathBuf>) -> bool {
+    let Ok(mut child) = Command::new("git")
+        .args(["check-ignore", "--stdin"])
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        for path in paths {
+            let _ = writeln!(stdin, "{}", path.display());
+        }
+    }
+    let Ok(output) = child.wait_with_output() else {
+        return false;
+    };
+    // check-ignore prints each ignored path; exit 1 means none were ignored
+    let ignored: BTreeSet<&str> = std::str::from_utf8(&output.stdout)
+        .unwrap_or_default()
+        .lines()
+        .collect();
+    output.status.success()
+        && paths
+            .iter()
+            .all(|p| ignored.contains(p.display().to_string().as_str()))
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_map_to_the_innermost_directory() {
+        let roots = vec![
+            PathBuf::from("/ws/api"),
+            PathBuf::from("/ws/api/plugins/auth"),
+        ];
+        assert_eq!(
+            owning_dir(&roots, Path::new("/ws/api/src/main.rs")),
+            Some(0)
+        );
+        assert_eq!(
+            owning_dir(&roots, Path::new("/ws/api/plugins/auth/x")),
+            Some(1)
+        );
+        assert_eq!(owning_dir(&roots, Path::new("/ws/web/index.js")), None);
+
+        let ignore = vec!["node_modules".to_string()];
+        let root = Path::new("/ws/api");
+        assert!(in_ignored_dir(
+            root,
+            Path::new("/ws/api/.git/index"),
+            &ignore
+        ));
+        assert!(in_ignored_dir(
+            root,
+            Path::new("/ws/api/node_modules/a/b.js"),
+            &ignore
+        ));
+        assert!(!in_ignored_dir(
+            root,
+            Path::new("/ws/api/src/node.js"),
+            &ignore
+        ));
+
+        assert!(is_git_state(root, Path::new("/ws/api/.git/index")));
+        assert!(is_git_state(
+            root,
+            Path::new("/ws/api/.git/refs/heads/main")
+        ));
+        assert!(!is_git_state(
+            root,
+            Path::new("/ws/api/.git/objects/ab/cdef")
+        ));
+        assert!(!is_git_state(root, Path::new("/ws/api/src/index")));
+    }
+
+    #[test]
+    fn gitignored_paths_do_not_count() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = canonical(tmp.path());
+        Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(&root)
+            .status()
+            .unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+
+        let paths =
+            |names: &[&str]| -> BTreeSet<PathBuf> { names.iter().map(|n| root.join(n)).collect() };
+        assert!(git_ignores_all(&root, &paths(&["target/debug/app"])));
+        assert!(!git_ignores_all(
+            &root,
+            &paths(&["target/debug/app", "src/lib.rs"])
+        ));
+        assert!(!git_ignores_all(&root, &paths(&["src/lib.rs"])));
+    }
+}
@@ -0,0 +1,139 @@
+//! Running commands in a subdirectory of each repo.
+//!
+//! Some projects keep what commands care about in a subdirectory, e.g.
+//! `frontend/` inside a repo. A project entry's `workdir` makes every
+//! command for that repo run there:
+//!
+//! ```json
+//! "projects": {
+//!   "web": { "repo": "git@github.com:org/web.git", "workdir": "frontend" }
+//! }
+//! ```
+//!
+//! `--workdir <rel>` runs every repo's command in `<rel>` instead,
+//! overriding `workdir`. Both must be relative paths inside the repo.
+
+use anyhow::{Context, Result};
+use loop_lib::DirCommand;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config;
+
+/// Check that `rel` is a relative path that stays inside the repo.
+pub fn parse(rel: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(rel);
+    let inside = path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if rel.is_empty() || !inside {
+        anyhow::bail!("'{rel}' must be a relative path inside the repo");
+    }
+    Ok(path)
+}
+
+/// The subdirectory each command runs in, keyed by `DirCommand::dir`:
+/// `flag` for all of them if given, otherwise each project's `workdir`
+/// from the config at `config_path`. Projects are matched to directories
+/// by path, then by name for worktree checkouts.
+pub fn resolve(
+    commands: &[DirCommand],
+    config_path: Option<&Path>,
+    flag: Option<&str>,
+) -> Result<HashMap<String, PathBuf>> {
+    if let Some(flag) = flag {
+        let workdir = parse(flag).context("Invalid --workdir")?;
+        return Ok(commands
+            .iter()
+            .map(|c| (c.dir.clone(), workdir.clone()))
+            .collect());
+    }
+    let Some(config_path) = config_path else {
+        return Ok(HashMap::new());
+    };
+    let meta_dir = config_path.parent().unwrap_or(Path::new("."));
+    let (projects, _ignore) = config::parse_meta_config(config_path)?;
+    let value = config::read_meta_config_value(config_path)?;
+
+    let mut workdirs = HashMap::new();
+    for command in commands {
+        let dir = Path::new(&command.dir);
+        let base = dir.file_name().and_then(|n| n.to_str()).unwrap_or(".");
+        let project = projects
+            .iter()
+            .find(|p| meta_dir.join(&p.path) == dir)
+            .or_else(|| projects.iter().find(|p| p.name == base));
+        let Some(project) = project else {
+            continue;
+        };
+        let Some(workdir) = value["projects"][&project.name].get("workdir") else {
+            continue;
+        };
+        let workdir = workdir
+            .as_str()
+            .context("expected a string")
+            .and_then(parse)
+            .with_context(|| {
+                format!(
+                    "Invalid projects.{}.workdir in {}",
+                    project.name,
+                    config_path.display()
+                )
+            })?;
+        workdirs.insert(command.dir.clone(), workdir);
+    }
+    Ok(workdirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir_cmd(dir: &Path) -> DirCommand {
+        DirCommand {
+            dir: dir.display().to_string(),
+            cmd: "pwd".to_string(),
+            env: None,
+        }
+    }
+
+    #[test]
+    fn workdir_must_stay_inside_the_repo() {
+        assert_eq!(
+            parse("frontend/app").unwrap(),
+            PathBuf::from("frontend/app")
+        );
+        assert!(parse("./frontend").is_ok());
+        assert!(parse("../other").is_err());
+        assert!(parse("/tmp").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn project_workdir_applies_unless_the_flag_overrides_it() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let config_path = tmp.path().join(".meta");
+        std::fs::write(
+            &config_path,
+            r#"{"projects": {
+                "web": {"repo": "git@example.com:web.git", "workdir": "frontend"},
+                "api": "git@example.com:api.git"
+            }}"#,
+        )
+        .unwrap();
+        let commands = [
+            dir_cmd(&tmp.path().join("web")),
+            dir_cmd(&tmp.path().join("api")),
+        ];
+
+        let workdirs = resolve(&commands, Some(&config_path), None).unwrap();
+        assert_eq!(workdirs.len(), 1);
+        assert_eq!(workdirs[&commands[0].dir], PathBuf::from("frontend"));
+
+        let workdirs = resolve(&commands, Some(&config_path), Some("src")).unwrap();
+        assert_eq!(workdirs[&commands[0].dir], PathBuf::from("src"));
+        assert_eq!(workdirs[&commands[1].dir], PathBuf::from("src"));
+
+        assert!(resolve(&commands, None, Some("../up")).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! Registry of known workspaces for `meta workspaces` and `-w <name>`.
+//!
+//! Every invocation inside a meta workspace records its root in
+//! `~/.meta/workspaces.json` under a short name (the directory name, with a
+//! numeric suffix when two workspaces share one). `meta -w <name> ...` then
+//! runs any command in that workspace from anywhere, like `-C <path>`.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How stale `last_used` may get before an invocation rewrites it, so
+/// most invocations only read the registry.
+const TOUCH_INTERVAL_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceEntry {
+    pub path: PathBuf,
+    pub last_used: DateTime<Utc>,
+}
+
+/// Workspace name → where it is and when it was last used.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Registry {
+    #[serde(default)]
+    pub workspaces: BTreeMap<String, WorkspaceEntry>,
+}
+
+fn registry_path() -> PathBuf {
+    meta_core::meta_dir().join("workspaces.json")
+}
+
+impl Registry {
+    pub fn load() -> Result<Self> {
+        Self::load_from(&registry_path())
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        meta_core::store::read(path)
+    }
+
+    /// Name of the workspace rooted at `root`, if registered.
+    pub fn name_of(&self, root: &Path) -> Option<&str> {
+        self.workspaces
+            .iter()
+            .find(|(_, e)| e.path == root)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Root of the workspace called `name`.
+    pub fn resolve(&self, name: &str) -> Result<&Path> {
+        match self.workspaces.get(name) {
+            Some(entry) => Ok(&entry.path),
+            None if self.workspaces.is_empty() => {
+                anyhow::bail!("Unknown workspace '{name}' (no workspaces recorded yet)")
+            }
+            None => anyhow::bail!(
+                "Unknown workspace '{name}' (known: {})",
+                self.workspaces
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Record that `root` was used at `now`, registering it under a free
+    /// name if it is new. Returns whether anything changed.
+    pub fn touch(&mut self, root: &Path, now: DateTime<Utc>) -> bool {
+        if let Some(name) = self.name_of(root).map(str::to_string) {
+            let entry = self.workspaces.get_mut(&name).expect("name_of found it");
+            if now - entry.last_used < Duration::minutes(TOUCH_INTERVAL_MINUTES) {
+                return false;
+            }
+            entry.last_used = now;
+            return true;
+        }
+
+        let base = root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "workspace".to_string());
+        let name = std::iter::once(base.clone())
+            .chain((2..).map(|n| format!("{base}-{n}")))
+            .find(|name| !self.workspaces.contains_key(name))
+            .expect("an unused name exists");
+        self.workspaces.insert(
+            name,
+            WorkspaceEntry {
+                path: root.to_path_buf(),
+                last_used: now,
+            },
+        );
+        true
+    }
+}
+
+/// Record the workspace rooted at `root` in the registry. Best-effort: the
+/// registry is a convenience and must never fail a command.
+pub fn record(root: &Path) {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let now = Utc::now();
+    // Most invocations find the entry fresh and never take the lock
+    if let Ok(mut registry) = Registry::load() {
+        if !registry.touch(&root, now) {
+            return;
+        }
+    }
+
+    let result = (|| -> Result<()> {
+        meta_core::data_dir::ensure_meta_dir()?;
+        let lock = meta_core::meta_dir().join("workspaces.lock");
+        meta_core::store::update(&registry_path(), &lock, |registry: &mut Registry| {
+            registry.touch(&root, now);
+        })?;
+        Ok(())
+    })();
+    if let Err(e) = result {
+        log::debug!("Could not record workspace {}: {e}", root.display());
+    }
+}
+
+/// Root of the workspace called `name`, for `-w <name>`.
+pub fn resolve(name: &str) -> Result<PathBuf> {
+    Ok(Registry::load()?.resolve(name)?.to_path_buf())
+}
+
+/// `meta workspaces list`: known workspaces, most recently used first.
+pub fn handle_list(json: bool) -> Result<()> {
+    let registry = Registry::load()?;
+    let mut entries: Vec<(&String, &WorkspaceEntry)> = registry.workspaces.iter().collect();
+    entries.sort_by(|a, b| b.1.last_used.cmp(&a.1.last_used));
+
+    if json {
+        let list: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|(name, e)| {
+                serde_json::json!({
+                    "name": name,
+                    "path": e.path,
+                    "last_used": e.last_used,
+                    "exists": e.path.exists(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&list)?);
+        return Ok(());
+    }
+    if entries.is_empty() {
+        println!("No workspaces recorded yet.");
+        return Ok(());
+    }
+
+    let width = entries
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, entry) in entries {
+        let missing = if entry.path.exists() {
+            String::new()
+        } else {
+            format!("  {}", "(missing)".red())
+        };
+        println!(
+            "  {:<width$}  {}  {}{missing}",
+            name.bold(),
+            entry
+                .last_used
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+                .dimmed(),
+            entry.path.display()
+        );
+    }
+    Ok(())
+}
+
+/// `meta workspaces switch <name>`: print the workspace root, for
+/// `cd "$(meta workspaces switch <name>)"`, and mark it as used.
+pub fn handle_switch(name: &str, json: bool) -> Result<()> {
+    let path = resolve(name)?;
+    anyhow::ensure!(
+        path.exists(),
+        "Workspace '{name}' no longer exists at {}",
+        path.display()
+    );
+    record(&path);
+    if json {
+        println!("{}", serde_json::json!({ "name": name, "path": path }));
+    } else {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap() + Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn new_workspaces_get_unique_names() {
+        let mut registry = Registry::default();
+        assert!(registry.touch(Path::new("/work/platform"), at(0)));
+        assert!(registry.touch(Path::new("/other/platform"), at(0)));
+        assert!(registry.touch(Path::new("/work/tools"), at(0)));
+
+        let names: Vec<&str> = registry.workspaces.keys().map(String::as_str).collect();
+        assert_eq!(names, ["platform", "platform-2", "tools"]);
+        assert_eq!(
+            registry.resolve("platform-2").unwrap(),
+            Path::new("/other/platform")
+        );
+        let err = registry.resolve("web").unwrap_err().to_string();
+        assert!(err.contains("known: platform, platform-2, tools"), "{err}");
+    }
+
+    #[test]
+    fn last_used_is_only_rewritten_when_stale() {
+        let mut registry = Registry::default();
+        let root = Path::new("/work/platform");
+        registry.touch(root, at(0));
+        assert!(!registry.touch(root, at(5)));
+        assert!(registry.touch(root, at(15)));
+        assert_eq!(registry.workspaces["platform"].last_used, at(15));
+        assert_eq!(registry.name_of(root), Some("platform"));
+    }
+}
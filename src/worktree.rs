@@ -6,10 +6,20 @@
 //! for worktree management commands.
 
 use anyhow::{Context, Result};
-use serde::Serialize;
+use colored::*;
+use loop_lib::DirCommand;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
-use crate::git_utils;
+use crate::clone::{self, CloneOptions, CloneStatus};
+use crate::error::MetaError;
+use crate::git_utils::{self, git};
+use crate::hooks::{self, HookPayload, RepoOutcome};
+use crate::journal::{Journal, OpStatus, UndoAction};
+use crate::worktree_template::{self, ApplyOptions, CloneMode};
 
 /// Discovered information about a repo within a worktree set.
 #[derive(Debug, Clone, Serialize)]
@@ -195,3 +205,2812 @@ fn source_repo_from_gitfile(git_file: &Path) -> Result<PathBuf> {
 
     Ok(repo_root.to_path_buf())
 }
+
+// ── Sets ────────────────────────────────────────────────
+
+/// Fail unless `name` can name a worktree set: a single directory name that
+/// is not hidden, so it cannot point outside the worktrees directory.
+pub fn check_name(name: &str) -> Result<()> {
+    anyhow::ensure!(
+        !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\']),
+        "Invalid worktree name '{name}'"
+    );
+    Ok(())
+}
+
+/// Where to look for the workspace config from `cwd`: the directory above
+/// `.worktrees` when `cwd` is inside a set, so a set's own `.meta` copy is
+/// not taken for the workspace; `cwd` otherwise.
+pub fn primary_dir(cwd: &Path) -> PathBuf {
+    cwd.ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == ".worktrees"))
+        .and_then(Path::parent)
+        .unwrap_or(cwd)
+        .to_path_buf()
+}
+
+/// The directory of the set `name` in the workspace at `meta_dir`.
+pub fn set_dir(meta_dir: &Path, name: &str) -> Result<PathBuf> {
+    check_name(name)?;
+    let task_dir = worktrees_root(meta_dir).join(name);
+    anyhow::ensure!(task_dir.is_dir(), "Worktree set '{name}' not found");
+    Ok(task_dir)
+}
+
+/// The per-repo part of a hook payload, for repos the operation handled.
+fn repo_outcomes(repos: &[WorktreeRepoInfo]) -> Vec<RepoOutcome> {
+    repos.iter().map(repo_outcome).collect()
+}
+
+fn repo_outcome(repo: &WorktreeRepoInfo) -> RepoOutcome {
+    RepoOutcome {
+        alias: repo.alias.clone(),
+        path: repo.path.clone(),
+        branch: repo.branch.clone(),
+        success: true,
+        error: None,
+        duration_ms: None,
+    }
+}
+
+// ── Hooks ───────────────────────────────────────────────
+
+/// Take `--no-verify` out of a plugin command's arguments; meta runs the
+/// `pre-*` hooks, so it is meta's flag, not the plugin's.
+pub fn take_no_verify(args: &mut Vec<String>) -> bool {
+    let before = args.len();
+    args.retain(|a| a != "--no-verify");
+    args.len() != before
+}
+
+/// Fire the workspace's `worktree.hooks` for `payload.event`. A failing
+/// `pre-*` hook is a `HOOK_REJECTED` error unless `no_verify` skips it.
+pub fn fire_hook(meta_dir: &Path, payload: &HookPayload, no_verify: bool) -> Result<()> {
+    let worktree_hooks = hooks::load_worktree_hooks(meta_dir)?;
+    hooks::fire(
+        &worktree_hooks,
+        &payload.event,
+        &payload.to_value(),
+        meta_dir,
+        no_verify,
+    )?;
+    Ok(())
+}
+
+/// `pre-create` for the set `name`, before anything is created. The
+/// payload's `projects` are the projects the set is created with.
+pub fn fire_pre_create(
+    meta_dir: &Path,
+    name: &str,
+    projects: &[String],
+    no_verify: bool,
+) -> Result<()> {
+    let mut payload = HookPayload::new("pre-create", name, &worktrees_root(meta_dir).join(name));
+    payload
+        .extra
+        .insert("projects".to_string(), serde_json::json!(projects));
+    fire_hook(meta_dir, &payload, no_verify)
+}
+
+/// `pre-destroy` for the set `name`, with the repos about to be removed.
+pub fn fire_pre_destroy(meta_dir: &Path, name: &str, no_verify: bool) -> Result<()> {
+    let task_dir = worktrees_root(meta_dir).join(name);
+    let mut payload = HookPayload::new("pre-destroy", name, &task_dir);
+    payload.repos = repo_outcomes(&discover_worktree_repos(&task_dir).unwrap_or_default());
+    fire_hook(meta_dir, &payload, no_verify)
+}
+
+/// A `post-*` hook of an operation on one set, started with the operation
+/// so its payload has the right `started_at` and `duration_ms`.
+pub struct PostHook {
+    payload: HookPayload,
+    started: Instant,
+    /// The set's repos when the operation started
+    before: Vec<WorktreeRepoInfo>,
+}
+
+impl PostHook {
+    pub fn start(meta_dir: &Path, event: &str, name: &str) -> PostHook {
+        let task_dir = worktrees_root(meta_dir).join(name);
+        PostHook {
+            payload: HookPayload::new(event, name, &task_dir),
+            started: Instant::now(),
+            before: discover_worktree_repos(&task_dir).unwrap_or_default(),
+        }
+    }
+
+    /// Fire after a create: every repo the set has now succeeded. `error`
+    /// is why the operation as a whole failed, if it did.
+    pub fn fire_created(mut self, meta_dir: &Path, error: Option<String>) {
+        let repos = discover_worktree_repos(&self.payload.path).unwrap_or_default();
+        self.payload.finish(repo_outcomes(&repos), self.started);
+        if let Some(error) = error {
+            self.payload.success = Some(false);
+            self.payload.extra.insert("error".to_string(), error.into());
+        }
+        self.fire(meta_dir);
+    }
+
+    /// Fire after a destroy or prune: a repo succeeded if its worktree is
+    /// gone. Nothing fires when the set was untouched.
+    pub fn fire_removed(mut self, meta_dir: &Path) {
+        let repos: Vec<RepoOutcome> = self
+            .before
+            .iter()
+            .map(|repo| {
+                let removed = !repo.path.exists();
+                RepoOutcome {
+                    success: removed,
+                    error: (!removed).then(|| "worktree still exists".to_string()),
+                    ..repo_outcome(repo)
+                }
+            })
+            .collect();
+        if repos.is_empty() || repos.iter().all(|r| !r.success) {
+            return;
+        }
+        self.payload.finish(repos, self.started);
+        self.fire(meta_dir);
+    }
+
+    fn fire(self, meta_dir: &Path) {
+        // Post hooks never block; fire only fails on a broken config
+        if let Err(e) = fire_hook(meta_dir, &self.payload, false) {
+            eprintln!(
+                "{}: {} hook skipped: {e:#}",
+                "warning".yellow().bold(),
+                self.payload.event
+            );
+        }
+    }
+}
+
+// ── Create ──────────────────────────────────────────────
+
+/// Options of the plugin's set commands (`create`, `destroy`, ...) that
+/// take a value.
+const VALUE_FLAGS: &[&str] = &[
+    "--repo",
+    "--branch",
+    "--from-ref",
+    "--from-pr",
+    "--ttl",
+    "--meta",
+];
+
+/// Take `--clone-mode <mode>` (or `--clone-mode=<mode>`) out of the
+/// arguments of `meta worktree create`; meta applies the template, not the
+/// plugin.
+pub fn take_clone_mode(args: &mut Vec<String>) -> Result<Option<CloneMode>> {
+    let Some(i) = args
+        .iter()
+        .position(|a| a == "--clone-mode" || a.starts_with("--clone-mode="))
+    else {
+        return Ok(None);
+    };
+    let flag = args.remove(i);
+    let value = match flag.strip_prefix("--clone-mode=") {
+        Some(value) => value.to_string(),
+        None => {
+            anyhow::ensure!(i < args.len(), "--clone-mode needs a value");
+            args.remove(i)
+        }
+    };
+    value.parse().map(Some)
+}
+
+/// The set name in the arguments of a plugin set command such as
+/// `meta worktree create` (after `create`): the first positional argument.
+pub fn name_arg(args: &[String]) -> Option<&str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            args.next();
+        } else if !arg.starts_with('-') {
+            return Some(arg);
+        }
+    }
+    None
+}
+
+/// Bootstrap the set `name` the worktree plugin just created from the
+/// workspace's `worktree.template`: bring the template's files into every
+/// repo and return the setup commands, for the caller to run with the
+/// usual runner. `clone_mode` (from `--clone-mode`) overrides the
+/// template's. Without a template there is nothing to do.
+pub fn apply_template(
+    meta_dir: &Path,
+    name: &str,
+    clone_mode: Option<CloneMode>,
+) -> Result<Vec<DirCommand>> {
+    let Some((config_path, _)) = crate::config::find_meta_config_in(meta_dir) else {
+        return Ok(Vec::new());
+    };
+    let Some(template) = worktree_template::load(&config_path)? else {
+        return Ok(Vec::new());
+    };
+    let root = worktrees_root(meta_dir);
+    let repos = discover_worktree_repos(&set_dir(meta_dir, name)?)?;
+    let options = ApplyOptions {
+        clone_mode: clone_mode.or(template.clone_mode).unwrap_or_default(),
+        shared_root: Some(root.join(SHARED_DIR)),
+    };
+    for repo in &repos {
+        let applied =
+            worktree_template::apply_files_with(&template.for_repo(&repo.alias), repo, &options)
+                .with_context(|| {
+                    format!("Failed to apply the worktree template to {}", repo.alias)
+                })?;
+        let paths: Vec<String> = [
+            &applied.copied,
+            &applied.symlinked,
+            &applied.hardlinked,
+            &applied.shared,
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+        if !paths.is_empty() {
+            eprintln!(
+                "{} {}: set up {}",
+                "✓".green(),
+                repo.alias.bold(),
+                paths.join(", ")
+            );
+        }
+    }
+    Ok(worktree_template::setup_commands(&template, &repos))
+}
+
+// ── Rename ──────────────────────────────────────────────
+
+/// `meta worktree rename <name> <new-name>`: rename the set, repoint its
+/// entry in the worktree store and fire `post-rename`.
+pub fn handle_rename(meta_dir: &Path, name: &str, new_name: &str, json: bool) -> Result<()> {
+    let task_dir = set_dir(meta_dir, name)?;
+    ensure_unlocked(&task_dir)?;
+    let mut payload = HookPayload::new("post-rename", new_name, &task_dir);
+    let started = Instant::now();
+    let new_dir = rename_worktree_set(&task_dir, new_name)?;
+
+    let store = store_path();
+    if store.exists() {
+        let renamed = rename_in_store(
+            &store,
+            &store_lock_path(),
+            &task_dir,
+            &new_dir,
+            name,
+            new_name,
+        );
+        if let Err(e) = renamed {
+            eprintln!(
+                "{}: failed to update {}: {e:#}",
+                "warning".yellow().bold(),
+                store.display()
+            );
+        }
+    }
+
+    payload.path = new_dir.clone();
+    payload.extra.insert("old_name".to_string(), name.into());
+    payload.extra.insert(
+        "old_path".to_string(),
+        task_dir.display().to_string().into(),
+    );
+    payload.finish(repo_outcomes(&discover_worktree_repos(&new_dir)?), started);
+    fire_hook(meta_dir, &payload, false)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "name": new_name, "path": new_dir, "old_name": name })
+        );
+    } else {
+        println!(
+            "{} Renamed worktree set '{name}' to '{new_name}' ({})",
+            "✓".green(),
+            new_dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Rename a worktree set: move `task_dir` to a sibling named `new_name` and
+/// repoint every repo's worktree metadata at the new location.
+///
+/// The directory is moved in one step (nested repos move with it), then
+/// `git worktree repair` fixes the links in both directions for each source
+/// repo. Returns the new task directory. Updating the worktree store and
+/// firing the `post-rename` hook are left to the caller.
+pub fn rename_worktree_set(task_dir: &Path, new_name: &str) -> Result<PathBuf> {
+    check_name(new_name)?;
+    let parent = task_dir
+        .parent()
+        .with_context(|| format!("Invalid worktree path {}", task_dir.display()))?;
+    let new_dir = parent.join(new_name);
+    anyhow::ensure!(
+        !new_dir.exists(),
+        "Worktree '{new_name}' already exists at {}",
+        new_dir.display()
+    );
+
+    let repos = discover_worktree_repos(task_dir)?;
+    anyhow::ensure!(
+        !repos.is_empty(),
+        "No worktrees found in {}",
+        task_dir.display()
+    );
+
+    std::fs::rename(task_dir, &new_dir).with_context(|| {
+        format!(
+            "Failed to move {} to {}",
+            task_dir.display(),
+            new_dir.display()
+        )
+    })?;
+
+    for repo in &repos {
+        let relative = repo.path.strip_prefix(task_dir).unwrap_or(Path::new(""));
+        let moved = new_dir.join(relative);
+        git(
+            &repo.source_path,
+            &["worktree", "repair", &moved.to_string_lossy()],
+        )?;
+    }
+    Ok(new_dir)
+}
+
+/// The worktree plugin's store of sets, `~/.meta/worktree.json`.
+fn store_path() -> PathBuf {
+    meta_core::data_dir::data_file("worktree")
+}
+
+fn store_lock_path() -> PathBuf {
+    meta_core::meta_dir().join("worktree.lock")
+}
+
+/// Repoint the worktree store from the set at `old_dir` to `new_dir`. The
+/// store's layout belongs to the plugin, so only what clearly refers to the
+/// set changes: paths under `old_dir`, as keys or values, and the old name
+/// as a key or `name` next to such a path.
+fn rename_in_store(
+    store: &Path,
+    lock: &Path,
+    old_dir: &Path,
+    new_dir: &Path,
+    old_name: &str,
+    new_name: &str,
+) -> Result<()> {
+    let rename = StoreRename {
+        old_dir: old_dir.display().to_string(),
+        new_dir: new_dir.display().to_string(),
+        old_name,
+        new_name,
+    };
+    meta_core::store::update(store, lock, |value: &mut serde_json::Value| {
+        rename.apply(value);
+    })
+}
+
+struct StoreRename<'a> {
+    old_dir: String,
+    new_dir: String,
+    old_name: &'a str,
+    new_name: &'a str,
+}
+
+impl StoreRename<'_> {
+    /// Rewrite `value` in place; true if it refers to the old directory.
+    fn apply(&self, value: &mut serde_json::Value) -> bool {
+        use serde_json::Value;
+
+        match value {
+            Value::String(text) => match self.moved(text) {
+                Some(moved) => {
+                    *text = moved;
+                    true
+                }
+                None => false,
+            },
+            Value::Array(items) => items
+                .iter_mut()
+                .fold(false, |found, item| self.apply(item) || found),
+            Value::Object(map) => {
+                let mut found = false;
+                for (key, mut item) in std::mem::take(map) {
+                    let inner = self.apply(&mut item);
+                    let key = match self.moved(&key) {
+                        Some(moved) => {
+                            found = true;
+                            moved
+                        }
+                        None if inner && key == self.old_name => self.new_name.to_string(),
+                        None => key,
+                    };
+                    found |= inner;
+                    map.insert(key, item);
+                }
+                if let Some(Value::String(name)) = map.get_mut("name") {
+                    if found && name == self.old_name {
+                        *name = self.new_name.to_string();
+                    }
+                }
+                found
+            }
+            _ => false,
+        }
+    }
+
+    /// `path` moved to the new directory, if it is under the old one.
+    fn moved(&self, path: &str) -> Option<String> {
+        let rest = path.strip_prefix(&self.old_dir)?;
+        (rest.is_empty() || rest.starts_with(['/', '\\']))
+            .then(|| format!("{}{rest}", self.new_dir))
+    }
+}
+
+// ── Merge Back ──────────────────────────────────────────
+
+/// How a worktree branch is integrated into its base branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// A merge commit (`git merge --no-ff`).
+    #[default]
+    Merge,
+    /// Rebase the branch onto the base, then fast-forward the base.
+    Rebase,
+    /// One squashed commit on the base.
+    Squash,
+}
+
+impl std::str::FromStr for MergeStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "merge" => Ok(MergeStrategy::Merge),
+            "rebase" => Ok(MergeStrategy::Rebase),
+            "squash" => Ok(MergeStrategy::Squash),
+            other => anyhow::bail!("Unknown strategy '{other}' (expected merge, rebase or squash)"),
+        }
+    }
+}
+
+/// Result of merging one repo's worktree branch back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum MergeOutcome {
+    Merged,
+    /// The branch has no commits the base lacks.
+    UpToDate,
+    /// The merge stopped on conflicts and was aborted; nothing changed.
+    Conflict {
+        files: Vec<String>,
+    },
+}
+
+/// Merge a worktree repo's branch into `base` in its primary checkout.
+///
+/// The primary checkout must be clean; it is switched to `base` if needed.
+/// Conflicts abort the operation without changing either branch, so a
+/// caller merging a whole set can report per repo and carry on. Destroying
+/// the worktree afterwards (`--delete`) is up to the caller.
+pub fn merge_back(
+    repo: &WorktreeRepoInfo,
+    base: &str,
+    strategy: MergeStrategy,
+) -> Result<MergeOutcome> {
+    let primary = &repo.source_path;
+    let branch = repo.branch.as_str();
+    if git_utils::is_dirty(primary) == Some(true) {
+        anyhow::bail!(
+            "{} has uncommitted changes; commit or stash them first",
+            primary.display()
+        );
+    }
+
+    if !has_new_commits(repo, base)? {
+        return Ok(MergeOutcome::UpToDate);
+    }
+    if git_utils::current_branch(primary).as_deref() != Some(base) {
+        git(primary, &["checkout", "-q", base])?;
+    }
+
+    match strategy {
+        MergeStrategy::Merge => {
+            if git(primary, &["merge", "--no-ff", "--no-edit", branch]).is_err() {
+                return abort(primary, &["merge", "--abort"]);
+            }
+        }
+        MergeStrategy::Squash => {
+            if git(primary, &["merge", "--squash", branch]).is_err() {
+                return abort(primary, &["reset", "--merge"]);
+            }
+            // Squashed commits never become ancestors of the base, so an
+            // earlier squash shows up as an empty result here
+            if git(primary, &["diff", "--cached", "--quiet"]).is_ok() {
+                return Ok(MergeOutcome::UpToDate);
+            }
+            let message = format!("Merge branch '{branch}' (squashed)");
+            git(primary, &["commit", "-q", "-m", &message])?;
+        }
+        MergeStrategy::Rebase => {
+            if git(&repo.path, &["rebase", base]).is_err() {
+                return abort(&repo.path, &["rebase", "--abort"]);
+            }
+            git(primary, &["merge", "--ff-only", branch])?;
+        }
+    }
+    Ok(MergeOutcome::Merged)
+}
+
+/// [`merge_back`], recording a successful merge in `journal` so
+/// `meta undo` can move the base (and, after a rebase, the task branch)
+/// back to where it was.
+pub fn merge_back_with_journal(
+    repo: &WorktreeRepoInfo,
+    base: &str,
+    strategy: MergeStrategy,
+    journal: &mut Journal,
+) -> Result<MergeOutcome> {
+    let primary = &repo.source_path;
+    let previous = match git_utils::current_branch(primary) {
+        Some(branch) => branch,
+        None => git(primary, &["rev-parse", "HEAD"])?,
+    };
+    let base_before = git(primary, &["rev-parse", &format!("refs/heads/{base}")])?;
+    let branch_before = git(&repo.path, &["rev-parse", "HEAD"])?;
+
+    let outcome = merge_back(repo, base, strategy)?;
+    if outcome != MergeOutcome::Merged {
+        return Ok(outcome);
+    }
+    let mut undo = vec![UndoAction::ResetKeep {
+        from: git(primary, &["rev-parse", "HEAD"])?,
+        to: base_before,
+    }];
+    if previous != base {
+        undo.push(UndoAction::Checkout { to: previous });
+    }
+    journal.step(
+        &repo.alias,
+        primary,
+        &format!("merged {} into {base}", repo.branch),
+        undo,
+    );
+    if strategy == MergeStrategy::Rebase {
+        let branch_after = git(&repo.path, &["rev-parse", "HEAD"])?;
+        if branch_after != branch_before {
+            journal.step(
+                &repo.alias,
+                &repo.path,
+                &format!("rebased {} onto {base}", repo.branch),
+                vec![UndoAction::ResetKeep {
+                    from: branch_after,
+                    to: branch_before,
+                }],
+            );
+        }
+    }
+    Ok(outcome)
+}
+
+/// How merging one repo of a set went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeReport {
+    pub alias: String,
+    /// Branch the repo was merged into.
+    pub base: String,
+    #[serde(flatten)]
+    pub outcome: Option<MergeOutcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl MergeReport {
+    pub fn failed(&self) -> bool {
+        !matches!(
+            self.outcome,
+            Some(MergeOutcome::Merged | MergeOutcome::UpToDate)
+        )
+    }
+}
+
+/// Merge every repo of the set at `task_dir` back into `base`, or into the
+/// branch its primary checkout is on. A repo that fails or conflicts is
+/// reported; the others go ahead. Every merge is recorded in `journal`.
+pub fn merge_back_set(
+    task_dir: &Path,
+    base: Option<&str>,
+    strategy: MergeStrategy,
+    journal: &mut Journal,
+) -> Result<Vec<MergeReport>> {
+    let reports = discover_worktree_repos(task_dir)?
+        .iter()
+        .map(|repo| {
+            let base = base
+                .map(str::to_string)
+                .or_else(|| git_utils::current_branch(&repo.source_path));
+            let result = base
+                .as_deref()
+                .context("primary checkout is not on a branch; pass --base")
+                .and_then(|base| merge_back_with_journal(repo, base, strategy, journal));
+            let (outcome, error) = match result {
+                Ok(outcome) => (Some(outcome), None),
+                Err(e) => (None, Some(format!("{e:#}"))),
+            };
+            MergeReport {
+                alias: repo.alias.clone(),
+                base: base.unwrap_or_default(),
+                outcome,
+                error,
+            }
+        })
+        .collect();
+    Ok(reports)
+}
+
+/// `meta worktree merge-back <name>`: merge the set's branches back and
+/// report per repo, journaled for `meta undo`. Fails with `COMMAND_FAILED`
+/// when a repo conflicted or could not be merged.
+pub fn handle_merge_back(
+    meta_dir: &Path,
+    name: &str,
+    base: Option<&str>,
+    strategy: MergeStrategy,
+    json: bool,
+) -> Result<()> {
+    let task_dir = set_dir(meta_dir, name)?;
+    let mut journal = Journal::begin(
+        "worktree-merge-back",
+        &format!("meta worktree merge-back {name}"),
+    );
+    let reports = merge_back_set(&task_dir, base, strategy, &mut journal)?;
+    let failed = reports.iter().any(MergeReport::failed);
+    journal.finish(if failed {
+        OpStatus::Failed
+    } else {
+        OpStatus::Completed
+    });
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        print!("{}", format_merge_reports(&reports));
+        if let Some(hint) = journal.hint() {
+            println!("{}", hint.dimmed());
+        }
+    }
+    if failed {
+        return Err(MetaError::CommandFailed.into());
+    }
+    Ok(())
+}
+
+/// One line per repo: merged, already up to date, conflicted or failed.
+pub fn format_merge_reports(reports: &[MergeReport]) -> String {
+    let mut out = String::new();
+    for report in reports {
+        let (alias, base) = (report.alias.bold(), &report.base);
+        let line = match (&report.outcome, &report.error) {
+            (Some(MergeOutcome::Merged), _) => {
+                format!("{} {alias}: merged into {base}", "✓".green())
+            }
+            (Some(MergeOutcome::UpToDate), _) => {
+                format!("{} {alias}: {base} is already up to date", "·".dimmed())
+            }
+            (Some(MergeOutcome::Conflict { files }), _) => format!(
+                "{} {alias}: conflicts with {base} in {} (nothing was changed)",
+                "✗".red(),
+                files.join(", ")
+            ),
+            (None, error) => format!(
+                "{} {alias}: {}",
+                "✗".red(),
+                error.as_deref().unwrap_or("failed")
+            ),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Collect the conflicted files, then undo the failed operation.
+fn abort(dir: &Path, undo: &[&str]) -> Result<MergeOutcome> {
+    let files = git(dir, &["diff", "--name-only", "--diff-filter=U"])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    git(dir, undo)?;
+    Ok(MergeOutcome::Conflict { files })
+}
+
+// ── Push ────────────────────────────────────────────────
+
+/// Options for pushing a worktree set.
+#[derive(Debug, Clone, Default)]
+pub struct PushOptions<'a> {
+    /// Base branch the task branches are compared against and PRs target.
+    pub base: &'a str,
+    /// Open a pull request (via `gh`) for every pushed repo.
+    pub create_pr: bool,
+    /// PR title; defaults to the branch name.
+    pub title: Option<&'a str>,
+    pub body: Option<&'a str>,
+}
+
+/// What happened to one repo of the set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PushResult {
+    pub pushed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pr_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Whether the worktree branch has commits `base` does not.
+pub fn has_new_commits(repo: &WorktreeRepoInfo, base: &str) -> Result<bool> {
+    let range = format!("{base}..{}", repo.branch);
+    Ok(git(&repo.path, &["rev-list", "--count", &range])? != "0")
+}
+
+/// Push the worktree branch to `origin`, setting it as upstream.
+pub fn push_branch(repo: &WorktreeRepoInfo) -> Result<()> {
+    git(&repo.path, &["push", "-q", "-u", "origin", &repo.branch]).map(drop)
+}
+
+/// Open a pull request for the worktree branch with `gh`. Returns its URL.
+pub fn create_pr(repo: &WorktreeRepoInfo, base: &str, title: &str, body: &str) -> Result<String> {
+    let stdout = gh(
+        &repo.path,
+        &[
+            "pr",
+            "create",
+            "--head",
+            &repo.branch,
+            "--base",
+            base,
+            "--title",
+            title,
+            "--body",
+            body,
+        ],
+    )?;
+    stdout
+        .lines()
+        .rev()
+        .find(|l| l.starts_with("http"))
+        .map(str::to_string)
+        .context("gh pr create did not print a PR URL")
+}
+
+/// Push every repo of the set that has new commits and optionally open
+/// PRs. With several PRs, each body is updated to link the others. Failures
+/// are recorded per repo rather than stopping the set.
+pub fn push_set(repos: &[WorktreeRepoInfo], options: &PushOptions) -> BTreeMap<String, PushResult> {
+    let mut results = BTreeMap::new();
+    for repo in repos {
+        let mut result = PushResult::default();
+        let outcome = has_new_commits(repo, options.base).and_then(|changed| {
+            if !changed {
+                return Ok(());
+            }
+            push_branch(repo)?;
+            result.pushed = true;
+            if options.create_pr {
+                let title = options.title.unwrap_or(&repo.branch);
+                let body = options.body.unwrap_or_default();
+                result.pr_url = Some(create_pr(repo, options.base, title, body)?);
+            }
+            Ok(())
+        });
+        if let Err(e) = outcome {
+            result.error = Some(format!("{e:#}"));
+        }
+        results.insert(repo.alias.clone(), result);
+    }
+
+    let urls: Vec<(&str, &str)> = results
+        .iter()
+        .filter_map(|(alias, r)| Some((alias.as_str(), r.pr_url.as_deref()?)))
+        .collect();
+    if urls.len() > 1 {
+        for repo in repos {
+            let Some((_, url)) = urls.iter().find(|(alias, _)| *alias == repo.alias) else {
+                continue;
+            };
+            let body = with_related_prs(options.body.unwrap_or_default(), &urls, &repo.alias);
+            if let Err(e) = gh(&repo.path, &["pr", "edit", url, "--body", &body]) {
+                log::warn!("Failed to link PRs for {}: {e:#}", repo.alias);
+            }
+        }
+    }
+    results
+}
+
+/// `meta worktree push <name>`: push the set's branches and optionally open
+/// PRs. Fails with `COMMAND_FAILED` when a repo could not be pushed.
+pub fn handle_push(meta_dir: &Path, name: &str, options: &PushOptions, json: bool) -> Result<()> {
+    let task_dir = set_dir(meta_dir, name)?;
+    let results = push_set(&discover_worktree_repos(&task_dir)?, options);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print!("{}", format_push_results(&results, options.base));
+    }
+    if results.values().any(|r| r.error.is_some()) {
+        return Err(MetaError::CommandFailed.into());
+    }
+    Ok(())
+}
+
+/// One line per repo: pushed (with its PR), nothing to push, or the error.
+pub fn format_push_results(results: &BTreeMap<String, PushResult>, base: &str) -> String {
+    let mut out = String::new();
+    for (alias, result) in results {
+        let alias = alias.bold();
+        let line = match (&result.error, result.pushed, &result.pr_url) {
+            (Some(error), _, _) => format!("{} {alias}: {error}", "✗".red()),
+            (None, true, Some(url)) => format!("{} {alias}: pushed, PR {url}", "✓".green()),
+            (None, true, None) => format!("{} {alias}: pushed", "✓".green()),
+            (None, false, _) => format!("{} {alias}: no commits ahead of {base}", "·".dimmed()),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// `body` followed by links to the set's other PRs.
+fn with_related_prs(body: &str, urls: &[(&str, &str)], own: &str) -> String {
+    let mut text = body.trim_end().to_string();
+    if !text.is_empty() {
+        text.push_str("\n\n");
+    }
+    text.push_str("Related PRs:\n");
+    for (alias, url) in urls.iter().filter(|(alias, _)| *alias != own) {
+        text.push_str(&format!("- {alias}: {url}\n"));
+    }
+    text
+}
+
+/// Run the GitHub CLI in `dir`, returning its stdout.
+fn gh(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("gh")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run gh (is the GitHub CLI installed?)")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "gh {} failed in {}: {}",
+            args[..2.min(args.len())].join(" "),
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// ── Current Worktree ────────────────────────────────────
+
+/// Worktree sets chosen with `meta worktree switch`, per workspace root.
+/// Kept in `~/.meta/worktree_current.json`, next to the worktree store.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CurrentWorktrees {
+    #[serde(default)]
+    pub workspaces: BTreeMap<String, PathBuf>,
+}
+
+fn current_store_path() -> PathBuf {
+    meta_core::data_dir::data_file("worktree_current")
+}
+
+/// Directory holding a workspace's worktree sets: `worktrees_dir` from the
+/// config, or `.worktrees`.
+pub fn worktrees_root(meta_dir: &Path) -> PathBuf {
+    let configured = crate::config::find_meta_config_in(meta_dir)
+        .and_then(|(path, _)| crate::config::read_meta_config_value(&path).ok())
+        .and_then(|v| v.get("worktrees_dir")?.as_str().map(str::to_string));
+    meta_dir.join(configured.as_deref().unwrap_or(".worktrees"))
+}
+
+/// `meta worktree switch <name> [repo]`: print the path to `cd` to. With
+/// `make_current`, also make the set the workspace's current one, so
+/// commands run outside `.worktrees` are scoped to it until cleared.
+pub fn handle_switch(
+    meta_dir: &Path,
+    name: &str,
+    repo: Option<&str>,
+    make_current: bool,
+    json: bool,
+) -> Result<()> {
+    let path = switch_target(meta_dir, name, repo)?;
+    if make_current {
+        set_current(meta_dir, Some(&set_dir(meta_dir, name)?))?;
+    }
+    if json {
+        println!("{}", serde_json::json!({ "name": name, "path": path }));
+    } else {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+/// `meta worktree switch --clear`: forget the workspace's current set.
+pub fn handle_clear_current(meta_dir: &Path) -> Result<()> {
+    if let Some(task_dir) = current_worktree(meta_dir) {
+        set_current(meta_dir, None)?;
+        eprintln!("Cleared the current worktree set ({})", task_dir.display());
+    }
+    Ok(())
+}
+
+/// Path `meta worktree switch <name> [repo]` resolves to: the set's
+/// directory, or one repo inside it.
+pub fn switch_target(meta_dir: &Path, name: &str, repo: Option<&str>) -> Result<PathBuf> {
+    let task_dir = set_dir(meta_dir, name)?;
+    let Some(alias) = repo else {
+        return Ok(task_dir);
+    };
+    discover_worktree_repos(&task_dir)?
+        .into_iter()
+        .find(|r| r.alias == alias)
+        .map(|r| r.path)
+        .with_context(|| format!("Worktree set '{name}' has no repo '{alias}'"))
+}
+
+/// Record `task_dir` as the current worktree set of a workspace, or clear
+/// it with `None`.
+pub fn set_current(meta_dir: &Path, task_dir: Option<&Path>) -> Result<()> {
+    meta_core::data_dir::ensure_meta_dir()?;
+    let lock = meta_core::meta_dir().join("worktree_current.lock");
+    let key = meta_dir.display().to_string();
+    meta_core::store::update(
+        &current_store_path(),
+        &lock,
+        |store: &mut CurrentWorktrees| {
+            match task_dir {
+                Some(dir) => store.workspaces.insert(key, dir.to_path_buf()),
+                None => store.workspaces.remove(&key),
+            };
+        },
+    )
+}
+
+/// The current worktree set of a workspace, if one was recorded and still
+/// exists.
+pub fn current_worktree(meta_dir: &Path) -> Option<PathBuf> {
+    let store: CurrentWorktrees = meta_core::store::read(&current_store_path()).ok()?;
+    store
+        .workspaces
+        .get(&meta_dir.display().to_string())
+        .filter(|dir| dir.is_dir())
+        .cloned()
+}
+
+/// Like [`detect_worktree_context`], but for the workspace's current
+/// worktree set; used when cwd is not inside a worktree. The set is only
+/// current after `meta worktree switch --set-current`, and a notice on
+/// stderr says it is in effect, so it never narrows a command silently.
+pub fn current_worktree_context(meta_dir: &Path) -> Option<(String, PathBuf, Vec<PathBuf>)> {
+    let task_dir = current_worktree(meta_dir)?;
+    let name = task_dir.file_name()?.to_string_lossy().to_string();
+    let repos = discover_worktree_repos(&task_dir).ok()?;
+    if repos.is_empty() {
+        return None;
+    }
+    eprintln!(
+        "{} Using current worktree set '{name}' ({} repos); `meta worktree switch --clear` or --primary to stop",
+        "→".cyan(),
+        repos.len()
+    );
+    let paths = repos.into_iter().map(|r| r.path).collect();
+    Some((name, task_dir, paths))
+}
+
+// ── Locking ─────────────────────────────────────────────
+
+/// Why and when a worktree set was locked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// RFC 3339 time the set was locked.
+    pub locked_at: String,
+}
+
+/// Locked worktree sets, keyed by task directory. Kept in
+/// `~/.meta/worktree_locks.json`, next to the worktree store.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WorktreeLocks {
+    #[serde(default)]
+    pub sets: BTreeMap<String, LockInfo>,
+}
+
+fn locks_store_path() -> PathBuf {
+    meta_core::data_dir::data_file("worktree_locks")
+}
+
+fn locks_lock_path() -> PathBuf {
+    meta_core::meta_dir().join("worktree_locks.lock")
+}
+
+/// Lock a worktree set so destroy and prune (including TTL expiry) leave it
+/// alone until [`unlock_set`].
+///
+/// Each repo's worktree is also locked with `git worktree lock`, so plain
+/// `git worktree remove`/`prune` refuse to touch it either.
+pub fn lock_set(task_dir: &Path, reason: Option<&str>) -> Result<LockInfo> {
+    meta_core::data_dir::ensure_meta_dir()?;
+    lock_set_in(&locks_store_path(), &locks_lock_path(), task_dir, reason)
+}
+
+/// Remove a set's lock. Unlocking a set that is not locked is a no-op.
+pub fn unlock_set(task_dir: &Path) -> Result<()> {
+    meta_core::data_dir::ensure_meta_dir()?;
+    unlock_set_in(&locks_store_path(), &locks_lock_path(), task_dir)
+}
+
+/// The lock on a worktree set, if any, for `meta worktree list`.
+pub fn lock_info(task_dir: &Path) -> Option<LockInfo> {
+    lock_info_in(&locks_store_path(), task_dir)
+}
+
+/// Every locked worktree set, keyed by task directory.
+pub fn locked_sets() -> BTreeMap<String, LockInfo> {
+    meta_core::store::read::<WorktreeLocks>(&locks_store_path())
+        .map(|locks| locks.sets)
+        .unwrap_or_default()
+}
+
+/// Fail if a worktree set is locked; called before destroying or pruning it.
+pub fn ensure_unlocked(task_dir: &Path) -> Result<()> {
+    ensure_unlocked_in(&locks_store_path(), task_dir)
+}
+
+/// The worktree store entries of a workspace's locked sets, taken out of
+/// the store while the plugin prunes, so it neither expires nor orphans
+/// them. [`ShieldedSets::restore`] puts them back.
+pub struct ShieldedSets {
+    /// Names of the locked sets
+    pub names: Vec<String>,
+    store: PathBuf,
+    lock: PathBuf,
+    held: Vec<HeldEntry>,
+}
+
+impl ShieldedSets {
+    pub fn restore(self) -> Result<()> {
+        if self.held.is_empty() {
+            return Ok(());
+        }
+        restore_in_store(&self.store, &self.lock, &self.held)
+    }
+}
+
+/// Shield the locked sets of the workspace at `meta_dir` from a prune; see
+/// [`ShieldedSets`].
+pub fn shield_locked_sets(meta_dir: &Path) -> Result<ShieldedSets> {
+    shield_locked_sets_in(
+        &locks_store_path(),
+        &store_path(),
+        &store_lock_path(),
+        &worktrees_root(meta_dir),
+    )
+}
+
+/// `meta worktree lock <name> [--reason <text>]`.
+pub fn handle_lock(meta_dir: &Path, name: &str, reason: Option<&str>, json: bool) -> Result<()> {
+    let info = lock_set(&set_dir(meta_dir, name)?, reason)?;
+    if json {
+        println!("{}", serde_json::json!({ "name": name, "lock": info }));
+    } else {
+        let reason = info.reason.map(|r| format!(": {r}")).unwrap_or_default();
+        println!("{} Locked worktree set '{name}'{reason}", "✓".green());
+    }
+    Ok(())
+}
+
+/// `meta worktree unlock <name>`.
+pub fn handle_unlock(meta_dir: &Path, name: &str, json: bool) -> Result<()> {
+    unlock_set(&set_dir(meta_dir, name)?)?;
+    if json {
+        println!("{}", serde_json::json!({ "name": name, "lock": null }));
+    } else {
+        println!("{} Unlocked worktree set '{name}'", "✓".green());
+    }
+    Ok(())
+}
+
+/// The locked sets under `root`, one line each with its reason, for the
+/// end of `meta worktree list`. Empty when none is locked.
+pub fn format_locks(locks: &BTreeMap<String, LockInfo>, root: &Path) -> String {
+    let mut out = String::new();
+    for (dir, info) in locks {
+        let Ok(name) = Path::new(dir).strip_prefix(root) else {
+            continue;
+        };
+        if out.is_empty() {
+            out.push_str(&format!("\n{}\n", "Locked:".bold()));
+        }
+        let reason = info.reason.as_deref().unwrap_or("no reason given");
+        let since = format!("(since {})", info.locked_at);
+        out.push_str(&format!(
+            "  {}  {reason} {}\n",
+            name.display(),
+            since.dimmed()
+        ));
+    }
+    out
+}
+
+fn lock_set_in(
+    store: &Path,
+    lock: &Path,
+    task_dir: &Path,
+    reason: Option<&str>,
+) -> Result<LockInfo> {
+    let repos = discover_worktree_repos(task_dir)?;
+    anyhow::ensure!(
+        !repos.is_empty(),
+        "No worktrees found in {}",
+        task_dir.display()
+    );
+    for repo in &repos {
+        if !git_lock_file(&repo.path)?.exists() {
+            let path = repo.path.to_string_lossy();
+            let mut args = vec!["worktree", "lock"];
+            if let Some(reason) = reason {
+                args.extend(["--reason", reason]);
+            }
+            args.push(&path);
+            git(&repo.source_path, &args)?;
+        }
+    }
+
+    let info = LockInfo {
+        reason: reason.map(str::to_string),
+        locked_at: chrono::Local::now().to_rfc3339(),
+    };
+    let key = task_dir.display().to_string();
+    meta_core::store::update(store, lock, |locks: &mut WorktreeLocks| {
+        locks.sets.insert(key, info.clone());
+    })?;
+    Ok(info)
+}
+
+fn unlock_set_in(store: &Path, lock: &Path, task_dir: &Path) -> Result<()> {
+    for repo in discover_worktree_repos(task_dir)? {
+        if git_lock_file(&repo.path)?.exists() {
+            git(
+                &repo.source_path,
+                &["worktree", "unlock", &repo.path.to_string_lossy()],
+            )?;
+        }
+    }
+    let key = task_dir.display().to_string();
+    meta_core::store::update(store, lock, |locks: &mut WorktreeLocks| {
+        locks.sets.remove(&key);
+    })
+}
+
+fn lock_info_in(store: &Path, task_dir: &Path) -> Option<LockInfo> {
+    let locks: WorktreeLocks = meta_core::store::read(store).ok()?;
+    locks.sets.get(&task_dir.display().to_string()).cloned()
+}
+
+fn ensure_unlocked_in(store: &Path, task_dir: &Path) -> Result<()> {
+    let Some(info) = lock_info_in(store, task_dir) else {
+        return Ok(());
+    };
+    let name = task_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| task_dir.display().to_string());
+    let reason = info.reason.map(|r| format!(" ({r})")).unwrap_or_default();
+    anyhow::bail!(
+        "Worktree set '{name}' is locked{reason}; run 'meta worktree unlock {name}' first"
+    )
+}
+
+fn shield_locked_sets_in(
+    locks: &Path,
+    store: &Path,
+    lock: &Path,
+    root: &Path,
+) -> Result<ShieldedSets> {
+    let sets: Vec<(String, String)> = meta_core::store::read::<WorktreeLocks>(locks)
+        .map(|locks| locks.sets)
+        .unwrap_or_default()
+        .into_keys()
+        .filter_map(|dir| {
+            let name = Path::new(&dir).strip_prefix(root).ok()?;
+            let name = name.to_string_lossy().to_string();
+            Some((dir, name))
+        })
+        .collect();
+    let held = if sets.is_empty() || !store.exists() {
+        Vec::new()
+    } else {
+        hold_in_store(store, lock, &sets)?
+    };
+    Ok(ShieldedSets {
+        names: sets.into_iter().map(|(_, name)| name).collect(),
+        store: store.to_path_buf(),
+        lock: lock.to_path_buf(),
+        held,
+    })
+}
+
+/// A value taken out of the worktree store, and where it was.
+struct HeldEntry {
+    /// JSON pointer to the object or array that held it
+    parent: String,
+    slot: Slot,
+    value: serde_json::Value,
+}
+
+enum Slot {
+    Key(String),
+    Index(usize),
+}
+
+/// Take the entries of `sets` (directory, name) out of the worktree store.
+/// As with [`rename_in_store`], the layout belongs to the plugin, so an
+/// entry is what clearly refers to a set: a member keyed by a path in its
+/// directory, or by its name with a value that refers to it, a member whose
+/// value is such a path, and an array item that refers to it.
+fn hold_in_store(store: &Path, lock: &Path, sets: &[(String, String)]) -> Result<Vec<HeldEntry>> {
+    let mut held = Vec::new();
+    meta_core::store::update(store, lock, |value: &mut serde_json::Value| {
+        take_entries(value, "", sets, &mut held);
+    })?;
+    Ok(held)
+}
+
+fn take_entries(
+    value: &mut serde_json::Value,
+    pointer: &str,
+    sets: &[(String, String)],
+    held: &mut Vec<HeldEntry>,
+) {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => {
+            let keys: Vec<String> = map.keys().cloned().collect();
+            for key in keys {
+                let item = &map[&key];
+                let entry = sets.iter().any(|(dir, name)| {
+                    is_under(&key, dir)
+                        || (key == *name && refers_to(item, dir))
+                        || item.as_str().is_some_and(|path| is_under(path, dir))
+                });
+                if entry {
+                    if let Some(value) = map.remove(&key) {
+                        held.push(HeldEntry {
+                            parent: pointer.to_string(),
+                            slot: Slot::Key(key),
+                            value,
+                        });
+                    }
+                } else if let Some(item) = map.get_mut(&key) {
+                    let escaped = key.replace('~', "~0").replace('/', "~1");
+                    take_entries(item, &format!("{pointer}/{escaped}"), sets, held);
+                }
+            }
+        }
+        Value::Array(items) => {
+            let mut index = 0;
+            while index < items.len() {
+                if sets.iter().any(|(dir, _)| refers_to(&items[index], dir)) {
+                    held.push(HeldEntry {
+                        parent: pointer.to_string(),
+                        slot: Slot::Index(index),
+                        value: items.remove(index),
+                    });
+                } else {
+                    take_entries(&mut items[index], &format!("{pointer}/{index}"), sets, held);
+                    index += 1;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Put `held` back, newest first so array positions line up again. Entries
+/// the plugin has since recreated are left as the plugin wrote them.
+fn restore_in_store(store: &Path, lock: &Path, held: &[HeldEntry]) -> Result<()> {
+    use serde_json::Value;
+
+    let mut lost = 0;
+    meta_core::store::update(store, lock, |value: &mut Value| {
+        for entry in held.iter().rev() {
+            match (value.pointer_mut(&entry.parent), &entry.slot) {
+                (Some(Value::Object(map)), Slot::Key(key)) => {
+                    map.entry(key.clone())
+                        .or_insert_with(|| entry.value.clone());
+                }
+                (Some(Value::Array(items)), Slot::Index(index)) => {
+                    items.insert((*index).min(items.len()), entry.value.clone());
+                }
+                _ => lost += 1,
+            }
+        }
+    })?;
+    anyhow::ensure!(
+        lost == 0,
+        "{lost} worktree store entries of locked sets could not be put back in {}",
+        store.display()
+    );
+    Ok(())
+}
+
+/// Whether `value` mentions a path in `dir`, as a string or an object key.
+fn refers_to(value: &serde_json::Value, dir: &str) -> bool {
+    use serde_json::Value;
+
+    match value {
+        Value::String(text) => is_under(text, dir),
+        Value::Array(items) => items.iter().any(|item| refers_to(item, dir)),
+        Value::Object(map) => map
+            .iter()
+            .any(|(key, item)| is_under(key, dir) || refers_to(item, dir)),
+        _ => false,
+    }
+}
+
+/// Whether `path` is `dir` or a path inside it.
+fn is_under(path: &str, dir: &str) -> bool {
+    path.strip_prefix(dir)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '\\']))
+}
+
+/// Git's lock marker for a linked worktree: `<gitdir>/locked`.
+fn git_lock_file(worktree: &Path) -> Result<PathBuf> {
+    Ok(PathBuf::from(git(worktree, &["rev-parse", "--absolute-git-dir"])?).join("locked"))
+}
+
+// ── Clone On Create ─────────────────────────────────────
+
+/// The projects `meta worktree create` will check out, from its arguments
+/// (after `create`): each `--repo <alias>[:<branch>]`, or every project in
+/// `.meta` with `--all`.
+pub fn create_aliases(meta_dir: &Path, args: &[String]) -> Result<Vec<String>> {
+    if args.iter().any(|a| a == "--all") {
+        let Some((config_path, _)) = crate::config::find_meta_config_in(meta_dir) else {
+            return Ok(Vec::new());
+        };
+        let (projects, _) = crate::config::parse_meta_config(&config_path)?;
+        return Ok(projects.into_iter().map(|p| p.name).collect());
+    }
+    let mut aliases = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--repo=") {
+            Some(value) => value,
+            None if arg == "--repo" => match args.next() {
+                Some(value) => value,
+                None => break,
+            },
+            None => continue,
+        };
+        let alias = value
+            .split_once(':')
+            .map_or(value, |(alias, _branch)| alias);
+        aliases.push(alias.to_string());
+    }
+    Ok(aliases)
+}
+
+/// Clone the projects named in `aliases` that `.meta` lists with a `repo`
+/// (or `url`) but that are not checked out yet, so `meta worktree create
+/// --repo api` works on a fresh machine. Returns the names cloned. Fails
+/// if a clone fails or a missing project has nothing to clone from.
+pub fn clone_missing_repos(
+    meta_dir: &Path,
+    aliases: &[String],
+    quiet: bool,
+) -> Result<Vec<String>> {
+    let mut options = CloneOptions::new(String::new());
+    options.include_only = aliases.iter().filter(|a| *a != ".").cloned().collect();
+    if options.include_only.is_empty() {
+        return Ok(Vec::new());
+    }
+    options.quiet = quiet;
+
+    let mut cloned = Vec::new();
+    for result in clone::clone_missing(meta_dir, &options)? {
+        match result.status {
+            CloneStatus::Cloned => cloned.push(result.name),
+            CloneStatus::Failed => anyhow::bail!(
+                "Failed to clone '{}': {}",
+                result.name,
+                result.error.unwrap_or_default()
+            ),
+            CloneStatus::NoRepo if !meta_dir.join(&result.path).exists() => anyhow::bail!(
+                "'{}' is not cloned and has no repo url in .meta",
+                result.name
+            ),
+            _ => {}
+        }
+    }
+    Ok(cloned)
+}
+
+// ── Scheduled Prune ─────────────────────────────────────
+
+/// How often a workspace is pruned when `worktree.prune.interval` is unset.
+pub const DEFAULT_PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The `worktree.prune` section of `.meta`:
+///
+/// ```json
+/// "worktree": { "prune": { "auto": true, "interval": "6h" } }
+/// ```
+///
+/// With `auto`, a running `meta daemon` runs `meta worktree prune` in the
+/// workspace once per `interval`, removing expired and orphaned sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PruneSchedule {
+    pub auto: bool,
+    pub interval: Duration,
+}
+
+impl Default for PruneSchedule {
+    fn default() -> Self {
+        Self {
+            auto: false,
+            interval: DEFAULT_PRUNE_INTERVAL,
+        }
+    }
+}
+
+/// Parse `worktree.prune` from a config document.
+pub fn prune_schedule(config: &serde_json::Value) -> Result<PruneSchedule> {
+    let mut schedule = PruneSchedule::default();
+    let Some(prune) = config.get("worktree").and_then(|w| w.get("prune")) else {
+        return Ok(schedule);
+    };
+    if let Some(auto) = prune.get("auto") {
+        schedule.auto = auto
+            .as_bool()
+            .context("worktree.prune.auto must be true or false")?;
+    }
+    if let Some(interval) = prune.get("interval") {
+        schedule.interval = crate::config::parse_timeout(interval)
+            .context("worktree.prune.interval must be a duration like \"6h\"")?;
+    }
+    Ok(schedule)
+}
+
+/// The last scheduled prune of a workspace, for `meta worktree list`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneRun {
+    /// RFC 3339 time the prune ran.
+    pub last_run: String,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Scheduled prune runs, keyed by workspace directory. Kept in
+/// `~/.meta/worktree_prune.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PruneRuns {
+    #[serde(default)]
+    pub workspaces: BTreeMap<String, PruneRun>,
+}
+
+fn prune_store_path() -> PathBuf {
+    meta_core::data_dir::data_file("worktree_prune")
+}
+
+fn prune_lock_path() -> PathBuf {
+    meta_core::meta_dir().join("worktree_prune.lock")
+}
+
+/// The last scheduled prune of the workspace at `meta_dir`, if any.
+pub fn last_prune(meta_dir: &Path) -> Option<PruneRun> {
+    last_prune_in(&prune_store_path(), meta_dir)
+}
+
+/// Whether a workspace with `schedule` is due a prune at `now`.
+pub fn prune_due(
+    schedule: &PruneSchedule,
+    last: Option<&PruneRun>,
+    now: chrono::DateTime<chrono::Local>,
+) -> bool {
+    if !schedule.auto {
+        return false;
+    }
+    let Some(last) = last.and_then(|r| chrono::DateTime::parse_from_rfc3339(&r.last_run).ok())
+    else {
+        return true;
+    };
+    let elapsed = now.signed_duration_since(last).to_std().unwrap_or_default();
+    elapsed >= schedule.interval
+}
+
+/// Run `meta worktree prune` in the workspace whose config is at
+/// `config_path` if its schedule says it is due, recording the run. Called
+/// periodically by `meta daemon`; `None` when nothing was due.
+pub fn prune_if_due(config_path: &Path) -> Result<Option<PruneRun>> {
+    let meta_dir = config_path
+        .parent()
+        .context("Config path has no parent directory")?;
+    let config = crate::config::read_meta_config_value(config_path)?;
+    let schedule = prune_schedule(&config)?;
+    if !prune_due(
+        &schedule,
+        last_prune(meta_dir).as_ref(),
+        chrono::Local::now(),
+    ) {
+        return Ok(None);
+    }
+
+    let output = Command::new(std::env::current_exe()?)
+        .args(["worktree", "prune"])
+        .current_dir(meta_dir)
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run meta worktree prune")?;
+    let run = PruneRun {
+        last_run: chrono::Local::now().to_rfc3339(),
+        ok: output.status.success(),
+        error: (!output.status.success())
+            .then(|| String::from_utf8_lossy(&output.stderr).trim().to_string()),
+    };
+    meta_core::data_dir::ensure_meta_dir()?;
+    record_prune_in(&prune_store_path(), &prune_lock_path(), meta_dir, &run)?;
+    Ok(Some(run))
+}
+
+/// When the last scheduled prune ran and how it went, for the end of
+/// `meta worktree list`.
+pub fn format_last_prune(run: &PruneRun) -> String {
+    match (run.ok, &run.error) {
+        (true, _) => format!("Last scheduled prune: {}\n", run.last_run)
+            .dimmed()
+            .to_string(),
+        (false, error) => format!(
+            "{} Last scheduled prune failed at {}: {}\n",
+            "✗".red(),
+            run.last_run,
+            error.as_deref().unwrap_or("unknown error")
+        ),
+    }
+}
+
+fn last_prune_in(store: &Path, meta_dir: &Path) -> Option<PruneRun> {
+    let runs: PruneRuns = meta_core::store::read(store).ok()?;
+    runs.workspaces
+        .get(&meta_dir.display().to_string())
+        .cloned()
+}
+
+fn record_prune_in(store: &Path, lock: &Path, meta_dir: &Path, run: &PruneRun) -> Result<()> {
+    let key = meta_dir.display().to_string();
+    meta_core::store::update(store, lock, |runs: &mut PruneRuns| {
+        runs.workspaces.insert(key, run.clone());
+    })
+}
+
+// ── Status Across Sets ──────────────────────────────────
+
+/// Change counts of a diff.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DiffStat {
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// One repo of a set, compared with its primary checkout's branch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RepoState {
+    pub alias: String,
+    pub branch: String,
+    /// The primary checkout's branch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// Files with uncommitted changes
+    pub modified: usize,
+    /// Commits on the worktree branch that `base` does not have
+    pub ahead: usize,
+    /// Commits on `base` since the worktree branch forked from it
+    pub behind: usize,
+    /// The working tree against the fork point, when diffs were asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<DiffStat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Summary of one worktree set, for `status --all` and `diff --all`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SetState {
+    pub name: String,
+    pub path: PathBuf,
+    /// Some repo has uncommitted changes
+    pub dirty: bool,
+    /// Some repo's branch has commits of its own or is behind its base
+    pub diverged: bool,
+    pub repos: Vec<RepoState>,
+}
+
+/// `meta worktree status --all` and, with `diff`, `meta worktree diff
+/// --all`: every set of the workspace at a glance.
+pub fn handle_all_sets(meta_dir: &Path, diff: bool, json: bool) -> Result<()> {
+    let states = all_set_states(&worktrees_root(meta_dir), diff)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&states)?);
+    } else if diff {
+        print!("{}", format_set_diffs(&states));
+    } else {
+        print!("{}", format_set_states(&states));
+    }
+    Ok(())
+}
+
+/// The state of every set under `root`, collected in parallel. With
+/// `diff`, each repo also gets a diff stat against its fork point.
+pub fn all_set_states(root: &Path, diff: bool) -> Result<Vec<SetState>> {
+    use rayon::prelude::*;
+
+    list_sets(root)
+        .par_iter()
+        .map(|(name, path)| set_state(name, path, diff))
+        .collect()
+}
+
+/// The state of one set.
+pub fn set_state(name: &str, task_dir: &Path, diff: bool) -> Result<SetState> {
+    let repos: Vec<RepoState> = discover_worktree_repos(task_dir)?
+        .iter()
+        .map(|repo| repo_state(repo, diff))
+        .collect();
+    Ok(SetState {
+        name: name.to_string(),
+        path: task_dir.to_path_buf(),
+        dirty: repos.iter().any(|r| r.modified > 0),
+        diverged: repos.iter().any(|r| r.ahead > 0 || r.behind > 0),
+        repos,
+    })
+}
+
+/// One line per set, with the repos that have uncommitted work or have
+/// diverged from their base underneath.
+pub fn format_set_states(states: &[SetState]) -> String {
+    if states.is_empty() {
+        return "No worktree sets\n".to_string();
+    }
+    let mut out = String::new();
+    for set in states {
+        let mark = if set.dirty || set.diverged {
+            "!".yellow()
+        } else {
+            "✓".green()
+        };
+        let mut notes = Vec::new();
+        let dirty = set.repos.iter().filter(|r| r.modified > 0).count();
+        if dirty > 0 {
+            notes.push(format!("{dirty} with uncommitted changes"));
+        }
+        let diverged = set.repos.iter().filter(|r| r.ahead + r.behind > 0).count();
+        if diverged > 0 {
+            notes.push(format!("{diverged} diverged"));
+        }
+        let notes = if notes.is_empty() {
+            "clean".dimmed().to_string()
+        } else {
+            notes.join(", ")
+        };
+        out.push_str(&format!(
+            "{mark} {} ({} repos): {notes}\n",
+            set.name.bold(),
+            set.repos.len()
+        ));
+        for repo in &set.repos {
+            let mut parts = Vec::new();
+            if repo.modified > 0 {
+                parts.push(format!("{} modified", repo.modified));
+            }
+            if repo.ahead > 0 {
+                parts.push(format!("↑{}", repo.ahead));
+            }
+            if repo.behind > 0 {
+                parts.push(format!("↓{}", repo.behind));
+            }
+            if let Some(error) = &repo.error {
+                parts.push(error.red().to_string());
+            }
+            if !parts.is_empty() {
+                let base = repo.base.as_deref().unwrap_or("?");
+                out.push_str(&format!(
+                    "    {} [{} vs {base}]: {}\n",
+                    repo.alias.cyan(),
+                    repo.branch,
+                    parts.join(" ")
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// Per set, the repos whose working tree differs from their fork point,
+/// with a total for the set.
+pub fn format_set_diffs(states: &[SetState]) -> String {
+    if states.is_empty() {
+        return "No worktree sets\n".to_string();
+    }
+    let stat = |d: &DiffStat| {
+        format!(
+            "{} file(s) {} {}",
+            d.files_changed,
+            format!("+{}", d.insertions).green(),
+            format!("-{}", d.deletions).red()
+        )
+    };
+    let mut out = String::new();
+    for set in states {
+        let mut total = DiffStat::default();
+        let mut lines = String::new();
+        for repo in &set.repos {
+            if let Some(error) = &repo.error {
+                lines.push_str(&format!("    {}: {}\n", repo.alias.cyan(), error.red()));
+            }
+            let Some(diff) = repo.diff.filter(|d| d.files_changed > 0) else {
+                continue;
+            };
+            total.files_changed += diff.files_changed;
+            total.insertions += diff.insertions;
+            total.deletions += diff.deletions;
+            lines.push_str(&format!("    {}: {}\n", repo.alias.cyan(), stat(&diff)));
+        }
+        if total.files_changed == 0 {
+            out.push_str(&format!("{}: {}\n", set.name.bold(), "no changes".dimmed()));
+        } else {
+            out.push_str(&format!("{}: {}\n", set.name.bold(), stat(&total)));
+        }
+        out.push_str(&lines);
+    }
+    out
+}
+
+fn repo_state(repo: &WorktreeRepoInfo, diff: bool) -> RepoState {
+    let mut state = RepoState {
+        alias: repo.alias.clone(),
+        branch: repo.branch.clone(),
+        base: git_utils::current_branch(&repo.source_path),
+        modified: git_utils::dirty_file_count(&repo.path).unwrap_or(0),
+        ..Default::default()
+    };
+    if let Some(base) = state.base.clone() {
+        if let Err(e) = compare_with_base(&repo.path, &base, diff, &mut state) {
+            state.error = Some(format!("{e:#}"));
+        }
+    }
+    state
+}
+
+fn compare_with_base(path: &Path, base: &str, diff: bool, state: &mut RepoState) -> Result<()> {
+    let counts = git(
+        path,
+        &[
+            "rev-list",
+            "--left-right",
+            "--count",
+            &format!("HEAD...{base}"),
+        ],
+    )?;
+    let mut counts = counts.split_whitespace().map(|n| n.parse().unwrap_or(0));
+    state.ahead = counts.next().unwrap_or(0);
+    state.behind = counts.next().unwrap_or(0);
+
+    if diff {
+        let fork = git(path, &["merge-base", base, "HEAD"])?;
+        let mut stat = DiffStat::default();
+        for line in git(path, &["diff", "--numstat", &fork])?.lines() {
+            let mut cols = line.split('\t');
+            // Binary files show "-" for both counts
+            stat.files_changed += 1;
+            stat.insertions += cols.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+            stat.deletions += cols.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+        }
+        state.diff = Some(stat);
+    }
+    Ok(())
+}
+
+// ── Exec Across Sets ────────────────────────────────────
+
+/// Environment variable naming the worktree set a command runs in, set by
+/// [`multi_set_commands`].
+pub const SET_ENV: &str = "META_WORKTREE_SET";
+
+/// The sets named in `names` (comma-separated, e.g. `set-a,set-b`), or
+/// every set under `root` with `all`. Unknown names are an error.
+pub fn resolve_sets(root: &Path, names: &str, all: bool) -> Result<Vec<(String, PathBuf)>> {
+    let existing = list_sets(root);
+    if all {
+        anyhow::ensure!(
+            !existing.is_empty(),
+            "No worktree sets in {}",
+            root.display()
+        );
+        return Ok(existing);
+    }
+    let mut sets = Vec::new();
+    for name in names.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let set = existing
+            .iter()
+            .find(|(n, _)| n == name)
+            .with_context(|| format!("Worktree set '{name}' not found"))?;
+        if !sets.contains(set) {
+            sets.push(set.clone());
+        }
+    }
+    anyhow::ensure!(!sets.is_empty(), "No worktree sets given");
+    Ok(sets)
+}
+
+/// `meta worktree exec` across several sets: `--all-sets`, or names
+/// separated by commas.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultiSetExec {
+    /// Comma-separated set names; ignored with `all`.
+    pub names: String,
+    pub all: bool,
+    /// Repo aliases to limit to (`--include`).
+    pub include: Vec<String>,
+    pub parallel: bool,
+    /// The command, joined as `meta exec` joins it.
+    pub command: String,
+}
+
+impl MultiSetExec {
+    /// Parse the arguments after `exec`. `None` when they name a single set,
+    /// which the worktree plugin runs itself.
+    pub fn parse(args: &[String]) -> Result<Option<MultiSetExec>> {
+        let (options, command) = match args.iter().position(|a| a == "--") {
+            Some(i) => (&args[..i], &args[i + 1..]),
+            None => (args, &[][..]),
+        };
+        let mut exec = MultiSetExec::default();
+        let mut options = options.iter();
+        while let Some(arg) = options.next() {
+            match arg.as_str() {
+                "--all-sets" => exec.all = true,
+                "--parallel" => exec.parallel = true,
+                "--include" => {
+                    let value = options.next().context("--include needs a value")?;
+                    exec.include.extend(split_list(value));
+                }
+                other => match other.strip_prefix("--include=") {
+                    Some(value) => exec.include.extend(split_list(value)),
+                    // Anything else is the plugin's to handle
+                    None if other.starts_with('-') => return Ok(None),
+                    None if exec.names.is_empty() => exec.names = other.to_string(),
+                    None => return Ok(None),
+                },
+            }
+        }
+        if !exec.all && !exec.names.contains(',') {
+            return Ok(None);
+        }
+        anyhow::ensure!(!command.is_empty(), "No command given (use -- <command>)");
+        exec.command = command.join(" ");
+        Ok(Some(exec))
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// One command per repo of every set, in set then repo order, for the
+/// usual runner. Each runs in its own set's worktree with [`SET_ENV`] set,
+/// so sets never share a working directory. `include` limits the repos by
+/// alias when non-empty.
+pub fn multi_set_commands(
+    sets: &[(String, PathBuf)],
+    cmd: &str,
+    include: &[String],
+) -> Result<Vec<DirCommand>> {
+    let mut commands = Vec::new();
+    for (name, task_dir) in sets {
+        for repo in discover_worktree_repos(task_dir)? {
+            if !include.is_empty() && !include.contains(&repo.alias) {
+                continue;
+            }
+            commands.push(DirCommand {
+                dir: repo.path.display().to_string(),
+                cmd: cmd.to_string(),
+                env: Some(std::collections::HashMap::from([(
+                    SET_ENV.to_string(),
+                    name.clone(),
+                )])),
+            });
+        }
+    }
+    Ok(commands)
+}
+
+// ── Disk Usage ──────────────────────────────────────────
+
+/// Directory under the worktrees root holding build artifacts shared by
+/// every set (see `shared` in [`crate::worktree_template`]).
+pub const SHARED_DIR: &str = ".shared";
+
+/// The worktree sets under `root`, by name. Hidden directories (such as
+/// [`SHARED_DIR`]) are not sets.
+pub fn list_sets(root: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut sets: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| (e.file_name().to_string_lossy().to_string(), e.path()))
+        .filter(|(name, _)| !name.starts_with('.'))
+        .collect();
+    sets.sort();
+    sets
+}
+
+/// Disk used by one worktree set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SetUsage {
+    pub name: String,
+    pub path: PathBuf,
+    /// Bytes on disk, each hard-linked file counted once
+    pub bytes: u64,
+    /// Part of `bytes` in files hard-linked from elsewhere (the primary
+    /// checkout or another set), which this set does not add
+    pub hardlinked_bytes: u64,
+    /// Per repo alias
+    pub repos: BTreeMap<String, u64>,
+}
+
+/// Disk used by all worktree sets of a workspace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DiskUsage {
+    pub sets: Vec<SetUsage>,
+    /// Bytes in [`SHARED_DIR`]
+    pub shared_bytes: u64,
+}
+
+impl DiskUsage {
+    /// Bytes the sets add on top of the primary checkouts.
+    pub fn total(&self) -> u64 {
+        self.shared_bytes
+            + self
+                .sets
+                .iter()
+                .map(|s| s.bytes - s.hardlinked_bytes)
+                .sum::<u64>()
+    }
+}
+
+/// `meta worktree du`: disk used per set, per repo with `verbose`.
+pub fn handle_du(meta_dir: &Path, verbose: bool, json: bool) -> Result<()> {
+    let usage = disk_usage(&worktrees_root(meta_dir))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&usage)?);
+    } else {
+        print!("{}", format_usage(&usage, verbose));
+    }
+    Ok(())
+}
+
+/// Measure every set under `root`. Symlinks are not followed, so shared
+/// artifacts are counted once, under `shared_bytes`.
+pub fn disk_usage(root: &Path) -> Result<DiskUsage> {
+    let sets = list_sets(root)
+        .into_iter()
+        .map(|(name, path)| set_usage(&name, &path))
+        .collect::<Result<_>>()?;
+    let shared = root.join(SHARED_DIR);
+    let shared_bytes = if shared.is_dir() {
+        tree_usage(&shared).0
+    } else {
+        0
+    };
+    Ok(DiskUsage { sets, shared_bytes })
+}
+
+/// Measure one set, attributing files to the repo they are in.
+pub fn set_usage(name: &str, task_dir: &Path) -> Result<SetUsage> {
+    let (bytes, hardlinked_bytes) = tree_usage(task_dir);
+    let repos = discover_worktree_repos(task_dir)?
+        .into_iter()
+        .filter(|r| r.alias != ".")
+        .map(|r| (r.alias, tree_usage(&r.path).0))
+        .collect();
+    Ok(SetUsage {
+        name: name.to_string(),
+        path: task_dir.to_path_buf(),
+        bytes,
+        hardlinked_bytes,
+        repos,
+    })
+}
+
+/// One line per set, largest first, with a total.
+pub fn format_usage(usage: &DiskUsage, verbose: bool) -> String {
+    if usage.sets.is_empty() && usage.shared_bytes == 0 {
+        return "No worktree sets\n".to_string();
+    }
+    let mut sets: Vec<&SetUsage> = usage.sets.iter().collect();
+    sets.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.name.cmp(&b.name)));
+    let width = sets
+        .iter()
+        .map(|s| s.name.len())
+        .chain([SHARED_DIR.len(), 5])
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for set in sets {
+        let linked = if set.hardlinked_bytes > 0 {
+            format!("  ({} hardlinked)", human_bytes(set.hardlinked_bytes))
+        } else {
+            String::new()
+        };
+        out.push_str(&format!(
+            "{:<width$}  {:>9}{linked}\n",
+            set.name,
+            human_bytes(set.bytes)
+        ));
+        if verbose {
+            for (alias, bytes) in &set.repos {
+                out.push_str(&format!(
+                    "  {alias:<w$}  {:>9}\n",
+                    human_bytes(*bytes),
+                    w = width - 2
+                ));
+            }
+        }
+    }
+    if usage.shared_bytes > 0 {
+        out.push_str(&format!(
+            "{SHARED_DIR:<width$}  {:>9}\n",
+            human_bytes(usage.shared_bytes)
+        ));
+    }
+    out.push_str(&format!(
+        "{:<width$}  {:>9}  (excluding hardlinked files)\n",
+        "total",
+        human_bytes(usage.total())
+    ));
+    out
+}
+
+/// `1536` → `1.5 KiB`.
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// (bytes, bytes in files with other hard links) under `dir`, each inode
+/// counted once.
+fn tree_usage(dir: &Path) -> (u64, u64) {
+    let mut seen = std::collections::HashSet::new();
+    let (mut bytes, mut linked) = (0, 0);
+    for entry in walkdir::WalkDir::new(dir).into_iter().flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let (size, links, id) = file_size(&meta);
+        if id.is_some_and(|id| !seen.insert(id)) {
+            continue;
+        }
+        bytes += size;
+        if links > 1 {
+            linked += size;
+        }
+    }
+    (bytes, linked)
+}
+
+/// Allocated size, link count and (device, inode) of a file.
+#[cfg(unix)]
+fn file_size(meta: &std::fs::Metadata) -> (u64, u64, Option<(u64, u64)>) {
+    use std::os::unix::fs::MetadataExt;
+    (
+        meta.blocks() * 512,
+        meta.nlink(),
+        Some((meta.dev(), meta.ino())),
+    )
+}
+
+#[cfg(not(unix))]
+fn file_size(meta: &std::fs::Metadata) -> (u64, u64, Option<(u64, u64)>) {
+    (meta.len(), 1, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::testing::init_repo;
+
+    fn run(dir: &Path, args: &[&str]) {
+        git(dir, args).unwrap();
+    }
+
+    /// A repo with one commit and a worktree of it in `.worktrees/<task>/repo`.
+    fn repo_with_worktree(tmp: &Path, task: &str) -> (PathBuf, PathBuf) {
+        let source = tmp.join("repo");
+        init_repo(&source);
+        commit_file(&source, "a.txt", "one");
+
+        let task_dir = tmp.join(".worktrees").join(task);
+        let worktree = task_dir.join("repo");
+        run(
+            &source,
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                task,
+                &worktree.to_string_lossy(),
+            ],
+        );
+        (source, task_dir)
+    }
+
+    #[test]
+    fn clones_missing_repos_from_their_url() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, _) = repo_with_worktree(tmp.path(), "t1");
+        let ws = tmp.path().join("ws");
+        std::fs::create_dir(&ws).unwrap();
+        let config = serde_json::json!({"projects": {
+            "api": {"url": source.display().to_string()},
+            "docs": {"path": "docs"}
+        }});
+        std::fs::write(ws.join(".meta"), config.to_string()).unwrap();
+
+        let all = ["api".to_string(), ".".to_string()];
+        assert_eq!(clone_missing_repos(&ws, &all, true).unwrap(), ["api"]);
+        assert!(ws.join("api/a.txt").exists());
+        assert!(clone_missing_repos(&ws, &all, true).unwrap().is_empty());
+
+        let err = clone_missing_repos(&ws, &["docs".to_string()], true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'docs' is not cloned and has no repo url in .meta"
+        );
+    }
+
+    #[test]
+    fn prune_schedule_parses_and_tracks_last_run() {
+        let config = serde_json::json!({"worktree": {"prune": {"auto": true, "interval": "6h"}}});
+        let schedule = prune_schedule(&config).unwrap();
+        assert!(schedule.auto);
+        assert_eq!(schedule.interval, Duration::from_secs(6 * 3600));
+        assert_eq!(
+            prune_schedule(&serde_json::json!({})).unwrap(),
+            PruneSchedule::default()
+        );
+        let bad = serde_json::json!({"worktree": {"prune": {"interval": "soon"}}});
+        assert!(prune_schedule(&bad).is_err());
+
+        let now = chrono::Local::now();
+        let ran = |hours_ago: i64| PruneRun {
+            last_run: (now - chrono::Duration::hours(hours_ago)).to_rfc3339(),
+            ok: true,
+            error: None,
+        };
+        assert!(prune_due(&schedule, None, now));
+        assert!(!prune_due(&schedule, Some(&ran(1)), now));
+        assert!(prune_due(&schedule, Some(&ran(7)), now));
+        assert!(!prune_due(&PruneSchedule::default(), None, now));
+
+        let tmp = tempfile::tempdir().unwrap();
+        let (store, lock) = (tmp.path().join("prune.json"), tmp.path().join("prune.lock"));
+        let ws = tmp.path().join("ws");
+        assert_eq!(last_prune_in(&store, &ws), None);
+        record_prune_in(&store, &lock, &ws, &ran(2)).unwrap();
+        assert_eq!(last_prune_in(&store, &ws), Some(ran(2)));
+    }
+
+    #[test]
+    fn set_states_report_dirty_and_diverged_sets() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, task_dir) = repo_with_worktree(tmp.path(), "t1");
+        let root = tmp.path().join(".worktrees");
+        let wt = task_dir.join("repo");
+        std::fs::write(wt.join("b.txt"), "two\nthree\n").unwrap();
+        run(&wt, &["add", "."]);
+        run(&wt, &["commit", "-q", "-m", "b"]);
+        std::fs::write(wt.join("a.txt"), "changed").unwrap();
+        std::fs::write(source.join("c.txt"), "base").unwrap();
+        run(&source, &["add", "."]);
+        run(&source, &["commit", "-q", "-m", "c"]);
+
+        let states = all_set_states(&root, true).unwrap();
+        assert_eq!(states.len(), 1);
+        let set = &states[0];
+        assert!(set.dirty && set.diverged);
+        let repo = &set.repos[0];
+        assert_eq!((repo.alias.as_str(), repo.branch.as_str()), ("repo", "t1"));
+        assert_eq!((repo.modified, repo.ahead, repo.behind), (1, 1, 1));
+        assert_eq!(
+            repo.diff,
+            Some(DiffStat {
+                files_changed: 2,
+                insertions: 3,
+                deletions: 1
+            })
+        );
+
+        let text = console::strip_ansi_codes(&format_set_states(&states)).to_string();
+        assert!(
+            text.starts_with("! t1 (1 repos): 1 with uncommitted changes, 1 diverged\n"),
+            "{text}"
+        );
+        assert!(text.contains("    repo [t1 vs "), "{text}");
+        assert!(text.ends_with("]: 1 modified ↑1 ↓1\n"), "{text}");
+        let text = console::strip_ansi_codes(&format_set_diffs(&states)).to_string();
+        assert_eq!(text, "t1: 2 file(s) +3 -1\n    repo: 2 file(s) +3 -1\n");
+    }
+
+    #[test]
+    fn multi_set_commands_cover_each_named_set() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, t1) = repo_with_worktree(tmp.path(), "t1");
+        let t2 = tmp.path().join(".worktrees/t2");
+        let wt = t2.join("repo").to_string_lossy().to_string();
+        run(&source, &["worktree", "add", "-q", "-b", "t2", &wt]);
+        let root = tmp.path().join(".worktrees");
+
+        let sets = resolve_sets(&root, "t2, t1,t2", false).unwrap();
+        let names: Vec<&str> = sets.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["t2", "t1"]);
+        assert_eq!(resolve_sets(&root, "", true).unwrap().len(), 2);
+        let err = resolve_sets(&root, "t1,t3", false).unwrap_err();
+        assert_eq!(err.to_string(), "Worktree set 't3' not found");
+
+        let commands = multi_set_commands(&sets, "cargo test", &[]).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].dir, wt);
+        assert_eq!(commands[0].env.as_ref().unwrap()[SET_ENV], "t2");
+        assert_eq!(commands[1].dir, t1.join("repo").display().to_string());
+        assert_eq!(commands[1].cmd, "cargo test");
+        let only = multi_set_commands(&sets, "ls", &["web".to_string()]).unwrap();
+        assert!(only.is_empty());
+    }
+
+    #[test]
+    fn disk_usage_counts_sets_repos_and_shared_artifacts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, task_dir) = repo_with_worktree(tmp.path(), "t1");
+        let root = tmp.path().join(".worktrees");
+        std::fs::write(task_dir.join("repo/big.bin"), vec![1u8; 64 * 1024]).unwrap();
+        std::fs::write(source.join("dep.bin"), vec![2u8; 32 * 1024]).unwrap();
+        std::fs::hard_link(source.join("dep.bin"), task_dir.join("repo/dep.bin")).unwrap();
+        std::fs::create_dir_all(root.join(SHARED_DIR)).unwrap();
+        std::fs::write(root.join(SHARED_DIR).join("cache.bin"), vec![3u8; 8 * 1024]).unwrap();
+
+        let usage = disk_usage(&root).unwrap();
+        assert_eq!(usage.sets.len(), 1);
+        let set = &usage.sets[0];
+        assert_eq!(set.name, "t1");
+        assert!(set.bytes >= 96 * 1024, "{set:?}");
+        assert_eq!(set.repos["repo"], set.bytes);
+        #[cfg(unix)]
+        {
+            assert_eq!(set.hardlinked_bytes, 32 * 1024);
+            assert_eq!(usage.shared_bytes, 8 * 1024);
+            assert_eq!(usage.total(), set.bytes - 32 * 1024 + 8 * 1024);
+        }
+
+        let text = format_usage(&usage, true);
+        assert!(text.starts_with("t1 "), "{text}");
+        assert!(text.contains("\n  repo "), "{text}");
+        assert!(text.contains("\n.shared "), "{text}");
+        assert_eq!(human_bytes(1536), "1.5 KiB");
+        assert_eq!(human_bytes(12), "12 B");
+    }
+
+    #[test]
+    fn rename_moves_the_set_and_repairs_links() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tmp = tmp.path().canonicalize().unwrap();
+        let (source, task_dir) = repo_with_worktree(&tmp, "old");
+
+        let new_dir = rename_worktree_set(&task_dir, "new").unwrap();
+        assert_eq!(new_dir, tmp.join(".worktrees/new"));
+        assert!(!task_dir.exists());
+
+        let repos = discover_worktree_repos(&new_dir).unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].source_path, source);
+        assert_eq!(repos[0].branch, "old");
+        let list = git(&source, &["worktree", "list", "--porcelain"]).unwrap();
+        assert!(list.contains(&new_dir.join("repo").display().to_string()));
+    }
+
+    #[test]
+    fn rename_rejects_bad_or_taken_names() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_source, task_dir) = repo_with_worktree(tmp.path(), "a");
+        std::fs::create_dir(tmp.path().join(".worktrees/b")).unwrap();
+        assert!(rename_worktree_set(&task_dir, "b").is_err());
+        assert!(rename_worktree_set(&task_dir, "x/y").is_err());
+        assert!(rename_worktree_set(&task_dir, "").is_err());
+        assert!(task_dir.exists());
+    }
+
+    #[test]
+    fn name_arg_skips_options() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(name_arg(&args(&["auth-fix", "--all"])), Some("auth-fix"));
+        assert_eq!(
+            name_arg(&args(&["--repo", "api", "--ephemeral", "auth-fix"])),
+            Some("auth-fix")
+        );
+        assert_eq!(name_arg(&args(&["--ttl=1h", "auth-fix"])), Some("auth-fix"));
+        assert_eq!(name_arg(&args(&["--repo", "api"])), None);
+    }
+
+    #[test]
+    fn clone_mode_is_taken_from_create_args() {
+        let mut args: Vec<String> = ["create", "t", "--clone-mode", "reflink", "--all"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            take_clone_mode(&mut args).unwrap(),
+            Some(CloneMode::Reflink)
+        );
+        assert_eq!(args, ["create", "t", "--all"]);
+
+        let mut args = vec!["--clone-mode=hardlink".to_string()];
+        assert_eq!(
+            take_clone_mode(&mut args).unwrap(),
+            Some(CloneMode::Hardlink)
+        );
+        assert!(args.is_empty());
+        assert!(take_clone_mode(&mut vec!["--clone-mode".to_string()]).is_err());
+        assert!(take_clone_mode(&mut vec!["--clone-mode=fast".to_string()]).is_err());
+        assert_eq!(take_clone_mode(&mut vec!["t".to_string()]).unwrap(), None);
+    }
+
+    #[test]
+    fn multi_set_exec_args() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            MultiSetExec::parse(&args(&["a,b", "--parallel", "--", "cargo", "test"])).unwrap(),
+            Some(MultiSetExec {
+                names: "a,b".to_string(),
+                parallel: true,
+                command: "cargo test".to_string(),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            MultiSetExec::parse(&args(&["--all-sets", "--include", "api,web", "--", "make"]))
+                .unwrap(),
+            Some(MultiSetExec {
+                all: true,
+                include: vec!["api".to_string(), "web".to_string()],
+                command: "make".to_string(),
+                ..Default::default()
+            })
+        );
+        // One set, or options only the plugin knows: left to the plugin
+        assert_eq!(
+            MultiSetExec::parse(&args(&["a", "--", "make"])).unwrap(),
+            None
+        );
+        assert_eq!(
+            MultiSetExec::parse(&args(&["a,b", "--ephemeral", "--", "make"])).unwrap(),
+            None
+        );
+        assert!(MultiSetExec::parse(&args(&["--all-sets"])).is_err());
+    }
+
+    #[test]
+    fn create_aliases_come_from_repo_options() {
+        let tmp = tempfile::tempdir().unwrap();
+        let args: Vec<String> = ["t", "--repo", "api:main", "--repo=web", "--repo", "."]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            create_aliases(tmp.path(), &args).unwrap(),
+            ["api", "web", "."]
+        );
+
+        let config =
+            serde_json::json!({"projects": {"api": "git@x:api.git", "web": "git@x:web.git"}});
+        std::fs::write(tmp.path().join(".meta"), config.to_string()).unwrap();
+        let mut all = create_aliases(tmp.path(), &["t".to_string(), "--all".to_string()]).unwrap();
+        all.sort();
+        assert_eq!(all, ["api", "web"]);
+    }
+
+    #[test]
+    fn pre_create_hook_can_veto() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = serde_json::json!({
+            "projects": {},
+            "worktree": {"hooks": {"pre-create": "echo 'no creating on fridays' >&2; exit 1"}},
+        });
+        std::fs::write(tmp.path().join(".meta"), config.to_string()).unwrap();
+
+        let mut args: Vec<String> = ["create", "t", "--no-verify"].map(String::from).to_vec();
+        assert!(take_no_verify(&mut args));
+        assert_eq!(args, ["create", "t"]);
+        assert!(!take_no_verify(&mut args));
+
+        let projects = ["api".to_string()];
+        let err = fire_pre_create(tmp.path(), "t", &projects, false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MetaError>(),
+            Some(MetaError::HookRejected { .. })
+        ));
+        assert!(err.to_string().contains("no creating on fridays"));
+        fire_pre_create(tmp.path(), "t", &projects, true).unwrap();
+    }
+
+    #[test]
+    fn post_destroy_payload_has_repo_outcomes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, task_dir) = repo_with_worktree(tmp.path(), "task");
+        let config = serde_json::json!({
+            "projects": {},
+            "worktree": {"hooks": {"post-destroy": "cat > payload.json"}},
+        });
+        std::fs::write(tmp.path().join(".meta"), config.to_string()).unwrap();
+
+        let hook = PostHook::start(tmp.path(), "post-destroy", "task");
+        let worktree = task_dir.join("repo");
+        run(
+            &source,
+            &["worktree", "remove", &worktree.to_string_lossy()],
+        );
+        hook.fire_removed(tmp.path());
+
+        let payload: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(tmp.path().join("payload.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(payload["schema_version"], 2);
+        assert_eq!(payload["event"], "post-destroy");
+        assert_eq!(payload["name"], "task");
+        assert_eq!(payload["success"], true);
+        assert!(payload["duration_ms"].is_u64());
+        assert_eq!(payload["repos"][0]["alias"], "repo");
+        assert_eq!(payload["repos"][0]["branch"], "task");
+        assert_eq!(payload["repos"][0]["success"], true);
+    }
+
+    #[test]
+    fn template_is_applied_to_a_new_set() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, task_dir) = repo_with_worktree(tmp.path(), "task");
+        std::fs::write(source.join(".env"), "KEY=1").unwrap();
+        let config = serde_json::json!({
+            "projects": {"repo": "git@example.com:org/repo.git"},
+            "worktree": {"template": {"copy": [".env"], "setup": ["make setup"]}},
+        });
+        std::fs::write(tmp.path().join(".meta"), config.to_string()).unwrap();
+
+        let commands = apply_template(tmp.path(), "task", None).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(task_dir.join("repo/.env")).unwrap(),
+            "KEY=1"
+        );
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].cmd, "make setup");
+        assert_eq!(commands[0].dir, task_dir.join("repo").display().to_string());
+    }
+
+    #[test]
+    fn rename_repoints_the_store_entry() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = tmp.path().join("worktree.json");
+        let lock = tmp.path().join("worktree.lock");
+        let old = tmp.path().join(".worktrees/old");
+        let new = tmp.path().join(".worktrees/new");
+        let other = tmp.path().join(".worktrees/older");
+        let before = serde_json::json!({
+            "worktrees": {
+                old.display().to_string(): {
+                    "name": "old",
+                    "repos": [{"alias": "api", "path": old.join("api")}],
+                },
+                other.display().to_string(): {"name": "older", "repos": []},
+            },
+            "names": {"old": old, "unrelated": "old"},
+        });
+        std::fs::write(&store, before.to_string()).unwrap();
+
+        rename_in_store(&store, &lock, &old, &new, "old", "new").unwrap();
+        let after: serde_json::Value = meta_core::store::read(&store).unwrap();
+        assert_eq!(
+            after,
+            serde_json::json!({
+                "worktrees": {
+                    new.display().to_string(): {
+                        "name": "new",
+                        "repos": [{"alias": "api", "path": new.join("api")}],
+                    },
+                    other.display().to_string(): {"name": "older", "repos": []},
+                },
+                "names": {"new": new, "unrelated": "old"},
+            })
+        );
+    }
+
+    #[test]
+    fn prune_leaves_locked_expired_sets_alone() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join(".worktrees");
+        let locks = tmp.path().join("worktree_locks.json");
+        let store = tmp.path().join("worktree.json");
+        let lock = tmp.path().join("worktree.lock");
+        let (held, expired) = (root.join("held"), root.join("expired"));
+        let locked = serde_json::json!({
+            "sets": {held.display().to_string(): {"locked_at": "2026-01-05T10:00:00+00:00"}},
+        });
+        std::fs::write(&locks, locked.to_string()).unwrap();
+        let before = serde_json::json!({
+            "worktrees": {
+                held.display().to_string(): {"name": "held", "ttl_seconds": 1},
+                expired.display().to_string(): {"name": "expired", "ttl_seconds": 1},
+            },
+            "ephemeral": [held, expired],
+        });
+        std::fs::write(&store, before.to_string()).unwrap();
+
+        let shielded = shield_locked_sets_in(&locks, &store, &lock, &root).unwrap();
+        assert_eq!(shielded.names, ["held"]);
+
+        // The plugin prunes every expired set it can see
+        let during: serde_json::Value = meta_core::store::read(&store).unwrap();
+        assert_eq!(
+            during,
+            serde_json::json!({
+                "worktrees": {
+                    expired.display().to_string(): {"name": "expired", "ttl_seconds": 1},
+                },
+                "ephemeral": [expired],
+            })
+        );
+        let pruned = serde_json::json!({"worktrees": {}, "ephemeral": []});
+        std::fs::write(&store, pruned.to_string()).unwrap();
+
+        shielded.restore().unwrap();
+        let after: serde_json::Value = meta_core::store::read(&store).unwrap();
+        assert_eq!(
+            after,
+            serde_json::json!({
+                "worktrees": {
+                    held.display().to_string(): {"name": "held", "ttl_seconds": 1},
+                },
+                "ephemeral": [held],
+            })
+        );
+    }
+
+    fn commit_file(dir: &Path, file: &str, content: &str) {
+        std::fs::write(dir.join(file), content).unwrap();
+        run(dir, &["add", "."]);
+        run(dir, &["commit", "-q", "-m", file]);
+    }
+
+    fn worktree_repo(task_dir: &Path) -> WorktreeRepoInfo {
+        discover_worktree_repos(task_dir).unwrap().remove(0)
+    }
+
+    #[test]
+    fn merge_back_strategies() {
+        for (strategy, parents) in [
+            (MergeStrategy::Merge, 2),
+            (MergeStrategy::Squash, 1),
+            (MergeStrategy::Rebase, 1),
+        ] {
+            let tmp = tempfile::tempdir().unwrap();
+            let (source, task_dir) = repo_with_worktree(tmp.path(), "task");
+            let base = git_utils::current_branch(&source).unwrap();
+            commit_file(&task_dir.join("repo"), "b.txt", "feature");
+            commit_file(&source, "c.txt", "moved on");
+
+            let repo = worktree_repo(&task_dir);
+            assert_eq!(
+                merge_back(&repo, &base, strategy).unwrap(),
+                MergeOutcome::Merged
+            );
+            assert!(source.join("b.txt").exists(), "{strategy:?}");
+            let head = git(&source, &["log", "-1", "--format=%P"]).unwrap();
+            assert_eq!(head.split_whitespace().count(), parents, "{strategy:?}");
+            assert_eq!(
+                merge_back(&repo, &base, strategy).unwrap(),
+                MergeOutcome::UpToDate
+            );
+        }
+    }
+
+    #[test]
+    fn merge_back_reports_and_aborts_conflicts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, task_dir) = repo_with_worktree(tmp.path(), "task");
+        let base = git_utils::current_branch(&source).unwrap();
+        commit_file(&task_dir.join("repo"), "a.txt", "theirs");
+        commit_file(&source, "a.txt", "ours");
+
+        let repo = worktree_repo(&task_dir);
+        for strategy in [MergeStrategy::Merge, MergeStrategy::Rebase] {
+            let outcome = merge_back(&repo, &base, strategy).unwrap();
+            assert_eq!(
+                outcome,
+                MergeOutcome::Conflict {
+                    files: vec!["a.txt".to_string()]
+                }
+            );
+            assert_eq!(git_utils::is_dirty(&source), Some(false));
+        }
+        assert_eq!(
+            std::fs::read_to_string(source.join("a.txt")).unwrap(),
+            "ours"
+        );
+    }
+
+    #[test]
+    fn merge_back_set_defaults_to_the_primary_branch() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, task_dir) = repo_with_worktree(tmp.path(), "task");
+        let base = git_utils::current_branch(&source).unwrap();
+        commit_file(&task_dir.join("repo"), "b.txt", "feature");
+
+        let mut journal = Journal::disabled("worktree-merge-back", "task");
+        let reports = merge_back_set(&task_dir, None, MergeStrategy::Merge, &mut journal).unwrap();
+        assert_eq!(
+            reports,
+            [MergeReport {
+                alias: "repo".to_string(),
+                base: base.clone(),
+                outcome: Some(MergeOutcome::Merged),
+                error: None,
+            }]
+        );
+        assert!(source.join("b.txt").exists());
+
+        let reports =
+            merge_back_set(&task_dir, Some("nope"), MergeStrategy::Merge, &mut journal).unwrap();
+        assert!(reports[0].failed());
+        assert!(reports[0].error.is_some());
+    }
+
+    #[test]
+    fn merge_strategy_parses() {
+        assert_eq!(
+            "squash".parse::<MergeStrategy>().unwrap(),
+            MergeStrategy::Squash
+        );
+        assert!("octopus".parse::<MergeStrategy>().is_err());
+    }
+
+    #[test]
+    fn push_set_pushes_only_changed_repos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, task_dir) = repo_with_worktree(tmp.path(), "task");
+        let base = git_utils::current_branch(&source).unwrap();
+        let remote = tmp.path().join("remote.git");
+        run(
+            tmp.path(),
+            &["init", "-q", "--bare", &remote.to_string_lossy()],
+        );
+        run(
+            &source,
+            &["remote", "add", "origin", &remote.to_string_lossy()],
+        );
+
+        let repos = discover_worktree_repos(&task_dir).unwrap();
+        let options = PushOptions {
+            base: &base,
+            ..Default::default()
+        };
+        assert_eq!(push_set(&repos, &options)["repo"], PushResult::default());
+
+        commit_file(&task_dir.join("repo"), "b.txt", "feature");
+        let result = &push_set(&repos, &options)["repo"];
+        assert!(result.pushed, "{result:?}");
+        assert!(git(&remote, &["rev-parse", "--verify", "refs/heads/task"]).is_ok());
+    }
+
+    #[test]
+    fn related_prs_skip_the_own_repo() {
+        let urls = [("api", "https://x/api/1"), ("web", "https://x/web/2")];
+        assert_eq!(
+            with_related_prs("Fix login", &urls, "api"),
+            "Fix login\n\nRelated PRs:\n- web: https://x/web/2\n"
+        );
+    }
+
+    #[test]
+    fn switch_target_resolves_set_and_repo() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_source, task_dir) = repo_with_worktree(tmp.path(), "task");
+        assert_eq!(switch_target(tmp.path(), "task", None).unwrap(), task_dir);
+        assert_eq!(
+            switch_target(tmp.path(), "task", Some("repo")).unwrap(),
+            task_dir.join("repo")
+        );
+        assert!(switch_target(tmp.path(), "task", Some("nope")).is_err());
+        assert!(switch_target(tmp.path(), "other", None).is_err());
+        assert!(switch_target(tmp.path(), "../task", None).is_err());
+        assert!(switch_target(tmp.path(), "..", None).is_err());
+    }
+
+    #[test]
+    fn worktrees_root_honors_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(worktrees_root(tmp.path()), tmp.path().join(".worktrees"));
+        std::fs::write(
+            tmp.path().join(".meta"),
+            r#"{"projects": {}, "worktrees_dir": "../wt"}"#,
+        )
+        .unwrap();
+        assert_eq!(worktrees_root(tmp.path()), tmp.path().join("../wt"));
+    }
+
+    #[test]
+    fn locked_sets_refuse_removal_until_unlocked() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (source, task_dir) = repo_with_worktree(tmp.path(), "task");
+        let (store, lock) = (tmp.path().join("locks.json"), tmp.path().join("locks.lock"));
+
+        let info = lock_set_in(&store, &lock, &task_dir, Some("agent running")).unwrap();
+        assert_eq!(info.reason.as_deref(), Some("agent running"));
+        assert_eq!(lock_info_in(&store, &task_dir), Some(info));
+        let err = ensure_unlocked_in(&store, &task_dir).unwrap_err();
+        assert!(err.to_string().contains("'task' is locked (agent running)"));
+
+        // Git itself refuses too
+        let worktree = task_dir.join("repo");
+        assert!(git(
+            &source,
+            &["worktree", "remove", &worktree.to_string_lossy()]
+        )
+        .is_err());
+        // Locking again is fine
+        lock_set_in(&store, &lock, &task_dir, None).unwrap();
+
+        unlock_set_in(&store, &lock, &task_dir).unwrap();
+        assert_eq!(lock_info_in(&store, &task_dir), None);
+        ensure_unlocked_in(&store, &task_dir).unwrap();
+        git(
+            &source,
+            &["worktree", "remove", &worktree.to_string_lossy()],
+        )
+        .unwrap();
+    }
+}
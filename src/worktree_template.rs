@@ -0,0 +1,541 @@
+//! Worktree templates: bootstrap freshly created worktrees.
+//!
+//! A `worktree.template` section in `.meta` lists files to bring over from
+//! each repo's primary checkout and setup commands to run in the new
+//! worktree, so a set is runnable straight after `meta worktree create`:
+//!
+//! ```json
+//! "worktree": {
+//!   "template": {
+//!     "copy": [".env"],
+//!     "symlink": [".cache"],
+//!     "hardlink": ["node_modules"],
+//!     "shared": ["target"],
+//!     "clone_mode": "reflink",
+//!     "setup": ["npm ci --prefer-offline"],
+//!     "repos": {
+//!       "api": { "copy": ["config/local.toml"], "setup": ["cargo fetch"] }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Paths are relative to the repo. `hardlink` recreates a directory tree
+//! with hard links to the original files (falling back to copies across
+//! filesystems), which is cheap for large, rarely modified trees. `shared`
+//! directories exist once per repo under `.worktrees/.shared/<repo>/` and
+//! every set symlinks to them, so build artifacts such as `target` are not
+//! duplicated per set; the first set seeds them from the primary checkout.
+//! `clone_mode` (`copy`, `hardlink` or `reflink`) decides how `copy`
+//! entries are made, and can be overridden per `meta worktree create`.
+//! Entries under `repos` extend the top-level lists for that repo. Sources
+//! that do not exist and destinations that already exist are skipped.
+
+use anyhow::{Context, Result};
+use loop_lib::DirCommand;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use crate::config;
+use crate::worktree::WorktreeRepoInfo;
+
+/// What to set up in one repo's worktree.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoTemplate {
+    #[serde(default)]
+    pub copy: Vec<String>,
+    #[serde(default)]
+    pub symlink: Vec<String>,
+    #[serde(default)]
+    pub hardlink: Vec<String>,
+    #[serde(default)]
+    pub shared: Vec<String>,
+    #[serde(default)]
+    pub setup: Vec<String>,
+}
+
+/// The `worktree.template` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorktreeTemplate {
+    #[serde(default)]
+    pub copy: Vec<String>,
+    #[serde(default)]
+    pub symlink: Vec<String>,
+    #[serde(default)]
+    pub hardlink: Vec<String>,
+    #[serde(default)]
+    pub shared: Vec<String>,
+    #[serde(default)]
+    pub setup: Vec<String>,
+    /// How `copy` entries are made unless `create` says otherwise.
+    #[serde(default)]
+    pub clone_mode: Option<CloneMode>,
+    /// Per-repo additions, keyed by project name.
+    #[serde(default)]
+    pub repos: BTreeMap<String, RepoTemplate>,
+}
+
+impl WorktreeTemplate {
+    /// The top-level lists extended with the repo's own entries.
+    pub fn for_repo(&self, alias: &str) -> RepoTemplate {
+        let extra = self.repos.get(alias).cloned().unwrap_or_default();
+        let join = |all: &[String], own: Vec<String>| [all.to_vec(), own].concat();
+        RepoTemplate {
+            copy: join(&self.copy, extra.copy),
+            symlink: join(&self.symlink, extra.symlink),
+            hardlink: join(&self.hardlink, extra.hardlink),
+            shared: join(&self.shared, extra.shared),
+            setup: join(&self.setup, extra.setup),
+        }
+    }
+
+    fn paths(&self) -> impl Iterator<Item = &String> {
+        let repos = self.repos.values();
+        self.copy
+            .iter()
+            .chain(&self.symlink)
+            .chain(&self.hardlink)
+            .chain(&self.shared)
+            .chain(repos.flat_map(|t| {
+                t.copy
+                    .iter()
+                    .chain(&t.symlink)
+                    .chain(&t.hardlink)
+                    .chain(&t.shared)
+            }))
+    }
+}
+
+/// How files are brought into a new worktree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloneMode {
+    /// Plain copies
+    #[default]
+    Copy,
+    /// Hard links to the primary checkout's files (copies across
+    /// filesystems). Editing a file in place edits it everywhere.
+    Hardlink,
+    /// Copy-on-write clones where the filesystem supports them (btrfs, XFS,
+    /// APFS), plain copies elsewhere
+    Reflink,
+}
+
+impl FromStr for CloneMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "copy" => Ok(CloneMode::Copy),
+            "hardlink" => Ok(CloneMode::Hardlink),
+            "reflink" => Ok(CloneMode::Reflink),
+            other => {
+                anyhow::bail!("unknown clone mode '{other}' (expected copy, hardlink or reflink)")
+            }
+        }
+    }
+}
+
+/// Load the template from a meta config file, if it has one.
+pub fn load(meta_path: &Path) -> Result<Option<WorktreeTemplate>> {
+    let value = config::read_meta_config_value(meta_path)?;
+    parse(&value).with_context(|| format!("Invalid worktree.template in {}", meta_path.display()))
+}
+
+/// Parse `worktree.template` from a config document.
+pub fn parse(config: &serde_json::Value) -> Result<Option<WorktreeTemplate>> {
+    let Some(value) = config.get("worktree").and_then(|w| w.get("template")) else {
+        return Ok(None);
+    };
+    let template: WorktreeTemplate = serde_json::from_value(value.clone())?;
+    for path in template.paths() {
+        check_relative(path)?;
+    }
+    Ok(Some(template))
+}
+
+/// Template paths must stay inside the repo.
+fn check_relative(path: &str) -> Result<()> {
+    let inside = Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    anyhow::ensure!(
+        inside && !path.is_empty(),
+        "'{path}' must be a path inside the repo"
+    );
+    Ok(())
+}
+
+// ── Applying ────────────────────────────────────────────
+
+/// Paths set up in one worktree, for reporting.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Applied {
+    pub copied: Vec<String>,
+    pub symlinked: Vec<String>,
+    pub hardlinked: Vec<String>,
+    pub shared: Vec<String>,
+}
+
+/// Options for [`apply_files_with`].
+#[derive(Debug, Clone, Default)]
+pub struct ApplyOptions {
+    /// How `copy` entries are made
+    pub clone_mode: CloneMode,
+    /// Where `shared` directories live (`.worktrees/.shared`); `shared`
+    /// entries are skipped without one
+    pub shared_root: Option<PathBuf>,
+}
+
+/// Bring the template's files from the primary checkout into the worktree
+/// with plain copies. Setup commands are not run here; see
+/// [`setup_commands`].
+pub fn apply_files(template: &RepoTemplate, repo: &WorktreeRepoInfo) -> Result<Applied> {
+    apply_files_with(template, repo, &ApplyOptions::default())
+}
+
+/// [`apply_files`] with a clone mode and shared artifact directories.
+pub fn apply_files_with(
+    template: &RepoTemplate,
+    repo: &WorktreeRepoInfo,
+    options: &ApplyOptions,
+) -> Result<Applied> {
+    let mut applied = Applied::default();
+    let pending = |rel: &String| {
+        let (from, to) = (repo.source_path.join(rel), repo.path.join(rel));
+        let ready = from.symlink_metadata().is_ok() && to.symlink_metadata().is_err();
+        ready.then_some((from, to))
+    };
+
+    for rel in &template.copy {
+        if let Some((from, to)) = pending(rel) {
+            clone_tree(&from, &to, options.clone_mode)?;
+            applied.copied.push(rel.clone());
+        }
+    }
+    for rel in &template.symlink {
+        if let Some((from, to)) = pending(rel) {
+            create_parent(&to)?;
+            symlink(&from, &to).with_context(|| format!("Failed to symlink {}", to.display()))?;
+            applied.symlinked.push(rel.clone());
+        }
+    }
+    for rel in &template.hardlink {
+        if let Some((from, to)) = pending(rel) {
+            hardlink_tree(&from, &to)?;
+            applied.hardlinked.push(rel.clone());
+        }
+    }
+    if let Some(root) = &options.shared_root {
+        for rel in &template.shared {
+            let to = repo.path.join(rel);
+            if to.symlink_metadata().is_ok() {
+                continue;
+            }
+            let shared = root.join(&repo.alias).join(rel);
+            if !shared.exists() {
+                let from = repo.source_path.join(rel);
+                if from.is_dir() {
+                    clone_tree(&from, &shared, options.clone_mode)?;
+                } else {
+                    std::fs::create_dir_all(&shared)
+                        .with_context(|| format!("Failed to create {}", shared.display()))?;
+                }
+            }
+            create_parent(&to)?;
+            symlink(&shared, &to).with_context(|| format!("Failed to symlink {}", to.display()))?;
+            applied.shared.push(rel.clone());
+        }
+    }
+    Ok(applied)
+}
+
+/// Setup commands for every repo of a new worktree set, in repo order, for
+/// the caller to run with the usual runner.
+pub fn setup_commands(template: &WorktreeTemplate, repos: &[WorktreeRepoInfo]) -> Vec<DirCommand> {
+    repos
+        .iter()
+        .flat_map(|repo| {
+            template
+                .for_repo(&repo.alias)
+                .setup
+                .into_iter()
+                .map(|cmd| DirCommand {
+                    dir: repo.path.display().to_string(),
+                    cmd,
+                    env: None,
+                })
+        })
+        .collect()
+}
+
+fn create_parent(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    Ok(())
+}
+
+fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    link_or_copy_tree(from, to, false)
+}
+
+fn clone_tree(from: &Path, to: &Path, mode: CloneMode) -> Result<()> {
+    match mode {
+        CloneMode::Copy => copy_tree(from, to),
+        CloneMode::Hardlink => hardlink_tree(from, to),
+        CloneMode::Reflink => {
+            if !reflink_tree(from, to) {
+                // cp may have got partway
+                if to.is_dir() {
+                    let _ = std::fs::remove_dir_all(to);
+                } else {
+                    let _ = std::fs::remove_file(to);
+                }
+                copy_tree(from, to)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Clone with `cp`, which knows each platform's copy-on-write call. False
+/// if the filesystem (or `cp`) can't.
+fn reflink_tree(from: &Path, to: &Path) -> bool {
+    let flag = if cfg!(target_os = "macos") {
+        "-cR"
+    } else if cfg!(target_os = "linux") {
+        "--reflink=always"
+    } else {
+        return false;
+    };
+    if create_parent(to).is_err() {
+        return false;
+    }
+    let mut cmd = Command::new("cp");
+    cmd.arg(flag);
+    if cfg!(target_os = "linux") {
+        cmd.arg("-R");
+    }
+    cmd.arg(from)
+        .arg(to)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+fn hardlink_tree(from: &Path, to: &Path) -> Result<()> {
+    link_or_copy_tree(from, to, true)
+}
+
+/// Recreate `from` at `to`, hard-linking files when `link` is set (and the
+/// filesystem allows it) and copying them otherwise.
+fn link_or_copy_tree(from: &Path, to: &Path, link: bool) -> Result<()> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry?;
+        // `from` itself has an empty suffix; joining it would add a slash
+        let target = match entry.path().strip_prefix(from) {
+            Ok(rel) if !rel.as_os_str().is_empty() => to.join(rel),
+            _ => to.to_path_buf(),
+        };
+        let kind = entry.file_type();
+        if kind.is_dir() {
+            std::fs::create_dir_all(&target)
+                .with_context(|| format!("Failed to create {}", target.display()))?;
+            continue;
+        }
+        create_parent(&target)?;
+        if kind.is_symlink() {
+            symlink(&std::fs::read_link(entry.path())?, &target)?;
+        } else if !link || std::fs::hard_link(entry.path(), &target).is_err() {
+            std::fs::copy(entry.path(), &target)
+                .with_context(|| format!("Failed to copy to {}", target.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(from, to)
+}
+
+#[cfg(windows)]
+fn symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(from, to)
+    } else {
+        std::os::windows::fs::symlink_file(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn repo(root: &Path) -> WorktreeRepoInfo {
+        let (source, path) = (root.join("api"), root.join(".worktrees/t/api"));
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::create_dir_all(&path).unwrap();
+        WorktreeRepoInfo {
+            alias: "api".to_string(),
+            branch: "t".to_string(),
+            path,
+            source_path: source,
+            created_branch: None,
+        }
+    }
+
+    #[test]
+    fn parses_and_merges_repo_entries() {
+        let template = parse(&json!({"worktree": {"template": {
+            "copy": [".env"],
+            "setup": ["npm ci"],
+            "repos": {"api": {"copy": ["local.toml"], "setup": ["cargo fetch"]}}
+        }}}))
+        .unwrap()
+        .unwrap();
+
+        let api = template.for_repo("api");
+        assert_eq!(api.copy, vec![".env", "local.toml"]);
+        assert_eq!(api.setup, vec!["npm ci", "cargo fetch"]);
+        assert_eq!(template.for_repo("web").copy, vec![".env"]);
+        assert_eq!(parse(&json!({"projects": {}})).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_escaping_paths() {
+        assert!(parse(&json!({"worktree": {"template": {"cp": []}}})).is_err());
+        let err = parse(&json!({"worktree": {"template": {"copy": ["../secret"]}}})).unwrap_err();
+        assert!(err.to_string().contains("inside the repo"));
+        assert!(parse(&json!({"worktree": {"template": {"symlink": ["/etc"]}}})).is_err());
+    }
+
+    #[test]
+    fn applies_copies_links_and_skips_existing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = repo(tmp.path());
+        let src = &repo.source_path;
+        std::fs::write(src.join(".env"), "A=1").unwrap();
+        std::fs::create_dir_all(src.join("node_modules/pkg")).unwrap();
+        std::fs::write(src.join("node_modules/pkg/index.js"), "x").unwrap();
+        std::fs::create_dir(src.join(".cache")).unwrap();
+        std::fs::write(repo.path.join("kept"), "worktree").unwrap();
+        std::fs::write(src.join("kept"), "primary").unwrap();
+
+        let template = RepoTemplate {
+            copy: vec![".env".into(), "kept".into(), "missing".into()],
+            symlink: vec![".cache".into()],
+            hardlink: vec!["node_modules".into()],
+            ..Default::default()
+        };
+        let applied = apply_files(&template, &repo).unwrap();
+        assert_eq!(applied.copied, vec![".env"]);
+        assert_eq!(applied.symlinked, vec![".cache"]);
+        assert_eq!(applied.hardlinked, vec!["node_modules"]);
+
+        let wt = &repo.path;
+        assert_eq!(std::fs::read_to_string(wt.join(".env")).unwrap(), "A=1");
+        assert_eq!(
+            std::fs::read_to_string(wt.join("kept")).unwrap(),
+            "worktree"
+        );
+        assert_eq!(
+            std::fs::read_link(wt.join(".cache")).unwrap(),
+            src.join(".cache")
+        );
+        assert_eq!(
+            std::fs::read_to_string(wt.join("node_modules/pkg/index.js")).unwrap(),
+            "x"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let ino = |p: PathBuf| std::fs::metadata(p).unwrap().ino();
+            assert_eq!(
+                ino(wt.join("node_modules/pkg/index.js")),
+                ino(src.join("node_modules/pkg/index.js"))
+            );
+        }
+    }
+
+    #[test]
+    fn shares_artifact_dirs_and_honours_clone_mode() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = repo(tmp.path());
+        let src = &repo.source_path;
+        std::fs::create_dir_all(src.join("target/debug")).unwrap();
+        std::fs::write(src.join("target/debug/app"), "bin").unwrap();
+        std::fs::write(src.join("data.db"), "rows").unwrap();
+
+        let template = RepoTemplate {
+            copy: vec!["data.db".into()],
+            shared: vec!["target".into(), "dist".into()],
+            ..Default::default()
+        };
+        let shared_root = tmp.path().join(".worktrees/.shared");
+        let options = ApplyOptions {
+            clone_mode: CloneMode::Reflink,
+            shared_root: Some(shared_root.clone()),
+        };
+        let applied = apply_files_with(&template, &repo, &options).unwrap();
+        assert_eq!(applied.copied, vec!["data.db"]);
+        assert_eq!(applied.shared, vec!["target", "dist"]);
+
+        let wt = &repo.path;
+        // Reflink falls back to a copy where the filesystem can't
+        assert_eq!(std::fs::read_to_string(wt.join("data.db")).unwrap(), "rows");
+        assert_eq!(
+            std::fs::read_link(wt.join("target")).unwrap(),
+            shared_root.join("api/target")
+        );
+        assert_eq!(
+            std::fs::read_to_string(wt.join("target/debug/app")).unwrap(),
+            "bin"
+        );
+        assert!(shared_root.join("api/dist").is_dir());
+
+        // Without a shared root, shared entries are left alone
+        std::fs::remove_file(wt.join("dist")).unwrap();
+        let applied = apply_files(&template, &repo).unwrap();
+        assert!(applied.shared.is_empty());
+        assert!(!wt.join("dist").exists());
+
+        assert_eq!(
+            "hardlink".parse::<CloneMode>().unwrap(),
+            CloneMode::Hardlink
+        );
+        assert!("symlink".parse::<CloneMode>().is_err());
+        let template = parse(&json!({"worktree": {"template": {
+            "clone_mode": "reflink",
+            "repos": {"api": {"shared": ["target"]}}
+        }}}))
+        .unwrap()
+        .unwrap();
+        assert_eq!(template.clone_mode, Some(CloneMode::Reflink));
+        assert_eq!(template.for_repo("api").shared, vec!["target"]);
+    }
+
+    #[test]
+    fn setup_commands_run_in_each_worktree() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = repo(tmp.path());
+        let template = WorktreeTemplate {
+            setup: vec!["make setup".into()],
+            ..Default::default()
+        };
+        let commands = setup_commands(&template, std::slice::from_ref(&repo));
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].dir, repo.path.display().to_string());
+        assert_eq!(commands[0].cmd, "make setup");
+    }
+}